@@ -11,5 +11,6 @@ create table rlink_ck
 	checkpoint_id bigint default 0 not null comment 'checkpoint id',
     completed_checkpoint_id bigint default 0 not null comment 'completed checkpoint id',
 	handle text comment 'checkpoint handle can access checkpoint state. eg: mq''s offset, file''s path',
+    savepoint tinyint default 0 not null comment 'the checkpoint is a savepoint',
 	create_time datetime default '1900-01-01 00:00:00' not null comment 'create datetime'
 ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci
\ No newline at end of file
@@ -1,14 +1,16 @@
 use std::borrow::BorrowMut;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::thread::JoinHandle;
 use std::time::Duration;
 
 use clickhouse_rs::{ClientHandle, Options, Pool};
 use rlink::channel::utils::handover::Handover;
+use rlink::core::cancellation::CancellationToken;
 use rlink::core::checkpoint::CheckpointFunction;
 use rlink::core::element::{FnSchema, Record};
 use rlink::core::function::{Context, NamedFunction, OutputFormat};
-use rlink::utils::thread::{async_runtime, async_sleep, async_spawn};
+use rlink::utils::thread::{async_sleep, async_spawn};
 use rlink::{core, utils};
 
 pub type CkBlock = clickhouse_rs::Block;
@@ -31,6 +33,9 @@ pub struct ClickhouseSink {
     tasks: usize,
     converter: Arc<Box<dyn ClickhouseConverter>>,
     handover: Option<Handover>,
+    /// a child of the task's token, cancelled on `close` to stop the write loops
+    cancellation_token: CancellationToken,
+    write_thread: Option<JoinHandle<()>>,
 }
 
 impl ClickhouseSink {
@@ -50,6 +55,8 @@ impl ClickhouseSink {
             tasks,
             converter: Arc::new(builder),
             handover: None,
+            cancellation_token: CancellationToken::new(),
+            write_thread: None,
         }
     }
 }
@@ -57,6 +64,7 @@ impl ClickhouseSink {
 impl OutputFormat for ClickhouseSink {
     fn open(&mut self, context: &Context) -> core::Result<()> {
         self.handover = Some(Handover::new(self.name(), context.task_id.to_tags(), 10000));
+        self.cancellation_token = context.cancellation_token.child();
 
         let urls: Vec<&str> = self.url.split(",").collect();
         let url = if urls.len() > 1 {
@@ -75,22 +83,36 @@ impl OutputFormat for ClickhouseSink {
             self.batch_timeout,
             self.converter.clone(),
             self.handover.as_ref().unwrap().clone(),
-        );
+        )
+        .with_cancellation(self.cancellation_token.clone());
         let tasks = self.tasks;
-        utils::thread::spawn("clickhouse-sink-block", move || {
-            async_runtime("ck_sink").block_on(async {
+        let runtime = context.async_runtime();
+        let write_thread = utils::thread::spawn("clickhouse-sink-block", move || {
+            runtime.block_on(async {
                 task.run(tasks).await;
             });
         });
+        self.write_thread = Some(write_thread);
 
         Ok(())
     }
 
     fn write_record(&mut self, record: Record) {
-        self.handover.as_ref().unwrap().produce(record).unwrap();
+        let handover = self.handover.as_ref().unwrap();
+        if !handover.produce_cancellable(record, &self.cancellation_token) {
+            debug!("the clickhouse write loops exited, the record is dropped");
+        }
     }
 
+    /// the write loops insert the records in the handover and exit
     fn close(&mut self) -> core::Result<()> {
+        self.cancellation_token
+            .cancel("the clickhouse sink is closed");
+        if let Some(write_thread) = self.write_thread.take() {
+            write_thread
+                .join()
+                .map_err(|_e| anyhow::anyhow!("the clickhouse write thread panicked"))?;
+        }
         Ok(())
     }
 
@@ -109,6 +131,7 @@ pub struct ClickhouseSinkTask {
     batch_timeout: Duration,
     converter: Arc<Box<dyn ClickhouseConverter>>,
     handover: Handover,
+    cancellation_token: CancellationToken,
 }
 
 impl ClickhouseSinkTask {
@@ -129,9 +152,16 @@ impl ClickhouseSinkTask {
             batch_timeout,
             converter: builder,
             handover,
+            cancellation_token: CancellationToken::new(),
         }
     }
 
+    /// exit the write loops after the handover is drained once the `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = token;
+        self
+    }
+
     pub async fn run(&mut self, tasks: usize) {
         let mut join_handlers = Vec::new();
         for _ in 0..tasks {
@@ -160,6 +190,9 @@ impl ClickhouseSinkTask {
             match self.batch_send(client.borrow_mut()).await {
                 Ok(len) => {
                     if len == 0 {
+                        if self.cancellation_token.is_cancelled() {
+                            return Ok(());
+                        }
                         async_sleep(Duration::from_secs(1)).await;
                     }
                 }
@@ -176,6 +209,9 @@ impl ClickhouseSinkTask {
     async fn reconnection(&mut self, client: &mut ClientHandle) -> anyhow::Result<()> {
         let mut err = None;
         for _ in 0..180 {
+            if self.cancellation_token.is_cancelled() {
+                return Err(anyhow::anyhow!("cancelled on reconnection"));
+            }
             async_sleep(Duration::from_secs(1)).await;
             match client.check_connection().await {
                 Ok(_) => {
@@ -199,13 +235,16 @@ impl ClickhouseSinkTask {
         let mut batch_block = self.converter.create_batch(self.batch_size);
         let begin_timestamp = utils::date_time::current_timestamp();
         let mut size = 0;
-        for n in 0..self.batch_size {
+        for _ in 0..self.batch_size {
             match self.handover.try_poll_next() {
                 Ok(record) => {
                     batch_block.append(record);
-                    size = n;
+                    size += 1;
                 }
                 Err(_e) => {
+                    if self.cancellation_token.is_cancelled() {
+                        break;
+                    }
                     async_sleep(Duration::from_millis(100)).await;
                     let current_timestamp = utils::date_time::current_timestamp();
                     if current_timestamp - begin_timestamp > self.batch_timeout {
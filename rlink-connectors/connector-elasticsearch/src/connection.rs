@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use elasticsearch::auth::Credentials;
+use elasticsearch::cert::{Certificate, CertificateValidation};
+use elasticsearch::http::headers::{HeaderMap, HeaderName, HeaderValue};
+use elasticsearch::http::transport::{
+    Connection, ConnectionPool, SingleNodeConnectionPool, TransportBuilder,
+};
+use elasticsearch::http::Url;
+use elasticsearch::nodes::NodesInfoParts;
+use elasticsearch::Elasticsearch;
+use serde_json::Value;
+
+/// The authentication of the elasticsearch cluster
+#[derive(Clone, Debug)]
+pub enum Auth {
+    Basic { username: String, password: String },
+    ApiKey { id: String, api_key: String },
+    Bearer { token: String },
+}
+
+/// The validation of the server certificate
+#[derive(Clone, Debug)]
+pub enum TlsValidation {
+    /// validate by the certificates trusted by the operating system
+    Default,
+    /// validate by the CA certificate in PEM format, and verify the hostname
+    Full { ca_pem_path: String },
+    /// validate by the CA certificate in PEM format, but not verify the hostname
+    Certificate { ca_pem_path: String },
+    /// no validation, only for testing
+    None,
+}
+
+#[derive(Clone, Debug)]
+pub struct ElasticsearchConfig {
+    hosts: Vec<String>,
+    headers: HashMap<String, String>,
+    auth: Option<Auth>,
+    tls_validation: TlsValidation,
+    sniff_on_start: bool,
+    timeout: Option<Duration>,
+}
+
+impl ElasticsearchConfig {
+    /// `hosts` is the address list of the nodes, eg: `http://10.0.0.1:9200`,
+    /// the requests are load balanced in round-robin
+    pub fn new(hosts: Vec<String>) -> Self {
+        ElasticsearchConfig {
+            hosts,
+            headers: HashMap::new(),
+            auth: None,
+            tls_validation: TlsValidation::Default,
+            sniff_on_start: false,
+            timeout: None,
+        }
+    }
+
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.headers.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.auth = Some(Auth::Basic {
+            username: username.to_string(),
+            password: password.to_string(),
+        });
+        self
+    }
+
+    pub fn api_key_auth(mut self, id: &str, api_key: &str) -> Self {
+        self.auth = Some(Auth::ApiKey {
+            id: id.to_string(),
+            api_key: api_key.to_string(),
+        });
+        self
+    }
+
+    pub fn bearer_auth(mut self, token: &str) -> Self {
+        self.auth = Some(Auth::Bearer {
+            token: token.to_string(),
+        });
+        self
+    }
+
+    pub fn tls_validation(mut self, tls_validation: TlsValidation) -> Self {
+        self.tls_validation = tls_validation;
+        self
+    }
+
+    /// discover all http nodes of the cluster by the `hosts` when the client is created
+    pub fn sniff_on_start(mut self, sniff_on_start: bool) -> Self {
+        self.sniff_on_start = sniff_on_start;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// A connection pool that selects the connections in round-robin
+#[derive(Debug, Clone)]
+pub struct RoundRobinConnectionPool {
+    connections: Vec<Connection>,
+    index: Arc<AtomicUsize>,
+}
+
+impl RoundRobinConnectionPool {
+    pub fn new(urls: Vec<Url>) -> anyhow::Result<Self> {
+        if urls.is_empty() {
+            return Err(anyhow!("no elasticsearch host"));
+        }
+
+        Ok(RoundRobinConnectionPool {
+            connections: urls.into_iter().map(|url| Connection::new(url)).collect(),
+            index: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+}
+
+impl ConnectionPool for RoundRobinConnectionPool {
+    fn next(&self) -> &Connection {
+        let index = self.index.fetch_add(1, Ordering::Relaxed);
+        &self.connections[index % self.connections.len()]
+    }
+}
+
+/// create the client by the config, the nodes are sniffed if `sniff_on_start` is enabled
+pub async fn create_client(config: &ElasticsearchConfig) -> anyhow::Result<Elasticsearch> {
+    if config.hosts.is_empty() {
+        return Err(anyhow!("no elasticsearch host"));
+    }
+
+    let mut urls = Vec::new();
+    for host in &config.hosts {
+        urls.push(Url::parse(host.as_str())?);
+    }
+
+    if config.sniff_on_start {
+        let client = build_client(config, urls.clone())?;
+        match sniff_nodes(&client, urls[0].scheme()).await {
+            Ok(nodes) if nodes.len() > 0 => {
+                info!("sniff elasticsearch nodes: {:?}", nodes);
+                urls = nodes;
+            }
+            Ok(_) => warn!("no elasticsearch nodes sniffed, use the configured hosts"),
+            Err(e) => warn!(
+                "sniff elasticsearch nodes error, use the configured hosts. {}",
+                e
+            ),
+        }
+    }
+
+    build_client(config, urls)
+}
+
+fn build_client(config: &ElasticsearchConfig, mut urls: Vec<Url>) -> anyhow::Result<Elasticsearch> {
+    let mut header_map = HeaderMap::new();
+    for (key, value) in &config.headers {
+        header_map.insert(
+            HeaderName::from_bytes(key.as_bytes())?,
+            HeaderValue::from_str(value.as_str())?,
+        );
+    }
+
+    let mut builder = if urls.len() == 1 {
+        TransportBuilder::new(SingleNodeConnectionPool::new(urls.remove(0)))
+    } else {
+        TransportBuilder::new(RoundRobinConnectionPool::new(urls)?)
+    };
+    builder = builder.headers(header_map);
+
+    if let Some(auth) = &config.auth {
+        let credentials = match auth {
+            Auth::Basic { username, password } => {
+                Credentials::Basic(username.clone(), password.clone())
+            }
+            Auth::ApiKey { id, api_key } => Credentials::ApiKey(id.clone(), api_key.clone()),
+            Auth::Bearer { token } => Credentials::Bearer(token.clone()),
+        };
+        builder = builder.auth(credentials);
+    }
+
+    let cert_validation = match &config.tls_validation {
+        TlsValidation::Default => CertificateValidation::Default,
+        TlsValidation::Full { ca_pem_path } => {
+            CertificateValidation::Full(read_certificate(ca_pem_path.as_str())?)
+        }
+        TlsValidation::Certificate { ca_pem_path } => {
+            CertificateValidation::Certificate(read_certificate(ca_pem_path.as_str())?)
+        }
+        TlsValidation::None => CertificateValidation::None,
+    };
+    builder = builder.cert_validation(cert_validation);
+
+    if let Some(timeout) = config.timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    let transport = builder.build()?;
+    Ok(Elasticsearch::new(transport))
+}
+
+fn read_certificate(path: &str) -> anyhow::Result<Certificate> {
+    let pem = std::fs::read(path)?;
+    let certificate = Certificate::from_pem(pem.as_slice())?;
+    Ok(certificate)
+}
+
+/// the http address of all nodes in the cluster
+async fn sniff_nodes(client: &Elasticsearch, scheme: &str) -> anyhow::Result<Vec<Url>> {
+    let response = client
+        .nodes()
+        .info(NodesInfoParts::Metric(&["http"]))
+        .send()
+        .await?;
+    let response_body = response.json::<Value>().await?;
+
+    let mut urls = Vec::new();
+    if let Some(nodes) = response_body["nodes"].as_object() {
+        for node in nodes.values() {
+            if let Some(address) = node["http"]["publish_address"].as_str() {
+                urls.push(parse_publish_address(scheme, address)?);
+            }
+        }
+    }
+
+    Ok(urls)
+}
+
+/// the `publish_address` is `ip:port` or `hostname/ip:port`
+fn parse_publish_address(scheme: &str, address: &str) -> anyhow::Result<Url> {
+    let address = match address.split_once('/') {
+        Some((hostname, ip_port)) => {
+            let port = ip_port.rsplit_once(':').map(|(_ip, port)| port);
+            match port {
+                Some(port) if !hostname.is_empty() => format!("{}:{}", hostname, port),
+                _ => ip_port.to_string(),
+            }
+        }
+        None => address.to_string(),
+    };
+
+    let url = Url::parse(format!("{}://{}", scheme, address).as_str())?;
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::{parse_publish_address, RoundRobinConnectionPool};
+
+    #[test]
+    pub fn parse_publish_address_test() {
+        let url = parse_publish_address("http", "10.0.0.1:9200").unwrap();
+        assert_eq!(url.as_str(), "http://10.0.0.1:9200/");
+
+        let url = parse_publish_address("https", "es-node-1/10.0.0.1:9200").unwrap();
+        assert_eq!(url.as_str(), "https://es-node-1:9200/");
+    }
+
+    #[test]
+    pub fn empty_hosts_test() {
+        assert!(RoundRobinConnectionPool::new(vec![]).is_err());
+    }
+}
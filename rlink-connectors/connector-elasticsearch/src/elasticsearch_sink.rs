@@ -2,36 +2,134 @@ use std::borrow::BorrowMut;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::thread::JoinHandle;
 use std::time::Duration;
 
-use elasticsearch::http::headers::HeaderMap;
 use elasticsearch::http::request::JsonBody;
-use elasticsearch::http::transport::{SingleNodeConnectionPool, TransportBuilder};
-use elasticsearch::http::Url;
 use elasticsearch::{BulkParts, Elasticsearch};
 use rlink::channel::utils::handover::Handover;
-use rlink::core::checkpoint::CheckpointFunction;
+use rlink::core::cancellation::CancellationToken;
+use rlink::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
+use rlink::core::data_types::Schema;
+use rlink::core::dynamic_record::DynamicRecord;
 use rlink::core::element::{FnSchema, Record};
 use rlink::core::function::{Context, NamedFunction, OutputFormat};
-use rlink::utils::thread::{async_runtime, async_sleep, async_spawn};
+use rlink::core::runtime::CheckpointId;
+use rlink::metrics::metric::Counter;
+use rlink::metrics::register_counter;
+use rlink::utils::thread::{async_sleep, async_spawn};
 use rlink::{core, utils};
+use rlink_connector_sdk::ack::DEFAULT_ACK_TIMEOUT;
+pub use rlink_connector_sdk::AckCounter;
+use rlink_connector_sdk::RetryPolicy;
 use serde_json::Value;
 use thiserror::Error;
 
+use crate::connection::{create_client, ElasticsearchConfig};
+
+/// The bulk action of the document
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+pub enum OpType {
+    /// add or replace the document
+    #[default]
+    Index,
+    /// add the document, fail if the `_id` already exists
+    Create,
+    /// partial update the document by `_id`, create it if not exists (`doc_as_upsert`)
+    Update,
+    /// delete the document by `_id`, the body is ignored
+    Delete,
+}
+
+impl OpType {
+    pub fn action(&self) -> &'static str {
+        match self {
+            OpType::Index => "index",
+            OpType::Create => "create",
+            OpType::Update => "update",
+            OpType::Delete => "delete",
+        }
+    }
+}
+
 pub struct ElasticsearchModel {
     pub index: String,
     pub es_type: &'static str,
     pub body: Value,
+    /// the document `_id`, generated by elasticsearch if `None`
+    pub id: Option<String>,
+    pub routing: Option<String>,
+    pub op_type: OpType,
+}
+
+impl ElasticsearchModel {
+    pub fn new(index: String, es_type: &'static str, body: Value) -> Self {
+        ElasticsearchModel {
+            index,
+            es_type,
+            body,
+            id: None,
+            routing: None,
+            op_type: OpType::Index,
+        }
+    }
+
+    /// the business key of the record, write with the same `id` is idempotent
+    pub fn with_id(mut self, id: String) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn with_routing(mut self, routing: String) -> Self {
+        self.routing = Some(routing);
+        self
+    }
+
+    pub fn with_op_type(mut self, op_type: OpType) -> Self {
+        self.op_type = op_type;
+        self
+    }
+
+    /// the lines of the bulk body, an action line and a source line(except `Delete`),
+    /// the body is returned as the error if the `id` required by the op type is missing
+    pub fn to_bulk_lines(self) -> Result<Vec<Value>, Value> {
+        let mut index_model = Index::new(self.op_type);
+        index_model.set_index(self.index);
+        index_model.set_type(self.es_type.to_string());
+        if let Some(id) = self.id {
+            index_model.set_id(id);
+        } else if self.op_type == OpType::Update || self.op_type == OpType::Delete {
+            return Err(self.body);
+        }
+        if let Some(routing) = self.routing {
+            index_model.set_routing(routing);
+        }
+
+        let action = index_model.to_json().unwrap();
+        let lines = match self.op_type {
+            OpType::Index | OpType::Create => vec![action, self.body],
+            OpType::Update => vec![
+                action,
+                serde_json::json!({ "doc": self.body, "doc_as_upsert": true }),
+            ],
+            OpType::Delete => vec![action],
+        };
+        Ok(lines)
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Index {
+    op_type: OpType,
     index: HashMap<String, String>,
 }
 
 impl Index {
-    pub fn new() -> Self {
+    pub fn new(op_type: OpType) -> Self {
         Index {
+            op_type,
             index: HashMap::new(),
         }
     }
@@ -44,8 +142,22 @@ impl Index {
         self.index.insert("_type".to_string(), type_value);
     }
 
+    pub fn set_id(&mut self, id: String) {
+        self.index.insert("_id".to_string(), id);
+    }
+
+    pub fn set_routing(&mut self, routing: String) {
+        self.index.insert("routing".to_string(), routing);
+    }
+
+    /// the action line, eg: `{"index": {"_index": "test", "_id": "1"}}`
     pub fn to_json(&self) -> Result<Value, serde_json::Error> {
-        serde_json::to_value(self)
+        let mut action = serde_json::Map::new();
+        action.insert(
+            self.op_type.action().to_string(),
+            serde_json::to_value(&self.index)?,
+        );
+        Ok(Value::Object(action))
     }
 }
 
@@ -53,57 +165,335 @@ pub trait ElasticsearchConverter: Send + Sync {
     fn to_json(&self, record: &mut Record) -> ElasticsearchModel;
 }
 
+/// Convert the record to the document by the `Schema`, the field names are the document keys.
+/// The `Decimal` is written as a string to keep the precision and the `TimestampTz` as a RFC 3339
+/// string, both are coerced by the `scaled_float`/`date` mapping of elasticsearch.
+pub struct SchemaElasticsearchConverter {
+    index: String,
+    es_type: &'static str,
+    schema: Schema,
+    id_field: Option<String>,
+}
+
+impl SchemaElasticsearchConverter {
+    pub fn new(index: &str, es_type: &'static str, schema: Schema) -> Self {
+        SchemaElasticsearchConverter {
+            index: index.to_string(),
+            es_type,
+            schema,
+            id_field: None,
+        }
+    }
+
+    /// use the value of the field as the document `_id`
+    pub fn with_id_field(mut self, id_field: &str) -> Self {
+        self.id_field = Some(id_field.to_string());
+        self
+    }
+}
+
+impl ElasticsearchConverter for SchemaElasticsearchConverter {
+    fn to_json(&self, record: &mut Record) -> ElasticsearchModel {
+        let body = DynamicRecord::new(record, &self.schema)
+            .to_json()
+            .expect("convert record to json error");
+        let id = self
+            .id_field
+            .as_ref()
+            .and_then(|id_field| body.get(id_field))
+            .map(|id| match id {
+                Value::String(id) => id.clone(),
+                id => id.to_string(),
+            });
+
+        let model = ElasticsearchModel::new(self.index.clone(), self.es_type, body);
+        match id {
+            Some(id) => model.with_id(id),
+            None => model,
+        }
+    }
+}
+
+/// Handle the documents failed permanently, such as mapping errors or version conflicts.
+pub trait DeadLetterHandler: Send + Sync {
+    /// `lines` is the bulk lines of the document, `error` is the error object of the bulk item
+    fn handle(&self, lines: &[Value], status: u16, error: &Value);
+}
+
+/// Log the failed documents
+#[derive(Default)]
+pub struct LogDeadLetterHandler {}
+
+impl DeadLetterHandler for LogDeadLetterHandler {
+    fn handle(&self, lines: &[Value], status: u16, error: &Value) {
+        error!(
+            "elasticsearch document failed, status: {}, error: {}, document: {:?}",
+            status, error, lines
+        );
+    }
+}
+
+/// the default attempts of sending a document, the backoff from 100ms up to 10s
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 10;
+
+/// the status of the documents failed by the bulk request, eg: the connection error
+const REQUEST_FAILED_STATUS: u16 = 0;
+/// the status of the documents rejected before sending, eg: no `_id` of the `Update`
+const INVALID_DOCUMENT_STATUS: u16 = 400;
+
+/// The retry policy and the dead-letter handler of the failed bulk items
+#[derive(Clone)]
+pub struct BulkFailureHandler {
+    dead_letter_handler: Arc<Box<dyn DeadLetterHandler>>,
+    retry_policy: RetryPolicy,
+    retry_counter: Counter,
+    failure_counter: Counter,
+}
+
+impl BulkFailureHandler {
+    pub fn new(
+        dead_letter_handler: Arc<Box<dyn DeadLetterHandler>>,
+        retry_policy: RetryPolicy,
+        retry_counter: Counter,
+        failure_counter: Counter,
+    ) -> Self {
+        BulkFailureHandler {
+            dead_letter_handler,
+            retry_policy,
+            retry_counter,
+            failure_counter,
+        }
+    }
+
+    /// `429 Too Many Requests` and `503 Service Unavailable` are retryable
+    pub fn is_retryable(status: u16) -> bool {
+        status == 429 || status == 503
+    }
+
+    /// the exponential backoff of the retry `attempt`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.retry_policy.backoff(attempt)
+    }
+
+    fn should_retry(&self, attempt: u32) -> bool {
+        self.retry_policy.should_retry(attempt)
+    }
+
+    fn on_retry(&self, n: usize) {
+        self.retry_counter.fetch_add(n as u64);
+    }
+
+    fn on_failure(&self, lines: &[Value], status: u16, error: &Value) {
+        self.failure_counter.fetch_add(1);
+        self.dead_letter_handler.handle(lines, status, error);
+    }
+}
+
+/// How to derive the `_id` of the documents without an id given by the `ElasticsearchConverter`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DocumentIdStrategy {
+    /// the `_id` is generated by elasticsearch, the replayed records after a failover are
+    /// written as the duplicate documents
+    Generated,
+    /// the `_id` is `{checkpoint_id}-{task_number}-{offset}`, where `checkpoint_id` is the checkpoint
+    /// the task is started from and `offset` is the index of the record since then. The `offset`
+    /// is stored in the checkpoints, the replayed records after recovering from a checkpoint get
+    /// the same ids and overwrite the documents written before the failover, which gives the
+    /// effective exactly-once if the upstream replays the records in the same order
+    Checkpoint,
+}
+
+impl Default for DocumentIdStrategy {
+    fn default() -> Self {
+        DocumentIdStrategy::Generated
+    }
+}
+
+/// Generate the deterministic document ids of `DocumentIdStrategy::Checkpoint`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CheckpointIdGenerator {
+    checkpoint_id: u64,
+    task_number: u16,
+    offset: u64,
+}
+
+impl CheckpointIdGenerator {
+    pub fn new(checkpoint_id: CheckpointId, task_number: u16) -> Self {
+        CheckpointIdGenerator {
+            checkpoint_id: checkpoint_id.0,
+            task_number,
+            offset: 0,
+        }
+    }
+
+    /// continue the ids from the generator stored in the `handle` of the restored checkpoint,
+    /// start from the `checkpoint_id` if the checkpoint has no generator
+    pub fn restore(
+        checkpoint_id: CheckpointId,
+        task_number: u16,
+        handle: &Option<CheckpointHandle>,
+    ) -> Self {
+        handle
+            .as_ref()
+            .filter(|handle| !handle.handle.is_empty())
+            .and_then(|handle| serde_json::from_str::<CheckpointIdGenerator>(&handle.handle).ok())
+            .filter(|generator| generator.task_number == task_number)
+            .unwrap_or_else(|| CheckpointIdGenerator::new(checkpoint_id, task_number))
+    }
+
+    /// the state stored in the checkpoint, the ids after the `Barrier` continue from it
+    pub fn snapshot(&self) -> CheckpointHandle {
+        CheckpointHandle {
+            handle: serde_json::to_string(self).unwrap(),
+        }
+    }
+
+    pub fn next_id(&mut self) -> String {
+        let id = format!(
+            "{}-{}-{}",
+            self.checkpoint_id, self.task_number, self.offset
+        );
+        self.offset += 1;
+        id
+    }
+}
+
 #[derive(NamedFunction)]
 pub struct ElasticsearchOutputFormat {
-    address: String,
-    headers: HashMap<String, String>,
+    config: ElasticsearchConfig,
 
     builder: Arc<Box<dyn ElasticsearchConverter>>,
-    handover: Option<Handover>,
+    handover: Option<Handover<(Record, Option<String>)>>,
+    ack_counter: AckCounter,
+    dead_letter_handler: Arc<Box<dyn DeadLetterHandler>>,
+    id_strategy: DocumentIdStrategy,
+    id_generator: CheckpointIdGenerator,
+    retry_policy: RetryPolicy,
+    /// a child of the task's token, cancelled on `close` to stop the write loops
+    cancellation_token: CancellationToken,
+    write_thread: Option<JoinHandle<()>>,
 }
 
 impl ElasticsearchOutputFormat {
-    pub fn new(
-        address: &str,
-        headers: HashMap<String, String>,
-        builder: Box<dyn ElasticsearchConverter>,
-    ) -> Self {
+    pub fn new(config: ElasticsearchConfig, builder: Box<dyn ElasticsearchConverter>) -> Self {
         ElasticsearchOutputFormat {
-            address: address.to_string(),
-            headers,
+            config,
             builder: Arc::new(builder),
             handover: None,
+            ack_counter: AckCounter::default(),
+            dead_letter_handler: Arc::new(Box::new(LogDeadLetterHandler::default())),
+            id_strategy: DocumentIdStrategy::Generated,
+            id_generator: CheckpointIdGenerator::default(),
+            retry_policy: RetryPolicy::new(
+                Some(DEFAULT_MAX_ATTEMPTS),
+                Duration::from_millis(100),
+                Duration::from_secs(10),
+            ),
+            cancellation_token: CancellationToken::new(),
+            write_thread: None,
         }
     }
+
+    /// the `_id` of the documents without an id given by the converter, the user key given by the
+    /// converter (eg: `SchemaElasticsearchConverter::with_id_field`) always takes precedence
+    pub fn with_id_strategy(mut self, id_strategy: DocumentIdStrategy) -> Self {
+        self.id_strategy = id_strategy;
+        self
+    }
+
+    /// the retry of the documents failed by the throttling or the request errors, the documents
+    /// are handed to the dead-letter handler after the attempts, `DEFAULT_MAX_ATTEMPTS` by default
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// the handler of the documents failed permanently, log them by default
+    pub fn with_dead_letter_handler<T>(mut self, dead_letter_handler: T) -> Self
+    where
+        T: DeadLetterHandler + 'static,
+    {
+        self.dead_letter_handler = Arc::new(Box::new(dead_letter_handler));
+        self
+    }
 }
 
 impl OutputFormat for ElasticsearchOutputFormat {
     fn open(&mut self, context: &Context) -> core::Result<()> {
         self.handover = Some(Handover::new(self.name(), context.task_id.to_tags(), 10000));
-
-        let mut write_thead = ElasticsearchWriteThread::new(
-            self.address.as_str(),
-            self.headers.clone(),
-            self.handover.as_ref().unwrap().clone(),
-            3000,
-        )
-        .expect("build elasticsearch connection error");
-
+        self.id_generator = CheckpointIdGenerator::restore(
+            context.checkpoint_id,
+            context.task_id.task_number(),
+            &context.checkpoint_handle,
+        );
+
+        let failure_handler = BulkFailureHandler::new(
+            self.dead_letter_handler.clone(),
+            self.retry_policy.clone(),
+            register_counter(
+                format!("Elasticsearch_Retry_{}", self.name()),
+                context.task_id.to_tags(),
+            ),
+            register_counter(
+                format!("Elasticsearch_Failure_{}", self.name()),
+                context.task_id.to_tags(),
+            ),
+        );
+
+        // fail the task on an invalid config instead of the write thread
+        let runtime = context.async_runtime();
+        let client = runtime
+            .block_on(create_client(&self.config))
+            .map_err(|e| anyhow!("build elasticsearch connection error. {}", e))?;
+
+        self.cancellation_token = context.cancellation_token.child();
+
+        let handover = self.handover.as_ref().unwrap().clone();
+        let ack_counter = self.ack_counter.clone();
         let convert = self.builder.clone();
-        utils::thread::spawn("elastic-sink-block", move || {
-            async_runtime("es_sink").block_on(async {
+        let cancellation_token = self.cancellation_token.clone();
+        let write_thread = utils::thread::spawn("elastic-sink-block", move || {
+            runtime.block_on(async {
+                let mut write_thead = ElasticsearchWriteThread::new(
+                    client,
+                    handover,
+                    ack_counter,
+                    failure_handler,
+                    3000,
+                )
+                .with_cancellation(cancellation_token);
                 write_thead.run(convert, 5).await;
             });
         });
+        self.write_thread = Some(write_thread);
 
         Ok(())
     }
 
     fn write_record(&mut self, record: Record) {
-        self.handover.as_ref().unwrap().produce(record).unwrap();
+        // the ids are generated in the order of the records, not in the parallel write threads
+        let id = match self.id_strategy {
+            DocumentIdStrategy::Generated => None,
+            DocumentIdStrategy::Checkpoint => Some(self.id_generator.next_id()),
+        };
+
+        self.ack_counter.produce();
+        let handover = self.handover.as_ref().unwrap();
+        if !handover.produce_cancellable((record, id), &self.cancellation_token) {
+            // the write loops exited, the record is read again after the restart
+            self.ack_counter.acknowledge(1);
+        }
     }
 
+    /// the write loops write the records in the handover and exit
     fn close(&mut self) -> core::Result<()> {
+        self.cancellation_token
+            .cancel("the elasticsearch sink is closed");
+        if let Some(write_thread) = self.write_thread.take() {
+            write_thread
+                .join()
+                .map_err(|_e| anyhow!("the elasticsearch write thread panicked"))?;
+        }
         Ok(())
     }
 
@@ -112,40 +502,54 @@ impl OutputFormat for ElasticsearchOutputFormat {
     }
 }
 
-impl CheckpointFunction for ElasticsearchOutputFormat {}
+impl CheckpointFunction for ElasticsearchOutputFormat {
+    /// wait for all records before the `Barrier` are written to elasticsearch,
+    /// the records are sent again after recovering if the application crash before the checkpoint completed.
+    /// the id generator is stored, so the replayed records get the same ids
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        let ack_snapshot = self
+            .ack_counter
+            .snapshot(context.checkpoint_id, DEFAULT_ACK_TIMEOUT);
+        let handle = self.id_generator.snapshot();
+        Some(Box::new(move || {
+            ack_snapshot()?;
+            Ok(handle)
+        }))
+    }
+}
 
 #[derive(Clone)]
 pub struct ElasticsearchWriteThread {
     client: Elasticsearch,
     batch_size: usize,
-    handover: Handover,
+    handover: Handover<(Record, Option<String>)>,
+    ack_counter: AckCounter,
+    failure_handler: BulkFailureHandler,
+    cancellation_token: CancellationToken,
 }
 
 impl ElasticsearchWriteThread {
     pub fn new(
-        address: &str,
-        headers: HashMap<String, String>,
-        handover: Handover,
+        client: Elasticsearch,
+        handover: Handover<(Record, Option<String>)>,
+        ack_counter: AckCounter,
+        failure_handler: BulkFailureHandler,
         batch_size: usize,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut header_map = HeaderMap::new();
-        if headers.contains_key("stoken") {
-            let val = headers.get("stoken").unwrap();
-            header_map.insert("stoken", val.as_str().parse().unwrap());
-        }
-
-        let url = Url::parse(address)?;
-        let conn_pool = SingleNodeConnectionPool::new(url);
-        let transport = TransportBuilder::new(conn_pool)
-            .headers(header_map)
-            .build()?;
-        let client = Elasticsearch::new(transport);
-
-        Ok(ElasticsearchWriteThread {
+    ) -> Self {
+        ElasticsearchWriteThread {
             client,
             batch_size,
             handover,
-        })
+            ack_counter,
+            failure_handler,
+            cancellation_token: CancellationToken::new(),
+        }
+    }
+
+    /// exit the write loops after the handover is drained once the `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = token;
+        self
     }
 
     pub async fn run(
@@ -172,40 +576,52 @@ impl ElasticsearchWriteThread {
 
     pub async fn run0(&mut self, converter: Arc<Box<dyn ElasticsearchConverter>>) {
         loop {
-            match self.batch_send(&converter).await {
-                Ok(len) => {
-                    if len == 0 {
-                        async_sleep(Duration::from_secs(1)).await;
-                    }
-                }
-                Err(e) => {
-                    error!("write elasticsearch error. {}", e);
-                    async_sleep(Duration::from_millis(100)).await;
+            let (documents, record_size) = self.poll_batch(&converter);
+            if record_size == 0 {
+                if self.cancellation_token.is_cancelled() {
+                    break;
                 }
+                async_sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+
+            if !documents.is_empty() {
+                self.send_documents(documents).await;
             }
+            self.ack_counter.acknowledge(record_size);
         }
     }
 
-    async fn batch_send(
-        &self,
-        converter: &Box<dyn ElasticsearchConverter>,
-    ) -> Result<usize, Box<dyn std::error::Error + Send>> {
-        let mut bulk_bodies = Vec::with_capacity(self.batch_size);
+    /// poll a batch of records from the `Handover`, return the bulk lines of each valid record
+    /// and the number of the polled records, the invalid records are handed to the dead-letter
+    /// handler
+    fn poll_batch(&self, converter: &Box<dyn ElasticsearchConverter>) -> (Vec<Vec<Value>>, usize) {
+        let mut documents = Vec::with_capacity(self.batch_size);
+        let mut record_size = 0;
         for _ in 0..self.batch_size {
             match self.handover.try_poll_next() {
-                Ok(mut record) => {
-                    let ElasticsearchModel {
-                        index,
-                        es_type,
-                        body,
-                    } = converter.to_json(record.borrow_mut());
-
-                    let mut index_model = Index::new();
-                    index_model.set_index(index.clone());
-                    index_model.set_type(es_type.to_string());
-                    bulk_bodies.push(JsonBody::new(index_model.to_json().unwrap()));
-
-                    bulk_bodies.push(JsonBody::new(body));
+                Ok((mut record, id)) => {
+                    record_size += 1;
+                    let mut model = converter.to_json(record.borrow_mut());
+                    if model.id.is_none() {
+                        model.id = id;
+                    }
+
+                    let op_type = model.op_type;
+                    match model.to_bulk_lines() {
+                        Ok(lines) => documents.push(lines),
+                        Err(body) => {
+                            let reason =
+                                format!("`id` is required by the `{}` op type", op_type.action());
+                            let error =
+                                serde_json::json!({ "type": "missing_id", "reason": reason });
+                            self.failure_handler.on_failure(
+                                &[body],
+                                INVALID_DOCUMENT_STATUS,
+                                &error,
+                            );
+                        }
+                    }
                 }
                 Err(_e) => {
                     break;
@@ -213,23 +629,84 @@ impl ElasticsearchWriteThread {
             }
         }
 
-        let len = bulk_bodies.len();
-        self.flush(bulk_bodies).await.map_err(|e| {
-            let err = std::io::Error::new(std::io::ErrorKind::Other, format!("{}", e));
-            let source: Box<dyn std::error::Error + Send> = Box::new(err);
-            source
-        })?;
+        (documents, record_size)
+    }
+
+    /// Send the documents until all of them are succeeded or failed permanently,
+    /// the retryable documents and the failed requests are sent again with backoff, and handed
+    /// to the dead-letter handler after the attempts of the `RetryPolicy`.
+    async fn send_documents(&self, mut documents: Vec<Vec<Value>>) {
+        let mut attempt = 0;
+        loop {
+            // the retryable documents with the status and the error of the last attempt
+            let mut retry_documents = Vec::new();
+            // the error is not `Send`, format it before the next `await`
+            match self.flush(&documents).await.map_err(|e| e.to_string()) {
+                Ok(items) => {
+                    for (document, item) in documents.into_iter().zip(items.iter()) {
+                        let (status, error) = bulk_item_status(item);
+                        if status < 300 {
+                            continue;
+                        }
+
+                        if BulkFailureHandler::is_retryable(status) {
+                            retry_documents.push((document, status, error.clone()));
+                        } else {
+                            self.failure_handler.on_failure(&document, status, error);
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("write elasticsearch error. {}", e);
+                    let error = Value::String(e);
+                    retry_documents = documents
+                        .into_iter()
+                        .map(|document| (document, REQUEST_FAILED_STATUS, error.clone()))
+                        .collect();
+                }
+            }
+
+            if retry_documents.is_empty() {
+                return;
+            }
+            if !self.failure_handler.should_retry(attempt) {
+                error!(
+                    "give up {} elasticsearch documents after {} attempts",
+                    retry_documents.len(),
+                    attempt + 1
+                );
+                for (document, status, error) in &retry_documents {
+                    self.failure_handler.on_failure(document, *status, error);
+                }
+                return;
+            }
 
-        Ok(len)
+            warn!(
+                "retry {} elasticsearch documents at attempt {}",
+                retry_documents.len(),
+                attempt
+            );
+            self.failure_handler.on_retry(retry_documents.len());
+            documents = retry_documents
+                .into_iter()
+                .map(|(document, _, _)| document)
+                .collect();
+
+            async_sleep(self.failure_handler.backoff(attempt)).await;
+            attempt += 1;
+        }
     }
 
+    /// send the bulk request, return the result items which are in the same order as the documents
     async fn flush(
         &self,
-        body_bulk: Vec<JsonBody<Value>>,
-    ) -> Result<bool, Box<dyn std::error::Error>> {
-        if body_bulk.len() == 0 {
-            return Ok(true);
-        }
+        documents: &[Vec<Value>],
+    ) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+        let body_bulk: Vec<JsonBody<Value>> = documents
+            .iter()
+            .flat_map(|lines| lines.iter())
+            .map(|line| JsonBody::new(line.clone()))
+            .collect();
         let response = self
             .client
             .bulk(BulkParts::None)
@@ -240,20 +717,145 @@ impl ElasticsearchWriteThread {
         let errors = response_body["errors"]
             .as_bool()
             .ok_or(anyhow!("no errors field in es response"))?;
+        if !errors {
+            return Ok(vec![]);
+        }
 
-        if errors {
-            let err = std::io::Error::new(std::io::ErrorKind::Other, "");
-            let source: Box<dyn std::error::Error + Send> = Box::new(err);
-            Err(source)
-        } else {
-            Ok(true)
+        let items = response_body["items"]
+            .as_array()
+            .ok_or(anyhow!("no items field in es response"))?;
+        if items.len() != documents.len() {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "the bulk response has {} items, but {} documents sent",
+                    items.len(),
+                    documents.len()
+                ),
+            )));
         }
+
+        Ok(items.clone())
     }
 }
 
+/// the status and the error of the bulk item, eg: `{"index": {"status": 429, "error": {..}}}`
+fn bulk_item_status(item: &Value) -> (u16, &Value) {
+    let result = item
+        .as_object()
+        .and_then(|item| item.values().next())
+        .unwrap_or(&Value::Null);
+    let status = result["status"].as_u64().unwrap_or(500) as u16;
+    (status, &result["error"])
+}
+
 #[derive(Error, Debug)]
 #[error("boxed source")]
 pub struct BoxedSource {
     #[source]
     source: Box<dyn std::error::Error + Send + 'static>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use rlink::core::runtime::CheckpointId;
+    use rlink::metrics::metric::Counter;
+    use rlink_connector_sdk::RetryPolicy;
+
+    use crate::elasticsearch_sink::{
+        bulk_item_status, BulkFailureHandler, CheckpointIdGenerator, ElasticsearchModel,
+        LogDeadLetterHandler, OpType, DEFAULT_MAX_ATTEMPTS,
+    };
+
+    #[test]
+    pub fn bulk_lines_test() {
+        let body = serde_json::json!({"name": "rlink"});
+
+        let lines = ElasticsearchModel::new("test".to_string(), "_doc", body.clone())
+            .with_id("1".to_string())
+            .with_routing("r".to_string())
+            .with_op_type(OpType::Update)
+            .to_bulk_lines()
+            .unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                serde_json::json!({"update": {"_index": "test", "_type": "_doc", "_id": "1", "routing": "r"}}),
+                serde_json::json!({"doc": {"name": "rlink"}, "doc_as_upsert": true}),
+            ]
+        );
+
+        let lines = ElasticsearchModel::new("test".to_string(), "_doc", body.clone())
+            .with_id("1".to_string())
+            .with_op_type(OpType::Delete)
+            .to_bulk_lines()
+            .unwrap();
+        assert_eq!(lines.len(), 1);
+
+        // the `id` is required by the `Update` and the `Delete`
+        let result = ElasticsearchModel::new("test".to_string(), "_doc", body.clone())
+            .with_op_type(OpType::Delete)
+            .to_bulk_lines();
+        assert_eq!(result, Err(body));
+        assert_eq!(OpType::default(), OpType::Index);
+    }
+
+    #[test]
+    pub fn bulk_item_status_test() {
+        let item = serde_json::json!({"index": {"_id": "1", "status": 429, "error": {"type": "es_rejected_execution_exception"}}});
+        let (status, error) = bulk_item_status(&item);
+        assert_eq!(status, 429);
+        assert_eq!(error["type"], "es_rejected_execution_exception");
+        assert!(BulkFailureHandler::is_retryable(status));
+
+        let failure_handler = BulkFailureHandler::new(
+            Arc::new(Box::new(LogDeadLetterHandler::default())),
+            RetryPolicy::new(
+                Some(DEFAULT_MAX_ATTEMPTS),
+                Duration::from_millis(100),
+                Duration::from_secs(10),
+            ),
+            Counter::default(),
+            Counter::default(),
+        );
+        assert_eq!(failure_handler.backoff(0), Duration::from_millis(100));
+        assert_eq!(failure_handler.backoff(20), Duration::from_secs(10));
+        assert!(failure_handler.should_retry(DEFAULT_MAX_ATTEMPTS - 2));
+        assert!(!failure_handler.should_retry(DEFAULT_MAX_ATTEMPTS - 1));
+    }
+
+    #[test]
+    pub fn checkpoint_id_generator_test() {
+        let mut generator = CheckpointIdGenerator::new(CheckpointId(0), 2);
+        assert_eq!(generator.next_id(), "0-2-0");
+        assert_eq!(generator.next_id(), "0-2-1");
+
+        // the checkpoint without the generator state
+        let mut generator = CheckpointIdGenerator::restore(CheckpointId(1634000000000), 2, &None);
+        assert_eq!(generator.next_id(), "1634000000000-2-0");
+    }
+
+    #[test]
+    pub fn checkpoint_id_replay_test() {
+        let mut generator = CheckpointIdGenerator::restore(CheckpointId(1), 2, &None);
+        let mut ids: Vec<String> = (0..3).map(|_| generator.next_id()).collect();
+
+        // the barrier of the checkpoint 2, then the application crash
+        let handle = Some(generator.snapshot());
+        ids.extend((0..2).map(|_| generator.next_id()));
+        assert_eq!(ids, vec!["1-2-0", "1-2-1", "1-2-2", "1-2-3", "1-2-4"]);
+
+        // replay the records after the barrier
+        let mut generator = CheckpointIdGenerator::restore(CheckpointId(2), 2, &handle);
+        let replayed: Vec<String> = (0..2).map(|_| generator.next_id()).collect();
+        assert_eq!(replayed, ids[3..].to_vec());
+
+        // replay all records across the barrier
+        let mut generator = CheckpointIdGenerator::restore(CheckpointId(1), 2, &None);
+        let replayed: Vec<String> = (0..5).map(|_| generator.next_id()).collect();
+        assert_eq!(replayed, ids);
+    }
+}
@@ -7,6 +7,7 @@ extern crate rlink_derive;
 #[macro_use]
 extern crate anyhow;
 
+pub mod connection;
 pub mod elasticsearch_sink;
 
 // pub static ES_DATA_TYPES: [u8; 2] = [
@@ -1,5 +1,5 @@
 use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
-use rlink::core::element::{FnSchema, Record};
+use rlink::core::element::{Element, FnSchema, Record};
 use rlink::core::function::{Context, OutputFormat};
 use rlink::core::runtime::TaskId;
 
@@ -33,6 +33,14 @@ impl OutputFormat for HdfsOutputFormat {
             .unwrap();
     }
 
+    fn observe_element(&mut self, element: &Element) {
+        if let Element::Watermark(watermark) = element {
+            self.writer_manager
+                .observe_watermark(watermark.timestamp())
+                .unwrap();
+        }
+    }
+
     fn close(&mut self) -> rlink::core::Result<()> {
         self.writer_manager.close()?;
         Ok(())
@@ -1,4 +1,10 @@
+use std::collections::HashMap;
 use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use rlink::utils::object_store::{ObjectStore, ObjectStoreUrl};
 
 use crate::writer::{FileSystem, FileSystemBuilder};
 
@@ -6,6 +12,9 @@ pub struct LocalFileSystem {}
 
 impl FileSystem<File> for LocalFileSystem {
     fn create_write(&mut self, path: &str) -> anyhow::Result<File> {
+        if let Some(dir) = Path::new(path).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
         File::create(path).map_err(|e| anyhow!(e))
     }
 }
@@ -17,3 +26,61 @@ impl FileSystemBuilder<LocalFileSystem, File> for LocalFileSystemBuilder {
         LocalFileSystem {}
     }
 }
+
+/// The writer buffers the content and puts it as an object on the `flush`
+pub struct ObjectWriter {
+    store: Arc<dyn ObjectStore>,
+    path: String,
+    buffer: Vec<u8>,
+}
+
+impl Write for ObjectWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.store
+            .put(self.path.as_str(), self.buffer.clone())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    }
+}
+
+/// Write the files to the object stores by the uri of the path, eg: `s3://bucket/path/file`,
+/// `gs://bucket/path/file`, `az://container/path/file` or a local path.
+/// The credentials are read from the env, see `rlink::utils::object_store`.
+pub struct ObjectStoreFileSystem {
+    stores: HashMap<String, Arc<dyn ObjectStore>>,
+}
+
+impl FileSystem<ObjectWriter> for ObjectStoreFileSystem {
+    fn create_write(&mut self, path: &str) -> anyhow::Result<ObjectWriter> {
+        let url = ObjectStoreUrl::parse(path)?;
+        let store_key = format!("{:?}/{}", url.scheme, url.bucket);
+        let store = match self.stores.get(store_key.as_str()) {
+            Some(store) => store.clone(),
+            None => {
+                let store = url.object_store()?;
+                self.stores.insert(store_key, store.clone());
+                store
+            }
+        };
+
+        Ok(ObjectWriter {
+            store,
+            path: url.path,
+            buffer: Vec::new(),
+        })
+    }
+}
+
+pub struct ObjectStoreFileSystemBuilder {}
+
+impl FileSystemBuilder<ObjectStoreFileSystem, ObjectWriter> for ObjectStoreFileSystemBuilder {
+    fn build(&self) -> ObjectStoreFileSystem {
+        ObjectStoreFileSystem {
+            stores: HashMap::new(),
+        }
+    }
+}
@@ -9,6 +9,8 @@ use rlink::core::runtime::TaskId;
 pub mod file_system;
 pub mod parquet_writer;
 pub mod parquet_writer_manager;
+pub mod partition;
+pub mod partitioned_writer_manager;
 
 pub trait FileSystem<W>
 where
@@ -39,6 +41,10 @@ pub trait BlockWriter {
 pub trait BlockWriterManager {
     fn open(&mut self) -> anyhow::Result<()>;
     fn append(&mut self, record: Record, task_id: &TaskId) -> anyhow::Result<()>;
+    /// the watermark arriving at the sink, eg: commit the partitions passed by it
+    fn observe_watermark(&mut self, _watermark: u64) -> anyhow::Result<()> {
+        Ok(())
+    }
     fn snapshot(&mut self) -> anyhow::Result<()>;
     fn close(&mut self) -> anyhow::Result<()>;
 }
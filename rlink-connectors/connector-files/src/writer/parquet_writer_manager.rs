@@ -76,6 +76,7 @@ impl ParquetBlockWriterManager {
                     FlushData::Bytes((path, bytes)) => {
                         let mut writer = fs.create_write(path.as_str()).unwrap();
                         writer.write_all(bytes.as_slice()).unwrap();
+                        writer.flush().unwrap();
 
                         info!("success write file {}", path);
                     }
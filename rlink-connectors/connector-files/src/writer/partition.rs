@@ -0,0 +1,261 @@
+use std::io::Write;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use rlink::core::element::Record;
+use rlink::utils::date_time::fmt_date_time;
+use rlink::utils::http::client::post_json_sync;
+
+use crate::writer::{FileSystem, FileSystemBuilder};
+
+/// the file marking the partition is complete, written by `SuccessFileCommitPolicy`
+pub const SUCCESS_FILE_NAME: &str = "_SUCCESS";
+
+/// A `name=value` level of the Hive-style partition path
+pub enum PartitionField {
+    /// the value is extracted from the record
+    Field {
+        name: String,
+        value: Box<dyn Fn(&mut Record) -> String + Send + Sync>,
+    },
+    /// the value is the event time of the record formatted by the `chrono` format, eg: `%Y-%m-%d`
+    EventTime { name: String, format: String },
+}
+
+impl PartitionField {
+    pub fn field<F>(name: &str, value: F) -> Self
+    where
+        F: Fn(&mut Record) -> String + Send + Sync + 'static,
+    {
+        PartitionField::Field {
+            name: name.to_string(),
+            value: Box::new(value),
+        }
+    }
+
+    pub fn event_time(name: &str, format: &str) -> Self {
+        PartitionField::EventTime {
+            name: name.to_string(),
+            format: format.to_string(),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            PartitionField::Field { name, .. } => name.as_str(),
+            PartitionField::EventTime { name, .. } => name.as_str(),
+        }
+    }
+
+    fn value(&self, record: &mut Record) -> String {
+        match self {
+            PartitionField::Field { value, .. } => value(record),
+            PartitionField::EventTime { format, .. } => {
+                fmt_date_time(Duration::from_millis(record.timestamp()), format.as_str())
+            }
+        }
+    }
+}
+
+/// Bucket the records to the Hive-style partition paths, eg: `dt=2024-05-01/hour=13`
+pub struct HivePartitioner {
+    fields: Vec<PartitionField>,
+    /// the event time span of a partition, eg: 1 hour for the `dt/hour` partitions,
+    /// the partition is committed when the watermark passes its end. `None` if the partitions
+    /// are not bounded by the event time and never committed
+    granularity: Option<Duration>,
+}
+
+impl HivePartitioner {
+    pub fn new(fields: Vec<PartitionField>, granularity: Option<Duration>) -> Self {
+        HivePartitioner {
+            fields,
+            granularity,
+        }
+    }
+
+    /// the relative path of the record's partition
+    pub fn partition(&self, record: &mut Record) -> String {
+        self.fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{}={}",
+                    escape_path_name(field.name()),
+                    escape_path_name(field.value(record).as_str())
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    /// the end of the event time span the `timestamp` belongs to. The spans are aligned to
+    /// the epoch, so the end of a partition in the local timezone may be later than its
+    /// exact end, it's committed late but never early
+    pub fn partition_end(&self, timestamp: u64) -> Option<u64> {
+        self.granularity.map(|granularity| {
+            let granularity = granularity.as_millis() as u64;
+            (timestamp / granularity + 1) * granularity
+        })
+    }
+}
+
+/// escape the special characters of the partition name and value as `%XX`, same as Hive
+fn escape_path_name(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\u{01}'..='\u{1F}'
+            | '"'
+            | '#'
+            | '%'
+            | '\''
+            | '*'
+            | '/'
+            | ':'
+            | '='
+            | '?'
+            | '\\'
+            | '\u{7F}'
+            | '{'
+            | '['
+            | ']'
+            | '^' => escaped.push_str(format!("%{:02X}", c as u32).as_str()),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// When to roll the part file of a partition bucket over, besides it reaches the max bytes
+#[derive(Clone, Debug)]
+pub struct RollingPolicy {
+    /// the max time a part file is open
+    pub rollover_interval: Duration,
+    /// the max time a part file is open without new records
+    pub inactivity_interval: Duration,
+}
+
+impl Default for RollingPolicy {
+    fn default() -> Self {
+        RollingPolicy {
+            rollover_interval: Duration::from_secs(60 * 15),
+            inactivity_interval: Duration::from_secs(60 * 5),
+        }
+    }
+}
+
+impl RollingPolicy {
+    pub fn should_roll(&self, opened_at: u64, last_append_at: u64, now: u64) -> bool {
+        now.saturating_sub(opened_at) >= self.rollover_interval.as_millis() as u64
+            || now.saturating_sub(last_append_at) >= self.inactivity_interval.as_millis() as u64
+    }
+}
+
+/// A partition passed by the watermark, all its part files are written
+#[derive(Clone, Debug)]
+pub struct PartitionCommit {
+    /// the full path of the partition directory
+    pub path: String,
+    /// the relative path of the partition, eg: `dt=2024-05-01/hour=13`
+    pub partition: String,
+    pub partition_end: u64,
+    /// the part files written to the partition by the task since the last commit
+    pub files: Vec<String>,
+}
+
+/// The hook called in order on a partition is committed, eg: write the `_SUCCESS` file,
+/// notify the downstream system. A partition receiving the late records is committed again
+pub trait PartitionCommitPolicy: Send {
+    fn commit(&mut self, partition: &PartitionCommit) -> anyhow::Result<()>;
+}
+
+/// Write an empty `_SUCCESS` file to the committed partition
+pub struct SuccessFileCommitPolicy<FsB, FS, W> {
+    fs_builder: FsB,
+    a: PhantomData<fn() -> (FS, W)>,
+}
+
+impl<FsB, FS, W> SuccessFileCommitPolicy<FsB, FS, W>
+where
+    FsB: FileSystemBuilder<FS, W>,
+    FS: FileSystem<W>,
+    W: Write,
+{
+    pub fn new(fs_builder: FsB) -> Self {
+        SuccessFileCommitPolicy {
+            fs_builder,
+            a: PhantomData,
+        }
+    }
+}
+
+impl<FsB, FS, W> PartitionCommitPolicy for SuccessFileCommitPolicy<FsB, FS, W>
+where
+    FsB: FileSystemBuilder<FS, W>,
+    FS: FileSystem<W>,
+    W: Write,
+{
+    fn commit(&mut self, partition: &PartitionCommit) -> anyhow::Result<()> {
+        let path = format!("{}/{}", partition.path, SUCCESS_FILE_NAME);
+        let mut writer = self.fs_builder.build().create_write(path.as_str())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Post the committed partition to the webhook as json
+pub struct WebhookCommitPolicy {
+    url: String,
+}
+
+impl WebhookCommitPolicy {
+    pub fn new(url: &str) -> Self {
+        WebhookCommitPolicy {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl PartitionCommitPolicy for WebhookCommitPolicy {
+    fn commit(&mut self, partition: &PartitionCommit) -> anyhow::Result<()> {
+        let body = serde_json::json!({
+            "path": partition.path,
+            "partition": partition.partition,
+            "partitionEnd": partition.partition_end,
+            "files": partition.files,
+        });
+        post_json_sync(self.url.as_str(), body.to_string()).map_err(|e| anyhow!(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rlink::core::element::Record;
+
+    use crate::writer::partition::{escape_path_name, HivePartitioner, PartitionField};
+
+    #[test]
+    pub fn hive_partitioner_test() {
+        let partitioner = HivePartitioner::new(
+            vec![
+                PartitionField::field("region", |_record| "us/east".to_string()),
+                PartitionField::event_time("dt", "%Y-%m-%d"),
+            ],
+            Some(Duration::from_secs(3600)),
+        );
+
+        let partition = partitioner.partition(&mut Record::new());
+        assert!(partition.starts_with("region=us%2Feast/dt="));
+        assert_eq!(escape_path_name("a=b:c"), "a%3Db%3Ac");
+
+        assert_eq!(partitioner.partition_end(0), Some(3600 * 1000));
+        assert_eq!(
+            partitioner.partition_end(3600 * 1000 - 1),
+            Some(3600 * 1000)
+        );
+        assert_eq!(partitioner.partition_end(3600 * 1000), Some(7200 * 1000));
+    }
+}
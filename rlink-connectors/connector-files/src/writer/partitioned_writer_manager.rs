@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use parquet::file::properties::WriterPropertiesPtr;
+use parquet::schema::types::TypePtr;
+use rlink::channel::{bounded, Receiver, Sender};
+use rlink::core::element::Record;
+use rlink::core::runtime::TaskId;
+use rlink::utils::date_time::current_timestamp_millis;
+
+use crate::writer::parquet_writer::{BlocksBuilder, ParquetBlockWriter};
+use crate::writer::partition::{
+    HivePartitioner, PartitionCommit, PartitionCommitPolicy, RollingPolicy,
+};
+use crate::writer::{BlockWriter, BlockWriterManager, FileSystem, FileSystemBuilder};
+
+enum FlushData {
+    Bytes((String, Vec<u8>)),
+    /// committed after the part files of the partition sent before it are written
+    Commit(PartitionCommit),
+    Finish(Sender<bool>),
+}
+
+/// the open part file of a partition
+struct Bucket {
+    writer: ParquetBlockWriter,
+    opened_at: u64,
+    last_append_at: u64,
+}
+
+/// the partition open in the task
+#[derive(Default)]
+struct PartitionStatus {
+    /// the max end of the event time spans of the records
+    end: Option<u64>,
+    /// the part files written since the last commit
+    files: Vec<String>,
+}
+
+/// Write the records to the Hive-style partitions under the `base_path`, each partition
+/// has its own part file rolled over by the max bytes and the `RollingPolicy`. The partition
+/// is committed by the `PartitionCommitPolicy`s when the watermark passes its end.
+pub struct PartitionedBlockWriterManager {
+    row_group_size: usize,
+    max_bytes: i64,
+    schema: TypePtr,
+    props: WriterPropertiesPtr,
+    blocks_builder: Arc<Box<dyn BlocksBuilder>>,
+    base_path: String,
+    partitioner: HivePartitioner,
+    rolling_policy: RollingPolicy,
+
+    task_id: TaskId,
+    file_sequence: u64,
+    buckets: HashMap<String, Bucket>,
+    partitions: HashMap<String, PartitionStatus>,
+
+    bytes_flush_sender: Sender<FlushData>,
+}
+
+impl PartitionedBlockWriterManager {
+    pub fn new<FsB, FS, W>(
+        row_group_size: usize,
+        max_bytes: i64,
+        schema: TypePtr,
+        props: WriterPropertiesPtr,
+        blocks_builder: Arc<Box<dyn BlocksBuilder>>,
+        base_path: &str,
+        partitioner: HivePartitioner,
+        rolling_policy: RollingPolicy,
+        commit_policies: Vec<Box<dyn PartitionCommitPolicy>>,
+        fs_factory: FsB,
+    ) -> Self
+    where
+        FsB: FileSystemBuilder<FS, W> + 'static,
+        FS: FileSystem<W>,
+        W: Write,
+    {
+        let (sender, receiver) = bounded(10);
+        Self::fs_write(fs_factory, commit_policies, receiver);
+        Self {
+            row_group_size,
+            max_bytes,
+            schema,
+            props,
+            blocks_builder,
+            base_path: base_path.trim_end_matches('/').to_string(),
+            partitioner,
+            rolling_policy,
+            task_id: TaskId::default(),
+            file_sequence: 0,
+            buckets: HashMap::new(),
+            partitions: HashMap::new(),
+            bytes_flush_sender: sender,
+        }
+    }
+
+    fn fs_write<FsB, FS, W>(
+        fs_factory: FsB,
+        mut commit_policies: Vec<Box<dyn PartitionCommitPolicy>>,
+        bytes_receiver: Receiver<FlushData>,
+    ) where
+        FsB: FileSystemBuilder<FS, W> + 'static,
+        FS: FileSystem<W>,
+        W: Write,
+    {
+        rlink::utils::thread::spawn("file_writer", move || {
+            let mut fs = fs_factory.build();
+
+            while let Ok(data) = bytes_receiver.recv() {
+                match data {
+                    FlushData::Bytes((path, bytes)) => {
+                        let mut writer = fs.create_write(path.as_str()).unwrap();
+                        writer.write_all(bytes.as_slice()).unwrap();
+                        writer.flush().unwrap();
+
+                        info!("success write file {}", path);
+                    }
+                    FlushData::Commit(partition) => {
+                        for policy in commit_policies.iter_mut() {
+                            if let Err(e) = policy.commit(&partition) {
+                                error!("commit partition {} error. {}", partition.path, e);
+                            }
+                        }
+                        info!(
+                            "commit partition {}, {} files",
+                            partition.path,
+                            partition.files.len()
+                        );
+                    }
+                    FlushData::Finish(notify) => notify.send(true).unwrap(),
+                }
+            }
+        });
+    }
+
+    fn create_writer(&self) -> ParquetBlockWriter {
+        ParquetBlockWriter::new(
+            self.row_group_size,
+            self.max_bytes,
+            self.schema.clone(),
+            self.props.clone(),
+            self.blocks_builder.clone(),
+        )
+    }
+
+    /// close the part file of the partition and send it to the file writer
+    fn flush_bucket(&mut self, partition: &str) -> anyhow::Result<()> {
+        let bucket = match self.buckets.remove(partition) {
+            Some(bucket) => bucket,
+            None => return Ok(()),
+        };
+        let bytes = bucket.writer.close()?;
+
+        let path = format!(
+            "{}/{}/part-{}-{}-{}-{}.parquet",
+            self.base_path,
+            partition,
+            self.task_id.job_id().0,
+            self.task_id.task_number(),
+            bucket.opened_at,
+            self.file_sequence
+        );
+        self.file_sequence += 1;
+
+        self.partitions
+            .entry(partition.to_string())
+            .or_default()
+            .files
+            .push(path.clone());
+        self.bytes_flush_sender
+            .send(FlushData::Bytes((path, bytes)))
+            .unwrap();
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        let partitions: Vec<String> = self.buckets.keys().cloned().collect();
+        for partition in partitions {
+            self.flush_bucket(partition.as_str())?;
+        }
+
+        Ok(())
+    }
+
+    /// roll over the part files reaching the `RollingPolicy`
+    fn roll(&mut self, now: u64) -> anyhow::Result<()> {
+        let rolling_policy = &self.rolling_policy;
+        let partitions: Vec<String> = self
+            .buckets
+            .iter()
+            .filter(|(_, bucket)| {
+                rolling_policy.should_roll(bucket.opened_at, bucket.last_append_at, now)
+            })
+            .map(|(partition, _)| partition.clone())
+            .collect();
+        for partition in partitions {
+            self.flush_bucket(partition.as_str())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl BlockWriterManager for PartitionedBlockWriterManager {
+    fn open(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn append(&mut self, mut record: Record, task_id: &TaskId) -> anyhow::Result<()> {
+        self.task_id = *task_id;
+
+        let partition = self.partitioner.partition(&mut record);
+        let partition_end = self.partitioner.partition_end(record.timestamp());
+        let status = self.partitions.entry(partition.clone()).or_default();
+        status.end = status.end.max(partition_end);
+
+        let now = current_timestamp_millis();
+        if !self.buckets.contains_key(partition.as_str()) {
+            let bucket = Bucket {
+                writer: self.create_writer(),
+                opened_at: now,
+                last_append_at: now,
+            };
+            self.buckets.insert(partition.clone(), bucket);
+        }
+
+        let bucket = self.buckets.get_mut(partition.as_str()).unwrap();
+        bucket.last_append_at = now;
+        let full = bucket.writer.append(record)?;
+        if full {
+            self.flush_bucket(partition.as_str())?;
+        }
+
+        Ok(())
+    }
+
+    fn observe_watermark(&mut self, watermark: u64) -> anyhow::Result<()> {
+        self.roll(current_timestamp_millis())?;
+
+        let passed_partitions: Vec<String> = self
+            .partitions
+            .iter()
+            .filter(|(_, status)| status.end.map(|end| end <= watermark).unwrap_or(false))
+            .map(|(partition, _)| partition.clone())
+            .collect();
+        for partition in passed_partitions {
+            self.flush_bucket(partition.as_str())?;
+
+            let status = self.partitions.remove(partition.as_str()).unwrap();
+            let commit = PartitionCommit {
+                path: format!("{}/{}", self.base_path, partition),
+                partition,
+                partition_end: status.end.unwrap(),
+                files: status.files,
+            };
+            self.bytes_flush_sender
+                .send(FlushData::Commit(commit))
+                .unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn snapshot(&mut self) -> anyhow::Result<()> {
+        self.flush()
+    }
+
+    fn close(&mut self) -> anyhow::Result<()> {
+        self.flush()?;
+
+        let (sender, receiver) = bounded(0);
+        self.bytes_flush_sender
+            .send(FlushData::Finish(sender))
+            .unwrap();
+        receiver.recv().unwrap();
+
+        Ok(())
+    }
+}
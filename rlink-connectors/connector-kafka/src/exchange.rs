@@ -0,0 +1,200 @@
+//! The named exchange backed by a kafka topic, see `rlink::core::exchange`.
+//!
+//! The publisher writes the records to the topic `rlink-exchange-{name}` as the payloads in the
+//! binary format of `Serde for Record` and registers the exchange with the input schema of the
+//! sink. The subscriber looks up the catalog and reads the topic as a source of that schema:
+//! ```ignore
+//! let exchange = KafkaExchange::new("enrichment", conf_map, catalog);
+//! // in the publishing application
+//! data_stream.add_sink(exchange.publisher());
+//! // in the subscribing applications
+//! env.register_source(exchange.subscriber("group_b", 3)?);
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::BytesMut;
+use rdkafka::ClientConfig;
+use rlink::core;
+use rlink::core::checkpoint::CheckpointFunction;
+use rlink::core::element::{FnSchema, Record, Serde};
+use rlink::core::exchange::{ExchangeCatalog, ExchangeDescriptor, ExchangeTransport};
+use rlink::core::function::{Context, OutputFormat};
+use rlink::core::lineage::LineageDataset;
+use rlink::core::properties::SystemProperties;
+use rlink::utils::date_time::current_timestamp_millis;
+
+use crate::source::builder::KafkaInputFormatBuilder;
+use crate::source::deserializer::{KafkaRecordDeserializer, KafkaRecordDeserializerBuilder};
+use crate::{
+    build_kafka_record, KafkaInputFormat, KafkaOutputFormat, BOOTSTRAP_SERVERS, GROUP_ID,
+    SINK_CHANNEL_SIZE,
+};
+
+/// the prefix of the topics of the exchanges
+pub const EXCHANGE_TOPIC_PREFIX: &str = "rlink-exchange-";
+
+#[derive(Clone)]
+pub struct KafkaExchange {
+    name: String,
+    /// the config of the kafka clients, must contain the `bootstrap.servers`
+    conf_map: HashMap<String, String>,
+    catalog: Arc<dyn ExchangeCatalog>,
+}
+
+impl KafkaExchange {
+    pub fn new<C>(name: &str, conf_map: HashMap<String, String>, catalog: C) -> Self
+    where
+        C: ExchangeCatalog + 'static,
+    {
+        KafkaExchange {
+            name: name.to_string(),
+            conf_map,
+            catalog: Arc::new(catalog),
+        }
+    }
+
+    pub fn topic(&self) -> String {
+        format!("{}{}", EXCHANGE_TOPIC_PREFIX, self.name)
+    }
+
+    /// the sink publishing the records of the stream to the exchange
+    pub fn publisher(&self) -> KafkaExchangeOutputFormat {
+        let bootstrap_servers = self
+            .conf_map
+            .get(BOOTSTRAP_SERVERS)
+            .cloned()
+            .expect("`bootstrap.servers` not found in the exchange config");
+
+        let mut client_config = ClientConfig::new();
+        for (key, val) in &self.conf_map {
+            client_config.set(key.as_str(), val.as_str());
+        }
+        let topic = self.topic();
+        let output_format =
+            KafkaOutputFormat::new(client_config, Some(topic.clone()), SINK_CHANNEL_SIZE);
+
+        KafkaExchangeOutputFormat {
+            name: self.name.clone(),
+            transport: ExchangeTransport::Kafka {
+                bootstrap_servers,
+                topic,
+            },
+            catalog: self.catalog.clone(),
+            output_format,
+        }
+    }
+
+    /// the source subscribing the exchange in the consumer group `group_id`, the exchange must
+    /// be published, the bootstrap servers and the topic are read from the catalog
+    pub fn subscriber(&self, group_id: &str, parallelism: u16) -> anyhow::Result<KafkaInputFormat> {
+        let descriptor = self.catalog.lookup(self.name.as_str())?;
+        let (bootstrap_servers, topic) = match &descriptor.transport {
+            ExchangeTransport::Kafka {
+                bootstrap_servers,
+                topic,
+            } => (bootstrap_servers.clone(), topic.clone()),
+        };
+
+        let mut conf_map = self.conf_map.clone();
+        conf_map.insert(BOOTSTRAP_SERVERS.to_string(), bootstrap_servers);
+        conf_map.insert(GROUP_ID.to_string(), group_id.to_string());
+
+        let deserializer_builder: Box<dyn KafkaRecordDeserializerBuilder> =
+            Box::new(ExchangeRecordDeserializerBuilder {
+                schema: descriptor.schema,
+            });
+        let input_format = KafkaInputFormatBuilder::new(conf_map, vec![topic], parallelism)
+            .fn_name(format!("KafkaExchange_{}", self.name).as_str())
+            .build(Some(deserializer_builder));
+        Ok(input_format)
+    }
+}
+
+/// The sink of `KafkaExchange::publisher`, the first task registers the exchange on opening
+#[derive(NamedFunction)]
+pub struct KafkaExchangeOutputFormat {
+    name: String,
+    transport: ExchangeTransport,
+    catalog: Arc<dyn ExchangeCatalog>,
+    output_format: KafkaOutputFormat,
+}
+
+impl OutputFormat for KafkaExchangeOutputFormat {
+    fn open(&mut self, context: &Context) -> core::Result<()> {
+        if context.task_id.task_number() == 0 {
+            let application_name = context.application_properties.get_application_name();
+            let descriptor = ExchangeDescriptor::new(
+                self.name.as_str(),
+                application_name.as_str(),
+                context.input_schema.clone(),
+                self.transport.clone(),
+            );
+            self.catalog.publish(&descriptor)?;
+            info!("exchange `{}` published", self.name);
+        }
+
+        self.output_format.open(context)
+    }
+
+    fn write_record(&mut self, record: Record) {
+        let payload = record.to_bytes();
+        let kafka_record = build_kafka_record(
+            current_timestamp_millis() as i64,
+            &[],
+            payload.as_ref(),
+            "",
+            0,
+            0,
+        )
+        .expect("build the exchange record error");
+        self.output_format.write_record(kafka_record);
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        self.output_format.close()
+    }
+
+    fn probe(&self) -> core::Result<()> {
+        self.output_format.probe()
+    }
+
+    fn lineage_datasets(&self) -> Vec<LineageDataset> {
+        self.output_format.lineage_datasets()
+    }
+}
+
+impl CheckpointFunction for KafkaExchangeOutputFormat {}
+
+/// Deserialize the payloads written by `KafkaExchangeOutputFormat`
+pub struct ExchangeRecordDeserializer {}
+
+impl KafkaRecordDeserializer for ExchangeRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        _timestamp: i64,
+        _key: &[u8],
+        payload: &[u8],
+        _topic: &str,
+        _partition: i32,
+        _offset: i64,
+    ) -> Vec<Record> {
+        let mut bytes = BytesMut::from(payload);
+        vec![Record::deserialize(&mut bytes)]
+    }
+}
+
+pub struct ExchangeRecordDeserializerBuilder {
+    schema: FnSchema,
+}
+
+impl KafkaRecordDeserializerBuilder for ExchangeRecordDeserializerBuilder {
+    fn build(&self) -> Box<dyn KafkaRecordDeserializer> {
+        Box::new(ExchangeRecordDeserializer {})
+    }
+
+    fn schema(&self) -> FnSchema {
+        self.schema.clone()
+    }
+}
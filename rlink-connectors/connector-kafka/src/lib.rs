@@ -7,6 +7,7 @@ extern crate rlink_derive;
 #[macro_use]
 extern crate anyhow;
 
+pub mod exchange;
 pub mod sink;
 pub mod source;
 
@@ -14,19 +15,46 @@ pub mod buffer_gen {
     include!(concat!(env!("OUT_DIR"), "/buffer_gen/mod.rs"));
 }
 
+pub use exchange::KafkaExchange;
 pub use sink::output_format::KafkaOutputFormat;
+pub use sink::transactional::KafkaTransactionalOutputFormat;
 pub use source::input_format::KafkaInputFormat;
+pub use source::watermark::KafkaPartitionWatermarks;
 
+use rdkafka::ClientConfig;
 use rlink::core::element::Record;
+use rlink::core::lineage::LineageDataset;
 
 use crate::buffer_gen::kafka_message;
 
 pub const KAFKA: &str = "kafka";
 pub const BOOTSTRAP_SERVERS: &str = "bootstrap.servers";
 pub const GROUP_ID: &str = "group.id";
+/// the kafka consumer config, `read_committed` consumes only the committed records of the
+/// transactional producers, eg: `kafka.isolation.level=read_committed`
+pub const ISOLATION_LEVEL: &str = "isolation.level";
+pub const READ_COMMITTED: &str = "read_committed";
 
 pub const TOPICS: &str = "topics";
 pub const BUFFER_SIZE: &str = "buffer.size";
+/// the prefix of the `transactional.id`s of the exactly-once sink, see `KafkaTransactionalOutputFormat`
+pub const TRANSACTIONAL_ID_PREFIX: &str = "transactional.id.prefix";
+/// the comma separated `KafkaMetadataColumn`s, eg: `topic,partition,offset,timestamp,ingestion_time`
+pub const METADATA_COLUMNS: &str = "metadata.columns";
+/// the cluster-wide records per second of all tasks, see `rlink::core::rate_budget::RateBudget`
+pub const RATE_BUDGET: &str = "rate.budget";
+/// the name of the rate budget, the sources with the same name share the budget, default the fn name
+pub const RATE_BUDGET_NAME: &str = "rate.budget.name";
+/// the millis of refreshing the metadata to discover the new partitions, disabled if absent or 0
+pub const PARTITION_DISCOVERY_INTERVAL: &str = "partition.discovery.interval.ms";
+
+/// the strategy of assigning the partitions to the tasks: `round-robin`(default), `range`,
+/// `sticky` or `rack-aware`, see `source::assignment`
+pub const ASSIGNMENT_STRATEGY: &str = "assignment.strategy";
+/// the comma separated `broker_id:rack` of the `rack-aware` strategy, eg: `1:az-a,2:az-b`
+pub const ASSIGNMENT_BROKER_RACKS: &str = "assignment.broker.racks";
+/// the comma separated racks of the tasks of the `rack-aware` strategy, indexed by task number
+pub const ASSIGNMENT_TASK_RACKS: &str = "assignment.task.racks";
 
 pub const OFFSET: &str = "offset";
 pub const OFFSET_TYPE: &str = "type";
@@ -39,6 +67,23 @@ pub const OUTPUT_FORMAT_FN_NAME_DEFAULT: &str = "KafkaOutputFormat";
 pub const SOURCE_CHANNEL_SIZE: usize = 50000;
 pub const SINK_CHANNEL_SIZE: usize = 50000;
 
+/// the datasets of the topics named by the OpenLineage conventions, the namespace is
+/// `kafka://{host:port}` of the first bootstrap server
+pub(crate) fn lineage_datasets(
+    client_config: &ClientConfig,
+    topics: &[String],
+) -> Vec<LineageDataset> {
+    let bootstrap_server = client_config
+        .get(BOOTSTRAP_SERVERS)
+        .and_then(|servers| servers.split(',').next())
+        .unwrap_or_default();
+    let namespace = format!("kafka://{}", bootstrap_server.trim());
+    topics
+        .iter()
+        .map(|topic| LineageDataset::new(namespace.as_str(), topic.as_str()))
+        .collect()
+}
+
 pub fn build_kafka_record(
     timestamp: i64,
     key: &[u8],
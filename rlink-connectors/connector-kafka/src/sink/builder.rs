@@ -1,12 +1,14 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::sync::Arc;
 
 use rdkafka::ClientConfig;
 use rlink::core::properties::Properties;
 
+use crate::sink::delivery::{DeliveryFailureHandler, FailureHandler};
 use crate::{
-    KafkaOutputFormat, BOOTSTRAP_SERVERS, BUFFER_SIZE, KAFKA, SINK_CHANNEL_SIZE,
-    SOURCE_CHANNEL_SIZE, TOPICS,
+    KafkaOutputFormat, KafkaTransactionalOutputFormat, BOOTSTRAP_SERVERS, BUFFER_SIZE, KAFKA,
+    SINK_CHANNEL_SIZE, SOURCE_CHANNEL_SIZE, TOPICS, TRANSACTIONAL_ID_PREFIX,
 };
 
 #[derive(Debug)]
@@ -14,6 +16,8 @@ pub struct KafkaOutputFormatBuilder {
     conf_map: HashMap<String, String>,
     topics: Option<String>,
     buffer_size: Option<usize>,
+    failure_handler: Option<FailureHandler>,
+    transactional_id_prefix: Option<String>,
 }
 
 impl KafkaOutputFormatBuilder {
@@ -22,6 +26,8 @@ impl KafkaOutputFormatBuilder {
             conf_map,
             topics,
             buffer_size: None,
+            failure_handler: None,
+            transactional_id_prefix: None,
         }
     }
 
@@ -30,17 +36,56 @@ impl KafkaOutputFormatBuilder {
         self
     }
 
-    pub fn build(self) -> KafkaOutputFormat {
-        info!("build kafka sink with: {:?}", &self);
+    /// handle the records failed to deliver, the failures are counted by `KafkaSink_Failed` anyway
+    pub fn failure_handler<H>(mut self, failure_handler: H) -> Self
+    where
+        H: DeliveryFailureHandler + 'static,
+    {
+        self.failure_handler = Some(FailureHandler(Arc::new(failure_handler)));
+        self
+    }
 
+    /// write exactly-once by the transactional producers, see `build_transactional`
+    pub fn transactional(mut self, transactional_id_prefix: &str) -> Self {
+        self.transactional_id_prefix = Some(transactional_id_prefix.to_string());
+        self
+    }
+
+    fn client_config(&self) -> ClientConfig {
         let mut client_config = ClientConfig::new();
         for (key, val) in &self.conf_map {
             client_config.set(key.as_str(), val.as_str());
         }
+        client_config
+    }
+
+    /// build the exactly-once sink, the `transactional` prefix must be set and unique in the
+    /// kafka cluster, the buffer size and the failure handler are not used, a delivery
+    /// failure fails the commit of the transaction
+    pub fn build_transactional(self) -> anyhow::Result<KafkaTransactionalOutputFormat> {
+        info!("build kafka transactional sink with: {:?}", &self);
+
+        let transactional_id_prefix = self
+            .transactional_id_prefix
+            .as_ref()
+            .ok_or_else(|| anyhow!("the transactional id prefix is not set"))?;
+        Ok(KafkaTransactionalOutputFormat::new(
+            self.client_config(),
+            self.topics.clone(),
+            transactional_id_prefix.as_str(),
+        ))
+    }
+
+    pub fn build(self) -> KafkaOutputFormat {
+        info!("build kafka sink with: {:?}", &self);
+
+        let client_config = self.client_config();
 
         let buffer_size = self.buffer_size.unwrap_or(SOURCE_CHANNEL_SIZE);
 
-        KafkaOutputFormat::new(client_config, self.topics, buffer_size)
+        let mut output_format = KafkaOutputFormat::new(client_config, self.topics, buffer_size);
+        output_format.set_failure_handler(self.failure_handler);
+        output_format
     }
 }
 
@@ -72,7 +117,11 @@ impl TryFrom<Properties> for KafkaOutputFormatBuilder {
             .get_usize(BUFFER_SIZE)
             .unwrap_or(SINK_CHANNEL_SIZE);
 
-        let builder = KafkaOutputFormatBuilder::new(client_config, topic).buffer_size(buffer_size);
+        let mut builder =
+            KafkaOutputFormatBuilder::new(client_config, topic).buffer_size(buffer_size);
+        if let Ok(transactional_id_prefix) = properties.get_string(TRANSACTIONAL_ID_PREFIX) {
+            builder = builder.transactional(transactional_id_prefix.as_str());
+        }
 
         Ok(builder)
     }
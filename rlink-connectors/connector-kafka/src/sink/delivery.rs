@@ -0,0 +1,76 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use rdkafka::message::{Message, OwnedMessage};
+use rlink::metrics::metric::{Counter, Gauge};
+use rlink::metrics::{register_counter, register_gauge, Tag};
+
+/// A record that failed to be delivered to kafka after the retries of the producer
+#[derive(Clone, Debug)]
+pub struct DeliveryFailure {
+    pub topic: String,
+    pub key: Vec<u8>,
+    pub payload: Vec<u8>,
+    pub timestamp: i64,
+    pub error: String,
+}
+
+impl DeliveryFailure {
+    pub(crate) fn from_message(message: &OwnedMessage, error: String) -> Self {
+        DeliveryFailure {
+            topic: message.topic().to_string(),
+            key: message.key().map(|x| x.to_vec()).unwrap_or_default(),
+            payload: message.payload().map(|x| x.to_vec()).unwrap_or_default(),
+            timestamp: message.timestamp().to_millis().unwrap_or_default(),
+            error,
+        }
+    }
+}
+
+/// Handle the records failed to deliver, eg: write them to a dead letter storage or alert.
+/// It's called in the producer thread, so the handler should not block for long.
+pub trait DeliveryFailureHandler: Send + Sync {
+    fn on_failure(&self, failure: DeliveryFailure);
+}
+
+impl<F> DeliveryFailureHandler for F
+where
+    F: Fn(DeliveryFailure) + Send + Sync,
+{
+    fn on_failure(&self, failure: DeliveryFailure) {
+        self(failure)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct FailureHandler(pub(crate) Arc<dyn DeliveryFailureHandler>);
+
+impl Debug for FailureHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DeliveryFailureHandler")
+    }
+}
+
+/// The delivery metrics of the kafka sink task:
+/// `KafkaSink_Acked`: the records acknowledged by the brokers
+/// `KafkaSink_Failed`: the records failed to send or deliver
+/// `KafkaSink_Retried`: the sending retries when the producer queue is full
+/// `KafkaSink_QueueDepth`: the messages in the producer queue waiting for the acknowledgement
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DeliveryMetrics {
+    pub(crate) acked: Counter,
+    pub(crate) failed: Counter,
+    pub(crate) retried: Counter,
+    pub(crate) queue_depth: Gauge,
+}
+
+impl DeliveryMetrics {
+    pub(crate) fn register(tags: Vec<Tag>) -> Self {
+        DeliveryMetrics {
+            acked: register_counter("KafkaSink_Acked", tags.clone()),
+            failed: register_counter("KafkaSink_Failed", tags.clone()),
+            retried: register_counter("KafkaSink_Retried", tags.clone()),
+            queue_depth: register_gauge("KafkaSink_QueueDepth", tags),
+        }
+    }
+}
@@ -1,3 +1,5 @@
 pub mod builder;
+pub mod delivery;
 pub mod output_format;
 pub mod producer;
+pub mod transactional;
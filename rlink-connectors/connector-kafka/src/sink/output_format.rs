@@ -1,14 +1,23 @@
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use rdkafka::producer::{BaseProducer, Producer};
 use rdkafka::ClientConfig;
 use rlink::channel::utils::handover::Handover;
+use rlink::core::cancellation::CancellationToken;
 use rlink::core::checkpoint::CheckpointFunction;
 use rlink::core::element::Record;
 use rlink::core::function::{Context, NamedFunction, OutputFormat};
+use rlink::core::lineage::LineageDataset;
 use rlink::metrics::Tag;
-use rlink::utils::thread::async_runtime;
 use rlink::{core, utils};
 
+use crate::sink::delivery::{DeliveryFailureHandler, DeliveryMetrics, FailureHandler};
 use crate::sink::producer::KafkaProducerThread;
 
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
 #[derive(NamedFunction)]
 pub struct KafkaOutputFormat {
     client_config: ClientConfig,
@@ -16,6 +25,10 @@ pub struct KafkaOutputFormat {
 
     buffer_size: usize,
     handover: Option<Handover>,
+    failure_handler: Option<FailureHandler>,
+    /// a child of the task's token, cancelled on `close` to stop the write loop
+    cancellation_token: CancellationToken,
+    write_thread: Option<JoinHandle<()>>,
 }
 
 impl KafkaOutputFormat {
@@ -25,8 +38,24 @@ impl KafkaOutputFormat {
             topic,
             buffer_size,
             handover: None,
+            failure_handler: None,
+            cancellation_token: CancellationToken::new(),
+            write_thread: None,
         }
     }
+
+    /// handle the records failed to deliver, see `DeliveryFailureHandler`
+    pub fn with_failure_handler<H>(mut self, failure_handler: H) -> Self
+    where
+        H: DeliveryFailureHandler + 'static,
+    {
+        self.failure_handler = Some(FailureHandler(Arc::new(failure_handler)));
+        self
+    }
+
+    pub(crate) fn set_failure_handler(&mut self, failure_handler: Option<FailureHandler>) {
+        self.failure_handler = failure_handler;
+    }
 }
 
 impl OutputFormat for KafkaOutputFormat {
@@ -36,26 +65,77 @@ impl OutputFormat for KafkaOutputFormat {
             "topic",
             self.topic.as_ref().map(|x| x.as_str()).unwrap_or(""),
         ));
-        self.handover = Some(Handover::new(self.name(), tags, self.buffer_size));
+        self.handover = Some(Handover::new(self.name(), tags.clone(), self.buffer_size));
+        self.cancellation_token = context.cancellation_token.child();
 
         let topic = self.topic.clone();
         let client_config = self.client_config.clone();
         let handover = self.handover.as_ref().unwrap().clone();
-        utils::thread::spawn("kafka-sink-block", move || {
-            async_runtime("kafka_sink").block_on(async {
-                let mut kafka_consumer = KafkaProducerThread::new(topic, client_config, handover);
+        let metrics = DeliveryMetrics::register(tags);
+        let failure_handler = self.failure_handler.clone();
+        let cancellation_token = self.cancellation_token.clone();
+        let runtime = context.async_runtime();
+        let write_thread = utils::thread::spawn("kafka-sink-block", move || {
+            runtime.block_on(async {
+                let mut kafka_consumer = KafkaProducerThread::new(
+                    topic,
+                    client_config,
+                    handover,
+                    metrics,
+                    failure_handler,
+                )
+                .with_cancellation(cancellation_token);
                 kafka_consumer.run().await;
             });
         });
+        self.write_thread = Some(write_thread);
 
         Ok(())
     }
 
     fn write_record(&mut self, record: Record) {
-        self.handover.as_ref().unwrap().produce(record).unwrap();
+        let handover = self.handover.as_ref().unwrap();
+        if !handover.produce_cancellable(record, &self.cancellation_token) {
+            debug!("the kafka write loop exited, the record is dropped");
+        }
     }
 
+    /// the write loop sends the records in the handover and exits
     fn close(&mut self) -> core::Result<()> {
+        self.cancellation_token.cancel("the kafka sink is closed");
+        if let Some(write_thread) = self.write_thread.take() {
+            write_thread
+                .join()
+                .map_err(|_e| anyhow!("the kafka write thread panicked"))?;
+        }
+        Ok(())
+    }
+
+    /// the configured topic, the topics of the records are unknown
+    fn lineage_datasets(&self) -> Vec<LineageDataset> {
+        let topics: Vec<String> = self.topic.iter().cloned().collect();
+        crate::lineage_datasets(&self.client_config, topics.as_slice())
+    }
+
+    /// fetch the metadata of the topic
+    fn probe(&self) -> core::Result<()> {
+        let producer: BaseProducer = self
+            .client_config
+            .create()
+            .map_err(|e| anyhow!("Producer creation failed. {}", e))?;
+        let metadata = producer
+            .client()
+            .fetch_metadata(self.topic.as_deref(), PROBE_TIMEOUT)
+            .map_err(|e| anyhow!("Failed to fetch metadata. {}", e))?;
+        if let Some(topic) = &self.topic {
+            let metadata_topic = metadata
+                .topics()
+                .get(0)
+                .ok_or(anyhow!("Topic({}) not found", topic))?;
+            if let Some(e) = metadata_topic.error() {
+                return Err(anyhow!("Topic({}) error. {:?}", topic, e).into());
+            }
+        }
         Ok(())
     }
 }
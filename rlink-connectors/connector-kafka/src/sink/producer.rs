@@ -1,14 +1,19 @@
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
 use std::time::Duration;
 
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
 use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::ClientConfig;
 use rlink::channel::utils::handover::Handover;
 use rlink::channel::TryRecvError;
+use rlink::core::cancellation::CancellationToken;
 use rlink::utils::thread::async_sleep;
 
 use crate::buffer_gen::kafka_message;
+use crate::sink::delivery::{DeliveryFailure, DeliveryMetrics, FailureHandler};
+
+/// the max retries of sending a record when the producer queue is full
+const QUEUE_FULL_MAX_RETRIES: usize = 100;
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(10);
 
 #[derive(Clone)]
 pub struct KafkaProducerThread {
@@ -16,20 +21,41 @@ pub struct KafkaProducerThread {
     producer: FutureProducer,
     handover: Handover,
 
-    drain_counter: Arc<AtomicU64>,
-    discard_counter: Arc<AtomicU64>,
+    metrics: DeliveryMetrics,
+    failure_handler: Option<FailureHandler>,
+    cancellation_token: CancellationToken,
 }
 
 impl KafkaProducerThread {
-    pub fn new(topic: Option<String>, client_config: ClientConfig, handover: Handover) -> Self {
+    pub(crate) fn new(
+        topic: Option<String>,
+        client_config: ClientConfig,
+        handover: Handover,
+        metrics: DeliveryMetrics,
+        failure_handler: Option<FailureHandler>,
+    ) -> Self {
         let producer: FutureProducer = client_config.create().expect("Consumer creation failed");
 
         KafkaProducerThread {
             topic,
             producer,
             handover,
-            drain_counter: Arc::new(AtomicU64::new(0)),
-            discard_counter: Arc::new(AtomicU64::new(0)),
+            metrics,
+            failure_handler,
+            cancellation_token: CancellationToken::new(),
+        }
+    }
+
+    /// exit the write loop after the handover is drained once the `token` is cancelled
+    pub(crate) fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = token;
+        self
+    }
+
+    fn on_failure(&self, failure: DeliveryFailure) {
+        self.metrics.failed.fetch_add(1);
+        if let Some(failure_handler) = &self.failure_handler {
+            failure_handler.0.on_failure(failure);
         }
     }
 
@@ -42,10 +68,11 @@ impl KafkaProducerThread {
 
         loop {
             let mut future_queue = Vec::with_capacity(batch);
-            let mut discard_counter = 0;
+            let mut record_size = 0;
             for _n in 0..batch {
                 match self.handover.try_poll_next() {
                     Ok(mut record) => {
+                        record_size += 1;
                         let kafka_message::Entity {
                             timestamp,
                             key,
@@ -62,16 +89,41 @@ impl KafkaProducerThread {
                             panic!("topic not found in `KafkaRecord`");
                         }
 
-                        let future_record = FutureRecord::to(topic)
+                        let mut future_record = FutureRecord::to(topic)
                             .payload(payload)
                             .timestamp(timestamp as i64)
                             .key(key);
 
-                        match self.producer.send_result(future_record) {
-                            Ok(delivery_future) => future_queue.push(delivery_future),
-                            Err((e, _future_record)) => {
-                                error!("send error. {}", e);
-                                discard_counter += 1;
+                        let mut retries = 0;
+                        loop {
+                            match self.producer.send_result(future_record) {
+                                Ok(delivery_future) => {
+                                    future_queue.push(delivery_future);
+                                    break;
+                                }
+                                Err((
+                                    KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull),
+                                    record,
+                                )) if retries < QUEUE_FULL_MAX_RETRIES => {
+                                    retries += 1;
+                                    self.metrics.retried.fetch_add(1);
+                                    async_sleep(QUEUE_FULL_BACKOFF).await;
+                                    future_record = record;
+                                }
+                                Err((e, record)) => {
+                                    error!("send error. {}", e);
+                                    self.on_failure(DeliveryFailure {
+                                        topic: record.topic.to_string(),
+                                        key: record.key.map(|x| x.to_vec()).unwrap_or_default(),
+                                        payload: record
+                                            .payload
+                                            .map(|x| x.to_vec())
+                                            .unwrap_or_default(),
+                                        timestamp: record.timestamp.unwrap_or_default(),
+                                        error: e.to_string(),
+                                    });
+                                    break;
+                                }
                             }
                         }
                     }
@@ -84,6 +136,16 @@ impl KafkaProducerThread {
                 }
             }
 
+            if record_size == 0 && self.cancellation_token.is_cancelled() {
+                // the handover is drained, wait for the in-flight records
+                self.producer.flush(Duration::from_secs(3));
+                break;
+            }
+
+            self.metrics
+                .queue_depth
+                .store(self.producer.in_flight_count() as i64);
+
             if future_queue.len() == 0 {
                 idle_counter += 1;
                 if idle_counter < 30 {
@@ -95,30 +157,26 @@ impl KafkaProducerThread {
                 idle_counter = 0;
                 self.producer.flush(Duration::from_secs(3));
 
-                let mut drain_counter = 0;
                 for future in future_queue {
                     match future.await {
                         Ok(result) => match result {
-                            Ok((_, _)) => drain_counter += 1,
-                            Err((err, _msg)) => {
+                            Ok((_, _)) => {
+                                self.metrics.acked.fetch_add(1);
+                            }
+                            Err((err, msg)) => {
                                 error!("produce error: {:?}", err);
-                                discard_counter += 1;
+                                self.on_failure(DeliveryFailure::from_message(
+                                    &msg,
+                                    err.to_string(),
+                                ));
                             }
                         },
                         Err(e) => {
                             error!("produce `Canceled` error. {}", e);
-                            discard_counter += 1;
+                            self.metrics.failed.fetch_add(1);
                         }
                     }
                 }
-
-                self.drain_counter
-                    .fetch_add(drain_counter as u64, Ordering::Relaxed);
-            }
-
-            if discard_counter > 0 {
-                self.discard_counter
-                    .fetch_add(discard_counter as u64, Ordering::Relaxed);
             }
         }
     }
@@ -126,13 +184,12 @@ impl KafkaProducerThread {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::atomic::Ordering;
-
     use rdkafka::ClientConfig;
     use rlink::channel::utils::handover::Handover;
     use rlink::core::element::Record;
     use rlink::utils::date_time::current_timestamp_millis;
 
+    use crate::sink::delivery::DeliveryMetrics;
     use crate::sink::producer::KafkaProducerThread;
     use crate::{build_kafka_record, BOOTSTRAP_SERVERS};
 
@@ -166,12 +223,17 @@ mod tests {
             println!("finish");
         });
 
-        let mut kafka_producer =
-            KafkaProducerThread::new(Some(topic.to_string()), client_config, handover);
+        let mut kafka_producer = KafkaProducerThread::new(
+            Some(topic.to_string()),
+            client_config,
+            handover,
+            DeliveryMetrics::default(),
+            None,
+        );
 
         let kafka_producer_clone = kafka_producer.clone();
         std::thread::spawn(move || loop {
-            if kafka_producer_clone.drain_counter.load(Ordering::Relaxed) == 1000000 {
+            if kafka_producer_clone.metrics.acked.load() == 1000000 {
                 println!(
                     "end... {}",
                     rlink::utils::date_time::current_timestamp_millis()
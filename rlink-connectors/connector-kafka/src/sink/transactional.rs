@@ -0,0 +1,453 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rdkafka::error::KafkaResult;
+use rdkafka::producer::{BaseProducer, BaseRecord, Producer};
+use rdkafka::ClientConfig;
+use rlink::core;
+use rlink::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
+use rlink::core::element::Record;
+use rlink::core::function::{Context, OutputFormat};
+use rlink::core::lineage::LineageDataset;
+use rlink::core::runtime::CheckpointId;
+
+use crate::buffer_gen::kafka_message;
+
+/// the config of the transactional producer
+pub const TRANSACTIONAL_ID: &str = "transactional.id";
+/// the broker aborts the transactions open longer than it, must be greater than the
+/// checkpoint interval, and not greater than the `transaction.max.timeout.ms` of the brokers
+pub const TRANSACTION_TIMEOUT_MS: &str = "transaction.timeout.ms";
+
+/// the producers of a task, a transaction is pending on a producer from the checkpoint
+/// barrier until the completion notification, so the pool bounds the pending checkpoints
+pub const TRANSACTIONAL_POOL_SIZE: usize = 5;
+
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(30);
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// the transactional operations of a producer
+pub(crate) trait TransactionalProducer: Send + Sync {
+    /// send the record to the open transaction, an error means the queue is full
+    fn send(&self, topic: &str, key: &[u8], payload: &[u8], timestamp: i64) -> KafkaResult<()>;
+    fn poll(&self, timeout: Duration);
+    fn begin_transaction(&self) -> KafkaResult<()>;
+    /// pre-commit the open transaction, wait for the delivery of the records
+    fn flush(&self, timeout: Duration);
+    fn commit_transaction(&self, timeout: Duration) -> KafkaResult<()>;
+}
+
+impl TransactionalProducer for BaseProducer {
+    fn send(&self, topic: &str, key: &[u8], payload: &[u8], timestamp: i64) -> KafkaResult<()> {
+        let record = BaseRecord::to(topic)
+            .payload(payload)
+            .key(key)
+            .timestamp(timestamp);
+        BaseProducer::send(self, record).map_err(|(e, _record)| e)
+    }
+
+    fn poll(&self, timeout: Duration) {
+        BaseProducer::poll(self, timeout);
+    }
+
+    fn begin_transaction(&self) -> KafkaResult<()> {
+        Producer::begin_transaction(self)
+    }
+
+    fn flush(&self, timeout: Duration) {
+        Producer::flush(self, timeout)
+    }
+
+    fn commit_transaction(&self, timeout: Duration) -> KafkaResult<()> {
+        Producer::commit_transaction(self, timeout)
+    }
+}
+
+/// The checkpoint ids of the pre-committed transactions, only recorded to log the in-doubt
+/// transactions on restoring, a transaction can't be resumed by another producer instance
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+struct TransactionalSinkState {
+    pending_checkpoints: Vec<u64>,
+}
+
+/// the producers of the task
+#[derive(Debug, Default)]
+struct TransactionPool {
+    /// the producers of the pre-committed transactions by the checkpoint
+    pending: BTreeMap<u64, usize>,
+    /// the producers without transaction
+    idle: Vec<usize>,
+}
+
+impl TransactionPool {
+    /// remove the pending transactions of the checkpoints not after the `checkpoint_id`,
+    /// in the checkpoint order
+    fn take_committable(&mut self, checkpoint_id: CheckpointId) -> Vec<(u64, usize)> {
+        let uncommittable = self.pending.split_off(&(checkpoint_id.0 + 1));
+        let committable = std::mem::replace(&mut self.pending, uncommittable);
+        committable.into_iter().collect()
+    }
+}
+
+/// Write the records to kafka exactly-once with the transactional producers.
+///
+/// A transaction is begun per checkpoint interval. On the checkpoint barrier the transaction
+/// is kept pending and a new transaction is begun on another producer of the pool, the pending
+/// transaction is flushed(pre-committed) by the async snapshot before the checkpoint is
+/// reported, then the pending transactions are committed when the checkpoint is completed,
+/// so the records of an uncompleted checkpoint are never visible. A checkpoint is declined if
+/// all producers are pending. The producers of the task use the stable `transactional.id`s
+/// `{prefix}-{job_id}-{task_number}-{i}`, so the producers of a failed task are fenced
+/// and their in-flight transactions are aborted by `init_transactions` on restoring, the
+/// records of them are replayed from the sources by the restored checkpoint.
+///
+/// The transactions of a checkpoint completed but not yet committed before a failover are
+/// in-doubt, they are aborted on restoring too and logged, the records are lost because the
+/// restored checkpoint doesn't replay them. The consumers must read with
+/// `isolation.level=read_committed`, see `KafkaInputFormatBuilder::read_committed`.
+#[derive(NamedFunction)]
+pub struct KafkaTransactionalOutputFormat {
+    client_config: ClientConfig,
+    topic: Option<String>,
+    transactional_id_prefix: String,
+
+    producers: Vec<Arc<dyn TransactionalProducer>>,
+    /// the producer of the current transaction
+    current: usize,
+    pool: TransactionPool,
+}
+
+impl KafkaTransactionalOutputFormat {
+    pub fn new(
+        client_config: ClientConfig,
+        topic: Option<String>,
+        transactional_id_prefix: &str,
+    ) -> Self {
+        KafkaTransactionalOutputFormat {
+            client_config,
+            topic,
+            transactional_id_prefix: transactional_id_prefix.to_string(),
+            producers: Vec::new(),
+            current: 0,
+            pool: TransactionPool::default(),
+        }
+    }
+
+    fn current_producer(&self) -> &dyn TransactionalProducer {
+        self.producers[self.current].as_ref()
+    }
+
+    /// create the producers, fence the producers of the previous attempt of the task and
+    /// abort their in-flight transactions
+    fn init_producers(&mut self, context: &FunctionSnapshotContext) -> anyhow::Result<()> {
+        self.producers.clear();
+        self.pool = TransactionPool::default();
+
+        for i in 0..TRANSACTIONAL_POOL_SIZE {
+            let transactional_id = format!(
+                "{}-{}-{}-{}",
+                self.transactional_id_prefix,
+                context.task_id.job_id().0,
+                context.task_id.task_number(),
+                i
+            );
+
+            let mut client_config = self.client_config.clone();
+            client_config.set(TRANSACTIONAL_ID, transactional_id.as_str());
+            let producer: BaseProducer = client_config
+                .create()
+                .map_err(|e| anyhow!("Producer creation failed. {}", e))?;
+            producer
+                .init_transactions(TRANSACTION_TIMEOUT)
+                .map_err(|e| anyhow!("init transactions of `{}` error. {}", transactional_id, e))?;
+
+            self.producers.push(Arc::new(producer));
+            self.pool.idle.push(i);
+        }
+
+        self.current = self.pool.idle.pop().unwrap();
+        self.begin_transaction()
+    }
+
+    fn begin_transaction(&self) -> anyhow::Result<()> {
+        self.current_producer()
+            .begin_transaction()
+            .map_err(|e| anyhow!("begin transaction error. {}", e))
+    }
+
+    /// commit the pending transactions of the checkpoints not after the `checkpoint_id` in the
+    /// checkpoint order, the notifications of the earlier checkpoints may be skipped
+    fn commit(&mut self, checkpoint_id: CheckpointId) -> anyhow::Result<()> {
+        for (id, index) in self.pool.take_committable(checkpoint_id) {
+            // the pending records are flushed before the commit
+            self.producers[index]
+                .commit_transaction(TRANSACTION_TIMEOUT)
+                .map_err(|e| anyhow!("commit transaction of checkpoint {} error. {}", id, e))?;
+            self.pool.idle.push(index);
+            debug!("transaction of checkpoint {} committed", id);
+        }
+        Ok(())
+    }
+}
+
+impl OutputFormat for KafkaTransactionalOutputFormat {
+    fn open(&mut self, context: &Context) -> core::Result<()> {
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle);
+        Ok(())
+    }
+
+    fn write_record(&mut self, mut record: Record) {
+        let kafka_message::Entity {
+            timestamp,
+            key,
+            payload,
+            topic,
+            ..
+        } = kafka_message::Entity::parse(record.as_buffer()).unwrap();
+
+        let topic = match self.topic.as_ref() {
+            Some(topic) => topic.as_str(),
+            None => topic,
+        };
+        if topic.is_empty() {
+            panic!("topic not found in `KafkaRecord`");
+        }
+
+        loop {
+            match self.current_producer().send(topic, key, payload, timestamp) {
+                Ok(_) => break,
+                Err(e) => {
+                    // the queue is full, serve the delivery reports and retry
+                    debug!("send error, retry. {}", e);
+                    self.current_producer().poll(Duration::from_millis(100));
+                }
+            }
+        }
+        self.current_producer().poll(Duration::from_millis(0));
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        // the open transactions are aborted by the brokers on the timeout, or by the next
+        // attempt of the task
+        Ok(())
+    }
+
+    fn lineage_datasets(&self) -> Vec<LineageDataset> {
+        let topics: Vec<String> = self.topic.iter().cloned().collect();
+        crate::lineage_datasets(&self.client_config, topics.as_slice())
+    }
+}
+
+impl CheckpointFunction for KafkaTransactionalOutputFormat {
+    /// abort the in-flight transactions of the failed task, include the in-doubt transactions
+    /// of the completed checkpoints not committed before the failover
+    fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        if let Some(handle) = handle {
+            if !handle.handle.is_empty() {
+                let state: TransactionalSinkState =
+                    serde_json::from_str(handle.handle.as_str()).unwrap();
+                if !state.pending_checkpoints.is_empty() {
+                    warn!(
+                        "abort the in-doubt transactions of checkpoints {:?} on restoring from checkpoint {:?}, \
+                        the records are lost if they were not committed before the failover",
+                        state.pending_checkpoints, context.checkpoint_id
+                    );
+                }
+            }
+        }
+
+        self.init_producers(context)
+            .expect("init the transactional producers error");
+    }
+
+    /// begin the transaction of the next checkpoint, and pre-commit the current transaction
+    /// in the background. the checkpoint is declined if all producers are pending, and the
+    /// records are kept in the current transaction for the next checkpoint
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        let checkpoint_id = context.checkpoint_id;
+        let next = match self.pool.idle.pop() {
+            Some(next) => next,
+            None => {
+                warn!(
+                    "all {} transactional producers are pending, decline checkpoint {:?}",
+                    TRANSACTIONAL_POOL_SIZE, checkpoint_id
+                );
+                return Some(Box::new(move || {
+                    Err(anyhow!(
+                        "no idle transactional producer for checkpoint {:?}",
+                        checkpoint_id
+                    ))
+                }));
+            }
+        };
+
+        let producer = self.producers[self.current].clone();
+        self.pool.pending.insert(checkpoint_id.0, self.current);
+        self.current = next;
+        self.begin_transaction()
+            .expect("begin the transaction of the next checkpoint error");
+
+        let state = TransactionalSinkState {
+            pending_checkpoints: self.pool.pending.keys().cloned().collect(),
+        };
+        Some(Box::new(move || {
+            producer.flush(FLUSH_TIMEOUT);
+            let handle = serde_json::to_string(&state)?;
+            Ok(CheckpointHandle { handle })
+        }))
+    }
+
+    /// a failed commit fails the task, the transaction is in-doubt and aborted on restoring
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        self.commit(checkpoint_id)
+            .expect("commit the kafka transaction error");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use rdkafka::error::KafkaResult;
+    use rdkafka::ClientConfig;
+    use rlink::core::checkpoint::{CheckpointFunction, FunctionSnapshotContext};
+    use rlink::core::runtime::{CheckpointId, OperatorId, TaskId};
+
+    use crate::sink::transactional::{
+        KafkaTransactionalOutputFormat, TransactionPool, TransactionalProducer,
+        TransactionalSinkState, TRANSACTIONAL_POOL_SIZE,
+    };
+
+    #[derive(Default)]
+    struct MockProducer {
+        flushed: AtomicUsize,
+        committed: AtomicUsize,
+    }
+
+    impl TransactionalProducer for MockProducer {
+        fn send(&self, _topic: &str, _key: &[u8], _payload: &[u8], _ts: i64) -> KafkaResult<()> {
+            Ok(())
+        }
+
+        fn poll(&self, _timeout: Duration) {}
+
+        fn begin_transaction(&self) -> KafkaResult<()> {
+            Ok(())
+        }
+
+        fn flush(&self, _timeout: Duration) {
+            self.flushed.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn commit_transaction(&self, _timeout: Duration) -> KafkaResult<()> {
+            self.committed.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn output_format() -> (KafkaTransactionalOutputFormat, Vec<Arc<MockProducer>>) {
+        let mut output_format = KafkaTransactionalOutputFormat::new(ClientConfig::new(), None, "t");
+        let producers: Vec<Arc<MockProducer>> = (0..TRANSACTIONAL_POOL_SIZE)
+            .map(|_| Arc::new(MockProducer::default()))
+            .collect();
+        for (i, producer) in producers.iter().enumerate() {
+            output_format.producers.push(producer.clone());
+            output_format.pool.idle.push(i);
+        }
+        output_format.current = output_format.pool.idle.pop().unwrap();
+        (output_format, producers)
+    }
+
+    fn snapshot_context(checkpoint_id: u64) -> FunctionSnapshotContext {
+        FunctionSnapshotContext::new(
+            OperatorId(1),
+            TaskId::default(),
+            CheckpointId(checkpoint_id),
+            None,
+        )
+    }
+
+    fn committed(producers: &[Arc<MockProducer>]) -> usize {
+        producers
+            .iter()
+            .map(|p| p.committed.load(Ordering::SeqCst))
+            .sum()
+    }
+
+    #[test]
+    pub fn take_committable_test() {
+        let mut pool = TransactionPool::default();
+        pool.pending.insert(1, 0);
+        pool.pending.insert(2, 3);
+        pool.pending.insert(4, 1);
+
+        // the transactions of the earlier checkpoints are committed by a later completion
+        assert_eq!(pool.take_committable(CheckpointId(2)), vec![(1, 0), (2, 3)]);
+        assert_eq!(pool.pending.keys().cloned().collect::<Vec<u64>>(), vec![4]);
+
+        assert!(pool.take_committable(CheckpointId(3)).is_empty());
+        assert_eq!(pool.take_committable(CheckpointId(5)), vec![(4, 1)]);
+        assert!(pool.pending.is_empty());
+    }
+
+    #[test]
+    pub fn commit_on_checkpoint_complete_test() {
+        let (mut output_format, producers) = output_format();
+
+        // the transactions are only pre-committed by the snapshots
+        for checkpoint_id in [1, 2] {
+            let snapshot = output_format
+                .snapshot_state_async(&snapshot_context(checkpoint_id))
+                .unwrap();
+            let handle = snapshot().unwrap();
+            let state: TransactionalSinkState =
+                serde_json::from_str(handle.handle.as_str()).unwrap();
+            assert_eq!(*state.pending_checkpoints.last().unwrap(), checkpoint_id);
+        }
+        let flushed: usize = producers
+            .iter()
+            .map(|p| p.flushed.load(Ordering::SeqCst))
+            .sum();
+        assert_eq!(flushed, 2);
+        assert_eq!(committed(producers.as_slice()), 0);
+
+        // the skipped notification of checkpoint 1 is committed by the later one
+        output_format.notify_checkpoint_complete(CheckpointId(2));
+        assert_eq!(committed(producers.as_slice()), 2);
+        assert!(output_format.pool.pending.is_empty());
+        assert_eq!(output_format.pool.idle.len(), TRANSACTIONAL_POOL_SIZE - 1);
+    }
+
+    #[test]
+    pub fn pool_exhausted_test() {
+        let (mut output_format, producers) = output_format();
+        for checkpoint_id in 1..TRANSACTIONAL_POOL_SIZE as u64 {
+            output_format.snapshot_state_async(&snapshot_context(checkpoint_id));
+        }
+        let current = output_format.current;
+
+        // all producers are pending, the checkpoint is declined instead of failing the task
+        let checkpoint_id = TRANSACTIONAL_POOL_SIZE as u64;
+        let snapshot = output_format
+            .snapshot_state_async(&snapshot_context(checkpoint_id))
+            .unwrap();
+        assert!(snapshot().is_err());
+
+        // the current transaction is kept for the next checkpoint
+        assert_eq!(output_format.current, current);
+        assert_eq!(
+            output_format.pool.pending.len(),
+            TRANSACTIONAL_POOL_SIZE - 1
+        );
+        assert_eq!(committed(producers.as_slice()), 0);
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+use crate::source::TopicPartition;
+
+/// the partition with the leader broker's id
+#[derive(Clone, Debug)]
+pub struct PartitionInfo {
+    pub topic_partition: TopicPartition,
+    pub leader: i32,
+}
+
+impl PartitionInfo {
+    pub fn new(topic: &str, partition: i32, leader: i32) -> Self {
+        PartitionInfo {
+            topic_partition: TopicPartition::new(topic, partition),
+            leader,
+        }
+    }
+}
+
+/// The strategy of mapping the partitions to the tasks
+pub trait PartitionAssignor
+where
+    Self: Debug + Send + Sync,
+{
+    /// assign the `partitions` to `num_tasks` tasks, the result is indexed by the task number.
+    /// the `previous` is the assignment restored from the checkpoint indexed by the task number,
+    /// it's empty at the first start.
+    fn assign(
+        &self,
+        partitions: &[PartitionInfo],
+        num_tasks: usize,
+        previous: &[Vec<TopicPartition>],
+    ) -> Vec<Vec<TopicPartition>>;
+}
+
+/// The partition `i` of all topics is owned by the task `i % num_tasks`
+#[derive(Debug, Default)]
+pub struct RoundRobinAssignor {}
+
+impl PartitionAssignor for RoundRobinAssignor {
+    fn assign(
+        &self,
+        partitions: &[PartitionInfo],
+        num_tasks: usize,
+        _previous: &[Vec<TopicPartition>],
+    ) -> Vec<Vec<TopicPartition>> {
+        let mut assignment = vec![Vec::new(); num_tasks];
+        for (index, partition) in partitions.iter().enumerate() {
+            assignment[index % num_tasks].push(partition.topic_partition.clone());
+        }
+        assignment
+    }
+}
+
+/// The partitions of each topic are divided into `num_tasks` contiguous ranges,
+/// the first tasks own one more partition if the partitions are not divisible
+#[derive(Debug, Default)]
+pub struct RangeAssignor {}
+
+impl PartitionAssignor for RangeAssignor {
+    fn assign(
+        &self,
+        partitions: &[PartitionInfo],
+        num_tasks: usize,
+        _previous: &[Vec<TopicPartition>],
+    ) -> Vec<Vec<TopicPartition>> {
+        let mut topics: Vec<&str> = Vec::new();
+        let mut topic_partitions: HashMap<&str, Vec<&TopicPartition>> = HashMap::new();
+        for partition in partitions {
+            let topic = partition.topic_partition.topic.as_str();
+            if !topic_partitions.contains_key(topic) {
+                topics.push(topic);
+            }
+            topic_partitions
+                .entry(topic)
+                .or_default()
+                .push(&partition.topic_partition);
+        }
+
+        let mut assignment = vec![Vec::new(); num_tasks];
+        for topic in topics {
+            let mut partitions = topic_partitions.remove(topic).unwrap();
+            partitions.sort_by_key(|x| x.partition);
+
+            let quota = partitions.len() / num_tasks;
+            let extra = partitions.len() % num_tasks;
+            let mut begin = 0;
+            for (task_number, task_partitions) in assignment.iter_mut().enumerate() {
+                let len = if task_number < extra {
+                    quota + 1
+                } else {
+                    quota
+                };
+                task_partitions.extend(partitions[begin..begin + len].iter().map(|x| (*x).clone()));
+                begin += len;
+            }
+        }
+        assignment
+    }
+}
+
+/// Keep the partitions on the tasks owned them in the previous checkpoint as far as possible,
+/// so the restarts preserve the partition locality and the cache-warm consumers.
+/// Each task owns at most `ceil(partitions / num_tasks)` partitions, the new partitions and
+/// the partitions of the removed tasks are assigned to the least loaded tasks.
+/// The `initial` assignor is used at the first start.
+#[derive(Debug)]
+pub struct StickyAssignor {
+    initial: Box<dyn PartitionAssignor>,
+}
+
+impl StickyAssignor {
+    pub fn new(initial: Box<dyn PartitionAssignor>) -> Self {
+        StickyAssignor { initial }
+    }
+}
+
+impl Default for StickyAssignor {
+    fn default() -> Self {
+        StickyAssignor::new(Box::new(RoundRobinAssignor::default()))
+    }
+}
+
+impl PartitionAssignor for StickyAssignor {
+    fn assign(
+        &self,
+        partitions: &[PartitionInfo],
+        num_tasks: usize,
+        previous: &[Vec<TopicPartition>],
+    ) -> Vec<Vec<TopicPartition>> {
+        if previous.iter().all(|x| x.is_empty()) {
+            return self.initial.assign(partitions, num_tasks, previous);
+        }
+
+        let max_partitions = partitions.len().div_ceil(num_tasks);
+        let mut unassigned: Vec<&TopicPartition> =
+            partitions.iter().map(|x| &x.topic_partition).collect();
+
+        let mut assignment = vec![Vec::new(); num_tasks];
+        for (task_partitions, previous_partitions) in assignment.iter_mut().zip(previous) {
+            for topic_partition in previous_partitions {
+                if task_partitions.len() == max_partitions {
+                    break;
+                }
+                if let Some(pos) = unassigned.iter().position(|x| *x == topic_partition) {
+                    task_partitions.push(unassigned.remove(pos).clone());
+                }
+            }
+        }
+
+        for topic_partition in unassigned {
+            let task_partitions = assignment.iter_mut().min_by_key(|x| x.len()).unwrap();
+            task_partitions.push(topic_partition.clone());
+        }
+        assignment
+    }
+}
+
+/// Assign the partitions to the tasks in the same rack as the partition leader, balanced in
+/// each rack. The partitions without a task in the leader's rack are assigned to the least
+/// loaded tasks. The racks of the brokers are configured because the metadata api doesn't
+/// contain them, the racks of the tasks are known by the deployment, eg: the node affinity.
+#[derive(Debug)]
+pub struct RackAwareAssignor {
+    /// broker id -> rack
+    broker_racks: HashMap<i32, String>,
+    /// the rack of each task, indexed by the task number
+    task_racks: Vec<String>,
+}
+
+impl RackAwareAssignor {
+    pub fn new(broker_racks: HashMap<i32, String>, task_racks: Vec<String>) -> Self {
+        RackAwareAssignor {
+            broker_racks,
+            task_racks,
+        }
+    }
+}
+
+impl PartitionAssignor for RackAwareAssignor {
+    fn assign(
+        &self,
+        partitions: &[PartitionInfo],
+        num_tasks: usize,
+        _previous: &[Vec<TopicPartition>],
+    ) -> Vec<Vec<TopicPartition>> {
+        let max_partitions = partitions.len().div_ceil(num_tasks);
+        let task_racks: Vec<Option<&String>> = (0..num_tasks)
+            .map(|task_number| self.task_racks.get(task_number))
+            .collect();
+        let racks: HashSet<&String> = task_racks.iter().filter_map(|x| *x).collect();
+
+        let mut assignment: Vec<Vec<TopicPartition>> = vec![Vec::new(); num_tasks];
+        let mut unassigned = Vec::new();
+        for partition in partitions {
+            let rack = self
+                .broker_racks
+                .get(&partition.leader)
+                .filter(|rack| racks.contains(rack));
+            let task_number = rack.and_then(|rack| {
+                (0..num_tasks)
+                    .filter(|x| task_racks[*x] == Some(rack))
+                    .filter(|x| assignment[*x].len() < max_partitions)
+                    .min_by_key(|x| assignment[*x].len())
+            });
+            match task_number {
+                Some(task_number) => {
+                    assignment[task_number].push(partition.topic_partition.clone())
+                }
+                None => unassigned.push(partition.topic_partition.clone()),
+            }
+        }
+
+        for topic_partition in unassigned {
+            let task_partitions = assignment.iter_mut().min_by_key(|x| x.len()).unwrap();
+            task_partitions.push(topic_partition);
+        }
+        assignment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::assignment::{
+        PartitionAssignor, PartitionInfo, RangeAssignor, RoundRobinAssignor, StickyAssignor,
+    };
+    use crate::source::TopicPartition;
+
+    fn partitions(num: i32) -> Vec<PartitionInfo> {
+        (0..num).map(|x| PartitionInfo::new("t", x, 0)).collect()
+    }
+
+    fn task_partitions(assignment: &[Vec<TopicPartition>]) -> Vec<Vec<i32>> {
+        assignment
+            .iter()
+            .map(|x| x.iter().map(|x| x.partition).collect())
+            .collect()
+    }
+
+    #[test]
+    pub fn assignor_test() {
+        let assignment = RoundRobinAssignor::default().assign(&partitions(5), 2, &[]);
+        assert_eq!(
+            task_partitions(&assignment),
+            vec![vec![0, 2, 4], vec![1, 3]]
+        );
+
+        let assignment = RangeAssignor::default().assign(&partitions(5), 2, &[]);
+        assert_eq!(
+            task_partitions(&assignment),
+            vec![vec![0, 1, 2], vec![3, 4]]
+        );
+
+        // the new partition 5 is assigned to the least loaded task, the others are kept
+        let assignment = StickyAssignor::default().assign(&partitions(6), 2, &assignment);
+        assert_eq!(
+            task_partitions(&assignment),
+            vec![vec![0, 1, 2], vec![3, 4, 5]]
+        );
+    }
+}
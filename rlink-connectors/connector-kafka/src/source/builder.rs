@@ -1,19 +1,26 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::time::Duration;
 
 use rdkafka::ClientConfig;
 use rlink::core::element::FnSchema;
 use rlink::core::properties::{Properties, PARALLELISM};
+use rlink::core::rate_budget::RateBudget;
 
 use crate::buffer_gen::kafka_message;
+use crate::source::assignment::{
+    PartitionAssignor, RackAwareAssignor, RangeAssignor, RoundRobinAssignor, StickyAssignor,
+};
 use crate::source::deserializer::{
-    DefaultKafkaRecordDeserializer, DefaultKafkaRecordDeserializerBuilder,
-    KafkaRecordDeserializerBuilder,
+    DefaultKafkaRecordDeserializer, DefaultKafkaRecordDeserializerBuilder, KafkaMetadataColumn,
+    KafkaRecordDeserializerBuilder, MetadataKafkaRecordDeserializerBuilder,
 };
 use crate::source::offset_range::OffsetRange;
 use crate::{
-    KafkaInputFormat, BOOTSTRAP_SERVERS, BUFFER_SIZE, GROUP_ID, KAFKA, OFFSET, SOURCE_CHANNEL_SIZE,
-    TOPICS,
+    KafkaInputFormat, ASSIGNMENT_BROKER_RACKS, ASSIGNMENT_STRATEGY, ASSIGNMENT_TASK_RACKS,
+    BOOTSTRAP_SERVERS, BUFFER_SIZE, GROUP_ID, ISOLATION_LEVEL, KAFKA, METADATA_COLUMNS, OFFSET,
+    PARTITION_DISCOVERY_INTERVAL, RATE_BUDGET, RATE_BUDGET_NAME, READ_COMMITTED,
+    SOURCE_CHANNEL_SIZE, TOPICS,
 };
 
 #[derive(Debug)]
@@ -24,6 +31,10 @@ pub struct KafkaInputFormatBuilder {
     topics: Vec<String>,
     buffer_size: Option<usize>,
     offset_range: OffsetRange,
+    metadata_columns: Vec<KafkaMetadataColumn>,
+    rate_budget: Option<RateBudget>,
+    assignor: Option<Box<dyn PartitionAssignor>>,
+    partition_discovery_interval: Option<Duration>,
 }
 
 impl KafkaInputFormatBuilder {
@@ -35,6 +46,10 @@ impl KafkaInputFormatBuilder {
             topics,
             buffer_size: None,
             offset_range: OffsetRange::None,
+            metadata_columns: vec![],
+            rate_budget: None,
+            assignor: None,
+            partition_discovery_interval: None,
         }
     }
 
@@ -53,6 +68,41 @@ impl KafkaInputFormatBuilder {
         self
     }
 
+    /// append the metadata of the message to the records as the virtual columns
+    pub fn metadata_columns(mut self, metadata_columns: Vec<KafkaMetadataColumn>) -> Self {
+        self.metadata_columns = metadata_columns;
+        self
+    }
+
+    /// limit the consumption of all tasks by a cluster-wide rate budget coordinated by the
+    /// coordinator, eg: to stay within the broker quotas
+    pub fn rate_budget(mut self, rate_budget: RateBudget) -> Self {
+        self.rate_budget = Some(rate_budget);
+        self
+    }
+
+    /// consume only the committed records of the transactional producers, the aborted records
+    /// and the control batches are skipped, and the end offsets of the bounded ranges are
+    /// limited by the last stable offset
+    pub fn read_committed(mut self) -> Self {
+        self.conf_map
+            .insert(ISOLATION_LEVEL.to_string(), READ_COMMITTED.to_string());
+        self
+    }
+
+    /// the strategy of assigning the partitions to the tasks, default `RoundRobinAssignor`
+    pub fn assignor(mut self, assignor: Box<dyn PartitionAssignor>) -> Self {
+        self.assignor = Some(assignor);
+        self
+    }
+
+    /// refresh the metadata of the topics by the `interval` to consume the partitions added
+    /// later, the unbounded source only
+    pub fn partition_discovery_interval(mut self, interval: Duration) -> Self {
+        self.partition_discovery_interval = Some(interval);
+        self
+    }
+
     pub fn build(
         self,
         deserializer_builder: Option<Box<dyn KafkaRecordDeserializerBuilder>>,
@@ -66,6 +116,9 @@ impl KafkaInputFormatBuilder {
 
         let fn_name = self.fn_name.unwrap_or("KafkaInputFormat".to_string());
         let buffer_size = self.buffer_size.unwrap_or(SOURCE_CHANNEL_SIZE);
+        let assignor = self
+            .assignor
+            .unwrap_or_else(|| Box::new(RoundRobinAssignor::default()));
 
         let deserializer_builder = deserializer_builder.unwrap_or_else(|| {
             let deserializer_builder: Box<dyn KafkaRecordDeserializerBuilder> =
@@ -77,8 +130,17 @@ impl KafkaInputFormatBuilder {
 
             deserializer_builder
         });
-
-        KafkaInputFormat::new(
+        let deserializer_builder: Box<dyn KafkaRecordDeserializerBuilder> =
+            if self.metadata_columns.is_empty() {
+                deserializer_builder
+            } else {
+                Box::new(MetadataKafkaRecordDeserializerBuilder::new(
+                    deserializer_builder,
+                    self.metadata_columns,
+                ))
+            };
+
+        let mut input_format = KafkaInputFormat::new(
             client_config,
             self.topics,
             buffer_size,
@@ -86,7 +148,11 @@ impl KafkaInputFormatBuilder {
             deserializer_builder,
             self.parallelism,
             fn_name,
-        )
+            self.rate_budget,
+            assignor,
+        );
+        input_format.set_partition_discovery_interval(self.partition_discovery_interval);
+        input_format
     }
 }
 
@@ -120,9 +186,57 @@ impl TryFrom<Properties> for KafkaInputFormatBuilder {
             builder = builder.buffer_size(buffer_size);
         }
 
+        if let Ok(records_per_second) = properties.get_string(RATE_BUDGET) {
+            let records_per_second = records_per_second
+                .parse::<f64>()
+                .map_err(|e| anyhow!("`{}` is not a number. {}", RATE_BUDGET, e))?;
+            let name = properties
+                .get_string(RATE_BUDGET_NAME)
+                .unwrap_or_else(|_| properties.name().to_string());
+            builder = builder.rate_budget(RateBudget::new(name.as_str(), records_per_second));
+        }
+
+        if let Ok(interval) = properties.get_u64(PARTITION_DISCOVERY_INTERVAL) {
+            builder = builder.partition_discovery_interval(Duration::from_millis(interval));
+        }
+
         let offset_properties = properties.to_sub_properties(OFFSET);
         let offset_range = OffsetRange::try_from(offset_properties)?;
-        let builder = builder.offset_range(offset_range);
+        let mut builder = builder.offset_range(offset_range);
+
+        if let Ok(metadata_columns) = properties.get_string(METADATA_COLUMNS) {
+            let metadata_columns = metadata_columns
+                .split(",")
+                .map(KafkaMetadataColumn::try_from)
+                .collect::<Result<Vec<KafkaMetadataColumn>, anyhow::Error>>()?;
+            builder = builder.metadata_columns(metadata_columns);
+        }
+
+        if let Ok(strategy) = properties.get_string(ASSIGNMENT_STRATEGY) {
+            let assignor: Box<dyn PartitionAssignor> = match strategy.as_str() {
+                "round-robin" => Box::new(RoundRobinAssignor::default()),
+                "range" => Box::new(RangeAssignor::default()),
+                "sticky" => Box::new(StickyAssignor::default()),
+                "rack-aware" => {
+                    let mut broker_racks = HashMap::new();
+                    let value = properties.get_string(ASSIGNMENT_BROKER_RACKS)?;
+                    for broker_rack in value.split(",") {
+                        let (broker_id, rack) = broker_rack
+                            .split_once(':')
+                            .ok_or(anyhow!("illegal broker rack `{}`", broker_rack))?;
+                        broker_racks.insert(broker_id.trim().parse::<i32>()?, rack.to_string());
+                    }
+                    let task_racks = properties
+                        .get_string(ASSIGNMENT_TASK_RACKS)?
+                        .split(",")
+                        .map(|x| x.trim().to_string())
+                        .collect();
+                    Box::new(RackAwareAssignor::new(broker_racks, task_racks))
+                }
+                _ => return Err(anyhow!("unknown assignment strategy `{}`", strategy)),
+            };
+            builder = builder.assignor(assignor);
+        }
 
         Ok(builder)
     }
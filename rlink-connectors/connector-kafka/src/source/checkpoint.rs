@@ -1,26 +1,22 @@
 use std::sync::atomic::{AtomicI64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
-use rlink::core::runtime::TaskId;
+use rlink::core::function::SourcePosition;
+
+use crate::source::TopicPartition;
 
 #[derive(Debug, Clone)]
 pub struct KafkaCheckpointFunction {
     pub(crate) state_recorder: Option<KafkaSourceStateRecorder>,
-    pub(crate) application_id: String,
-    pub(crate) task_id: TaskId,
-    topic: String,
-    partition: i32,
+    topic_partitions: Vec<TopicPartition>,
 }
 
 impl KafkaCheckpointFunction {
-    pub fn new(application_id: String, task_id: TaskId, topic: &str, partition: i32) -> Self {
+    pub fn new(topic_partitions: Vec<TopicPartition>) -> Self {
         KafkaCheckpointFunction {
             state_recorder: None,
-            application_id,
-            task_id,
-            topic: topic.to_string(),
-            partition,
+            topic_partitions,
         }
     }
 
@@ -35,10 +31,7 @@ impl CheckpointFunction for KafkaCheckpointFunction {
         context: &FunctionSnapshotContext,
         handle: &Option<CheckpointHandle>,
     ) {
-        self.state_recorder = Some(KafkaSourceStateRecorder::new(
-            self.topic.as_str(),
-            self.partition,
-        ));
+        self.state_recorder = Some(KafkaSourceStateRecorder::new(self.topic_partitions.clone()));
         info!("Checkpoint initialize, context: {:?}", context);
 
         if context.checkpoint_id.is_default() || handle.is_none() {
@@ -67,69 +60,176 @@ impl CheckpointFunction for KafkaCheckpointFunction {
 }
 
 #[derive(Serialize, Deserialize)]
-struct OffsetSnapshot<'a> {
-    topic: &'a str,
+struct OffsetSnapshot {
+    topic: String,
     partition: i32,
     offset: Option<i64>,
 }
 
+/// the snapshot of all partitions of the task
+#[derive(Serialize, Deserialize)]
+struct OffsetsSnapshot {
+    offsets: Vec<OffsetSnapshot>,
+}
+
+/// the snapshot of a single partition created by the early versions is compatible.
+fn parse_snapshot(snapshot_handle: &str) -> anyhow::Result<Vec<OffsetSnapshot>> {
+    match serde_json::from_str::<OffsetsSnapshot>(snapshot_handle) {
+        Ok(snapshot) => Ok(snapshot.offsets),
+        Err(_e) => Ok(vec![serde_json::from_str::<OffsetSnapshot>(
+            snapshot_handle,
+        )?]),
+    }
+}
+
+/// the partitions of the task's snapshot, see `KafkaSourceStateRecorder::snapshot`
+pub(crate) fn snapshot_partitions(snapshot_handle: &str) -> anyhow::Result<Vec<TopicPartition>> {
+    Ok(parse_snapshot(snapshot_handle)?
+        .into_iter()
+        .map(|x| TopicPartition {
+            topic: x.topic,
+            partition: x.partition,
+        })
+        .collect())
+}
+
+/// Record the offsets of the task's partitions independently,
+/// the offsets are indexed by the position of the partition in `topic_partitions`.
+/// The partitions discovered at runtime are appended by `add`.
 #[derive(Debug, Clone)]
 pub struct KafkaSourceStateRecorder {
-    topic: String,
-    partition: i32,
-    offset: Arc<AtomicI64>,
+    partitions: Arc<RwLock<Vec<(TopicPartition, AtomicI64)>>>,
 }
 
 impl KafkaSourceStateRecorder {
-    pub fn new(topic: &str, partition: i32) -> Self {
+    pub fn new(topic_partitions: Vec<TopicPartition>) -> Self {
+        let partitions = topic_partitions
+            .into_iter()
+            .map(|topic_partition| (topic_partition, AtomicI64::new(i64::MIN)))
+            .collect();
         KafkaSourceStateRecorder {
-            topic: topic.to_string(),
-            partition,
-            offset: Arc::new(AtomicI64::new(i64::MIN)),
+            partitions: Arc::new(RwLock::new(partitions)),
         }
     }
 
-    pub fn update(&self, offset: i64) {
-        self.offset.store(offset, Ordering::Relaxed);
+    /// append a partition discovered at runtime, return its index
+    pub fn add(&self, topic_partition: TopicPartition) -> usize {
+        let mut partitions = self.partitions.write().unwrap();
+        match partitions.iter().position(|(x, _)| x.eq(&topic_partition)) {
+            Some(index) => index,
+            None => {
+                partitions.push((topic_partition, AtomicI64::new(i64::MIN)));
+                partitions.len() - 1
+            }
+        }
     }
 
-    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
-        let offset_snapshot: OffsetSnapshot = serde_json::from_str(snapshot_handle)?;
+    pub fn update(&self, partition_index: usize, offset: i64) {
+        self.partitions.read().unwrap()[partition_index]
+            .1
+            .store(offset, Ordering::Relaxed);
+    }
 
-        if !offset_snapshot.topic.eq(self.topic.as_str())
-            || offset_snapshot.partition != self.partition
-        {
-            return Err(anyhow!("Does not belong to the checkpoint of the task"));
+    /// restore the offsets of the task's partitions, the partitions of the snapshot not
+    /// belonging to the task are ignored, eg: the partitions are reassigned.
+    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
+        for offset_snapshot in parse_snapshot(snapshot_handle)? {
+            let index = self.partitions.read().unwrap().iter().position(|(x, _)| {
+                x.partition == offset_snapshot.partition && x.topic.eq(&offset_snapshot.topic)
+            });
+            match index {
+                Some(index) => self.update(index, offset_snapshot.offset.unwrap_or(i64::MIN)),
+                None => warn!(
+                    "the partition {}:{} of the checkpoint does not belong to the task",
+                    offset_snapshot.topic, offset_snapshot.partition
+                ),
+            }
         }
-
-        self.update(offset_snapshot.offset.unwrap_or(i64::MIN));
         Ok(())
     }
 
     pub fn snapshot(&self) -> String {
-        let offset = {
-            let offset = self.offset.load(Ordering::Relaxed);
-            if offset == i64::MIN {
-                None
-            } else {
-                Some(offset)
+        let offsets = self
+            .partitions
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(topic_partition, offset)| OffsetSnapshot {
+                topic: topic_partition.topic.clone(),
+                partition: topic_partition.partition,
+                offset: to_offset(offset),
+            })
+            .collect();
+
+        serde_json::to_string(&OffsetsSnapshot { offsets }).unwrap()
+    }
+
+    /// the offsets of the partitions keyed by `topic:partition`, the partitions not consumed
+    /// yet are absent
+    pub fn position(&self) -> SourcePosition {
+        let mut position = SourcePosition::new();
+        for (topic_partition, offset) in self.partitions.read().unwrap().iter() {
+            if let Some(offset) = to_offset(offset) {
+                position.put(
+                    format!("{}:{}", topic_partition.topic, topic_partition.partition),
+                    offset,
+                );
             }
-        };
+        }
+        position
+    }
 
-        serde_json::to_string(&OffsetSnapshot {
-            topic: self.topic.as_str(),
-            partition: self.partition,
-            offset,
-        })
-        .unwrap()
+    pub fn get(&self, partition_index: usize) -> Option<i64> {
+        to_offset(&self.partitions.read().unwrap()[partition_index].1)
     }
+}
 
-    pub fn get(&self) -> Option<i64> {
-        let offset = self.offset.load(Ordering::Relaxed);
-        if offset == i64::MIN {
-            None
-        } else {
-            Some(offset)
-        }
+fn to_offset(offset: &AtomicI64) -> Option<i64> {
+    let offset = offset.load(Ordering::Relaxed);
+    if offset == i64::MIN {
+        None
+    } else {
+        Some(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::checkpoint::KafkaSourceStateRecorder;
+    use crate::source::TopicPartition;
+
+    #[test]
+    pub fn state_recorder_test() {
+        let recorder = KafkaSourceStateRecorder::new(vec![
+            TopicPartition::new("topic", 0),
+            TopicPartition::new("topic", 2),
+        ]);
+        recorder.update(1, 100);
+        let snapshot = recorder.snapshot();
+
+        let restored = KafkaSourceStateRecorder::new(vec![
+            TopicPartition::new("topic", 2),
+            TopicPartition::new("topic", 4),
+        ]);
+        restored.update_from_snapshot(snapshot.as_str()).unwrap();
+        assert_eq!(restored.get(0), Some(100));
+        assert_eq!(restored.get(1), None);
+        assert_eq!(
+            restored.position().positions.get("topic:2"),
+            Some(&"100".to_string())
+        );
+        assert!(restored.position().positions.get("topic:4").is_none());
+
+        // the partition discovered at runtime
+        assert_eq!(restored.add(TopicPartition::new("topic", 5)), 2);
+        assert_eq!(restored.add(TopicPartition::new("topic", 5)), 2);
+        restored.update(2, 3);
+        assert!(restored.snapshot().contains(r#""partition":5,"offset":3"#));
+
+        // the single partition snapshot of the early versions
+        restored
+            .update_from_snapshot(r#"{"topic":"topic","partition":4,"offset":7}"#)
+            .unwrap();
+        assert_eq!(restored.get(1), Some(7));
     }
 }
@@ -1,13 +1,22 @@
+use std::sync::mpsc::Receiver;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
 use futures::StreamExt;
-use rdkafka::consumer::{Consumer, DefaultConsumerContext, StreamConsumer};
+use rdkafka::consumer::{CommitMode, Consumer, DefaultConsumerContext, StreamConsumer};
+use rdkafka::error::{KafkaError, KafkaResult};
 use rdkafka::{ClientConfig, Message, Offset, TopicPartitionList};
 use rlink::channel::utils::handover::Handover;
+use rlink::core::cancellation::CancellationToken;
+use rlink::core::rate_budget::RateBudgetLimiter;
 use rlink::core::runtime::JobId;
 use rlink::utils;
-use rlink::utils::thread::async_runtime;
+use rlink::utils::thread::async_sleep;
+use tokio::runtime::Handle;
 
+use crate::source::checkpoint::KafkaSourceStateRecorder;
 use crate::source::deserializer::KafkaRecordDeserializer;
-use crate::source::{empty_record, ConsumerRecord};
+use crate::source::{empty_record, ConsumerRecord, TopicPartition};
 
 #[derive(Debug, Clone)]
 pub(crate) struct ConsumerRange {
@@ -17,16 +26,52 @@ pub(crate) struct ConsumerRange {
     pub(crate) end_offset: Option<i64>,
 }
 
+/// the interval the consumer loop checks the cancellation without any message
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The handle of the consumer thread to stop it on the task closing
+pub(crate) struct KafkaConsumerHandle {
+    shutdown: CancellationToken,
+    join_handle: JoinHandle<()>,
+}
+
+impl KafkaConsumerHandle {
+    /// signal the consumer loop to stop and wait the thread exit in the `timeout`,
+    /// return `false` if the thread is still running after the `timeout`
+    pub fn shutdown(self, timeout: Duration) -> bool {
+        self.shutdown.cancel("kafka source closed");
+
+        let deadline = Instant::now() + timeout;
+        while !self.join_handle.is_finished() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        if self.join_handle.join().is_err() {
+            error!("kafka consumer thread panicked");
+        }
+        true
+    }
+}
+
 pub(crate) fn create_kafka_consumer(
     job_id: JobId,
     task_number: u16,
     client_config: ClientConfig,
-    consumer_ranges: ConsumerRange,
+    consumer_ranges: Vec<ConsumerRange>,
     handover: Handover<ConsumerRecord>,
     deserializer: Box<dyn KafkaRecordDeserializer>,
-) {
-    utils::thread::spawn("kafka-source-block", move || {
-        async_runtime("kafka_source").block_on(async {
+    rate_limiter: Option<RateBudgetLimiter>,
+    state_recorder: KafkaSourceStateRecorder,
+    discovered: Option<Receiver<Vec<TopicPartition>>>,
+    shutdown: CancellationToken,
+    runtime: Handle,
+) -> KafkaConsumerHandle {
+    let shutdown_signal = shutdown.clone();
+    let join_handle = utils::thread::spawn("kafka-source-block", move || {
+        runtime.block_on(async {
             let mut kafka_consumer = KafkaConsumerThread::new(
                 job_id,
                 task_number,
@@ -34,6 +79,10 @@ pub(crate) fn create_kafka_consumer(
                 consumer_ranges,
                 handover,
                 deserializer,
+                rate_limiter,
+                state_recorder,
+                discovered,
+                shutdown_signal,
             );
             match kafka_consumer.run().await {
                 Ok(()) => {}
@@ -43,6 +92,11 @@ pub(crate) fn create_kafka_consumer(
             }
         });
     });
+
+    KafkaConsumerHandle {
+        shutdown,
+        join_handle,
+    }
 }
 
 pub(crate) struct KafkaConsumerThread {
@@ -50,11 +104,20 @@ pub(crate) struct KafkaConsumerThread {
     task_number: u16,
 
     client_config: ClientConfig,
-    consumer_ranges: ConsumerRange,
+    consumer_ranges: Vec<ConsumerRange>,
     with_end_consumer_ranges: bool,
+    /// the partitions reached the end offset
+    end_reached: Vec<bool>,
 
     handover: Handover<ConsumerRecord>,
     deserializer: Box<dyn KafkaRecordDeserializer>,
+    rate_limiter: Option<RateBudgetLimiter>,
+
+    /// the offsets consumed by the task, committed to the group on shutdown
+    state_recorder: KafkaSourceStateRecorder,
+    /// the new partitions owned by the task, see `PartitionDiscovery`
+    discovered: Option<Receiver<Vec<TopicPartition>>>,
+    shutdown: CancellationToken,
 }
 
 impl KafkaConsumerThread {
@@ -62,51 +125,214 @@ impl KafkaConsumerThread {
         job_id: JobId,
         task_number: u16,
         client_config: ClientConfig,
-        consumer_ranges: ConsumerRange,
+        consumer_ranges: Vec<ConsumerRange>,
         handover: Handover<ConsumerRecord>,
         deserializer: Box<dyn KafkaRecordDeserializer>,
+        rate_limiter: Option<RateBudgetLimiter>,
+        state_recorder: KafkaSourceStateRecorder,
+        discovered: Option<Receiver<Vec<TopicPartition>>>,
+        shutdown: CancellationToken,
     ) -> Self {
-        let with_end_consumer_ranges = consumer_ranges.end_offset.is_some();
+        let with_end_consumer_ranges = consumer_ranges.iter().any(|x| x.end_offset.is_some());
+        let end_reached = consumer_ranges.iter().map(|_| false).collect();
         KafkaConsumerThread {
             job_id,
             task_number,
             client_config,
             consumer_ranges,
             with_end_consumer_ranges,
+            end_reached,
             handover,
             deserializer,
+            rate_limiter,
+            state_recorder,
+            discovered,
+            shutdown,
         }
     }
 
-    fn end_check(&self, topic: &str, partition: i32, offset: i64) -> bool {
+    /// hand the record over to the task, return `false` if the consumer is shutdown or
+    /// the task dropped the handover
+    fn produce(&self, record: ConsumerRecord) -> bool {
+        self.handover.produce_cancellable(record, &self.shutdown)
+    }
+
+    /// commit the offsets consumed by the task to the group, so the group lag reflects the
+    /// stopped job. The offsets are restored from the checkpoint on restart, not the group.
+    fn commit_offsets(&self, consumer: &StreamConsumer<DefaultConsumerContext>) {
+        let mut offsets = TopicPartitionList::new();
+        for (index, consumer_range) in self.consumer_ranges.iter().enumerate() {
+            if let Some(offset) = self.state_recorder.get(index) {
+                if let Err(e) = offsets.add_partition_offset(
+                    consumer_range.topic.as_str(),
+                    consumer_range.partition,
+                    Offset::Offset(offset + 1),
+                ) {
+                    warn!("add the final offset error. {}", e);
+                }
+            }
+        }
+
+        info!(
+            "kafka consumer shutdown, final offsets: {:?}, job_id: {}, task_num: {}",
+            offsets, *self.job_id, self.task_number
+        );
+        if offsets.count() > 0 {
+            if let Err(e) = consumer.commit(&offsets, CommitMode::Sync) {
+                warn!("commit the final offsets error. {}", e);
+            }
+        }
+    }
+
+    fn partition_index(&self, topic: &str, partition: i32) -> Option<usize> {
+        self.consumer_ranges
+            .iter()
+            .position(|x| x.partition == partition && x.topic.eq(topic))
+    }
+
+    /// mark the partition reached the end offset, return `true` if all partitions are reached
+    fn end_check(&mut self, partition_index: usize, offset: i64) -> bool {
         if !self.with_end_consumer_ranges {
             return false;
         }
 
-        if self.consumer_ranges.partition == partition
-            && self.consumer_ranges.end_offset.unwrap() < offset
-            && self.consumer_ranges.topic.eq(topic)
-        {
-            return true;
+        if let Some(end_offset) = self.consumer_ranges[partition_index].end_offset {
+            if end_offset < offset {
+                self.end_reached[partition_index] = true;
+            }
         }
 
-        false
+        self.end_reached.iter().all(|x| *x)
+    }
+
+    /// the partition reached the end of the log, the last stable offset under `read_committed`.
+    /// The aborted records and the control batches(the transaction markers) are never delivered,
+    /// so the end offset may be skipped without a later message, check it by the fetch position.
+    /// return `true` if all partitions are reached
+    fn partition_eof(
+        &mut self,
+        consumer: &StreamConsumer<DefaultConsumerContext>,
+        partition: i32,
+    ) -> KafkaResult<bool> {
+        if !self.with_end_consumer_ranges {
+            return Ok(false);
+        }
+
+        let position = consumer.position()?;
+        let mut all_reached = false;
+        for partition_index in 0..self.consumer_ranges.len() {
+            let consumer_range = &self.consumer_ranges[partition_index];
+            if consumer_range.partition != partition || self.end_reached[partition_index] {
+                continue;
+            }
+
+            let next_offset = position
+                .find_partition(consumer_range.topic.as_str(), partition)
+                .and_then(|elem| elem.offset().to_raw())
+                .filter(|offset| *offset >= 0);
+            if let Some(next_offset) = next_offset {
+                // the next offset is fetched, all offsets before it are consumed or skipped
+                all_reached = self.end_check(partition_index, next_offset);
+            }
+        }
+        Ok(all_reached)
+    }
+
+    /// assign the discovered partitions from the beginning, the records produced before the
+    /// discovery are consumed too. The assigned partitions are reassigned with their fetch
+    /// positions, so the records in the handover are not fetched again.
+    fn assign_discovered(
+        &mut self,
+        consumer: &StreamConsumer<DefaultConsumerContext>,
+    ) -> KafkaResult<()> {
+        let discovered: Vec<TopicPartition> = match self.discovered.as_ref() {
+            Some(receiver) => receiver.try_iter().flatten().collect(),
+            None => return Ok(()),
+        };
+        let discovered: Vec<TopicPartition> = discovered
+            .into_iter()
+            .filter(|x| {
+                self.partition_index(x.topic.as_str(), x.partition)
+                    .is_none()
+            })
+            .collect();
+        if discovered.is_empty() {
+            return Ok(());
+        }
+
+        let position = consumer.position()?;
+        let mut assignment = TopicPartitionList::new();
+        for consumer_range in &self.consumer_ranges {
+            let offset = position
+                .find_partition(consumer_range.topic.as_str(), consumer_range.partition)
+                .map(|elem| elem.offset())
+                .filter(|offset| offset.to_raw().map(|x| x >= 0).unwrap_or(false))
+                .unwrap_or_else(|| Offset::from_raw(consumer_range.begin_offset));
+            assignment.add_partition_offset(
+                consumer_range.topic.as_str(),
+                consumer_range.partition,
+                offset,
+            )?;
+        }
+
+        for topic_partition in &discovered {
+            assignment.add_partition_offset(
+                topic_partition.topic.as_str(),
+                topic_partition.partition,
+                Offset::Beginning,
+            )?;
+        }
+        consumer.assign(&assignment)?;
+
+        for topic_partition in discovered {
+            // the partitions of the recorder and the consumer ranges are indexed the same
+            let partition_index = self.state_recorder.add(topic_partition.clone());
+            assert_eq!(partition_index, self.consumer_ranges.len());
+            self.consumer_ranges.push(ConsumerRange {
+                topic: topic_partition.topic,
+                partition: topic_partition.partition,
+                begin_offset: Offset::Beginning.to_raw().unwrap(),
+                end_offset: None,
+            });
+            self.end_reached.push(false);
+        }
+        info!(
+            "reassign with the discovered partitions: {:?}, job_id: {}, task_num: {}",
+            assignment, *self.job_id, self.task_number
+        );
+        Ok(())
+    }
+
+    fn end(&self) {
+        if !self.produce(ConsumerRecord::new(empty_record(), 0, 0)) {
+            return;
+        }
+        info!(
+            "kafka end offset reached. job_id: {}, task_num: {}",
+            *self.job_id, self.task_number
+        );
     }
 
     pub async fn run(&mut self) -> anyhow::Result<()> {
         let mut assignment = TopicPartitionList::new();
-        assignment
-            .add_partition_offset(
-                self.consumer_ranges.topic.as_str(),
-                self.consumer_ranges.partition,
-                Offset::from_raw(self.consumer_ranges.begin_offset),
-            )
-            .unwrap();
+        for consumer_range in &self.consumer_ranges {
+            assignment
+                .add_partition_offset(
+                    consumer_range.topic.as_str(),
+                    consumer_range.partition,
+                    Offset::from_raw(consumer_range.begin_offset),
+                )
+                .unwrap();
+        }
 
         // let group_id = format!("rlink{}", Uuid::new_v4());
         self.client_config
             .get("group.id")
             .ok_or(anyhow!("`group.id` not found in kafka consumer config"))?;
+        if self.with_end_consumer_ranges {
+            // the end offsets are checked on the partition EOF as well as the consumed offsets
+            self.client_config.set("enable.partition.eof", "true");
+        }
 
         let consumer: StreamConsumer<DefaultConsumerContext> = self.client_config.create()?;
         consumer.assign(&assignment)?;
@@ -117,7 +343,20 @@ impl KafkaConsumerThread {
         );
 
         let mut message_stream = consumer.stream();
-        while let Some(message) = message_stream.next().await {
+        while !self.shutdown.is_cancelled() {
+            if let Err(e) = self.assign_discovered(&consumer) {
+                warn!(
+                    "assign the discovered partitions error. job_id: {}, task_num: {}, error: {}",
+                    *self.job_id, self.task_number, e
+                );
+            }
+
+            let message =
+                match tokio::time::timeout(SHUTDOWN_CHECK_INTERVAL, message_stream.next()).await {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(_elapsed) => continue,
+                };
             match message {
                 Ok(borrowed_message) => {
                     let topic = borrowed_message.topic();
@@ -127,25 +366,52 @@ impl KafkaConsumerThread {
                     let key = borrowed_message.key().unwrap_or(&utils::EMPTY_SLICE);
                     let payload = borrowed_message.payload().unwrap_or(&utils::EMPTY_SLICE);
 
-                    if self.end_check(topic, partition, offset) {
-                        self.handover
-                            .produce(ConsumerRecord::new(empty_record(), 0))
-                            .expect("kafka consumer handover `Disconnected`");
-                        info!(
-                            "kafka end offset reached. job_id: {}, task_num: {}",
-                            *self.job_id, self.task_number
-                        );
+                    let partition_index = match self.partition_index(topic, partition) {
+                        Some(partition_index) => partition_index,
+                        None => {
+                            warn!("unassigned partition {}:{} consumed", topic, partition);
+                            continue;
+                        }
+                    };
+                    if self.end_reached[partition_index] {
+                        continue;
+                    }
+
+                    if self.end_check(partition_index, offset) {
+                        self.end();
                         break;
                     }
+                    if self.end_reached[partition_index] {
+                        continue;
+                    }
 
                     let records = self
                         .deserializer
                         .deserialize(timestamp, key, payload, topic, partition, offset);
 
+                    let mut handed_over = true;
                     for record in records {
-                        self.handover
-                            .produce(ConsumerRecord::new(record, offset))
-                            .expect("kafka consumer handover `Disconnected`");
+                        let record = ConsumerRecord::new(record, partition_index, offset);
+                        if !self.produce(record) {
+                            handed_over = false;
+                            break;
+                        }
+                    }
+                    if !handed_over {
+                        break;
+                    }
+
+                    if let Some(rate_limiter) = self.rate_limiter.as_mut() {
+                        let wait = rate_limiter.acquire(1);
+                        if !wait.is_zero() {
+                            async_sleep(wait).await;
+                        }
+                    }
+                }
+                Err(KafkaError::PartitionEOF(partition)) => {
+                    if self.partition_eof(&consumer, partition)? {
+                        self.end();
+                        break;
                     }
                 }
                 Err(e) => warn!(
@@ -155,6 +421,8 @@ impl KafkaConsumerThread {
             }
         }
 
+        drop(message_stream);
+        self.commit_offsets(&consumer);
         Ok(())
     }
 }
@@ -1,6 +1,10 @@
+use std::convert::TryFrom;
 use std::marker::PhantomData;
 
+use rlink::core::data_types::{DataType, Field, Schema};
+use rlink::core::dynamic_record::DynamicRecordBuilder;
 use rlink::core::element::{FnSchema, Record};
+use rlink::utils::date_time::current_timestamp_millis;
 
 use crate::build_kafka_record;
 
@@ -73,3 +77,207 @@ where
         self.schema.clone()
     }
 }
+
+/// Deserialize the json payload to the `Record` of the `Schema`, the missing and `null` fields are null.
+/// The `Decimal` can be a string or a number, and the `TimestampTz` can be a RFC 3339 string with any
+/// offset or the milliseconds since the unix epoch. The invalid payloads are dropped with an error log.
+pub struct JsonKafkaRecordDeserializer {
+    schema: Schema,
+}
+
+impl JsonKafkaRecordDeserializer {
+    pub fn new(schema: Schema) -> Self {
+        JsonKafkaRecordDeserializer { schema }
+    }
+
+    fn to_record(&self, payload: &[u8]) -> rlink::core::Result<Record> {
+        let json: serde_json::Value =
+            serde_json::from_slice(payload).map_err(rlink::core::Error::wrap)?;
+        let mut builder = DynamicRecordBuilder::new(&self.schema);
+        builder.set_json(&json)?;
+        builder.build()
+    }
+}
+
+impl KafkaRecordDeserializer for JsonKafkaRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        _timestamp: i64,
+        _key: &[u8],
+        payload: &[u8],
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> Vec<Record> {
+        match self.to_record(payload) {
+            Ok(record) => vec![record],
+            Err(e) => {
+                error!(
+                    "deserialize json payload error, topic={}, partition={}, offset={}. {}",
+                    topic, partition, offset, e
+                );
+                vec![]
+            }
+        }
+    }
+}
+
+pub struct JsonKafkaRecordDeserializerBuilder {
+    schema: Schema,
+}
+
+impl JsonKafkaRecordDeserializerBuilder {
+    pub fn new(schema: Schema) -> Self {
+        JsonKafkaRecordDeserializerBuilder { schema }
+    }
+}
+
+impl KafkaRecordDeserializerBuilder for JsonKafkaRecordDeserializerBuilder {
+    fn build(&self) -> Box<dyn KafkaRecordDeserializer> {
+        Box::new(JsonKafkaRecordDeserializer::new(self.schema.clone()))
+    }
+
+    fn schema(&self) -> FnSchema {
+        FnSchema::from(&self.schema)
+    }
+}
+
+/// The metadata of the kafka message, appended to the `Record` as the virtual columns
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum KafkaMetadataColumn {
+    /// `_kafka_topic`: String
+    Topic,
+    /// `_kafka_partition`: Int32
+    Partition,
+    /// `_kafka_offset`: Int64
+    Offset,
+    /// `_kafka_timestamp`: Int64, the timestamp of the message
+    Timestamp,
+    /// `_kafka_ingestion_time`: UInt64, the millis when the message is consumed
+    IngestionTime,
+}
+
+impl KafkaMetadataColumn {
+    pub fn field(&self) -> Field {
+        match self {
+            Self::Topic => Field::new("_kafka_topic", DataType::String),
+            Self::Partition => Field::new("_kafka_partition", DataType::Int32),
+            Self::Offset => Field::new("_kafka_offset", DataType::Int64),
+            Self::Timestamp => Field::new("_kafka_timestamp", DataType::Int64),
+            Self::IngestionTime => Field::new("_kafka_ingestion_time", DataType::UInt64),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for KafkaMetadataColumn {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "topic" => Ok(Self::Topic),
+            "partition" => Ok(Self::Partition),
+            "offset" => Ok(Self::Offset),
+            "timestamp" => Ok(Self::Timestamp),
+            "ingestion_time" => Ok(Self::IngestionTime),
+            _ => Err(anyhow!("unknown kafka metadata column `{}`", value)),
+        }
+    }
+}
+
+/// Append the metadata columns to the records of the inner deserializer
+pub struct MetadataKafkaRecordDeserializer {
+    inner: Box<dyn KafkaRecordDeserializer>,
+    columns: Vec<KafkaMetadataColumn>,
+    type_ids: Vec<u8>,
+    /// the number of fields of the inner deserializer's records
+    arity: usize,
+}
+
+impl MetadataKafkaRecordDeserializer {
+    fn metadata_record(
+        &self,
+        timestamp: i64,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> Result<Record, std::io::Error> {
+        let mut record = Record::with_capacity(topic.len() + 32);
+        let mut writer = record.as_writer(self.type_ids.as_slice());
+        for column in &self.columns {
+            match column {
+                KafkaMetadataColumn::Topic => writer.set_str(topic)?,
+                KafkaMetadataColumn::Partition => writer.set_i32(partition)?,
+                KafkaMetadataColumn::Offset => writer.set_i64(offset)?,
+                KafkaMetadataColumn::Timestamp => writer.set_i64(timestamp)?,
+                KafkaMetadataColumn::IngestionTime => writer.set_u64(current_timestamp_millis())?,
+            }
+        }
+        Ok(record)
+    }
+}
+
+impl KafkaRecordDeserializer for MetadataKafkaRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        timestamp: i64,
+        key: &[u8],
+        payload: &[u8],
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> Vec<Record> {
+        let mut records = self
+            .inner
+            .deserialize(timestamp, key, payload, topic, partition, offset);
+        if records.is_empty() {
+            return records;
+        }
+
+        let metadata_record = self
+            .metadata_record(timestamp, topic, partition, offset)
+            .expect("kafka metadata writer to Record error");
+        for record in &mut records {
+            record
+                .extend(metadata_record.clone(), self.arity)
+                .expect("append kafka metadata error");
+        }
+        records
+    }
+}
+
+/// Wrap a `KafkaRecordDeserializerBuilder`, the metadata columns are appended to its schema
+pub struct MetadataKafkaRecordDeserializerBuilder {
+    inner: Box<dyn KafkaRecordDeserializerBuilder>,
+    columns: Vec<KafkaMetadataColumn>,
+}
+
+impl MetadataKafkaRecordDeserializerBuilder {
+    pub fn new(
+        inner: Box<dyn KafkaRecordDeserializerBuilder>,
+        columns: Vec<KafkaMetadataColumn>,
+    ) -> Self {
+        MetadataKafkaRecordDeserializerBuilder { inner, columns }
+    }
+
+    fn metadata_schema(&self) -> Schema {
+        Schema::new(self.columns.iter().map(|x| x.field()).collect())
+    }
+}
+
+impl KafkaRecordDeserializerBuilder for MetadataKafkaRecordDeserializerBuilder {
+    fn build(&self) -> Box<dyn KafkaRecordDeserializer> {
+        let inner_schema: Schema = self.inner.schema().into();
+        Box::new(MetadataKafkaRecordDeserializer {
+            inner: self.inner.build(),
+            columns: self.columns.clone(),
+            type_ids: self.metadata_schema().as_type_ids().to_vec(),
+            arity: inner_schema.fields().len(),
+        })
+    }
+
+    fn schema(&self) -> FnSchema {
+        let mut schema: Schema = self.inner.schema().into();
+        schema.merge(&self.metadata_schema());
+        FnSchema::Single(schema)
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::ClientConfig;
+use rlink::core::cancellation::CancellationToken;
+use rlink::utils;
+
+use crate::source::TopicPartition;
+
+const METADATA_TIMEOUT: Duration = Duration::from_secs(3);
+/// the interval the discovery loop checks the cancellation
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// the owner task of a partition added after the assignment, all tasks agree on it without
+/// coordination, the new partitions of a topic are spread over the tasks by the partition id
+pub(crate) fn discovered_partition_owner(topic_partition: &TopicPartition, num_tasks: u16) -> u16 {
+    (topic_partition.partition as u32 % num_tasks.max(1) as u32) as u16
+}
+
+/// Fetch the metadata of the topics periodically in a background thread, and send the
+/// partitions not known at the assignment and owned by the task to the consumer thread.
+/// The thread is stopped by the `shutdown` of the consumer.
+pub(crate) struct PartitionDiscovery {
+    client_config: ClientConfig,
+    topics: Vec<String>,
+    interval: Duration,
+
+    task_number: u16,
+    num_tasks: u16,
+    known: HashSet<TopicPartition>,
+}
+
+impl PartitionDiscovery {
+    pub fn new(
+        client_config: ClientConfig,
+        topics: Vec<String>,
+        interval: Duration,
+        task_number: u16,
+        num_tasks: u16,
+        known: Vec<TopicPartition>,
+    ) -> Self {
+        PartitionDiscovery {
+            client_config,
+            topics,
+            interval,
+            task_number,
+            num_tasks,
+            known: known.into_iter().collect(),
+        }
+    }
+
+    pub fn spawn(mut self, sender: Sender<Vec<TopicPartition>>, shutdown: CancellationToken) {
+        utils::thread::spawn("kafka-partition-discovery", move || {
+            let consumer: BaseConsumer = match self.client_config.create() {
+                Ok(consumer) => consumer,
+                Err(e) => {
+                    error!(
+                        "create the consumer of the partition discovery error. {}",
+                        e
+                    );
+                    return;
+                }
+            };
+
+            let mut next_discovery = Instant::now() + self.interval;
+            while !shutdown.is_cancelled() {
+                if Instant::now() < next_discovery {
+                    std::thread::sleep(SHUTDOWN_CHECK_INTERVAL);
+                    continue;
+                }
+                next_discovery = Instant::now() + self.interval;
+
+                match self.discover(&consumer) {
+                    Ok(partitions) if partitions.is_empty() => {}
+                    Ok(partitions) => {
+                        info!(
+                            "new partitions discovered: {}, task_number: {}",
+                            TopicPartition::format_list(partitions.as_slice()),
+                            self.task_number
+                        );
+                        if sender.send(partitions).is_err() {
+                            // the consumer thread is stopped
+                            break;
+                        }
+                    }
+                    Err(e) => warn!("discover the kafka partitions error. {}", e),
+                }
+            }
+        });
+    }
+
+    /// the new partitions owned by the task, all new partitions are known after the call
+    fn discover(&mut self, consumer: &BaseConsumer) -> anyhow::Result<Vec<TopicPartition>> {
+        let mut owned = Vec::new();
+        for topic in &self.topics {
+            let metadata = consumer
+                .fetch_metadata(Some(topic.as_str()), METADATA_TIMEOUT)
+                .map_err(|e| anyhow!("Failed to fetch metadata. {}", e))?;
+            let metadata_topic = match metadata.topics().get(0) {
+                Some(metadata_topic) => metadata_topic,
+                None => continue,
+            };
+
+            for partition in metadata_topic.partitions() {
+                let topic_partition = TopicPartition::new(topic.as_str(), partition.id());
+                if self.known.insert(topic_partition.clone())
+                    && discovered_partition_owner(&topic_partition, self.num_tasks)
+                        == self.task_number
+                {
+                    owned.push(topic_partition);
+                }
+            }
+        }
+        Ok(owned)
+    }
+}
@@ -1,3 +1,4 @@
+use std::sync::mpsc::channel;
 use std::time::Duration;
 
 use rdkafka::consumer::{BaseConsumer, Consumer, DefaultConsumerContext};
@@ -5,22 +6,40 @@ use rdkafka::error::KafkaResult;
 use rdkafka::{ClientConfig, Offset, TopicPartitionList};
 use rlink::channel::utils::handover::Handover;
 use rlink::core;
-use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::cancellation::CancellationToken;
+use rlink::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
 use rlink::core::element::{FnSchema, Record};
-use rlink::core::function::{Context, InputFormat, InputSplit, InputSplitSource, NamedFunction};
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, NamedFunction, SourcePosition,
+};
+use rlink::core::lineage::LineageDataset;
 use rlink::core::properties::Properties;
+use rlink::core::rate_budget::{RateBudget, RateBudgetLimiter};
 use rlink::metrics::Tag;
 
-use crate::source::checkpoint::KafkaCheckpointFunction;
-use crate::source::consumer::{create_kafka_consumer, ConsumerRange};
+use crate::source::assignment::{PartitionAssignor, PartitionInfo};
+use crate::source::checkpoint::{snapshot_partitions, KafkaCheckpointFunction};
+use crate::source::consumer::{create_kafka_consumer, ConsumerRange, KafkaConsumerHandle};
 use crate::source::deserializer::KafkaRecordDeserializerBuilder;
+use crate::source::discovery::PartitionDiscovery;
 use crate::source::iterator::KafkaRecordIterator;
 use crate::source::offset_range::{OffsetRange, PartitionOffset};
-use crate::source::ConsumerRecord;
-
-/// Depending on whether the task has `InputSplit`, and whether the client needs to be created
-const CREATE_KAFKA_CONNECTION: &'static str = "create_kafka_connection";
-
+use crate::source::{ConsumerRecord, TopicPartition, KNOWN_TOPIC_PARTITIONS, TOPIC_PARTITIONS};
+use crate::{ISOLATION_LEVEL, READ_COMMITTED};
+
+/// the max time waiting the consumer thread to stop on closing
+const CONSUMER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Consume the partitions of the topics, the partitions are assigned to the tasks by the
+/// `PartitionAssignor`, so a task owns multiple partitions if the partitions are more than the parallelism,
+/// and the offset of each partition is tracked independently in the checkpoint.
+/// The tasks without any partition are idle if the partitions are less than the parallelism.
+///
+/// The partitions added to the topics after the assignment are discovered by refreshing the
+/// metadata periodically if the `partition_discovery_interval` is set, each new partition is
+/// consumed from the beginning by the task `partition % parallelism` without a restart.
 pub struct KafkaInputFormat {
     name: String,
     parallelism: u16,
@@ -28,18 +47,22 @@ pub struct KafkaInputFormat {
     client_config: ClientConfig,
     topics: Vec<String>,
 
-    task_topic: String,
-    task_partition: i32,
+    topic_partitions: Vec<TopicPartition>,
 
     buffer_size: usize,
     offset_range: OffsetRange,
 
     handover: Option<Handover<ConsumerRecord>>,
+    consumer_handle: Option<KafkaConsumerHandle>,
+    cancellation_token: CancellationToken,
 
     deserializer_builder: Box<dyn KafkaRecordDeserializerBuilder>,
     schema: FnSchema,
 
     checkpoint: Option<KafkaCheckpointFunction>,
+    rate_budget: Option<RateBudget>,
+    assignor: Box<dyn PartitionAssignor>,
+    partition_discovery_interval: Option<Duration>,
 }
 
 impl KafkaInputFormat {
@@ -51,6 +74,8 @@ impl KafkaInputFormat {
         deserializer_builder: Box<dyn KafkaRecordDeserializerBuilder>,
         parallelism: u16,
         fn_name: String,
+        rate_budget: Option<RateBudget>,
+        assignor: Box<dyn PartitionAssignor>,
     ) -> Self {
         let schema = deserializer_builder.schema();
         KafkaInputFormat {
@@ -58,23 +83,93 @@ impl KafkaInputFormat {
             parallelism,
             client_config,
             topics,
-            task_topic: "".to_string(),
-            task_partition: 0,
+            topic_partitions: Vec::new(),
             buffer_size,
             offset_range,
             handover: None,
+            consumer_handle: None,
+            cancellation_token: CancellationToken::new(),
             checkpoint: None,
             deserializer_builder,
             schema,
+            rate_budget,
+            assignor,
+            partition_discovery_interval: None,
+        }
+    }
+
+    pub(crate) fn set_partition_discovery_interval(&mut self, interval: Option<Duration>) {
+        self.partition_discovery_interval = interval;
+    }
+
+    /// the discovery is disabled for the bounded source
+    fn partition_discovery(&self) -> Option<Duration> {
+        self.partition_discovery_interval
+            .filter(|interval| !interval.is_zero() && !self.bounded())
+    }
+
+    /// fetch all partitions of the topics with the leaders
+    fn fetch_partitions(&self) -> core::Result<Vec<PartitionInfo>> {
+        let timeout = Duration::from_secs(3);
+
+        info!("kafka config {:?}", self.client_config);
+
+        let consumer: BaseConsumer = self
+            .client_config
+            .create()
+            .map_err(|e| anyhow!("Consumer creation failed. {}", e))?;
+
+        let mut partitions = Vec::new();
+        for topic in &self.topics {
+            let metadata = consumer
+                .fetch_metadata(Some(topic.as_str()), timeout)
+                .map_err(|e| anyhow!("Failed to fetch metadata. {}", e))?;
+            let metadata_topic = metadata
+                .topics()
+                .get(0)
+                .ok_or(anyhow!("Topic({}) not found", topic))?;
+
+            for partition in metadata_topic.partitions() {
+                partitions.push(PartitionInfo::new(
+                    topic.as_str(),
+                    partition.id(),
+                    partition.leader(),
+                ));
+            }
         }
+        Ok(partitions)
+    }
+
+    fn input_splits(assignment: Vec<Vec<TopicPartition>>) -> Vec<InputSplit> {
+        // all partitions at the assignment, the others are discovered later
+        let known: Vec<TopicPartition> = assignment.iter().flatten().cloned().collect();
+        let known = TopicPartition::format_list(known.as_slice());
+        assignment
+            .into_iter()
+            .enumerate()
+            .map(|(index, partitions)| {
+                let mut properties = Properties::new();
+                properties.set_string(
+                    TOPIC_PARTITIONS.to_string(),
+                    TopicPartition::format_list(partitions.as_slice()),
+                );
+                properties.set_str(KNOWN_TOPIC_PARTITIONS, known.as_str());
+                InputSplit::new(index as u16, properties)
+            })
+            .collect()
     }
 
-    fn consumer_ranges(&mut self, topic: String, partition: i32) -> KafkaResult<ConsumerRange> {
+    fn consumer_range(
+        &mut self,
+        partition_index: usize,
+        topic: String,
+        partition: i32,
+    ) -> KafkaResult<ConsumerRange> {
         let (begin_partition, end_partition) = match &self.offset_range {
             OffsetRange::None => {
                 let state = self.checkpoint.as_mut().unwrap().as_state_mut();
                 let begin_offset = state
-                    .get()
+                    .get(partition_index)
                     .map(|offset| PartitionOffset { partition, offset });
                 (begin_offset, None)
             }
@@ -148,15 +243,46 @@ impl KafkaInputFormat {
             }
         };
 
+        // the end timestamp is later than all messages
+        let end_offset = match end_partition.map(|x| x.offset) {
+            Some(end_offset) if end_offset < 0 => {
+                Some(self.last_offset(topic.as_str(), partition)?)
+            }
+            end_offset => end_offset,
+        };
+
         Ok(ConsumerRange {
             topic,
             partition,
             begin_offset: begin_partition
                 .map(|x| x.offset)
                 .unwrap_or(Offset::End.to_raw().unwrap()),
-            end_offset: end_partition.map(|x| x.offset),
+            end_offset,
         })
     }
+
+    /// the last offset of the partition by the high watermark, which is the last stable offset
+    /// of the `read_committed` consumer, so the open transactions aren't counted in the range.
+    /// The offset may be a control batch, the consumer checks it on the partition EOF
+    fn last_offset(&self, topic: &str, partition: i32) -> KafkaResult<i64> {
+        let consumer: BaseConsumer<DefaultConsumerContext> = self.client_config.create()?;
+        let (_low, high) = consumer.fetch_watermarks(topic, partition, Duration::from_secs(3))?;
+        info!(
+            "the end of {}:{} is the high watermark {}, read_committed: {}",
+            topic,
+            partition,
+            high,
+            self.read_committed()
+        );
+        Ok(high - 1)
+    }
+
+    fn read_committed(&self) -> bool {
+        self.client_config
+            .get(ISOLATION_LEVEL)
+            .map(|isolation_level| isolation_level.eq(READ_COMMITTED))
+            .unwrap_or(false)
+    }
 }
 
 impl NamedFunction for KafkaInputFormat {
@@ -169,22 +295,21 @@ impl InputFormat for KafkaInputFormat {
     fn open(&mut self, input_split: InputSplit, context: &Context) -> core::Result<()> {
         info!("kafka source open");
 
-        self.task_topic = input_split.properties().get_string("topic").unwrap();
-        self.task_partition = input_split.properties().get_i32("partition").unwrap();
+        let topic_partitions = input_split
+            .properties()
+            .get_string(TOPIC_PARTITIONS)
+            .unwrap_or_default();
+        self.topic_partitions = TopicPartition::parse_list(topic_partitions.as_str())?;
+        self.cancellation_token = context.cancellation_token.clone();
 
-        let kafka_checkpoint = KafkaCheckpointFunction::new(
-            context.application_id.clone(),
-            context.task_id,
-            self.task_topic.as_str(),
-            self.task_partition,
-        );
+        let kafka_checkpoint = KafkaCheckpointFunction::new(self.topic_partitions.clone());
         self.checkpoint = Some(kafka_checkpoint);
 
         self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle);
 
         let tags = vec![
-            Tag::new("topic", self.task_topic.as_str()),
-            Tag::new("partition", self.task_partition),
+            Tag::new("topic", topic_partitions.as_str()),
+            Tag::new("partition_num", self.topic_partitions.len()),
         ];
         self.handover = Some(Handover::<ConsumerRecord>::new(
             "KafkaSource_Handover",
@@ -192,20 +317,70 @@ impl InputFormat for KafkaInputFormat {
             self.buffer_size,
         ));
 
+        let partition_discovery = self.partition_discovery();
+        if self.topic_partitions.is_empty() && partition_discovery.is_none() {
+            info!("no partition assigned to the task, the task is idle");
+            return Ok(());
+        }
+
         let client_config = self.client_config.clone();
         let handover = self.handover.as_ref().unwrap().clone();
 
-        let consumer_ranges = self
-            .consumer_ranges(self.task_topic.to_string(), self.task_partition)
-            .unwrap();
-        create_kafka_consumer(
+        let mut consumer_ranges = Vec::with_capacity(self.topic_partitions.len());
+        for (index, topic_partition) in self.topic_partitions.clone().into_iter().enumerate() {
+            let consumer_range = self
+                .consumer_range(index, topic_partition.topic, topic_partition.partition)
+                .map_err(|e| anyhow!("create consumer range error. {}", e))?;
+            consumer_ranges.push(consumer_range);
+        }
+        let rate_limiter = self
+            .rate_budget
+            .as_ref()
+            .map(|rate_budget| RateBudgetLimiter::new(rate_budget.clone(), context.task_id));
+        // stopped by the task's cancellation as well as closing the source
+        let shutdown = context.cancellation_token.child();
+        let discovered = match partition_discovery {
+            Some(interval) => {
+                let known = match input_split.properties().get_string(KNOWN_TOPIC_PARTITIONS) {
+                    Ok(known) => TopicPartition::parse_list(known.as_str())?,
+                    // the split of the early versions
+                    Err(_e) => self
+                        .fetch_partitions()?
+                        .into_iter()
+                        .map(|x| x.topic_partition)
+                        .collect(),
+                };
+
+                let (sender, receiver) = channel();
+                PartitionDiscovery::new(
+                    self.client_config.clone(),
+                    self.topics.clone(),
+                    interval,
+                    context.task_id.task_number(),
+                    self.parallelism,
+                    known,
+                )
+                .spawn(sender, shutdown.clone());
+                Some(receiver)
+            }
+            None => None,
+        };
+
+        let state_recorder = self.checkpoint.as_mut().unwrap().as_state_mut().clone();
+        let consumer_handle = create_kafka_consumer(
             context.task_id.job_id(),
             context.task_id.task_number(),
             client_config,
             consumer_ranges,
             handover,
             self.deserializer_builder.build(),
+            rate_limiter,
+            state_recorder,
+            discovered,
+            shutdown,
+            context.async_runtime(),
         );
+        self.consumer_handle = Some(consumer_handle);
 
         info!("start with consumer and operator mode");
 
@@ -213,15 +388,86 @@ impl InputFormat for KafkaInputFormat {
     }
 
     fn record_iter(&mut self) -> Box<dyn Iterator<Item = Record> + Send> {
+        // the idle task of a bounded source is finished immediately,
+        // of an unbounded source is blocked on the handover forever
+        if self.topic_partitions.is_empty() && self.bounded() {
+            return Box::new(std::iter::empty());
+        }
+
         let handover = self.handover.as_ref().unwrap().clone();
         let state_recorder = self.checkpoint.as_mut().unwrap().as_state_mut().clone();
-        Box::new(KafkaRecordIterator::new(handover, state_recorder))
+        Box::new(KafkaRecordIterator::new(
+            handover,
+            state_recorder,
+            self.cancellation_token.clone(),
+        ))
     }
 
+    /// stop the consumer thread, it commits the final offsets and drops its handover
     fn close(&mut self) -> core::Result<()> {
+        if let Some(consumer_handle) = self.consumer_handle.take() {
+            if !consumer_handle.shutdown(CONSUMER_SHUTDOWN_TIMEOUT) {
+                warn!(
+                    "kafka consumer thread not stopped in {:?}",
+                    CONSUMER_SHUTDOWN_TIMEOUT
+                );
+            }
+        }
+        self.handover = None;
+
+        if let Some(checkpoint) = self.checkpoint.as_ref() {
+            if let Some(state_recorder) = checkpoint.state_recorder.as_ref() {
+                info!(
+                    "kafka source closed, offsets: {}",
+                    state_recorder.snapshot()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// the consumer stops at the end offsets or timestamps
+    fn bounded(&self) -> bool {
+        match &self.offset_range {
+            OffsetRange::None => false,
+            OffsetRange::Direct { end_offset, .. } => end_offset.is_some(),
+            OffsetRange::Timestamp { end_timestamp, .. } => end_timestamp.is_some(),
+        }
+    }
+
+    fn lineage_datasets(&self) -> Vec<LineageDataset> {
+        crate::lineage_datasets(&self.client_config, self.topics.as_slice())
+    }
+
+    /// fetch the metadata of the topics
+    fn probe(&self) -> core::Result<()> {
+        let consumer: BaseConsumer = self
+            .client_config
+            .create()
+            .map_err(|e| anyhow!("Consumer creation failed. {}", e))?;
+        for topic in &self.topics {
+            let metadata = consumer
+                .fetch_metadata(Some(topic.as_str()), Duration::from_secs(3))
+                .map_err(|e| anyhow!("Failed to fetch metadata. {}", e))?;
+            let metadata_topic = metadata
+                .topics()
+                .get(0)
+                .ok_or(anyhow!("Topic({}) not found", topic))?;
+            if let Some(e) = metadata_topic.error() {
+                return Err(anyhow!("Topic({}) error. {:?}", topic, e).into());
+            }
+        }
         Ok(())
     }
 
+    /// the latest consumed offsets of the task's partitions
+    fn position(&self) -> Option<SourcePosition> {
+        self.checkpoint
+            .as_ref()
+            .and_then(|x| x.state_recorder.as_ref())
+            .map(|x| x.position())
+    }
+
     fn schema(&self, _input_schema: FnSchema) -> FnSchema {
         self.schema.clone()
     }
@@ -250,67 +496,52 @@ impl CheckpointFunction for KafkaInputFormat {
             None => None,
         }
     }
+
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        match self.checkpoint.as_mut() {
+            Some(checkpoint) => checkpoint.snapshot_state_async(context),
+            None => None,
+        }
+    }
 }
 
 impl InputSplitSource for KafkaInputFormat {
     fn create_input_splits(&self, min_num_splits: u16) -> core::Result<Vec<InputSplit>> {
-        let timeout = Duration::from_secs(3);
-
-        info!("kafka config {:?}", self.client_config);
-
-        let consumer: BaseConsumer = self
-            .client_config
-            .create()
-            .map_err(|e| anyhow!("Consumer creation failed. {}", e))?;
+        let partitions = self.fetch_partitions()?;
+        let assignment = self
+            .assignor
+            .assign(partitions.as_slice(), min_num_splits as usize, &[]);
+        info!("kafka partitions assignment: {:?}", assignment);
 
-        let mut input_splits = Vec::new();
-        let mut index = 0;
-        for topic in &self.topics {
-            let metadata = consumer
-                .fetch_metadata(Some(topic.as_str()), timeout)
-                .map_err(|e| anyhow!("Failed to fetch metadata. {}", e))?;
-            let metadata_topic = metadata
-                .topics()
-                .get(0)
-                .ok_or(anyhow!("Topic({}) not found", topic))?;
-
-            for partition in metadata_topic.partitions() {
-                let mut properties = Properties::new();
-                properties.set_str("topic", topic.as_str());
-                properties.set_i32("partition", partition.id());
-                properties.set_bool(CREATE_KAFKA_CONNECTION, true);
-
-                let input_split = InputSplit::new(index, properties);
-                index += 1;
+        Ok(Self::input_splits(assignment))
+    }
 
-                input_splits.push(input_split);
-                if index == min_num_splits {
-                    break;
+    /// reassign the partitions with the previous assignment of the checkpoint,
+    /// the assignment is not changed unless the assignor is sticky
+    fn restore_input_splits(
+        &self,
+        input_splits: Vec<InputSplit>,
+        checkpoint_handles: &[Option<CheckpointHandle>],
+    ) -> core::Result<Vec<InputSplit>> {
+        let mut previous = Vec::with_capacity(checkpoint_handles.len());
+        for handle in checkpoint_handles {
+            let partitions = match handle {
+                Some(handle) if !handle.handle.is_empty() => {
+                    snapshot_partitions(handle.handle.as_str())?
                 }
-            }
+                _ => vec![],
+            };
+            previous.push(partitions);
         }
 
-        if input_splits.len() > min_num_splits as usize {
-            return Err(rlink::core::Error::from(
-                "kafka `input_splits.len()` != `min_num_splits`",
-            ));
-        }
-
-        if input_splits.len() < min_num_splits as usize {
-            let mut extend_input_splits = Vec::new();
-            let times = (min_num_splits as usize + input_splits.len() - 1) / input_splits.len();
-            for _ in 1..times {
-                for input_split in &input_splits {
-                    let split_number = input_split.split_number();
-                    let mut properties = input_split.properties().clone();
-                    properties.set_bool(CREATE_KAFKA_CONNECTION, false);
-
-                    extend_input_splits.push(InputSplit::new(split_number, properties));
-                }
-            }
-            input_splits.extend_from_slice(extend_input_splits.as_slice());
-        }
+        let partitions = self.fetch_partitions()?;
+        let assignment = self.assignor.assign(
+            partitions.as_slice(),
+            input_splits.len(),
+            previous.as_slice(),
+        );
+        info!("kafka partitions assignment restored: {:?}", assignment);
 
-        Ok(input_splits)
+        Ok(Self::input_splits(assignment))
     }
 }
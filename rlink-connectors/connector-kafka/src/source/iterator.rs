@@ -1,26 +1,29 @@
 use std::borrow::BorrowMut;
 
 use rlink::channel::utils::handover::Handover;
+use rlink::core::cancellation::CancellationToken;
 use rlink::core::element::Record;
 
 use crate::source::checkpoint::KafkaSourceStateRecorder;
 use crate::source::{is_empty_record, ConsumerRecord};
 
-/// Simulate a Kafka consumption stream as an iterator.
-/// only support one topic and one partition
+/// Simulate a Kafka consumption stream of the task's partitions as an iterator.
 pub struct KafkaRecordIterator {
     handover: Handover<ConsumerRecord>,
     state_recorder: KafkaSourceStateRecorder,
+    cancellation_token: CancellationToken,
 }
 
 impl KafkaRecordIterator {
     pub(crate) fn new(
         handover: Handover<ConsumerRecord>,
         state_recorder: KafkaSourceStateRecorder,
+        cancellation_token: CancellationToken,
     ) -> Self {
         KafkaRecordIterator {
             handover,
             state_recorder,
+            cancellation_token,
         }
     }
 }
@@ -29,17 +32,22 @@ impl Iterator for KafkaRecordIterator {
     type Item = Record;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.handover.poll_next() {
-            Ok(mut consumer_record) => {
+        match self
+            .handover
+            .poll_next_cancellable(&self.cancellation_token)
+        {
+            Some(mut consumer_record) => {
                 if is_empty_record(consumer_record.record.borrow_mut()) {
                     return None;
                 }
 
-                self.state_recorder.update(consumer_record.offset);
+                self.state_recorder
+                    .update(consumer_record.partition_index, consumer_record.offset);
 
                 Some(consumer_record.record)
             }
-            Err(_e) => {
+            None if self.cancellation_token.is_cancelled() => None,
+            None => {
                 panic!("kafka input recv channel disconnected");
             }
         }
@@ -1,10 +1,13 @@
+pub mod assignment;
 pub mod builder;
 pub mod checkpoint;
 pub mod consumer;
 pub mod deserializer;
+pub mod discovery;
 pub mod input_format;
 pub mod iterator;
 pub mod offset_range;
+pub mod watermark;
 
 #[inline]
 pub(crate) fn empty_record() -> rlink::core::element::Record {
@@ -16,14 +19,64 @@ pub(crate) fn is_empty_record(record: &mut rlink::core::element::Record) -> bool
     record.as_buffer().len() == 0
 }
 
+/// the input split property of the task's partitions, eg: `topic-a:0,topic-a:3,topic-b:1`
+pub(crate) const TOPIC_PARTITIONS: &str = "topic_partitions";
+/// the input split property of all partitions at the assignment, see `PartitionDiscovery`
+pub(crate) const KNOWN_TOPIC_PARTITIONS: &str = "known_topic_partitions";
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct TopicPartition {
+    pub topic: String,
+    pub partition: i32,
+}
+
+impl TopicPartition {
+    pub fn new(topic: &str, partition: i32) -> Self {
+        TopicPartition {
+            topic: topic.to_string(),
+            partition,
+        }
+    }
+
+    /// format as the `TOPIC_PARTITIONS` property
+    pub(crate) fn format_list(topic_partitions: &[TopicPartition]) -> String {
+        topic_partitions
+            .iter()
+            .map(|x| format!("{}:{}", x.topic, x.partition))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// parse the `TOPIC_PARTITIONS` property
+    pub(crate) fn parse_list(value: &str) -> anyhow::Result<Vec<TopicPartition>> {
+        value
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                let pos = x
+                    .rfind(':')
+                    .ok_or(anyhow!("illegal topic partition `{}`", x))?;
+                let partition = x[pos + 1..].parse()?;
+                Ok(TopicPartition::new(&x[..pos], partition))
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct ConsumerRecord {
     record: rlink::core::element::Record,
+    /// the index of the record's partition in the task's partitions
+    partition_index: usize,
     offset: i64,
 }
 
 impl ConsumerRecord {
-    pub fn new(record: rlink::core::element::Record, offset: i64) -> Self {
-        ConsumerRecord { record, offset }
+    pub fn new(record: rlink::core::element::Record, partition_index: usize, offset: i64) -> Self {
+        ConsumerRecord {
+            record,
+            partition_index,
+            offset,
+        }
     }
 }
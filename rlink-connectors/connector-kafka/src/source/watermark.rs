@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+use rlink::core::element::Record;
+use rlink::core::watermark::{Watermark, WatermarkGenerator, MAX_WATERMARK};
+
+use crate::buffer_gen::kafka_message;
+
+/// A `WatermarkGenerator` that generates the watermarks per Kafka partition, and emits the min
+/// watermark across the partitions owned by the task, so a slow partition holds back the
+/// watermark of the task instead of being overtaken by the fast partitions.
+///
+/// The record must be the `KafkaMessage` created by the `DefaultKafkaRecordDeserializer`,
+/// so assign the watermarks right after the `KafkaInputFormat`.
+/// A partition joins the watermark calculation since its first record.
+pub struct KafkaPartitionWatermarks {
+    generator_creator: Box<dyn Fn() -> Box<dyn WatermarkGenerator>>,
+    /// key: (topic, partition)
+    /// value: the generator and the latest watermark of the partition
+    partitions: HashMap<(String, i32), (Box<dyn WatermarkGenerator>, Option<Watermark>)>,
+    watermark: Option<Watermark>,
+}
+
+impl KafkaPartitionWatermarks {
+    pub fn new<F>(generator_creator: F) -> Self
+    where
+        F: Fn() -> Box<dyn WatermarkGenerator> + 'static,
+    {
+        KafkaPartitionWatermarks {
+            generator_creator: Box::new(generator_creator),
+            partitions: HashMap::new(),
+            watermark: None,
+        }
+    }
+
+    /// the min watermark of all partitions, the special watermark(eg: idle) is ignored
+    /// unless all partitions are special
+    fn min_watermark(&self) -> Option<Watermark> {
+        let mut min_watermark: Option<Watermark> = None;
+        let mut special_watermark: Option<Watermark> = None;
+        for (_generator, watermark) in self.partitions.values() {
+            match watermark {
+                Some(w) if w.timestamp() > MAX_WATERMARK.timestamp() => {
+                    special_watermark = Some(*w)
+                }
+                Some(w) => {
+                    if min_watermark
+                        .map(|m| w.timestamp() < m.timestamp())
+                        .unwrap_or(true)
+                    {
+                        min_watermark = Some(*w);
+                    }
+                }
+                None => return None,
+            }
+        }
+
+        min_watermark.or(special_watermark)
+    }
+
+    /// emit the min watermark if it's advanced, a partition joins later with a lower
+    /// watermark never moves the watermark of the task backward
+    fn emit_watermark(&mut self) -> Option<Watermark> {
+        match self.min_watermark() {
+            Some(w) if w.timestamp() > MAX_WATERMARK.timestamp() => Some(w),
+            Some(w) => {
+                let advanced = self
+                    .watermark
+                    .map(|current| w.timestamp() > current.timestamp())
+                    .unwrap_or(true);
+                if advanced {
+                    self.watermark = Some(w);
+                    Some(w)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        }
+    }
+}
+
+impl WatermarkGenerator for KafkaPartitionWatermarks {
+    fn on_event(&mut self, record: &mut Record, event_timestamp: u64) -> Option<Watermark> {
+        let key = {
+            let mut reader = kafka_message::FieldReader::new(record.as_buffer());
+            let topic = reader.get_topic().unwrap().to_string();
+            let partition = reader.get_partition().unwrap();
+            (topic, partition)
+        };
+
+        let generator_creator = &self.generator_creator;
+        let (generator, latest_watermark) = self
+            .partitions
+            .entry(key)
+            .or_insert_with(|| (generator_creator(), None));
+
+        match generator.on_event(record, event_timestamp) {
+            Some(watermark) => {
+                *latest_watermark = Some(watermark);
+                self.emit_watermark()
+            }
+            None => None,
+        }
+    }
+
+    fn on_periodic_emit(&mut self) -> Option<Watermark> {
+        for (generator, latest_watermark) in self.partitions.values_mut() {
+            if let Some(watermark) = generator.on_periodic_emit() {
+                *latest_watermark = Some(watermark);
+            }
+        }
+
+        // the `WatermarkAssignerRunnable` always expects a periodic watermark
+        self.emit_watermark().or(self.watermark)
+    }
+}
+
+impl Debug for KafkaPartitionWatermarks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaPartitionWatermarks")
+            .field("partitions", &self.partitions.keys())
+            .field("watermark", &self.watermark)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::element::Record;
+    use rlink::core::watermark::{Watermark, WatermarkGenerator};
+
+    use crate::build_kafka_record;
+    use crate::source::watermark::KafkaPartitionWatermarks;
+
+    /// emit the event timestamp as the watermark
+    #[derive(Debug)]
+    struct EventWatermarks {}
+
+    impl WatermarkGenerator for EventWatermarks {
+        fn on_event(&mut self, _record: &mut Record, event_timestamp: u64) -> Option<Watermark> {
+            Some(Watermark::new(event_timestamp))
+        }
+
+        fn on_periodic_emit(&mut self) -> Option<Watermark> {
+            None
+        }
+    }
+
+    fn on_event(
+        watermarks: &mut KafkaPartitionWatermarks,
+        partition: i32,
+        timestamp: u64,
+    ) -> Option<u64> {
+        let mut record =
+            build_kafka_record(timestamp as i64, &[], &[], "topic", partition, 0).unwrap();
+        watermarks
+            .on_event(&mut record, timestamp)
+            .map(|w| w.timestamp())
+    }
+
+    #[test]
+    pub fn partition_watermarks_test() {
+        let mut watermarks = KafkaPartitionWatermarks::new(|| Box::new(EventWatermarks {}));
+
+        assert_eq!(on_event(&mut watermarks, 0, 100), Some(100));
+        // the slow partition holds back the watermark
+        assert_eq!(on_event(&mut watermarks, 1, 50), None);
+        assert_eq!(on_event(&mut watermarks, 0, 200), None);
+        assert_eq!(on_event(&mut watermarks, 1, 150), Some(150));
+        // never moved backward by a late partition
+        assert_eq!(on_event(&mut watermarks, 2, 10), None);
+        assert_eq!(watermarks.on_periodic_emit().unwrap().timestamp(), 150);
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+/// The actions of a Delta Lake commit, each one is a json line of the `_delta_log/{version}.json`,
+/// only the actions written by the append-only streaming sink are supported
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Action {
+    Protocol(Protocol),
+    MetaData(MetaData),
+    Add(AddFile),
+    Txn(Txn),
+    CommitInfo(CommitInfo),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Protocol {
+    pub min_reader_version: u32,
+    pub min_writer_version: u32,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Protocol {
+            min_reader_version: 1,
+            min_writer_version: 2,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Format {
+    pub provider: String,
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetaData {
+    pub id: String,
+    pub format: Format,
+    /// the json of the table schema, see `delta::schema::delta_schema`
+    pub schema_string: String,
+    pub partition_columns: Vec<String>,
+    #[serde(default)]
+    pub configuration: HashMap<String, String>,
+    pub created_time: u64,
+}
+
+/// A data file added to the table, the `path` is relative to the table root
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AddFile {
+    pub path: String,
+    #[serde(default)]
+    pub partition_values: HashMap<String, String>,
+    pub size: u64,
+    pub modification_time: u64,
+    pub data_change: bool,
+}
+
+/// The latest version of an application's transaction committed to the table,
+/// the writer skips the commits of the versions not greater than it
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Txn {
+    pub app_id: String,
+    pub version: u64,
+    pub last_updated: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitInfo {
+    pub timestamp: u64,
+    pub operation: String,
+}
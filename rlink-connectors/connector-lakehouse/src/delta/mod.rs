@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use parquet::schema::types::TypePtr;
+use rlink::utils::date_time::current_timestamp_millis;
+
+use crate::delta::action::{Action, CommitInfo, Format, MetaData, Protocol, Txn};
+use crate::delta::schema::delta_schema;
+
+pub mod action;
+pub mod schema;
+
+/// the max retries of a commit when the version is taken by the other writers
+const MAX_COMMIT_RETRIES: usize = 100;
+
+const DELTA_LOG_DIR: &str = "_delta_log";
+
+/// A Delta Lake table on the file system, the commits are published by creating the next
+/// version file of the `_delta_log` atomically, so the concurrent writers never overwrite
+/// each other and the readers see either the whole commit or nothing
+pub struct DeltaTable {
+    path: PathBuf,
+    /// the latest version read from the log, `None` if the table is not created
+    version: Option<u64>,
+    /// the latest transaction version of the applications
+    txn_versions: HashMap<String, u64>,
+}
+
+impl DeltaTable {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        DeltaTable {
+            path: path.as_ref().to_path_buf(),
+            version: None,
+            txn_versions: HashMap::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+
+    fn log_path(&self, version: u64) -> PathBuf {
+        self.path
+            .join(DELTA_LOG_DIR)
+            .join(format!("{:020}.json", version))
+    }
+
+    /// read the commits after the known version
+    pub fn refresh(&mut self) -> anyhow::Result<()> {
+        loop {
+            let version = self.version.map(|v| v + 1).unwrap_or(0);
+            let file = match File::open(self.log_path(version)) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+                Err(e) => return Err(anyhow!(e)),
+            };
+
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+                // the actions written by the other engines are skipped
+                if let Ok(Action::Txn(txn)) = serde_json::from_str::<Action>(line.as_str()) {
+                    self.txn_versions.insert(txn.app_id, txn.version);
+                }
+            }
+            self.version = Some(version);
+        }
+    }
+
+    /// the latest transaction version of the application committed to the table
+    pub fn txn_version(&self, app_id: &str) -> Option<u64> {
+        self.txn_versions.get(app_id).cloned()
+    }
+
+    /// create the table with the schema if it's not created
+    pub fn create_if_absent(&mut self, schema: &TypePtr) -> anyhow::Result<()> {
+        self.refresh()?;
+        if self.version.is_some() {
+            return Ok(());
+        }
+
+        let schema_string = serde_json::to_string(&delta_schema(schema)?)?;
+        let now = current_timestamp_millis();
+        let actions = vec![
+            Action::Protocol(Protocol::default()),
+            Action::MetaData(MetaData {
+                id: format!("rlink-{}", now),
+                format: Format {
+                    provider: "parquet".to_string(),
+                    options: HashMap::new(),
+                },
+                schema_string,
+                partition_columns: vec![],
+                configuration: HashMap::new(),
+                created_time: now,
+            }),
+            Action::CommitInfo(CommitInfo {
+                timestamp: now,
+                operation: "CREATE TABLE".to_string(),
+            }),
+        ];
+
+        // the table may be created by the other tasks concurrently
+        if !self.try_commit(0, &actions)? {
+            info!("delta table {:?} is created by the other writer", self.path);
+        }
+        self.refresh()
+    }
+
+    /// commit the `actions` as the transaction `txn_version` of the `app_id`, the commit is
+    /// skipped if the transaction is committed, return the committed version
+    pub fn commit(
+        &mut self,
+        app_id: &str,
+        txn_version: u64,
+        mut actions: Vec<Action>,
+    ) -> anyhow::Result<Option<u64>> {
+        let now = current_timestamp_millis();
+        actions.push(Action::Txn(Txn {
+            app_id: app_id.to_string(),
+            version: txn_version,
+            last_updated: now,
+        }));
+        actions.push(Action::CommitInfo(CommitInfo {
+            timestamp: now,
+            operation: "STREAMING UPDATE".to_string(),
+        }));
+
+        for _ in 0..MAX_COMMIT_RETRIES {
+            self.refresh()?;
+            if let Some(committed_version) = self.txn_version(app_id) {
+                if committed_version >= txn_version {
+                    info!(
+                        "the transaction {} of {} is committed, skip it",
+                        txn_version, app_id
+                    );
+                    return Ok(None);
+                }
+            }
+
+            let version = self.version.map(|v| v + 1).unwrap_or(0);
+            if self.try_commit(version, &actions)? {
+                self.refresh()?;
+                return Ok(Some(version));
+            }
+        }
+
+        Err(anyhow!(
+            "commit to delta table {:?} failed after {} retries",
+            self.path,
+            MAX_COMMIT_RETRIES
+        ))
+    }
+
+    /// write the commit to a temporary file and publish it by linking to the version file,
+    /// return `false` if the version exists
+    fn try_commit(&self, version: u64, actions: &[Action]) -> anyhow::Result<bool> {
+        let log_path = self.log_path(version);
+        let log_dir = log_path.parent().unwrap();
+        std::fs::create_dir_all(log_dir)?;
+
+        let tmp_path = log_dir.join(format!(
+            ".{:020}.json.{}-{:?}.tmp",
+            version,
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        {
+            let mut tmp_file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&tmp_path)?;
+            for action in actions {
+                let line = serde_json::to_string(action)?;
+                tmp_file.write_all(line.as_bytes())?;
+                tmp_file.write_all(b"\n")?;
+            }
+            tmp_file.sync_all()?;
+        }
+
+        let result = std::fs::hard_link(&tmp_path, &log_path);
+        std::fs::remove_file(&tmp_path)?;
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(anyhow!(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use parquet::schema::parser::parse_message_type;
+
+    use crate::delta::action::{Action, AddFile};
+    use crate::delta::DeltaTable;
+
+    #[test]
+    pub fn delta_table_test() {
+        let path = std::env::temp_dir().join("rlink_delta_table_test");
+        let _ = std::fs::remove_dir_all(&path);
+
+        let schema = parse_message_type(
+            "message Document { required int64 id; optional binary name (UTF8); }",
+        )
+        .unwrap();
+        let mut table = DeltaTable::new(&path);
+        table.create_if_absent(&Arc::new(schema)).unwrap();
+        assert_eq!(table.version(), Some(0));
+
+        let add = Action::Add(AddFile {
+            path: "part-0.parquet".to_string(),
+            partition_values: Default::default(),
+            size: 10,
+            modification_time: 0,
+            data_change: true,
+        });
+        assert_eq!(table.commit("app", 1, vec![add.clone()]).unwrap(), Some(1));
+        // the committed transaction is skipped, eg: recommit after a failover
+        assert_eq!(table.commit("app", 1, vec![add.clone()]).unwrap(), None);
+
+        // the commits of the other writers are seen
+        let mut other = DeltaTable::new(&path);
+        assert_eq!(other.commit("other", 1, vec![add]).unwrap(), Some(2));
+        table.refresh().unwrap();
+        assert_eq!(table.version(), Some(2));
+        assert_eq!(table.txn_version("other"), Some(1));
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
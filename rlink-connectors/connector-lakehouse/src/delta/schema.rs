@@ -0,0 +1,80 @@
+use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+use parquet::schema::types::{Type, TypePtr};
+
+/// The field of the Delta table schema, serialized as the Spark `StructField` json
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct StructField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: String,
+    pub nullable: bool,
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct StructType {
+    #[serde(rename = "type")]
+    pub data_type: String,
+    pub fields: Vec<StructField>,
+}
+
+fn primitive_type(field: &Type) -> &'static str {
+    match (
+        field.get_physical_type(),
+        field.get_basic_info().converted_type(),
+    ) {
+        (PhysicalType::BOOLEAN, _) => "boolean",
+        (PhysicalType::INT32, ConvertedType::DATE) => "date",
+        (PhysicalType::INT32, ConvertedType::INT_8) => "byte",
+        (PhysicalType::INT32, ConvertedType::INT_16) => "short",
+        (PhysicalType::INT32, _) => "integer",
+        (PhysicalType::INT64, ConvertedType::TIMESTAMP_MILLIS)
+        | (PhysicalType::INT64, ConvertedType::TIMESTAMP_MICROS)
+        | (PhysicalType::INT96, _) => "timestamp",
+        (PhysicalType::INT64, _) => "long",
+        (PhysicalType::FLOAT, _) => "float",
+        (PhysicalType::DOUBLE, _) => "double",
+        (PhysicalType::BYTE_ARRAY, ConvertedType::UTF8) => "string",
+        (PhysicalType::BYTE_ARRAY, _) | (PhysicalType::FIXED_LEN_BYTE_ARRAY, _) => "binary",
+    }
+}
+
+/// convert the flat parquet message type to the Delta table schema,
+/// the nested groups and the repeated fields are not supported
+pub fn delta_schema(schema: &TypePtr) -> anyhow::Result<StructType> {
+    let mut fields = Vec::new();
+    for field in schema.get_fields() {
+        let basic_info = field.get_basic_info();
+        if !field.is_primitive() {
+            return Err(anyhow!(
+                "nested field `{}` is not supported",
+                basic_info.name()
+            ));
+        }
+
+        let repetition = if basic_info.has_repetition() {
+            basic_info.repetition()
+        } else {
+            Repetition::REQUIRED
+        };
+        if repetition == Repetition::REPEATED {
+            return Err(anyhow!(
+                "repeated field `{}` is not supported",
+                basic_info.name()
+            ));
+        }
+
+        fields.push(StructField {
+            name: basic_info.name().to_string(),
+            data_type: primitive_type(field.as_ref()).to_string(),
+            nullable: repetition == Repetition::OPTIONAL,
+            metadata: serde_json::Map::new(),
+        });
+    }
+
+    Ok(StructType {
+        data_type: "struct".to_string(),
+        fields,
+    })
+}
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use parquet::file::properties::WriterPropertiesPtr;
+use parquet::schema::types::TypePtr;
+use rlink::core;
+use rlink::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{Context, OutputFormat};
+use rlink::core::runtime::{CheckpointId, TaskId};
+use rlink::utils::date_time::current_timestamp_millis;
+use rlink_connector_files::writer::parquet_writer::{BlocksBuilder, ParquetBlockWriter};
+use rlink_connector_files::writer::BlockWriter;
+
+use crate::delta::action::{Action, AddFile};
+use crate::delta::DeltaTable;
+
+/// The data files written by the task and not committed yet, by the checkpoint they belong to
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+struct DeltaSinkState {
+    pending_files: BTreeMap<u64, Vec<AddFile>>,
+}
+
+/// Write the records to a Delta Lake table exactly-once. The records are buffered to the parquet
+/// data files, which are closed on the checkpoint barrier and recorded in the checkpoint,
+/// then committed to the table atomically when the checkpoint is completed. Each task commits
+/// with its own transaction id and the checkpoint id as the version, so the files recovered
+/// from a checkpoint are never committed twice. The files written after the latest completed
+/// checkpoint are not committed and left as orphans after a failover.
+#[derive(NamedFunction)]
+pub struct DeltaOutputFormat {
+    table_path: PathBuf,
+    schema: TypePtr,
+    props: WriterPropertiesPtr,
+    blocks_builder: Arc<Box<dyn BlocksBuilder>>,
+    row_group_size: usize,
+    max_file_bytes: i64,
+
+    table: Option<DeltaTable>,
+    app_id: String,
+    task_id: TaskId,
+    writer: Option<ParquetBlockWriter>,
+    file_sequence: u64,
+    /// the data files closed after the latest checkpoint
+    current_files: Vec<AddFile>,
+    state: DeltaSinkState,
+}
+
+impl DeltaOutputFormat {
+    pub fn new(
+        table_path: &str,
+        schema: TypePtr,
+        props: WriterPropertiesPtr,
+        blocks_builder: Arc<Box<dyn BlocksBuilder>>,
+        row_group_size: usize,
+        max_file_bytes: i64,
+    ) -> Self {
+        DeltaOutputFormat {
+            table_path: PathBuf::from(table_path),
+            schema,
+            props,
+            blocks_builder,
+            row_group_size,
+            max_file_bytes,
+            table: None,
+            app_id: "".to_string(),
+            task_id: TaskId::default(),
+            writer: None,
+            file_sequence: 0,
+            current_files: Vec::new(),
+            state: DeltaSinkState::default(),
+        }
+    }
+
+    fn create_writer(&self) -> ParquetBlockWriter {
+        ParquetBlockWriter::new(
+            self.row_group_size,
+            self.max_file_bytes,
+            self.schema.clone(),
+            self.props.clone(),
+            self.blocks_builder.clone(),
+        )
+    }
+
+    /// close the current writer and write the data file to the table's directory,
+    /// it's invisible until committed
+    fn flush_writer(&mut self) -> anyhow::Result<()> {
+        let writer = match self.writer.take() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        let bytes = writer.close()?;
+        let file_name = format!(
+            "part-{}-{}-{}-{}.parquet",
+            self.task_id.job_id().0,
+            self.task_id.task_number(),
+            current_timestamp_millis(),
+            self.file_sequence
+        );
+        self.file_sequence += 1;
+
+        let path = self.table_path.join(file_name.as_str());
+        std::fs::write(&path, bytes.as_slice())?;
+        info!("write delta data file {:?}, {} bytes", path, bytes.len());
+
+        self.current_files.push(AddFile {
+            path: file_name,
+            partition_values: HashMap::new(),
+            size: bytes.len() as u64,
+            modification_time: current_timestamp_millis(),
+            data_change: true,
+        });
+        Ok(())
+    }
+
+    /// commit the pending files of the checkpoints up to the `checkpoint_id` in order, each
+    /// checkpoint is a transaction, the files are kept to retry on the next completed checkpoint
+    /// if the commit fails
+    fn commit(&mut self, checkpoint_id: CheckpointId) -> anyhow::Result<()> {
+        let checkpoint_ids: Vec<u64> = self
+            .state
+            .pending_files
+            .range(..=checkpoint_id.0)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let table = self.table.as_mut().unwrap();
+        for id in checkpoint_ids {
+            let actions: Vec<Action> = self.state.pending_files[&id]
+                .iter()
+                .cloned()
+                .map(Action::Add)
+                .collect();
+            let num_files = actions.len();
+
+            let version = table.commit(self.app_id.as_str(), id, actions)?;
+            info!(
+                "commit {} files of checkpoint {} to delta table {:?}, version {:?}",
+                num_files,
+                id,
+                table.path(),
+                version
+            );
+            self.state.pending_files.remove(&id);
+        }
+        Ok(())
+    }
+}
+
+impl OutputFormat for DeltaOutputFormat {
+    fn open(&mut self, context: &Context) -> core::Result<()> {
+        self.task_id = context.task_id;
+        self.app_id = format!(
+            "{}-{}-{}",
+            context.application_id,
+            context.task_id.job_id().0,
+            context.task_id.task_number()
+        );
+
+        let mut table = DeltaTable::new(self.table_path.as_path());
+        table.create_if_absent(&self.schema)?;
+        self.table = Some(table);
+
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle);
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: Record) {
+        if self.writer.is_none() {
+            self.writer = Some(self.create_writer());
+        }
+
+        let full = self.writer.as_mut().unwrap().append(record).unwrap();
+        if full {
+            self.flush_writer().unwrap();
+        }
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn probe(&self) -> core::Result<()> {
+        std::fs::create_dir_all(self.table_path.as_path()).map_err(|e| anyhow!(e))?;
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+impl CheckpointFunction for DeltaOutputFormat {
+    /// commit the files of the restored checkpoint, they may be not committed before the failover
+    fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        let handle = match handle {
+            Some(handle) if !handle.handle.is_empty() => handle,
+            _ => return,
+        };
+
+        self.state = serde_json::from_str(handle.handle.as_str()).unwrap();
+        info!(
+            "restore {} checkpoints of delta sink from checkpoint {:?}",
+            self.state.pending_files.len(),
+            context.checkpoint_id
+        );
+        if let Some(checkpoint_id) = self.state.pending_files.keys().last().cloned() {
+            self.commit(CheckpointId(checkpoint_id)).unwrap();
+        }
+    }
+
+    /// the data file is closed on the element loop, a failed write fails the task so the
+    /// records are replayed, the state is serialized on the snapshot pool
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        self.flush_writer().unwrap();
+
+        let files = std::mem::take(&mut self.current_files);
+        if !files.is_empty() {
+            self.state
+                .pending_files
+                .insert(context.checkpoint_id.0, files);
+        }
+
+        let state = self.state.clone();
+        Some(Box::new(move || {
+            let handle = serde_json::to_string(&state)?;
+            Ok(CheckpointHandle { handle })
+        }))
+    }
+
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        if let Err(e) = self.commit(checkpoint_id) {
+            error!(
+                "commit checkpoint {:?} to delta table {:?} error, retry on the next checkpoint. {}",
+                checkpoint_id, self.table_path, e
+            );
+        }
+    }
+}
@@ -0,0 +1,119 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+
+pub mod sink;
+pub mod source;
+
+pub use sink::MqttOutputFormat;
+pub use source::input_format::MqttInputFormat;
+
+use std::time::Duration;
+
+use rlink::core::data_types::{DataType, Field, Schema};
+use rlink::core::element::Record;
+use rumqttc::{MqttOptions, QoS};
+use serbuffer::types;
+
+pub const INPUT_FORMAT_FN_NAME_DEFAULT: &str = "MqttInputFormat";
+pub const OUTPUT_FORMAT_FN_NAME_DEFAULT: &str = "MqttOutputFormat";
+
+pub const SOURCE_CHANNEL_SIZE: usize = 50000;
+pub const SINK_CHANNEL_SIZE: usize = 50000;
+
+/// the capacity of the request channel of the `rumqttc` client
+const CLIENT_CHANNEL_SIZE: usize = 100;
+
+/// the field index of the mqtt message record
+pub mod mqtt_message {
+    pub const TOPIC: usize = 0;
+    pub const PAYLOAD: usize = 1;
+    pub const QOS: usize = 2;
+    pub const RETAIN: usize = 3;
+    /// the millis of receiving the message, mqtt has no timestamp of the message
+    pub const TIMESTAMP: usize = 4;
+}
+
+/// the schema of the records consumed by `MqttInputFormat` and published by `MqttOutputFormat`
+pub fn mqtt_message_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("topic", DataType::String),
+        Field::new("payload", DataType::Binary),
+        Field::new("qos", DataType::UInt8),
+        Field::new("retain", DataType::Boolean),
+        Field::new("timestamp", DataType::UInt64),
+    ])
+}
+
+pub fn build_mqtt_record(
+    topic: &str,
+    payload: &[u8],
+    qos: QoS,
+    retain: bool,
+    timestamp: u64,
+) -> Result<Record, std::io::Error> {
+    // 22 = 8(len(topic) + len(payload)) + 1(qos) + 1(retain) + 8(timestamp) + 4(place_holder)
+    let mut record = Record::with_capacity(topic.len() + payload.len() + 22);
+    let mut writer = record.as_writer(&[
+        types::STRING,
+        types::BINARY,
+        types::U8,
+        types::BOOL,
+        types::U64,
+    ]);
+    writer.set_str(topic)?;
+    writer.set_binary(payload)?;
+    writer.set_u8(qos as u8)?;
+    writer.set_bool(retain)?;
+    writer.set_u64(timestamp)?;
+
+    Ok(record)
+}
+
+/// The connection options of the mqtt broker
+#[derive(Clone, Debug)]
+pub struct MqttConnectOptions {
+    pub host: String,
+    pub port: u16,
+    /// the client id is `{client_id_prefix}-{job_id}-{task_number}`, it's stable across
+    /// the restarts of the task, so the persistent session on the broker is resumed
+    pub client_id_prefix: String,
+    pub keep_alive: Duration,
+    pub credentials: Option<(String, String)>,
+}
+
+impl MqttConnectOptions {
+    pub fn new(host: &str, port: u16, client_id_prefix: &str) -> Self {
+        MqttConnectOptions {
+            host: host.to_string(),
+            port,
+            client_id_prefix: client_id_prefix.to_string(),
+            keep_alive: Duration::from_secs(30),
+            credentials: None,
+        }
+    }
+
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    pub(crate) fn mqtt_options(&self, client_id: &str) -> MqttOptions {
+        let mut options = MqttOptions::new(client_id, self.host.as_str(), self.port);
+        options.set_keep_alive(self.keep_alive);
+        if let Some((username, password)) = &self.credentials {
+            options.set_credentials(username.as_str(), password.as_str());
+        }
+        options
+    }
+}
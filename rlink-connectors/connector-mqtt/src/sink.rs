@@ -0,0 +1,140 @@
+use rlink::core;
+use rlink::core::checkpoint::CheckpointFunction;
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{Context, OutputFormat};
+use rumqttc::{Client, QoS};
+use serbuffer::types;
+
+use crate::{mqtt_message, MqttConnectOptions, CLIENT_CHANNEL_SIZE};
+
+#[derive(Debug)]
+pub struct MqttOutputFormatBuilder {
+    connect_options: MqttConnectOptions,
+    topic: Option<String>,
+    qos: QoS,
+    retain: bool,
+}
+
+impl MqttOutputFormatBuilder {
+    pub fn new(connect_options: MqttConnectOptions) -> Self {
+        MqttOutputFormatBuilder {
+            connect_options,
+            topic: None,
+            qos: QoS::AtLeastOnce,
+            retain: false,
+        }
+    }
+
+    /// publish all records to the `topic` instead of the `topic` field of the records
+    pub fn topic(mut self, topic: &str) -> Self {
+        self.topic = Some(topic.to_string());
+        self
+    }
+
+    /// the QoS of the published messages, default `AtLeastOnce`
+    pub fn qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    /// publish the messages as the retained messages of the topics, eg: the latest device status
+    pub fn retain(mut self, retain: bool) -> Self {
+        self.retain = retain;
+        self
+    }
+
+    pub fn build(self) -> MqttOutputFormat {
+        info!("build mqtt sink with: {:?}", &self);
+        MqttOutputFormat::new(self.connect_options, self.topic, self.qos, self.retain)
+    }
+}
+
+/// Publish the records of the `mqtt_message_schema` to the mqtt broker, the `qos` and `retain`
+/// fields of the records are ignored, the sink's are used.
+#[derive(NamedFunction)]
+pub struct MqttOutputFormat {
+    connect_options: MqttConnectOptions,
+    topic: Option<String>,
+    qos: QoS,
+    retain: bool,
+
+    client: Option<Client>,
+}
+
+impl MqttOutputFormat {
+    pub fn new(
+        connect_options: MqttConnectOptions,
+        topic: Option<String>,
+        qos: QoS,
+        retain: bool,
+    ) -> Self {
+        MqttOutputFormat {
+            connect_options,
+            topic,
+            qos,
+            retain,
+            client: None,
+        }
+    }
+}
+
+impl OutputFormat for MqttOutputFormat {
+    fn open(&mut self, context: &Context) -> core::Result<()> {
+        let client_id = format!(
+            "{}-{}-{}",
+            self.connect_options.client_id_prefix,
+            context.task_id.job_id().0,
+            context.task_id.task_number()
+        );
+        let options = self.connect_options.mqtt_options(client_id.as_str());
+        let (client, mut connection) = Client::new(options, CLIENT_CHANNEL_SIZE);
+
+        // the event loop sends the messages and handles the acknowledgements
+        rlink::utils::thread::spawn("mqtt-publisher", move || {
+            for notification in connection.iter() {
+                if let Err(e) = notification {
+                    error!("mqtt connection error, reconnect later. {}", e);
+                    std::thread::sleep(std::time::Duration::from_secs(3));
+                }
+            }
+        });
+
+        info!("mqtt sink open, client id: {}", client_id);
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn write_record(&mut self, mut record: Record) {
+        let reader = record.as_reader(&[
+            types::STRING,
+            types::BINARY,
+            types::U8,
+            types::BOOL,
+            types::U64,
+        ]);
+        let topic = match &self.topic {
+            Some(topic) => topic.as_str(),
+            None => reader.get_str(mqtt_message::TOPIC).unwrap(),
+        };
+        let payload = reader.get_binary(mqtt_message::PAYLOAD).unwrap();
+
+        self.client
+            .as_mut()
+            .unwrap()
+            .publish(topic, self.qos, self.retain, payload.to_vec())
+            .unwrap();
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        if let Some(mut client) = self.client.take() {
+            client.disconnect().map_err(|e| anyhow!(e))?;
+        }
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+impl CheckpointFunction for MqttOutputFormat {}
@@ -0,0 +1,71 @@
+use rumqttc::QoS;
+
+use crate::{
+    MqttConnectOptions, MqttInputFormat, INPUT_FORMAT_FN_NAME_DEFAULT, SOURCE_CHANNEL_SIZE,
+};
+
+#[derive(Debug)]
+pub struct MqttInputFormatBuilder {
+    fn_name: Option<String>,
+    parallelism: u16,
+    connect_options: MqttConnectOptions,
+    topics: Vec<String>,
+    qos: QoS,
+    shared_group: Option<String>,
+    buffer_size: Option<usize>,
+}
+
+impl MqttInputFormatBuilder {
+    pub fn new(connect_options: MqttConnectOptions, topics: Vec<String>, parallelism: u16) -> Self {
+        MqttInputFormatBuilder {
+            fn_name: None,
+            parallelism,
+            connect_options,
+            topics,
+            qos: QoS::AtLeastOnce,
+            shared_group: None,
+            buffer_size: None,
+        }
+    }
+
+    pub fn fn_name(mut self, name: &str) -> Self {
+        self.fn_name = Some(name.to_string());
+        self
+    }
+
+    /// the max QoS of the subscriptions, default `AtLeastOnce`
+    pub fn qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    /// share the messages among the tasks by the shared subscriptions of the `group`
+    pub fn shared_group(mut self, group: &str) -> Self {
+        self.shared_group = Some(group.to_string());
+        self
+    }
+
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    pub fn build(self) -> MqttInputFormat {
+        info!("build mqtt source with: {:?}", &self);
+
+        if self.parallelism > 1 && self.shared_group.is_none() {
+            warn!("each task of the parallel mqtt source receives all messages without the shared group");
+        }
+
+        MqttInputFormat::new(
+            self.connect_options,
+            self.topics,
+            self.qos,
+            self.shared_group,
+            self.buffer_size.unwrap_or(SOURCE_CHANNEL_SIZE),
+            self.parallelism,
+            self.fn_name
+                .unwrap_or(INPUT_FORMAT_FN_NAME_DEFAULT.to_string()),
+        )
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use rumqttc::Publish;
+
+/// The client session of the task, the client id is restored from the checkpoint, so the
+/// persistent session on the broker is resumed and the unacknowledged messages are redelivered
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct MqttSessionState {
+    pub client_id: String,
+    /// the topic filters subscribed by the session
+    pub subscriptions: Vec<String>,
+}
+
+#[derive(Default)]
+struct PendingAcksInner {
+    /// the messages emitted after the latest checkpoint
+    current: Vec<Publish>,
+    /// the messages emitted before the checkpoints, by the checkpoint id
+    checkpoints: BTreeMap<u64, Vec<Publish>>,
+}
+
+/// The QoS 1 and 2 messages emitted to the downstream but not acknowledged to the broker.
+/// A message is acknowledged after the checkpoint following it is completed, so the messages
+/// lost by a failover are redelivered by the broker, at-least-once.
+#[derive(Clone, Default)]
+pub(crate) struct PendingAcks {
+    inner: Arc<Mutex<PendingAcksInner>>,
+}
+
+impl PendingAcks {
+    pub fn push(&self, publish: Publish) {
+        self.inner.lock().unwrap().current.push(publish);
+    }
+
+    /// the messages emitted before the `checkpoint_id` are acknowledged when it's completed
+    pub fn snapshot(&self, checkpoint_id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        let current = std::mem::take(&mut inner.current);
+        if !current.is_empty() {
+            inner
+                .checkpoints
+                .entry(checkpoint_id)
+                .or_default()
+                .extend(current);
+        }
+    }
+
+    /// take the messages can be acknowledged when the `checkpoint_id` is completed
+    pub fn complete(&self, checkpoint_id: u64) -> Vec<Publish> {
+        let mut inner = self.inner.lock().unwrap();
+        let pending = inner.checkpoints.split_off(&(checkpoint_id + 1));
+        let completed = std::mem::replace(&mut inner.checkpoints, pending);
+        completed.into_values().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rumqttc::{Publish, QoS};
+
+    use crate::source::checkpoint::PendingAcks;
+
+    #[test]
+    pub fn pending_acks_test() {
+        let pending_acks = PendingAcks::default();
+        let publish = |pkid: u16| {
+            let mut publish = Publish::new("t", QoS::AtLeastOnce, vec![]);
+            publish.pkid = pkid;
+            publish
+        };
+
+        pending_acks.push(publish(1));
+        pending_acks.snapshot(1);
+        pending_acks.push(publish(2));
+        pending_acks.snapshot(2);
+        pending_acks.push(publish(3));
+
+        let acks: Vec<u16> = pending_acks.complete(1).iter().map(|x| x.pkid).collect();
+        assert_eq!(acks, vec![1]);
+        // the messages after the latest checkpoint are not acknowledged
+        let acks: Vec<u16> = pending_acks.complete(3).iter().map(|x| x.pkid).collect();
+        assert_eq!(acks, vec![2]);
+    }
+}
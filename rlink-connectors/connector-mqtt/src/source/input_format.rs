@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use rlink::channel::utils::handover::Handover;
+use rlink::core;
+use rlink::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{Context, InputFormat, InputSplit, InputSplitSource, NamedFunction};
+use rlink::core::runtime::CheckpointId;
+use rlink::utils::date_time::current_timestamp_millis;
+use rumqttc::{Client, Connection, Event, Packet, QoS};
+
+use crate::source::checkpoint::{MqttSessionState, PendingAcks};
+use crate::source::iterator::MqttRecordIterator;
+use crate::source::MqttMessage;
+use crate::{build_mqtt_record, mqtt_message_schema, MqttConnectOptions, CLIENT_CHANNEL_SIZE};
+
+/// the interval of reconnecting to the broker after the connection error
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Subscribe the topic filters of the mqtt broker. Each task is a client with a persistent
+/// session, the QoS 1 and 2 messages are acknowledged after the checkpoint is completed.
+/// The tasks of a parallel source share the messages by the shared subscription
+/// `$share/{group}/{filter}` if the `shared_group` is set, otherwise each task receives all
+/// messages of the topic filters.
+pub struct MqttInputFormat {
+    name: String,
+    parallelism: u16,
+
+    connect_options: MqttConnectOptions,
+    topics: Vec<String>,
+    qos: QoS,
+    shared_group: Option<String>,
+    buffer_size: usize,
+
+    client: Option<Client>,
+    handover: Option<Handover<MqttMessage>>,
+    pending_acks: PendingAcks,
+    state: MqttSessionState,
+}
+
+impl MqttInputFormat {
+    pub fn new(
+        connect_options: MqttConnectOptions,
+        topics: Vec<String>,
+        qos: QoS,
+        shared_group: Option<String>,
+        buffer_size: usize,
+        parallelism: u16,
+        fn_name: String,
+    ) -> Self {
+        MqttInputFormat {
+            name: fn_name,
+            parallelism,
+            connect_options,
+            topics,
+            qos,
+            shared_group,
+            buffer_size,
+            client: None,
+            handover: None,
+            pending_acks: PendingAcks::default(),
+            state: MqttSessionState::default(),
+        }
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        self.topics
+            .iter()
+            .map(|topic| match &self.shared_group {
+                Some(group) => format!("$share/{}/{}", group, topic),
+                None => topic.clone(),
+            })
+            .collect()
+    }
+
+    fn poll_connection(mut connection: Connection, handover: Handover<MqttMessage>) {
+        rlink::utils::thread::spawn("mqtt-connection", move || {
+            for notification in connection.iter() {
+                match notification {
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let record = build_mqtt_record(
+                            publish.topic.as_str(),
+                            publish.payload.as_ref(),
+                            publish.qos,
+                            publish.retain,
+                            current_timestamp_millis(),
+                        )
+                        .unwrap();
+                        handover.produce(MqttMessage::new(record, publish)).unwrap();
+                    }
+                    Ok(Event::Incoming(Packet::ConnAck(conn_ack))) => {
+                        info!(
+                            "mqtt connected, session present: {}",
+                            conn_ack.session_present
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        // the client reconnects on the next poll
+                        error!("mqtt connection error, reconnect later. {}", e);
+                        std::thread::sleep(RECONNECT_INTERVAL);
+                    }
+                }
+            }
+        });
+    }
+}
+
+impl NamedFunction for MqttInputFormat {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl InputFormat for MqttInputFormat {
+    fn open(&mut self, _input_split: InputSplit, context: &Context) -> core::Result<()> {
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle);
+        if self.state.client_id.is_empty() {
+            self.state.client_id = format!(
+                "{}-{}-{}",
+                self.connect_options.client_id_prefix,
+                context.task_id.job_id().0,
+                context.task_id.task_number()
+            );
+        }
+        self.state.subscriptions = self.subscriptions();
+        info!(
+            "mqtt source open, client id: {}, subscriptions: {:?}",
+            self.state.client_id, self.state.subscriptions
+        );
+
+        let mut options = self
+            .connect_options
+            .mqtt_options(self.state.client_id.as_str());
+        options.set_clean_session(false);
+        options.set_manual_acks(self.qos != QoS::AtMostOnce);
+
+        let (mut client, connection) = Client::new(options, CLIENT_CHANNEL_SIZE);
+        for subscription in &self.state.subscriptions {
+            client
+                .subscribe(subscription.as_str(), self.qos)
+                .map_err(|e| anyhow!("mqtt subscribe {} error. {}", subscription, e))?;
+        }
+
+        let handover = Handover::new(
+            "MqttSource_Handover",
+            context.task_id.to_tags(),
+            self.buffer_size,
+        );
+        Self::poll_connection(connection, handover.clone());
+
+        self.handover = Some(handover);
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn record_iter(&mut self) -> Box<dyn Iterator<Item = Record> + Send> {
+        let handover = self.handover.as_ref().unwrap().clone();
+        Box::new(MqttRecordIterator::new(handover, self.pending_acks.clone()))
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        if let Some(mut client) = self.client.take() {
+            client.disconnect().map_err(|e| anyhow!(e))?;
+        }
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&mqtt_message_schema())
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+impl CheckpointFunction for MqttInputFormat {
+    fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        let handle = match handle {
+            Some(handle) if !handle.handle.is_empty() => handle,
+            _ => return,
+        };
+
+        self.state = serde_json::from_str(handle.handle.as_str()).unwrap();
+        info!(
+            "restore mqtt session {:?} from checkpoint {:?}",
+            self.state, context.checkpoint_id
+        );
+    }
+
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        self.pending_acks.snapshot(context.checkpoint_id.0);
+
+        let state = self.state.clone();
+        Some(Box::new(move || {
+            let handle = serde_json::to_string(&state)?;
+            Ok(CheckpointHandle { handle })
+        }))
+    }
+
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        let client = match self.client.as_mut() {
+            Some(client) => client,
+            None => return,
+        };
+
+        for publish in self.pending_acks.complete(checkpoint_id.0) {
+            if let Err(e) = client.ack(&publish) {
+                // the message is redelivered by the broker
+                error!("mqtt ack message {} error. {}", publish.pkid, e);
+            }
+        }
+    }
+}
+
+impl InputSplitSource for MqttInputFormat {}
@@ -0,0 +1,40 @@
+use rlink::channel::utils::handover::Handover;
+use rlink::core::element::Record;
+use rumqttc::QoS;
+
+use crate::source::checkpoint::PendingAcks;
+use crate::source::MqttMessage;
+
+/// Simulate the mqtt subscription as an iterator, the QoS 1 and 2 messages are acknowledged
+/// after the checkpoint
+pub struct MqttRecordIterator {
+    handover: Handover<MqttMessage>,
+    pending_acks: PendingAcks,
+}
+
+impl MqttRecordIterator {
+    pub(crate) fn new(handover: Handover<MqttMessage>, pending_acks: PendingAcks) -> Self {
+        MqttRecordIterator {
+            handover,
+            pending_acks,
+        }
+    }
+}
+
+impl Iterator for MqttRecordIterator {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.handover.poll_next() {
+            Ok(message) => {
+                if message.publish.qos != QoS::AtMostOnce {
+                    self.pending_acks.push(message.publish);
+                }
+                Some(message.record)
+            }
+            Err(_e) => {
+                panic!("mqtt input recv channel disconnected");
+            }
+        }
+    }
+}
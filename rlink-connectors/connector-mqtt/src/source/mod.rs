@@ -0,0 +1,20 @@
+use rlink::core::element::Record;
+use rumqttc::Publish;
+
+pub mod builder;
+pub mod checkpoint;
+pub mod input_format;
+pub mod iterator;
+
+/// the record of a message and the message to be acknowledged
+#[derive(Clone, Debug)]
+pub(crate) struct MqttMessage {
+    record: Record,
+    publish: Publish,
+}
+
+impl MqttMessage {
+    pub fn new(record: Record, publish: Publish) -> Self {
+        MqttMessage { record, publish }
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rlink::core::checkpoint::{AsyncSnapshot, CheckpointHandle};
+use rlink::core::runtime::CheckpointId;
+
+/// the default timeout of waiting for the acknowledgements on the checkpoint
+pub const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The number of records produced to the `Handover` and acknowledged by the external system.
+/// All records before a `Barrier` are acknowledged when `acknowledged` catch up with `produced`.
+#[derive(Clone, Debug, Default)]
+pub struct AckCounter {
+    produced: Arc<AtomicU64>,
+    acknowledged: Arc<AtomicU64>,
+}
+
+impl AckCounter {
+    pub fn produce(&self) {
+        self.produced.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn acknowledge(&self, n: usize) {
+        self.acknowledged.fetch_add(n as u64, Ordering::SeqCst);
+    }
+
+    /// the number of records produced but not acknowledged
+    pub fn pending(&self) -> u64 {
+        let produced = self.produced.load(Ordering::SeqCst);
+        produced.saturating_sub(self.acknowledged.load(Ordering::SeqCst))
+    }
+
+    /// the number of records produced, the mark to wait by `wait_acknowledged`
+    pub fn produced(&self) -> u64 {
+        self.produced.load(Ordering::SeqCst)
+    }
+
+    /// block until the first `produced` records are acknowledged,
+    /// return `false` if they are not acknowledged in the `timeout`
+    pub fn wait_acknowledged(&self, produced: u64, timeout: Duration) -> bool {
+        let begin = Instant::now();
+        let mut warn_time = begin;
+        while self.acknowledged.load(Ordering::SeqCst) < produced {
+            if begin.elapsed() > timeout {
+                return false;
+            }
+            if warn_time.elapsed() > Duration::from_secs(10) {
+                warn!(
+                    "waiting for acknowledgements {:?}, produced {}, acknowledged {}",
+                    begin.elapsed(),
+                    produced,
+                    self.acknowledged.load(Ordering::SeqCst)
+                );
+                warn_time = Instant::now();
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+
+    /// wait for the records produced before the `Barrier` are acknowledged in the background,
+    /// the checkpoint is declined if they are not acknowledged in the `timeout`
+    pub fn snapshot(&self, checkpoint_id: CheckpointId, timeout: Duration) -> AsyncSnapshot {
+        let ack_counter = self.clone();
+        let produced = self.produced();
+        Box::new(move || {
+            if ack_counter.wait_acknowledged(produced, timeout) {
+                Ok(CheckpointHandle::default())
+            } else {
+                Err(anyhow!(
+                    "{} records are not acknowledged in {:?}, decline checkpoint {:?}",
+                    produced.saturating_sub(ack_counter.acknowledged.load(Ordering::SeqCst)),
+                    timeout,
+                    checkpoint_id
+                ))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rlink::core::runtime::CheckpointId;
+
+    use crate::ack::AckCounter;
+
+    #[test]
+    pub fn ack_timeout_test() {
+        let ack_counter = AckCounter::default();
+        ack_counter.produce();
+        ack_counter.produce();
+
+        let snapshot = ack_counter.snapshot(CheckpointId(1), Duration::from_millis(50));
+        // the records produced after the barrier are not waited
+        ack_counter.produce();
+        ack_counter.acknowledge(1);
+        assert!(snapshot().is_err());
+
+        let snapshot = ack_counter.snapshot(CheckpointId(2), Duration::from_millis(50));
+        ack_counter.acknowledge(2);
+        assert!(snapshot().is_ok());
+    }
+}
@@ -0,0 +1,17 @@
+//! The common plumbing of the connectors: the `Handover` between the task thread and the async
+//! write loops, the batching, the retry with backoff, the flush on the checkpoint and the metrics.
+//! A new sink only implements the `BatchWriter` and is checked by the `testkit`.
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate anyhow;
+
+pub mod ack;
+pub mod retry;
+pub mod sink;
+pub mod testkit;
+
+pub use ack::AckCounter;
+pub use retry::{RetryPolicy, WriteError};
+pub use sink::{BatchConfig, BatchSink, BatchWriteTask, BatchWriter, BoxFuture, SinkMetrics};
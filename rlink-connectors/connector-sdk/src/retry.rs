@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+/// The error of writing a batch
+#[derive(Debug)]
+pub enum WriteError {
+    /// the batch is written again after the backoff, eg: timeout, throttled or unavailable
+    Retryable(anyhow::Error),
+    /// the batch is dropped and counted as the failure, eg: the invalid records
+    Permanent(anyhow::Error),
+}
+
+impl WriteError {
+    pub fn retryable<E: Into<anyhow::Error>>(e: E) -> Self {
+        WriteError::Retryable(e.into())
+    }
+
+    pub fn permanent<E: Into<anyhow::Error>>(e: E) -> Self {
+        WriteError::Permanent(e.into())
+    }
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::Retryable(e) => write!(f, "retryable write error. {}", e),
+            WriteError::Permanent(e) => write!(f, "permanent write error. {}", e),
+        }
+    }
+}
+
+/// The exponential backoff of the retryable write errors
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// the attempts before the batch is failed permanently, retry forever if `None`
+    pub max_attempts: Option<u32>,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(
+        max_attempts: Option<u32>,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// the backoff before the retry `attempt`, doubled from `initial_backoff` up to `max_backoff`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .checked_mul(1 << attempt.min(31))
+            .unwrap_or(self.max_backoff);
+        backoff.min(self.max_backoff)
+    }
+
+    /// whether the batch failed at the `attempt`(start from 0) should be written again
+    pub fn should_retry(&self, attempt: u32) -> bool {
+        self.max_attempts
+            .map(|max_attempts| attempt + 1 < max_attempts)
+            .unwrap_or(true)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// retry forever, the backoff from 100ms up to 10s
+    fn default() -> Self {
+        RetryPolicy::new(None, Duration::from_millis(100), Duration::from_secs(10))
+    }
+}
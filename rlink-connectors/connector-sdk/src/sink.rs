@@ -0,0 +1,346 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rlink::channel::utils::handover::Handover;
+use rlink::core;
+use rlink::core::cancellation::CancellationToken;
+use rlink::core::checkpoint::{AsyncSnapshot, CheckpointFunction, FunctionSnapshotContext};
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{Context, NamedFunction, OutputFormat};
+use rlink::core::runtime::TaskId;
+use rlink::metrics::metric::Counter;
+use rlink::metrics::{register_counter, Tag};
+use rlink::utils::thread::{async_sleep, async_spawn};
+
+use crate::ack::{AckCounter, DEFAULT_ACK_TIMEOUT};
+use crate::retry::{RetryPolicy, WriteError};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The external system specific part of a sink, the rest is done by the `BatchSink`.
+///
+/// The futures are boxed to keep the trait object safe without `async_trait`, eg:
+/// ```ignore
+/// fn write_batch<'a>(&'a self, batch: &'a mut [Record]) -> BoxFuture<'a, Result<(), WriteError>> {
+///     Box::pin(async move {
+///         self.client.send(to_body(batch)).await.map_err(WriteError::retryable)
+///     })
+/// }
+/// ```
+pub trait BatchWriter: Send + Sync + 'static {
+    /// called once in the write thread before the first batch, eg: create the connection
+    fn open(&mut self, _task_id: TaskId) -> BoxFuture<'_, anyhow::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// write the batch, may be called concurrently by `BatchConfig::parallelism` loops and called
+    /// again with the same batch on `WriteError::Retryable`, so the write should be idempotent or
+    /// tolerate the duplicates as the at-least-once delivery
+    fn write_batch<'a>(&'a self, batch: &'a mut [Record]) -> BoxFuture<'a, Result<(), WriteError>>;
+}
+
+/// The batching options of the `BatchSink`
+#[derive(Clone, Debug)]
+pub struct BatchConfig {
+    /// the max records of a batch
+    pub batch_size: usize,
+    /// the max time waiting for a full batch since the first record of the batch
+    pub batch_timeout: Duration,
+    /// the concurrent write loops of each task
+    pub parallelism: usize,
+    /// the capacity of the `Handover`, the task is back-pressured when full
+    pub buffer_size: usize,
+    pub retry_policy: RetryPolicy,
+    /// the max time waiting for the records before the `Barrier` are written,
+    /// the checkpoint is declined on the timeout
+    pub ack_timeout: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfig {
+            batch_size: 1000,
+            batch_timeout: Duration::from_secs(1),
+            parallelism: 1,
+            buffer_size: 10000,
+            retry_policy: RetryPolicy::default(),
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+        }
+    }
+}
+
+/// The metrics of the sink, named by the sink name, eg: `Elasticsearch_Written`
+#[derive(Clone, Debug, Default)]
+pub struct SinkMetrics {
+    /// the records written
+    pub written: Counter,
+    /// the batches written again
+    pub retry: Counter,
+    /// the records dropped by the permanent errors
+    pub failure: Counter,
+}
+
+impl SinkMetrics {
+    pub fn register(name: &str, tags: Vec<Tag>) -> Self {
+        SinkMetrics {
+            written: register_counter(format!("{}_Written", name), tags.clone()),
+            retry: register_counter(format!("{}_Retry", name), tags.clone()),
+            failure: register_counter(format!("{}_Failure", name), tags),
+        }
+    }
+}
+
+/// A sink writing the records by the `BatchWriter` in batches. The records are handed over to the
+/// async write loops, and all records before a `Barrier` are acknowledged before the checkpoint,
+/// so no record is lost after recovering from the checkpoint.
+pub struct BatchSink<W>
+where
+    W: BatchWriter,
+{
+    name: String,
+    config: BatchConfig,
+    writer: Option<W>,
+    handover: Option<Handover>,
+    ack_counter: AckCounter,
+    cancellation_token: CancellationToken,
+}
+
+impl<W> BatchSink<W>
+where
+    W: BatchWriter,
+{
+    pub fn new(name: &str, writer: W) -> Self {
+        BatchSink {
+            name: name.to_string(),
+            config: BatchConfig::default(),
+            writer: Some(writer),
+            handover: None,
+            ack_counter: AckCounter::default(),
+            cancellation_token: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_config(mut self, config: BatchConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl<W> OutputFormat for BatchSink<W>
+where
+    W: BatchWriter,
+{
+    fn open(&mut self, context: &Context) -> core::Result<()> {
+        let tags = context.task_id.to_tags();
+        let handover = Handover::new(self.name.as_str(), tags.clone(), self.config.buffer_size);
+        self.handover = Some(handover.clone());
+        self.cancellation_token = context.cancellation_token.clone();
+
+        let mut writer = self
+            .writer
+            .take()
+            .ok_or(anyhow!("the sink {} is opened twice", self.name))?;
+        let task = BatchWriteTask::new(
+            self.config.clone(),
+            handover,
+            self.ack_counter.clone(),
+            SinkMetrics::register(self.name.as_str(), tags),
+        )
+        .with_cancellation(self.cancellation_token.clone());
+
+        let name = self.name.clone();
+        let task_id = context.task_id;
+        let runtime = context.async_runtime();
+        rlink::utils::thread::spawn(format!("{}-sink-block", name).as_str(), move || {
+            runtime.block_on(async move {
+                if let Err(e) = writer.open(task_id).await {
+                    panic!("open the writer of sink {} error. {}", name, e);
+                }
+                task.run(Arc::new(writer)).await;
+            });
+        });
+
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: Record) {
+        self.ack_counter.produce();
+        let handover = self.handover.as_ref().unwrap();
+        if !handover.produce_cancellable(record, &self.cancellation_token) {
+            // the write loops exited, the record is read again after the restart
+            self.ack_counter.acknowledge(1);
+        }
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+impl<W> NamedFunction for BatchSink<W>
+where
+    W: BatchWriter,
+{
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl<W> CheckpointFunction for BatchSink<W>
+where
+    W: BatchWriter,
+{
+    /// wait for all records before the `Barrier` are written,
+    /// the records are sent again after recovering if the application crash before the checkpoint completed.
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        Some(
+            self.ack_counter
+                .snapshot(context.checkpoint_id, self.config.ack_timeout),
+        )
+    }
+}
+
+/// The write loops polling the batches from the `Handover`
+#[derive(Clone)]
+pub struct BatchWriteTask {
+    config: BatchConfig,
+    handover: Handover,
+    ack_counter: AckCounter,
+    metrics: SinkMetrics,
+    cancellation_token: CancellationToken,
+}
+
+impl BatchWriteTask {
+    pub fn new(
+        config: BatchConfig,
+        handover: Handover,
+        ack_counter: AckCounter,
+        metrics: SinkMetrics,
+    ) -> Self {
+        BatchWriteTask {
+            config,
+            handover,
+            ack_counter,
+            metrics,
+            cancellation_token: CancellationToken::new(),
+        }
+    }
+
+    /// exit the write loops after writing the polled records once the `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = token;
+        self
+    }
+
+    pub async fn run<W>(&self, writer: Arc<W>)
+    where
+        W: BatchWriter,
+    {
+        let mut join_handlers = Vec::new();
+        for _ in 0..self.config.parallelism.max(1) {
+            let self_clone = self.clone();
+            let writer = writer.clone();
+
+            let handler = async_spawn(async move {
+                self_clone.run0(writer).await;
+            });
+            join_handlers.push(handler);
+        }
+
+        for handler in join_handlers {
+            handler.await.unwrap();
+        }
+    }
+
+    async fn run0<W>(&self, writer: Arc<W>)
+    where
+        W: BatchWriter,
+    {
+        while !self.cancellation_token.is_cancelled() {
+            let mut batch = self.poll_batch().await;
+            if batch.is_empty() {
+                continue;
+            }
+            let record_size = batch.len();
+            write_with_retry(
+                writer.as_ref(),
+                batch.as_mut_slice(),
+                &self.config.retry_policy,
+                &self.metrics,
+            )
+            .await;
+            self.ack_counter.acknowledge(record_size);
+        }
+    }
+
+    /// poll a batch until it's full or `batch_timeout` since the first record
+    async fn poll_batch(&self) -> Vec<Record> {
+        let mut batch = Vec::with_capacity(self.config.batch_size);
+        let mut first_time: Option<Instant> = None;
+        while batch.len() < self.config.batch_size {
+            match self.handover.try_poll_next() {
+                Ok(record) => {
+                    first_time.get_or_insert_with(Instant::now);
+                    batch.push(record);
+                }
+                Err(_e) => {
+                    if self.cancellation_token.is_cancelled() {
+                        break;
+                    }
+                    if let Some(first_time) = first_time {
+                        if first_time.elapsed() >= self.config.batch_timeout {
+                            break;
+                        }
+                    }
+                    async_sleep(Duration::from_millis(10)).await;
+                }
+            }
+        }
+        batch
+    }
+}
+
+/// Write the batch until it's succeeded or failed permanently, the retryable errors are retried
+/// with the backoff of the `retry_policy`. Return `false` if the batch is dropped.
+pub async fn write_with_retry<W>(
+    writer: &W,
+    batch: &mut [Record],
+    retry_policy: &RetryPolicy,
+    metrics: &SinkMetrics,
+) -> bool
+where
+    W: BatchWriter + ?Sized,
+{
+    let mut attempt = 0;
+    loop {
+        // the error may be not `Send`, format it before the next `await`
+        let error = match writer.write_batch(batch).await {
+            Ok(()) => {
+                metrics.written.fetch_add(batch.len() as u64);
+                return true;
+            }
+            Err(WriteError::Retryable(e)) if retry_policy.should_retry(attempt) => e.to_string(),
+            Err(e) => {
+                error!("drop {} records at attempt {}. {}", batch.len(), attempt, e);
+                metrics.failure.fetch_add(batch.len() as u64);
+                return false;
+            }
+        };
+
+        warn!(
+            "retry {} records at attempt {}. {}",
+            batch.len(),
+            attempt,
+            error
+        );
+        metrics.retry.fetch_add(1);
+        async_sleep(retry_policy.backoff(attempt)).await;
+        attempt += 1;
+    }
+}
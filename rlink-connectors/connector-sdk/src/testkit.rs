@@ -0,0 +1,198 @@
+//! The compliance suite of the `BatchWriter`s and an in-memory writer for testing the pipelines.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rlink::core::element::Record;
+use rlink::core::runtime::TaskId;
+use rlink::utils::thread::{async_runtime_single, async_spawn};
+
+use crate::retry::{RetryPolicy, WriteError};
+use crate::sink::{write_with_retry, BatchWriter, BoxFuture, SinkMetrics};
+
+/// An in-memory `BatchWriter` keeping the written records, the failures can be injected
+#[derive(Clone, Default)]
+pub struct MockBatchWriter {
+    records: Arc<Mutex<Vec<Record>>>,
+    batches: Arc<AtomicUsize>,
+    retryable_failures: Arc<AtomicUsize>,
+}
+
+impl MockBatchWriter {
+    pub fn new() -> Self {
+        MockBatchWriter::default()
+    }
+
+    /// fail the next `n` writes with `WriteError::Retryable`
+    pub fn fail_retryable(&self, n: usize) {
+        self.retryable_failures.store(n, Ordering::SeqCst);
+    }
+
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// the number of the succeeded writes
+    pub fn batches(&self) -> usize {
+        self.batches.load(Ordering::SeqCst)
+    }
+}
+
+impl BatchWriter for MockBatchWriter {
+    fn write_batch<'a>(&'a self, batch: &'a mut [Record]) -> BoxFuture<'a, Result<(), WriteError>> {
+        Box::pin(async move {
+            let failures = self.retryable_failures.load(Ordering::SeqCst);
+            if failures > 0 {
+                self.retryable_failures
+                    .store(failures - 1, Ordering::SeqCst);
+                return Err(WriteError::retryable(anyhow!("injected failure")));
+            }
+
+            self.records.lock().unwrap().extend_from_slice(batch);
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        })
+    }
+}
+
+/// Check a `BatchWriter` against the external system with the `records`:
+/// - the writer is opened
+/// - all batches are written by the concurrent loops without the permanent errors
+/// - the first batch is written again as the replay after a failover without the permanent errors
+///
+/// The retryable errors are retried by the `retry_policy`, 3 attempts by default. The writer is
+/// returned for asserting the content of the external system, eg:
+/// ```ignore
+/// let writer = ComplianceSuite::new(MyWriter::new(url), records).run().unwrap();
+/// assert_eq!(writer.count().unwrap(), 100);
+/// ```
+pub struct ComplianceSuite<W>
+where
+    W: BatchWriter,
+{
+    writer: W,
+    records: Vec<Record>,
+    batch_size: usize,
+    retry_policy: RetryPolicy,
+}
+
+impl<W> ComplianceSuite<W>
+where
+    W: BatchWriter,
+{
+    pub fn new(writer: W, records: Vec<Record>) -> Self {
+        ComplianceSuite {
+            writer,
+            records,
+            batch_size: 100,
+            retry_policy: RetryPolicy::new(
+                Some(3),
+                Duration::from_millis(10),
+                Duration::from_millis(100),
+            ),
+        }
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn run(self) -> anyhow::Result<W> {
+        let ComplianceSuite {
+            mut writer,
+            records,
+            batch_size,
+            retry_policy,
+        } = self;
+        let batches: Vec<Vec<Record>> = records.chunks(batch_size).map(|x| x.to_vec()).collect();
+
+        async_runtime_single().block_on(async move {
+            writer
+                .open(TaskId::default())
+                .await
+                .map_err(|e| anyhow!("open the writer error. {}", e))?;
+
+            let writer = Arc::new(writer);
+            let mut join_handlers = Vec::new();
+            for (index, mut batch) in batches.iter().cloned().enumerate() {
+                let writer = writer.clone();
+                let retry_policy = retry_policy.clone();
+                join_handlers.push(async_spawn(async move {
+                    let metrics = SinkMetrics::default();
+                    let written = write_with_retry(
+                        writer.as_ref(),
+                        batch.as_mut_slice(),
+                        &retry_policy,
+                        &metrics,
+                    )
+                    .await;
+                    (index, written)
+                }));
+            }
+            for handler in join_handlers {
+                let (index, written) = handler.await?;
+                if !written {
+                    return Err(anyhow!("the batch {} is failed", index));
+                }
+            }
+
+            if let Some(batch) = batches.first() {
+                let mut batch = batch.clone();
+                let metrics = SinkMetrics::default();
+                if !write_with_retry(
+                    writer.as_ref(),
+                    batch.as_mut_slice(),
+                    &retry_policy,
+                    &metrics,
+                )
+                .await
+                {
+                    return Err(anyhow!("the replayed batch is failed"));
+                }
+            }
+
+            Arc::try_unwrap(writer).map_err(|_| anyhow!("the writer is still referenced"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::element::Record;
+    use serbuffer::types;
+
+    use crate::testkit::{ComplianceSuite, MockBatchWriter};
+
+    #[test]
+    pub fn compliance_suite_test() {
+        let records: Vec<Record> = (0..250)
+            .map(|i| {
+                let mut record = Record::new();
+                let mut writer = record.as_writer(&[types::I64]);
+                writer.set_i64(i).unwrap();
+                record
+            })
+            .collect();
+
+        let writer = MockBatchWriter::new();
+        writer.fail_retryable(2);
+
+        let writer = ComplianceSuite::new(writer, records).run().unwrap();
+        // 3 batches and the replayed one
+        assert_eq!(writer.batches(), 4);
+        assert_eq!(writer.records().len(), 250 + 100);
+
+        let writer = MockBatchWriter::new();
+        writer.fail_retryable(10);
+        assert!(ComplianceSuite::new(writer, vec![Record::new()])
+            .run()
+            .is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use hyper::{Body, Client, Request};
+use hyper_tls::HttpsConnector;
+use rlink::utils::aws::{sign_v4, AwsCredentials};
+use tokio::runtime::Handle;
+
+/// the max entries of a batch request
+pub const MAX_BATCH_SIZE: usize = 10;
+
+const SQS_SERVICE: &str = "sqs";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.0";
+
+/// The connection config of a SQS queue
+#[derive(Clone, Debug)]
+pub struct SqsConfig {
+    pub region: String,
+    pub queue_url: String,
+    pub credentials: AwsCredentials,
+    /// the endpoint of the SQS api, default `https://sqs.{region}.amazonaws.com`,
+    /// eg: the endpoint of the local emulator
+    pub endpoint: Option<String>,
+}
+
+impl SqsConfig {
+    pub fn new(region: &str, queue_url: &str, credentials: AwsCredentials) -> Self {
+        SqsConfig {
+            region: region.to_string(),
+            queue_url: queue_url.to_string(),
+            credentials,
+            endpoint: None,
+        }
+    }
+
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.trim_end_matches('/').to_string());
+        self
+    }
+
+    fn endpoint(&self) -> String {
+        self.endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://sqs.{}.amazonaws.com", self.region))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SqsMessage {
+    pub message_id: String,
+    pub receipt_handle: String,
+    pub body: String,
+    #[serde(default)]
+    pub attributes: std::collections::HashMap<String, String>,
+}
+
+impl SqsMessage {
+    /// the millis of the message sent to the queue
+    pub fn sent_timestamp(&self) -> u64 {
+        self.attributes
+            .get("SentTimestamp")
+            .and_then(|x| x.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ReceiveMessageResult {
+    #[serde(default)]
+    messages: Vec<SqsMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BatchResultErrorEntry {
+    id: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BatchResult {
+    #[serde(default)]
+    failed: Vec<BatchResultErrorEntry>,
+}
+
+/// A blocking client of the SQS json api, the requests are run on the `runtime`,
+/// eg: the shared runtime of `Context::async_runtime`
+pub struct SqsClient {
+    config: SqsConfig,
+    client: Client<HttpsConnector<hyper::client::HttpConnector>, Body>,
+    runtime: Handle,
+}
+
+impl SqsClient {
+    pub fn new(config: SqsConfig, runtime: Handle) -> Self {
+        SqsClient {
+            config,
+            client: Client::builder().build::<_, Body>(HttpsConnector::new()),
+            runtime,
+        }
+    }
+
+    fn request(&self, action: &str, body: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+        let endpoint = self.config.endpoint();
+        let host = endpoint
+            .split("://")
+            .nth(1)
+            .unwrap_or(endpoint.as_str())
+            .to_string();
+        let payload = body.to_string();
+        let target = format!("AmazonSQS.{}", action);
+        let now = Utc::now();
+
+        let mut headers = vec![
+            ("content-type".to_string(), CONTENT_TYPE.to_string()),
+            ("host".to_string(), host),
+            (
+                "x-amz-date".to_string(),
+                now.format("%Y%m%dT%H%M%SZ").to_string(),
+            ),
+        ];
+        if let Some(token) = &self.config.credentials.session_token {
+            headers.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        headers.push(("x-amz-target".to_string(), target));
+
+        let authorization = sign_v4(
+            &self.config.credentials,
+            self.config.region.as_str(),
+            SQS_SERVICE,
+            "POST",
+            "/",
+            "",
+            headers.as_slice(),
+            payload.as_bytes(),
+            now,
+        );
+
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri(format!("{}/", endpoint));
+        for (name, value) in headers.iter().filter(|(name, _)| name.ne("host")) {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let req = builder
+            .header("authorization", authorization)
+            .body(Body::from(payload))?;
+
+        self.runtime.block_on(async {
+            let res = self.client.request(req).await?;
+            let status = res.status();
+            let bytes = hyper::body::to_bytes(res.into_body()).await?;
+            if !status.is_success() {
+                return Err(anyhow!(
+                    "sqs {} error, status {}, {}",
+                    action,
+                    status,
+                    String::from_utf8_lossy(bytes.as_ref())
+                ));
+            }
+            Ok(serde_json::from_slice(bytes.as_ref())?)
+        })
+    }
+
+    /// long poll the messages, they are invisible to the other consumers for the `visibility_timeout`
+    pub fn receive_messages(
+        &self,
+        max_messages: usize,
+        wait_time: Duration,
+        visibility_timeout: Duration,
+    ) -> anyhow::Result<Vec<SqsMessage>> {
+        let body = serde_json::json!({
+            "QueueUrl": self.config.queue_url,
+            "MaxNumberOfMessages": max_messages.min(MAX_BATCH_SIZE),
+            "WaitTimeSeconds": wait_time.as_secs(),
+            "VisibilityTimeout": visibility_timeout.as_secs(),
+            "AttributeNames": ["SentTimestamp"],
+        });
+        let result: ReceiveMessageResult =
+            serde_json::from_value(self.request("ReceiveMessage", body)?)?;
+        Ok(result.messages)
+    }
+
+    /// delete the messages by the receipt handles, return the number of the failed
+    pub fn delete_messages(&self, receipt_handles: &[String]) -> anyhow::Result<usize> {
+        let mut failed = 0;
+        for batch in receipt_handles.chunks(MAX_BATCH_SIZE) {
+            let entries: Vec<serde_json::Value> = batch
+                .iter()
+                .enumerate()
+                .map(|(id, handle)| serde_json::json!({"Id": id.to_string(), "ReceiptHandle": handle}))
+                .collect();
+            let body = serde_json::json!({"QueueUrl": self.config.queue_url, "Entries": entries});
+            failed += self.batch_request("DeleteMessageBatch", body)?;
+        }
+        Ok(failed)
+    }
+
+    /// extend the invisible time of the messages, return the number of the failed
+    pub fn change_visibility(
+        &self,
+        receipt_handles: &[String],
+        visibility_timeout: Duration,
+    ) -> anyhow::Result<usize> {
+        let mut failed = 0;
+        for batch in receipt_handles.chunks(MAX_BATCH_SIZE) {
+            let entries: Vec<serde_json::Value> = batch
+                .iter()
+                .enumerate()
+                .map(|(id, handle)| {
+                    serde_json::json!({
+                        "Id": id.to_string(),
+                        "ReceiptHandle": handle,
+                        "VisibilityTimeout": visibility_timeout.as_secs(),
+                    })
+                })
+                .collect();
+            let body = serde_json::json!({"QueueUrl": self.config.queue_url, "Entries": entries});
+            failed += self.batch_request("ChangeMessageVisibilityBatch", body)?;
+        }
+        Ok(failed)
+    }
+
+    fn batch_request(&self, action: &str, body: serde_json::Value) -> anyhow::Result<usize> {
+        let result: BatchResult = serde_json::from_value(self.request(action, body)?)?;
+        for entry in &result.failed {
+            warn!(
+                "sqs {} entry {} failed. {}",
+                action, entry.id, entry.message
+            );
+        }
+        Ok(result.failed.len())
+    }
+
+    /// check the queue exists
+    pub fn probe(&self) -> anyhow::Result<()> {
+        let body = serde_json::json!({
+            "QueueUrl": self.config.queue_url,
+            "AttributeNames": ["ApproximateNumberOfMessages"],
+        });
+        self.request("GetQueueAttributes", body).map(|_| ())
+    }
+}
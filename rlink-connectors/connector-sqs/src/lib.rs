@@ -0,0 +1,53 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate anyhow;
+
+pub mod client;
+pub mod source;
+
+pub use client::SqsConfig;
+pub use rlink::utils::aws::AwsCredentials;
+pub use source::input_format::SqsInputFormat;
+
+use rlink::core::data_types::{DataType, Field, Schema};
+use rlink::core::element::Record;
+use serbuffer::types;
+
+pub const INPUT_FORMAT_FN_NAME_DEFAULT: &str = "SqsInputFormat";
+
+pub const SOURCE_CHANNEL_SIZE: usize = 10000;
+
+/// the field index of the sqs message record
+pub mod sqs_message {
+    pub const MESSAGE_ID: usize = 0;
+    pub const BODY: usize = 1;
+    /// the millis of the message sent to the queue
+    pub const SENT_TIMESTAMP: usize = 2;
+}
+
+/// the schema of the records consumed by `SqsInputFormat`
+pub fn sqs_message_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("message_id", DataType::String),
+        Field::new("body", DataType::String),
+        Field::new("sent_timestamp", DataType::UInt64),
+    ])
+}
+
+pub fn build_sqs_record(
+    message_id: &str,
+    body: &str,
+    sent_timestamp: u64,
+) -> Result<Record, std::io::Error> {
+    // 20 = 8(len(message_id) + len(body)) + 8(sent_timestamp) + 4(place_holder)
+    let mut record = Record::with_capacity(message_id.len() + body.len() + 20);
+    let mut writer = record.as_writer(&[types::STRING, types::STRING, types::U64]);
+    writer.set_str(message_id)?;
+    writer.set_str(body)?;
+    writer.set_u64(sent_timestamp)?;
+
+    Ok(record)
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use crate::{SqsConfig, SqsInputFormat, INPUT_FORMAT_FN_NAME_DEFAULT, SOURCE_CHANNEL_SIZE};
+
+#[derive(Debug)]
+pub struct SqsInputFormatBuilder {
+    fn_name: Option<String>,
+    parallelism: u16,
+    config: SqsConfig,
+    visibility_timeout: Duration,
+    wait_time: Duration,
+    buffer_size: Option<usize>,
+}
+
+impl SqsInputFormatBuilder {
+    pub fn new(config: SqsConfig, parallelism: u16) -> Self {
+        SqsInputFormatBuilder {
+            fn_name: None,
+            parallelism,
+            config,
+            visibility_timeout: Duration::from_secs(60),
+            wait_time: Duration::from_secs(20),
+            buffer_size: None,
+        }
+    }
+
+    pub fn fn_name(mut self, name: &str) -> Self {
+        self.fn_name = Some(name.to_string());
+        self
+    }
+
+    /// the visibility timeout of the received messages, extended until the messages are deleted,
+    /// default 60s, must be at least 1s
+    pub fn visibility_timeout(mut self, visibility_timeout: Duration) -> Self {
+        self.visibility_timeout = visibility_timeout;
+        self
+    }
+
+    /// the long polling wait time of receiving the messages, max 20s
+    pub fn wait_time(mut self, wait_time: Duration) -> Self {
+        self.wait_time = wait_time.min(Duration::from_secs(20));
+        self
+    }
+
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<SqsInputFormat> {
+        info!("build sqs source with: {:?}", &self);
+
+        // the timeout is in seconds, a zero timeout spins the thread extending the visibility
+        if self.visibility_timeout.as_secs() == 0 {
+            return Err(anyhow!(
+                "the visibility timeout must be at least 1s, got {:?}",
+                self.visibility_timeout
+            ));
+        }
+
+        Ok(SqsInputFormat::new(
+            self.config,
+            self.visibility_timeout,
+            self.wait_time,
+            self.buffer_size.unwrap_or(SOURCE_CHANNEL_SIZE),
+            self.parallelism,
+            self.fn_name
+                .unwrap_or_else(|| INPUT_FORMAT_FN_NAME_DEFAULT.to_string()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::source::builder::SqsInputFormatBuilder;
+    use crate::{AwsCredentials, SqsConfig};
+
+    #[test]
+    pub fn visibility_timeout_test() {
+        let config = SqsConfig::new("us-east-1", "queue", AwsCredentials::new("ak", "sk"));
+
+        let builder = SqsInputFormatBuilder::new(config.clone(), 1)
+            .visibility_timeout(Duration::from_millis(500));
+        assert!(builder.build().is_err());
+
+        let builder =
+            SqsInputFormatBuilder::new(config, 1).visibility_timeout(Duration::from_secs(1));
+        assert!(builder.build().is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct InFlightInner {
+    /// the receipt handles of all messages received and not deleted, and the timestamp
+    /// the message becomes visible to the other consumers again
+    visible_at: HashMap<String, u64>,
+    /// the messages emitted after the latest checkpoint
+    current: Vec<String>,
+    /// the messages emitted before the checkpoints, by the checkpoint id
+    checkpoints: BTreeMap<u64, Vec<String>>,
+}
+
+/// The messages received from the queue and not deleted. A message is deleted after the
+/// checkpoint following its emit is completed, its visibility timeout is extended until then.
+#[derive(Clone, Default)]
+pub(crate) struct InFlightMessages {
+    inner: Arc<Mutex<InFlightInner>>,
+}
+
+impl InFlightMessages {
+    pub fn receive(&self, receipt_handle: &str, visible_at: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .visible_at
+            .insert(receipt_handle.to_string(), visible_at);
+    }
+
+    pub fn emit(&self, receipt_handle: String) {
+        self.inner.lock().unwrap().current.push(receipt_handle);
+    }
+
+    /// the messages emitted before the `checkpoint_id` are deleted when it's completed,
+    /// return the messages of the checkpoints not completed
+    pub fn snapshot(&self, checkpoint_id: u64) -> BTreeMap<u64, Vec<String>> {
+        let mut inner = self.inner.lock().unwrap();
+        let current = std::mem::take(&mut inner.current);
+        if !current.is_empty() {
+            inner
+                .checkpoints
+                .entry(checkpoint_id)
+                .or_default()
+                .extend(current);
+        }
+        inner.checkpoints.clone()
+    }
+
+    /// take the messages can be deleted when the `checkpoint_id` is completed
+    pub fn complete(&self, checkpoint_id: u64) -> Vec<String> {
+        let mut inner = self.inner.lock().unwrap();
+        let pending = inner.checkpoints.split_off(&(checkpoint_id + 1));
+        let completed = std::mem::replace(&mut inner.checkpoints, pending);
+        let handles: Vec<String> = completed.into_values().flatten().collect();
+        for handle in &handles {
+            inner.visible_at.remove(handle);
+        }
+        handles
+    }
+
+    /// the messages become visible before `deadline`, their visibility timeout is extended
+    /// to `visible_at`
+    pub fn extend(&self, deadline: u64, visible_at: u64) -> Vec<String> {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .visible_at
+            .iter_mut()
+            .filter(|(_, x)| **x < deadline)
+            .map(|(handle, x)| {
+                *x = visible_at;
+                handle.clone()
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().visible_at.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::in_flight::InFlightMessages;
+
+    #[test]
+    pub fn in_flight_messages_test() {
+        let in_flight = InFlightMessages::default();
+        for (handle, visible_at) in [("a", 100), ("b", 100), ("c", 200)].iter() {
+            in_flight.receive(handle, *visible_at);
+        }
+
+        in_flight.emit("a".to_string());
+        in_flight.snapshot(1);
+        in_flight.emit("b".to_string());
+        assert_eq!(in_flight.snapshot(2).len(), 2);
+
+        // the received but not emitted messages are extended too
+        let mut extended = in_flight.extend(150, 300);
+        extended.sort();
+        assert_eq!(extended, vec!["a".to_string(), "b".to_string()]);
+
+        assert_eq!(in_flight.complete(1), vec!["a".to_string()]);
+        assert_eq!(in_flight.len(), 2);
+        assert!(in_flight.complete(1).is_empty());
+    }
+}
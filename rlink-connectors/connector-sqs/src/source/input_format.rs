@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use rlink::channel::utils::handover::Handover;
+use rlink::core;
+use rlink::core::cancellation::CancellationToken;
+use rlink::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{Context, InputFormat, InputSplit, InputSplitSource, NamedFunction};
+use rlink::core::runtime::CheckpointId;
+use rlink::utils::date_time::current_timestamp_millis;
+use rlink::utils::thread::async_runtime_single;
+use tokio::runtime::Handle;
+
+use crate::client::{SqsClient, SqsConfig, MAX_BATCH_SIZE};
+use crate::source::in_flight::InFlightMessages;
+use crate::source::iterator::SqsRecordIterator;
+use crate::source::ReceivedMessage;
+use crate::{build_sqs_record, sqs_message_schema};
+
+/// the interval of retrying after the receiving error
+const RETRY_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Consume the messages of a SQS queue at-least-once. The received messages are emitted and
+/// their receipt handles are held in the state, they are deleted only after the checkpoint
+/// containing them is completed, and their visibility timeout is extended meanwhile, so the
+/// messages not checkpointed are redelivered after a failover. The receipt handles of the
+/// completed checkpoint are deleted on the restore, in case the task fails before deleting.
+/// The tasks of a parallel source compete for the messages of the queue.
+pub struct SqsInputFormat {
+    name: String,
+    parallelism: u16,
+
+    config: SqsConfig,
+    visibility_timeout: Duration,
+    wait_time: Duration,
+    buffer_size: usize,
+
+    client: Option<SqsClient>,
+    handover: Option<Handover<ReceivedMessage>>,
+    in_flight: InFlightMessages,
+    /// stop the receiving and the visibility threads on the task's cancellation or the closing
+    shutdown: CancellationToken,
+}
+
+impl SqsInputFormat {
+    pub fn new(
+        config: SqsConfig,
+        visibility_timeout: Duration,
+        wait_time: Duration,
+        buffer_size: usize,
+        parallelism: u16,
+        fn_name: String,
+    ) -> Self {
+        SqsInputFormat {
+            name: fn_name,
+            parallelism,
+            config,
+            visibility_timeout,
+            wait_time,
+            buffer_size,
+            client: None,
+            handover: None,
+            in_flight: InFlightMessages::default(),
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    fn receive(&self, handover: Handover<ReceivedMessage>, runtime: Handle) {
+        let client = SqsClient::new(self.config.clone(), runtime);
+        let in_flight = self.in_flight.clone();
+        let wait_time = self.wait_time;
+        let visibility_timeout = self.visibility_timeout;
+        let shutdown = self.shutdown.clone();
+
+        rlink::utils::thread::spawn("sqs-receiver", move || {
+            while !shutdown.is_cancelled() {
+                let messages =
+                    match client.receive_messages(MAX_BATCH_SIZE, wait_time, visibility_timeout) {
+                        Ok(messages) => messages,
+                        Err(e) => {
+                            error!("sqs receive messages error, retry later. {}", e);
+                            shutdown.wait_timeout(RETRY_INTERVAL);
+                            continue;
+                        }
+                    };
+
+                let visible_at = current_timestamp_millis() + visibility_timeout.as_millis() as u64;
+                for message in messages {
+                    // the message is redelivered after the visibility timeout
+                    let record = match build_sqs_record(
+                        message.message_id.as_str(),
+                        message.body.as_str(),
+                        message.sent_timestamp(),
+                    ) {
+                        Ok(record) => record,
+                        Err(e) => {
+                            error!(
+                                "build the record of sqs message {} error, skip it. {}",
+                                message.message_id, e
+                            );
+                            continue;
+                        }
+                    };
+
+                    in_flight.receive(message.receipt_handle.as_str(), visible_at);
+                    let message = ReceivedMessage::new(record, message.receipt_handle);
+                    if !handover.produce_cancellable(message, &shutdown) {
+                        info!("sqs source is closed, stop receiving");
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    /// extend the visibility timeout of the messages becoming visible in half of the timeout
+    fn keep_invisible(&self, runtime: Handle) {
+        let client = SqsClient::new(self.config.clone(), runtime);
+        let in_flight = self.in_flight.clone();
+        let visibility_timeout = self.visibility_timeout;
+        let interval = visibility_timeout / 4;
+        let shutdown = self.shutdown.clone();
+
+        rlink::utils::thread::spawn("sqs-visibility", move || {
+            while !shutdown.wait_timeout(interval) {
+                let now = current_timestamp_millis();
+                let timeout = visibility_timeout.as_millis() as u64;
+                let handles = in_flight.extend(now + timeout / 2, now + timeout);
+                if handles.is_empty() {
+                    continue;
+                }
+                match client.change_visibility(handles.as_slice(), visibility_timeout) {
+                    Ok(failed) => debug!(
+                        "extend the visibility of {} sqs messages, {} failed",
+                        handles.len(),
+                        failed
+                    ),
+                    Err(e) => error!("extend the visibility of sqs messages error. {}", e),
+                }
+            }
+        });
+    }
+
+    fn delete(&self, receipt_handles: Vec<String>) {
+        if receipt_handles.is_empty() {
+            return;
+        }
+
+        let client = self.client.as_ref().unwrap();
+        match client.delete_messages(receipt_handles.as_slice()) {
+            Ok(failed) => info!(
+                "delete {} sqs messages, {} failed, {} in flight",
+                receipt_handles.len(),
+                failed,
+                self.in_flight.len()
+            ),
+            // the messages are redelivered after the visibility timeout
+            Err(e) => error!("delete sqs messages error. {}", e),
+        }
+    }
+}
+
+impl NamedFunction for SqsInputFormat {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl InputFormat for SqsInputFormat {
+    fn open(&mut self, _input_split: InputSplit, context: &Context) -> core::Result<()> {
+        info!("sqs source open, queue: {}", self.config.queue_url);
+        let runtime = context.async_runtime();
+        self.client = Some(SqsClient::new(self.config.clone(), runtime.clone()));
+        self.shutdown = context.cancellation_token.child();
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle);
+
+        let handover = Handover::new(
+            "SqsSource_Handover",
+            context.task_id.to_tags(),
+            self.buffer_size,
+        );
+        self.receive(handover.clone(), runtime.clone());
+        self.keep_invisible(runtime);
+
+        self.handover = Some(handover);
+        Ok(())
+    }
+
+    fn record_iter(&mut self) -> Box<dyn Iterator<Item = Record> + Send> {
+        let handover = self.handover.as_ref().unwrap().clone();
+        Box::new(SqsRecordIterator::new(handover, self.in_flight.clone()))
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        self.shutdown.cancel("sqs source closed");
+        Ok(())
+    }
+
+    fn probe(&self) -> core::Result<()> {
+        // probed out of the tasks, no shared runtime
+        let runtime = async_runtime_single();
+        SqsClient::new(self.config.clone(), runtime.handle().clone()).probe()?;
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&sqs_message_schema())
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+impl CheckpointFunction for SqsInputFormat {
+    fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        let handle = match handle {
+            Some(handle) if !handle.handle.is_empty() => handle,
+            _ => return,
+        };
+
+        let pending: BTreeMap<u64, Vec<String>> =
+            serde_json::from_str(handle.handle.as_str()).unwrap();
+        let receipt_handles: Vec<String> = pending
+            .range(..=context.checkpoint_id.0)
+            .flat_map(|(_, x)| x.iter().cloned())
+            .collect();
+        info!(
+            "delete {} sqs messages of the restored checkpoint {:?}",
+            receipt_handles.len(),
+            context.checkpoint_id
+        );
+        self.delete(receipt_handles);
+    }
+
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        let pending = self.in_flight.snapshot(context.checkpoint_id.0);
+        Some(Box::new(move || {
+            let handle = serde_json::to_string(&pending)?;
+            Ok(CheckpointHandle { handle })
+        }))
+    }
+
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        let receipt_handles = self.in_flight.complete(checkpoint_id.0);
+        self.delete(receipt_handles);
+    }
+}
+
+impl InputSplitSource for SqsInputFormat {}
@@ -0,0 +1,37 @@
+use rlink::channel::utils::handover::Handover;
+use rlink::core::element::Record;
+
+use crate::source::in_flight::InFlightMessages;
+use crate::source::ReceivedMessage;
+
+/// Simulate the queue consumption as an iterator, the emitted messages are deleted after
+/// the checkpoint
+pub struct SqsRecordIterator {
+    handover: Handover<ReceivedMessage>,
+    in_flight: InFlightMessages,
+}
+
+impl SqsRecordIterator {
+    pub(crate) fn new(handover: Handover<ReceivedMessage>, in_flight: InFlightMessages) -> Self {
+        SqsRecordIterator {
+            handover,
+            in_flight,
+        }
+    }
+}
+
+impl Iterator for SqsRecordIterator {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.handover.poll_next() {
+            Ok(message) => {
+                self.in_flight.emit(message.receipt_handle);
+                Some(message.record)
+            }
+            Err(_e) => {
+                panic!("sqs input recv channel disconnected");
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use rlink::core::element::Record;
+
+pub mod builder;
+pub mod in_flight;
+pub mod input_format;
+pub mod iterator;
+
+/// the record of a message and the receipt handle to delete it
+#[derive(Clone, Debug)]
+pub(crate) struct ReceivedMessage {
+    record: Record,
+    receipt_handle: String,
+}
+
+impl ReceivedMessage {
+    pub fn new(record: Record, receipt_handle: String) -> Self {
+        ReceivedMessage {
+            record,
+            receipt_handle,
+        }
+    }
+}
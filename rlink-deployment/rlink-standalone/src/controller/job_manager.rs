@@ -1,18 +1,22 @@
+use std::collections::HashMap;
 use std::io::Write;
+use std::time::{Duration, Instant};
 
 use actix_multipart::Multipart;
 use actix_web::http::StatusCode;
 use actix_web::web::{Data, Path};
 use actix_web::{web, Error, HttpRequest, HttpResponse};
 use futures::{StreamExt, TryStreamExt};
+use rlink::core::checkpoint::{SavepointPath, SavepointStatus};
 use rlink::core::cluster::{
     BatchExecuteRequest, ExecuteRequest, ResponseCode, StdResponse, TaskResourceInfo,
 };
+use rlink::core::runtime::CheckpointId;
 
 use crate::config::Context;
-use crate::controller::get_resource_storage_path;
 use crate::controller::HttpClientError;
-use crate::job::{Application, Status};
+use crate::controller::{get_resource_storage_path, get_resource_storage_root};
+use crate::job::{scheduler, Application, Status};
 use crate::utils::current_timestamp_millis;
 
 #[derive(Deserialize)]
@@ -65,24 +69,186 @@ pub async fn create_application(
     Ok(HttpResponse::Ok().json(application_id))
 }
 
+#[derive(Serialize)]
+pub struct DistributeResult {
+    task_manager: String,
+    /// the path of the executable file on the TaskManager
+    path: Option<String>,
+    error: Option<String>,
+}
+
+/// distribute the executable file of the application to all TaskManagers, they download it from
+/// the JobManager and launch the tasks with it if the `task_launcher` is `Process`. the tasks
+/// download it on launching if it's not distributed
+pub async fn distribute_application(
+    application_id: Path<String>,
+    context: Data<Context>,
+) -> Result<HttpResponse, Error> {
+    let application_id = application_id.as_str();
+    let job = Application::load(get_resource_storage_path(application_id))?;
+
+    let mut results = Vec::with_capacity(context.task_managers.len());
+    for task_manager in &context.task_managers {
+        let result = prefetch_artifact(
+            application_id,
+            job.execute_file.as_str(),
+            task_manager.as_str(),
+        )
+        .await;
+        let (path, error) = match result {
+            Ok(path) => (Some(path), None),
+            Err(e) => {
+                error!(
+                    "distribute application {} to {} error. {}",
+                    application_id, task_manager, e
+                );
+                (None, Some(e.to_string()))
+            }
+        };
+        results.push(DistributeResult {
+            task_manager: task_manager.clone(),
+            path,
+            error,
+        });
+    }
+
+    let response = StdResponse {
+        code: ResponseCode::OK,
+        data: Some(results),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+async fn prefetch_artifact(
+    application_id: &str,
+    file_name: &str,
+    task_manager_address: &str,
+) -> Result<String, HttpClientError> {
+    let url = format!(
+        "http://{}:8771/task/{}/artifact",
+        task_manager_address, application_id
+    );
+    let mut response = actix_web::client::Client::default()
+        .post(url.as_str())
+        .header("Accept", "application/json")
+        .header("Content-type", "application/json")
+        // the download of a large file may be slow
+        .timeout(Duration::from_secs(600))
+        .send_json(&serde_json::json!({ "file_name": file_name }))
+        .await
+        .map_err(|e| HttpClientError::from(e))?;
+
+    let result_model = response
+        .json::<StdResponse<String>>()
+        .await
+        .map_err(|e| HttpClientError::from(e))?;
+    match result_model.code {
+        ResponseCode::OK => Ok(result_model.data.unwrap_or("".to_string())),
+        ResponseCode::ERR(msg) => Err(HttpClientError::from(msg)),
+    }
+}
+
+lazy_static! {
+    /// serialize the scheduling, the slots are shared by all applications in the session
+    static ref SCHEDULE_LOCK: futures::lock::Mutex<()> = futures::lock::Mutex::new(());
+}
+
+fn err_response(msg: String) -> HttpResponse {
+    let response: StdResponse<String> = StdResponse {
+        code: ResponseCode::ERR(msg),
+        data: None,
+    };
+    HttpResponse::Ok().json(response)
+}
+
+/// take the scheduling arguments, they are not passed to the tasks
+fn take_arg<T: std::str::FromStr>(
+    batch_args: &mut Vec<HashMap<String, String>>,
+    key: &str,
+) -> Result<Option<T>, String> {
+    let mut value = None;
+    for args in batch_args {
+        if let Some(v) = args.remove(key) {
+            value = Some(v);
+        }
+    }
+
+    match value {
+        Some(v) => v
+            .parse::<T>()
+            .map(|v| Some(v))
+            .map_err(|_e| format!("illegal argument `{}`={}", key, v)),
+        None => Ok(None),
+    }
+}
+
 pub async fn submit_job(
     application_id: Path<String>,
     batch_execute_model: web::Json<BatchExecuteRequest>,
     context: Data<Context>,
 ) -> Result<HttpResponse, Error> {
-    let application_id = application_id.as_str();
+    if context.task_managers.len() == 0 {
+        return Ok(HttpResponse::Ok().json("No TaskManager"));
+    }
+
+    let task_ids = match schedule_tasks(
+        application_id.as_str(),
+        batch_execute_model.into_inner(),
+        &context,
+    )
+    .await?
+    {
+        Ok(task_ids) => task_ids,
+        Err(e) => return Ok(err_response(e)),
+    };
+
+    let response_json = serde_json::to_string(&task_ids).unwrap();
+    info!("response task resource infos: {}", &response_json);
+
+    let response = StdResponse {
+        code: ResponseCode::OK,
+        data: Some(response_json),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// publish the tasks to the TaskManagers with free slots,
+/// the inner error is the reason of the rejected scheduling
+async fn schedule_tasks(
+    application_id: &str,
+    mut batch_execute_model: BatchExecuteRequest,
+    context: &Context,
+) -> Result<Result<Vec<TaskResourceInfo>, String>, Error> {
+    // the task quota and the free slots are checked against the scheduled tasks
+    let _schedule_guard = SCHEDULE_LOCK.lock().await;
 
     let storage_path = get_resource_storage_path(application_id);
     let job = Application::load(storage_path.clone())?;
-    if job.status == Status::Killed {
-        let response: StdResponse<String> = StdResponse {
-            code: ResponseCode::ERR("Job has killed".to_string()),
-            data: None,
-        };
-        return Ok(HttpResponse::Ok().json(response));
+    if job.status == Status::Killed || job.status == Status::Upgraded {
+        return Ok(Err(format!("Job has {:?}", job.status)));
     }
 
-    let batch_execute_model = batch_execute_model.into_inner();
+    let excluded_task_managers: Vec<String> = match take_arg::<String>(
+        &mut batch_execute_model.batch_args,
+        "excluded_task_managers",
+    ) {
+        Ok(excluded) => excluded
+            .map(|x| x.split(',').map(|x| x.to_string()).collect())
+            .unwrap_or_default(),
+        Err(e) => return Ok(Err(e)),
+    };
+
+    let (priority, task_quota) = match (
+        take_arg::<i32>(&mut batch_execute_model.batch_args, "priority"),
+        take_arg::<usize>(&mut batch_execute_model.batch_args, "task_quota"),
+    ) {
+        (Ok(priority), Ok(task_quota)) => (
+            priority.unwrap_or(job.priority),
+            task_quota.or(job.task_quota),
+        ),
+        (Err(e), _) | (_, Err(e)) => return Ok(Err(e)),
+    };
+
     // let executable_file = batch_execute_model.executable_file.as_str();
     let execute_models: Vec<ExecuteRequest> = batch_execute_model
         .batch_args
@@ -93,38 +259,126 @@ pub async fn submit_job(
         })
         .collect();
 
-    if context.task_managers.len() == 0 {
-        return Ok(HttpResponse::Ok().json("No TaskManager"));
+    if let Some(task_quota) = task_quota {
+        if job.tasks.len() + execute_models.len() > task_quota {
+            return Ok(Err(format!(
+                "exceed the task quota {}, running tasks {}, new tasks {}",
+                task_quota,
+                job.tasks.len(),
+                execute_models.len()
+            )));
+        }
     }
 
+    let mut free_slots = match context.config.task_manager_slots {
+        Some(slots) => {
+            let applications = Application::list(get_resource_storage_root())?;
+            let free_slots = scheduler::free_slots(&context.task_managers, &applications, slots);
+
+            let free: usize = free_slots.values().sum();
+            if free < execute_models.len() {
+                if !context.config.preemption {
+                    return Ok(Err(format!(
+                        "No enough slots, free {}, required {}",
+                        free,
+                        execute_models.len()
+                    )));
+                }
+
+                let preempted = scheduler::select_preempted(
+                    &applications,
+                    application_id,
+                    priority,
+                    execute_models.len(),
+                    free,
+                );
+                if preempted.is_empty() {
+                    return Ok(Err(format!(
+                        "No enough slots even preempt the lower priority applications, free {}, required {}",
+                        free,
+                        execute_models.len()
+                    )));
+                }
+
+                for application in preempted {
+                    info!(
+                        "preempt application {}(priority={}) for {}(priority={})",
+                        application.application_id, application.priority, application_id, priority
+                    );
+                    stop_application(
+                        application.application_id.as_str(),
+                        Status::Preempted,
+                        &context.task_managers,
+                    )
+                    .await?;
+                }
+
+                let applications = Application::list(get_resource_storage_root())?;
+                Some(scheduler::free_slots(
+                    &context.task_managers,
+                    &applications,
+                    slots,
+                ))
+            } else {
+                Some(free_slots)
+            }
+        }
+        None => None,
+    };
+
     let mut task_ids = Vec::new();
-    let mut start_index = 0;
+    let mut next_index = 0;
     for execute_model in &execute_models {
-        let (task_result_info, index) = publish_task_0(
+        // only the TaskManagers with free slots are candidates
+        let task_managers = scheduler::candidate_task_managers(
+            &context.task_managers,
+            free_slots.as_ref(),
+            &excluded_task_managers,
+        );
+        if task_managers.is_empty() {
+            return Ok(Err("No TaskManager with free slot".to_string()));
+        }
+
+        let (task_result_info, _) = publish_task_0(
             application_id,
             execute_model,
-            start_index,
-            &context.task_managers,
+            scheduler::round_robin_start(&context.task_managers, &task_managers, next_index),
+            &task_managers,
         )
         .await?;
 
-        start_index = if index == context.task_managers.len() {
-            0
-        } else {
-            index + 1
-        };
+        let task_manager = task_result_info.task_manager_address().unwrap();
+        next_index = context
+            .task_managers
+            .iter()
+            .position(|x| x.eq(task_manager))
+            .map(|n| n + 1)
+            .unwrap_or(0);
+        if let Some(free_slots) = free_slots.as_mut() {
+            if let Some(n) = free_slots.get_mut(task_manager) {
+                *n = n.saturating_sub(1);
+            }
+        }
 
         task_ids.push(task_result_info);
     }
 
-    let response_json = serde_json::to_string(&task_ids).unwrap();
-    info!("response task resource infos: {}", &response_json);
+    // the worker allocation from the coordinator carries the coordinator's address
+    let coordinator_address = batch_execute_model
+        .batch_args
+        .iter()
+        .find_map(|args| args.get("coordinator_address").map(|x| x.clone()));
+    Application::update(storage_path, |job| {
+        job.status = Status::Running;
+        job.priority = priority;
+        job.task_quota = task_quota;
+        if coordinator_address.is_some() {
+            job.coordinator_address = coordinator_address;
+        }
+        job.tasks.extend(task_ids.iter().map(|x| x.clone()));
+    })?;
 
-    let response = StdResponse {
-        code: ResponseCode::OK,
-        data: Some(response_json),
-    };
-    Ok(HttpResponse::Ok().json(response))
+    Ok(Ok(task_ids))
 }
 
 async fn publish_task_0(
@@ -210,8 +464,9 @@ pub async fn shutdown_tasks(
     _context: Data<Context>,
 ) -> Result<HttpResponse, Error> {
     let application_id = application_id.as_str();
+    let task_ids = task_ids.into_inner();
 
-    for task_res_info in &task_ids.into_inner() {
+    for task_res_info in &task_ids {
         let task_id = task_res_info.task_id().unwrap(); //.ok_or(anyhow!("task_id not found"))?;
         let task_manager_address = task_res_info.task_manager_address().unwrap();
 
@@ -228,6 +483,11 @@ pub async fn shutdown_tasks(
         }
     }
 
+    let storage_path = get_resource_storage_path(application_id);
+    Application::update(storage_path, |job| {
+        job.tasks.retain(|task| !task_ids.contains(task));
+    })?;
+
     let response: StdResponse<String> = StdResponse {
         code: ResponseCode::OK,
         data: None,
@@ -266,15 +526,12 @@ pub async fn kill_job(
     application_id: Path<String>,
     context: Data<Context>,
 ) -> Result<HttpResponse, Error> {
-    let storage_path = get_resource_storage_path(application_id.as_str());
-    let mut job = Application::load(storage_path.clone())?;
-    job.status = Status::Killed;
-
-    job.storage(storage_path)?;
-
-    for task_manager in &context.task_managers {
-        kill_job_task(application_id.as_str(), task_manager.as_str()).await?;
-    }
+    stop_application(
+        application_id.as_str(),
+        Status::Killed,
+        &context.task_managers,
+    )
+    .await?;
 
     let response: StdResponse<String> = StdResponse {
         code: ResponseCode::OK,
@@ -283,6 +540,29 @@ pub async fn kill_job(
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// only the tasks of the application are killed, the other applications in the session
+/// and the TaskManagers keep running
+async fn stop_application(
+    application_id: &str,
+    status: Status,
+    task_managers: &[String],
+) -> Result<(), Error> {
+    let storage_path = get_resource_storage_path(application_id);
+    Application::update(storage_path.clone(), |job| {
+        job.status = status;
+    })?;
+
+    for task_manager in task_managers {
+        kill_job_task(application_id, task_manager.as_str()).await?;
+    }
+
+    Application::update(storage_path, |job| {
+        job.tasks.clear();
+    })?;
+
+    Ok(())
+}
+
 async fn kill_job_task(
     application_id: &str,
     task_manager_address: &str,
@@ -308,3 +588,306 @@ async fn kill_job_task(
         ResponseCode::ERR(msg) => Err(HttpClientError::from(msg)),
     }
 }
+
+/// list all applications submitted to the session
+pub async fn list_applications(_context: Data<Context>) -> Result<HttpResponse, Error> {
+    let applications = Application::list(get_resource_storage_root())?;
+
+    let response = StdResponse {
+        code: ResponseCode::OK,
+        data: Some(applications),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+pub async fn get_application(
+    application_id: Path<String>,
+    _context: Data<Context>,
+) -> Result<HttpResponse, Error> {
+    let storage_path = get_resource_storage_root().join(application_id.as_str());
+    let job = Application::load(storage_path)?;
+
+    let response = StdResponse {
+        code: ResponseCode::OK,
+        data: Some(job),
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// the `ClusterDescriptor` of the application, served by its coordinator
+pub async fn get_application_cluster_metadata(
+    application_id: Path<String>,
+    _context: Data<Context>,
+) -> Result<HttpResponse, Error> {
+    proxy_coordinator_api(application_id.as_str(), "/api/cluster_metadata").await
+}
+
+/// the checkpoints of the application, served by its coordinator
+pub async fn get_application_checkpoints(
+    application_id: Path<String>,
+    _context: Data<Context>,
+) -> Result<HttpResponse, Error> {
+    proxy_coordinator_api(application_id.as_str(), "/api/checkpoints").await
+}
+
+async fn proxy_coordinator_api(application_id: &str, api: &str) -> Result<HttpResponse, Error> {
+    let storage_path = get_resource_storage_root().join(application_id);
+    let job = Application::load(storage_path)?;
+
+    let coordinator_address = match job.coordinator_address {
+        Some(coordinator_address) if job.status == Status::Running => coordinator_address,
+        _ => {
+            let response: StdResponse<String> = StdResponse {
+                code: ResponseCode::ERR("Coordinator is not running".to_string()),
+                data: None,
+            };
+            return Ok(HttpResponse::Ok().json(response));
+        }
+    };
+
+    let url = format!("{}{}", coordinator_address, api);
+    let mut response = actix_web::client::Client::default()
+        .get(url.as_str())
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| HttpClientError::from(e))?;
+    let body = response
+        .body()
+        .await
+        .map_err(|e| HttpClientError::from(e.to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(body))
+}
+
+/// the interval to poll the savepoint progress from the coordinator
+const UPGRADE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+fn default_upgrade_timeout_secs() -> u64 {
+    10 * 60
+}
+
+#[derive(Deserialize)]
+pub struct UpgradeRequest {
+    /// the application created with the new executable file
+    new_application_id: String,
+    /// the args to submit the new application, the `from_savepoint` arg is appended
+    batch_args: Vec<HashMap<String, String>>,
+    /// the timeout to wait the savepoint completed
+    #[serde(default = "default_upgrade_timeout_secs")]
+    savepoint_timeout_secs: u64,
+    /// the timeout to wait the first checkpoint of the new application completed
+    #[serde(default = "default_upgrade_timeout_secs")]
+    checkpoint_timeout_secs: u64,
+}
+
+#[derive(Serialize)]
+pub struct UpgradeResult {
+    application_id: String,
+    /// the savepoint that the new application restored from
+    savepoint: String,
+    /// the first completed checkpoint of the new application
+    checkpoint_id: CheckpointId,
+}
+
+/// upgrade a running application to the new application:
+/// 1. trigger a savepoint and wait it completed
+/// 2. stop the running application
+/// 3. submit the new application with `from_savepoint`
+/// 4. wait the first checkpoint of the new application completed
+pub async fn upgrade_application(
+    application_id: Path<String>,
+    upgrade_request: web::Json<UpgradeRequest>,
+    context: Data<Context>,
+) -> Result<HttpResponse, Error> {
+    let application_id = application_id.as_str();
+    match upgrade(application_id, upgrade_request.into_inner(), &context).await? {
+        Ok(upgrade_result) => {
+            info!(
+                "upgrade application {} to {} from savepoint {}",
+                application_id, upgrade_result.application_id, upgrade_result.savepoint
+            );
+            let response = StdResponse {
+                code: ResponseCode::OK,
+                data: Some(upgrade_result),
+            };
+            Ok(HttpResponse::Ok().json(response))
+        }
+        Err(e) => {
+            error!("upgrade application {} error. {}", application_id, e);
+            Ok(err_response(e))
+        }
+    }
+}
+
+async fn upgrade(
+    application_id: &str,
+    upgrade_request: UpgradeRequest,
+    context: &Context,
+) -> Result<Result<UpgradeResult, String>, Error> {
+    let storage_path = get_resource_storage_path(application_id);
+    let job = Application::load(storage_path.clone())?;
+    let coordinator_address = match job.coordinator_address {
+        Some(coordinator_address) if job.status == Status::Running => coordinator_address,
+        _ => return Ok(Err("the application is not running".to_string())),
+    };
+
+    let new_application_id = upgrade_request.new_application_id;
+    let new_job = Application::load(get_resource_storage_path(new_application_id.as_str()))?;
+    if new_job.status != Status::Ready {
+        return Ok(Err(format!(
+            "the new application {} has been submitted",
+            new_application_id
+        )));
+    }
+
+    let marked_id = match trigger_savepoint(coordinator_address.as_str()).await {
+        Ok(marked_id) => marked_id,
+        Err(e) => return Ok(Err(format!("trigger savepoint error. {}", e))),
+    };
+    let savepoint_timeout = Duration::from_secs(upgrade_request.savepoint_timeout_secs);
+    let savepoint_id = match wait_savepoint_status(
+        coordinator_address.as_str(),
+        Instant::now() + savepoint_timeout,
+        |status| status.savepoint_id.filter(|id| *id == marked_id),
+    )
+    .await
+    {
+        Ok(savepoint_id) => savepoint_id,
+        Err(e) => return Ok(Err(format!("wait savepoint error. {}", e))),
+    };
+
+    let savepoint = SavepointPath::new(application_id, savepoint_id).to_string();
+    info!(
+        "application {} savepoint {} completed",
+        application_id, savepoint
+    );
+
+    Application::update(storage_path, |job| {
+        job.savepoint = Some(savepoint.clone());
+    })?;
+    stop_application(application_id, Status::Upgraded, &context.task_managers).await?;
+
+    let mut batch_args = upgrade_request.batch_args;
+    for args in &mut batch_args {
+        args.insert("from_savepoint".to_string(), savepoint.clone());
+    }
+    let batch_execute_model = BatchExecuteRequest { batch_args };
+    if let Err(e) =
+        schedule_tasks(new_application_id.as_str(), batch_execute_model, context).await?
+    {
+        return Ok(Err(format!(
+            "submit the new application error, it can be restored from the savepoint {} manually. {}",
+            savepoint, e
+        )));
+    }
+
+    // the coordinator address is recorded when the new coordinator allocates the workers
+    let deadline = Instant::now() + Duration::from_secs(upgrade_request.checkpoint_timeout_secs);
+    let new_coordinator_address = loop {
+        let new_job = Application::load(get_resource_storage_path(new_application_id.as_str()))?;
+        if let Some(coordinator_address) = new_job.coordinator_address {
+            break coordinator_address;
+        }
+        if Instant::now() > deadline {
+            return Ok(Err(format!(
+                "the coordinator of the new application {} is not ready",
+                new_application_id
+            )));
+        }
+        actix_rt::time::delay_for(UPGRADE_POLL_INTERVAL).await;
+    };
+
+    let checkpoint_id =
+        match wait_savepoint_status(new_coordinator_address.as_str(), deadline, |status| {
+            status.completed_checkpoint_id
+        })
+        .await
+        {
+            Ok(checkpoint_id) => checkpoint_id,
+            Err(e) => {
+                return Ok(Err(format!(
+                    "the first checkpoint of the new application {} is not completed. {}",
+                    new_application_id, e
+                )))
+            }
+        };
+
+    Ok(Ok(UpgradeResult {
+        application_id: new_application_id,
+        savepoint,
+        checkpoint_id,
+    }))
+}
+
+/// poll the savepoint progress until `f` returns a `CheckpointId` or timeout,
+/// the request errors are retried because the coordinator may be starting
+async fn wait_savepoint_status<F>(
+    coordinator_address: &str,
+    deadline: Instant,
+    f: F,
+) -> Result<CheckpointId, String>
+where
+    F: Fn(&SavepointStatus) -> Option<CheckpointId>,
+{
+    loop {
+        match get_savepoint_status(coordinator_address).await {
+            Ok(status) => {
+                if let Some(checkpoint_id) = f(&status) {
+                    return Ok(checkpoint_id);
+                }
+            }
+            Err(e) => warn!(
+                "get savepoint status from {} error. {}",
+                coordinator_address, e
+            ),
+        }
+
+        if Instant::now() > deadline {
+            return Err("timeout".to_string());
+        }
+        actix_rt::time::delay_for(UPGRADE_POLL_INTERVAL).await;
+    }
+}
+
+async fn trigger_savepoint(coordinator_address: &str) -> Result<CheckpointId, HttpClientError> {
+    let url = format!("{}/api/savepoint", coordinator_address);
+    let mut response = actix_web::client::Client::default()
+        .post(url.as_str())
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| HttpClientError::from(e))?;
+
+    let result_model = response
+        .json::<StdResponse<CheckpointId>>()
+        .await
+        .map_err(|e| HttpClientError::from(e))?;
+    match result_model.code {
+        ResponseCode::OK => Ok(result_model.data.unwrap_or_default()),
+        ResponseCode::ERR(msg) => Err(HttpClientError::from(msg)),
+    }
+}
+
+async fn get_savepoint_status(
+    coordinator_address: &str,
+) -> Result<SavepointStatus, HttpClientError> {
+    let url = format!("{}/api/savepoint", coordinator_address);
+    let mut response = actix_web::client::Client::default()
+        .get(url.as_str())
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| HttpClientError::from(e))?;
+
+    let result_model = response
+        .json::<StdResponse<SavepointStatus>>()
+        .await
+        .map_err(|e| HttpClientError::from(e))?;
+    match result_model.code {
+        ResponseCode::OK => Ok(result_model.data.unwrap_or_default()),
+        ResponseCode::ERR(msg) => Err(HttpClientError::from(msg)),
+    }
+}
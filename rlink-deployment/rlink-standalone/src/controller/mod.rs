@@ -7,8 +7,14 @@ use crate::utils::get_work_space;
 pub mod job_manager;
 pub mod task_manager;
 
+fn get_resource_storage_root() -> PathBuf {
+    let p = get_work_space().join("download");
+    DirBuilder::new().recursive(true).create(p.clone()).unwrap();
+    p
+}
+
 fn get_resource_storage_path(application_id: &str) -> PathBuf {
-    let p = get_work_space().join("download").join(application_id);
+    let p = get_resource_storage_root().join(application_id);
     DirBuilder::new().recursive(true).create(p.clone()).unwrap();
     p
 }
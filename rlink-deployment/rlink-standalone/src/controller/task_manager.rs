@@ -1,17 +1,22 @@
 use std::collections::HashMap;
-use std::fs::{remove_file, DirBuilder};
+use std::fs::{remove_file, DirBuilder, File};
+use std::io::Write;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use actix_web::web::{Data, Path};
 use actix_web::{web, Error, HttpResponse};
-use rlink::core::cluster::{ExecuteRequest, ResponseCode, StdResponse};
+use rlink::core::cluster::{ExecuteRequest, ResponseCode, StdResponse, TaskLauncher};
 
 use crate::config::Context;
+use crate::controller::HttpClientError;
 use crate::utils::{current_timestamp_millis, read_file_as_lines};
 use rlink::utils::process::work_space;
 
+/// the max size of the executable file downloaded from the JobManager
+const MAX_ARTIFACT_SIZE: usize = 2 * 1024 * 1024 * 1024;
+
 lazy_static! {
     static ref TASK_MANAGER_START_TIME: u64 = current_timestamp_millis();
     static ref TASK_COUNTER: AtomicU64 = AtomicU64::new(0);
@@ -78,7 +83,35 @@ pub async fn execute_task(
         .create(task_work_space.clone())
         .unwrap();
 
-    execute_shell_command(execute_args, envs, script_path.as_str(), task_work_space);
+    match context.config.task_launcher {
+        TaskLauncher::Script => {
+            execute_shell_command(execute_args, envs, script_path.as_str(), task_work_space)
+        }
+        TaskLauncher::Process => {
+            let executable_file = envs.get("FILE_NAME").unwrap();
+            let executable_path =
+                match fetch_artifact(&context, application_id, executable_file).await {
+                    Ok(executable_path) => executable_path,
+                    Err(e) => {
+                        let response: StdResponse<String> = StdResponse {
+                            code: ResponseCode::ERR(format!("fetch executable file error. {}", e)),
+                            data: None,
+                        };
+                        return Ok(HttpResponse::Ok().json(response));
+                    }
+                };
+
+            let mut args = vec![
+                format!("cluster_config={}", envs.get("CLUSTER_CONFIG").unwrap()),
+                format!("application_id={}", application_id),
+                format!("task_id={}", task_id),
+                format!("bind_ip={}", envs.get("BIND_IP").unwrap()),
+                format!("dashboard_path={}", dashboard_path),
+            ];
+            args.extend(execute_args);
+            launch_process(executable_path, args, task_work_space)?;
+        }
+    }
 
     let response = StdResponse {
         code: ResponseCode::OK,
@@ -108,6 +141,145 @@ fn execute_shell_command(
         .unwrap();
 }
 
+/// the executable file of the application cached by the TaskManager, shared by the tasks
+fn artifact_path(context: &Context, application_id: &str, file_name: &str) -> PathBuf {
+    PathBuf::from(context.config.task_manager_work_dir.as_str())
+        .join(application_id)
+        .join("artifact")
+        .join(sanitize_filename::sanitize(file_name))
+}
+
+/// download the executable file from the JobManager if it's not cached
+async fn fetch_artifact(
+    context: &Context,
+    application_id: &str,
+    file_name: &str,
+) -> Result<PathBuf, HttpClientError> {
+    let path = artifact_path(context, application_id, file_name);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let url = format!(
+        "{}/job/resource/{}/{}",
+        context.config.application_manager_address[0], application_id, file_name
+    );
+    info!("download executable file from {}", url);
+    let mut response = actix_web::client::Client::default()
+        .get(url.as_str())
+        .send()
+        .await
+        .map_err(|e| HttpClientError::from(e))?;
+    if !response.status().is_success() {
+        return Err(HttpClientError::from(format!(
+            "download {} error, status {}",
+            url,
+            response.status()
+        )));
+    }
+    let body = response
+        .body()
+        .limit(MAX_ARTIFACT_SIZE)
+        .await
+        .map_err(|e| HttpClientError::from(e.to_string()))?;
+
+    // the concurrent tasks of the application download to their own temp file,
+    // the rename is atomic so a partial file is never executed
+    let tmp_path = path.with_extension(format!(
+        "{}.tmp",
+        TASK_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let target_path = path.clone();
+    web::block(move || -> std::io::Result<()> {
+        DirBuilder::new()
+            .recursive(true)
+            .create(target_path.parent().unwrap())?;
+        let mut f = File::create(tmp_path.as_path())?;
+        f.write_all(body.as_ref())?;
+        f.sync_all()?;
+        set_executable(tmp_path.as_path())?;
+        std::fs::rename(tmp_path, target_path)
+    })
+    .await
+    .map_err(|e| HttpClientError::from(e.to_string()))?;
+
+    Ok(path)
+}
+
+#[cfg(unix)]
+fn set_executable(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// launch the task in a child process with the same layout of the `run_task.sh`,
+/// the `stdout`, `stderr` and `pid` files are in the task's workspace
+fn launch_process(
+    executable_path: PathBuf,
+    args: Vec<String>,
+    work_space: PathBuf,
+) -> std::io::Result<()> {
+    info!(
+        "launch process: {}, workspace: {}",
+        executable_path.to_str().unwrap(),
+        work_space.to_str().unwrap()
+    );
+
+    let mut child = Command::new(executable_path)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(File::create(work_space.join("stdout"))?)
+        .stderr(File::create(work_space.join("stderr"))?)
+        .current_dir(work_space.clone())
+        .spawn()?;
+
+    let mut pid_file = File::create(work_space.join("pid"))?;
+    writeln!(pid_file, "{}", child.id())?;
+
+    // reap the child process once it exits, the pid file is left for the `kill_task`
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) => info!("task process {} exit, {}", child.id(), status),
+        Err(e) => error!("wait task process error. {}", e),
+    });
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct ArtifactModel {
+    file_name: String,
+}
+
+/// download the executable file of the application before launching the tasks,
+/// so the distribution errors are reported on submitting
+pub async fn prefetch_artifact(
+    application_id: Path<String>,
+    artifact_model: web::Json<ArtifactModel>,
+    context: Data<Context>,
+) -> Result<HttpResponse, Error> {
+    let response: StdResponse<String> = match fetch_artifact(
+        &context,
+        application_id.as_str(),
+        artifact_model.file_name.as_str(),
+    )
+    .await
+    {
+        Ok(path) => StdResponse {
+            code: ResponseCode::OK,
+            data: Some(path.to_str().unwrap().to_string()),
+        },
+        Err(e) => StdResponse {
+            code: ResponseCode::ERR(e.to_string()),
+            data: None,
+        },
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
 #[derive(Deserialize)]
 pub struct TaskModel {
     application_id: String,
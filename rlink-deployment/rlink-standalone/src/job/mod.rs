@@ -1,14 +1,29 @@
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Mutex;
+
+use rlink::core::cluster::TaskResourceInfo;
 
 use crate::utils::read_file_as_string;
 
+pub mod scheduler;
+
+lazy_static! {
+    /// serialize the read-modify-write of the application metadata,
+    /// the applications in a session share the JobManager
+    static ref METADATA_LOCK: Mutex<()> = Mutex::new(());
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Status {
     Ready,
     Running,
     Killed,
+    /// killed to free the slots for a higher priority application, can be submitted again
+    Preempted,
+    /// killed after a savepoint is taken, the new application is restored from the savepoint
+    Upgraded,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -16,6 +31,22 @@ pub struct Application {
     pub(crate) application_id: String,
     pub(crate) execute_file: String,
     pub(crate) status: Status,
+    /// the web address of the application's coordinator,
+    /// the descriptors and checkpoints of the application are served by it
+    #[serde(default)]
+    pub(crate) coordinator_address: Option<String>,
+    /// the tasks of the application running on the shared TaskManagers
+    #[serde(default)]
+    pub(crate) tasks: Vec<TaskResourceInfo>,
+    /// the application with higher priority can preempt the slots of the lower ones
+    #[serde(default)]
+    pub(crate) priority: i32,
+    /// the max number of tasks the application can run, unlimited if not set
+    #[serde(default)]
+    pub(crate) task_quota: Option<usize>,
+    /// the savepoint taken before the application is upgraded, `{application_id}/{checkpoint_id}`
+    #[serde(default)]
+    pub(crate) savepoint: Option<String>,
 }
 
 impl Application {
@@ -24,9 +55,47 @@ impl Application {
             application_id,
             execute_file,
             status: Status::Ready,
+            coordinator_address: None,
+            tasks: Vec::new(),
+            priority: 0,
+            task_quota: None,
+            savepoint: None,
         }
     }
 
+    /// load all applications submitted to the session, the broken metadata is skipped
+    pub fn list(root_path: PathBuf) -> std::io::Result<Vec<Self>> {
+        let mut applications = Vec::new();
+        for entry in std::fs::read_dir(root_path)? {
+            let path = entry?.path();
+            if !path.join("metadata").exists() {
+                continue;
+            }
+
+            match Application::load(path.clone()) {
+                Ok(application) => applications.push(application),
+                Err(e) => error!("load application from {:?} error. {}", path, e),
+            }
+        }
+
+        applications.sort_by(|a, b| a.application_id.cmp(&b.application_id));
+        Ok(applications)
+    }
+
+    /// load, modify and store the metadata atomically
+    pub fn update<F>(parent_path: PathBuf, f: F) -> std::io::Result<Self>
+    where
+        F: FnOnce(&mut Application),
+    {
+        let _guard = METADATA_LOCK.lock().unwrap();
+
+        let mut application = Application::load(parent_path.clone())?;
+        f(&mut application);
+        application.storage(parent_path)?;
+
+        Ok(application)
+    }
+
     pub fn load(parent_path: PathBuf) -> std::io::Result<Self> {
         let metadata_file = parent_path.join("metadata");
 
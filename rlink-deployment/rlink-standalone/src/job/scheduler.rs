@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use crate::job::{Application, Status};
+
+/// The free slots of each TaskManager, the slots are occupied by the tasks of
+/// all running applications in the session
+pub fn free_slots(
+    task_managers: &[String],
+    applications: &[Application],
+    slots: usize,
+) -> HashMap<String, usize> {
+    let mut free: HashMap<String, usize> = task_managers
+        .iter()
+        .map(|task_manager| (task_manager.clone(), slots))
+        .collect();
+
+    applications
+        .iter()
+        .filter(|application| application.status == Status::Running)
+        .flat_map(|application| application.tasks.iter())
+        .filter_map(|task| task.task_manager_address())
+        .for_each(|task_manager| {
+            if let Some(n) = free.get_mut(task_manager) {
+                *n = n.saturating_sub(1);
+            }
+        });
+
+    free
+}
+
+/// The candidate TaskManagers of the scheduling, the TaskManagers without free slot and the
+/// excluded TaskManagers(eg: the terminating hosts of the drained workers) are filtered out
+pub fn candidate_task_managers(
+    task_managers: &[String],
+    free_slots: Option<&HashMap<String, usize>>,
+    excluded_task_managers: &[String],
+) -> Vec<String> {
+    task_managers
+        .iter()
+        .filter(|task_manager| !excluded_task_managers.contains(task_manager))
+        .filter(|task_manager| match free_slots {
+            Some(free_slots) => free_slots.get(*task_manager).cloned().unwrap_or(0) > 0,
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// The index in the `candidates` to start the round robin scheduling from, `next` is the position
+/// in the stable `task_managers` after the last scheduled TaskManager. The first candidate at or
+/// after `next` is selected, so the round robin is not shifted when the candidates change.
+pub fn round_robin_start(task_managers: &[String], candidates: &[String], next: usize) -> usize {
+    let len = task_managers.len();
+    (0..len)
+        .map(|n| &task_managers[(next + n) % len])
+        .find_map(|task_manager| candidates.iter().position(|x| x.eq(task_manager)))
+        .unwrap_or(0)
+}
+
+/// Select the running applications to preempt, so that `required` slots are available.
+/// The lowest priority and the latest submitted application is preempted first.
+/// Return empty if the slots are still not enough after preempting all lower priority applications.
+pub fn select_preempted<'a>(
+    applications: &'a [Application],
+    application_id: &str,
+    priority: i32,
+    required: usize,
+    free: usize,
+) -> Vec<&'a Application> {
+    let mut candidates: Vec<&Application> = applications
+        .iter()
+        .filter(|application| {
+            application.status == Status::Running
+                && application.priority < priority
+                && application.application_id.ne(application_id)
+                && application.tasks.len() > 0
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then_with(|| b.application_id.cmp(&a.application_id))
+    });
+
+    let mut preempted = Vec::new();
+    let mut free = free;
+    for application in candidates {
+        if free >= required {
+            break;
+        }
+
+        free += application.tasks.len();
+        preempted.push(application);
+    }
+
+    if free >= required {
+        preempted
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::cluster::TaskResourceInfo;
+
+    use crate::job::scheduler::{
+        candidate_task_managers, free_slots, round_robin_start, select_preempted,
+    };
+    use crate::job::{Application, Status};
+
+    fn application(application_id: &str, priority: i32, tasks: usize) -> Application {
+        let mut application = Application::new(application_id.to_string(), "".to_string());
+        application.status = Status::Running;
+        application.priority = priority;
+        application.tasks = (0..tasks)
+            .map(|n| TaskResourceInfo::new(n.to_string(), "tm-1".to_string(), "".to_string()))
+            .collect();
+        application
+    }
+
+    #[test]
+    pub fn select_preempted_test() {
+        let applications = vec![
+            application("application-1", 0, 2),
+            application("application-2", 0, 2),
+            application("application-3", 5, 2),
+        ];
+
+        let free = free_slots(&["tm-1".to_string()], &applications, 6);
+        assert_eq!(free.get("tm-1"), Some(&0));
+
+        let preempted = select_preempted(&applications, "application-4", 5, 3, 0);
+        let preempted: Vec<&str> = preempted
+            .iter()
+            .map(|x| x.application_id.as_str())
+            .collect();
+        assert_eq!(preempted, vec!["application-2", "application-1"]);
+
+        assert!(select_preempted(&applications, "application-4", 5, 5, 0).is_empty());
+        assert!(select_preempted(&applications, "application-4", 0, 1, 0).is_empty());
+    }
+
+    #[test]
+    pub fn candidate_task_managers_test() {
+        let task_managers = vec!["tm-1".to_string(), "tm-2".to_string(), "tm-3".to_string()];
+
+        let candidates = candidate_task_managers(&task_managers, None, &["tm-2".to_string()]);
+        assert_eq!(candidates, vec!["tm-1".to_string(), "tm-3".to_string()]);
+
+        let applications = vec![application("application-1", 0, 2)];
+        let free = free_slots(&task_managers, &applications, 2);
+        let candidates =
+            candidate_task_managers(&task_managers, Some(&free), &["tm-3".to_string()]);
+        assert_eq!(candidates, vec!["tm-2".to_string()]);
+    }
+
+    #[test]
+    pub fn round_robin_start_test() {
+        let task_managers = vec!["tm-1".to_string(), "tm-2".to_string(), "tm-3".to_string()];
+
+        let candidates = task_managers.clone();
+        assert_eq!(round_robin_start(&task_managers, &candidates, 0), 0);
+        assert_eq!(round_robin_start(&task_managers, &candidates, 2), 2);
+        assert_eq!(round_robin_start(&task_managers, &candidates, 3), 0);
+
+        // `tm-2` has no free slot after the last scheduling on it
+        let candidates = vec!["tm-1".to_string(), "tm-3".to_string()];
+        assert_eq!(round_robin_start(&task_managers, &candidates, 2), 1);
+        assert_eq!(round_robin_start(&task_managers, &candidates, 1), 1);
+        assert_eq!(round_robin_start(&task_managers, &candidates, 3), 0);
+
+        assert_eq!(round_robin_start(&task_managers, &[], 1), 0);
+    }
+}
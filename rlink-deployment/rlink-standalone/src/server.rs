@@ -3,9 +3,11 @@ use actix_web::{middleware, web, App, HttpResponse, HttpServer};
 
 use crate::config::{create_context, Context};
 use crate::controller::job_manager::{
-    create_application, download_application_resource, kill_job, shutdown_tasks, submit_job,
+    create_application, distribute_application, download_application_resource, get_application,
+    get_application_checkpoints, get_application_cluster_metadata, kill_job, list_applications,
+    shutdown_tasks, submit_job, upgrade_application,
 };
-use crate::controller::task_manager::{execute_task, kill_job_tasks, kill_task};
+use crate::controller::task_manager::{execute_task, kill_job_tasks, kill_task, prefetch_artifact};
 use crate::utils::parse_arg;
 
 pub fn index() -> HttpResponse {
@@ -58,10 +60,23 @@ async fn run_as_job_manager(data: Data<Context>) -> std::io::Result<()> {
             .service(web::resource("/").route(web::get().to(index)))
             // .service(web::resource("/upload").route(web::post().to(upload_file)))
             // .service(web::resource("/download").route(web::post().to(download_file)))
-            .service(web::resource("/job/application").route(web::post().to(create_application)))
+            .service(
+                web::resource("/job/application")
+                    .route(web::post().to(create_application))
+                    .route(web::get().to(list_applications)),
+            )
             .service(
                 web::resource("/job/application/{application_id}")
-                    .route(web::post().to(submit_job)),
+                    .route(web::post().to(submit_job))
+                    .route(web::get().to(get_application)),
+            )
+            .service(
+                web::resource("/job/application/{application_id}/cluster_metadata")
+                    .route(web::get().to(get_application_cluster_metadata)),
+            )
+            .service(
+                web::resource("/job/application/{application_id}/checkpoints")
+                    .route(web::get().to(get_application_checkpoints)),
             )
             .service(
                 web::resource("/job/resource/{application_id}/{file_name}")
@@ -75,6 +90,14 @@ async fn run_as_job_manager(data: Data<Context>) -> std::io::Result<()> {
                 web::resource("/job/application/{application_id}/shutdown")
                     .route(web::post().to(kill_job)),
             )
+            .service(
+                web::resource("/job/application/{application_id}/upgrade")
+                    .route(web::post().to(upgrade_application)),
+            )
+            .service(
+                web::resource("/job/application/{application_id}/distribute")
+                    .route(web::post().to(distribute_application)),
+            )
     })
     .bind(ip)?
     .run()
@@ -89,6 +112,10 @@ async fn run_as_task_manager(data: Data<Context>) -> std::io::Result<()> {
             .app_data(data.clone())
             .service(web::resource("/").route(web::get().to(index)))
             .service(web::resource("/task/{application_id}").route(web::post().to(execute_task)))
+            .service(
+                web::resource("/task/{application_id}/artifact")
+                    .route(web::post().to(prefetch_artifact)),
+            )
             .service(
                 web::resource("/task/{application_id}/{task_id}/shutdown")
                     .route(web::delete().to(kill_task)),
@@ -0,0 +1,83 @@
+use rand::Rng;
+use rlink::core;
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{Context, FlatMapFunction};
+
+/// the exit code of a process killed by the `ChaosMonkey`
+pub const CHAOS_EXIT_CODE: i32 = 86;
+
+/// Random task failure injection.
+/// The failure is simulated by exiting the application process, the same as a crashed worker,
+/// only the first `max_failures` attempts of the `LocalCluster` are injected.
+#[derive(Debug, Clone, Copy)]
+pub struct ChaosMonkey {
+    /// the probability of a failure on each record
+    failure_probability: f64,
+    max_failures: u32,
+}
+
+impl ChaosMonkey {
+    pub fn new(failure_probability: f64, max_failures: u32) -> Self {
+        ChaosMonkey {
+            failure_probability,
+            max_failures,
+        }
+    }
+
+    /// never inject failure
+    pub fn disable() -> Self {
+        ChaosMonkey::new(0f64, 0)
+    }
+
+    pub fn max_failures(&self) -> u32 {
+        self.max_failures
+    }
+
+    pub fn is_enable(&self) -> bool {
+        self.failure_probability > 0f64 && crate::cluster::attempt() < self.max_failures
+    }
+
+    pub fn to_flat_map(&self) -> ChaosFlatMapFunction {
+        ChaosFlatMapFunction::new(*self)
+    }
+}
+
+/// Pass through all records, and inject failures by the `ChaosMonkey`
+#[derive(Debug, Function)]
+pub struct ChaosFlatMapFunction {
+    chaos_monkey: ChaosMonkey,
+    enable: bool,
+}
+
+impl ChaosFlatMapFunction {
+    pub fn new(chaos_monkey: ChaosMonkey) -> Self {
+        ChaosFlatMapFunction {
+            chaos_monkey,
+            enable: false,
+        }
+    }
+}
+
+impl FlatMapFunction for ChaosFlatMapFunction {
+    fn open(&mut self, _context: &Context) -> core::Result<()> {
+        self.enable = self.chaos_monkey.is_enable();
+        Ok(())
+    }
+
+    fn flat_map(&mut self, record: Record) -> Box<dyn Iterator<Item = Record>> {
+        if self.enable && rand::thread_rng().gen_bool(self.chaos_monkey.failure_probability) {
+            error!("chaos monkey inject a failure, the process exit");
+            std::process::exit(CHAOS_EXIT_CODE);
+        }
+
+        Box::new(vec![record].into_iter())
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        input_schema
+    }
+}
@@ -0,0 +1,135 @@
+use std::process::{Command, ExitStatus};
+use std::time::{Duration, Instant};
+
+use crate::chaos::CHAOS_EXIT_CODE;
+
+const ENV_CHILD: &str = "RLINK_TESTS_CHILD";
+const ENV_ATTEMPT: &str = "RLINK_TESTS_ATTEMPT";
+
+/// the attempt number of the current application process, start from `0`
+pub fn attempt() -> u32 {
+    std::env::var(ENV_ATTEMPT)
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(0)
+}
+
+/// whether the current process is an application process spawned by the `LocalCluster`
+pub fn is_child() -> bool {
+    std::env::var(ENV_CHILD).is_ok()
+}
+
+/// Run a test case as a `Local` mode application in child processes.
+///
+/// The child process is the current test binary filtered by the `test_name`,
+/// the test case should check `is_child()` to decide to run the application or to drive the cluster.
+/// When the child exit with `CHAOS_EXIT_CODE`, it is restarted with the same `application_id`
+/// and recovered from the latest completed checkpoint.
+#[derive(Debug, Clone)]
+pub struct LocalCluster {
+    test_name: String,
+    application_id: String,
+    num_task_managers: u32,
+    max_restarts: u32,
+    timeout: Duration,
+    include_ignored: bool,
+}
+
+impl LocalCluster {
+    pub fn new(test_name: &str, application_id: &str) -> Self {
+        LocalCluster {
+            test_name: test_name.to_string(),
+            application_id: application_id.to_string(),
+            num_task_managers: 1,
+            max_restarts: 10,
+            timeout: Duration::from_secs(300),
+            include_ignored: false,
+        }
+    }
+
+    /// run as a mini cluster if more than 1, each task manager is a child process of the application
+    pub fn num_task_managers(mut self, num_task_managers: u32) -> Self {
+        self.num_task_managers = num_task_managers;
+        self
+    }
+
+    pub fn max_restarts(mut self, max_restarts: u32) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// the test case is marked by `#[ignore]`
+    pub fn include_ignored(mut self) -> Self {
+        self.include_ignored = true;
+        self
+    }
+
+    /// Run the application until it finished, return the number of restarts
+    pub fn run(&self) -> anyhow::Result<u32> {
+        let begin = Instant::now();
+
+        let mut attempt = 0;
+        loop {
+            if begin.elapsed() > self.timeout {
+                return Err(anyhow!(
+                    "application `{}` timeout after {:?}",
+                    self.application_id,
+                    self.timeout
+                ));
+            }
+
+            let status = self.spawn(attempt)?;
+            match status.code() {
+                Some(0) => {
+                    info!(
+                        "application `{}` finished with {} restarts",
+                        self.application_id, attempt
+                    );
+                    return Ok(attempt);
+                }
+                Some(CHAOS_EXIT_CODE) if attempt < self.max_restarts => {
+                    warn!(
+                        "application `{}` failed by chaos monkey at attempt {}, restart",
+                        self.application_id, attempt
+                    );
+                    attempt += 1;
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "application `{}` exit with {} at attempt {}",
+                        self.application_id,
+                        status,
+                        attempt
+                    ));
+                }
+            }
+        }
+    }
+
+    fn spawn(&self, attempt: u32) -> anyhow::Result<ExitStatus> {
+        let program = std::env::current_exe()?;
+
+        let mut command = Command::new(program);
+        command
+            .arg(self.test_name.as_str())
+            .arg("--exact")
+            .arg("--nocapture")
+            .arg("--test-threads=1");
+        if self.include_ignored {
+            command.arg("--ignored");
+        }
+        command
+            .arg(format!("application_id={}", self.application_id))
+            .arg(format!("num_task_managers={}", self.num_task_managers))
+            .env(ENV_CHILD, "1")
+            .env(ENV_ATTEMPT, attempt.to_string());
+
+        let status = command.status()?;
+        Ok(status)
+    }
+}
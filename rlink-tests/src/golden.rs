@@ -0,0 +1,153 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::{DataType, Field, Schema};
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{Context, InputFormat, InputSplit, InputSplitSource};
+
+pub const FIELD_ID: usize = 0;
+
+/// the schema of golden records, only has an `id` field
+pub fn golden_schema() -> Schema {
+    Schema::new(vec![Field::new("id", DataType::UInt64)])
+}
+
+pub fn create_record(id: u64) -> Record {
+    let schema = golden_schema();
+    let mut record = Record::new();
+    let mut writer = record.as_writer(schema.as_type_ids());
+    writer.set_u64(id).unwrap();
+    record
+}
+
+pub fn read_id(record: &mut Record) -> u64 {
+    let schema = golden_schema();
+    let reader = record.as_reader(schema.as_type_ids());
+    reader.get_u64(FIELD_ID).unwrap()
+}
+
+/// A deterministic dataset `[0, size)`, all the ids are expected to be seen by the sink exactly once.
+#[derive(Debug, Clone, Copy)]
+pub struct GoldenDataset {
+    size: u64,
+}
+
+impl GoldenDataset {
+    pub fn new(size: u64) -> Self {
+        GoldenDataset { size }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u64> {
+        0..self.size
+    }
+
+    pub fn to_input_format(&self, parallelism: u16) -> GoldenInputFormat {
+        GoldenInputFormat::new(*self, parallelism)
+    }
+}
+
+/// Source of the `GoldenDataset`.
+/// The task `n` emits the ids which `id % num_tasks == n`,
+/// the offset of emitted ids is stored in the `CheckpointHandle` and recovered at `open`.
+#[derive(Debug, NamedFunction)]
+pub struct GoldenInputFormat {
+    dataset: GoldenDataset,
+    parallelism: u16,
+
+    task_number: u64,
+    num_tasks: u64,
+    offset: Arc<AtomicU64>,
+}
+
+impl GoldenInputFormat {
+    pub fn new(dataset: GoldenDataset, parallelism: u16) -> Self {
+        GoldenInputFormat {
+            dataset,
+            parallelism,
+            task_number: 0,
+            num_tasks: 1,
+            offset: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl InputSplitSource for GoldenInputFormat {}
+
+impl InputFormat for GoldenInputFormat {
+    fn open(&mut self, _input_split: InputSplit, context: &Context) -> core::Result<()> {
+        self.task_number = context.task_id.task_number() as u64;
+        self.num_tasks = context.task_id.num_tasks() as u64;
+
+        let offset = match &context.checkpoint_handle {
+            Some(handle) if !handle.handle.is_empty() => {
+                handle.handle.parse::<u64>().map_err(|e| anyhow!(e))?
+            }
+            _ => 0,
+        };
+        self.offset.store(offset, Ordering::SeqCst);
+        info!(
+            "golden source task {} recover from offset {}",
+            self.task_number, offset
+        );
+
+        Ok(())
+    }
+
+    fn record_iter(&mut self) -> Box<dyn Iterator<Item = Record> + Send> {
+        Box::new(GoldenIterator {
+            size: self.dataset.size,
+            task_number: self.task_number,
+            num_tasks: self.num_tasks,
+            offset: self.offset.clone(),
+        })
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&golden_schema())
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+impl CheckpointFunction for GoldenInputFormat {
+    fn snapshot_state(&mut self, _context: &FunctionSnapshotContext) -> Option<CheckpointHandle> {
+        let offset = self.offset.load(Ordering::SeqCst);
+        Some(CheckpointHandle {
+            handle: offset.to_string(),
+        })
+    }
+}
+
+struct GoldenIterator {
+    size: u64,
+    task_number: u64,
+    num_tasks: u64,
+    offset: Arc<AtomicU64>,
+}
+
+impl Iterator for GoldenIterator {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset.load(Ordering::SeqCst);
+        let id = offset * self.num_tasks + self.task_number;
+        if id >= self.size {
+            return None;
+        }
+
+        self.offset.store(offset + 1, Ordering::SeqCst);
+        Some(create_record(id))
+    }
+}
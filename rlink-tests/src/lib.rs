@@ -0,0 +1,19 @@
+//! End-to-end test kit of rlink.
+//!
+//! A test case runs a `StreamApp` which reads a `GoldenDataset`, injects failures by the `ChaosMonkey`,
+//! and writes to the `CollectOutputFormat`. The application is restarted by the `LocalCluster`
+//! after every failure, then the committed output is verified against the golden dataset
+//! by `verify_exactly_once`.
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate rlink_derive;
+
+pub mod chaos;
+pub mod cluster;
+pub mod golden;
+pub mod sink;
+pub mod verify;
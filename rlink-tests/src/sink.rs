@@ -0,0 +1,185 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{Context, OutputFormat};
+use rlink::core::runtime::CheckpointId;
+
+use crate::golden::read_id;
+
+const COMMITTED_FILE_EXT: &str = "committed";
+const PENDING_FILE_EXT: &str = "pending";
+
+/// Collect the golden ids to the `output_dir`.
+/// Records are staged in memory and written to a pending file of the checkpoint when the
+/// checkpoint is triggered, the pending file is committed when the checkpoint is completed.
+/// The staged records and the pending files after the restored checkpoint are discarded when
+/// the process crashed, and replayed from the source's checkpoint.
+#[derive(Debug, NamedFunction)]
+pub struct CollectOutputFormat {
+    output_dir: PathBuf,
+
+    task_number: u16,
+    staged_ids: Vec<u64>,
+    /// the checkpoint ids of the pending files
+    pending_checkpoints: Vec<u64>,
+}
+
+impl CollectOutputFormat {
+    pub fn new<P: AsRef<Path>>(output_dir: P) -> Self {
+        CollectOutputFormat {
+            output_dir: output_dir.as_ref().to_path_buf(),
+            task_number: 0,
+            staged_ids: Vec::new(),
+            pending_checkpoints: Vec::new(),
+        }
+    }
+
+    fn file_path(&self, name: &str, ext: &str) -> PathBuf {
+        self.output_dir
+            .join(format!("task-{}-{}.{}", self.task_number, name, ext))
+    }
+
+    /// write the staged ids to the file `name` with the `ext`
+    fn write_staged(&mut self, name: &str, ext: &str) -> anyhow::Result<()> {
+        let mut buf = String::new();
+        for id in &self.staged_ids {
+            buf.push_str(id.to_string().as_str());
+            buf.push('\n');
+        }
+
+        let mut file = File::create(self.file_path(name, ext))?;
+        file.write_all(buf.as_bytes())?;
+        file.sync_data()?;
+
+        self.staged_ids.clear();
+        Ok(())
+    }
+
+    /// commit the pending files of the checkpoints not after the `checkpoint_id`
+    fn commit(&mut self, checkpoint_id: u64) -> anyhow::Result<()> {
+        let (committable, pending): (Vec<u64>, Vec<u64>) = self
+            .pending_checkpoints
+            .iter()
+            .partition(|id| **id <= checkpoint_id);
+        for id in committable {
+            let name = id.to_string();
+            std::fs::rename(
+                self.file_path(name.as_str(), PENDING_FILE_EXT),
+                self.file_path(name.as_str(), COMMITTED_FILE_EXT),
+            )?;
+        }
+        self.pending_checkpoints = pending;
+        Ok(())
+    }
+
+    /// commit the pending files of the restored checkpoint, and delete the pending files of
+    /// the checkpoints after it
+    fn restore(&mut self, restored_checkpoint_id: Option<u64>) -> anyhow::Result<()> {
+        let prefix = format!("task-{}-", self.task_number);
+        for entry in std::fs::read_dir(self.output_dir.as_path())? {
+            let path = entry?.path();
+            let is_pending_file = path
+                .extension()
+                .map(|ext| ext.eq(PENDING_FILE_EXT))
+                .unwrap_or(false);
+            if !is_pending_file {
+                continue;
+            }
+
+            let checkpoint_id = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.strip_prefix(prefix.as_str()))
+                .and_then(|id| id.parse::<u64>().ok());
+            if let Some(checkpoint_id) = checkpoint_id {
+                match restored_checkpoint_id {
+                    Some(restored) if checkpoint_id <= restored => {
+                        self.pending_checkpoints.push(checkpoint_id)
+                    }
+                    _ => std::fs::remove_file(path)?,
+                }
+            }
+        }
+
+        self.commit(restored_checkpoint_id.unwrap_or_default())
+    }
+}
+
+impl OutputFormat for CollectOutputFormat {
+    fn open(&mut self, context: &Context) -> core::Result<()> {
+        std::fs::create_dir_all(self.output_dir.as_path()).map_err(|e| anyhow!(e))?;
+        self.task_number = context.task_id.task_number();
+
+        let restored_checkpoint_id = context
+            .checkpoint_handle
+            .as_ref()
+            .and_then(|handle| handle.handle.parse::<u64>().ok());
+        self.restore(restored_checkpoint_id)?;
+
+        Ok(())
+    }
+
+    fn write_record(&mut self, mut record: Record) {
+        self.staged_ids.push(read_id(&mut record));
+    }
+
+    fn close(&mut self) -> core::Result<()> {
+        // the end of bounded stream, commit all pending files and the tail records
+        self.commit(u64::MAX)?;
+        self.write_staged("end", COMMITTED_FILE_EXT)?;
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+impl CheckpointFunction for CollectOutputFormat {
+    fn snapshot_state(&mut self, context: &FunctionSnapshotContext) -> Option<CheckpointHandle> {
+        let checkpoint_id = context.checkpoint_id.0;
+        // a failure fails the task, the staged records are replayed from the last completed checkpoint
+        self.write_staged(checkpoint_id.to_string().as_str(), PENDING_FILE_EXT)
+            .expect("write the pending file error");
+        self.pending_checkpoints.push(checkpoint_id);
+
+        Some(CheckpointHandle {
+            handle: checkpoint_id.to_string(),
+        })
+    }
+
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        self.commit(checkpoint_id.0)
+            .expect("commit the pending files error");
+    }
+}
+
+/// Read all committed ids from the `output_dir`
+pub fn read_committed<P: AsRef<Path>>(output_dir: P) -> anyhow::Result<Vec<u64>> {
+    let mut ids = Vec::new();
+    for entry in std::fs::read_dir(output_dir)? {
+        let path = entry?.path();
+        let is_committed_file = path
+            .extension()
+            .map(|ext| ext.eq(COMMITTED_FILE_EXT))
+            .unwrap_or(false);
+        if !is_committed_file {
+            continue;
+        }
+
+        let reader = BufReader::new(File::open(path)?);
+        for line in reader.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            ids.push(line.parse::<u64>()?);
+        }
+    }
+
+    Ok(ids)
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// The difference between the golden dataset and the sink output
+#[derive(Debug, Clone, Default)]
+pub struct ExactlyOnceReport {
+    pub expected: usize,
+    pub actual: usize,
+    /// ids in the golden dataset but not in the output
+    pub missing: Vec<u64>,
+    /// ids in the output more than once, with the times
+    pub duplicated: Vec<(u64, usize)>,
+    /// ids in the output but not in the golden dataset
+    pub unexpected: Vec<u64>,
+}
+
+impl ExactlyOnceReport {
+    pub fn is_exactly_once(&self) -> bool {
+        self.missing.is_empty() && self.duplicated.is_empty() && self.unexpected.is_empty()
+    }
+
+    pub fn is_at_least_once(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl Display for ExactlyOnceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected: {}, actual: {}, missing: {}, duplicated: {}, unexpected: {}",
+            self.expected,
+            self.actual,
+            self.missing.len(),
+            self.duplicated.len(),
+            self.unexpected.len()
+        )
+    }
+}
+
+/// Verify the sink output against the golden dataset
+pub fn verify_exactly_once<I>(golden: I, output: &[u64]) -> ExactlyOnceReport
+where
+    I: IntoIterator<Item = u64>,
+{
+    let mut counter: HashMap<u64, usize> = HashMap::new();
+    for id in output {
+        *counter.entry(*id).or_insert(0) += 1;
+    }
+
+    let mut report = ExactlyOnceReport::default();
+    report.actual = output.len();
+
+    for id in golden {
+        report.expected += 1;
+        match counter.remove(&id) {
+            Some(1) => {}
+            Some(n) => report.duplicated.push((id, n)),
+            None => report.missing.push(id),
+        }
+    }
+
+    report.unexpected = counter.into_iter().map(|(id, _)| id).collect();
+    report.duplicated.sort();
+    report.unexpected.sort();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::verify::verify_exactly_once;
+
+    #[test]
+    pub fn verify_exactly_once_test() {
+        let report = verify_exactly_once(0..5, &[0, 1, 2, 3, 4]);
+        assert!(report.is_exactly_once());
+
+        let report = verify_exactly_once(0..5, &[0, 1, 1, 3, 4, 7]);
+        assert!(!report.is_exactly_once());
+        assert_eq!(report.missing, vec![2]);
+        assert_eq!(report.duplicated, vec![(1, 2)]);
+        assert_eq!(report.unexpected, vec![7]);
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rlink::core::backend::{CheckpointBackend, KeyedStateBackend};
+use rlink::core::data_stream::TDataStream;
+use rlink::core::env::{StreamApp, StreamExecutionEnvironment};
+use rlink::core::properties::{Properties, SystemProperties};
+use rlink::utils::process::parse_arg;
+use rlink_tests::chaos::ChaosMonkey;
+use rlink_tests::cluster::{is_child, LocalCluster};
+use rlink_tests::golden::GoldenDataset;
+use rlink_tests::sink::{read_committed, CollectOutputFormat};
+use rlink_tests::verify::verify_exactly_once;
+
+const APPLICATION_NAME: &str = "rlink-tests-exactly-once";
+
+#[derive(Clone, Debug)]
+struct ExactlyOnceStreamApp {
+    dataset: GoldenDataset,
+    chaos_monkey: ChaosMonkey,
+    checkpoint_backend: CheckpointBackend,
+    parallelism: u16,
+}
+
+impl StreamApp for ExactlyOnceStreamApp {
+    fn prepare_properties(&self, properties: &mut Properties) {
+        properties.set_application_name(APPLICATION_NAME);
+
+        properties.set_keyed_state_backend(KeyedStateBackend::Memory);
+        properties.set_checkpoint_interval(Duration::from_secs(2));
+        properties.set_checkpoint(self.checkpoint_backend.clone());
+    }
+
+    fn build_stream(&self, _properties: &Properties, env: &mut StreamExecutionEnvironment) {
+        env.register_source(self.dataset.to_input_format(self.parallelism))
+            .flat_map(self.chaos_monkey.to_flat_map())
+            .add_sink(CollectOutputFormat::new(output_dir()));
+    }
+}
+
+fn output_dir() -> PathBuf {
+    let application_id = parse_arg("application_id").unwrap();
+    std::env::temp_dir()
+        .join("rlink-tests")
+        .join(application_id)
+}
+
+fn application_id(test_name: &str) -> String {
+    format!(
+        "{}-{}",
+        test_name,
+        rlink::utils::date_time::current_timestamp_millis()
+    )
+}
+
+/// the root of the checkpoints in the local file system, shared by the restarted processes
+fn checkpoint_dir() -> PathBuf {
+    std::env::temp_dir().join("rlink-tests-checkpoints")
+}
+
+fn run(app: ExactlyOnceStreamApp, cluster: LocalCluster) {
+    if is_child() {
+        rlink::core::env::execute(app);
+        return;
+    }
+
+    let restarts = cluster.run().unwrap();
+    assert!(restarts <= app.chaos_monkey.max_failures());
+}
+
+fn verify(application_id: &str, dataset: GoldenDataset) {
+    let output_dir = std::env::temp_dir()
+        .join("rlink-tests")
+        .join(application_id);
+    let output = read_committed(output_dir.as_path()).unwrap();
+    let report = verify_exactly_once(dataset.ids(), output.as_slice());
+
+    std::fs::remove_dir_all(output_dir).unwrap();
+    let checkpoint_dir = checkpoint_dir().join(APPLICATION_NAME).join(application_id);
+    if checkpoint_dir.exists() {
+        std::fs::remove_dir_all(checkpoint_dir).unwrap();
+    }
+    assert!(report.is_exactly_once(), "{:?}", report);
+}
+
+#[test]
+pub fn exactly_once_test() {
+    let test_name = "exactly_once_test";
+    let dataset = GoldenDataset::new(10000);
+    let app = ExactlyOnceStreamApp {
+        dataset,
+        chaos_monkey: ChaosMonkey::disable(),
+        checkpoint_backend: CheckpointBackend::Memory,
+        parallelism: 2,
+    };
+
+    let application_id = application_id(test_name);
+    let cluster = LocalCluster::new(test_name, application_id.as_str()).max_restarts(0);
+    run(app, cluster);
+
+    if !is_child() {
+        verify(application_id.as_str(), dataset);
+    }
+}
+
+/// Run with 2 task managers, the records are exchanged by the network between the worker processes
+#[test]
+pub fn exactly_once_mini_cluster_test() {
+    let test_name = "exactly_once_mini_cluster_test";
+    let dataset = GoldenDataset::new(10000);
+    let app = ExactlyOnceStreamApp {
+        dataset,
+        chaos_monkey: ChaosMonkey::disable(),
+        checkpoint_backend: CheckpointBackend::Memory,
+        parallelism: 2,
+    };
+
+    let application_id = application_id(test_name);
+    let cluster = LocalCluster::new(test_name, application_id.as_str())
+        .num_task_managers(2)
+        .max_restarts(0);
+    run(app, cluster);
+
+    if !is_child() {
+        verify(application_id.as_str(), dataset);
+    }
+}
+
+/// Random failures are injected, the application is recovered from the checkpoint in the
+/// local file system
+#[test]
+pub fn exactly_once_chaos_test() {
+    let test_name = "exactly_once_chaos_test";
+    let dataset = GoldenDataset::new(200000);
+    let app = ExactlyOnceStreamApp {
+        dataset,
+        chaos_monkey: ChaosMonkey::new(0.00005, 5),
+        checkpoint_backend: CheckpointBackend::ObjectStore {
+            uri: checkpoint_dir().to_str().unwrap().to_string(),
+        },
+        parallelism: 2,
+    };
+
+    let application_id = application_id(test_name);
+    let cluster = LocalCluster::new(test_name, application_id.as_str()).max_restarts(5);
+    run(app, cluster);
+
+    if !is_child() {
+        verify(application_id.as_str(), dataset);
+    }
+}
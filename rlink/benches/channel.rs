@@ -0,0 +1,71 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use rlink::channel::ring::ring;
+use rlink::channel::{bounded, unbounded};
+use rlink::core::element::{Element, Record};
+
+const MESSAGES: u64 = 100_000;
+
+fn element() -> Element {
+    Element::Record(Record::new())
+}
+
+/// send `MESSAGES` elements by a producer thread and receive them in the bench thread
+fn channel_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("channel");
+    group.throughput(Throughput::Elements(MESSAGES));
+
+    for cap in [1024usize, 16384].iter() {
+        group.bench_with_input(BenchmarkId::new("bounded", cap), cap, |b, cap| {
+            b.iter(|| {
+                let (sender, receiver) = bounded(*cap);
+                let producer = std::thread::spawn(move || {
+                    for _ in 0..MESSAGES {
+                        sender.send(element()).unwrap();
+                    }
+                });
+                for _ in 0..MESSAGES {
+                    receiver.recv().unwrap();
+                }
+                producer.join().unwrap();
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("ring", cap), cap, |b, cap| {
+            b.iter(|| {
+                let (sender, receiver) = ring(*cap);
+                let producer = std::thread::spawn(move || {
+                    for _ in 0..MESSAGES {
+                        sender.send(element()).unwrap();
+                    }
+                });
+                for _ in 0..MESSAGES {
+                    receiver.recv().unwrap();
+                }
+                producer.join().unwrap();
+            })
+        });
+    }
+
+    group.bench_function("unbounded", |b| {
+        b.iter(|| {
+            let (sender, receiver) = unbounded();
+            let producer = std::thread::spawn(move || {
+                for _ in 0..MESSAGES {
+                    sender.send(element()).unwrap();
+                }
+            });
+            for _ in 0..MESSAGES {
+                receiver.recv().unwrap();
+            }
+            producer.join().unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, channel_throughput);
+criterion_main!(benches);
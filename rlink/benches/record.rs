@@ -0,0 +1,53 @@
+#[macro_use]
+extern crate criterion;
+
+use bytes::BytesMut;
+use criterion::{black_box, Criterion, Throughput};
+use rlink::core::element::{Element, Record, Serde};
+use serbuffer::types;
+
+const FIELD_TYPES: [u8; 4] = [types::U64, types::I32, types::F64, types::STRING];
+
+fn record(i: u64) -> Record {
+    let mut record = Record::with_capacity(64);
+    let mut writer = record.as_writer(&FIELD_TYPES);
+    writer.set_u64(i).unwrap();
+    writer.set_i32(i as i32).unwrap();
+    writer.set_f64(i as f64 * 0.5).unwrap();
+    writer.set_str("rlink-benchmark").unwrap();
+    record
+}
+
+fn record_serde(c: &mut Criterion) {
+    let mut group = c.benchmark_group("record");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("write", |b| b.iter(|| record(black_box(1))));
+
+    group.bench_function("read", |b| {
+        let mut record = record(1);
+        b.iter(|| {
+            let reader = record.as_reader(&FIELD_TYPES);
+            black_box(reader.get_u64(0).unwrap());
+            black_box(reader.get_str(3).unwrap());
+        })
+    });
+
+    let element = Element::Record(record(1));
+    group.bench_function("serialize", |b| {
+        b.iter(|| black_box(element.to_bytes()));
+    });
+
+    let bytes = element.to_bytes();
+    group.bench_function("deserialize", |b| {
+        b.iter(|| {
+            let mut bytes: BytesMut = bytes.clone();
+            black_box(Element::deserialize(&mut bytes))
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, record_serde);
+criterion_main!(benches);
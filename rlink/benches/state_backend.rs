@@ -0,0 +1,63 @@
+#[macro_use]
+extern crate criterion;
+
+use std::time::Duration;
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use rlink::core::backend::{
+    JoinSide, JoinState, KeyedStateBackend, TJoinState, TWindowState, WindowState,
+};
+use rlink::core::element::Record;
+use rlink::core::runtime::JobId;
+use rlink::core::window::{TimeWindow, Window};
+use serbuffer::types;
+
+const OPERATIONS: u64 = 10_000;
+
+fn u64_record(v: u64) -> Record {
+    let mut record = Record::new();
+    record.as_writer(&[types::U64]).set_u64(v).unwrap();
+    record
+}
+
+/// put and get the keyed state of the memory backend, by the number of the distinct keys
+fn state_get_put(c: &mut Criterion) {
+    let mut group = c.benchmark_group("state_backend");
+    group.throughput(Throughput::Elements(OPERATIONS));
+
+    let window = Window::TimeWindow(TimeWindow::new(0, 60_000));
+    for keys in [100u64, 10_000].iter() {
+        group.bench_with_input(BenchmarkId::new("window_put", keys), keys, |b, keys| {
+            b.iter(|| {
+                let mut state =
+                    WindowState::new("bench".to_string(), JobId(0), 0, KeyedStateBackend::Memory);
+                for i in 0..OPERATIONS {
+                    let mut record = u64_record(i);
+                    record.set_location_windows(vec![window.clone()]);
+                    state.merge(u64_record(i % keys), record, |value, record| match value {
+                        Some(value) => value.clone(),
+                        None => record.clone(),
+                    });
+                }
+                state.purge_window(&window);
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("join_put_get", keys), keys, |b, keys| {
+            b.iter(|| {
+                let mut state =
+                    JoinState::new(KeyedStateBackend::Memory, Some(Duration::from_secs(60)));
+                for i in 0..OPERATIONS {
+                    let key = u64_record(i % keys);
+                    state.add(JoinSide::Left, key.clone(), u64_record(i), i);
+                    black_box(state.get(JoinSide::Left, &key).len());
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, state_get_put);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use rlink::core::backend::{KeyedStateBackend, TWindowState, WindowState};
+use rlink::core::element::Record;
+use rlink::core::runtime::JobId;
+use rlink::core::window::{TimeWindow, Window};
+use serbuffer::types;
+
+const RECORDS: u64 = 10_000;
+const KEYS: u64 = 1_000;
+const WINDOW_SIZE: u64 = 60_000;
+
+fn u64_record(v: u64) -> Record {
+    let mut record = Record::new();
+    record.as_writer(&[types::U64]).set_u64(v).unwrap();
+    record
+}
+
+/// the sliding windows of `size` and `slide` containing the `timestamp`
+fn sliding_windows(timestamp: u64, size: u64, slide: u64) -> Vec<Window> {
+    let last_start = timestamp - timestamp % slide;
+    let mut windows = Vec::new();
+    let mut start = last_start as i64;
+    while start > timestamp as i64 - size as i64 {
+        let start_u = start.max(0) as u64;
+        windows.push(Window::TimeWindow(TimeWindow::new(start_u, start_u + size)));
+        start -= slide as i64;
+    }
+    windows
+}
+
+fn sum(value: Option<&mut Record>, record: &mut Record) -> Record {
+    let v = record.as_reader(&[types::U64]).get_u64(0).unwrap();
+    match value {
+        Some(state) => u64_record(state.as_reader(&[types::U64]).get_u64(0).unwrap() + v),
+        None => u64_record(v),
+    }
+}
+
+/// reduce `RECORDS` records of `KEYS` keys into the windows, then purge the windows
+fn window_aggregation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("window_aggregation");
+    group.throughput(Throughput::Elements(RECORDS));
+
+    for slide in [WINDOW_SIZE, WINDOW_SIZE / 4].iter() {
+        let records: Vec<(Record, Record)> = (0..RECORDS)
+            .map(|i| {
+                let mut record = u64_record(i);
+                record.set_location_windows(sliding_windows(i * 10, WINDOW_SIZE, *slide));
+                (u64_record(i % KEYS), record)
+            })
+            .collect();
+
+        group.bench_with_input(BenchmarkId::new("slide", slide), &records, |b, records| {
+            b.iter(|| {
+                let mut state =
+                    WindowState::new("bench".to_string(), JobId(0), 0, KeyedStateBackend::Memory);
+                for (key, record) in records.iter() {
+                    state.merge(key.clone(), record.clone(), sum);
+                }
+                for window in state.windows() {
+                    state.purge_window(&window);
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, window_aggregation);
+criterion_main!(benches);
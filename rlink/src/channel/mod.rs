@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 
-use crate::channel::receiver::ChannelReceiver;
-use crate::channel::sender::ChannelSender;
+use crate::channel::receiver::{ChannelReceiver, ReceiverFlavor};
+use crate::channel::sender::{ChannelSender, SenderFlavor};
 use crate::core::element::Element;
 use crate::metrics::metric::Tag;
 use crate::metrics::{register_counter, register_gauge};
@@ -31,6 +31,7 @@ pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
 }
 
 pub mod receiver;
+pub mod ring;
 pub mod select;
 pub mod sender;
 pub mod utils;
@@ -38,10 +39,12 @@ pub mod utils;
 pub type ElementReceiver = ChannelReceiver<Element>;
 pub type ElementSender = ChannelSender<Element>;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ChannelBaseOn {
     Unbounded,
     Bounded,
+    /// the fixed-size lock-free ring buffer, see `ring::ring`
+    Ring,
 }
 
 impl<'a> TryFrom<&'a str> for ChannelBaseOn {
@@ -52,6 +55,7 @@ impl<'a> TryFrom<&'a str> for ChannelBaseOn {
         match mode_str.as_str() {
             "bounded" => Ok(Self::Bounded),
             "unbounded" => Ok(Self::Unbounded),
+            "ring" => Ok(Self::Ring),
             _ => Err(anyhow!("Unsupported mode {}", mode_str)),
         }
     }
@@ -62,10 +66,53 @@ impl std::fmt::Display for ChannelBaseOn {
         match self {
             ChannelBaseOn::Bounded => write!(f, "Bounded"),
             ChannelBaseOn::Unbounded => write!(f, "Unbounded"),
+            ChannelBaseOn::Ring => write!(f, "Ring"),
         }
     }
 }
 
+/// The options of the channel that transfers elements to a job,
+/// the unset option is inherited from the `SystemProperties`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChannelOptions {
+    pub channel_size: Option<usize>,
+    pub base_on: Option<ChannelBaseOn>,
+}
+
+impl ChannelOptions {
+    pub fn new(channel_size: usize, base_on: ChannelBaseOn) -> Self {
+        ChannelOptions {
+            channel_size: Some(channel_size),
+            base_on: Some(base_on),
+        }
+    }
+
+    /// merge the options of the operators in the same job,
+    /// the larger `channel_size` and the first `base_on` win
+    pub(crate) fn merge(&self, other: &ChannelOptions) -> ChannelOptions {
+        let channel_size = match (self.channel_size, other.channel_size) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        ChannelOptions {
+            channel_size,
+            base_on: self.base_on.or(other.base_on),
+        }
+    }
+
+    /// resolve the options with the default values
+    pub(crate) fn unwrap_or(
+        &self,
+        channel_size: usize,
+        base_on: ChannelBaseOn,
+    ) -> (usize, ChannelBaseOn) {
+        (
+            self.channel_size.unwrap_or(channel_size),
+            self.base_on.unwrap_or(base_on),
+        )
+    }
+}
+
 pub fn named_channel<T>(
     name: &str,
     tags: Vec<Tag>,
@@ -91,9 +138,19 @@ where
         name, cap, base_on
     );
 
-    let (sender, receiver) = match base_on {
-        ChannelBaseOn::Bounded => bounded(cap),
-        ChannelBaseOn::Unbounded => unbounded(),
+    let (sender, receiver): (SenderFlavor<T>, ReceiverFlavor<T>) = match base_on {
+        ChannelBaseOn::Bounded => {
+            let (sender, receiver) = bounded(cap);
+            (sender.into(), receiver.into())
+        }
+        ChannelBaseOn::Unbounded => {
+            let (sender, receiver) = unbounded();
+            (sender.into(), receiver.into())
+        }
+        ChannelBaseOn::Ring => {
+            let (sender, receiver) = ring::ring(cap);
+            (sender.into(), receiver.into())
+        }
     };
 
     // add_channel_metric(name.to_string(), size.clone(), capacity.clone());
@@ -112,7 +169,7 @@ where
 mod tests {
     use std::time::Duration;
 
-    use crate::channel::{named_channel_with_base, ChannelBaseOn};
+    use crate::channel::{named_channel_with_base, ChannelBaseOn, ChannelOptions};
     use crate::utils::date_time::current_timestamp;
     use crate::utils::thread::spawn;
 
@@ -183,4 +240,18 @@ mod tests {
         println!("finish");
         std::thread::park();
     }
+
+    #[test]
+    pub fn channel_options_merge_test() {
+        let options = ChannelOptions::default()
+            .merge(&ChannelOptions::new(1024, ChannelBaseOn::Bounded))
+            .merge(&ChannelOptions::new(4096, ChannelBaseOn::Unbounded));
+        assert_eq!(options.channel_size, Some(4096));
+        assert_eq!(options.base_on, Some(ChannelBaseOn::Bounded));
+
+        let (channel_size, base_on) =
+            ChannelOptions::default().unwrap_or(100, ChannelBaseOn::Unbounded);
+        assert_eq!(channel_size, 100);
+        assert_eq!(base_on, ChannelBaseOn::Unbounded);
+    }
 }
@@ -1,8 +1,50 @@
 use std::time::Duration;
 
+use crate::channel::ring::RingReceiver;
 use crate::channel::{Receiver, RecvError, RecvTimeoutError, TryRecvError, CHANNEL_SIZE_PREFIX};
 use crate::metrics::metric::{Counter, Gauge};
 
+#[derive(Clone)]
+pub enum ReceiverFlavor<T> {
+    Crossbeam(Receiver<T>),
+    Ring(RingReceiver<T>),
+}
+
+impl<T> ReceiverFlavor<T> {
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self {
+            ReceiverFlavor::Crossbeam(receiver) => receiver.try_recv(),
+            ReceiverFlavor::Ring(receiver) => receiver.try_recv(),
+        }
+    }
+
+    fn recv(&self) -> Result<T, RecvError> {
+        match self {
+            ReceiverFlavor::Crossbeam(receiver) => receiver.recv(),
+            ReceiverFlavor::Ring(receiver) => receiver.recv(),
+        }
+    }
+
+    fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        match self {
+            ReceiverFlavor::Crossbeam(receiver) => receiver.recv_timeout(timeout),
+            ReceiverFlavor::Ring(receiver) => receiver.recv_timeout(timeout),
+        }
+    }
+}
+
+impl<T> From<Receiver<T>> for ReceiverFlavor<T> {
+    fn from(receiver: Receiver<T>) -> Self {
+        ReceiverFlavor::Crossbeam(receiver)
+    }
+}
+
+impl<T> From<RingReceiver<T>> for ReceiverFlavor<T> {
+    fn from(receiver: RingReceiver<T>) -> Self {
+        ReceiverFlavor::Ring(receiver)
+    }
+}
+
 #[derive(Clone)]
 pub struct ChannelReceiver<T>
 where
@@ -11,7 +53,7 @@ where
     name: String,
     guava_size_name: String,
 
-    pub(crate) receiver: Receiver<T>,
+    pub(crate) receiver: ReceiverFlavor<T>,
 
     size: Gauge,
     drain_counter: Counter,
@@ -21,11 +63,16 @@ impl<T> ChannelReceiver<T>
 where
     T: Sync + Send,
 {
-    pub fn new(name: &str, receiver: Receiver<T>, size: Gauge, drain_counter: Counter) -> Self {
+    pub fn new<R: Into<ReceiverFlavor<T>>>(
+        name: &str,
+        receiver: R,
+        size: Gauge,
+        drain_counter: Counter,
+    ) -> Self {
         ChannelReceiver {
             name: name.to_string(),
             guava_size_name: CHANNEL_SIZE_PREFIX.to_owned() + name,
-            receiver,
+            receiver: receiver.into(),
             size,
             drain_counter,
         }
@@ -0,0 +1,202 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crossbeam::queue::ArrayQueue;
+use crossbeam::utils::Backoff;
+
+use crate::channel::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+
+/// the max time of a blocked sender/receiver parks before the next spin
+const PARK_TIMEOUT: Duration = Duration::from_micros(200);
+
+struct Ring<T> {
+    queue: ArrayQueue<T>,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+}
+
+/// Create a fixed-size lock-free ring buffer channel.
+///
+/// Unlike the crossbeam channel, there is no waker registered on the ring,
+/// the blocked sender/receiver spins with backoff and parks for a while,
+/// so the ring fits the channels with busy and known senders/receivers, eg: the memory edges.
+pub fn ring<T>(cap: usize) -> (RingSender<T>, RingReceiver<T>) {
+    let ring = Arc::new(Ring {
+        queue: ArrayQueue::new(cap.max(1)),
+        senders: AtomicUsize::new(1),
+        receivers: AtomicUsize::new(1),
+    });
+
+    (RingSender { ring: ring.clone() }, RingReceiver { ring })
+}
+
+pub struct RingSender<T> {
+    ring: Arc<Ring<T>>,
+}
+
+impl<T> RingSender<T> {
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        if self.ring.receivers.load(Ordering::Acquire) == 0 {
+            return Err(TrySendError::Disconnected(t));
+        }
+
+        self.ring.queue.push(t).map_err(|t| TrySendError::Full(t))
+    }
+
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let backoff = Backoff::new();
+        let mut t = t;
+        loop {
+            t = match self.try_send(t) {
+                Ok(_) => return Ok(()),
+                Err(TrySendError::Disconnected(t)) => return Err(crossbeam::channel::SendError(t)),
+                Err(TrySendError::Full(t)) => t,
+            };
+
+            if backoff.is_completed() {
+                std::thread::park_timeout(PARK_TIMEOUT);
+            } else {
+                backoff.snooze();
+            }
+        }
+    }
+}
+
+impl<T> Clone for RingSender<T> {
+    fn clone(&self) -> Self {
+        self.ring.senders.fetch_add(1, Ordering::AcqRel);
+        RingSender {
+            ring: self.ring.clone(),
+        }
+    }
+}
+
+impl<T> Drop for RingSender<T> {
+    fn drop(&mut self) {
+        self.ring.senders.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+pub struct RingReceiver<T> {
+    ring: Arc<Ring<T>>,
+}
+
+impl<T> RingReceiver<T> {
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.ring.queue.pop() {
+            Some(t) => Ok(t),
+            None => {
+                if self.ring.senders.load(Ordering::Acquire) == 0 {
+                    // double check, the element may be pushed before the sender dropped
+                    self.ring.queue.pop().ok_or(TryRecvError::Disconnected)
+                } else {
+                    Err(TryRecvError::Empty)
+                }
+            }
+        }
+    }
+
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let backoff = Backoff::new();
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(TryRecvError::Disconnected) => return Err(crossbeam::channel::RecvError),
+                Err(TryRecvError::Empty) => {}
+            }
+
+            if backoff.is_completed() {
+                std::thread::park_timeout(PARK_TIMEOUT);
+            } else {
+                backoff.snooze();
+            }
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let backoff = Backoff::new();
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+
+            if backoff.is_completed() {
+                std::thread::park_timeout(PARK_TIMEOUT.min(deadline - now));
+            } else {
+                backoff.snooze();
+            }
+        }
+    }
+
+    /// a receive operation can be executed without blocking
+    pub fn is_ready(&self) -> bool {
+        !self.ring.queue.is_empty() || self.ring.senders.load(Ordering::Acquire) == 0
+    }
+}
+
+impl<T> Clone for RingReceiver<T> {
+    fn clone(&self) -> Self {
+        self.ring.receivers.fetch_add(1, Ordering::AcqRel);
+        RingReceiver {
+            ring: self.ring.clone(),
+        }
+    }
+}
+
+impl<T> Drop for RingReceiver<T> {
+    fn drop(&mut self) {
+        self.ring.receivers.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::channel::ring::ring;
+    use crate::channel::{RecvTimeoutError, TryRecvError, TrySendError};
+
+    #[test]
+    pub fn ring_test() {
+        let (sender, receiver) = ring::<u64>(2);
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+        assert_eq!(sender.try_send(3), Err(TrySendError::Full(3)));
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(10)),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        let senders: Vec<_> = (0..4)
+            .map(|n| {
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    for i in 0..1000 {
+                        sender.send(n * 1000 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let mut sum = 0;
+        while let Ok(v) = receiver.recv() {
+            sum += v;
+        }
+        senders.into_iter().for_each(|h| h.join().unwrap());
+        assert_eq!(sum, (0..4000).sum::<u64>());
+    }
+}
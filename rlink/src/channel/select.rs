@@ -1,14 +1,28 @@
-use crate::channel::receiver::ChannelReceiver;
+use std::time::Duration;
+
+use crate::channel::receiver::{ChannelReceiver, ReceiverFlavor};
 use crate::channel::Select;
 
+/// the interval of polling the ring receivers when waiting
+const RING_POLL_INTERVAL: Duration = Duration::from_micros(200);
+
+enum Operation<'a> {
+    /// the index of the crossbeam `Select` operation
+    Select(usize),
+    /// the ring has no waker, check the readiness by polling
+    Ring(Box<dyn Fn() -> bool + 'a>),
+}
+
 pub struct ChannelSelect<'a> {
     select: Select<'a>,
+    operations: Vec<Operation<'a>>,
 }
 
 impl<'a> ChannelSelect<'a> {
     pub fn new() -> Self {
         ChannelSelect {
             select: Select::new(),
+            operations: Vec::new(),
         }
     }
 
@@ -16,11 +30,58 @@ impl<'a> ChannelSelect<'a> {
     where
         T: Sync + Send,
     {
-        self.select.recv(&r.receiver)
+        let operation = match &r.receiver {
+            ReceiverFlavor::Crossbeam(receiver) => Operation::Select(self.select.recv(receiver)),
+            ReceiverFlavor::Ring(receiver) => {
+                Operation::Ring(Box::new(move || receiver.is_ready()))
+            }
+        };
+        self.operations.push(operation);
+        self.operations.len() - 1
     }
 
     pub fn ready(&mut self) -> usize {
-        self.select.ready()
+        let has_ring = self
+            .operations
+            .iter()
+            .any(|op| matches!(op, Operation::Ring(_)));
+        if !has_ring {
+            let index = self.select.ready();
+            return self.select_index(index);
+        }
+
+        let has_select = self
+            .operations
+            .iter()
+            .any(|op| matches!(op, Operation::Select(_)));
+        loop {
+            for (index, op) in self.operations.iter().enumerate() {
+                if let Operation::Ring(is_ready) = op {
+                    if is_ready() {
+                        return index;
+                    }
+                }
+            }
+
+            if has_select {
+                if let Ok(index) = self.select.ready_timeout(RING_POLL_INTERVAL) {
+                    return self.select_index(index);
+                }
+            } else {
+                std::thread::park_timeout(RING_POLL_INTERVAL);
+            }
+        }
+    }
+
+    /// map the index of the crossbeam `Select` to the operation's index
+    fn select_index(&self, select_index: usize) -> usize {
+        self.operations
+            .iter()
+            .position(|op| match op {
+                Operation::Select(index) => *index == select_index,
+                _ => false,
+            })
+            .unwrap()
     }
 }
 
@@ -1,8 +1,43 @@
 use std::time::Duration;
 
+use crate::channel::ring::RingSender;
 use crate::channel::{ChannelBaseOn, SendError, Sender, TrySendError, CHANNEL_SIZE_PREFIX};
 use crate::metrics::metric::{Counter, Gauge};
 
+#[derive(Clone)]
+pub enum SenderFlavor<T> {
+    Crossbeam(Sender<T>),
+    Ring(RingSender<T>),
+}
+
+impl<T> SenderFlavor<T> {
+    fn send(&self, t: T) -> Result<(), SendError<T>> {
+        match self {
+            SenderFlavor::Crossbeam(sender) => sender.send(t),
+            SenderFlavor::Ring(sender) => sender.send(t),
+        }
+    }
+
+    fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        match self {
+            SenderFlavor::Crossbeam(sender) => sender.try_send(t),
+            SenderFlavor::Ring(sender) => sender.try_send(t),
+        }
+    }
+}
+
+impl<T> From<Sender<T>> for SenderFlavor<T> {
+    fn from(sender: Sender<T>) -> Self {
+        SenderFlavor::Crossbeam(sender)
+    }
+}
+
+impl<T> From<RingSender<T>> for SenderFlavor<T> {
+    fn from(sender: RingSender<T>) -> Self {
+        SenderFlavor::Ring(sender)
+    }
+}
+
 #[derive(Clone)]
 pub struct ChannelSender<T>
 where
@@ -11,7 +46,7 @@ where
     name: String,
     guava_size_name: String,
 
-    sender: Sender<T>,
+    sender: SenderFlavor<T>,
     base_on: ChannelBaseOn,
     cap: usize,
 
@@ -23,9 +58,9 @@ impl<T> ChannelSender<T>
 where
     T: Sync + Send,
 {
-    pub fn new(
+    pub fn new<S: Into<SenderFlavor<T>>>(
         name: &str,
-        sender: Sender<T>,
+        sender: S,
         base_on: ChannelBaseOn,
         cap: usize,
         size: Gauge,
@@ -34,7 +69,7 @@ where
         ChannelSender {
             name: name.to_string(),
             guava_size_name: CHANNEL_SIZE_PREFIX.to_owned() + name,
-            sender,
+            sender: sender.into(),
             base_on,
             cap,
             size,
@@ -1,9 +1,17 @@
 use crate::channel::receiver::ChannelReceiver;
 use crate::channel::sender::ChannelSender;
-use crate::channel::{named_channel, RecvError, SendError, TryRecvError, TrySendError};
+use std::time::Duration;
+
+use crate::channel::{
+    named_channel, RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError,
+};
+use crate::core::cancellation::CancellationToken;
 use crate::core::element::Record;
 use crate::metrics::Tag;
 
+/// the interval the cancellable `Handover` methods check the token while blocked
+const CANCELLATION_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
 #[derive(Clone)]
 pub struct Handover<T = Record>
 where
@@ -41,4 +49,34 @@ where
     pub fn try_produce(&self, record: T) -> Result<(), TrySendError<T>> {
         self.sender.try_send(record)
     }
+
+    /// poll the next record, blocking until one arrives or the `token` is cancelled,
+    /// `None` if the `token` is cancelled or the handover is disconnected
+    pub fn poll_next_cancellable(&self, token: &CancellationToken) -> Option<T> {
+        while !token.is_cancelled() {
+            match self.receiver.recv_timeout(CANCELLATION_CHECK_INTERVAL) {
+                Ok(record) => return Some(record),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+        None
+    }
+
+    /// produce the record, blocking while the handover is full until the `token` is cancelled,
+    /// return `false` if the record is dropped by the cancellation or the disconnection
+    pub fn produce_cancellable(&self, mut record: T, token: &CancellationToken) -> bool {
+        loop {
+            match self.sender.try_send(record) {
+                Ok(()) => return true,
+                Err(TrySendError::Full(r)) => {
+                    if token.wait_timeout(CANCELLATION_CHECK_INTERVAL) {
+                        return false;
+                    }
+                    record = r;
+                }
+                Err(TrySendError::Disconnected(_r)) => return false,
+            }
+        }
+    }
 }
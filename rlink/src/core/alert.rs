@@ -0,0 +1,154 @@
+use std::fmt::Debug;
+
+use serde_json::json;
+
+use crate::core::error::ErrorClass;
+use crate::utils::http::client::post_json_sync;
+
+/// The health transitions of the application that trigger an alert
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq, Hash)]
+pub enum AlertKind {
+    /// the consecutive failed checkpoints exceed the threshold
+    CheckpointFailures,
+    /// a worker's heartbeat is timeout
+    WorkerLost,
+    /// the application restarts too many times in a short period
+    RestartStorm,
+    /// the watermark of a task does not advance for a long time
+    WatermarkStalled,
+    /// a task is failed with an error of the `AlertRules::task_failure_classes`
+    TaskFailure,
+    /// the application is terminated for restarting too many times
+    CrashLoop,
+    /// the keyed state of an operator exceeds its `StateLimit`
+    StateLimitExceeded,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Alert {
+    pub kind: AlertKind,
+    /// the alerts with the same key are deduplicated in the cooldown period
+    pub dedup_key: String,
+    pub application_id: String,
+    pub application_name: String,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+/// The thresholds of the alerts, `0` means disable the alert
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AlertRules {
+    /// alert when the number of the consecutive failed checkpoints reaches it
+    pub checkpoint_failure_threshold: u32,
+    pub worker_lost: bool,
+    /// alert when the number of restarts in the `restart_storm_window_ms` reaches it
+    pub restart_storm_threshold: u32,
+    pub restart_storm_window_ms: u64,
+    /// alert when the watermark of a task does not advance in the timeout
+    pub watermark_stall_timeout_ms: u64,
+    /// alert when a task is failed with the error of these classes, empty means disable
+    #[serde(default)]
+    pub task_failure_classes: Vec<ErrorClass>,
+    /// the same alert is sent at most once in the cooldown period
+    pub cooldown_ms: u64,
+}
+
+impl Default for AlertRules {
+    fn default() -> Self {
+        AlertRules {
+            checkpoint_failure_threshold: 3,
+            worker_lost: true,
+            restart_storm_threshold: 3,
+            restart_storm_window_ms: 10 * 60 * 1000,
+            watermark_stall_timeout_ms: 0,
+            task_failure_classes: vec![],
+            cooldown_ms: 10 * 60 * 1000,
+        }
+    }
+}
+
+/// Send the alerts to the external system, invoked by the `Coordinator`
+/// in a dedicated thread, so a blocking implementation is allowed
+pub trait AlertNotifier: Send + Sync + Debug {
+    fn notify(&self, alert: &Alert) -> anyhow::Result<()>;
+}
+
+/// Post the `Alert` as json to a webhook
+#[derive(Clone, Debug)]
+pub struct WebhookAlertNotifier {
+    url: String,
+}
+
+impl WebhookAlertNotifier {
+    pub fn new(url: &str) -> Self {
+        WebhookAlertNotifier {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl AlertNotifier for WebhookAlertNotifier {
+    fn notify(&self, alert: &Alert) -> anyhow::Result<()> {
+        let body = serde_json::to_string(alert)?;
+        post_json_sync(self.url.as_str(), body).map_err(|e| anyhow!(e))
+    }
+}
+
+/// Post the alert to a Slack incoming webhook
+#[derive(Clone, Debug)]
+pub struct SlackAlertNotifier {
+    webhook_url: String,
+}
+
+impl SlackAlertNotifier {
+    pub fn new(webhook_url: &str) -> Self {
+        SlackAlertNotifier {
+            webhook_url: webhook_url.to_string(),
+        }
+    }
+}
+
+impl AlertNotifier for SlackAlertNotifier {
+    fn notify(&self, alert: &Alert) -> anyhow::Result<()> {
+        let body = json!({
+            "text": format!(
+                "[{:?}] {}({}): {}",
+                alert.kind, alert.application_name, alert.application_id, alert.message
+            ),
+        });
+        post_json_sync(self.webhook_url.as_str(), body.to_string()).map_err(|e| anyhow!(e))
+    }
+}
+
+const PAGER_DUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// Trigger an incident by the PagerDuty Events API v2
+#[derive(Clone, Debug)]
+pub struct PagerDutyAlertNotifier {
+    routing_key: String,
+}
+
+impl PagerDutyAlertNotifier {
+    pub fn new(routing_key: &str) -> Self {
+        PagerDutyAlertNotifier {
+            routing_key: routing_key.to_string(),
+        }
+    }
+}
+
+impl AlertNotifier for PagerDutyAlertNotifier {
+    fn notify(&self, alert: &Alert) -> anyhow::Result<()> {
+        let body = json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "dedup_key": format!("{}-{}", alert.application_id, alert.dedup_key),
+            "payload": {
+                "summary": alert.message,
+                "source": alert.application_name,
+                "severity": "error",
+                "custom_details": alert,
+            },
+        });
+        post_json_sync(PAGER_DUTY_EVENTS_URL, body.to_string()).map_err(|e| anyhow!(e))
+    }
+}
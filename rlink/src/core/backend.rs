@@ -1,5 +1,7 @@
 use std::fmt::{Debug, Display, Formatter};
 
+pub use crate::storage::keyed_state::{JoinSide, JoinState, TJoinState, TWindowState, WindowState};
+
 /// checkpoint backend storage type
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(tag = "type", content = "param")]
@@ -13,6 +15,12 @@ pub enum CheckpointBackend {
         /// storage table's name, if `None` use default table name
         table: Option<String>,
     },
+    /// storage in the object store, eg: `s3://bucket/path`, `gs://bucket/path`,
+    /// `az://container/path` or a local path, see `rlink::utils::object_store`
+    ObjectStore {
+        /// the root uri of all applications' checkpoints
+        uri: String,
+    },
 }
 
 impl Display for CheckpointBackend {
@@ -22,6 +30,7 @@ impl Display for CheckpointBackend {
             CheckpointBackend::MySql { endpoint, table } => {
                 write!(f, "MySql{{endpoint={}}}, table={:?}}}", endpoint, table)
             }
+            CheckpointBackend::ObjectStore { uri } => write!(f, "ObjectStore{{uri={}}}", uri),
         }
     }
 }
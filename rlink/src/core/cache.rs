@@ -0,0 +1,36 @@
+//! The distributed cache of the auxiliary files, eg: the ML models and the lookup files.
+//!
+//! The files are registered by `SystemProperties::register_cached_file` at the submission,
+//! served by the coordinator at `/api/cache/{name}`, downloaded and unpacked by the workers
+//! before opening the tasks, and located by `Context::cached_file`.
+
+use std::path::PathBuf;
+
+/// A file registered in the distributed cache
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CachedFile {
+    /// the name to locate the file, it's also the file name in the cache directory
+    pub name: String,
+    /// a local path or an object store uri readable by the coordinator,
+    /// see `rlink::utils::object_store`
+    pub uri: String,
+    /// unpack the `.tar`, `.tar.gz`, `.tgz` or `.zip` archive to the directory `name`
+    pub archive: bool,
+}
+
+impl CachedFile {
+    pub fn new(name: &str, uri: &str, archive: bool) -> Self {
+        CachedFile {
+            name: name.to_string(),
+            uri: uri.to_string(),
+            archive,
+        }
+    }
+}
+
+/// the local directory of the application's cached files
+pub(crate) fn cache_dir(application_id: &str) -> PathBuf {
+    crate::utils::process::work_space()
+        .join("cache")
+        .join(application_id)
+}
@@ -0,0 +1,139 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::utils::thread::async_sleep;
+
+/// the interval the async `cancelled` checks the token
+const ASYNC_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    reason: Mutex<Option<String>>,
+    condvar: Condvar,
+}
+
+impl CancellationState {
+    fn cancel(&self, reason: &str) -> bool {
+        let mut guard = self.reason.lock().unwrap();
+        if self.cancelled.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        *guard = Some(reason.to_string());
+        self.condvar.notify_all();
+        true
+    }
+}
+
+/// The cancellation of a task, cancelled by the runtime when the task is stopped or failed over,
+/// see `Context::cancellation_token`. The threads and the async loops started by the functions,
+/// eg: a consumer thread of a source or a write loop of a sink, should observe it and exit,
+/// a task which is still running after the grace period is aborted by the worker, eg:
+/// ```ignore
+/// let token = context.cancellation_token.clone();
+/// rlink::utils::thread::spawn("poller", move || {
+///     while !token.is_cancelled() {
+///         let records = client.poll();
+///         handover.produce_cancellable(records, &token);
+///     }
+/// });
+/// ```
+/// A `child` token is cancelled by itself or its parent, so a function can stop its own
+/// threads on `close` without cancelling the task.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    state: Arc<CancellationState>,
+    parent: Option<Arc<CancellationState>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// a token cancelled by itself or by `self`
+    pub fn child(&self) -> Self {
+        CancellationToken {
+            state: Arc::new(CancellationState::default()),
+            parent: Some(self.state.clone()),
+        }
+    }
+
+    /// cancel the token with the `reason`, return `false` if it's already cancelled
+    pub fn cancel(&self, reason: &str) -> bool {
+        self.state.cancel(reason)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+            || self
+                .parent
+                .as_ref()
+                .map(|parent| parent.cancelled.load(Ordering::SeqCst))
+                .unwrap_or(false)
+    }
+
+    /// the reason of the cancellation, `None` if not cancelled
+    pub fn reason(&self) -> Option<String> {
+        let reason = self.state.reason.lock().unwrap().clone();
+        reason.or_else(|| {
+            self.parent
+                .as_ref()
+                .and_then(|parent| parent.reason.lock().unwrap().clone())
+        })
+    }
+
+    /// sleep for the `timeout` or until the token is cancelled, return `true` if cancelled.
+    /// a child token wakes up on the cancellation of its parent in the check interval of
+    /// the async `cancelled`
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut guard = self.state.reason.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            if self.is_cancelled() || now >= deadline {
+                return self.is_cancelled();
+            }
+            let wait = match &self.parent {
+                Some(_) => (deadline - now).min(ASYNC_CHECK_INTERVAL),
+                None => deadline - now,
+            };
+            guard = self.state.condvar.wait_timeout(guard, wait).unwrap().0;
+        }
+    }
+
+    /// complete once the token is cancelled, to `select` with the other futures in a loop
+    pub async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            async_sleep(ASYNC_CHECK_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::cancellation::CancellationToken;
+
+    #[test]
+    pub fn cancellation_token_test() {
+        let token = CancellationToken::new();
+        let child = token.child();
+        assert!(!child.wait_timeout(Duration::from_millis(1)));
+
+        child.cancel("closed");
+        assert!(child.is_cancelled());
+        assert!(!token.is_cancelled());
+
+        let child = token.child();
+        let t = token.clone();
+        let handle = std::thread::spawn(move || t.wait_timeout(Duration::from_secs(10)));
+        assert!(token.cancel("stopped"));
+        assert!(!token.cancel("failed"));
+        assert!(handle.join().unwrap());
+        assert!(child.is_cancelled());
+        assert_eq!(child.reason(), Some("stopped".to_string()));
+    }
+}
@@ -1,4 +1,8 @@
+use std::convert::TryFrom;
 use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
 
@@ -44,6 +48,36 @@ impl Default for CheckpointHandle {
     }
 }
 
+/// statistics of an operator's snapshot, collected by the `Worker` during snapshotting
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CheckpointStats {
+    /// the duration(ms) of the synchronous part of the snapshot
+    pub sync_duration: u64,
+    /// the duration(ms) of the asynchronous part of the snapshot, it's filled when the
+    /// `AsyncSnapshot` returned by `CheckpointFunction::snapshot_state_async` finished,
+    /// always 0 for the synchronous snapshots
+    pub async_duration: u64,
+    /// the size(bytes) of the `CheckpointHandle`
+    pub state_size: u64,
+    /// the duration(ms) from the first `Barrier` reached to all `Barrier`s aligned,
+    /// only the first operator of a task aligns `Barrier`
+    pub alignment_duration: u64,
+    /// the size(bytes) of records processed during the `Barrier` alignment
+    pub alignment_bytes: u64,
+}
+
+impl CheckpointStats {
+    pub fn new(sync_duration: u64, handle: &CheckpointHandle) -> Self {
+        CheckpointStats {
+            sync_duration,
+            async_duration: 0,
+            state_size: handle.handle.len() as u64,
+            alignment_duration: 0,
+            alignment_bytes: 0,
+        }
+    }
+}
+
 /// descriptor a `Checkpoint`
 /// use for network communication between `Coordinator` and `Worker`
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,8 +87,180 @@ pub struct Checkpoint {
     pub checkpoint_id: CheckpointId,
     pub completed_checkpoint_id: Option<CheckpointId>,
     pub handle: CheckpointHandle,
+    #[serde(default)]
+    pub stats: CheckpointStats,
+    /// the reason the task declined the checkpoint, eg: the async snapshot failed,
+    /// a declined checkpoint is never completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub declined: Option<String>,
+    /// the checkpoint is a savepoint, it's stored with the checkpoint so the
+    /// `CheckpointRetention` still knows the savepoints after the `Coordinator` restarted
+    #[serde(default)]
+    pub savepoint: bool,
+}
+
+/// the retention policy of completed checkpoints,
+/// enforced by the `Coordinator` every time a checkpoint is completed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointRetention {
+    /// keep the last `N` completed checkpoints, `0` means disable the policy and only the `ttl` works
+    pub retained_checkpoints: usize,
+    /// keep the last savepoint of each day, even if it has been out of `retained_checkpoints`
+    pub retain_daily_savepoint: bool,
+}
+
+impl CheckpointRetention {
+    pub fn new(retained_checkpoints: usize, retain_daily_savepoint: bool) -> Self {
+        CheckpointRetention {
+            retained_checkpoints,
+            retain_daily_savepoint,
+        }
+    }
+
+    pub fn is_enable(&self) -> bool {
+        self.retained_checkpoints > 0
+    }
+}
+
+impl Default for CheckpointRetention {
+    fn default() -> Self {
+        CheckpointRetention::new(0, true)
+    }
 }
 
+/// The delivery guarantee of the records from the sources to the sinks,
+/// verified by the assertion mode, see `SystemProperties::set_assertion_mode`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum ProcessingGuarantee {
+    /// no duplicate, the records may be lost
+    AtMostOnce,
+    /// no gap, the records may be duplicated
+    AtLeastOnce,
+    /// neither gap nor duplicate
+    ExactlyOnce,
+}
+
+impl ProcessingGuarantee {
+    pub fn allow_gap(&self) -> bool {
+        *self == ProcessingGuarantee::AtMostOnce
+    }
+
+    pub fn allow_duplicate(&self) -> bool {
+        *self == ProcessingGuarantee::AtLeastOnce
+    }
+}
+
+/// a savepoint to restore a new application from, it's a completed checkpoint of another
+/// application with the same `application_name` and the same DAG,
+/// formatted as `{application_id}/{checkpoint_id}` in the `from_savepoint` arg
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SavepointPath {
+    pub application_id: String,
+    pub checkpoint_id: CheckpointId,
+}
+
+impl SavepointPath {
+    pub fn new(application_id: &str, checkpoint_id: CheckpointId) -> Self {
+        SavepointPath {
+            application_id: application_id.to_string(),
+            checkpoint_id,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for SavepointPath {
+    type Error = anyhow::Error;
+
+    fn try_from(path: &'a str) -> Result<Self, Self::Error> {
+        let (application_id, checkpoint_id) = path
+            .rsplit_once('/')
+            .ok_or_else(|| anyhow!("illegal savepoint path `{}`", path))?;
+        let checkpoint_id = u64::from_str(checkpoint_id)
+            .map_err(|_e| anyhow!("illegal checkpoint_id in savepoint path `{}`", path))?;
+        if application_id.is_empty() || checkpoint_id == 0 {
+            return Err(anyhow!("illegal savepoint path `{}`", path));
+        }
+
+        Ok(SavepointPath::new(
+            application_id,
+            CheckpointId(checkpoint_id),
+        ))
+    }
+}
+
+impl std::fmt::Display for SavepointPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.application_id, self.checkpoint_id.0)
+    }
+}
+
+/// the savepoint progress of an application, served by the `Coordinator`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SavepointStatus {
+    /// the latest completed savepoint
+    pub savepoint_id: Option<CheckpointId>,
+    /// the latest completed checkpoint, include the savepoints
+    pub completed_checkpoint_id: Option<CheckpointId>,
+}
+
+/// The progress of a checkpoint triggered by `POST /applications/{id}/checkpoints`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CheckpointTriggerState {
+    /// the barrier has not been emitted by all sources
+    Pending,
+    InProgress,
+    /// the checkpoint or a later one is completed
+    Completed,
+    /// a later checkpoint is started before the checkpoint completed
+    Aborted,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointTriggerStatus {
+    pub checkpoint_id: CheckpointId,
+    pub state: CheckpointTriggerState,
+    /// the latest completed checkpoint
+    pub completed_checkpoint_id: Option<CheckpointId>,
+}
+
+/// Request a checkpoint from a source at a business-defined boundary, eg: an "end of batch"
+/// marker in the data, see `Context::checkpoint_requester`. The barrier is emitted right after
+/// the record being read when requested, so the records before it(including the marker) are
+/// committed by the checkpoint, and the other sources emit the same barrier once the coordinator
+/// receives the request, eg:
+/// ```ignore
+/// impl Iterator for BatchIterator {
+///     type Item = Record;
+///     fn next(&mut self) -> Option<Record> {
+///         let record = self.reader.next()?;
+///         if is_end_of_batch(&record) {
+///             self.checkpoint_requester.request_checkpoint();
+///         }
+///         Some(record)
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CheckpointRequester {
+    requested: Arc<AtomicBool>,
+}
+
+impl CheckpointRequester {
+    /// request a checkpoint after the record being read, the requests before the barrier
+    /// emitted are merged
+    pub fn request_checkpoint(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn take(&self) -> bool {
+        self.requested.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// The asynchronous part of a snapshot, serialize and upload the copy of the state taken by
+/// `CheckpointFunction::snapshot_state_async`, run on the snapshot pool of the worker
+pub type AsyncSnapshot = Box<dyn FnOnce() -> anyhow::Result<CheckpointHandle> + Send>;
+
 pub trait CheckpointFunction {
     fn consult_version(
         &mut self,
@@ -76,4 +282,44 @@ pub trait CheckpointFunction {
     fn snapshot_state(&mut self, _context: &FunctionSnapshotContext) -> Option<CheckpointHandle> {
         None
     }
+
+    /// trigger the method when the `operator` operate a `Barrier` event, before the
+    /// `snapshot_state`. take a cheap copy of the state on the element loop, eg: clone an
+    /// `Arc` of the copy-on-write state, and return the `AsyncSnapshot` serializing it in the
+    /// background. the checkpoint is reported to the coordinator after the `AsyncSnapshot`
+    /// is done, and the `snapshot_state` is not called. an `AsyncSnapshot` failure declines
+    /// the checkpoint, it's never completed. return `None` to snapshot synchronously
+    fn snapshot_state_async(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<AsyncSnapshot> {
+        None
+    }
+
+    /// trigger the method when the checkpoint is acknowledged by all operators and persisted
+    /// by the coordinator, the `checkpoint_id` and all checkpoints before it are durable.
+    /// the sink can make the data written before the checkpoint visible, such as commit the transaction.
+    /// the notification is delivered asynchronously, some checkpoints may be skipped
+    fn notify_checkpoint_complete(&mut self, _checkpoint_id: CheckpointId) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use crate::core::checkpoint::SavepointPath;
+    use crate::core::runtime::CheckpointId;
+
+    #[test]
+    pub fn savepoint_path_test() {
+        let path = SavepointPath::try_from("application-1591174445599/1620000000000").unwrap();
+        assert_eq!(
+            path,
+            SavepointPath::new("application-1591174445599", CheckpointId(1620000000000))
+        );
+        assert_eq!(path.to_string(), "application-1591174445599/1620000000000");
+
+        assert!(SavepointPath::try_from("1620000000000").is_err());
+        assert!(SavepointPath::try_from("application-1591174445599/abc").is_err());
+    }
 }
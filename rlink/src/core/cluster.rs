@@ -29,6 +29,29 @@ pub struct ClusterConfig {
 
     pub task_manager_bind_ip: String,
     pub task_manager_work_dir: String,
+
+    /// the max number of tasks run on a TaskManager, shared by all applications
+    /// in the session cluster. unlimited if not set
+    #[serde(default)]
+    pub task_manager_slots: Option<usize>,
+    /// kill the applications with lower priority to free the slots
+    /// when a higher priority application can't be scheduled
+    #[serde(default)]
+    pub preemption: bool,
+    /// how the TaskManager launches the tasks
+    #[serde(default)]
+    pub task_launcher: TaskLauncher,
+}
+
+/// The launcher of the tasks on the TaskManager
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskLauncher {
+    /// launched by the `config/run_task.sh` of the TaskManager, it downloads the executable file
+    #[default]
+    Script,
+    /// the TaskManager downloads the executable file from the JobManager once per application
+    /// and launches it in a child process, no script or tool is required on the nodes
+    Process,
 }
 
 impl ClusterConfig {
@@ -39,6 +62,10 @@ impl ClusterConfig {
 
             task_manager_bind_ip: "".to_string(),
             task_manager_work_dir: "./".to_string(),
+
+            task_manager_slots: None,
+            preemption: false,
+            task_launcher: TaskLauncher::Script,
         }
     }
 }
@@ -148,7 +175,7 @@ impl<T> Into<StdResponse<T>> for anyhow::Result<T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::core::cluster::{ClusterConfig, MetadataStorageType};
+    use crate::core::cluster::{ClusterConfig, MetadataStorageType, TaskLauncher};
 
     #[test]
     pub fn ser_cluster_config_test() {
@@ -160,6 +187,9 @@ mod tests {
             metadata_storage: MetadataStorageType::Memory,
             task_manager_bind_ip: "0.0.0.0".to_string(),
             task_manager_work_dir: "/data/rlink/application".to_string(),
+            task_manager_slots: Some(8),
+            preemption: false,
+            task_launcher: TaskLauncher::Process,
         };
 
         let yaml = serde_yaml::to_string(&config).unwrap();
@@ -168,5 +198,6 @@ mod tests {
         let config1: ClusterConfig = serde_yaml::from_str(yaml.as_str()).unwrap();
 
         assert!(config.metadata_storage.eq(&config1.metadata_storage));
+        assert_eq!(config.task_launcher, config1.task_launcher);
     }
 }
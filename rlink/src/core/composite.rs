@@ -0,0 +1,284 @@
+//! Run several independent applications in one process, eg: the producer and the consumer
+//! applications of an integration test in a local cluster:
+//! ```ignore
+//! let apps = CompositeStreamApp::new("integration_test")
+//!     .register("producer", ProducerApp {})
+//!     .register("consumer", ConsumerApp {});
+//! rlink::core::env::execute(apps);
+//! ```
+//!
+//! The members are built as the disconnected pipelines of one stream graph, so they are
+//! scheduled as the separate jobs of the same cluster, and their checkpoints and metrics
+//! are kept apart by the operator ids and the job ids.
+//!
+//! Each member prepares its own properties, which are kept in the namespace
+//! `apps.{name}` and passed to its `build_stream`. The process-wide settings are merged from
+//! the members in the registration order, the first member setting a key wins, and the
+//! application name is the composite's name.
+//!
+//! The members share one checkpoint coordinator: the checkpoint interval, storage, ttl and
+//! retention are the application's, a checkpoint is triggered on the sources of all members
+//! and completed once all operators of all members acknowledged it, so a slow member delays
+//! the checkpoints of the others. The members setting different checkpoint properties are
+//! rejected, set them once with the same value or in the base properties.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::core::env::{StreamApp, StreamExecutionEnvironment};
+use crate::core::properties::{is_checkpoint_property, Properties, SystemProperties};
+use crate::core::runtime::{ClusterDescriptor, OperatorId};
+
+/// the namespace of the properties of the members
+pub const COMPOSITE_PROPERTIES_PREFIX: &str = "apps";
+
+/// the object safe part of `StreamApp`
+trait MemberApp: Send + Sync {
+    fn prepare_properties(&self, properties: &mut Properties);
+
+    fn build_stream(&self, properties: &Properties, env: &mut StreamExecutionEnvironment);
+
+    fn pre_worker_startup(&self, cluster_descriptor: &ClusterDescriptor);
+}
+
+impl<S> MemberApp for S
+where
+    S: StreamApp,
+{
+    fn prepare_properties(&self, properties: &mut Properties) {
+        StreamApp::prepare_properties(self, properties)
+    }
+
+    fn build_stream(&self, properties: &Properties, env: &mut StreamExecutionEnvironment) {
+        StreamApp::build_stream(self, properties, env)
+    }
+
+    fn pre_worker_startup(&self, cluster_descriptor: &ClusterDescriptor) {
+        StreamApp::pre_worker_startup(self, cluster_descriptor)
+    }
+}
+
+#[derive(Clone)]
+pub struct CompositeStreamApp {
+    application_name: String,
+    members: Vec<(String, Arc<dyn MemberApp>)>,
+}
+
+impl CompositeStreamApp {
+    pub fn new(application_name: &str) -> Self {
+        CompositeStreamApp {
+            application_name: application_name.to_string(),
+            members: Vec::new(),
+        }
+    }
+
+    /// add a member application, the `name` must be unique in the composite
+    pub fn register<S>(mut self, name: &str, stream_app: S) -> Self
+    where
+        S: StreamApp + 'static,
+    {
+        if name.is_empty() || name.contains('.') {
+            panic!(
+                "invalid member name `{}`, must be non-empty without `.`",
+                name
+            );
+        }
+        if self.members.iter().any(|(member, _)| member.eq(name)) {
+            panic!("the member `{}` is registered", name);
+        }
+
+        self.members.push((name.to_string(), Arc::new(stream_app)));
+        self
+    }
+
+    pub fn member_names(&self) -> Vec<&str> {
+        self.members.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    fn member_prefix(name: &str) -> String {
+        format!("{}.{}", COMPOSITE_PROPERTIES_PREFIX, name)
+    }
+}
+
+impl StreamApp for CompositeStreamApp {
+    fn prepare_properties(&self, properties: &mut Properties) {
+        let base = properties.clone();
+        for (name, member) in &self.members {
+            let mut member_properties = base.clone();
+            member.prepare_properties(&mut member_properties);
+
+            for (key, value) in member_properties.as_map() {
+                match properties.as_map().get(key) {
+                    Some(v) if v.eq(value) => {}
+                    Some(v) if base.as_map().get(key) != Some(v) && is_checkpoint_property(key) => {
+                        panic!(
+                            "the checkpoint property {}={} of the member `{}` conflicts with {}, \
+                            the members share one checkpoint coordinator",
+                            key, value, name, v
+                        )
+                    }
+                    Some(v) if base.as_map().get(key) != Some(v) => warn!(
+                        "the property {}={} of the member `{}` is ignored, the value is {}",
+                        key, value, name, v
+                    ),
+                    _ => properties.set_str(key, value),
+                }
+            }
+            properties.extend_sub_properties(Self::member_prefix(name).as_str(), member_properties);
+        }
+
+        properties.set_application_name(self.application_name.as_str());
+    }
+
+    fn build_stream(&self, properties: &Properties, env: &mut StreamExecutionEnvironment) {
+        for (name, member) in &self.members {
+            let member_properties =
+                properties.to_sub_properties(Self::member_prefix(name).as_str());
+
+            let existing: HashSet<OperatorId> = env
+                .stream_manager
+                .operator_names()
+                .into_iter()
+                .map(|(operator_id, _)| operator_id)
+                .collect();
+            member.build_stream(&member_properties, env);
+
+            // name the operators of the member by `{name}/{operator}` on the dashboard
+            for (operator_id, display_name) in env.stream_manager.operator_names() {
+                if !existing.contains(&operator_id) {
+                    env.stream_manager
+                        .set_name(operator_id, format!("{}/{}", name, display_name).as_str());
+                }
+            }
+        }
+    }
+
+    fn pre_worker_startup(&self, cluster_descriptor: &ClusterDescriptor) {
+        for (_name, member) in &self.members {
+            member.pre_worker_startup(cluster_descriptor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::composite::CompositeStreamApp;
+    use crate::core::env::{StreamApp, StreamExecutionEnvironment};
+    use crate::core::properties::{Properties, SystemProperties};
+
+    #[derive(Clone)]
+    struct PropertiesApp {
+        value: &'static str,
+    }
+
+    impl StreamApp for PropertiesApp {
+        fn prepare_properties(&self, properties: &mut Properties) {
+            properties.set_str("member.value", self.value);
+            properties.set_str("shared.value", self.value);
+        }
+
+        fn build_stream(&self, properties: &Properties, _env: &mut StreamExecutionEnvironment) {
+            assert_eq!(properties.get_string("member.value").unwrap(), self.value);
+        }
+    }
+
+    #[test]
+    pub fn member_properties_test() {
+        let apps = CompositeStreamApp::new("composite")
+            .register("a", PropertiesApp { value: "1" })
+            .register("b", PropertiesApp { value: "2" });
+        assert_eq!(apps.member_names(), vec!["a", "b"]);
+
+        let mut properties = Properties::new();
+        properties.set_str("base.value", "0");
+        apps.prepare_properties(&mut properties);
+
+        // the members see the base properties and their own settings only
+        let a = properties.to_sub_properties("apps.a");
+        assert_eq!(a.get_string("base.value").unwrap(), "0");
+        assert_eq!(a.get_string("member.value").unwrap(), "1");
+        let b = properties.to_sub_properties("apps.b");
+        assert_eq!(b.get_string("member.value").unwrap(), "2");
+        assert_eq!(b.get_string("shared.value").unwrap(), "2");
+
+        // the process-wide value is set by the first member
+        assert_eq!(properties.get_string("shared.value").unwrap(), "1");
+        assert_eq!(properties.get_application_name(), "composite");
+
+        let mut env = StreamExecutionEnvironment::new();
+        apps.build_stream(&properties, &mut env);
+    }
+
+    #[derive(Clone)]
+    struct CheckpointApp {
+        interval: Duration,
+    }
+
+    impl StreamApp for CheckpointApp {
+        fn prepare_properties(&self, properties: &mut Properties) {
+            properties.set_checkpoint_interval(self.interval);
+        }
+
+        fn build_stream(&self, _properties: &Properties, _env: &mut StreamExecutionEnvironment) {}
+    }
+
+    #[test]
+    pub fn checkpoint_properties_test() {
+        let apps = CompositeStreamApp::new("composite")
+            .register(
+                "a",
+                CheckpointApp {
+                    interval: Duration::from_secs(30),
+                },
+            )
+            .register(
+                "b",
+                CheckpointApp {
+                    interval: Duration::from_secs(30),
+                },
+            );
+        let mut properties = Properties::new();
+        apps.prepare_properties(&mut properties);
+        assert_eq!(
+            properties.get_checkpoint_interval().unwrap(),
+            Duration::from_secs(30)
+        );
+
+        // the members share one checkpoint coordinator
+        let conflict = std::panic::catch_unwind(|| {
+            let apps = CompositeStreamApp::new("composite")
+                .register(
+                    "a",
+                    CheckpointApp {
+                        interval: Duration::from_secs(30),
+                    },
+                )
+                .register(
+                    "b",
+                    CheckpointApp {
+                        interval: Duration::from_secs(60),
+                    },
+                );
+            let mut properties = Properties::new();
+            apps.prepare_properties(&mut properties);
+        });
+        assert!(conflict.is_err());
+    }
+
+    #[test]
+    pub fn register_invalid_name_test() {
+        let duplicate = std::panic::catch_unwind(|| {
+            CompositeStreamApp::new("composite")
+                .register("a", PropertiesApp { value: "1" })
+                .register("a", PropertiesApp { value: "2" })
+        });
+        assert!(duplicate.is_err());
+
+        let dotted = std::panic::catch_unwind(|| {
+            CompositeStreamApp::new("composite").register("a.b", PropertiesApp { value: "1" })
+        });
+        assert!(dotted.is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! The typed configuration of an application, see `ExecutionEnvironment::builder`.
+//!
+//! It's applied to the application properties after `StreamApp::prepare_properties`, so the
+//! properties of the settings not configured here are kept, and the configured settings
+//! override the properties with a warning.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::core::backend::{CheckpointBackend, KeyedStateBackend};
+use crate::core::error::ErrorClass;
+use crate::core::properties::{Properties, SystemProperties};
+
+/// the min interval of the checkpoints
+pub const MIN_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How the application is restarted when a task fails
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RestartStrategy {
+    /// restart forever
+    Always,
+    /// terminate if the application restarts `max_restarts` times in the `interval`,
+    /// see `SystemProperties::set_crash_loop_max_restarts`
+    FailureRate {
+        max_restarts: u32,
+        interval: Duration,
+    },
+}
+
+/// The validated configuration built by `ExecutionConfigBuilder`
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionConfig {
+    application_name: Option<String>,
+    checkpoint_interval: Option<Duration>,
+    checkpoint_backend: Option<CheckpointBackend>,
+    checkpoint_ttl: Option<Duration>,
+    keyed_state_backend: Option<KeyedStateBackend>,
+    restart_strategy: Option<RestartStrategy>,
+    fatal_error_classes: Option<Vec<ErrorClass>>,
+    max_parallelism: Option<u16>,
+}
+
+impl ExecutionConfig {
+    pub fn builder() -> ExecutionConfigBuilder {
+        ExecutionConfigBuilder::default()
+    }
+
+    pub fn application_name(&self) -> Option<&str> {
+        self.application_name.as_deref()
+    }
+
+    pub fn checkpoint_interval(&self) -> Option<Duration> {
+        self.checkpoint_interval
+    }
+
+    pub fn checkpoint_backend(&self) -> Option<&CheckpointBackend> {
+        self.checkpoint_backend.as_ref()
+    }
+
+    pub fn checkpoint_ttl(&self) -> Option<Duration> {
+        self.checkpoint_ttl
+    }
+
+    pub fn keyed_state_backend(&self) -> Option<KeyedStateBackend> {
+        self.keyed_state_backend
+    }
+
+    pub fn restart_strategy(&self) -> Option<&RestartStrategy> {
+        self.restart_strategy.as_ref()
+    }
+
+    pub fn fatal_error_classes(&self) -> Option<&[ErrorClass]> {
+        self.fatal_error_classes.as_deref()
+    }
+
+    pub fn max_parallelism(&self) -> Option<u16> {
+        self.max_parallelism
+    }
+
+    /// set the configured settings to the `properties`, the overridden properties are warned
+    pub(crate) fn apply(&self, properties: &mut Properties) {
+        let previous: HashMap<String, String> = properties.as_map().clone();
+
+        if let Some(application_name) = &self.application_name {
+            properties.set_application_name(application_name.as_str());
+        }
+        if let Some(checkpoint_interval) = self.checkpoint_interval {
+            properties.set_checkpoint_interval(checkpoint_interval);
+        }
+        if let Some(checkpoint_backend) = &self.checkpoint_backend {
+            properties.set_checkpoint(checkpoint_backend.clone());
+        }
+        if let Some(checkpoint_ttl) = self.checkpoint_ttl {
+            properties.set_checkpoint_ttl(checkpoint_ttl);
+        }
+        if let Some(keyed_state_backend) = self.keyed_state_backend {
+            properties.set_keyed_state_backend(keyed_state_backend);
+        }
+        match &self.restart_strategy {
+            Some(RestartStrategy::Always) => properties.set_crash_loop_max_restarts(0),
+            Some(RestartStrategy::FailureRate {
+                max_restarts,
+                interval,
+            }) => {
+                properties.set_crash_loop_max_restarts(*max_restarts);
+                properties.set_crash_loop_interval(*interval);
+            }
+            None => {}
+        }
+        if let Some(fatal_error_classes) = &self.fatal_error_classes {
+            properties.set_fatal_error_classes(fatal_error_classes.clone());
+        }
+        if let Some(max_parallelism) = self.max_parallelism {
+            properties.set_max_parallelism(max_parallelism);
+        }
+
+        for (key, value) in properties.as_map() {
+            if let Some(previous_value) = previous.get(key) {
+                if previous_value != value {
+                    warn!(
+                        "the property {}={} is overridden by the execution config: {}",
+                        key, previous_value, value
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionConfigBuilder {
+    config: ExecutionConfig,
+}
+
+impl ExecutionConfigBuilder {
+    pub fn application_name(mut self, application_name: &str) -> Self {
+        self.config.application_name = Some(application_name.to_string());
+        self
+    }
+
+    pub fn checkpoint_interval(mut self, checkpoint_interval: Duration) -> Self {
+        self.config.checkpoint_interval = Some(checkpoint_interval);
+        self
+    }
+
+    pub fn checkpoint_backend(mut self, checkpoint_backend: CheckpointBackend) -> Self {
+        self.config.checkpoint_backend = Some(checkpoint_backend);
+        self
+    }
+
+    pub fn checkpoint_ttl(mut self, checkpoint_ttl: Duration) -> Self {
+        self.config.checkpoint_ttl = Some(checkpoint_ttl);
+        self
+    }
+
+    pub fn state_backend(mut self, keyed_state_backend: KeyedStateBackend) -> Self {
+        self.config.keyed_state_backend = Some(keyed_state_backend);
+        self
+    }
+
+    pub fn restart_strategy(mut self, restart_strategy: RestartStrategy) -> Self {
+        self.config.restart_strategy = Some(restart_strategy);
+        self
+    }
+
+    /// terminate instead of restarting when a task fails with these classes
+    pub fn fatal_error_classes(mut self, fatal_error_classes: Vec<ErrorClass>) -> Self {
+        self.config.fatal_error_classes = Some(fatal_error_classes);
+        self
+    }
+
+    pub fn max_parallelism(mut self, max_parallelism: u16) -> Self {
+        self.config.max_parallelism = Some(max_parallelism);
+        self
+    }
+
+    /// validate the settings, all problems are reported at once
+    pub fn build(self) -> anyhow::Result<ExecutionConfig> {
+        let config = self.config;
+        let mut errors = Vec::new();
+
+        if let Some(application_name) = &config.application_name {
+            if application_name.trim().is_empty() {
+                errors.push("the application name is empty".to_string());
+            }
+        }
+        if let Some(checkpoint_interval) = config.checkpoint_interval {
+            if checkpoint_interval < MIN_CHECKPOINT_INTERVAL {
+                errors.push(format!(
+                    "the checkpoint interval {:?} is less than {:?}",
+                    checkpoint_interval, MIN_CHECKPOINT_INTERVAL
+                ));
+            }
+            if let Some(checkpoint_ttl) = config.checkpoint_ttl {
+                if checkpoint_ttl <= checkpoint_interval {
+                    errors.push(format!(
+                        "the checkpoint ttl {:?} is not greater than the interval {:?}",
+                        checkpoint_ttl, checkpoint_interval
+                    ));
+                }
+            }
+        }
+        match &config.checkpoint_backend {
+            Some(CheckpointBackend::MySql { endpoint, .. }) if endpoint.trim().is_empty() => {
+                errors.push("the mysql endpoint of the checkpoint backend is empty".to_string())
+            }
+            Some(CheckpointBackend::ObjectStore { uri }) if uri.trim().is_empty() => {
+                errors.push("the object store uri of the checkpoint backend is empty".to_string())
+            }
+            _ => {}
+        }
+        if let Some(RestartStrategy::FailureRate {
+            max_restarts,
+            interval,
+        }) = &config.restart_strategy
+        {
+            if *max_restarts == 0 || interval.as_millis() == 0 {
+                errors.push(
+                    "the max restarts and the interval of the failure rate must be positive, \
+                     use `RestartStrategy::Always` to restart forever"
+                        .to_string(),
+                );
+            }
+        }
+        if config.max_parallelism == Some(0) {
+            errors.push("the max parallelism is 0".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(anyhow!("invalid execution config: {}", errors.join("; ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::backend::CheckpointBackend;
+    use crate::core::config::{ExecutionConfig, RestartStrategy};
+    use crate::core::properties::{Properties, SystemProperties};
+
+    #[test]
+    pub fn execution_config_test() {
+        let errors = ExecutionConfig::builder()
+            .checkpoint_interval(Duration::from_millis(10))
+            .checkpoint_backend(CheckpointBackend::ObjectStore {
+                uri: "".to_string(),
+            })
+            .build()
+            .unwrap_err()
+            .to_string();
+        assert!(errors.contains("checkpoint interval"));
+        assert!(errors.contains("object store uri"));
+
+        let config = ExecutionConfig::builder()
+            .checkpoint_interval(Duration::from_secs(30))
+            .restart_strategy(RestartStrategy::FailureRate {
+                max_restarts: 3,
+                interval: Duration::from_secs(60),
+            })
+            .build()
+            .unwrap();
+
+        let mut properties = Properties::new();
+        properties.set_checkpoint_interval(Duration::from_secs(10));
+        properties.set_max_parallelism(16);
+        config.apply(&mut properties);
+
+        assert_eq!(
+            properties.get_checkpoint_interval().unwrap(),
+            Duration::from_secs(30)
+        );
+        assert_eq!(properties.get_crash_loop_max_restarts().unwrap(), 3);
+        // the settings not configured fall back to the properties
+        assert_eq!(properties.get_max_parallelism().unwrap(), 16);
+    }
+}
@@ -1,15 +1,24 @@
 use std::fmt::Debug;
 use std::rc::Rc;
+use std::time::Duration;
 
+use crate::channel::{ChannelBaseOn, ChannelOptions};
 use crate::core::env::StreamManager;
 use crate::core::function::{
     CoProcessFunction, FilterFunction, FlatMapFunction, InputFormat, KeySelectorFunction,
     OutputFormat, ReduceFunction,
 };
 use crate::core::operator::{FunctionCreator, StreamOperator};
+use crate::core::retry::RetryPolicy;
 use crate::core::runtime::OperatorId;
+use crate::core::state_limit::StateLimit;
 use crate::core::watermark::WatermarkStrategy;
-use crate::core::window::WindowAssigner;
+use crate::core::window::{WindowAssigner, WindowEmitStrategy};
+use crate::core::write_ahead_log::WriteAheadLog;
+use crate::functions::filter::{DynamicFilterFunction, RulesSource};
+use crate::functions::flat_map::{TapFlatMapFunction, UidFlatMapFunction};
+use crate::functions::key_selector::GlobalKeySelector;
+use crate::functions::reduce::AllWindowReduceFunction;
 use crate::functions::system::window_base_reduce::WindowBaseReduceFunction;
 
 /// A DataStream represents a stream of elements of the same type. A DataStream can be transformed
@@ -23,10 +32,32 @@ pub trait TDataStream {
     where
         F: FilterFunction + 'static;
 
+    /// Mirror a sampled fraction of the passing records, and the watermarks and barriers
+    /// into a ring buffer named `name`, which can be queried by the worker's web api.
+    /// use `flat_map(TapFlatMapFunction::new(name))` to customize the sample ratio and capacity.
+    fn tap(self, name: &str) -> DataStream;
+
+    /// Append a cluster-wide unique `UInt64` id field `field_name` to the records by a
+    /// snowflake-style generator, see `UidFlatMapFunction`
+    fn assign_uid(self, field_name: &str) -> DataStream;
+
+    /// Filter the records by the `FilterRules` loaded from the `rules_source`, the rules are
+    /// hot-reloaded without redeploying, eg: the blocklists and the sampling overrides.
+    /// use `filter(DynamicFilterFunction::new(rules_source))` to customize the reload interval,
+    /// or `DynamicFilterCoProcessFunction` to update the rules by a broadcast stream
+    fn dynamic_filter(self, rules_source: RulesSource) -> DataStream;
+
     fn key_by<F>(self, key_selector: F) -> KeyedStream
     where
         F: KeySelectorFunction + 'static;
 
+    /// Windows the non-keyed stream, all records are funneled to a single task,
+    /// so it fits the global top-N or alerting on a low-volume stream.
+    /// the input rate of the single task is reported by the `WindowAll_InputRate` gauge.
+    fn window_all<W>(self, window_assigner: W) -> AllWindowedStream
+    where
+        W: WindowAssigner + 'static;
+
     fn assign_timestamps_and_watermarks<W>(self, timestamp_and_watermark_assigner: W) -> DataStream
     where
         W: WatermarkStrategy + 'static;
@@ -108,6 +139,40 @@ impl DataStream {
     pub(crate) fn new(data_stream: StreamBuilder) -> Self {
         DataStream { data_stream }
     }
+
+    /// Override the capacity and the implementation of the input channel of the job
+    /// which the current operator belongs to, eg: a bigger buffer before a slow sink.
+    /// the larger `channel_size` wins if multiple operators in a job are set
+    pub fn channel_options(self, channel_size: usize, base_on: ChannelBaseOn) -> Self {
+        DataStream::new(self.data_stream.channel_options(channel_size, base_on))
+    }
+
+    /// Set a meaningful name of the current operator, eg: "clean and enrich",
+    /// it's shown in the metrics tags and the coordinator api instead of the function name
+    pub fn name(self, name: &str) -> Self {
+        DataStream::new(self.data_stream.name(name))
+    }
+
+    /// Set the description of the current operator, shown in the coordinator api
+    pub fn description(self, description: &str) -> Self {
+        DataStream::new(self.data_stream.description(description))
+    }
+
+    /// Set the processing timeout of a record in the current operator, the task is failed
+    /// with a dump of the operator if it makes no progress within the timeout
+    pub fn processing_timeout(self, timeout: Duration) -> Self {
+        DataStream::new(self.data_stream.processing_timeout(timeout))
+    }
+
+    /// Retry the `flat_map` of a record in the current operator by the `retry_policy`
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        DataStream::new(self.data_stream.retry_policy(retry_policy))
+    }
+
+    /// Limit the keyed state of the current operator in a task, see `StateLimit`
+    pub fn state_limit(self, state_limit: StateLimit) -> Self {
+        DataStream::new(self.data_stream.state_limit(state_limit))
+    }
 }
 
 impl TDataStream for DataStream {
@@ -125,6 +190,18 @@ impl TDataStream for DataStream {
         self.data_stream.filter(filter)
     }
 
+    fn tap(self, name: &str) -> DataStream {
+        self.data_stream.tap(name)
+    }
+
+    fn assign_uid(self, field_name: &str) -> DataStream {
+        self.data_stream.assign_uid(field_name)
+    }
+
+    fn dynamic_filter(self, rules_source: RulesSource) -> DataStream {
+        self.data_stream.dynamic_filter(rules_source)
+    }
+
     fn key_by<F>(self, key_selector: F) -> KeyedStream
     where
         F: KeySelectorFunction + 'static,
@@ -132,6 +209,13 @@ impl TDataStream for DataStream {
         self.data_stream.key_by(key_selector)
     }
 
+    fn window_all<W>(self, window_assigner: W) -> AllWindowedStream
+    where
+        W: WindowAssigner + 'static,
+    {
+        self.data_stream.window_all(window_assigner)
+    }
+
     fn assign_timestamps_and_watermarks<W>(self, timestamp_and_watermark_assigner: W) -> DataStream
     where
         W: WatermarkStrategy + 'static,
@@ -168,6 +252,38 @@ impl ConnectedStreams {
             parent_pipeline_ids: dependency_pipeline_ids,
         }
     }
+
+    /// see `DataStream::channel_options`
+    pub fn channel_options(self, channel_size: usize, base_on: ChannelBaseOn) -> Self {
+        ConnectedStreams {
+            co_stream: self.co_stream.channel_options(channel_size, base_on),
+            parent_pipeline_ids: self.parent_pipeline_ids,
+        }
+    }
+
+    /// see `DataStream::name`
+    pub fn name(self, name: &str) -> Self {
+        ConnectedStreams {
+            co_stream: self.co_stream.name(name),
+            parent_pipeline_ids: self.parent_pipeline_ids,
+        }
+    }
+
+    /// see `DataStream::description`
+    pub fn description(self, description: &str) -> Self {
+        ConnectedStreams {
+            co_stream: self.co_stream.description(description),
+            parent_pipeline_ids: self.parent_pipeline_ids,
+        }
+    }
+
+    /// see `DataStream::processing_timeout`
+    pub fn processing_timeout(self, timeout: Duration) -> Self {
+        ConnectedStreams {
+            co_stream: self.co_stream.processing_timeout(timeout),
+            parent_pipeline_ids: self.parent_pipeline_ids,
+        }
+    }
 }
 
 impl TConnectedStreams for ConnectedStreams {
@@ -195,6 +311,26 @@ impl KeyedStream {
     pub(crate) fn new(keyed_stream: StreamBuilder) -> Self {
         KeyedStream { keyed_stream }
     }
+
+    /// see `DataStream::channel_options`
+    pub fn channel_options(self, channel_size: usize, base_on: ChannelBaseOn) -> Self {
+        KeyedStream::new(self.keyed_stream.channel_options(channel_size, base_on))
+    }
+
+    /// see `DataStream::name`
+    pub fn name(self, name: &str) -> Self {
+        KeyedStream::new(self.keyed_stream.name(name))
+    }
+
+    /// see `DataStream::description`
+    pub fn description(self, description: &str) -> Self {
+        KeyedStream::new(self.keyed_stream.description(description))
+    }
+
+    /// see `DataStream::processing_timeout`
+    pub fn processing_timeout(self, timeout: Duration) -> Self {
+        KeyedStream::new(self.keyed_stream.processing_timeout(timeout))
+    }
 }
 
 impl TKeyedStream for KeyedStream {
@@ -216,11 +352,25 @@ impl TKeyedStream for KeyedStream {
 #[derive(Debug)]
 pub struct WindowedStream {
     windowed_stream: StreamBuilder,
+    emit_strategy: WindowEmitStrategy,
+    /// see `WindowAssigner::pane_size`
+    pane_size: Option<u64>,
 }
 
 impl WindowedStream {
-    pub(crate) fn new(windowed_stream: StreamBuilder) -> Self {
-        WindowedStream { windowed_stream }
+    pub(crate) fn new(windowed_stream: StreamBuilder, pane_size: Option<u64>) -> Self {
+        WindowedStream {
+            windowed_stream,
+            emit_strategy: WindowEmitStrategy::default(),
+            pane_size,
+        }
+    }
+
+    /// Set when the results of the windows are emitted and whether the fired state is purged,
+    /// default `WindowEmitStrategy::on_close`
+    pub fn emit_strategy(mut self, emit_strategy: WindowEmitStrategy) -> Self {
+        self.emit_strategy = emit_strategy;
+        self
     }
 }
 
@@ -229,7 +379,35 @@ impl TWindowedStream for WindowedStream {
     where
         F: ReduceFunction + 'static,
     {
-        self.windowed_stream.reduce(reduce)
+        self.windowed_stream
+            .window_reduce(reduce, self.emit_strategy, self.pane_size)
+    }
+}
+
+#[derive(Debug)]
+pub struct AllWindowedStream {
+    windowed_stream: WindowedStream,
+}
+
+impl AllWindowedStream {
+    pub(crate) fn new(windowed_stream: WindowedStream) -> Self {
+        AllWindowedStream { windowed_stream }
+    }
+
+    /// See `WindowedStream::emit_strategy`
+    pub fn emit_strategy(self, emit_strategy: WindowEmitStrategy) -> Self {
+        AllWindowedStream::new(self.windowed_stream.emit_strategy(emit_strategy))
+    }
+}
+
+impl TWindowedStream for AllWindowedStream {
+    /// the `reduce` runs in a single task whatever its `parallelism`
+    fn reduce<F>(self, reduce: F) -> DataStream
+    where
+        F: ReduceFunction + 'static,
+    {
+        self.windowed_stream
+            .reduce(AllWindowReduceFunction::new(Box::new(reduce)))
     }
 }
 
@@ -242,6 +420,36 @@ impl SinkStream {
     pub(crate) fn new(end_stream: StreamBuilder) -> Self {
         SinkStream { end_stream }
     }
+
+    /// Override the capacity and the implementation of the input channel of the sink's job
+    pub fn channel_options(self, channel_size: usize, base_on: ChannelBaseOn) -> Self {
+        SinkStream::new(self.end_stream.channel_options(channel_size, base_on))
+    }
+
+    /// see `DataStream::name`
+    pub fn name(self, name: &str) -> Self {
+        SinkStream::new(self.end_stream.name(name))
+    }
+
+    /// see `DataStream::description`
+    pub fn description(self, description: &str) -> Self {
+        SinkStream::new(self.end_stream.description(description))
+    }
+
+    /// see `DataStream::processing_timeout`
+    pub fn processing_timeout(self, timeout: Duration) -> Self {
+        SinkStream::new(self.end_stream.processing_timeout(timeout))
+    }
+
+    /// Retry the `write_record` of a record in the current sink by the `retry_policy`
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        SinkStream::new(self.end_stream.retry_policy(retry_policy))
+    }
+
+    /// Upgrade the current sink to at-least-once by a write-ahead log, see `WriteAheadLog`
+    pub fn write_ahead_log(self, write_ahead_log: WriteAheadLog) -> Self {
+        SinkStream::new(self.end_stream.write_ahead_log(write_ahead_log))
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -286,6 +494,49 @@ impl StreamBuilder {
             stream_manager,
         }
     }
+
+    pub fn channel_options(self, channel_size: usize, base_on: ChannelBaseOn) -> Self {
+        self.stream_manager.set_channel_options(
+            self.cur_operator_id,
+            ChannelOptions::new(channel_size, base_on),
+        );
+        self
+    }
+
+    pub fn name(self, name: &str) -> Self {
+        self.stream_manager.set_name(self.cur_operator_id, name);
+        self
+    }
+
+    pub fn description(self, description: &str) -> Self {
+        self.stream_manager
+            .set_description(self.cur_operator_id, description);
+        self
+    }
+
+    pub fn processing_timeout(self, timeout: Duration) -> Self {
+        self.stream_manager
+            .set_processing_timeout(self.cur_operator_id, timeout);
+        self
+    }
+
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        self.stream_manager
+            .set_retry_policy(self.cur_operator_id, retry_policy);
+        self
+    }
+
+    pub fn state_limit(self, state_limit: StateLimit) -> Self {
+        self.stream_manager
+            .set_state_limit(self.cur_operator_id, state_limit);
+        self
+    }
+
+    pub fn write_ahead_log(self, write_ahead_log: WriteAheadLog) -> Self {
+        self.stream_manager
+            .set_write_ahead_log(self.cur_operator_id, write_ahead_log);
+        self
+    }
 }
 
 impl TDataStream for StreamBuilder {
@@ -317,6 +568,18 @@ impl TDataStream for StreamBuilder {
         DataStream::new(self)
     }
 
+    fn tap(self, name: &str) -> DataStream {
+        self.flat_map(TapFlatMapFunction::new(name))
+    }
+
+    fn assign_uid(self, field_name: &str) -> DataStream {
+        self.flat_map(UidFlatMapFunction::new(field_name))
+    }
+
+    fn dynamic_filter(self, rules_source: RulesSource) -> DataStream {
+        self.filter(DynamicFilterFunction::new(rules_source))
+    }
+
     fn key_by<F>(mut self, key_selector: F) -> KeyedStream
     where
         F: KeySelectorFunction + 'static,
@@ -331,6 +594,15 @@ impl TDataStream for StreamBuilder {
         KeyedStream::new(self)
     }
 
+    fn window_all<W>(self, window_assigner: W) -> AllWindowedStream
+    where
+        W: WindowAssigner + 'static,
+    {
+        let windowed_stream =
+            TDataStream::key_by(self, GlobalKeySelector::new()).window(window_assigner);
+        AllWindowedStream::new(windowed_stream)
+    }
+
     fn assign_timestamps_and_watermarks<W>(
         mut self,
         timestamp_and_watermark_assigner: W,
@@ -390,6 +662,7 @@ impl TKeyedStream for StreamBuilder {
     where
         W: WindowAssigner + 'static,
     {
+        let pane_size = window_assigner.pane_size();
         let window_assigner_func = Box::new(window_assigner);
         let stream_window_assigner = StreamOperator::new_window_assigner(window_assigner_func);
 
@@ -397,7 +670,7 @@ impl TKeyedStream for StreamBuilder {
             .stream_manager
             .add_operator(stream_window_assigner, vec![self.cur_operator_id]);
 
-        WindowedStream::new(self)
+        WindowedStream::new(self, pane_size)
     }
 
     fn add_sink<O>(mut self, output_format: O) -> SinkStream
@@ -416,13 +689,31 @@ impl TKeyedStream for StreamBuilder {
 }
 
 impl TWindowedStream for StreamBuilder {
-    fn reduce<F>(mut self, reduce: F) -> DataStream
+    fn reduce<F>(self, reduce: F) -> DataStream
+    where
+        F: ReduceFunction + 'static,
+    {
+        self.window_reduce(reduce, WindowEmitStrategy::default(), None)
+    }
+}
+
+impl StreamBuilder {
+    fn window_reduce<F>(
+        mut self,
+        reduce: F,
+        emit_strategy: WindowEmitStrategy,
+        pane_size: Option<u64>,
+    ) -> DataStream
     where
         F: ReduceFunction + 'static,
     {
         let parallelism = reduce.parallelism();
         let reduce_func = Box::new(reduce);
-        let base_reduce_func = Box::new(WindowBaseReduceFunction::new(reduce_func));
+        let base_reduce_func = Box::new(WindowBaseReduceFunction::new(
+            reduce_func,
+            emit_strategy,
+            pane_size,
+        ));
         let stream_reduce = StreamOperator::new_reduce(parallelism, base_reduce_func);
 
         self.cur_operator_id = self
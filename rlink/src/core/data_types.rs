@@ -1,7 +1,12 @@
 use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 use serbuffer::{types, FieldMetadata};
 
+/// the max precision of `DataType::Decimal`, the unscaled value is stored as `i64`
+pub const MAX_DECIMAL_PRECISION: u8 = 18;
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum DataType {
     /// A boolean datatype representing the values `true` and `false`.
@@ -30,6 +35,18 @@ pub enum DataType {
     Binary,
     /// A variable-length string in Unicode with UTF-8 encoding.
     String,
+    /// A fixed-point decimal with the `precision`(max 18) and `scale`,
+    /// the unscaled value is stored as `Int64` in the buffer.
+    Decimal(u8, u8),
+    /// A timestamp with time zone, normalized to the milliseconds since the unix epoch in UTC,
+    /// and stored as `Int64` in the buffer.
+    TimestampTz,
+    /// A nested struct of the ordered fields, encoded as `Binary` in the buffer.
+    Struct(Vec<Field>),
+    /// A variable-length list of the same type, encoded as `Binary` in the buffer.
+    List(Box<DataType>),
+    /// A variable-length map from the key type to the value type, encoded as `Binary` in the buffer.
+    Map(Box<DataType>, Box<DataType>),
 }
 
 impl DataType {
@@ -48,6 +65,11 @@ impl DataType {
             Self::Float64 => 8,
             Self::Binary => 0,
             Self::String => 0,
+            Self::Decimal(_, _) => 8,
+            Self::TimestampTz => 8,
+            Self::Struct(_) => 0,
+            Self::List(_) => 0,
+            Self::Map(_, _) => 0,
         }
     }
 
@@ -66,8 +88,18 @@ impl DataType {
             Self::Float64 => types::F64,
             Self::Binary => types::BINARY,
             Self::String => types::STRING,
+            Self::Decimal(_, _) => types::I64,
+            Self::TimestampTz => types::I64,
+            Self::Struct(_) => types::BINARY,
+            Self::List(_) => types::BINARY,
+            Self::Map(_, _) => types::BINARY,
         }
     }
+
+    /// the nested types are encoded by `dynamic_record::Value`
+    pub fn is_nested(&self) -> bool {
+        matches!(self, Self::Struct(_) | Self::List(_) | Self::Map(_, _))
+    }
 }
 
 impl TryFrom<u8> for DataType {
@@ -93,15 +125,131 @@ impl TryFrom<u8> for DataType {
     }
 }
 
+/// A fixed-point decimal number, the value is `unscaled / 10^scale`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    unscaled: i64,
+    scale: u8,
+}
+
+impl Decimal {
+    pub fn new(unscaled: i64, scale: u8) -> Self {
+        Decimal { unscaled, scale }
+    }
+
+    #[inline]
+    pub fn unscaled(&self) -> i64 {
+        self.unscaled
+    }
+
+    #[inline]
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// the number of the digits of the unscaled value
+    pub fn precision(&self) -> u8 {
+        let mut precision = 1;
+        let mut v = self.unscaled.unsigned_abs() / 10;
+        while v > 0 {
+            precision += 1;
+            v /= 10;
+        }
+        precision
+    }
+
+    /// change the scale, round half away from zero if the scale is reduced
+    pub fn rescale(&self, scale: u8) -> Result<Decimal, crate::core::Error> {
+        let unscaled = if scale >= self.scale {
+            10i64
+                .checked_pow((scale - self.scale) as u32)
+                .and_then(|x| self.unscaled.checked_mul(x))
+        } else {
+            10i64.checked_pow((self.scale - scale) as u32).map(|x| {
+                let (v, rem) = (self.unscaled / x, self.unscaled % x);
+                if rem.abs() * 2 >= x {
+                    v + self.unscaled.signum()
+                } else {
+                    v
+                }
+            })
+        };
+
+        unscaled
+            .map(|unscaled| Decimal::new(unscaled, scale))
+            .ok_or_else(|| crate::core::Error::from(format!("decimal {} overflow", self)))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.unscaled as f64 / 10f64.powi(self.scale as i32)
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.scale == 0 {
+            return write!(f, "{}", self.unscaled);
+        }
+
+        let digits = format!(
+            "{:0>width$}",
+            self.unscaled.unsigned_abs(),
+            width = self.scale as usize + 1
+        );
+        let (integer, fraction) = digits.split_at(digits.len() - self.scale as usize);
+        let sign = if self.unscaled < 0 { "-" } else { "" };
+        write!(f, "{}{}.{}", sign, integer, fraction)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = crate::core::Error;
+
+    /// parse the plain decimal string, eg: `-12.345`, the scale is the number of the fraction digits
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (integer, fraction) = match s.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (s, ""),
+        };
+
+        let digits = format!("{}{}", integer, fraction);
+        let unscaled = i64::from_str(digits.as_str())
+            .map_err(|_e| crate::core::Error::from(format!("invalid decimal `{}`", s)))?;
+        Ok(Decimal::new(unscaled, fraction.len() as u8))
+    }
+}
+
 /// Contains the meta-data for a single relative type.
 ///
 /// The `Schema` object is an ordered collection of `Field` objects.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(from = "FieldDef")]
 pub struct Field {
     name: String,
     data_type: DataType,
     len: usize,
     type_id: u8,
+    /// the field can be marked null in the `Record`, the buffer of the null field keeps
+    /// the default value of the `DataType`, so it can be read as the default value
+    #[serde(default)]
+    nullable: bool,
+}
+
+/// The deserialized `Field`, the `len` and `type_id` are derived from the `data_type`
+#[derive(Deserialize)]
+struct FieldDef {
+    name: String,
+    #[serde(alias = "type")]
+    data_type: DataType,
+    #[serde(default)]
+    nullable: bool,
+}
+
+impl From<FieldDef> for Field {
+    fn from(field_def: FieldDef) -> Self {
+        Field::new(field_def.name.as_str(), field_def.data_type).with_nullable(field_def.nullable)
+    }
 }
 
 impl Field {
@@ -113,9 +261,19 @@ impl Field {
             data_type,
             len,
             type_id,
+            nullable: false,
         }
     }
 
+    pub fn new_nullable(name: &str, data_type: DataType) -> Self {
+        Field::new(name, data_type).with_nullable(true)
+    }
+
+    pub fn with_nullable(mut self, nullable: bool) -> Self {
+        self.nullable = nullable;
+        self
+    }
+
     #[inline]
     pub fn data_type_id(&self) -> u8 {
         self.type_id
@@ -136,6 +294,11 @@ impl Field {
         self.len
     }
 
+    #[inline]
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+
     pub fn is_numeric(&self) -> bool {
         self.type_id < types::BINARY
     }
@@ -226,6 +389,26 @@ impl Schema {
         }
     }
 
+    /// Creates a `Schema` from a json array of the fields, the `type` is the `DataType`,
+    /// `nullable` is optional and default `false`.
+    /// eg: `[{"name":"id","type":"Int64"},{"name":"name","type":"String","nullable":true}]`,
+    /// the nested types: `{"Struct":[{"name":"id","type":"Int64"}]}`, `{"List":"String"}`
+    /// and `{"Map":["String","Int64"]}`
+    pub fn from_json(json: &str) -> Result<Schema, crate::core::Error> {
+        let fields: Vec<Field> = serde_json::from_str(json).map_err(crate::core::Error::wrap)?;
+        Ok(Schema::new(fields))
+    }
+
+    /// Creates a `Schema` with all fields nullable, eg: the output of the missing side of an outer join
+    pub fn to_nullable(&self) -> Schema {
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| field.clone().with_nullable(true))
+            .collect();
+        Schema::new(fields)
+    }
+
     pub fn sub_schema(&self, column_indies: &[usize]) -> Schema {
         let mut fields = Vec::new();
         for index in column_indies {
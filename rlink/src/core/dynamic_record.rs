@@ -0,0 +1,902 @@
+use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
+
+use serde_json::{Map, Number};
+
+use crate::core::data_types::{DataType, Decimal, Field, Schema};
+use crate::core::element::Record;
+use crate::core::Error;
+use crate::utils::date_time::{fmt_rfc3339_millis, parse_rfc3339_millis};
+
+/// A typed value of a field in the `DynamicRecord`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Value {
+    Boolean(bool),
+    Int8(i8),
+    UInt8(u8),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    Int64(i64),
+    UInt64(u64),
+    Float32(f32),
+    Float64(f64),
+    Binary(Vec<u8>),
+    String(String),
+    Decimal(Decimal),
+    /// the milliseconds since the unix epoch in UTC
+    TimestampTz(i64),
+    /// the values of the struct fields in order, `None` is the null field
+    Struct(Vec<Option<Value>>),
+    List(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+}
+
+macro_rules! decode_number {
+    ($t:ty, $bytes:expr) => {{
+        let data = take($bytes, std::mem::size_of::<$t>())?;
+        <$t>::from_le_bytes(data.try_into().unwrap())
+    }};
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if bytes.len() < len {
+        return Err(Error::from("unexpected end of the nested value"));
+    }
+    let (data, remaining) = bytes.split_at(len);
+    *bytes = remaining;
+    Ok(data)
+}
+
+fn put_len(bytes: &mut Vec<u8>, len: usize) {
+    bytes.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+impl Value {
+    /// check the value recursively, the null field of the struct must be nullable
+    pub fn is_type_of(&self, data_type: &DataType) -> bool {
+        match (self, data_type) {
+            (Value::Boolean(_), DataType::Boolean)
+            | (Value::Int8(_), DataType::Int8)
+            | (Value::UInt8(_), DataType::UInt8)
+            | (Value::Int16(_), DataType::Int16)
+            | (Value::UInt16(_), DataType::UInt16)
+            | (Value::Int32(_), DataType::Int32)
+            | (Value::UInt32(_), DataType::UInt32)
+            | (Value::Int64(_), DataType::Int64)
+            | (Value::UInt64(_), DataType::UInt64)
+            | (Value::Float32(_), DataType::Float32)
+            | (Value::Float64(_), DataType::Float64)
+            | (Value::Binary(_), DataType::Binary)
+            | (Value::String(_), DataType::String)
+            | (Value::TimestampTz(_), DataType::TimestampTz) => true,
+            (Value::Decimal(v), DataType::Decimal(precision, scale)) => {
+                v.scale() == *scale && v.precision() <= *precision
+            }
+            (Value::Struct(values), DataType::Struct(fields)) => {
+                values.len() == fields.len()
+                    && values.iter().zip(fields).all(|(value, field)| match value {
+                        Some(value) => value.is_type_of(field.data_type()),
+                        None => field.is_nullable(),
+                    })
+            }
+            (Value::List(values), DataType::List(item_type)) => {
+                values.iter().all(|value| value.is_type_of(item_type))
+            }
+            (Value::Map(entries), DataType::Map(key_type, value_type)) => entries
+                .iter()
+                .all(|(key, value)| key.is_type_of(key_type) && value.is_type_of(value_type)),
+            _ => false,
+        }
+    }
+
+    /// the default value of the `DataType`, it's written in the buffer of the null field
+    pub fn default_of(data_type: &DataType) -> Value {
+        match data_type {
+            DataType::Boolean => Value::Boolean(false),
+            DataType::Int8 => Value::Int8(0),
+            DataType::UInt8 => Value::UInt8(0),
+            DataType::Int16 => Value::Int16(0),
+            DataType::UInt16 => Value::UInt16(0),
+            DataType::Int32 => Value::Int32(0),
+            DataType::UInt32 => Value::UInt32(0),
+            DataType::Int64 => Value::Int64(0),
+            DataType::UInt64 => Value::UInt64(0),
+            DataType::Float32 => Value::Float32(0.0),
+            DataType::Float64 => Value::Float64(0.0),
+            DataType::Binary => Value::Binary(vec![]),
+            DataType::String => Value::String("".to_string()),
+            DataType::Decimal(_, scale) => Value::Decimal(Decimal::new(0, *scale)),
+            DataType::TimestampTz => Value::TimestampTz(0),
+            DataType::Struct(fields) => Value::Struct(
+                fields
+                    .iter()
+                    .map(|field| {
+                        if field.is_nullable() {
+                            None
+                        } else {
+                            Some(Value::default_of(field.data_type()))
+                        }
+                    })
+                    .collect(),
+            ),
+            DataType::List(_) => Value::List(vec![]),
+            DataType::Map(_, _) => Value::Map(vec![]),
+        }
+    }
+
+    /// Encode the value to the bytes, the nested value is written as a `Binary` field in the buffer,
+    /// so it's transferred by the network and stored by the state backends as it is.
+    /// the numbers are little-endian, the binary, string, list and map are prefixed by the u32 length,
+    /// the struct is prefixed by the null bitmap of the fields and the null fields are skipped.
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Value::Boolean(v) => bytes.push(*v as u8),
+            Value::Int8(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::UInt8(v) => bytes.push(*v),
+            Value::Int16(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::UInt16(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::Int32(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::UInt32(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::Int64(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::UInt64(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::Float32(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::Float64(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::Decimal(v) => bytes.extend_from_slice(&v.unscaled().to_le_bytes()),
+            Value::TimestampTz(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+            Value::Binary(v) => {
+                put_len(bytes, v.len());
+                bytes.extend_from_slice(v.as_slice());
+            }
+            Value::String(v) => {
+                put_len(bytes, v.len());
+                bytes.extend_from_slice(v.as_bytes());
+            }
+            Value::Struct(values) => {
+                let mut nulls = vec![0u8; values.len().div_ceil(8)];
+                for (index, value) in values.iter().enumerate() {
+                    if value.is_none() {
+                        nulls[index / 8] |= 1u8 << (index % 8);
+                    }
+                }
+                bytes.extend_from_slice(nulls.as_slice());
+
+                for value in values.iter().flatten() {
+                    value.encode(bytes);
+                }
+            }
+            Value::List(values) => {
+                put_len(bytes, values.len());
+                for value in values {
+                    value.encode(bytes);
+                }
+            }
+            Value::Map(entries) => {
+                put_len(bytes, entries.len());
+                for (key, value) in entries {
+                    key.encode(bytes);
+                    value.encode(bytes);
+                }
+            }
+        }
+    }
+
+    /// Decode the value of the `DataType` from the bytes encoded by `encode`,
+    /// and advance the `bytes` to the end of the value
+    pub fn decode(data_type: &DataType, bytes: &mut &[u8]) -> Result<Value, Error> {
+        let value = match data_type {
+            DataType::Boolean => Value::Boolean(take(bytes, 1)?[0] != 0),
+            DataType::Int8 => Value::Int8(decode_number!(i8, bytes)),
+            DataType::UInt8 => Value::UInt8(take(bytes, 1)?[0]),
+            DataType::Int16 => Value::Int16(decode_number!(i16, bytes)),
+            DataType::UInt16 => Value::UInt16(decode_number!(u16, bytes)),
+            DataType::Int32 => Value::Int32(decode_number!(i32, bytes)),
+            DataType::UInt32 => Value::UInt32(decode_number!(u32, bytes)),
+            DataType::Int64 => Value::Int64(decode_number!(i64, bytes)),
+            DataType::UInt64 => Value::UInt64(decode_number!(u64, bytes)),
+            DataType::Float32 => Value::Float32(decode_number!(f32, bytes)),
+            DataType::Float64 => Value::Float64(decode_number!(f64, bytes)),
+            DataType::Decimal(_, scale) => {
+                Value::Decimal(Decimal::new(decode_number!(i64, bytes), *scale))
+            }
+            DataType::TimestampTz => Value::TimestampTz(decode_number!(i64, bytes)),
+            DataType::Binary => {
+                let len = decode_number!(u32, bytes) as usize;
+                Value::Binary(take(bytes, len)?.to_vec())
+            }
+            DataType::String => {
+                let len = decode_number!(u32, bytes) as usize;
+                let data = take(bytes, len)?.to_vec();
+                Value::String(String::from_utf8(data).map_err(Error::wrap)?)
+            }
+            DataType::Struct(fields) => {
+                let nulls = take(bytes, fields.len().div_ceil(8))?;
+                let mut values = Vec::with_capacity(fields.len());
+                for (index, field) in fields.iter().enumerate() {
+                    if nulls[index / 8] & (1u8 << (index % 8)) != 0 {
+                        values.push(None);
+                    } else {
+                        values.push(Some(Value::decode(field.data_type(), bytes)?));
+                    }
+                }
+                Value::Struct(values)
+            }
+            DataType::List(item_type) => {
+                let len = decode_number!(u32, bytes) as usize;
+                let mut values = Vec::with_capacity(len);
+                for _ in 0..len {
+                    values.push(Value::decode(item_type, bytes)?);
+                }
+                Value::List(values)
+            }
+            DataType::Map(key_type, value_type) => {
+                let len = decode_number!(u32, bytes) as usize;
+                let mut entries = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = Value::decode(key_type, bytes)?;
+                    let value = Value::decode(value_type, bytes)?;
+                    entries.push((key, value));
+                }
+                Value::Map(entries)
+            }
+        };
+        Ok(value)
+    }
+
+    /// the struct is an object, the map is an object if the keys are string,
+    /// otherwise an array of the `[key, value]` pairs.
+    /// the decimal is a string to keep the precision, the timestamp is a RFC 3339 string in UTC
+    pub fn to_json(&self, data_type: &DataType) -> serde_json::Value {
+        match (self, data_type) {
+            (Value::Boolean(v), _) => serde_json::Value::Bool(*v),
+            (Value::Int8(v), _) => serde_json::Value::from(*v),
+            (Value::UInt8(v), _) => serde_json::Value::from(*v),
+            (Value::Int16(v), _) => serde_json::Value::from(*v),
+            (Value::UInt16(v), _) => serde_json::Value::from(*v),
+            (Value::Int32(v), _) => serde_json::Value::from(*v),
+            (Value::UInt32(v), _) => serde_json::Value::from(*v),
+            (Value::Int64(v), _) => serde_json::Value::from(*v),
+            (Value::UInt64(v), _) => serde_json::Value::from(*v),
+            (Value::Float32(v), _) => Number::from_f64(*v as f64)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            (Value::Float64(v), _) => Number::from_f64(*v)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            (Value::Binary(v), _) => serde_json::Value::from(v.clone()),
+            (Value::String(v), _) => serde_json::Value::String(v.clone()),
+            (Value::Decimal(v), _) => serde_json::Value::String(v.to_string()),
+            (Value::TimestampTz(v), _) => serde_json::Value::String(fmt_rfc3339_millis(*v)),
+            (Value::Struct(values), DataType::Struct(fields)) => {
+                let mut map = Map::new();
+                for (value, field) in values.iter().zip(fields) {
+                    let value = value
+                        .as_ref()
+                        .map(|v| v.to_json(field.data_type()))
+                        .unwrap_or(serde_json::Value::Null);
+                    map.insert(field.name().to_string(), value);
+                }
+                serde_json::Value::Object(map)
+            }
+            (Value::List(values), DataType::List(item_type)) => {
+                serde_json::Value::Array(values.iter().map(|v| v.to_json(item_type)).collect())
+            }
+            (Value::Map(entries), DataType::Map(key_type, value_type)) => {
+                if key_type.as_ref().eq(&DataType::String) {
+                    let mut map = Map::new();
+                    for (key, value) in entries {
+                        if let Value::String(key) = key {
+                            map.insert(key.clone(), value.to_json(value_type));
+                        }
+                    }
+                    serde_json::Value::Object(map)
+                } else {
+                    serde_json::Value::Array(
+                        entries
+                            .iter()
+                            .map(|(key, value)| {
+                                serde_json::Value::Array(vec![
+                                    key.to_json(key_type),
+                                    value.to_json(value_type),
+                                ])
+                            })
+                            .collect(),
+                    )
+                }
+            }
+            _ => serde_json::Value::Null,
+        }
+    }
+}
+
+impl Value {
+    /// Parse the json value by the `DataType`, `None` if it's `null`.
+    /// the decimal can be a string or a number, the timestamp can be a RFC 3339 string
+    /// or the milliseconds since the unix epoch, the map can be an object if the keys are string
+    pub fn from_json(
+        data_type: &DataType,
+        json: &serde_json::Value,
+    ) -> Result<Option<Value>, Error> {
+        if json.is_null() {
+            return Ok(None);
+        }
+
+        let invalid = || Error::from(format!("invalid {:?} value `{}`", data_type, json));
+        let value = match data_type {
+            DataType::Boolean => Value::Boolean(json.as_bool().ok_or_else(invalid)?),
+            DataType::Int8 => Value::Int8(json_int(json).ok_or_else(invalid)?),
+            DataType::UInt8 => Value::UInt8(json_uint(json).ok_or_else(invalid)?),
+            DataType::Int16 => Value::Int16(json_int(json).ok_or_else(invalid)?),
+            DataType::UInt16 => Value::UInt16(json_uint(json).ok_or_else(invalid)?),
+            DataType::Int32 => Value::Int32(json_int(json).ok_or_else(invalid)?),
+            DataType::UInt32 => Value::UInt32(json_uint(json).ok_or_else(invalid)?),
+            DataType::Int64 => Value::Int64(json.as_i64().ok_or_else(invalid)?),
+            DataType::UInt64 => Value::UInt64(json.as_u64().ok_or_else(invalid)?),
+            DataType::Float32 => Value::Float32(json.as_f64().ok_or_else(invalid)? as f32),
+            DataType::Float64 => Value::Float64(json.as_f64().ok_or_else(invalid)?),
+            DataType::Binary => match json {
+                serde_json::Value::String(v) => Value::Binary(v.as_bytes().to_vec()),
+                serde_json::Value::Array(values) => Value::Binary(
+                    values
+                        .iter()
+                        .map(json_uint)
+                        .collect::<Option<Vec<u8>>>()
+                        .ok_or_else(invalid)?,
+                ),
+                _ => return Err(invalid()),
+            },
+            DataType::String => Value::String(json.as_str().ok_or_else(invalid)?.to_string()),
+            DataType::Decimal(_, scale) => {
+                let decimal = match json {
+                    serde_json::Value::String(v) => Decimal::from_str(v)?,
+                    serde_json::Value::Number(v) => Decimal::from_str(v.to_string().as_str())?,
+                    _ => return Err(invalid()),
+                };
+                Value::Decimal(decimal.rescale(*scale)?)
+            }
+            DataType::TimestampTz => match json {
+                serde_json::Value::String(v) => {
+                    Value::TimestampTz(parse_rfc3339_millis(v).map_err(Error::wrap)?)
+                }
+                serde_json::Value::Number(v) => Value::TimestampTz(v.as_i64().ok_or_else(invalid)?),
+                _ => return Err(invalid()),
+            },
+            DataType::Struct(fields) => {
+                let object = json.as_object().ok_or_else(invalid)?;
+                let mut values = Vec::with_capacity(fields.len());
+                for field in fields {
+                    let value = match object.get(field.name()) {
+                        Some(json) => Value::from_json(field.data_type(), json)?,
+                        None => None,
+                    };
+                    values.push(value);
+                }
+                Value::Struct(values)
+            }
+            DataType::List(item_type) => {
+                let array = json.as_array().ok_or_else(invalid)?;
+                let mut values = Vec::with_capacity(array.len());
+                for json in array {
+                    values.push(Value::from_json(item_type, json)?.ok_or_else(invalid)?);
+                }
+                Value::List(values)
+            }
+            DataType::Map(key_type, value_type) => {
+                let mut entries = Vec::new();
+                match json {
+                    serde_json::Value::Object(object) => {
+                        for (key, json) in object {
+                            let key = Value::from_json(
+                                key_type,
+                                &serde_json::Value::String(key.clone()),
+                            )?
+                            .ok_or_else(invalid)?;
+                            let value = Value::from_json(value_type, json)?.ok_or_else(invalid)?;
+                            entries.push((key, value));
+                        }
+                    }
+                    serde_json::Value::Array(array) => {
+                        for pair in array {
+                            match pair.as_array().map(|x| x.as_slice()) {
+                                Some([key, value]) => {
+                                    let key =
+                                        Value::from_json(key_type, key)?.ok_or_else(invalid)?;
+                                    let value =
+                                        Value::from_json(value_type, value)?.ok_or_else(invalid)?;
+                                    entries.push((key, value));
+                                }
+                                _ => return Err(invalid()),
+                            }
+                        }
+                    }
+                    _ => return Err(invalid()),
+                }
+                Value::Map(entries)
+            }
+        };
+
+        if !value.is_type_of(data_type) {
+            return Err(invalid());
+        }
+        Ok(Some(value))
+    }
+}
+
+fn json_int<T: TryFrom<i64>>(json: &serde_json::Value) -> Option<T> {
+    json.as_i64().and_then(|v| T::try_from(v).ok())
+}
+
+fn json_uint<T: TryFrom<u64>>(json: &serde_json::Value) -> Option<T> {
+    json.as_u64().and_then(|v| T::try_from(v).ok())
+}
+
+macro_rules! impl_from_value {
+    ($t:ty, $variant:ident) => {
+        impl From<$t> for Value {
+            fn from(v: $t) -> Self {
+                Value::$variant(v)
+            }
+        }
+    };
+}
+
+impl_from_value!(bool, Boolean);
+impl_from_value!(i8, Int8);
+impl_from_value!(u8, UInt8);
+impl_from_value!(i16, Int16);
+impl_from_value!(u16, UInt16);
+impl_from_value!(i32, Int32);
+impl_from_value!(u32, UInt32);
+impl_from_value!(i64, Int64);
+impl_from_value!(u64, UInt64);
+impl_from_value!(f32, Float32);
+impl_from_value!(f64, Float64);
+impl_from_value!(Vec<u8>, Binary);
+impl_from_value!(String, String);
+impl_from_value!(Decimal, Decimal);
+
+impl From<Vec<Value>> for Value {
+    fn from(v: Vec<Value>) -> Self {
+        Value::List(v)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(v: &'a str) -> Self {
+        Value::String(v.to_string())
+    }
+}
+
+fn field_index(schema: &Schema, name: &str) -> Result<usize, Error> {
+    schema
+        .index_of(name)
+        .ok_or_else(|| Error::from(format!("field `{}` not found", name)))
+}
+
+fn check_type(field: &Field, data_type: &DataType) -> Result<(), Error> {
+    if field.data_type().ne(data_type) {
+        return Err(Error::from(format!(
+            "field `{}` is {:?}, but {:?} is used",
+            field.name(),
+            field.data_type(),
+            data_type
+        )));
+    }
+    Ok(())
+}
+
+/// Build a `Record` by the field names of a runtime `Schema`, without the codegen of `buffer_gen`.
+/// The fields can be set in any order, and are checked by the `Schema` at runtime.
+///
+/// ```
+/// use rlink::core::data_types::{DataType, Field, Schema};
+/// use rlink::core::dynamic_record::{DynamicRecord, DynamicRecordBuilder};
+///
+/// let schema = Schema::new(vec![
+///     Field::new("name", DataType::String),
+///     Field::new("value", DataType::Int64),
+/// ]);
+///
+/// let mut builder = DynamicRecordBuilder::new(&schema);
+/// builder.set("value", 10i64).unwrap();
+/// builder.set("name", "rlink").unwrap();
+/// let mut record = builder.build().unwrap();
+///
+/// let mut reader = DynamicRecord::new(&mut record, &schema);
+/// assert_eq!(reader.get_i64("value").unwrap(), 10);
+/// ```
+pub struct DynamicRecordBuilder<'a> {
+    schema: &'a Schema,
+    values: Vec<Option<Value>>,
+}
+
+impl<'a> DynamicRecordBuilder<'a> {
+    pub fn new(schema: &'a Schema) -> Self {
+        DynamicRecordBuilder {
+            schema,
+            values: vec![None; schema.fields().len()],
+        }
+    }
+
+    pub fn set<V>(&mut self, name: &str, value: V) -> Result<(), Error>
+    where
+        V: Into<Value>,
+    {
+        let index = field_index(self.schema, name)?;
+        let field = self.schema.field(index);
+        let value = match (value.into(), field.data_type()) {
+            (Value::Decimal(v), DataType::Decimal(_, scale)) => Value::Decimal(v.rescale(*scale)?),
+            (value, _) => value,
+        };
+        if !value.is_type_of(field.data_type()) {
+            return Err(Error::from(format!(
+                "field `{}` is {:?}, but the value is {:?}",
+                field.name(),
+                field.data_type(),
+                value
+            )));
+        }
+
+        self.values[index] = Some(value);
+        Ok(())
+    }
+
+    /// set the fields by the json object, the missing and `null` fields are null
+    pub fn set_json(&mut self, json: &serde_json::Value) -> Result<(), Error> {
+        let object = json
+            .as_object()
+            .ok_or_else(|| Error::from("the json is not an object"))?;
+        for (index, field) in self.schema.fields().iter().enumerate() {
+            self.values[index] = match object.get(field.name()) {
+                Some(json) => Value::from_json(field.data_type(), json)
+                    .map_err(|e| Error::from(format!("field `{}`, {}", field.name(), e)))?,
+                None => None,
+            };
+        }
+        Ok(())
+    }
+
+    /// only the nullable field can be set null
+    pub fn set_null(&mut self, name: &str) -> Result<(), Error> {
+        let index = field_index(self.schema, name)?;
+        let field = self.schema.field(index);
+        if !field.is_nullable() {
+            return Err(Error::from(format!("field `{}` is not nullable", name)));
+        }
+
+        self.values[index] = None;
+        Ok(())
+    }
+
+    /// all non-nullable fields must be set, the nullable fields not set are null.
+    /// the values are written in the order of the `Schema`
+    pub fn build(self) -> Result<Record, Error> {
+        let schema = self.schema;
+        let mut record = Record::new();
+        let mut null_indexes = Vec::new();
+        let mut writer = record.as_writer(schema.as_type_ids());
+        for (index, value) in self.values.into_iter().enumerate() {
+            let field = schema.field(index);
+            let value = match value {
+                Some(value) => value,
+                None if field.is_nullable() => {
+                    null_indexes.push(index);
+                    Value::default_of(field.data_type())
+                }
+                None => {
+                    return Err(Error::from(format!("field `{}` is not set", field.name())));
+                }
+            };
+
+            match value {
+                Value::Boolean(v) => writer.set_bool(v),
+                Value::Int8(v) => writer.set_i8(v),
+                Value::UInt8(v) => writer.set_u8(v),
+                Value::Int16(v) => writer.set_i16(v),
+                Value::UInt16(v) => writer.set_u16(v),
+                Value::Int32(v) => writer.set_i32(v),
+                Value::UInt32(v) => writer.set_u32(v),
+                Value::Int64(v) => writer.set_i64(v),
+                Value::UInt64(v) => writer.set_u64(v),
+                Value::Float32(v) => writer.set_f32(v),
+                Value::Float64(v) => writer.set_f64(v),
+                Value::Binary(v) => writer.set_binary(v.as_slice()),
+                Value::String(v) => writer.set_str(v.as_str()),
+                Value::Decimal(v) => writer.set_i64(v.unscaled()),
+                Value::TimestampTz(v) => writer.set_i64(v),
+                Value::Struct(_) | Value::List(_) | Value::Map(_) => {
+                    let mut bytes = Vec::new();
+                    value.encode(&mut bytes);
+                    writer.set_binary(bytes.as_slice())
+                }
+            }
+            .map_err(Error::wrap)?;
+        }
+
+        for index in null_indexes {
+            record.set_null(index, true);
+        }
+
+        Ok(record)
+    }
+}
+
+/// Read the fields of a `Record` by the names of a runtime `Schema`,
+/// the type of the field is checked at runtime.
+/// The typed getters return the default value of the null field, check it by `is_null` or use `get`
+pub struct DynamicRecord<'a> {
+    record: &'a mut Record,
+    schema: &'a Schema,
+}
+
+macro_rules! impl_get {
+    ($fn_name:ident, $t:ty, $data_type:ident, $reader_fn:ident) => {
+        pub fn $fn_name(&mut self, name: &str) -> Result<$t, Error> {
+            let index = self.index_of(name, DataType::$data_type)?;
+            let reader = self.record.as_reader(self.schema.as_type_ids());
+            reader.$reader_fn(index).map_err(Error::wrap)
+        }
+    };
+}
+
+impl<'a> DynamicRecord<'a> {
+    pub fn new(record: &'a mut Record, schema: &'a Schema) -> Self {
+        DynamicRecord { record, schema }
+    }
+
+    fn index_of(&self, name: &str, data_type: DataType) -> Result<usize, Error> {
+        let index = field_index(self.schema, name)?;
+        check_type(self.schema.field(index), &data_type)?;
+        Ok(index)
+    }
+
+    impl_get!(get_bool, bool, Boolean, get_bool);
+    impl_get!(get_i8, i8, Int8, get_i8);
+    impl_get!(get_u8, u8, UInt8, get_u8);
+    impl_get!(get_i16, i16, Int16, get_i16);
+    impl_get!(get_u16, u16, UInt16, get_u16);
+    impl_get!(get_i32, i32, Int32, get_i32);
+    impl_get!(get_u32, u32, UInt32, get_u32);
+    impl_get!(get_i64, i64, Int64, get_i64);
+    impl_get!(get_u64, u64, UInt64, get_u64);
+    impl_get!(get_f32, f32, Float32, get_f32);
+    impl_get!(get_f64, f64, Float64, get_f64);
+
+    pub fn get_str(&mut self, name: &str) -> Result<String, Error> {
+        let index = self.index_of(name, DataType::String)?;
+        let reader = self.record.as_reader(self.schema.as_type_ids());
+        reader
+            .get_str(index)
+            .map(|v| v.to_string())
+            .map_err(Error::wrap)
+    }
+
+    pub fn get_binary(&mut self, name: &str) -> Result<Vec<u8>, Error> {
+        let index = self.index_of(name, DataType::Binary)?;
+        let reader = self.record.as_reader(self.schema.as_type_ids());
+        reader
+            .get_binary(index)
+            .map(|v| v.to_vec())
+            .map_err(Error::wrap)
+    }
+
+    pub fn get_decimal(&mut self, name: &str) -> Result<Decimal, Error> {
+        let index = field_index(self.schema, name)?;
+        match self.schema.field(index).data_type() {
+            DataType::Decimal(_, scale) => {
+                let reader = self.record.as_reader(self.schema.as_type_ids());
+                let unscaled = reader.get_i64(index).map_err(Error::wrap)?;
+                Ok(Decimal::new(unscaled, *scale))
+            }
+            _ => Err(Error::from(format!("field `{}` is not Decimal", name))),
+        }
+    }
+
+    /// the milliseconds since the unix epoch in UTC
+    pub fn get_timestamp_tz(&mut self, name: &str) -> Result<i64, Error> {
+        let index = self.index_of(name, DataType::TimestampTz)?;
+        let reader = self.record.as_reader(self.schema.as_type_ids());
+        reader.get_i64(index).map_err(Error::wrap)
+    }
+
+    /// the values of the struct fields in order, `None` is the null field
+    pub fn get_struct(&mut self, name: &str) -> Result<Vec<Option<Value>>, Error> {
+        match self.get_nested(name)? {
+            Value::Struct(values) => Ok(values),
+            _ => Err(Error::from(format!("field `{}` is not Struct", name))),
+        }
+    }
+
+    pub fn get_list(&mut self, name: &str) -> Result<Vec<Value>, Error> {
+        match self.get_nested(name)? {
+            Value::List(values) => Ok(values),
+            _ => Err(Error::from(format!("field `{}` is not List", name))),
+        }
+    }
+
+    pub fn get_map(&mut self, name: &str) -> Result<Vec<(Value, Value)>, Error> {
+        match self.get_nested(name)? {
+            Value::Map(entries) => Ok(entries),
+            _ => Err(Error::from(format!("field `{}` is not Map", name))),
+        }
+    }
+
+    fn get_nested(&mut self, name: &str) -> Result<Value, Error> {
+        let index = field_index(self.schema, name)?;
+        let data_type = self.schema.field(index).data_type();
+        if !data_type.is_nested() {
+            return Err(Error::from(format!(
+                "field `{}` is {:?}, not a nested type",
+                name, data_type
+            )));
+        }
+        if self.record.is_null(index) {
+            return Ok(Value::default_of(data_type));
+        }
+
+        let reader = self.record.as_reader(self.schema.as_type_ids());
+        let mut bytes = reader.get_binary(index).map_err(Error::wrap)?;
+        Value::decode(data_type, &mut bytes)
+    }
+
+    pub fn is_null(&self, name: &str) -> Result<bool, Error> {
+        let index = field_index(self.schema, name)?;
+        Ok(self.record.is_null(index))
+    }
+
+    /// the value of any type, `None` if the field is null
+    pub fn get(&mut self, name: &str) -> Result<Option<Value>, Error> {
+        let index = field_index(self.schema, name)?;
+        self.get_by_index(index)
+    }
+
+    fn get_by_index(&mut self, index: usize) -> Result<Option<Value>, Error> {
+        if self.record.is_null(index) {
+            return Ok(None);
+        }
+
+        let data_type = self.schema.field(index).data_type();
+        let reader = self.record.as_reader(self.schema.as_type_ids());
+        if data_type.is_nested() {
+            let mut bytes = reader.get_binary(index).map_err(Error::wrap)?;
+            return Value::decode(data_type, &mut bytes).map(Some);
+        }
+
+        let value = match data_type {
+            DataType::Boolean => reader.get_bool(index).map(Value::Boolean),
+            DataType::Int8 => reader.get_i8(index).map(Value::Int8),
+            DataType::UInt8 => reader.get_u8(index).map(Value::UInt8),
+            DataType::Int16 => reader.get_i16(index).map(Value::Int16),
+            DataType::UInt16 => reader.get_u16(index).map(Value::UInt16),
+            DataType::Int32 => reader.get_i32(index).map(Value::Int32),
+            DataType::UInt32 => reader.get_u32(index).map(Value::UInt32),
+            DataType::Int64 => reader.get_i64(index).map(Value::Int64),
+            DataType::UInt64 => reader.get_u64(index).map(Value::UInt64),
+            DataType::Float32 => reader.get_f32(index).map(Value::Float32),
+            DataType::Float64 => reader.get_f64(index).map(Value::Float64),
+            DataType::Binary => reader.get_binary(index).map(|v| Value::Binary(v.to_vec())),
+            DataType::String => reader.get_str(index).map(|v| Value::String(v.to_string())),
+            DataType::Decimal(_, scale) => reader
+                .get_i64(index)
+                .map(|v| Value::Decimal(Decimal::new(v, *scale))),
+            DataType::TimestampTz => reader.get_i64(index).map(Value::TimestampTz),
+            DataType::Struct(_) | DataType::List(_) | DataType::Map(_, _) => unreachable!(),
+        };
+        value.map(Some).map_err(Error::wrap)
+    }
+
+    /// all fields as a json object, the binary is an array of bytes and the null field is `null`
+    pub fn to_json(&mut self) -> Result<serde_json::Value, Error> {
+        let mut map = Map::new();
+        for index in 0..self.schema.fields().len() {
+            let field = self.schema.field(index);
+            let value = self
+                .get_by_index(index)?
+                .map(|v| v.to_json(field.data_type()))
+                .unwrap_or(serde_json::Value::Null);
+            map.insert(field.name().to_string(), value);
+        }
+        Ok(serde_json::Value::Object(map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::core::data_types::{Decimal, Schema};
+    use crate::core::dynamic_record::{DynamicRecord, DynamicRecordBuilder, Value};
+
+    #[test]
+    pub fn dynamic_record_test() {
+        let schema = Schema::from_json(
+            r#"[{"name":"name","type":"String"},{"name":"count","type":"UInt32"},{"name":"ok","type":"Boolean"},{"name":"score","type":"Float64","nullable":true}]"#,
+        )
+        .unwrap();
+
+        let mut builder = DynamicRecordBuilder::new(&schema);
+        builder.set("ok", true).unwrap();
+        builder.set("name", "rlink").unwrap();
+        assert!(builder.set("count", 1i64).is_err());
+        assert!(builder.set("unknown", 1u32).is_err());
+        assert!(builder.set_null("ok").is_err());
+        builder.set("count", 3u32).unwrap();
+        let mut record = builder.build().unwrap();
+
+        let mut reader = DynamicRecord::new(&mut record, &schema);
+        assert_eq!(reader.get_str("name").unwrap(), "rlink");
+        assert_eq!(reader.get_u32("count").unwrap(), 3);
+        assert!(reader.get_i64("count").is_err());
+        assert_eq!(reader.get("ok").unwrap(), Some(Value::Boolean(true)));
+        assert!(reader.is_null("score").unwrap());
+        assert_eq!(reader.get("score").unwrap(), None);
+        assert_eq!(reader.get_f64("score").unwrap(), 0.0);
+        let json = reader.to_json().unwrap();
+        assert_eq!(json["name"], "rlink");
+        assert_eq!(json["count"], 3);
+        assert!(json["score"].is_null());
+
+        let builder = DynamicRecordBuilder::new(&schema);
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    pub fn nested_record_test() {
+        let schema = Schema::from_json(
+            r#"[{"name":"user","type":{"Struct":[{"name":"id","type":"Int64"},{"name":"email","type":"String","nullable":true}]}},{"name":"tags","type":{"List":"String"}},{"name":"scores","type":{"Map":["String","Float64"]}}]"#,
+        )
+        .unwrap();
+
+        let user = Value::Struct(vec![Some(Value::Int64(7)), None]);
+        let tags = vec![Value::from("a"), Value::from("b")];
+        let scores = Value::Map(vec![(Value::from("math"), Value::Float64(90.5))]);
+
+        let mut builder = DynamicRecordBuilder::new(&schema);
+        assert!(builder
+            .set("user", Value::Struct(vec![None, None]))
+            .is_err());
+        assert!(builder.set("tags", vec![Value::Int64(1)]).is_err());
+        builder.set("user", user.clone()).unwrap();
+        builder.set("tags", tags.clone()).unwrap();
+        builder.set("scores", scores.clone()).unwrap();
+        let mut record = builder.build().unwrap();
+
+        let mut reader = DynamicRecord::new(&mut record, &schema);
+        assert_eq!(reader.get("user").unwrap(), Some(user));
+        assert_eq!(reader.get_list("tags").unwrap(), tags);
+        assert!(reader.get_list("user").is_err());
+
+        let json = reader.to_json().unwrap();
+        assert_eq!(json["user"]["id"], 7);
+        assert!(json["user"]["email"].is_null());
+        assert_eq!(json["tags"][1], "b");
+        assert_eq!(json["scores"]["math"], 90.5);
+    }
+
+    #[test]
+    pub fn decimal_timestamp_test() {
+        let schema = Schema::from_json(
+            r#"[{"name":"amount","type":{"Decimal":[10,2]}},{"name":"ts","type":"TimestampTz"}]"#,
+        )
+        .unwrap();
+
+        let amount = Decimal::from_str("-12.345").unwrap();
+        assert_eq!(amount.rescale(2).unwrap().to_string(), "-12.35");
+        assert_eq!(Decimal::from_str("0.5").unwrap().to_string(), "0.5");
+
+        let mut builder = DynamicRecordBuilder::new(&schema);
+        builder
+            .set_json(&serde_json::json!({"amount": 0.1, "ts": "2021-06-01T08:00:00.123+08:00"}))
+            .unwrap();
+        let mut record = builder.build().unwrap();
+
+        let mut reader = DynamicRecord::new(&mut record, &schema);
+        assert_eq!(reader.get_decimal("amount").unwrap(), Decimal::new(10, 2));
+        assert_eq!(reader.get_timestamp_tz("ts").unwrap(), 1622505600123);
+
+        let json = reader.to_json().unwrap();
+        assert_eq!(json["amount"], "0.10");
+        assert_eq!(json["ts"], "2021-06-01T00:00:00.123Z");
+    }
+}
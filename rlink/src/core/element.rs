@@ -36,6 +36,15 @@ impl FnSchema {
             _ => panic!("no schema"),
         }
     }
+
+    /// mark all fields of the schemas nullable
+    pub fn to_nullable(&self) -> FnSchema {
+        match self {
+            Self::Empty => Self::Empty,
+            Self::Single(v) => Self::Single(v.to_nullable()),
+            Self::Tuple(v, v1) => Self::Tuple(v.to_nullable(), v1.to_nullable()),
+        }
+    }
 }
 
 impl<'a> From<&'a Schema> for FnSchema {
@@ -78,7 +87,13 @@ const SER_DE_WATERMARK: u8 = 2;
 const SER_DE_STREAM_STATUS: u8 = 3;
 const SER_DE_BARRIER: u8 = 4;
 
-pub(crate) trait Serde {
+/// the flags byte of the optional `Record` trailers following the values,
+/// the trailers are absent in the layout without the flags byte
+const RECORD_TRAILER_NULLS: u8 = 0b0000_0001;
+const RECORD_TRAILER_SEQUENCE: u8 = 0b0000_0010;
+
+/// the binary format of the elements exchanged between the tasks
+pub trait Serde {
     fn capacity(&self) -> usize;
     fn to_bytes(&self) -> BytesMut {
         let mut data = BytesMut::with_capacity(self.capacity());
@@ -89,6 +104,35 @@ pub(crate) trait Serde {
     fn deserialize(bytes: &mut BytesMut) -> Self;
 }
 
+/// The stamp of a record in the assertion mode, the `sequence` is continuous in the records
+/// produced by a source task, see `SystemProperties::set_assertion_mode`
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub(crate) struct RecordSequence {
+    /// the operator id of the source
+    pub source: u32,
+    pub task_number: u16,
+    pub sequence: u64,
+    /// the index of the record in the outputs of a `FlatMapFunction` from the same input
+    pub fanout: u16,
+    /// the record is dropped by a filter or a flat_map, the stamp is forwarded without values
+    /// so the sinks see no gap, and it's never passed to the user functions
+    pub dropped: bool,
+}
+
+impl RecordSequence {
+    const SIZE: usize = 17;
+
+    pub fn new(source: u32, task_number: u16, sequence: u64) -> Self {
+        RecordSequence {
+            source,
+            task_number,
+            sequence,
+            fanout: 0,
+            dropped: false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Hash)]
 pub struct Record {
     pub partition_num: u16,
@@ -102,17 +146,25 @@ pub struct Record {
     pub(crate) trigger_window: Option<Window>,
 
     pub(crate) values: Buffer,
+    /// the null bitmap of the fields, bit `n` marks the field with index `n` null.
+    /// it's empty if there is no null field, and the trailing zero bytes are trimmed
+    pub(crate) nulls: Vec<u8>,
+    /// stamped by the sources in the assertion mode
+    pub(crate) sequence: Option<RecordSequence>,
 }
 
 impl Ord for Record {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.values.as_slice().cmp(other.values.as_slice())
+        self.values
+            .as_slice()
+            .cmp(other.values.as_slice())
+            .then_with(|| self.nulls.cmp(&other.nulls))
     }
 }
 
 impl PartialOrd for Record {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.values.as_slice().partial_cmp(other.values.as_slice())
+        Some(self.cmp(other))
     }
 }
 
@@ -120,7 +172,7 @@ impl Eq for Record {}
 
 impl PartialEq for Record {
     fn eq(&self, other: &Self) -> bool {
-        self.values.as_slice().eq(other.values.as_slice())
+        self.values.as_slice().eq(other.values.as_slice()) && self.nulls.eq(&other.nulls)
     }
 }
 
@@ -133,6 +185,8 @@ impl Record {
             location_windows: None,
             trigger_window: None,
             values: Buffer::new(),
+            nulls: Vec::new(),
+            sequence: None,
         }
     }
 
@@ -144,6 +198,8 @@ impl Record {
             location_windows: None,
             trigger_window: None,
             values: Buffer::with_capacity(capacity),
+            nulls: Vec::new(),
+            sequence: None,
         }
     }
 
@@ -151,11 +207,52 @@ impl Record {
         self.values.len()
     }
 
-    pub fn extend(&mut self, record: Record) -> Result<(), std::io::Error> {
-        self.values.extend(&record.values)
+    /// append the values of `record`, `arity` is the number of fields in `self`,
+    /// the null marks of `record` are shifted by it
+    pub fn extend(&mut self, record: Record, arity: usize) -> Result<(), std::io::Error> {
+        self.values.extend(&record.values)?;
+        for (byte_index, b) in record.nulls.iter().enumerate() {
+            for bit_index in 0..8 {
+                if b & (1u8 << bit_index) != 0 {
+                    self.set_null(arity + byte_index * 8 + bit_index, true);
+                }
+            }
+        }
+        Ok(())
     }
 
-    pub(crate) fn set_location_windows(&mut self, windows: Vec<Window>) {
+    /// mark the field with the `index` null or not null,
+    /// the value of the field in the buffer should be written as the default value
+    pub fn set_null(&mut self, index: usize, is_null: bool) {
+        let (byte_index, bit) = (index / 8, 1u8 << (index % 8));
+        if is_null {
+            if self.nulls.len() <= byte_index {
+                self.nulls.resize(byte_index + 1, 0);
+            }
+            self.nulls[byte_index] |= bit;
+        } else if byte_index < self.nulls.len() {
+            self.nulls[byte_index] &= !bit;
+            while let Some(0) = self.nulls.last() {
+                self.nulls.pop();
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_null(&self, index: usize) -> bool {
+        self.nulls
+            .get(index / 8)
+            .map(|b| b & (1u8 << (index % 8)) != 0)
+            .unwrap_or(false)
+    }
+
+    #[inline]
+    pub fn has_null(&self) -> bool {
+        !self.nulls.is_empty()
+    }
+
+    /// the windows the record belongs to, assigned by the `WindowAssigner`
+    pub fn set_location_windows(&mut self, windows: Vec<Window>) {
         self.location_windows = Some(windows);
     }
 
@@ -191,6 +288,11 @@ impl Record {
         self.trigger_window.clone()
     }
 
+    /// the event time of the record assigned by the `WatermarkStrategy`
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
     pub fn as_buffer(&mut self) -> &mut Buffer {
         self.values.borrow_mut()
     }
@@ -210,6 +312,18 @@ impl Record {
     pub fn len(&self) -> usize {
         self.values.len()
     }
+
+    /// the stamp of a record dropped in the assertion mode, see `RecordSequence::dropped`
+    pub(crate) fn dropped(mut sequence: RecordSequence) -> Self {
+        sequence.dropped = true;
+        let mut record = Record::new();
+        record.sequence = Some(sequence);
+        record
+    }
+
+    pub(crate) fn is_dropped(&self) -> bool {
+        self.sequence.map(|x| x.dropped).unwrap_or(false)
+    }
 }
 
 impl Partition for Record {
@@ -224,7 +338,17 @@ impl Partition for Record {
 
 impl Serde for Record {
     fn capacity(&self) -> usize {
-        15 + self.values.len()
+        let nulls_len = if self.has_null() {
+            2 + self.nulls.len()
+        } else {
+            0
+        };
+        let sequence_len = self.sequence.map(|_| RecordSequence::SIZE).unwrap_or(0);
+        let trailers_len = match nulls_len + sequence_len {
+            0 => 0,
+            n => 1 + n,
+        };
+        15 + self.values.len() + trailers_len
     }
 
     fn serialize(&self, bytes: &mut BytesMut) {
@@ -240,6 +364,33 @@ impl Serde for Record {
         assert_eq!(data_slice.len(), value_len);
 
         bytes.put_slice(data_slice);
+
+        let mut trailers = 0u8;
+        if self.has_null() {
+            trailers |= RECORD_TRAILER_NULLS;
+        }
+        if self.sequence.is_some() {
+            trailers |= RECORD_TRAILER_SEQUENCE;
+        }
+        // the record without trailers keeps the layout without the flags byte
+        if trailers == 0 {
+            return;
+        }
+        bytes.put_u8(trailers);
+
+        if self.has_null() {
+            bytes.put_u16(self.nulls.len() as u16);
+            bytes.put_slice(self.nulls.as_slice());
+        }
+
+        // the stamp of the assertion mode
+        if let Some(sequence) = &self.sequence {
+            bytes.put_u32(sequence.source);
+            bytes.put_u16(sequence.task_number);
+            bytes.put_u64(sequence.sequence);
+            bytes.put_u16(sequence.fanout);
+            bytes.put_u8(sequence.dropped as u8);
+        }
     }
 
     fn deserialize(bytes: &mut BytesMut) -> Self {
@@ -250,10 +401,40 @@ impl Serde for Record {
         let timestamp = bytes.get_u64();
 
         let value_len = bytes.get_u32() as usize;
-        assert_eq!(bytes.remaining(), value_len);
-
         let values = bytes.split_to(value_len);
 
+        let trailers = if bytes.has_remaining() {
+            bytes.get_u8()
+        } else {
+            0
+        };
+        assert_eq!(
+            trailers & !(RECORD_TRAILER_NULLS | RECORD_TRAILER_SEQUENCE),
+            0,
+            "Invalid `Record` trailers {:#b}",
+            trailers
+        );
+
+        let nulls = if trailers & RECORD_TRAILER_NULLS != 0 {
+            let nulls_len = bytes.get_u16() as usize;
+            bytes.split_to(nulls_len).to_vec()
+        } else {
+            Vec::new()
+        };
+
+        let sequence = if trailers & RECORD_TRAILER_SEQUENCE != 0 {
+            Some(RecordSequence {
+                source: bytes.get_u32(),
+                task_number: bytes.get_u16(),
+                sequence: bytes.get_u64(),
+                fanout: bytes.get_u16(),
+                dropped: bytes.get_u8() != 0,
+            })
+        } else {
+            None
+        };
+        assert_eq!(bytes.remaining(), 0, "Invalid `Record` trailing bytes");
+
         Record {
             partition_num,
             timestamp,
@@ -261,6 +442,8 @@ impl Serde for Record {
             location_windows: None,
             trigger_window: None,
             values: Buffer::from(values),
+            nulls,
+            sequence,
         }
     }
 }
@@ -294,6 +477,10 @@ impl Watermark {
         }
     }
 
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
     pub(crate) fn set_location_windows(&mut self, windows: Vec<Window>) {
         self.location_windows = Some(windows);
     }
@@ -540,6 +727,14 @@ impl Element {
         }
     }
 
+    /// the stamp of a dropped record in the assertion mode, see `RecordSequence::dropped`
+    pub(crate) fn is_dropped_record(&self) -> bool {
+        match self {
+            Element::Record(record) => record.is_dropped(),
+            _ => false,
+        }
+    }
+
     pub(crate) fn as_record_mut(&mut self) -> &mut Record {
         match self {
             Element::Record(record) => record,
@@ -711,7 +906,7 @@ mod tests {
 
     use serbuffer::types;
 
-    use crate::core::element::{Element, Record, Serde, StreamStatus, Watermark};
+    use crate::core::element::{Element, Record, RecordSequence, Serde, StreamStatus, Watermark};
 
     #[test]
     pub fn serde_element_record_test() {
@@ -733,6 +928,7 @@ mod tests {
         writer.set_i32(30).unwrap();
         writer.set_i64(40).unwrap();
         writer.set_binary("abc".as_bytes()).unwrap();
+        record.set_null(9, true);
 
         let record_clone = record.clone();
         let reader = record.as_reader(&data_types);
@@ -750,6 +946,77 @@ mod tests {
             reader.get_binary(4).unwrap(),
             de_reader.get_binary(4).unwrap()
         );
+
+        let record_de = element_record_de.as_record_mut();
+        assert!(record_de.is_null(9));
+        assert!(!record_de.is_null(2));
+        record_de.set_null(9, false);
+        assert!(!record_de.has_null());
+    }
+
+    #[test]
+    pub fn serde_record_layout_test() {
+        let data_types = vec![types::U32, types::I64];
+
+        let mut record = Record::new();
+        record.partition_num = 2;
+        record.timestamp = 3;
+        let mut writer = record.as_writer(&data_types);
+        writer.set_u32(10).unwrap();
+        writer.set_i64(20).unwrap();
+
+        // the record without the null marks and the sequence is in the layout without trailers
+        let mut data = record.to_bytes();
+        assert_eq!(data.len(), 15 + record.values.len());
+        assert_eq!(data.len(), record.capacity());
+        let record_de = Record::deserialize(data.borrow_mut());
+        assert_eq!(record_de, record);
+        assert!(!record_de.has_null());
+        assert!(record_de.sequence.is_none());
+
+        // the null marks and the sequence round-trip by the trailers
+        record.set_null(1, true);
+        record.set_null(12, true);
+        record.sequence = Some(RecordSequence {
+            source: 1,
+            task_number: 2,
+            sequence: 3,
+            fanout: 4,
+            dropped: false,
+        });
+        let mut data = record.to_bytes();
+        assert_eq!(data.len(), record.capacity());
+        let record_de = Record::deserialize(data.borrow_mut());
+        assert_eq!(record_de, record);
+        assert!(record_de.is_null(1));
+        assert!(record_de.is_null(12));
+        assert!(!record_de.is_null(0));
+        assert_eq!(record_de.sequence, record.sequence);
+    }
+
+    #[test]
+    pub fn extend_record_test() {
+        let mut record = Record::new();
+        let mut writer = record.as_writer(&[types::U32, types::U32]);
+        writer.set_u32(1).unwrap();
+        writer.set_u32(0).unwrap();
+        record.set_null(1, true);
+
+        let mut other = Record::new();
+        let mut writer = other.as_writer(&[types::U32, types::U32]);
+        writer.set_u32(0).unwrap();
+        writer.set_u32(4).unwrap();
+        other.set_null(0, true);
+
+        record.extend(other, 2).unwrap();
+
+        let reader = record.as_reader(&[types::U32, types::U32, types::U32, types::U32]);
+        assert_eq!(reader.get_u32(0).unwrap(), 1);
+        assert_eq!(reader.get_u32(3).unwrap(), 4);
+        assert!(!record.is_null(0));
+        assert!(record.is_null(1));
+        assert!(record.is_null(2));
+        assert!(!record.is_null(3));
     }
 
     #[test]
@@ -0,0 +1,137 @@
+use std::fmt::Debug;
+
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+
+/// the env name of the hex encoded 256-bit master key used by `EnvKeyProvider`
+pub const DEFAULT_CHECKPOINT_KEY_ENV: &str = "RLINK_CHECKPOINT_KEY";
+
+/// the length of AES-256 key
+pub const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// The master key(key encryption key) of the envelope encryption, the data keys are wrapped
+/// by it and stored with the ciphertext. Implement it to wrap the data keys by a KMS,
+/// the master key never leaves the KMS.
+/// It's invoked by the `Coordinator` when the checkpoints are saved and loaded.
+pub trait KeyProvider: Send + Sync + Debug {
+    /// wrap the `data_key`, return the id of the master key and the wrapped key
+    fn wrap_key(&self, data_key: &[u8]) -> crate::core::Result<(String, Vec<u8>)>;
+
+    /// unwrap the `wrapped_key` by the master key of `key_id`
+    fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> crate::core::Result<Vec<u8>>;
+}
+
+/// The master key is read from a hex encoded env, the data keys are wrapped by AES-256-GCM.
+/// The key id is the name of the env, so the key can be rotated by a new env name
+/// while the old env is kept by `previous_key` for the old checkpoints.
+#[derive(Debug)]
+pub struct EnvKeyProvider {
+    env_name: String,
+    previous_env_names: Vec<String>,
+}
+
+impl EnvKeyProvider {
+    pub fn new(env_name: &str) -> Self {
+        EnvKeyProvider {
+            env_name: env_name.to_string(),
+            previous_env_names: Vec::new(),
+        }
+    }
+
+    /// the rotated master key, only used to unwrap the data keys of the old checkpoints
+    pub fn previous_key(mut self, env_name: &str) -> Self {
+        self.previous_env_names.push(env_name.to_string());
+        self
+    }
+
+    fn master_key(env_name: &str) -> crate::core::Result<Vec<u8>> {
+        let value = std::env::var(env_name)
+            .map_err(|_e| format!("the checkpoint key env `{}` not found", env_name))?;
+        let key = hex::decode(value.trim())
+            .map_err(|e| format!("the checkpoint key env `{}` is not hex. {}", env_name, e))?;
+        if key.len() != KEY_LEN {
+            return Err(crate::core::Error::from(format!(
+                "the checkpoint key env `{}` must be {} bytes",
+                env_name, KEY_LEN
+            )));
+        }
+        Ok(key)
+    }
+}
+
+impl Default for EnvKeyProvider {
+    fn default() -> Self {
+        EnvKeyProvider::new(DEFAULT_CHECKPOINT_KEY_ENV)
+    }
+}
+
+impl KeyProvider for EnvKeyProvider {
+    fn wrap_key(&self, data_key: &[u8]) -> crate::core::Result<(String, Vec<u8>)> {
+        let master_key = EnvKeyProvider::master_key(self.env_name.as_str())?;
+        let wrapped_key = seal(master_key.as_slice(), data_key, &[])?;
+        Ok((self.env_name.clone(), wrapped_key))
+    }
+
+    fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> crate::core::Result<Vec<u8>> {
+        if !self.env_name.eq(key_id) && !self.previous_env_names.iter().any(|x| x.eq(key_id)) {
+            return Err(crate::core::Error::from(format!(
+                "unknown checkpoint key id `{}`",
+                key_id
+            )));
+        }
+
+        let master_key = EnvKeyProvider::master_key(key_id)?;
+        open(master_key.as_slice(), wrapped_key, &[])
+    }
+}
+
+/// random bytes from the OS CSPRNG
+pub fn random_bytes(len: usize) -> crate::core::Result<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    rand_bytes(bytes.as_mut_slice()).map_err(crate::core::Error::wrap)?;
+    Ok(bytes)
+}
+
+/// AES-256-GCM encrypt, the output is `nonce | ciphertext | tag`.
+/// The `aad` is authenticated but not encrypted, the same `aad` must be given to `open`
+pub fn seal(key: &[u8], plaintext: &[u8], aad: &[u8]) -> crate::core::Result<Vec<u8>> {
+    let nonce = random_bytes(NONCE_LEN)?;
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(nonce.as_slice()),
+        aad,
+        plaintext,
+        &mut tag,
+    )
+    .map_err(crate::core::Error::wrap)?;
+
+    let mut output = nonce;
+    output.extend_from_slice(ciphertext.as_slice());
+    output.extend_from_slice(&tag);
+    Ok(output)
+}
+
+/// AES-256-GCM decrypt the output of `seal`, fail if the data or the `aad` is tampered
+pub fn open(key: &[u8], sealed: &[u8], aad: &[u8]) -> crate::core::Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return Err(crate::core::Error::from("the sealed data is truncated"));
+    }
+
+    let (nonce, rest) = sealed.split_at(NONCE_LEN);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+    decrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(nonce),
+        aad,
+        ciphertext,
+        tag,
+    )
+    .map_err(|_e| {
+        crate::core::Error::from("decrypt error, the key is wrong or the data is tampered")
+    })
+}
@@ -1,12 +1,25 @@
 use std::cell::RefCell;
+use std::ops::DerefMut;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
 
+use crate::channel::ChannelOptions;
+use crate::core::alert::AlertNotifier;
+use crate::core::config::{ExecutionConfig, ExecutionConfigBuilder};
 use crate::core::data_stream::{DataStream, StreamBuilder};
+use crate::core::encryption::KeyProvider;
 use crate::core::function::InputFormat;
+use crate::core::lineage::LineageEmitter;
 use crate::core::operator::StreamOperator;
 use crate::core::properties::Properties;
+use crate::core::retry::RetryPolicy;
 use crate::core::runtime::{ClusterDescriptor, OperatorId};
-use crate::dag::RawStreamGraph;
+use crate::core::shuffle::ShuffleService;
+use crate::core::state_limit::StateLimit;
+use crate::core::write_ahead_log::WriteAheadLog;
+use crate::dag::optimizer::{optimize, OptimizeReport};
+use crate::dag::{DagError, RawStreamGraph};
 use crate::runtime;
 
 /// define a stream application
@@ -28,15 +41,64 @@ pub trait StreamApp: Send + Sync + Clone {
 #[derive(Debug)]
 pub struct StreamExecutionEnvironment {
     pub(crate) stream_manager: Rc<StreamManager>,
+    /// the typed configuration applied on the `Coordinator`, see `ExecutionEnvironment`
+    pub(crate) config: ExecutionConfig,
+    pub(crate) alert_notifiers: Vec<Box<dyn AlertNotifier>>,
+    pub(crate) lineage_emitters: Vec<Box<dyn LineageEmitter>>,
+    pub(crate) checkpoint_key_provider: Option<Arc<dyn KeyProvider>>,
+    pub(crate) shuffle_service: Option<Arc<dyn ShuffleService>>,
 }
 
 impl StreamExecutionEnvironment {
     pub(crate) fn new() -> Self {
         StreamExecutionEnvironment {
             stream_manager: Rc::new(StreamManager::new()),
+            config: ExecutionConfig::default(),
+            alert_notifiers: Vec::new(),
+            lineage_emitters: Vec::new(),
+            checkpoint_key_provider: None,
+            shuffle_service: None,
         }
     }
 
+    /// Register a notifier for the alerts of the application, eg: `SlackAlertNotifier`,
+    /// the alerts are triggered by the `AlertRules` in the properties on the `Coordinator`
+    pub fn register_alert_notifier<N>(&mut self, alert_notifier: N)
+    where
+        N: AlertNotifier + 'static,
+    {
+        self.alert_notifiers.push(Box::new(alert_notifier));
+    }
+
+    /// Register an emitter of the OpenLineage events, eg: `HttpLineageEmitter`, the lineage of
+    /// the sources, the operators and the sinks is emitted by the `Coordinator` at the submission
+    pub fn register_lineage_emitter<E>(&mut self, lineage_emitter: E)
+    where
+        E: LineageEmitter + 'static,
+    {
+        self.lineage_emitters.push(Box::new(lineage_emitter));
+    }
+
+    /// Encrypt the checkpoint handles at rest by the envelope encryption, the data keys are
+    /// wrapped by the `key_provider`, eg: `EnvKeyProvider` or a KMS implementation.
+    /// the handles saved without encryption can still be loaded
+    pub fn set_checkpoint_key_provider<P>(&mut self, key_provider: P)
+    where
+        P: KeyProvider + 'static,
+    {
+        self.checkpoint_key_provider = Some(Arc::new(key_provider));
+    }
+
+    /// Exchange the data of the network edges by the `shuffle_service` instead of the pipelined
+    /// channels, eg: `FileShuffleService`. The downstream tasks read the output of an upstream
+    /// task after it finished, so it's for the bounded jobs only
+    pub fn set_shuffle_service<S>(&mut self, shuffle_service: S)
+    where
+        S: ShuffleService + 'static,
+    {
+        self.shuffle_service = Some(Arc::new(shuffle_service));
+    }
+
     pub fn register_source<I>(&mut self, input_format: I) -> DataStream
     where
         I: InputFormat + 'static,
@@ -51,11 +113,38 @@ impl StreamExecutionEnvironment {
     }
 }
 
+/// Build the typed configuration of the application instead of the property strings, eg:
+/// ```ignore
+/// let config = ExecutionEnvironment::builder()
+///     .checkpoint_interval(Duration::from_secs(30))
+///     .state_backend(KeyedStateBackend::Memory)
+///     .restart_strategy(RestartStrategy::Always)
+///     .build()?;
+/// execute_with_config(MyStreamApp {}, config);
+/// ```
+pub struct ExecutionEnvironment {}
+
+impl ExecutionEnvironment {
+    pub fn builder() -> ExecutionConfigBuilder {
+        ExecutionConfig::builder()
+    }
+}
+
 pub fn execute<S>(stream_app: S)
 where
     S: StreamApp + 'static,
 {
-    let stream_env = StreamExecutionEnvironment::new();
+    execute_with_config(stream_app, ExecutionConfig::default())
+}
+
+/// execute the application with the typed configuration, the configured settings override
+/// the properties set by `StreamApp::prepare_properties`
+pub fn execute_with_config<S>(stream_app: S, config: ExecutionConfig)
+where
+    S: StreamApp + 'static,
+{
+    let mut stream_env = StreamExecutionEnvironment::new();
+    stream_env.config = config;
     match runtime::run(stream_env, stream_app) {
         Ok(_) => {}
         Err(e) => {
@@ -89,4 +178,69 @@ impl StreamManager {
             .add_operator(operator, parent_operator_ids)
             .expect("add operator error")
     }
+
+    pub fn set_channel_options(&self, operator_id: OperatorId, channel_options: ChannelOptions) {
+        self.stream_graph
+            .borrow_mut()
+            .set_channel_options(operator_id, channel_options)
+            .expect("set channel options error")
+    }
+
+    /// the operators added to the graph with their display names
+    pub(crate) fn operator_names(&self) -> Vec<(OperatorId, String)> {
+        self.stream_graph
+            .borrow()
+            .dag
+            .raw_nodes()
+            .iter()
+            .map(|node| (node.weight.id, node.weight.display_name().to_string()))
+            .collect()
+    }
+
+    pub fn set_name(&self, operator_id: OperatorId, name: &str) {
+        self.stream_graph
+            .borrow_mut()
+            .set_name(operator_id, name)
+            .expect("set operator name error")
+    }
+
+    pub fn set_description(&self, operator_id: OperatorId, description: &str) {
+        self.stream_graph
+            .borrow_mut()
+            .set_description(operator_id, description)
+            .expect("set operator description error")
+    }
+
+    /// optimize the graph built by the `StreamApp`, see `SystemProperties::set_stream_graph_optimizer`
+    pub fn optimize(&self) -> Result<OptimizeReport, DagError> {
+        optimize(self.stream_graph.borrow_mut().deref_mut())
+    }
+
+    pub fn set_processing_timeout(&self, operator_id: OperatorId, timeout: Duration) {
+        self.stream_graph
+            .borrow_mut()
+            .set_processing_timeout(operator_id, timeout)
+            .expect("set operator processing timeout error")
+    }
+
+    pub fn set_retry_policy(&self, operator_id: OperatorId, retry_policy: RetryPolicy) {
+        self.stream_graph
+            .borrow_mut()
+            .set_retry_policy(operator_id, retry_policy)
+            .expect("set operator retry policy error")
+    }
+
+    pub fn set_state_limit(&self, operator_id: OperatorId, state_limit: StateLimit) {
+        self.stream_graph
+            .borrow_mut()
+            .set_state_limit(operator_id, state_limit)
+            .expect("set operator state limit error")
+    }
+
+    pub fn set_write_ahead_log(&self, operator_id: OperatorId, write_ahead_log: WriteAheadLog) {
+        self.stream_graph
+            .borrow_mut()
+            .set_write_ahead_log(operator_id, write_ahead_log)
+            .expect("set operator write-ahead log error")
+    }
 }
@@ -45,3 +45,233 @@ impl std::fmt::Display for Error {
         self.inner_error.fmt(f)
     }
 }
+
+/// The class of the errors, the restart policy and the alerts can branch on it
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq, Hash)]
+pub enum ErrorClass {
+    Source,
+    Checkpoint,
+    Network,
+    UserFunction,
+    /// the error is not classified, eg: created by `Error::from("...")`
+    Unknown,
+}
+
+impl ErrorClass {
+    /// the class of the error `code`, the codes of a class are in a range of thousand
+    pub fn of_code(code: u16) -> Self {
+        match code / 1000 {
+            1 => ErrorClass::Source,
+            2 => ErrorClass::Checkpoint,
+            3 => ErrorClass::Network,
+            4 => ErrorClass::UserFunction,
+            _ => ErrorClass::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// the error code of the unclassified errors
+pub const UNKNOWN_ERROR_CODE: u16 = 0;
+
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum SourceError {
+    #[error("source open error. {0}")]
+    Open(String),
+    #[error("source read error. {0}")]
+    Read(String),
+    #[error("source deserialize error. {0}")]
+    Deserialize(String),
+}
+
+impl SourceError {
+    pub fn code(&self) -> u16 {
+        match self {
+            SourceError::Open(_) => 1001,
+            SourceError::Read(_) => 1002,
+            SourceError::Deserialize(_) => 1003,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum CheckpointError {
+    #[error("checkpoint snapshot error. {0}")]
+    Snapshot(String),
+    #[error("checkpoint restore error. {0}")]
+    Restore(String),
+    #[error("checkpoint storage error. {0}")]
+    Storage(String),
+    #[error("checkpoint align error. {0}")]
+    Align(String),
+}
+
+impl CheckpointError {
+    pub fn code(&self) -> u16 {
+        match self {
+            CheckpointError::Snapshot(_) => 2001,
+            CheckpointError::Restore(_) => 2002,
+            CheckpointError::Storage(_) => 2003,
+            CheckpointError::Align(_) => 2004,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum NetworkError {
+    #[error("network connect error. {0}")]
+    Connect(String),
+    #[error("network subscribe error. {0}")]
+    Subscribe(String),
+    #[error("network publish error. {0}")]
+    Publish(String),
+    #[error("network timeout. {0}")]
+    Timeout(String),
+}
+
+impl NetworkError {
+    pub fn code(&self) -> u16 {
+        match self {
+            NetworkError::Connect(_) => 3001,
+            NetworkError::Subscribe(_) => 3002,
+            NetworkError::Publish(_) => 3003,
+            NetworkError::Timeout(_) => 3004,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum UserFunctionError {
+    #[error("user function open error. {0}")]
+    Open(String),
+    #[error("user function process error. {0}")]
+    Process(String),
+    #[error("user function close error. {0}")]
+    Close(String),
+    #[error("user function timeout. {0}")]
+    Timeout(String),
+    #[error("operator state limit exceeded. {0}")]
+    StateLimit(String),
+}
+
+impl UserFunctionError {
+    pub fn code(&self) -> u16 {
+        match self {
+            UserFunctionError::Open(_) => 4001,
+            UserFunctionError::Process(_) => 4002,
+            UserFunctionError::Close(_) => 4003,
+            UserFunctionError::Timeout(_) => 4004,
+            UserFunctionError::StateLimit(_) => 4005,
+        }
+    }
+}
+
+macro_rules! from_typed_error {
+    ($t:ty) => {
+        impl From<$t> for Error {
+            fn from(e: $t) -> Self {
+                Error {
+                    inner_error: anyhow::Error::new(e),
+                }
+            }
+        }
+    };
+}
+
+from_typed_error!(SourceError);
+from_typed_error!(CheckpointError);
+from_typed_error!(NetworkError);
+from_typed_error!(UserFunctionError);
+
+/// the code of the typed error in the chain of the `error`
+fn error_code(error: &anyhow::Error) -> u16 {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<SourceError>() {
+            return e.code();
+        } else if let Some(e) = cause.downcast_ref::<CheckpointError>() {
+            return e.code();
+        } else if let Some(e) = cause.downcast_ref::<NetworkError>() {
+            return e.code();
+        } else if let Some(e) = cause.downcast_ref::<UserFunctionError>() {
+            return e.code();
+        } else if let Some(e) = cause.downcast_ref::<Error>() {
+            return e.code();
+        }
+    }
+    UNKNOWN_ERROR_CODE
+}
+
+impl Error {
+    /// the code of the typed error, `UNKNOWN_ERROR_CODE` if the error is not classified
+    pub fn code(&self) -> u16 {
+        error_code(&self.inner_error)
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        ErrorClass::of_code(self.code())
+    }
+
+    /// classify the error by `f` if it is not classified, the classified error is kept
+    pub fn or_classify<E, F>(self, f: F) -> Self
+    where
+        E: Into<Error>,
+        F: FnOnce(String) -> E,
+    {
+        match self.class() {
+            ErrorClass::Unknown => f(self.to_string()).into(),
+            _ => self,
+        }
+    }
+}
+
+/// The error reported by a failed task to the coordinator
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct ErrorReport {
+    pub class: ErrorClass,
+    pub code: u16,
+    pub message: String,
+}
+
+impl<'a> From<&'a anyhow::Error> for ErrorReport {
+    fn from(error: &'a anyhow::Error) -> Self {
+        let code = error_code(error);
+        ErrorReport {
+            class: ErrorClass::of_code(code),
+            code,
+            message: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::error::{
+        Error, ErrorClass, ErrorReport, SourceError, UserFunctionError, UNKNOWN_ERROR_CODE,
+    };
+
+    #[test]
+    pub fn error_class_test() {
+        let error = Error::from("unclassified");
+        assert_eq!(error.code(), UNKNOWN_ERROR_CODE);
+        assert_eq!(error.class(), ErrorClass::Unknown);
+
+        let error = error.or_classify(UserFunctionError::Open);
+        assert_eq!(error.code(), 4001);
+        assert_eq!(error.class(), ErrorClass::UserFunction);
+        // the classified error is kept
+        let error = error.or_classify(SourceError::Open);
+        assert_eq!(error.class(), ErrorClass::UserFunction);
+
+        // the typed error is found in the chain
+        let error = anyhow::Error::new(Error::from(SourceError::Read("eof".to_string())))
+            .context("run task");
+        let report = ErrorReport::from(&error);
+        assert_eq!(report.class, ErrorClass::Source);
+        assert_eq!(report.code, 1002);
+    }
+}
@@ -0,0 +1,158 @@
+//! The named exchanges share the output of an operator with the other applications, eg: several
+//! applications consume the same enrichment output instead of computing it again.
+//!
+//! The publisher writes the records to the transport of the exchange in the binary format of
+//! `Serde for Record`, and registers the exchange with the schema of the records to the
+//! `ExchangeCatalog`. The subscribers look up the catalog by the name to read the transport
+//! and to build their sources with the published schema.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::core::element::FnSchema;
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the transport of the records of an exchange
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ExchangeTransport {
+    Kafka {
+        bootstrap_servers: String,
+        topic: String,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExchangeDescriptor {
+    pub name: String,
+    /// the application publishing the exchange
+    pub application_name: String,
+    /// the schema of the records
+    pub schema: FnSchema,
+    pub transport: ExchangeTransport,
+    /// the millis the exchange is published, updated by each publishing
+    pub publish_timestamp: u64,
+}
+
+impl ExchangeDescriptor {
+    pub fn new(
+        name: &str,
+        application_name: &str,
+        schema: FnSchema,
+        transport: ExchangeTransport,
+    ) -> Self {
+        ExchangeDescriptor {
+            name: name.to_string(),
+            application_name: application_name.to_string(),
+            schema,
+            transport,
+            publish_timestamp: current_timestamp_millis(),
+        }
+    }
+}
+
+/// The registry of the exchanges shared by the publishers and the subscribers
+pub trait ExchangeCatalog: Send + Sync {
+    /// register or update the exchange, an exchange is published by only one application
+    fn publish(&self, descriptor: &ExchangeDescriptor) -> anyhow::Result<()>;
+
+    fn lookup(&self, name: &str) -> anyhow::Result<ExchangeDescriptor>;
+
+    fn list(&self) -> anyhow::Result<Vec<ExchangeDescriptor>>;
+}
+
+/// The catalog of the json files `{name}.json` in a directory shared by the applications,
+/// eg: a nfs mount
+#[derive(Clone, Debug)]
+pub struct FileExchangeCatalog {
+    dir: PathBuf,
+}
+
+impl FileExchangeCatalog {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        FileExchangeCatalog { dir: dir.into() }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+impl ExchangeCatalog for FileExchangeCatalog {
+    fn publish(&self, descriptor: &ExchangeDescriptor) -> anyhow::Result<()> {
+        if let Ok(published) = self.lookup(descriptor.name.as_str()) {
+            if published.application_name != descriptor.application_name {
+                return Err(anyhow!(
+                    "the exchange `{}` is published by the application `{}`",
+                    descriptor.name,
+                    published.application_name
+                ));
+            }
+        }
+
+        fs::create_dir_all(self.dir.as_path())?;
+        let content = serde_json::to_string_pretty(descriptor)?;
+
+        // write a temp file then rename it, so the subscribers never read a partial file
+        let path = self.path(descriptor.name.as_str());
+        let tmp_path = path.with_extension(format!("json.{}", std::process::id()));
+        fs::write(tmp_path.as_path(), content)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> anyhow::Result<ExchangeDescriptor> {
+        let path = self.path(name);
+        let content = fs::read_to_string(path.as_path())
+            .map_err(|e| anyhow!("the exchange `{}` not found at {:?}. {}", name, path, e))?;
+        serde_json::from_str(content.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<ExchangeDescriptor>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut descriptors = Vec::new();
+        for entry in fs::read_dir(self.dir.as_path())? {
+            let path = entry?.path();
+            if path.extension().map(|x| x == "json").unwrap_or(false) {
+                let content = fs::read_to_string(path)?;
+                descriptors.push(serde_json::from_str(content.as_str())?);
+            }
+        }
+        Ok(descriptors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::element::FnSchema;
+    use crate::core::exchange::{
+        ExchangeCatalog, ExchangeDescriptor, ExchangeTransport, FileExchangeCatalog,
+    };
+
+    #[test]
+    pub fn file_exchange_catalog_test() {
+        let dir = std::env::temp_dir().join(format!("rlink_exchange_{}", std::process::id()));
+        let catalog = FileExchangeCatalog::new(dir.as_path());
+
+        let schema = FnSchema::Single(Schema::new(vec![Field::new("id", DataType::Int64)]));
+        let transport = ExchangeTransport::Kafka {
+            bootstrap_servers: "localhost:9092".to_string(),
+            topic: "rlink-exchange-enrichment".to_string(),
+        };
+        let descriptor = ExchangeDescriptor::new("enrichment", "app_a", schema, transport.clone());
+        catalog.publish(&descriptor).unwrap();
+
+        let published = catalog.lookup("enrichment").unwrap();
+        assert_eq!(published.transport, transport);
+        assert_eq!(published.schema.first().field(0).name(), "id");
+        assert_eq!(catalog.list().unwrap().len(), 1);
+
+        let other = ExchangeDescriptor::new("enrichment", "app_b", FnSchema::Empty, transport);
+        assert!(catalog.publish(&other).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,10 +1,25 @@
+use std::collections::BTreeMap;
 use std::fmt::Debug;
-
-use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::channel::ChannelOptions;
+use crate::core::backend::{JoinState, KeyedStateBackend};
+use crate::core::cache::cache_dir;
+use crate::core::cancellation::CancellationToken;
+use crate::core::checkpoint::{
+    CheckpointFunction, CheckpointHandle, CheckpointRequester, FunctionSnapshotContext,
+};
 use crate::core::element::{Element, FnSchema, Record};
-use crate::core::properties::Properties;
+use crate::core::lineage::LineageDataset;
+use crate::core::properties::{Properties, SystemProperties};
 use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
+use crate::core::shuffle::ShuffleService;
 use crate::dag::execution_graph::{ExecutionEdge, ExecutionNode};
+use crate::metrics::keyed_state::KeyGroupStats;
+use crate::runtime::worker::runnable::reduce_runnable::ReduceCheckpointHandle;
+use crate::utils::thread::{shared_runtime, DEFAULT_SHARED_RUNTIME_THREADS};
 
 /// Base class of all operators in the Rust API.
 pub trait NamedFunction {
@@ -27,6 +42,24 @@ pub struct Context {
 
     pub(crate) children: Vec<(ExecutionNode, ExecutionEdge)>,
     pub(crate) parents: Vec<(ExecutionNode, ExecutionEdge)>,
+    /// the options of the current task's input channel
+    pub(crate) channel_options: ChannelOptions,
+    /// the intermediate data service of the network edges, `None` is pipelined
+    #[serde(skip)]
+    pub(crate) shuffle_service: Option<Arc<dyn ShuffleService>>,
+    /// all sources of the application are bounded, see `InputFormat::bounded`
+    #[serde(default)]
+    pub(crate) bounded: bool,
+    /// the index of the current task's worker assigned by the coordinator
+    #[serde(default)]
+    pub worker_id: u16,
+    /// cancelled by the runtime when the task ends, fails or the application stops, the threads
+    /// and the async loops started by the function should observe it, see `CancellationToken`
+    #[serde(skip)]
+    pub cancellation_token: CancellationToken,
+    /// request a checkpoint from the source of the task, see `CheckpointRequester`
+    #[serde(skip)]
+    pub checkpoint_requester: CheckpointRequester,
 }
 
 impl Context {
@@ -38,6 +71,40 @@ impl Context {
             self.completed_checkpoint_id,
         )
     }
+
+    /// the local path of the file registered by `SystemProperties::register_cached_file`,
+    /// the archive is unpacked to the directory of the path. `None` if not registered
+    pub fn cached_file(&self, name: &str) -> Option<PathBuf> {
+        let cached_files = self
+            .application_properties
+            .get_cached_files()
+            .unwrap_or_default();
+        if cached_files.iter().any(|x| x.name.eq(name)) {
+            Some(cache_dir(self.application_id.as_str()).join(name))
+        } else {
+            None
+        }
+    }
+
+    /// the state of a streaming join backed by the `KeyedStateBackend` of the application,
+    /// the entries expire after the `ttl` passed by the watermark, see `TJoinState`
+    pub fn join_state(&self, ttl: Option<Duration>) -> JoinState {
+        let backend = self
+            .application_properties
+            .get_keyed_state_backend()
+            .unwrap_or(KeyedStateBackend::Memory);
+        JoinState::new(backend, ttl)
+    }
+
+    /// the async runtime shared by all tasks of the worker,
+    /// use it instead of creating a runtime per task, see `utils::thread::shared_runtime`
+    pub fn async_runtime(&self) -> tokio::runtime::Handle {
+        let threads = self
+            .application_properties
+            .get_shared_runtime_threads()
+            .unwrap_or(DEFAULT_SHARED_RUNTIME_THREADS);
+        shared_runtime(threads)
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -103,6 +170,67 @@ pub trait InputSplitSource {
     fn input_split_assigner(&self, input_splits: Vec<InputSplit>) -> InputSplitAssigner {
         InputSplitAssigner::new(input_splits)
     }
+
+    /// Reassign the InputSplits[`input_splits`] by the restored checkpoint handles of the tasks,
+    /// both are indexed by the task number, eg: keep the partitions on the tasks owned them.
+    /// Called by the coordinator after the checkpoints are loaded
+    ///
+    /// Returns a InputSplit vec with the same size
+    fn restore_input_splits(
+        &self,
+        input_splits: Vec<InputSplit>,
+        _checkpoint_handles: &[Option<CheckpointHandle>],
+    ) -> crate::core::Result<Vec<InputSplit>> {
+        Ok(input_splits)
+    }
+
+    /// Create the enumerator discovering the splits on the coordinator, the tasks ignore the
+    /// splits of `create_input_splits` and pull the splits one by one from the enumerator.
+    /// It's also called by the tasks to detect the pull mode, so create the connections in
+    /// `SplitEnumerator::discover` rather than here
+    ///
+    /// Returns `None` if the splits are assigned statically
+    fn split_enumerator(&self) -> Option<Box<dyn SplitEnumerator>> {
+        None
+    }
+}
+
+/// Discover the splits of a source on the coordinator, the splits are handed out to the source
+/// tasks on request, so a fast task reads more splits than a slow one. The state of the
+/// `InputFormat` is not restored for the pulled splits, the splits are discovered again after
+/// the application restarted
+pub trait SplitEnumerator: Send {
+    /// discover the new splits, called when no split is left to hand out and the
+    /// `discovery_interval` elapsed since the last call. The `split_number`s must be unique in
+    /// the source, the known ones are ignored
+    fn discover(&mut self) -> crate::core::Result<Vec<InputSplit>>;
+
+    /// no more splits will be discovered, the tasks end after all splits are completed
+    fn is_exhausted(&self) -> bool {
+        true
+    }
+
+    fn discovery_interval(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+/// The current read position of a source task, eg: the offsets of the kafka partitions,
+/// the byte offset of a file or the LSN of a CDC stream.
+/// key: the partition/file/stream, value: the position in it
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct SourcePosition {
+    pub positions: BTreeMap<String, String>,
+}
+
+impl SourcePosition {
+    pub fn new() -> Self {
+        SourcePosition::default()
+    }
+
+    pub fn put<K: ToString, V: ToString>(&mut self, key: K, value: V) {
+        self.positions.insert(key.to_string(), value.to_string());
+    }
 }
 
 /// The base interface for data sources that produces records.
@@ -130,6 +258,25 @@ where
     fn daemon(&self) -> bool {
         false
     }
+    /// mark the input of `InputFormat` is finite, eg: a file or a kafka topic with the end offsets.
+    /// if all sources are bounded, the keyed reduce is grouped by sorting with a fixed memory
+    fn bounded(&self) -> bool {
+        false
+    }
+    /// check the external system can be connected, called by the dry-run validation at the
+    /// submission when `SystemProperties::set_connector_probe` is enabled
+    fn probe(&self) -> crate::core::Result<()> {
+        Ok(())
+    }
+    /// the datasets read by the source, exported as the inputs of the lineage of the application
+    fn lineage_datasets(&self) -> Vec<LineageDataset> {
+        vec![]
+    }
+    /// the current read position of the task, reported to the coordinator by the heartbeat
+    /// and queried by `/api/source/positions`. `None` if the source has no position
+    fn position(&self) -> Option<SourcePosition> {
+        None
+    }
 
     fn schema(&self, input_schema: FnSchema) -> FnSchema;
     fn parallelism(&self) -> u16;
@@ -153,8 +300,23 @@ where
         self.write_record(element.into_record())
     }
 
+    /// observe the `Watermark`, `Barrier` and `StreamStatus` arriving at the sink,
+    /// eg: finalize the output of the event time passed by the watermark
+    fn observe_element(&mut self, _element: &Element) {}
+
     fn close(&mut self) -> crate::core::Result<()>;
 
+    /// check the external system can be connected, called by the dry-run validation at the
+    /// submission when `SystemProperties::set_connector_probe` is enabled
+    fn probe(&self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    /// the datasets written by the sink, exported as the outputs of the lineage of the application
+    fn lineage_datasets(&self) -> Vec<LineageDataset> {
+        vec![]
+    }
+
     // todo unsupported. `TwoPhaseCommitSinkFunction`
     // fn begin_transaction(&mut self) {}
     // fn prepare_commit(&mut self) {}
@@ -176,9 +338,27 @@ where
         let iterator = self.flat_map(element.into_record());
         Box::new(ElementIterator::new(iterator))
     }
+    /// observe the `Watermark`, `Barrier` and `StreamStatus` passing through,
+    /// they are forwarded to the next operator by the runtime
+    fn observe_element(&mut self, _element: &Element) {}
+
+    /// emit the records buffered by the function, called with `all = false` on the periodic
+    /// `StreamStatus` for the timed out ones, and with `all = true` before a `Barrier` and at
+    /// the end of the stream, eg: `ResequenceFlatMapFunction`
+    fn flush(&mut self, _all: bool) -> Box<dyn Iterator<Item = Record>> {
+        Box::new(std::iter::empty())
+    }
+
     fn close(&mut self) -> crate::core::Result<()>;
 
     fn schema(&self, input_schema: FnSchema) -> FnSchema;
+
+    /// the function emits each input record once and unchanged, eg: counting or logging,
+    /// so a `FilterFunction` after it is pushed before it by the stream graph optimizer,
+    /// see `SystemProperties::set_stream_graph_optimizer`
+    fn preserves_records(&self) -> bool {
+        false
+    }
 }
 
 pub trait FilterFunction
@@ -211,6 +391,17 @@ where
 
     fn schema(&self, input_schema: FnSchema) -> FnSchema;
     fn parallelism(&self) -> u16;
+
+    /// the results of `reduce` can be combined by `combine`, so the sliding windows are
+    /// assembled from the panes reduced once per record, see `WindowAssigner::pane_size`
+    fn combinable(&self) -> bool {
+        false
+    }
+
+    /// combine the `partial` result of `reduce` into the `value`
+    fn combine(&self, _value: Option<&mut Record>, _partial: &mut Record) -> Record {
+        panic!("the partial results of {} can't be combined", self.name())
+    }
 }
 
 pub(crate) trait BaseReduceFunction
@@ -223,6 +414,21 @@ where
     fn close(&mut self) -> crate::core::Result<()>;
 
     fn value_schema(&self, key_schema: FnSchema) -> FnSchema;
+
+    /// the time(ms) the close of the windows is held after the watermark, the records of the
+    /// held windows are accepted, see `WindowEmitStrategy::watermark_hold`
+    fn watermark_hold(&self) -> u64 {
+        0
+    }
+
+    /// the stats of the keyed state, `None` if the state can't be scanned
+    fn key_group_stats(&self) -> Option<KeyGroupStats> {
+        None
+    }
+
+    /// the synchronous part of the snapshot on the `Barrier`, record the current windows
+    /// as the state of the checkpoint, the handle is serialized asynchronously
+    fn snapshot_windows(&mut self, context: &FunctionSnapshotContext) -> ReduceCheckpointHandle;
 }
 
 pub trait CoProcessFunction
@@ -239,6 +445,17 @@ where
         stream_seq: usize,
         record: Record,
     ) -> Box<dyn Iterator<Item = Record>>;
+    /// This method is called when the aligned watermark of the connected streams advances,
+    /// fire the `WatermarkTimers` or `TJoinState::expire` to clean up the state of the keys
+    /// never matched.
+    fn on_watermark(&mut self, _watermark: u64) -> Box<dyn Iterator<Item = Record>> {
+        Box::new(std::iter::empty())
+    }
+    /// the number of the retained state entries, reported as a gauge after each watermark,
+    /// eg: `TJoinState::len` of the `Context::join_state`
+    fn state_entries(&self) -> usize {
+        0
+    }
     fn close(&mut self) -> crate::core::Result<()>;
 
     fn schema(&self, input_schema: FnSchema) -> FnSchema;
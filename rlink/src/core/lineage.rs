@@ -0,0 +1,56 @@
+use std::fmt::Debug;
+
+use crate::utils::http::client::post_json_sync;
+
+/// the `producer` of the OpenLineage events emitted by rlink
+pub const LINEAGE_PRODUCER: &str = "https://github.com/rlink-rs/rlink-rs";
+
+/// the default namespace of the jobs, see `SystemProperties::set_lineage_namespace`
+pub const DEFAULT_LINEAGE_NAMESPACE: &str = "rlink";
+
+/// A dataset read by a source or written by a sink, named by the OpenLineage naming conventions,
+/// eg: `kafka://broker:9092` and the topic, see `InputFormat::lineage_datasets`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LineageDataset {
+    pub namespace: String,
+    pub name: String,
+}
+
+impl LineageDataset {
+    pub fn new(namespace: &str, name: &str) -> Self {
+        LineageDataset {
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Send the OpenLineage `RunEvent`s of the application to a data catalog, invoked by the
+/// `Coordinator` in a dedicated thread, so a blocking implementation is allowed.
+///
+/// A `START` event is emitted at each submission with the input and output datasets, their
+/// schemas and the operators between them, so the catalog tracks the changed schemas by the
+/// latest event of the job.
+pub trait LineageEmitter: Send + Sync + Debug {
+    fn emit(&self, run_event: &serde_json::Value) -> anyhow::Result<()>;
+}
+
+/// Post the events to an OpenLineage http endpoint, eg: `http://marquez:5000/api/v1/lineage`
+#[derive(Clone, Debug)]
+pub struct HttpLineageEmitter {
+    url: String,
+}
+
+impl HttpLineageEmitter {
+    pub fn new(url: &str) -> Self {
+        HttpLineageEmitter {
+            url: url.to_string(),
+        }
+    }
+}
+
+impl LineageEmitter for HttpLineageEmitter {
+    fn emit(&self, run_event: &serde_json::Value) -> anyhow::Result<()> {
+        post_json_sync(self.url.as_str(), run_event.to_string()).map_err(|e| anyhow!(e))
+    }
+}
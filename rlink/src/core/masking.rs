@@ -0,0 +1,185 @@
+use crate::core::data_types::{DataType, Field, Schema};
+use crate::core::dynamic_record::Value;
+
+/// How a masked field is rewritten before the record is written to the sink
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub enum MaskingAction {
+    /// replace the String field by the `replacement`, the other types by the default value
+    Redact { replacement: String },
+    /// replace the String field by the hex of `SHA-256(salt | value)`,
+    /// so the masked values are still joinable and countable
+    Hash { salt: String },
+    /// keep the first `keep_prefix` and the last `keep_suffix` chars of the String field,
+    /// the others are replaced by `*`
+    Partial {
+        keep_prefix: usize,
+        keep_suffix: usize,
+    },
+    /// set the nullable field null
+    Nullify,
+}
+
+impl MaskingAction {
+    pub fn kind(&self) -> &str {
+        match self {
+            MaskingAction::Redact { .. } => "redact",
+            MaskingAction::Hash { .. } => "hash",
+            MaskingAction::Partial { .. } => "partial",
+            MaskingAction::Nullify => "nullify",
+        }
+    }
+
+    fn check(&self, field: &Field) -> crate::core::Result<()> {
+        let supported = match self {
+            MaskingAction::Redact { .. } => !field.data_type().is_nested(),
+            MaskingAction::Hash { .. } | MaskingAction::Partial { .. } => {
+                field.data_type().eq(&DataType::String)
+            }
+            MaskingAction::Nullify => field.is_nullable(),
+        };
+        if supported {
+            Ok(())
+        } else {
+            Err(crate::core::Error::from(format!(
+                "the masking action `{}` is not supported by the field `{}` of {:?}, nullable: {}",
+                self.kind(),
+                field.name(),
+                field.data_type(),
+                field.is_nullable()
+            )))
+        }
+    }
+
+    /// the masked value, `None` is null
+    pub fn apply(&self, data_type: &DataType, value: Option<Value>) -> Option<Value> {
+        let value = value?;
+        match (self, value) {
+            (MaskingAction::Redact { replacement }, Value::String(_)) => {
+                Some(Value::String(replacement.clone()))
+            }
+            (MaskingAction::Redact { .. }, _) => Some(Value::default_of(data_type)),
+            (MaskingAction::Hash { salt }, Value::String(v)) => {
+                let mut bytes = salt.as_bytes().to_vec();
+                bytes.extend_from_slice(v.as_bytes());
+                Some(Value::String(hex::encode(openssl::sha::sha256(
+                    bytes.as_slice(),
+                ))))
+            }
+            (
+                MaskingAction::Partial {
+                    keep_prefix,
+                    keep_suffix,
+                },
+                Value::String(v),
+            ) => {
+                let len = v.chars().count();
+                let masked = v
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        if keep_prefix + keep_suffix < len
+                            && (i < *keep_prefix || i >= len - keep_suffix)
+                        {
+                            c
+                        } else {
+                            '*'
+                        }
+                    })
+                    .collect();
+                Some(Value::String(masked))
+            }
+            (MaskingAction::Nullify, _) => None,
+            // unreachable after `check`, never write the unmasked value
+            (_, _) => Some(Value::default_of(data_type)),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct MaskingRule {
+    pub field: String,
+    pub action: MaskingAction,
+}
+
+/// The masked fields of an output schema, declared in the `Properties` by
+/// `SystemProperties::set_masking_policy` and applied by the `MaskingOutputFormat`,
+/// so the connectors don't need any masking logic and the policies can be audited centrally.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+pub struct MaskingPolicy {
+    pub rules: Vec<MaskingRule>,
+}
+
+impl MaskingPolicy {
+    pub fn new() -> Self {
+        MaskingPolicy::default()
+    }
+
+    pub fn rule(mut self, field: &str, action: MaskingAction) -> Self {
+        self.rules.push(MaskingRule {
+            field: field.to_string(),
+            action,
+        });
+        self
+    }
+
+    pub fn redact(self, field: &str) -> Self {
+        self.rule(
+            field,
+            MaskingAction::Redact {
+                replacement: "***".to_string(),
+            },
+        )
+    }
+
+    pub fn hash(self, field: &str, salt: &str) -> Self {
+        self.rule(
+            field,
+            MaskingAction::Hash {
+                salt: salt.to_string(),
+            },
+        )
+    }
+
+    pub fn partial(self, field: &str, keep_prefix: usize, keep_suffix: usize) -> Self {
+        self.rule(
+            field,
+            MaskingAction::Partial {
+                keep_prefix,
+                keep_suffix,
+            },
+        )
+    }
+
+    pub fn nullify(self, field: &str) -> Self {
+        self.rule(field, MaskingAction::Nullify)
+    }
+
+    /// resolve the rules to the field indexes of the `schema`, fail if a field is missing,
+    /// the action is not supported by the field type or a field has more than one rule
+    pub fn resolve(&self, schema: &Schema) -> crate::core::Result<Vec<Option<MaskingAction>>> {
+        let mut actions = vec![None; schema.fields().len()];
+        for rule in &self.rules {
+            let index = schema.index_of(rule.field.as_str()).ok_or_else(|| {
+                format!("the masked field `{}` not found in the schema", rule.field)
+            })?;
+            rule.action.check(schema.field(index))?;
+            if actions[index].is_some() {
+                return Err(crate::core::Error::from(format!(
+                    "the field `{}` has more than one masking rule",
+                    rule.field
+                )));
+            }
+            actions[index] = Some(rule.action.clone());
+        }
+        Ok(actions)
+    }
+
+    /// the audit description of the policy without the secrets, eg: `email:hash, phone:partial`
+    pub fn describe(&self) -> String {
+        self.rules
+            .iter()
+            .map(|rule| format!("{}:{}", rule.field, rule.action.kind()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
@@ -1,16 +1,31 @@
+pub mod alert;
 pub mod backend;
+pub mod cache;
+pub mod cancellation;
 pub mod checkpoint;
 pub mod cluster;
+pub mod composite;
+pub mod config;
 pub mod data_stream;
 pub mod data_types;
+pub mod dynamic_record;
 pub mod element;
+pub mod encryption;
 pub mod env;
 pub mod error;
+pub mod exchange;
 pub mod function;
+pub mod lineage;
+pub mod masking;
 pub mod operator;
 pub mod properties;
+pub mod rate_budget;
+pub mod retry;
 pub mod runtime;
+pub mod shuffle;
+pub mod state_limit;
 pub mod watermark;
 pub mod window;
+pub mod write_ahead_log;
 
 pub use error::*;
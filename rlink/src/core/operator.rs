@@ -6,6 +6,7 @@ use crate::core::function::{
     BaseReduceFunction, CoProcessFunction, FilterFunction, FlatMapFunction, InputFormat,
     KeySelectorFunction, NamedFunction, OutputFormat,
 };
+use crate::core::lineage::LineageDataset;
 use crate::core::watermark::WatermarkStrategy;
 use crate::core::window::WindowAssigner;
 
@@ -160,6 +161,34 @@ impl StreamOperator {
         }
     }
 
+    pub fn is_bounded(&self) -> bool {
+        if let StreamOperator::StreamSource(stream_source) = self {
+            stream_source.operator_fn.bounded()
+        } else {
+            false
+        }
+    }
+
+    /// probe the connectivity of the external system of the sources and sinks
+    pub fn probe(&self) -> crate::core::Result<()> {
+        match self {
+            StreamOperator::StreamSource(stream_source) => stream_source.operator_fn.probe(),
+            StreamOperator::StreamSink(stream_sink) => stream_sink.operator_fn.probe(),
+            _ => Ok(()),
+        }
+    }
+
+    /// the datasets of the external system read by the sources or written by the sinks
+    pub fn lineage_datasets(&self) -> Vec<LineageDataset> {
+        match self {
+            StreamOperator::StreamSource(stream_source) => {
+                stream_source.operator_fn.lineage_datasets()
+            }
+            StreamOperator::StreamSink(stream_sink) => stream_sink.operator_fn.lineage_datasets(),
+            _ => vec![],
+        }
+    }
+
     pub fn is_source(&self) -> bool {
         if let StreamOperator::StreamSource(_stream_source) = self {
             return true;
@@ -1,17 +1,27 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::ops::Index;
 use std::str::FromStr;
 use std::time::Duration;
 
+use crate::core::alert::AlertRules;
 use crate::core::backend::{CheckpointBackend, KeyedStateBackend};
+use crate::core::cache::CachedFile;
+use crate::core::checkpoint::{CheckpointRetention, ProcessingGuarantee};
 use crate::core::cluster::MetadataStorageType;
+use crate::core::error::ErrorClass;
+use crate::core::masking::MaskingPolicy;
+use crate::utils::hash::KeyHasher;
 
 pub type ClusterMode = crate::runtime::ClusterMode;
 pub type ChannelBaseOn = crate::channel::ChannelBaseOn;
 
 pub const PARALLELISM: &'static str = "parallelism";
 
+/// the label of the tenant, the checkpoints of the application are stored in the namespace of
+/// the tenant, see `SystemProperties::set_application_labels`
+pub const TENANT_LABEL: &str = "tenant";
+
 pub(crate) trait InnerSystemProperties {
     fn set_cluster_mode(&mut self, cluster_mode: ClusterMode);
 }
@@ -35,6 +45,12 @@ pub trait SystemProperties {
     fn set_checkpoint_ttl(&mut self, ttl: Duration);
     fn get_checkpoint_ttl(&self) -> anyhow::Result<Duration>;
 
+    fn set_checkpoint_retention(&mut self, retention: CheckpointRetention);
+    fn get_checkpoint_retention(&self) -> anyhow::Result<CheckpointRetention>;
+
+    fn set_alert_rules(&mut self, alert_rules: AlertRules);
+    fn get_alert_rules(&self) -> anyhow::Result<AlertRules>;
+
     fn get_cluster_mode(&self) -> anyhow::Result<ClusterMode>;
 
     fn set_pub_sub_channel_size(&mut self, channel_size: usize);
@@ -42,6 +58,136 @@ pub trait SystemProperties {
 
     fn set_pub_sub_channel_base(&mut self, base_on: ChannelBaseOn);
     fn get_pub_sub_channel_base(&self) -> anyhow::Result<ChannelBaseOn>;
+
+    /// the max time of a partially-filled network batch is held before flushing to the next job,
+    /// trade throughput for latency with a smaller timeout. default 3 seconds
+    fn set_buffer_timeout(&mut self, buffer_timeout: Duration);
+    fn get_buffer_timeout(&self) -> anyhow::Result<Duration>;
+
+    /// force all execution edges to `Network` even if the tasks are colocated, for testing
+    fn set_force_network_edge(&mut self, force_network_edge: bool);
+    fn get_force_network_edge(&self) -> anyhow::Result<bool>;
+
+    /// optimize the user operators before splitting the jobs: remove the operators without
+    /// a path to a sink, push the filters before the record-preserving maps and merge the
+    /// consecutive maps. the merged maps share the operator id of the first one, so enabling
+    /// it on a running application changes the checkpoint layout. default false
+    fn set_stream_graph_optimizer(&mut self, enable: bool);
+    fn get_stream_graph_optimizer(&self) -> anyhow::Result<bool>;
+
+    /// drain the control elements(barriers/watermarks/stream status) from any input channel
+    /// before the records when multiple channels are ready. default false
+    fn set_control_element_priority(&mut self, priority: bool);
+    fn get_control_element_priority(&self) -> anyhow::Result<bool>;
+
+    /// the priority lane of the network edges: a pulled batch is cut right after a control
+    /// element(barrier/watermark/stream status) which is flushed as a separate frame, and the
+    /// next batch is pulled without the buffer timeout. the control elements never overtake
+    /// the records of the same channel. default false
+    fn set_network_control_priority(&mut self, priority: bool);
+    fn get_network_control_priority(&self) -> anyhow::Result<bool>;
+
+    /// the max time of draining the worker on `SIGTERM`, the worker exits if the application
+    /// is not stopped with the savepoint in time. it should be less than the grace period of
+    /// the termination, eg: `terminationGracePeriodSeconds` of kubernetes. default 120s
+    fn set_worker_drain_timeout(&mut self, timeout: Duration);
+    fn get_worker_drain_timeout(&self) -> anyhow::Result<Duration>;
+
+    /// the max time of the tasks exiting after the `CancellationToken` cancelled by the stop of
+    /// the application, the worker is aborted with the stacks of the stuck tasks. default 30s
+    fn set_cancellation_grace_period(&mut self, grace_period: Duration);
+    fn get_cancellation_grace_period(&self) -> anyhow::Result<Duration>;
+
+    /// the stack size(bytes) of the task threads, for the stack-hungry user functions.
+    /// default the stack size of `std::thread`, 2MiB or the `RUST_MIN_STACK`
+    fn set_task_stack_size(&mut self, stack_size: usize);
+    fn get_task_stack_size(&self) -> anyhow::Result<usize>;
+
+    /// the labels of the application, eg: `tenant` and `team`, they are attached to all metrics
+    /// and logs of the application, and the `TENANT_LABEL` namespaces the checkpoint storage.
+    /// The labels can be set at the submission by the `labels=tenant:acme,team:ads` argument,
+    /// which overrides the labels of the same names set here
+    fn set_application_labels(&mut self, labels: &BTreeMap<String, String>);
+    fn get_application_labels(&self) -> anyhow::Result<BTreeMap<String, String>>;
+
+    /// the masking policy applied by the `MaskingOutputFormat` named `policy_name`
+    fn set_masking_policy(&mut self, policy_name: &str, policy: &MaskingPolicy);
+    fn get_masking_policy(&self, policy_name: &str) -> anyhow::Result<MaskingPolicy>;
+
+    /// the memory of the sort-based grouping of a keyed reduce in the bounded jobs,
+    /// the sorted runs are spilled to the disk when the buffered bytes reach it. default 64MB
+    fn set_sort_memory_limit(&mut self, memory_limit: usize);
+    fn get_sort_memory_limit(&self) -> anyhow::Result<usize>;
+
+    /// the directory of the spilled sorted runs. default the temp directory of the system
+    fn set_sort_spill_dir(&mut self, spill_dir: &str);
+    fn get_sort_spill_dir(&self) -> anyhow::Result<String>;
+
+    /// the worker threads of the async runtime shared by the tasks of a worker,
+    /// see `utils::thread::shared_runtime`. default 4
+    fn set_shared_runtime_threads(&mut self, threads: usize);
+    fn get_shared_runtime_threads(&self) -> anyhow::Result<usize>;
+
+    /// only validate the job at the submission without allocating the resources
+    fn set_dry_run(&mut self, dry_run: bool);
+    fn get_dry_run(&self) -> anyhow::Result<bool>;
+
+    /// probe the connectivity of the sources and sinks in the validation at the submission
+    fn set_connector_probe(&mut self, probe: bool);
+    fn get_connector_probe(&self) -> anyhow::Result<bool>;
+
+    /// the max parallelism of an operator, checked in the validation at the submission
+    fn set_max_parallelism(&mut self, max_parallelism: u16);
+    fn get_max_parallelism(&self) -> anyhow::Result<u16>;
+
+    /// the application is terminated instead of restarted when a task is failed with the error
+    /// of these classes, eg: the `UserFunction` errors are usually not recoverable by restarting
+    fn set_fatal_error_classes(&mut self, error_classes: Vec<ErrorClass>);
+    fn get_fatal_error_classes(&self) -> anyhow::Result<Vec<ErrorClass>>;
+
+    /// the default processing timeout of a record in the operators, a task which makes no
+    /// progress within it is failed with a dump of the stuck operator, disabled if not set
+    fn set_processing_timeout(&mut self, timeout: Duration);
+    fn get_processing_timeout(&self) -> anyhow::Result<Duration>;
+
+    /// the application is in a crash loop if it restarts `max_restarts` times in the `interval`,
+    /// then it's terminated instead of restarted and the logs of the workers are preserved,
+    /// `0` means restart forever
+    fn set_crash_loop_max_restarts(&mut self, max_restarts: u32);
+    fn get_crash_loop_max_restarts(&self) -> anyhow::Result<u32>;
+
+    fn set_crash_loop_interval(&mut self, interval: Duration);
+    fn get_crash_loop_interval(&self) -> anyhow::Result<Duration>;
+
+    /// a debug mode for the tests, the sources stamp each record with a sequence per task and
+    /// the sinks verify there is no gap or duplicate against the `guarantee`, the task panics
+    /// at the first violation. the records are tracked until the end, so don't enable it in
+    /// production. the lineage is broken by the `reduce` and the `connect`, so their outputs
+    /// are not verified
+    fn set_assertion_mode(&mut self, guarantee: ProcessingGuarantee);
+    fn get_assertion_mode(&self) -> anyhow::Result<ProcessingGuarantee>;
+
+    /// register a file to the distributed cache, the file is downloaded by the workers before
+    /// opening the tasks and located by `Context::cached_file(name)`, see `CachedFile`
+    fn register_cached_file(&mut self, name: &str, uri: &str, archive: bool);
+    fn get_cached_files(&self) -> anyhow::Result<Vec<CachedFile>>;
+
+    /// track the splits of the bounded sources by the coordinator, the unfinished splits of a
+    /// failed task or a split failed in the middle are reassigned to the other tasks of the
+    /// source instead of reading all splits again. a source task ends after all splits of the
+    /// source are completed. the records of the failed split are read again
+    fn set_split_reassignment(&mut self, enable: bool);
+    fn get_split_reassignment(&self) -> anyhow::Result<bool>;
+
+    /// the hash function of the keys to route the records by the key groups, see `utils::hash`.
+    /// default `KeyHasher::Murmur3`, it can't be changed when restoring from a checkpoint
+    fn set_key_hasher(&mut self, key_hasher: KeyHasher);
+    fn get_key_hasher(&self) -> anyhow::Result<KeyHasher>;
+
+    /// the namespace of the job in the OpenLineage events, default `DEFAULT_LINEAGE_NAMESPACE`,
+    /// see `StreamExecutionEnvironment::register_lineage_emitter`
+    fn set_lineage_namespace(&mut self, namespace: &str);
+    fn get_lineage_namespace(&self) -> anyhow::Result<String>;
 }
 
 pub trait FunctionProperties {
@@ -307,9 +453,41 @@ const SYSTEM_KEYED_STATE_BACKEND: &str = "SYSTEM_KEYED_STATE_BACKEND";
 const SYSTEM_CHECKPOINT: &str = "SYSTEM_CHECKPOINT";
 const SYSTEM_CHECKPOINT_INTERVAL: &str = "SYSTEM_CHECKPOINT_INTERVAL";
 const SYSTEM_CHECKPOINT_TTL: &str = "SYSTEM_CHECKPOINT_TTL";
+const SYSTEM_CHECKPOINT_RETENTION: &str = "SYSTEM_CHECKPOINT_RETENTION";
+const SYSTEM_ALERT_RULES: &str = "SYSTEM_ALERT_RULES";
 const SYSTEM_CLUSTER_MODE: &str = "SYSTEM_CLUSTER_MODE";
 const SYSTEM_PUB_SUB_CHANNEL_SIZE: &str = "SYSTEM_PUB_SUB_CHANNEL_SIZE";
 const SYSTEM_PUB_SUB_CHANNEL_BASE_ON: &str = "SYSTEM_PUB_SUB_CHANNEL_BASE_ON";
+const SYSTEM_PUB_SUB_BUFFER_TIMEOUT: &str = "SYSTEM_PUB_SUB_BUFFER_TIMEOUT";
+const SYSTEM_FORCE_NETWORK_EDGE: &str = "SYSTEM_FORCE_NETWORK_EDGE";
+const SYSTEM_STREAM_GRAPH_OPTIMIZER: &str = "SYSTEM_STREAM_GRAPH_OPTIMIZER";
+const SYSTEM_CACHED_FILES: &str = "SYSTEM_CACHED_FILES";
+const SYSTEM_CONTROL_ELEMENT_PRIORITY: &str = "SYSTEM_CONTROL_ELEMENT_PRIORITY";
+const SYSTEM_NETWORK_CONTROL_PRIORITY: &str = "SYSTEM_NETWORK_CONTROL_PRIORITY";
+const SYSTEM_WORKER_DRAIN_TIMEOUT: &str = "SYSTEM_WORKER_DRAIN_TIMEOUT";
+const SYSTEM_CANCELLATION_GRACE_PERIOD: &str = "SYSTEM_CANCELLATION_GRACE_PERIOD";
+const SYSTEM_TASK_STACK_SIZE: &str = "SYSTEM_TASK_STACK_SIZE";
+const SYSTEM_APPLICATION_LABELS: &str = "SYSTEM_APPLICATION_LABELS";
+const SYSTEM_MASKING_POLICY: &str = "SYSTEM_MASKING_POLICY";
+const SYSTEM_SORT_MEMORY_LIMIT: &str = "SYSTEM_SORT_MEMORY_LIMIT";
+const SYSTEM_SORT_SPILL_DIR: &str = "SYSTEM_SORT_SPILL_DIR";
+const SYSTEM_SHARED_RUNTIME_THREADS: &str = "SYSTEM_SHARED_RUNTIME_THREADS";
+const SYSTEM_DRY_RUN: &str = "SYSTEM_DRY_RUN";
+const SYSTEM_CONNECTOR_PROBE: &str = "SYSTEM_CONNECTOR_PROBE";
+const SYSTEM_MAX_PARALLELISM: &str = "SYSTEM_MAX_PARALLELISM";
+const SYSTEM_FATAL_ERROR_CLASSES: &str = "SYSTEM_FATAL_ERROR_CLASSES";
+const SYSTEM_PROCESSING_TIMEOUT: &str = "SYSTEM_PROCESSING_TIMEOUT";
+const SYSTEM_CRASH_LOOP_MAX_RESTARTS: &str = "SYSTEM_CRASH_LOOP_MAX_RESTARTS";
+const SYSTEM_CRASH_LOOP_INTERVAL: &str = "SYSTEM_CRASH_LOOP_INTERVAL";
+const SYSTEM_ASSERTION_MODE: &str = "SYSTEM_ASSERTION_MODE";
+const SYSTEM_SPLIT_REASSIGNMENT: &str = "SYSTEM_SPLIT_REASSIGNMENT";
+const SYSTEM_KEY_HASHER: &str = "SYSTEM_KEY_HASHER";
+const SYSTEM_LINEAGE_NAMESPACE: &str = "SYSTEM_LINEAGE_NAMESPACE";
+
+/// the checkpoint settings of the application, eg: the checkpoint interval and storage
+pub(crate) fn is_checkpoint_property(key: &str) -> bool {
+    key.starts_with(SYSTEM_CHECKPOINT)
+}
 
 impl SystemProperties for Properties {
     fn set_application_name(&mut self, application_name: &str) {
@@ -374,6 +552,26 @@ impl SystemProperties for Properties {
         self.get_duration(SYSTEM_CHECKPOINT_TTL)
     }
 
+    fn set_checkpoint_retention(&mut self, retention: CheckpointRetention) {
+        let value = serde_json::to_string(&retention).unwrap();
+        self.set_string(SYSTEM_CHECKPOINT_RETENTION.to_string(), value);
+    }
+
+    fn get_checkpoint_retention(&self) -> anyhow::Result<CheckpointRetention> {
+        let value = self.get_string(SYSTEM_CHECKPOINT_RETENTION)?;
+        serde_json::from_str(value.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    fn set_alert_rules(&mut self, alert_rules: AlertRules) {
+        let value = serde_json::to_string(&alert_rules).unwrap();
+        self.set_string(SYSTEM_ALERT_RULES.to_string(), value);
+    }
+
+    fn get_alert_rules(&self) -> anyhow::Result<AlertRules> {
+        let value = self.get_string(SYSTEM_ALERT_RULES)?;
+        serde_json::from_str(value.as_str()).map_err(|e| anyhow!(e))
+    }
+
     fn get_cluster_mode(&self) -> anyhow::Result<ClusterMode> {
         let value = self.get_string(SYSTEM_CLUSTER_MODE)?;
         ClusterMode::try_from(value.as_str())
@@ -396,6 +594,223 @@ impl SystemProperties for Properties {
         let value = self.get_string(SYSTEM_PUB_SUB_CHANNEL_BASE_ON)?;
         ChannelBaseOn::try_from(value.as_str()).map_err(|e| anyhow!(e))
     }
+
+    fn set_buffer_timeout(&mut self, buffer_timeout: Duration) {
+        self.set_duration(SYSTEM_PUB_SUB_BUFFER_TIMEOUT, buffer_timeout);
+    }
+
+    fn get_buffer_timeout(&self) -> anyhow::Result<Duration> {
+        self.get_duration(SYSTEM_PUB_SUB_BUFFER_TIMEOUT)
+    }
+
+    fn set_force_network_edge(&mut self, force_network_edge: bool) {
+        self.set_bool(SYSTEM_FORCE_NETWORK_EDGE, force_network_edge);
+    }
+
+    fn get_force_network_edge(&self) -> anyhow::Result<bool> {
+        self.get_bool(SYSTEM_FORCE_NETWORK_EDGE)
+    }
+
+    fn set_stream_graph_optimizer(&mut self, enable: bool) {
+        self.set_bool(SYSTEM_STREAM_GRAPH_OPTIMIZER, enable);
+    }
+
+    fn get_stream_graph_optimizer(&self) -> anyhow::Result<bool> {
+        self.get_bool(SYSTEM_STREAM_GRAPH_OPTIMIZER)
+    }
+
+    fn set_control_element_priority(&mut self, priority: bool) {
+        self.set_bool(SYSTEM_CONTROL_ELEMENT_PRIORITY, priority);
+    }
+
+    fn get_control_element_priority(&self) -> anyhow::Result<bool> {
+        self.get_bool(SYSTEM_CONTROL_ELEMENT_PRIORITY)
+    }
+
+    fn set_network_control_priority(&mut self, priority: bool) {
+        self.set_bool(SYSTEM_NETWORK_CONTROL_PRIORITY, priority);
+    }
+
+    fn get_network_control_priority(&self) -> anyhow::Result<bool> {
+        self.get_bool(SYSTEM_NETWORK_CONTROL_PRIORITY)
+    }
+
+    fn set_worker_drain_timeout(&mut self, timeout: Duration) {
+        self.set_duration(SYSTEM_WORKER_DRAIN_TIMEOUT, timeout);
+    }
+
+    fn get_worker_drain_timeout(&self) -> anyhow::Result<Duration> {
+        self.get_duration(SYSTEM_WORKER_DRAIN_TIMEOUT)
+    }
+
+    fn set_cancellation_grace_period(&mut self, grace_period: Duration) {
+        self.set_duration(SYSTEM_CANCELLATION_GRACE_PERIOD, grace_period);
+    }
+
+    fn get_cancellation_grace_period(&self) -> anyhow::Result<Duration> {
+        self.get_duration(SYSTEM_CANCELLATION_GRACE_PERIOD)
+    }
+
+    fn set_task_stack_size(&mut self, stack_size: usize) {
+        self.set_usize(SYSTEM_TASK_STACK_SIZE, stack_size);
+    }
+
+    fn get_task_stack_size(&self) -> anyhow::Result<usize> {
+        self.get_usize(SYSTEM_TASK_STACK_SIZE)
+    }
+
+    fn set_application_labels(&mut self, labels: &BTreeMap<String, String>) {
+        let value = serde_json::to_string(labels).unwrap();
+        self.set_string(SYSTEM_APPLICATION_LABELS.to_string(), value);
+    }
+
+    fn get_application_labels(&self) -> anyhow::Result<BTreeMap<String, String>> {
+        let value = self.get_string(SYSTEM_APPLICATION_LABELS)?;
+        serde_json::from_str(value.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    fn set_masking_policy(&mut self, policy_name: &str, policy: &MaskingPolicy) {
+        let value = serde_json::to_string(policy).unwrap();
+        self.set_string(format!("{}.{}", SYSTEM_MASKING_POLICY, policy_name), value);
+    }
+
+    fn get_masking_policy(&self, policy_name: &str) -> anyhow::Result<MaskingPolicy> {
+        let value =
+            self.get_string(format!("{}.{}", SYSTEM_MASKING_POLICY, policy_name).as_str())?;
+        serde_json::from_str(value.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    fn set_sort_memory_limit(&mut self, memory_limit: usize) {
+        self.set_usize(SYSTEM_SORT_MEMORY_LIMIT, memory_limit);
+    }
+
+    fn get_sort_memory_limit(&self) -> anyhow::Result<usize> {
+        self.get_usize(SYSTEM_SORT_MEMORY_LIMIT)
+    }
+
+    fn set_sort_spill_dir(&mut self, spill_dir: &str) {
+        self.set_str(SYSTEM_SORT_SPILL_DIR, spill_dir);
+    }
+
+    fn get_sort_spill_dir(&self) -> anyhow::Result<String> {
+        self.get_string(SYSTEM_SORT_SPILL_DIR)
+    }
+
+    fn set_shared_runtime_threads(&mut self, threads: usize) {
+        self.set_usize(SYSTEM_SHARED_RUNTIME_THREADS, threads);
+    }
+
+    fn get_shared_runtime_threads(&self) -> anyhow::Result<usize> {
+        self.get_usize(SYSTEM_SHARED_RUNTIME_THREADS)
+    }
+
+    fn set_dry_run(&mut self, dry_run: bool) {
+        self.set_bool(SYSTEM_DRY_RUN, dry_run);
+    }
+
+    fn get_dry_run(&self) -> anyhow::Result<bool> {
+        self.get_bool(SYSTEM_DRY_RUN)
+    }
+
+    fn set_connector_probe(&mut self, probe: bool) {
+        self.set_bool(SYSTEM_CONNECTOR_PROBE, probe);
+    }
+
+    fn get_connector_probe(&self) -> anyhow::Result<bool> {
+        self.get_bool(SYSTEM_CONNECTOR_PROBE)
+    }
+
+    fn set_max_parallelism(&mut self, max_parallelism: u16) {
+        self.set_u16(SYSTEM_MAX_PARALLELISM, max_parallelism);
+    }
+
+    fn get_max_parallelism(&self) -> anyhow::Result<u16> {
+        self.get_u16(SYSTEM_MAX_PARALLELISM)
+    }
+
+    fn set_fatal_error_classes(&mut self, error_classes: Vec<ErrorClass>) {
+        let value = serde_json::to_string(&error_classes).unwrap();
+        self.set_string(SYSTEM_FATAL_ERROR_CLASSES.to_string(), value);
+    }
+
+    fn get_fatal_error_classes(&self) -> anyhow::Result<Vec<ErrorClass>> {
+        let value = self.get_string(SYSTEM_FATAL_ERROR_CLASSES)?;
+        serde_json::from_str(value.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    fn set_processing_timeout(&mut self, timeout: Duration) {
+        self.set_duration(SYSTEM_PROCESSING_TIMEOUT, timeout);
+    }
+
+    fn get_processing_timeout(&self) -> anyhow::Result<Duration> {
+        self.get_duration(SYSTEM_PROCESSING_TIMEOUT)
+    }
+
+    fn set_crash_loop_max_restarts(&mut self, max_restarts: u32) {
+        self.set_u32(SYSTEM_CRASH_LOOP_MAX_RESTARTS, max_restarts);
+    }
+
+    fn get_crash_loop_max_restarts(&self) -> anyhow::Result<u32> {
+        self.get_u32(SYSTEM_CRASH_LOOP_MAX_RESTARTS)
+    }
+
+    fn set_crash_loop_interval(&mut self, interval: Duration) {
+        self.set_duration(SYSTEM_CRASH_LOOP_INTERVAL, interval);
+    }
+
+    fn get_crash_loop_interval(&self) -> anyhow::Result<Duration> {
+        self.get_duration(SYSTEM_CRASH_LOOP_INTERVAL)
+    }
+
+    fn set_assertion_mode(&mut self, guarantee: ProcessingGuarantee) {
+        let value = serde_json::to_string(&guarantee).unwrap();
+        self.set_string(SYSTEM_ASSERTION_MODE.to_string(), value);
+    }
+
+    fn get_assertion_mode(&self) -> anyhow::Result<ProcessingGuarantee> {
+        let value = self.get_string(SYSTEM_ASSERTION_MODE)?;
+        serde_json::from_str(value.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    fn register_cached_file(&mut self, name: &str, uri: &str, archive: bool) {
+        let mut cached_files = self.get_cached_files().unwrap_or_default();
+        cached_files.retain(|x| x.name.ne(name));
+        cached_files.push(CachedFile::new(name, uri, archive));
+
+        let value = serde_json::to_string(&cached_files).unwrap();
+        self.set_string(SYSTEM_CACHED_FILES.to_string(), value);
+    }
+
+    fn get_cached_files(&self) -> anyhow::Result<Vec<CachedFile>> {
+        let value = self.get_string(SYSTEM_CACHED_FILES)?;
+        serde_json::from_str(value.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    fn set_split_reassignment(&mut self, enable: bool) {
+        self.set_bool(SYSTEM_SPLIT_REASSIGNMENT, enable);
+    }
+
+    fn get_split_reassignment(&self) -> anyhow::Result<bool> {
+        self.get_bool(SYSTEM_SPLIT_REASSIGNMENT)
+    }
+
+    fn set_key_hasher(&mut self, key_hasher: KeyHasher) {
+        let value = serde_json::to_string(&key_hasher).unwrap();
+        self.set_string(SYSTEM_KEY_HASHER.to_string(), value);
+    }
+
+    fn get_key_hasher(&self) -> anyhow::Result<KeyHasher> {
+        let value = self.get_string(SYSTEM_KEY_HASHER)?;
+        serde_json::from_str(value.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    fn set_lineage_namespace(&mut self, namespace: &str) {
+        self.set_str(SYSTEM_LINEAGE_NAMESPACE, namespace);
+    }
+
+    fn get_lineage_namespace(&self) -> anyhow::Result<String> {
+        self.get_string(SYSTEM_LINEAGE_NAMESPACE)
+    }
 }
 
 impl InnerSystemProperties for Properties {
@@ -406,7 +821,8 @@ impl InnerSystemProperties for Properties {
 
 #[cfg(test)]
 mod tests {
-    use crate::core::properties::Properties;
+    use crate::core::cache::CachedFile;
+    use crate::core::properties::{Properties, SystemProperties};
 
     #[test]
     pub fn row_properties() {
@@ -428,4 +844,20 @@ mod tests {
         println!("{:?}", properties);
         println!("{:?}", sub_properties);
     }
+
+    #[test]
+    pub fn cached_files_test() {
+        let mut properties = Properties::new();
+        properties.register_cached_file("model.bin", "s3://bucket/model_v1.bin", false);
+        properties.register_cached_file("dict", "/data/dict.tar.gz", true);
+        properties.register_cached_file("model.bin", "s3://bucket/model_v2.bin", false);
+
+        assert_eq!(
+            properties.get_cached_files().unwrap(),
+            vec![
+                CachedFile::new("dict", "/data/dict.tar.gz", true),
+                CachedFile::new("model.bin", "s3://bucket/model_v2.bin", false),
+            ]
+        );
+    }
 }
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use crate::core::runtime::TaskId;
+use crate::runtime::worker::heart_beat::{get_rate_cap, submit_heartbeat};
+use crate::runtime::HeartbeatItem;
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the interval of the consumption rate reported to the coordinator, same as the heartbeat
+pub const RATE_REPORT_INTERVAL_MS: u64 = 10 * 1000;
+
+/// A cluster-wide consumption rate shared by all tasks that use the budget `name`,
+/// eg: the quota of a Kafka cluster. The budget is split into the per-task rate caps
+/// by the coordinator according to the rates reported by the tasks.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RateBudget {
+    pub name: String,
+    pub records_per_second: f64,
+}
+
+impl RateBudget {
+    pub fn new(name: &str, records_per_second: f64) -> Self {
+        RateBudget {
+            name: name.to_string(),
+            records_per_second,
+        }
+    }
+}
+
+/// The rate cap of a task assigned by the coordinator
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct TaskRateCap {
+    pub task_id: TaskId,
+    pub budget: String,
+    pub records_per_second: f64,
+}
+
+/// A token bucket of a task limited by the cap of the `RateBudget`.
+/// It starts with an even share of the budget, reports the consumption rate via the heartbeat
+/// and follows the cap adjusted by the coordinator. The burst is at most one second of the cap.
+pub struct RateBudgetLimiter {
+    budget: RateBudget,
+    task_id: TaskId,
+
+    cap: f64,
+    tokens: f64,
+    refill_timestamp: u64,
+
+    report_timestamp: u64,
+    report_permits: u64,
+}
+
+impl RateBudgetLimiter {
+    pub fn new(budget: RateBudget, task_id: TaskId) -> Self {
+        let cap = budget.records_per_second / task_id.num_tasks().max(1) as f64;
+        let now = current_timestamp_millis();
+        RateBudgetLimiter {
+            budget,
+            task_id,
+            cap,
+            tokens: cap,
+            refill_timestamp: now,
+            report_timestamp: now,
+            report_permits: 0,
+        }
+    }
+
+    /// the current rate cap of the task
+    pub fn cap(&self) -> f64 {
+        self.cap
+    }
+
+    /// take the `permits`, return the time to wait before consuming them
+    pub fn acquire(&mut self, permits: u64) -> Duration {
+        let now = current_timestamp_millis();
+        self.report(now);
+        self.acquire_at(permits, now)
+    }
+
+    fn acquire_at(&mut self, permits: u64, now: u64) -> Duration {
+        let elapsed = now.saturating_sub(self.refill_timestamp) as f64 / 1000f64;
+        self.refill_timestamp = now;
+        self.tokens = (self.tokens + elapsed * self.cap).min(self.cap);
+
+        self.tokens -= permits as f64;
+        self.report_permits += permits;
+        if self.tokens >= 0f64 || self.cap <= 0f64 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.cap)
+        }
+    }
+
+    fn report(&mut self, now: u64) {
+        if now < self.report_timestamp + RATE_REPORT_INTERVAL_MS {
+            return;
+        }
+
+        let elapsed = (now - self.report_timestamp) as f64 / 1000f64;
+        submit_heartbeat(HeartbeatItem::TaskRate {
+            task_id: self.task_id,
+            budget: self.budget.clone(),
+            records_per_second: self.report_permits as f64 / elapsed,
+            cap: self.cap,
+        });
+        self.report_timestamp = now;
+        self.report_permits = 0;
+
+        if let Some(cap) = get_rate_cap(self.budget.name.as_str(), self.task_id) {
+            if (cap - self.cap).abs() > f64::EPSILON {
+                debug!(
+                    "the rate cap of {:?} in the budget `{}` changed: {} -> {}",
+                    self.task_id, self.budget.name, self.cap, cap
+                );
+                self.cap = cap;
+            }
+        }
+    }
+}
+
+/// The tasks reported rate is close to the cap are throttled, their demand is unlimited
+const SATURATION_RATIO: f64 = 0.9;
+/// The headroom of the not throttled tasks to grow
+const HEADROOM_RATIO: f64 = 1.2;
+/// The min cap of a task relative to the even share, so an idle task can be throttled
+/// and report its demand when it becomes busy
+const MIN_SHARE_RATIO: f64 = 0.1;
+
+/// Split the `budget` into the caps of the tasks by the max-min fairness.
+/// `rates` are the reported `(records_per_second, cap)` of the tasks, the throttled tasks
+/// share the budget evenly, the others get their rates with a headroom(at least `MIN_SHARE_RATIO`
+/// of the even share),
+/// and the unused budget is split evenly, so the sum of the caps is the budget.
+pub(crate) fn allocate_rate_caps(budget: f64, rates: &[(f64, f64)]) -> Vec<f64> {
+    if rates.is_empty() {
+        return Vec::new();
+    }
+
+    let min_cap = budget / rates.len() as f64 * MIN_SHARE_RATIO;
+    let demands: Vec<f64> = rates
+        .iter()
+        .map(|(rate, cap)| {
+            if *rate >= *cap * SATURATION_RATIO {
+                f64::INFINITY
+            } else {
+                (*rate * HEADROOM_RATIO).max(min_cap)
+            }
+        })
+        .collect();
+
+    let mut indexes: Vec<usize> = (0..demands.len()).collect();
+    indexes.sort_by(|a, b| demands[*a].partial_cmp(&demands[*b]).unwrap());
+
+    let mut caps = vec![0f64; demands.len()];
+    let mut remaining = budget;
+    for (i, index) in indexes.iter().enumerate() {
+        let share = remaining / (indexes.len() - i) as f64;
+        caps[*index] = demands[*index].min(share);
+        remaining -= caps[*index];
+    }
+
+    let extra = remaining / caps.len() as f64;
+    caps.iter().map(|cap| cap + extra).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::rate_budget::{allocate_rate_caps, RateBudget, RateBudgetLimiter};
+    use crate::core::runtime::{JobId, TaskId};
+
+    #[test]
+    pub fn allocate_rate_caps_test() {
+        // all throttled
+        let caps = allocate_rate_caps(100f64, &[(25f64, 25f64); 4]);
+        assert_eq!(caps, vec![25f64; 4]);
+
+        // the idle tasks give the budget to the busy ones
+        let caps = allocate_rate_caps(100f64, &[(10f64, 25f64), (0f64, 25f64), (25f64, 25f64)]);
+        assert!((caps[0] - 12f64).abs() < 1e-6);
+        assert!((caps[1] - 100f64 / 30f64).abs() < 1e-6);
+        assert!((caps[2] - (88f64 - 100f64 / 30f64)).abs() < 1e-6);
+        assert!((caps.iter().sum::<f64>() - 100f64).abs() < 1e-6);
+
+        // the unused budget is split evenly
+        let caps = allocate_rate_caps(100f64, &[(10f64, 50f64), (20f64, 50f64)]);
+        assert!((caps[0] - 44f64).abs() < 1e-6);
+        assert!((caps[1] - 56f64).abs() < 1e-6);
+    }
+
+    #[test]
+    pub fn rate_budget_limiter_test() {
+        let task_id = TaskId {
+            job_id: JobId(0),
+            task_number: 0,
+            num_tasks: 4,
+        };
+        let mut limiter = RateBudgetLimiter::new(RateBudget::new("kafka", 400f64), task_id);
+        assert_eq!(limiter.cap(), 100f64);
+
+        let now = limiter.refill_timestamp;
+        assert_eq!(limiter.acquire_at(100, now), Duration::from_secs(0));
+        assert_eq!(limiter.acquire_at(50, now), Duration::from_millis(500));
+        // refill 100 tokens in a second, but the burst is capped
+        assert_eq!(limiter.acquire_at(10, now + 3000), Duration::from_secs(0));
+        assert!((limiter.tokens - 90f64).abs() < 1e-6);
+    }
+}
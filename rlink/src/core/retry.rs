@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+/// The retry of the user function of an operator, the record is processed again by the
+/// `flat_map` or the `write_record` if the function panics with a retryable message,
+/// so a transient error of the external system doesn't fail the task, eg:
+/// ```ignore
+/// data_stream
+///     .flat_map(LookupFunction::new())
+///     .retry_policy(RetryPolicy::new(3).retry_on("timed out"))
+/// ```
+/// The outputs of a failed `flat_map` attempt are dropped, and the panics of the chained
+/// operators are not retried.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// the max attempts including the first one
+    pub(crate) max_attempts: u32,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    /// the panic messages containing any of the patterns are retried, all panics if empty
+    pub(crate) retryable: Vec<String>,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            retryable: Vec::new(),
+        }
+    }
+
+    /// the backoff is doubled on each attempt from `initial` up to `max`
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// retry the panics with the message containing the `pattern` only
+    pub fn retry_on(mut self, pattern: &str) -> Self {
+        self.retryable.push(pattern.to_string());
+        self
+    }
+
+    /// the `attempt` failed with the panic `message` is retried
+    pub(crate) fn should_retry(&self, attempt: u32, message: &str) -> bool {
+        attempt + 1 < self.max_attempts
+            && (self.retryable.is_empty()
+                || self
+                    .retryable
+                    .iter()
+                    .any(|pattern| message.contains(pattern.as_str())))
+    }
+
+    /// the backoff before the next attempt of the failed `attempt`, `attempt` starts from 0
+    pub(crate) fn backoff_of(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(self.max_backoff);
+        backoff.min(self.max_backoff)
+    }
+}
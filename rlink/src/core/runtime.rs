@@ -197,6 +197,8 @@ pub enum HeartBeatStatus {
     Ok,
     Panic,
     End,
+    /// the worker received `SIGTERM`, it's draining the tasks before exiting
+    Draining,
 }
 
 impl std::fmt::Display for HeartBeatStatus {
@@ -205,6 +207,7 @@ impl std::fmt::Display for HeartBeatStatus {
             HeartBeatStatus::Ok => write!(f, "ok"),
             HeartBeatStatus::Panic => write!(f, "panic"),
             HeartBeatStatus::End => write!(f, "end"),
+            HeartBeatStatus::Draining => write!(f, "draining"),
         }
     }
 }
@@ -217,6 +220,7 @@ impl<'a> TryFrom<&'a str> for HeartBeatStatus {
             "ok" => Ok(HeartBeatStatus::Ok),
             "panic" => Ok(HeartBeatStatus::Panic),
             "end" => Ok(HeartBeatStatus::End),
+            "draining" => Ok(HeartBeatStatus::Draining),
             _ => Err(anyhow!("unrecognized status: {}", value)),
         }
     }
@@ -232,6 +236,43 @@ pub struct WorkerManagerDescriptor {
     pub metrics_address: String,
     pub web_address: String,
     pub task_descriptors: Vec<TaskDescriptor>,
+    /// the latest resource usage reported by the heartbeat
+    #[serde(default)]
+    pub resource_usage: Option<ResourceUsage>,
+}
+
+/// The resource usage of a worker process, reported by the heartbeat
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct ResourceUsage {
+    /// the cpu usage of the process, `100.0` is a fully used core
+    pub cpu_usage: f32,
+    /// the resident set size
+    pub rss_bytes: u64,
+    /// `None` if the allocator stats are unavailable on the platform
+    pub allocator: Option<AllocatorStats>,
+    pub disks: Vec<DiskUsage>,
+    pub timestamp: u64,
+}
+
+/// The heap stats of the allocator
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct AllocatorStats {
+    /// the bytes allocated and in use
+    pub allocated_bytes: u64,
+    /// the bytes freed but retained by the allocator
+    pub free_bytes: u64,
+    /// the bytes of the large allocations mapped directly
+    pub mapped_bytes: u64,
+}
+
+/// The disk usage of a local directory used by the worker, eg: the spill directory
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct DiskUsage {
+    pub path: String,
+    /// the size of the files in the directory
+    pub used_bytes: u64,
+    /// the free space of the file system of the directory for the unprivileged users
+    pub available_bytes: u64,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
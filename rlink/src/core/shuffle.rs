@@ -0,0 +1,353 @@
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::core::element::{Element, Serde};
+use crate::core::runtime::{JobId, TaskId};
+
+/// The intermediate data service of the network edges for the bounded jobs. The upstream tasks
+/// write the elements of the child tasks(partitions) to the `ShuffleWriter` and publish them
+/// on finish, the downstream tasks pull them by the `ShuffleReader` after published, so the
+/// producers and the consumers don't need to run at the same time and a failed consumer can
+/// re-read the data without re-running the producers.
+/// Set by `StreamExecutionEnvironment::set_shuffle_service`, eg: `FileShuffleService`.
+pub trait ShuffleService: Send + Sync + Debug {
+    /// create the writer of the `source_task_id` for the `num_partitions` tasks of the `target_job_id`
+    fn create_writer(
+        &self,
+        application_id: &str,
+        source_task_id: TaskId,
+        target_job_id: JobId,
+        num_partitions: u16,
+    ) -> crate::core::Result<Box<dyn ShuffleWriter>>;
+
+    /// create the reader of the partition `target_task_id.task_number` written by `source_task_id`
+    fn create_reader(
+        &self,
+        application_id: &str,
+        source_task_id: TaskId,
+        target_task_id: TaskId,
+    ) -> crate::core::Result<Box<dyn ShuffleReader>>;
+}
+
+pub trait ShuffleWriter: Send {
+    fn write(&mut self, partition: u16, element: &Element) -> crate::core::Result<()>;
+
+    /// all elements are written, publish them to the readers atomically
+    fn finish(&mut self) -> crate::core::Result<()>;
+}
+
+pub trait ShuffleReader: Send {
+    /// the elements of the partition, `None` if the upstream task has not finished yet
+    fn read(&mut self) -> crate::core::Result<Option<Box<dyn Iterator<Item = Element> + Send>>>;
+}
+
+/// The shuffle files on the local disk or a shared storage(eg: a NFS mount) of the workers.
+/// The elements of each partition are appended to a spill file, and merged to a data file
+/// sorted by the partition with an index of the partition ranges on finish.
+/// The files are in `{dir}/{application_id}/{target_job_id}/{source_job_id}-{source_task_number}`,
+/// the downstream tasks must be able to read the `dir` of the upstream tasks.
+#[derive(Debug, Clone)]
+pub struct FileShuffleService {
+    dir: PathBuf,
+}
+
+impl FileShuffleService {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        FileShuffleService {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn base_path(
+        &self,
+        application_id: &str,
+        source_task_id: TaskId,
+        target_job_id: JobId,
+    ) -> PathBuf {
+        self.dir
+            .join(application_id)
+            .join(target_job_id.0.to_string())
+            .join(format!(
+                "{}-{}",
+                source_task_id.job_id.0, source_task_id.task_number
+            ))
+    }
+}
+
+impl ShuffleService for FileShuffleService {
+    fn create_writer(
+        &self,
+        application_id: &str,
+        source_task_id: TaskId,
+        target_job_id: JobId,
+        num_partitions: u16,
+    ) -> crate::core::Result<Box<dyn ShuffleWriter>> {
+        let base_path = self.base_path(application_id, source_task_id, target_job_id);
+        let writer = FileShuffleWriter::new(base_path, num_partitions)
+            .map_err(|e| format!("create shuffle writer error. {}", e))?;
+        Ok(Box::new(writer))
+    }
+
+    fn create_reader(
+        &self,
+        application_id: &str,
+        source_task_id: TaskId,
+        target_task_id: TaskId,
+    ) -> crate::core::Result<Box<dyn ShuffleReader>> {
+        let base_path = self.base_path(application_id, source_task_id, target_task_id.job_id);
+        Ok(Box::new(FileShuffleReader {
+            base_path,
+            partition: target_task_id.task_number,
+        }))
+    }
+}
+
+fn with_extension(base_path: &Path, extension: &str) -> PathBuf {
+    let mut path = base_path.as_os_str().to_os_string();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// the `(offset, len)` of each partition in the data file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ShuffleIndex {
+    partitions: Vec<(u64, u64)>,
+}
+
+struct FileShuffleWriter {
+    base_path: PathBuf,
+    spill_files: Vec<Option<BufWriter<File>>>,
+    buffer: BytesMut,
+}
+
+impl FileShuffleWriter {
+    fn new(base_path: PathBuf, num_partitions: u16) -> anyhow::Result<Self> {
+        if let Some(parent) = base_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // the output of the previous attempt is overwritten
+        let _ = std::fs::remove_file(with_extension(&base_path, "index"));
+
+        let spill_files = (0..num_partitions).map(|_| None).collect();
+        Ok(FileShuffleWriter {
+            base_path,
+            spill_files,
+            buffer: BytesMut::new(),
+        })
+    }
+
+    fn spill_path(&self, partition: usize) -> PathBuf {
+        with_extension(&self.base_path, format!("spill.{}", partition).as_str())
+    }
+
+    fn write0(&mut self, partition: u16, element: &Element) -> anyhow::Result<()> {
+        let partition = partition as usize;
+        if partition >= self.spill_files.len() {
+            return Err(anyhow!(
+                "the shuffle partition {} out of range {}",
+                partition,
+                self.spill_files.len()
+            ));
+        }
+
+        if self.spill_files[partition].is_none() {
+            let file = File::create(self.spill_path(partition))?;
+            self.spill_files[partition] = Some(BufWriter::new(file));
+        }
+
+        self.buffer.clear();
+        self.buffer.put_u32(element.capacity() as u32);
+        element.serialize(&mut self.buffer);
+        self.spill_files[partition]
+            .as_mut()
+            .unwrap()
+            .write_all(self.buffer.as_ref())?;
+
+        Ok(())
+    }
+
+    fn finish0(&mut self) -> anyhow::Result<()> {
+        let data_tmp_path = with_extension(&self.base_path, "data.tmp");
+        let mut data_file = BufWriter::new(File::create(&data_tmp_path)?);
+
+        let mut partitions = Vec::with_capacity(self.spill_files.len());
+        let mut offset = 0u64;
+        for partition in 0..self.spill_files.len() {
+            let len = match self.spill_files[partition].take() {
+                Some(spill_file) => {
+                    spill_file
+                        .into_inner()
+                        .map_err(|e| anyhow!(e.to_string()))?
+                        .sync_all()?;
+                    let spill_path = self.spill_path(partition);
+                    let len = std::io::copy(&mut File::open(&spill_path)?, &mut data_file)?;
+                    std::fs::remove_file(spill_path)?;
+                    len
+                }
+                None => 0,
+            };
+            partitions.push((offset, len));
+            offset += len;
+        }
+
+        data_file
+            .into_inner()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .sync_all()?;
+        std::fs::rename(data_tmp_path, with_extension(&self.base_path, "data"))?;
+
+        // the index is the completion marker, written last
+        let index_tmp_path = with_extension(&self.base_path, "index.tmp");
+        let index = serde_json::to_vec(&ShuffleIndex { partitions })?;
+        let mut index_file = File::create(&index_tmp_path)?;
+        index_file.write_all(index.as_slice())?;
+        index_file.sync_all()?;
+        std::fs::rename(index_tmp_path, with_extension(&self.base_path, "index"))?;
+
+        info!("shuffle output {:?} published", self.base_path);
+        Ok(())
+    }
+}
+
+impl ShuffleWriter for FileShuffleWriter {
+    fn write(&mut self, partition: u16, element: &Element) -> crate::core::Result<()> {
+        self.write0(partition, element)
+            .map_err(|e| crate::core::Error::from(format!("write shuffle file error. {}", e)))
+    }
+
+    fn finish(&mut self) -> crate::core::Result<()> {
+        self.finish0()
+            .map_err(|e| crate::core::Error::from(format!("finish shuffle file error. {}", e)))
+    }
+}
+
+struct FileShuffleReader {
+    base_path: PathBuf,
+    partition: u16,
+}
+
+type ElementIter = Box<dyn Iterator<Item = Element> + Send>;
+
+impl FileShuffleReader {
+    fn read0(&mut self) -> anyhow::Result<Option<ElementIter>> {
+        let index_path = with_extension(&self.base_path, "index");
+        if !index_path.exists() {
+            return Ok(None);
+        }
+
+        let index: ShuffleIndex = serde_json::from_slice(std::fs::read(index_path)?.as_slice())?;
+        let (offset, len) = index
+            .partitions
+            .get(self.partition as usize)
+            .cloned()
+            .ok_or_else(|| anyhow!("the shuffle partition {} not found", self.partition))?;
+
+        let mut data_file = OpenOptions::new()
+            .read(true)
+            .open(with_extension(&self.base_path, "data"))?;
+        data_file.seek(SeekFrom::Start(offset))?;
+
+        Ok(Some(Box::new(FileShuffleIterator {
+            reader: BufReader::new(data_file).take(len),
+        })))
+    }
+}
+
+impl ShuffleReader for FileShuffleReader {
+    fn read(&mut self) -> crate::core::Result<Option<ElementIter>> {
+        self.read0()
+            .map_err(|e| crate::core::Error::from(format!("read shuffle file error. {}", e)))
+    }
+}
+
+struct FileShuffleIterator {
+    reader: std::io::Take<BufReader<File>>,
+}
+
+impl Iterator for FileShuffleIterator {
+    type Item = Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.reader.limit() == 0 {
+            return None;
+        }
+
+        let mut len = [0u8; 4];
+        self.reader
+            .read_exact(&mut len)
+            .expect("read shuffle file error");
+        let mut bytes = vec![0u8; u32::from_be_bytes(len) as usize];
+        self.reader
+            .read_exact(bytes.as_mut_slice())
+            .expect("read shuffle file error");
+
+        Some(Element::deserialize(&mut BytesMut::from(bytes.as_slice())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::element::{Element, Partition, Record, StreamStatus};
+    use crate::core::runtime::{JobId, TaskId};
+    use crate::core::shuffle::{FileShuffleService, ShuffleService};
+
+    #[test]
+    pub fn file_shuffle_service_test() {
+        let dir = std::env::temp_dir().join("rlink_shuffle_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let service = FileShuffleService::new(&dir);
+
+        let source_task_id = TaskId {
+            job_id: JobId(1),
+            task_number: 0,
+            num_tasks: 1,
+        };
+        let target_task_id = |task_number| TaskId {
+            job_id: JobId(2),
+            task_number,
+            num_tasks: 3,
+        };
+
+        let mut reader = service
+            .create_reader("app", source_task_id, target_task_id(2))
+            .unwrap();
+        let mut writer = service
+            .create_writer("app", source_task_id, JobId(2), 3)
+            .unwrap();
+        for i in 0..10u16 {
+            let mut record = Record::new();
+            record.partition_num = i % 2 * 2;
+            writer
+                .write(record.partition_num, &Element::Record(record))
+                .unwrap();
+        }
+        for partition in 0..3 {
+            let end = Element::StreamStatus(StreamStatus::new(0, true));
+            writer.write(partition, &end).unwrap();
+        }
+        assert!(reader.read().unwrap().is_none());
+        writer.finish().unwrap();
+
+        // read twice, eg: the downstream task is restarted
+        for _ in 0..2 {
+            let elements: Vec<Element> = reader.read().unwrap().unwrap().collect();
+            assert_eq!(elements.len(), 6);
+            assert!(elements[..5]
+                .iter()
+                .all(|x| x.is_record() && x.partition() == 2));
+            assert!(!elements[5].is_record());
+        }
+
+        let mut reader = service
+            .create_reader("app", source_task_id, target_task_id(1))
+            .unwrap();
+        assert_eq!(reader.read().unwrap().unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+/// The max size of the keyed state of an operator in a task, checked by the worker
+/// periodically, so a growing state is caught before the whole worker OOMs, eg:
+/// ```ignore
+/// data_stream
+///     .key_by(key_selector)
+///     .window(window_assigner)
+///     .reduce(reduce_function)
+///     .state_limit(StateLimit::bytes(512 * 1024 * 1024).fail_task())
+/// ```
+/// The `ReduceStateLimitWarnings` metric is increased once the state reaches `warn_ratio` of
+/// the limit, an alert is raised once it exceeds the limit, and the task is failed if
+/// `fail_task` is enabled.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct StateLimit {
+    pub(crate) max_bytes: Option<u64>,
+    pub(crate) max_entries: Option<u64>,
+    pub(crate) warn_ratio: f64,
+    pub(crate) fail_task: bool,
+}
+
+impl StateLimit {
+    /// limit the bytes of the keys and values
+    pub fn bytes(max_bytes: u64) -> Self {
+        StateLimit {
+            max_bytes: Some(max_bytes),
+            max_entries: None,
+            warn_ratio: 0.8,
+            fail_task: false,
+        }
+    }
+
+    /// limit the number of the keys
+    pub fn entries(max_entries: u64) -> Self {
+        StateLimit {
+            max_bytes: None,
+            max_entries: Some(max_entries),
+            warn_ratio: 0.8,
+            fail_task: false,
+        }
+    }
+
+    /// limit the number of the keys as well
+    pub fn max_entries(mut self, max_entries: u64) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// warn when the state reaches the `ratio` of the limit, default `0.8`
+    pub fn warn_at(mut self, ratio: f64) -> Self {
+        self.warn_ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// fail the task once the state exceeds the limit
+    pub fn fail_task(mut self) -> Self {
+        self.fail_task = true;
+        self
+    }
+
+    /// the level of the state with the `bytes` and `entries`
+    pub(crate) fn check(&self, bytes: u64, entries: u64) -> StateLimitLevel {
+        let limits = [(bytes, self.max_bytes), (entries, self.max_entries)];
+        let exceeded = limits
+            .iter()
+            .any(|(size, limit)| limit.map(|limit| *size > limit).unwrap_or(false));
+        if exceeded {
+            return StateLimitLevel::Exceeded;
+        }
+
+        let warning = limits.iter().any(|(size, limit)| {
+            limit
+                .map(|limit| *size as f64 >= limit as f64 * self.warn_ratio)
+                .unwrap_or(false)
+        });
+        if warning {
+            StateLimitLevel::Warning
+        } else {
+            StateLimitLevel::Normal
+        }
+    }
+}
+
+impl std::fmt::Display for StateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.max_bytes, self.max_entries) {
+            (Some(bytes), Some(entries)) => write!(f, "{} bytes, {} entries", bytes, entries),
+            (Some(bytes), None) => write!(f, "{} bytes", bytes),
+            (None, Some(entries)) => write!(f, "{} entries", entries),
+            (None, None) => write!(f, "unlimited"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum StateLimitLevel {
+    Normal,
+    Warning,
+    Exceeded,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::state_limit::{StateLimit, StateLimitLevel};
+
+    #[test]
+    pub fn state_limit_check_test() {
+        let limit = StateLimit::bytes(1000).max_entries(10).warn_at(0.5);
+        assert_eq!(limit.check(100, 1), StateLimitLevel::Normal);
+        assert_eq!(limit.check(500, 1), StateLimitLevel::Warning);
+        assert_eq!(limit.check(100, 6), StateLimitLevel::Warning);
+        assert_eq!(limit.check(1001, 1), StateLimitLevel::Exceeded);
+        assert_eq!(limit.check(100, 11), StateLimitLevel::Exceeded);
+    }
+}
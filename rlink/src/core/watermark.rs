@@ -1,4 +1,6 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
+use std::hash::Hash;
 
 use crate::core::checkpoint::CheckpointFunction;
 use crate::core::element::Record;
@@ -31,6 +33,11 @@ impl Watermark {
     pub fn new(timestamp: u64) -> Self {
         Watermark { timestamp }
     }
+
+    /// the timestamp of the watermark in milliseconds
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
 }
 
 impl PartialEq for Watermark {
@@ -64,6 +71,19 @@ pub trait WatermarkGenerator: Debug {
     fn on_periodic_emit(&mut self) -> Option<Watermark>;
 }
 
+/// A `PunctuatedWatermarkAssigner` inspects every record after its timestamp is extracted,
+/// and decides whether to emit a watermark immediately, such as the record is a completeness
+/// marker embedded in the data.
+pub trait PunctuatedWatermarkAssigner: Debug {
+    /// Called for every record with the extracted timestamp, return a `Watermark` to emit it
+    /// immediately, or `None` for regular records.
+    fn check_and_get_next_watermark(
+        &mut self,
+        record: &mut Record,
+        extracted_timestamp: u64,
+    ) -> Option<Watermark>;
+}
+
 /// The WatermarkStrategy defines how to generate `Watermark`s in the stream sources. The
 /// WatermarkStrategy is a builder/factory for the `WatermarkGenerator` that generates the watermarks
 /// and the `TimestampAssigner` which assigns the internal timestamp of a record.
@@ -74,3 +94,107 @@ pub trait WatermarkStrategy: NamedFunction + CheckpointFunction + Debug {
     /// Instantiates a `TimestampAssigner` for assigning timestamps according to this strategy.
     fn create_timestamp_assigner(&mut self) -> Box<dyn TimestampAssigner>;
 }
+
+/// Event time timers fired by the watermark progression. It's used to clean up the keyed state
+/// that never sees a match, eg: the buffered records of a join.
+/// Register a key again replaces its previous timer.
+#[derive(Debug)]
+pub struct WatermarkTimers<K>
+where
+    K: Hash + Eq + Clone,
+{
+    /// key: the fire timestamp, value: the keys
+    timers: BTreeMap<u64, HashSet<K>>,
+    /// key: the key, value: the fire timestamp
+    keys: HashMap<K, u64>,
+}
+
+impl<K> WatermarkTimers<K>
+where
+    K: Hash + Eq + Clone,
+{
+    pub fn new() -> Self {
+        WatermarkTimers {
+            timers: BTreeMap::new(),
+            keys: HashMap::new(),
+        }
+    }
+
+    /// fire the timer of the `key` when the watermark reaches the `timestamp`
+    pub fn register(&mut self, key: K, timestamp: u64) {
+        self.delete(&key);
+        self.timers
+            .entry(timestamp)
+            .or_default()
+            .insert(key.clone());
+        self.keys.insert(key, timestamp);
+    }
+
+    pub fn delete(&mut self, key: &K) {
+        if let Some(timestamp) = self.keys.remove(key) {
+            if let Some(keys) = self.timers.get_mut(&timestamp) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.timers.remove(&timestamp);
+                }
+            }
+        }
+    }
+
+    /// remove and return the keys whose timer timestamp is not greater than the `watermark`
+    pub fn advance(&mut self, watermark: u64) -> Vec<K> {
+        let mut fired = Vec::new();
+        while let Some((timestamp, _keys)) = self.timers.iter().next() {
+            if *timestamp > watermark {
+                break;
+            }
+
+            let timestamp = *timestamp;
+            let keys = self.timers.remove(&timestamp).unwrap();
+            for key in keys {
+                self.keys.remove(&key);
+                fired.push(key);
+            }
+        }
+        fired
+    }
+
+    /// the number of the registered timers
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl<K> Default for WatermarkTimers<K>
+where
+    K: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        WatermarkTimers::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::watermark::WatermarkTimers;
+
+    #[test]
+    pub fn watermark_timers_test() {
+        let mut timers = WatermarkTimers::new();
+        timers.register("a", 100);
+        timers.register("b", 200);
+        timers.register("c", 300);
+        // replace the timer of `a`
+        timers.register("a", 250);
+        timers.delete(&"c");
+
+        assert!(timers.advance(99).is_empty());
+        assert_eq!(timers.advance(200), vec!["b"]);
+        assert_eq!(timers.advance(1000), vec!["a"]);
+        assert!(timers.is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 use std::cmp::{max, min};
 use std::fmt::Debug;
+use std::time::Duration;
 
 use crate::core::checkpoint::CheckpointFunction;
 use crate::core::function::NamedFunction;
@@ -81,6 +82,94 @@ impl Default for Window {
     }
 }
 
+/// When a window reduce emits the results of the windows
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WindowEmitMode {
+    /// emit once when the watermark passes the end of the window, fits the append sinks
+    OnClose,
+    /// emit the updated results of the open windows on the firings, and the results updated
+    /// since the latest firing at the close, fits the upsert sinks
+    Incremental,
+    /// the firings of `Incremental` and the whole results at the close, a `Boolean` column
+    /// is appended to the results, `true` if emitted by the close
+    Both,
+}
+
+/// The emission strategy of a window reduce, see `WindowedStream::emit_strategy`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct WindowEmitStrategy {
+    pub(crate) mode: WindowEmitMode,
+    /// the min interval(ms) of the firings of the open windows, fired by the watermarks
+    pub(crate) fire_interval: u64,
+    /// clear the state of the fired windows, so the firings emit the delta since the previous
+    /// firing instead of the accumulated results, the close emits the delta too
+    pub(crate) purge: bool,
+    /// the name of the column appended by `WindowEmitMode::Both`
+    pub(crate) final_field: String,
+    /// the time(ms) the close of a window is held after the watermark passes its end, the late
+    /// records in the hold still update the window and are emitted by the firings, so the
+    /// close emits the corrected final result
+    #[serde(default)]
+    pub(crate) watermark_hold: u64,
+}
+
+impl WindowEmitStrategy {
+    pub fn on_close() -> Self {
+        WindowEmitStrategy {
+            mode: WindowEmitMode::OnClose,
+            fire_interval: 0,
+            purge: false,
+            final_field: "window_final".to_string(),
+            watermark_hold: 0,
+        }
+    }
+
+    pub fn incremental(fire_interval: Duration) -> Self {
+        WindowEmitStrategy {
+            mode: WindowEmitMode::Incremental,
+            fire_interval: fire_interval.as_millis() as u64,
+            ..WindowEmitStrategy::on_close()
+        }
+    }
+
+    /// the early speculative results fired every `fire_interval` and the final result at the
+    /// close, flagged by the `final_field` column, see `WindowEmitMode::Both`
+    pub fn both(fire_interval: Duration) -> Self {
+        WindowEmitStrategy {
+            mode: WindowEmitMode::Both,
+            fire_interval: fire_interval.as_millis() as u64,
+            ..WindowEmitStrategy::on_close()
+        }
+    }
+
+    pub fn purge(mut self, purge: bool) -> Self {
+        self.purge = purge;
+        self
+    }
+
+    pub fn final_field(mut self, final_field: &str) -> Self {
+        self.final_field = final_field.to_string();
+        self
+    }
+
+    /// hold the close of the windows for the `hold` after the watermark passes the end,
+    /// the records later than the hold are dropped
+    pub fn watermark_hold(mut self, hold: Duration) -> Self {
+        self.watermark_hold = hold.as_millis() as u64;
+        self
+    }
+
+    pub fn mode(&self) -> WindowEmitMode {
+        self.mode
+    }
+}
+
+impl Default for WindowEmitStrategy {
+    fn default() -> Self {
+        WindowEmitStrategy::on_close()
+    }
+}
+
 #[derive(Debug)]
 pub struct WindowAssignerContext {}
 
@@ -97,4 +186,10 @@ where
 {
     /// Returns a collection of windows that should be assigned to the element.
     fn assign_windows(&self, timestamp: u64, context: WindowAssignerContext) -> Vec<Window>;
+
+    /// the size(ms) of the panes the overlapping windows are split into, a pane is aligned to
+    /// the latest window of the element. `None` if the windows don't overlap
+    fn pane_size(&self) -> Option<u64> {
+        None
+    }
 }
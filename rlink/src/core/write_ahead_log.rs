@@ -0,0 +1,227 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::core::element::{Record, Serde};
+use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
+
+/// The write-ahead log of a sink that can't commit with the checkpoint, eg: an HTTP api.
+/// The records are appended to the log before written to the sink, the log is sealed into
+/// a segment on each `Barrier` and the segments are truncated after the checkpoints complete,
+/// so the records handed to the sink but not yet delivered by it(eg: in an async buffer) when
+/// a checkpoint completes are written to the sink again on restart, eg:
+/// ```ignore
+/// data_stream
+///     .add_sink(HttpOutputFormat::new(url))
+///     .write_ahead_log(WriteAheadLog::new("/data/rlink/wal"))
+/// ```
+/// The log is in `{dir}/{application_id}/{operator_id}-{task_number}`, the `dir` should be
+/// a shared storage of the workers(eg: a NFS mount) if a task may restart on another host.
+/// The records after the latest `Barrier` are not replayed, they are read again by the sources.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WriteAheadLog {
+    pub(crate) dir: String,
+    /// the number of the latest completed checkpoints the segments are retained for
+    pub(crate) retained_checkpoints: usize,
+}
+
+impl WriteAheadLog {
+    pub fn new(dir: &str) -> Self {
+        WriteAheadLog {
+            dir: dir.to_string(),
+            retained_checkpoints: 1,
+        }
+    }
+
+    /// retain the segments of the latest `n` completed checkpoints, the sink delivering the
+    /// records slower than the checkpoint interval needs more, default `1`
+    pub fn retained_checkpoints(mut self, n: usize) -> Self {
+        self.retained_checkpoints = n;
+        self
+    }
+
+    pub(crate) fn path(
+        &self,
+        application_id: &str,
+        operator_id: OperatorId,
+        task_id: TaskId,
+    ) -> PathBuf {
+        PathBuf::from(self.dir.as_str())
+            .join(application_id)
+            .join(format!("{}-{}", operator_id.0, task_id.task_number))
+    }
+}
+
+const ACTIVE_SEGMENT: &str = "active.wal";
+const SEGMENT_EXTENSION: &str = "wal";
+
+/// The segments of the log in a directory, `active.wal` for the records after the latest
+/// `Barrier`, and `{checkpoint_id}.wal` sealed for the records before the `Barrier`
+pub(crate) struct SinkWal {
+    dir: PathBuf,
+    retained_checkpoints: usize,
+    active: BufWriter<File>,
+    buffer: BytesMut,
+    completed_checkpoints: VecDeque<CheckpointId>,
+}
+
+impl SinkWal {
+    /// open the log and return the records of the sealed segments to replay,
+    /// the records after the latest `Barrier` of the previous run are discarded
+    pub fn open(dir: &Path, retained_checkpoints: usize) -> anyhow::Result<(Self, Vec<Record>)> {
+        std::fs::create_dir_all(dir)?;
+
+        let mut records = Vec::new();
+        for (_checkpoint_id, path) in sealed_segments(dir)? {
+            read_segment(path.as_path(), &mut records)?;
+        }
+
+        let active = BufWriter::new(File::create(dir.join(ACTIVE_SEGMENT))?);
+        let wal = SinkWal {
+            dir: dir.to_path_buf(),
+            retained_checkpoints,
+            active,
+            buffer: BytesMut::new(),
+            completed_checkpoints: VecDeque::new(),
+        };
+        Ok((wal, records))
+    }
+
+    pub fn append(&mut self, record: &Record) -> anyhow::Result<()> {
+        self.buffer.clear();
+        self.buffer.put_u32(record.capacity() as u32);
+        record.serialize(&mut self.buffer);
+        self.active.write_all(self.buffer.as_ref())?;
+        Ok(())
+    }
+
+    /// seal the records before the `Barrier` of the `checkpoint_id`
+    pub fn seal(&mut self, checkpoint_id: CheckpointId) -> anyhow::Result<()> {
+        self.active.flush()?;
+        self.active.get_ref().sync_all()?;
+        std::fs::rename(
+            self.dir.join(ACTIVE_SEGMENT),
+            segment_path(self.dir.as_path(), checkpoint_id),
+        )?;
+        self.active = BufWriter::new(File::create(self.dir.join(ACTIVE_SEGMENT))?);
+        Ok(())
+    }
+
+    /// the replayed records are sealed into the segment of the latest replayed one, the atomic
+    /// rename overwrites it, then the older segments are removed
+    pub fn seal_replayed(&mut self) -> anyhow::Result<()> {
+        let segments = sealed_segments(self.dir.as_path())?;
+        if let Some((checkpoint_id, _path)) = segments.last() {
+            self.seal(*checkpoint_id)?;
+            for (_checkpoint_id, path) in &segments[..segments.len() - 1] {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// remove the segments not retained after the `checkpoint_id` completed
+    pub fn truncate(&mut self, checkpoint_id: CheckpointId) -> anyhow::Result<()> {
+        self.completed_checkpoints.push_back(checkpoint_id);
+        if self.completed_checkpoints.len() <= self.retained_checkpoints {
+            return Ok(());
+        }
+
+        // the segments sealed by the checkpoints before the retained ones
+        let truncated = self.completed_checkpoints.pop_front().unwrap();
+        for (checkpoint_id, path) in sealed_segments(self.dir.as_path())? {
+            if checkpoint_id.0 <= truncated.0 {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn segment_path(dir: &Path, checkpoint_id: CheckpointId) -> PathBuf {
+    dir.join(format!("{}.{}", checkpoint_id.0, SEGMENT_EXTENSION))
+}
+
+/// the sealed segments ordered by the checkpoint id
+fn sealed_segments(dir: &Path) -> anyhow::Result<Vec<(CheckpointId, PathBuf)>> {
+    let mut segments = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|x| x.to_str()) != Some(SEGMENT_EXTENSION) {
+            continue;
+        }
+        let checkpoint_id = path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .and_then(|x| x.parse::<u64>().ok());
+        if let Some(checkpoint_id) = checkpoint_id {
+            segments.push((CheckpointId(checkpoint_id), path));
+        }
+    }
+    segments.sort_by_key(|(checkpoint_id, _path)| *checkpoint_id);
+    Ok(segments)
+}
+
+fn read_segment(path: &Path, records: &mut Vec<Record>) -> anyhow::Result<()> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut len = [0u8; 4];
+    loop {
+        match reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+        let mut bytes = vec![0u8; u32::from_be_bytes(len) as usize];
+        reader.read_exact(bytes.as_mut_slice())?;
+        records.push(Record::deserialize(&mut BytesMut::from(bytes.as_slice())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::element::Record;
+    use crate::core::runtime::CheckpointId;
+    use crate::core::write_ahead_log::SinkWal;
+
+    fn record(timestamp: u64) -> Record {
+        let mut record = Record::new();
+        record.timestamp = timestamp;
+        record
+    }
+
+    fn timestamps(records: Vec<Record>) -> Vec<u64> {
+        records.iter().map(|x| x.timestamp).collect()
+    }
+
+    #[test]
+    pub fn sink_wal_test() {
+        let dir = std::env::temp_dir().join("rlink_sink_wal_test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (mut wal, records) = SinkWal::open(dir.as_path(), 1).unwrap();
+        assert!(records.is_empty());
+        for ck in 1..=3u64 {
+            wal.append(&record(ck)).unwrap();
+            wal.seal(CheckpointId(ck)).unwrap();
+        }
+        // the records after the latest barrier are read again by the sources
+        wal.append(&record(4)).unwrap();
+        // the segment of the latest completed checkpoint is retained
+        wal.truncate(CheckpointId(1)).unwrap();
+        wal.truncate(CheckpointId(2)).unwrap();
+
+        // restart
+        let (mut wal, records) = SinkWal::open(dir.as_path(), 1).unwrap();
+        assert_eq!(timestamps(records), vec![2, 3]);
+        for ts in [2, 3] {
+            wal.append(&record(ts)).unwrap();
+        }
+        wal.seal_replayed().unwrap();
+
+        let (_wal, records) = SinkWal::open(dir.as_path(), 1).unwrap();
+        assert_eq!(timestamps(records), vec![2, 3]);
+    }
+}
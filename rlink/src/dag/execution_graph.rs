@@ -3,6 +3,7 @@ use std::ops::Index;
 
 use daggy::{Dag, EdgeIndex, NodeIndex, Walker};
 
+use crate::channel::ChannelOptions;
 use crate::core::function::InputSplit;
 use crate::core::operator::StreamOperator;
 use crate::core::runtime::{JobId, OperatorId};
@@ -26,6 +27,17 @@ pub(crate) struct ExecutionNode {
     pub daemon: bool,
 }
 
+impl ExecutionNode {
+    /// the options of the task's input channel, merged from all operators in the job
+    pub fn channel_options(&self) -> ChannelOptions {
+        self.stream_nodes
+            .iter()
+            .fold(ChannelOptions::default(), |options, stream_node| {
+                options.merge(&stream_node.channel_options)
+            })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct ExecutionGraph {
     pub(crate) node_indies: HashMap<TaskId, NodeIndex>,
@@ -44,9 +56,10 @@ impl ExecutionGraph {
         &mut self,
         job_graph: &JobGraph,
         operators: &mut HashMap<OperatorId, &StreamOperator>,
+        force_network_edge: bool,
     ) -> Result<(), DagError> {
         let execution_node_indies = self.build_nodes(job_graph, operators)?;
-        self.build_edges(job_graph, execution_node_indies)
+        self.build_edges(job_graph, execution_node_indies, force_network_edge)
     }
 
     pub fn build_nodes(
@@ -116,6 +129,7 @@ impl ExecutionGraph {
         &mut self,
         job_graph: &JobGraph,
         execution_node_index_map: HashMap<JobId, Vec<NodeIndex>>,
+        force_network_edge: bool,
     ) -> Result<(), DagError> {
         let job_dag = &job_graph.dag;
 
@@ -137,12 +151,18 @@ impl ExecutionGraph {
                 let job_edge = job_dag.index(edge_index);
                 match job_edge {
                     JobEdge::Forward => {
-                        // build pipeline execution edge
+                        // build pipeline execution edge,
+                        // the pipeline is cut by the `Network` edge if `force_network_edge`
+                        let execution_edge = if force_network_edge {
+                            ExecutionEdge::Network
+                        } else {
+                            ExecutionEdge::Memory
+                        };
                         for number in 0..execution_node_indies.len() {
                             let node_index = execution_node_indies[number];
                             let child_node_index = child_execution_node_indies[number];
                             self.dag
-                                .add_edge(node_index, child_node_index, ExecutionEdge::Memory)
+                                .add_edge(node_index, child_node_index, execution_edge)
                                 .map_err(|_e| DagError::WouldCycle)?;
                         }
                     }
@@ -0,0 +1,108 @@
+//! Build the OpenLineage `RunEvent` of the stream graph, see `core::lineage`
+
+use serde_json::{json, Value};
+
+use crate::core::element::FnSchema;
+use crate::core::lineage::{LineageDataset, LINEAGE_PRODUCER};
+use crate::dag::stream_graph::RawStreamGraph;
+use crate::dag::OperatorType;
+use crate::utils::date_time::fmt_rfc3339_millis;
+
+const RUN_EVENT_SCHEMA_URL: &str =
+    "https://openlineage.io/spec/1-0-5/OpenLineage.json#/definitions/RunEvent";
+const SCHEMA_FACET_SCHEMA_URL: &str =
+    "https://openlineage.io/spec/facets/1-0-0/SchemaDatasetFacet.json";
+/// the custom job facet of the operators between the inputs and the outputs
+const OPERATORS_FACET: &str = "rlink_operators";
+
+/// the `START` event of the application with the datasets of the sources and the sinks,
+/// the schema of a source dataset is the output of the source, and the schema of a sink
+/// dataset is the input of the sink
+pub(crate) fn start_event(
+    raw_stream_graph: &RawStreamGraph,
+    namespace: &str,
+    job_name: &str,
+    run_id: &str,
+    event_time: u64,
+) -> Value {
+    let operators = raw_stream_graph.operators();
+
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut nodes = Vec::new();
+    for node in raw_stream_graph
+        .dag
+        .raw_nodes()
+        .iter()
+        .map(|node| &node.weight)
+    {
+        let datasets = operators
+            .get(&node.id)
+            .map(|operator| operator.lineage_datasets())
+            .unwrap_or_default();
+        match node.operator_type {
+            OperatorType::Source => datasets
+                .iter()
+                .for_each(|dataset| inputs.push(dataset_json(dataset, &node.output_schema))),
+            OperatorType::Sink => datasets
+                .iter()
+                .for_each(|dataset| outputs.push(dataset_json(dataset, &node.input_schema))),
+            _ => {}
+        }
+
+        nodes.push(json!({
+            "id": node.id.0,
+            "name": node.display_name(),
+            "type": node.operator_type,
+            "parallelism": node.parallelism,
+            "parents": node.parent_ids.iter().map(|id| id.0).collect::<Vec<_>>(),
+            "datasets": datasets,
+        }));
+    }
+
+    json!({
+        "eventType": "START",
+        "eventTime": fmt_rfc3339_millis(event_time as i64),
+        "run": { "runId": run_id },
+        "job": {
+            "namespace": namespace,
+            "name": job_name,
+            "facets": {
+                OPERATORS_FACET: {
+                    "_producer": LINEAGE_PRODUCER,
+                    "_schemaURL": RUN_EVENT_SCHEMA_URL,
+                    "operators": nodes,
+                }
+            }
+        },
+        "inputs": inputs,
+        "outputs": outputs,
+        "producer": LINEAGE_PRODUCER,
+        "schemaURL": RUN_EVENT_SCHEMA_URL,
+    })
+}
+
+fn dataset_json(dataset: &LineageDataset, schema: &FnSchema) -> Value {
+    let schemas = match schema {
+        FnSchema::Empty => vec![],
+        FnSchema::Single(schema) => vec![schema],
+        FnSchema::Tuple(key_schema, schema) => vec![key_schema, schema],
+    };
+    let fields: Vec<Value> = schemas
+        .into_iter()
+        .flat_map(|schema| schema.fields().iter())
+        .map(|field| json!({"name": field.name(), "type": format!("{:?}", field.data_type())}))
+        .collect();
+
+    json!({
+        "namespace": dataset.namespace,
+        "name": dataset.name,
+        "facets": {
+            "schema": {
+                "_producer": LINEAGE_PRODUCER,
+                "_schemaURL": SCHEMA_FACET_SCHEMA_URL,
+                "fields": fields,
+            }
+        }
+    })
+}
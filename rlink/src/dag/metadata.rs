@@ -3,7 +3,7 @@ use crate::dag::execution_graph::{ExecutionEdge, ExecutionNode};
 use crate::dag::job_graph::{JobEdge, JobNode};
 use crate::dag::stream_graph::{StreamEdge, StreamNode};
 use crate::dag::utils::{JsonDag, JsonNode};
-use crate::dag::DagManager;
+use crate::dag::{DagManager, OperatorType};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub(crate) struct DagMetadata {
@@ -12,6 +12,18 @@ pub(crate) struct DagMetadata {
     execution_graph: JsonDag<ExecutionNode, ExecutionEdge>,
 }
 
+/// The operator id to the user-defined name mapping, for the observability tools
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub(crate) struct OperatorInfo {
+    pub(crate) operator_id: OperatorId,
+    pub(crate) job_id: JobId,
+    /// the user-defined name, or the name of the operator if not set
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) operator_name: String,
+    pub(crate) parallelism: u16,
+}
+
 impl<'a> From<&'a DagManager> for DagMetadata {
     fn from(dag_manager: &'a DagManager) -> Self {
         DagMetadata {
@@ -41,6 +53,18 @@ impl DagMetadata {
 }
 
 impl DagMetadata {
+    /// all user sources(the operators without parent) are bounded
+    pub fn is_bounded(&self) -> bool {
+        let mut sources = self
+            .stream_graph
+            .nodes()
+            .iter()
+            .map(|node| node.detail())
+            .filter(|node| node.operator_type == OperatorType::Source && node.parent_ids.is_empty())
+            .peekable();
+        sources.peek().is_some() && sources.all(|node| node.bounded)
+    }
+
     pub fn stream_node(&self, operator_id: OperatorId) -> Option<&StreamNode> {
         self.get_stream_node(operator_id).map(|node| node.detail())
     }
@@ -52,6 +76,27 @@ impl DagMetadata {
             .find(|node| node.detail().id.eq(&operator_id))
     }
 
+    pub fn operator_infos(&self) -> Vec<OperatorInfo> {
+        self.job_graph
+            .nodes()
+            .iter()
+            .flat_map(|job_node| {
+                let job_node = job_node.detail();
+                job_node
+                    .stream_nodes
+                    .iter()
+                    .map(move |stream_node| OperatorInfo {
+                        operator_id: stream_node.id,
+                        job_id: job_node.job_id,
+                        name: stream_node.display_name().to_string(),
+                        description: stream_node.description.clone(),
+                        operator_name: stream_node.operator_name.clone(),
+                        parallelism: job_node.parallelism,
+                    })
+            })
+            .collect()
+    }
+
     ////////////////////////////////////////////////////////////////////////////////////////////////
     ////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -139,6 +184,10 @@ impl DagMetadata {
         }
     }
 
+    pub fn execution_node(&self, task_id: &TaskId) -> Option<&ExecutionNode> {
+        self.get_execution_node(task_id).map(|node| node.detail())
+    }
+
     fn get_execution_node(&self, task_id: &TaskId) -> Option<&JsonNode<ExecutionNode>> {
         self.execution_graph
             .nodes()
@@ -3,10 +3,13 @@
 
 pub(crate) mod execution_graph;
 pub(crate) mod job_graph;
+pub(crate) mod lineage;
 pub(crate) mod metadata;
+pub(crate) mod optimizer;
 pub(crate) mod physic_graph;
 pub(crate) mod stream_graph;
 pub(crate) mod utils;
+pub(crate) mod validation;
 
 use std::borrow::BorrowMut;
 use std::convert::TryFrom;
@@ -130,6 +133,15 @@ impl<'a> TryFrom<&'a RawStreamGraph> for DagManager {
     type Error = DagError;
 
     fn try_from(raw_stream_graph: &'a RawStreamGraph) -> Result<Self, Self::Error> {
+        DagManager::new(raw_stream_graph, false)
+    }
+}
+
+impl DagManager {
+    pub fn new(
+        raw_stream_graph: &RawStreamGraph,
+        force_network_edge: bool,
+    ) -> Result<Self, DagError> {
         let stream_graph = StreamGraph::new(
             raw_stream_graph.sources.clone(),
             raw_stream_graph.dag.clone(),
@@ -139,7 +151,11 @@ impl<'a> TryFrom<&'a RawStreamGraph> for DagManager {
         job_graph.build(&stream_graph)?;
 
         let mut execution_graph = ExecutionGraph::new();
-        execution_graph.build(&job_graph, raw_stream_graph.operators().borrow_mut())?;
+        execution_graph.build(
+            &job_graph,
+            raw_stream_graph.operators().borrow_mut(),
+            force_network_edge,
+        )?;
 
         let mut physic_graph = PhysicGraph::new();
         physic_graph.build(&execution_graph);
@@ -151,9 +167,7 @@ impl<'a> TryFrom<&'a RawStreamGraph> for DagManager {
             physic_graph,
         })
     }
-}
 
-impl DagManager {
     pub fn stream_graph(&self) -> &StreamGraph {
         &self.stream_graph
     }
@@ -179,20 +193,27 @@ mod tests {
 
     use crate::core;
     use crate::core::checkpoint::CheckpointFunction;
+    use crate::core::composite::CompositeStreamApp;
     use crate::core::data_stream::CoStream;
     use crate::core::data_stream::{TConnectedStreams, TKeyedStream};
     use crate::core::data_stream::{TDataStream, TWindowedStream};
     use crate::core::data_types::{DataType, Field, Schema};
     use crate::core::element::{FnSchema, Record};
-    use crate::core::env::StreamExecutionEnvironment;
+    use crate::core::env::{StreamApp, StreamExecutionEnvironment};
     use crate::core::function::{
         CoProcessFunction, Context, FlatMapFunction, InputFormat, InputSplit, InputSplitSource,
         KeySelectorFunction, NamedFunction, OutputFormat, ReduceFunction,
     };
-    use crate::core::properties::Properties;
+    use crate::core::lineage::LineageDataset;
+    use crate::core::properties::{Properties, SystemProperties};
     use crate::core::watermark::TimestampAssigner;
+    use crate::dag::execution_graph::ExecutionEdge;
+    use crate::dag::lineage::start_event;
+    use crate::dag::metadata::DagMetadata;
     use crate::dag::utils::JsonDag;
-    use crate::dag::DagManager;
+    use crate::dag::validation::{validate, ValidationError, ValidationOptions};
+    use crate::dag::{DagManager, OperatorType};
+    use crate::functions::filter::range_window_filter::RangeWindowFilter;
     use crate::functions::watermark::DefaultWatermarkStrategy;
     use crate::functions::window::SlidingEventTimeWindows;
 
@@ -258,6 +279,226 @@ mod tests {
         print_dag(&dag_manager);
     }
 
+    #[test]
+    pub fn data_stream_window_all_test() {
+        let mut env = StreamExecutionEnvironment::new();
+
+        env.register_source(MyInputFormat::new())
+            .flat_map(MyFlatMapFunction::new())
+            .assign_timestamps_and_watermarks(
+                DefaultWatermarkStrategy::new()
+                    .for_bounded_out_of_orderness(Duration::from_secs(1))
+                    .for_timestamp_assigner(MyTimestampAssigner::new()),
+            )
+            .window_all(SlidingEventTimeWindows::new(
+                Duration::from_secs(60),
+                Duration::from_secs(20),
+                None,
+            ))
+            .reduce(MyReduceFunction::new())
+            .add_sink(MyOutputFormat::new(Properties::new()));
+
+        let dag_manager =
+            DagManager::try_from(env.stream_manager.stream_graph.borrow().deref()).unwrap();
+        print_dag(&dag_manager);
+
+        let reduce_job = dag_manager
+            .job_graph()
+            .dag
+            .raw_nodes()
+            .iter()
+            .map(|node| &node.weight)
+            .find(|job_node| {
+                job_node
+                    .stream_nodes
+                    .iter()
+                    .any(|stream_node| stream_node.operator_type == OperatorType::Reduce)
+            })
+            .unwrap();
+        assert_eq!(reduce_job.parallelism, 1);
+    }
+
+    #[test]
+    pub fn data_stream_operator_name_test() {
+        let mut env = StreamExecutionEnvironment::new();
+
+        env.register_source(MyInputFormat::new())
+            .flat_map(MyFlatMapFunction::new())
+            .name("clean and enrich")
+            .description("drop the invalid records and join the dimensions")
+            .add_sink(MyOutputFormat::new(Properties::new()));
+
+        let dag_manager =
+            DagManager::try_from(env.stream_manager.stream_graph.borrow().deref()).unwrap();
+        let dag_metadata = DagMetadata::from(&dag_manager);
+
+        let operators = dag_metadata.operator_infos();
+        let flat_map = operators
+            .iter()
+            .find(|operator| operator.name.eq("clean and enrich"))
+            .unwrap();
+        assert_eq!(flat_map.operator_name, "MyFlatMapFunction");
+        assert_eq!(
+            flat_map.description.as_deref(),
+            Some("drop the invalid records and join the dimensions")
+        );
+        assert!(operators
+            .iter()
+            .filter(|operator| operator.operator_id != flat_map.operator_id)
+            .all(|operator| operator.name.eq(&operator.operator_name)));
+    }
+
+    #[test]
+    pub fn data_stream_force_network_edge_test() {
+        let mut env = StreamExecutionEnvironment::new();
+
+        env.register_source(MyInputFormat::new())
+            .flat_map(MyFlatMapFunction::new())
+            .assign_timestamps_and_watermarks(
+                DefaultWatermarkStrategy::new()
+                    .for_bounded_out_of_orderness(Duration::from_secs(1))
+                    .for_timestamp_assigner(MyTimestampAssigner::new()),
+            )
+            .key_by(MyKeySelectorFunction::new())
+            .window(SlidingEventTimeWindows::new(
+                Duration::from_secs(60),
+                Duration::from_secs(20),
+                None,
+            ))
+            .reduce(MyReduceFunction::new())
+            .flat_map(MyFlatMapFunction::new())
+            .add_sink(MyOutputFormat::new(Properties::new()));
+
+        let dag_manager =
+            DagManager::new(env.stream_manager.stream_graph.borrow().deref(), true).unwrap();
+        print_dag(&dag_manager);
+
+        let execution_dag = &dag_manager.execution_graph().dag;
+        assert!(execution_dag
+            .raw_edges()
+            .iter()
+            .all(|edge| edge.weight == ExecutionEdge::Network));
+    }
+
+    #[test]
+    pub fn data_stream_validation_test() {
+        let mut env = StreamExecutionEnvironment::new();
+
+        env.register_source(MyInputFormat::new())
+            .flat_map(MyFlatMapFunction::new())
+            .add_sink(MyOutputFormat::new(Properties::new()));
+        env.register_source(MyInputFormat::new())
+            .flat_map(MyFlatMapFunction::new());
+
+        let options = ValidationOptions {
+            max_parallelism: 1,
+            connector_probe: true,
+        };
+        let errors = validate(env.stream_manager.stream_graph.borrow().deref(), &options)
+            .unwrap_err()
+            .0;
+        println!("{:?}", errors);
+
+        // all problems are reported at once
+        assert_eq!(errors.len(), 6);
+        assert_eq!(
+            errors
+                .iter()
+                .filter(|e| matches!(e, ValidationError::ParallelismExceeded(..)))
+                .count(),
+            5
+        );
+        assert_eq!(
+            errors[5],
+            ValidationError::SinkNotConnected("MyFlatMapFunction(4)".to_string())
+        );
+    }
+
+    #[test]
+    pub fn lineage_test() {
+        let mut env = StreamExecutionEnvironment::new();
+
+        env.register_source(MyInputFormat::new())
+            .flat_map(MyFlatMapFunction::new())
+            .add_sink(MyOutputFormat::new(Properties::new()));
+
+        let run_event = start_event(
+            env.stream_manager.stream_graph.borrow().deref(),
+            "rlink",
+            "lineage_test",
+            "0176c5c8-4f2b-4a8b-9e4a-0a1b2c3d4e5f",
+            0,
+        );
+        println!("{}", run_event);
+
+        assert_eq!(run_event["inputs"][0]["name"], "input");
+        assert_eq!(run_event["outputs"][0]["name"], "output");
+        let fields = &run_event["outputs"][0]["facets"]["schema"]["fields"];
+        assert_eq!(fields[1]["name"], "b");
+        assert_eq!(fields[1]["type"], "Int64");
+        let operators = run_event["job"]["facets"]["rlink_operators"]["operators"]
+            .as_array()
+            .unwrap();
+        assert_eq!(operators.len(), 3);
+    }
+
+    #[derive(Clone)]
+    struct MyStreamApp {
+        parallelism: u16,
+    }
+
+    impl StreamApp for MyStreamApp {
+        fn prepare_properties(&self, properties: &mut Properties) {
+            properties.set_str("parallelism", self.parallelism.to_string().as_str());
+            properties.set_checkpoint_interval(Duration::from_secs(1));
+        }
+
+        fn build_stream(&self, properties: &Properties, env: &mut StreamExecutionEnvironment) {
+            assert_eq!(properties.get_u16("parallelism").unwrap(), self.parallelism);
+            env.register_source(MyInputFormat::new())
+                .flat_map(MyFlatMapFunction::new())
+                .add_sink(MyOutputFormat::new(Properties::new()));
+        }
+    }
+
+    #[test]
+    pub fn composite_stream_app_test() {
+        let apps = CompositeStreamApp::new("composite_test")
+            .register("producer", MyStreamApp { parallelism: 1 })
+            .register("consumer", MyStreamApp { parallelism: 2 });
+
+        let mut properties = Properties::new();
+        apps.prepare_properties(&mut properties);
+        assert_eq!(properties.get_application_name(), "composite_test");
+        // the first member wins
+        assert_eq!(properties.get_string("parallelism").unwrap(), "1");
+        assert_eq!(
+            properties.get_checkpoint_interval().unwrap(),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            properties.get_string("apps.consumer.parallelism").unwrap(),
+            "2"
+        );
+
+        let mut env = StreamExecutionEnvironment::new();
+        apps.build_stream(&properties, &mut env);
+
+        let names: Vec<String> = env
+            .stream_manager
+            .operator_names()
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect();
+        assert_eq!(names.len(), 6);
+        assert!(names[0].starts_with("producer/"));
+        assert!(names[5].starts_with("consumer/"));
+
+        let dag_manager =
+            DagManager::try_from(env.stream_manager.stream_graph.borrow().deref()).unwrap();
+        print_dag(&dag_manager);
+    }
+
     #[test]
     pub fn data_stream_connect_test() {
         let mut env = StreamExecutionEnvironment::new();
@@ -294,6 +535,30 @@ mod tests {
         print_dag(&dag_manager);
     }
 
+    #[test]
+    pub fn data_stream_optimize_test() {
+        let mut env = StreamExecutionEnvironment::new();
+
+        env.register_source(MyInputFormat::new())
+            .flat_map(MyFlatMapFunction::new())
+            .filter(RangeWindowFilter::new(0, u64::MAX))
+            .flat_map(MyFlatMapFunction::new())
+            .add_sink(MyOutputFormat::new(Properties::new()));
+        // the dead branch without sink
+        env.register_source(MyInputFormat::new())
+            .flat_map(MyFlatMapFunction::new());
+
+        let report = env.stream_manager.optimize().unwrap();
+        println!("{:?}", report);
+        assert_eq!(report.removed.len(), 2);
+        assert_eq!(report.pushed_down.len(), 1);
+        assert_eq!(report.merged.len(), 1);
+
+        let dag_manager =
+            DagManager::try_from(env.stream_manager.stream_graph.borrow().deref()).unwrap();
+        print_dag(&dag_manager);
+    }
+
     fn print_dag(dag_manager: &DagManager) {
         {
             let dag = &dag_manager.stream_graph().dag;
@@ -357,6 +622,10 @@ mod tests {
         fn parallelism(&self) -> u16 {
             3
         }
+
+        fn lineage_datasets(&self) -> Vec<LineageDataset> {
+            vec![LineageDataset::new("kafka://localhost:9092", "input")]
+        }
     }
 
     #[derive(Serialize, Deserialize, Debug)]
@@ -384,6 +653,10 @@ mod tests {
         fn schema(&self, input_schema: FnSchema) -> FnSchema {
             input_schema
         }
+
+        fn preserves_records(&self) -> bool {
+            true
+        }
     }
 
     impl NamedFunction for MyFlatMapFunction {
@@ -525,6 +798,10 @@ mod tests {
         fn schema(&self, input_schema: FnSchema) -> FnSchema {
             input_schema
         }
+
+        fn lineage_datasets(&self) -> Vec<LineageDataset> {
+            vec![LineageDataset::new("kafka://localhost:9092", "output")]
+        }
     }
 
     impl NamedFunction for MyOutputFormat {
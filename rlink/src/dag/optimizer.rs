@@ -0,0 +1,330 @@
+//! The optimization of the user operators before the stream graph is split into the jobs,
+//! see `SystemProperties::set_stream_graph_optimizer`.
+//!
+//! The user operators are read from the `RawStreamGraph` without the virtual operators,
+//! rewritten by the passes and added to a new `RawStreamGraph` with their original ids,
+//! so the checkpoints and the task descriptors of the coordinator and the workers match.
+//! 1. the dead operator elimination: remove the operators without a path to a sink
+//! 2. the filter pushdown: move a filter before the `FlatMapFunction::preserves_records` maps
+//! 3. the map merging: merge the consecutive flat maps into a `ChainedFlatMapFunction`
+
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use crate::channel::ChannelOptions;
+use crate::core::operator::{
+    DefaultStreamOperator, FunctionCreator, StreamOperator, TStreamOperator,
+};
+use crate::core::retry::RetryPolicy;
+use crate::core::runtime::OperatorId;
+use crate::core::state_limit::StateLimit;
+use crate::core::write_ahead_log::WriteAheadLog;
+use crate::dag::stream_graph::StreamNode;
+use crate::dag::{DagError, OperatorType, RawStreamGraph};
+use crate::functions::flat_map::chained_flat_map::ChainedFlatMapFunction;
+
+/// A user operator, the `parents` are the user operators ignoring the virtual ones
+struct LogicalNode {
+    parents: Vec<OperatorId>,
+    operator: StreamOperator,
+    operator_type: OperatorType,
+    channel_options: ChannelOptions,
+    name: Option<String>,
+    description: Option<String>,
+    processing_timeout: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    state_limit: Option<StateLimit>,
+    write_ahead_log: Option<WriteAheadLog>,
+}
+
+impl LogicalNode {
+    fn new(stream_node: StreamNode, operator: StreamOperator, parents: Vec<OperatorId>) -> Self {
+        LogicalNode {
+            parents,
+            operator,
+            operator_type: stream_node.operator_type,
+            channel_options: stream_node.channel_options,
+            name: stream_node.name,
+            description: stream_node.description,
+            processing_timeout: stream_node.processing_timeout,
+            retry_policy: stream_node.retry_policy,
+            state_limit: stream_node.state_limit,
+            write_ahead_log: stream_node.write_ahead_log,
+        }
+    }
+}
+
+/// The changes of the optimization, logged by the coordinator
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub(crate) struct OptimizeReport {
+    pub removed: Vec<OperatorId>,
+    /// (filter, the map it's pushed before)
+    pub pushed_down: Vec<(OperatorId, OperatorId)>,
+    /// (the merged map, the map merged into it)
+    pub merged: Vec<(OperatorId, OperatorId)>,
+}
+
+pub(crate) fn optimize(raw_stream_graph: &mut RawStreamGraph) -> Result<OptimizeReport, DagError> {
+    let first_virtual_id = OperatorId(raw_stream_graph.max_operator_id().0 + 1);
+    let mut nodes = logical_nodes(raw_stream_graph);
+
+    let mut report = OptimizeReport::default();
+    eliminate_dead_operators(&mut nodes, &mut report);
+    push_down_filters(&mut nodes, &mut report);
+    merge_flat_maps(&mut nodes, &mut report)?;
+
+    let mut optimized = RawStreamGraph::new();
+    for operator_id in topological_order(&nodes) {
+        let node = nodes.remove(&operator_id).unwrap();
+        optimized.add_operator_with_id(
+            node.operator,
+            node.parents,
+            operator_id,
+            first_virtual_id,
+        )?;
+        optimized.set_channel_options(operator_id, node.channel_options)?;
+        if let Some(name) = &node.name {
+            optimized.set_name(operator_id, name)?;
+        }
+        if let Some(description) = &node.description {
+            optimized.set_description(operator_id, description)?;
+        }
+        if let Some(processing_timeout) = node.processing_timeout {
+            optimized.set_processing_timeout(operator_id, processing_timeout)?;
+        }
+        if let Some(retry_policy) = node.retry_policy {
+            optimized.set_retry_policy(operator_id, retry_policy)?;
+        }
+        if let Some(state_limit) = node.state_limit {
+            optimized.set_state_limit(operator_id, state_limit)?;
+        }
+        if let Some(write_ahead_log) = node.write_ahead_log {
+            optimized.set_write_ahead_log(operator_id, write_ahead_log)?;
+        }
+    }
+
+    *raw_stream_graph = optimized;
+    Ok(report)
+}
+
+/// pop the user operators, the virtual parents are replaced by their nearest user ancestors
+fn logical_nodes(raw_stream_graph: &mut RawStreamGraph) -> BTreeMap<OperatorId, LogicalNode> {
+    let stream_nodes: BTreeMap<OperatorId, StreamNode> = raw_stream_graph
+        .dag
+        .raw_nodes()
+        .iter()
+        .map(|node| (node.weight.id, node.weight.clone()))
+        .collect();
+
+    let mut nodes = BTreeMap::new();
+    for (operator_id, stream_node) in &stream_nodes {
+        if let FunctionCreator::System = stream_node.fn_creator {
+            continue;
+        }
+
+        let (stream_node, operator) = raw_stream_graph.pop_operator(*operator_id).unwrap();
+        let mut parents = Vec::new();
+        for parent_id in &stream_node.parent_ids {
+            user_ancestors(&stream_nodes, *parent_id, &mut parents);
+        }
+        nodes.insert(
+            *operator_id,
+            LogicalNode::new(stream_node, operator, parents),
+        );
+    }
+    nodes
+}
+
+fn user_ancestors(
+    stream_nodes: &BTreeMap<OperatorId, StreamNode>,
+    operator_id: OperatorId,
+    ancestors: &mut Vec<OperatorId>,
+) {
+    let stream_node = &stream_nodes[&operator_id];
+    match stream_node.fn_creator {
+        FunctionCreator::User => ancestors.push(operator_id),
+        FunctionCreator::System => {
+            for parent_id in &stream_node.parent_ids {
+                user_ancestors(stream_nodes, *parent_id, ancestors);
+            }
+        }
+    }
+}
+
+fn children(nodes: &BTreeMap<OperatorId, LogicalNode>, operator_id: OperatorId) -> Vec<OperatorId> {
+    nodes
+        .iter()
+        .filter(|(_, node)| node.parents.contains(&operator_id))
+        .map(|(child_id, _)| *child_id)
+        .collect()
+}
+
+/// replace the parent `from` of the children of `from` with `to`
+fn reparent_children(
+    nodes: &mut BTreeMap<OperatorId, LogicalNode>,
+    from: OperatorId,
+    to: OperatorId,
+) {
+    for node in nodes.values_mut() {
+        for parent in node.parents.iter_mut() {
+            if *parent == from {
+                *parent = to;
+            }
+        }
+    }
+}
+
+fn eliminate_dead_operators(
+    nodes: &mut BTreeMap<OperatorId, LogicalNode>,
+    report: &mut OptimizeReport,
+) {
+    let mut live: Vec<OperatorId> = nodes
+        .iter()
+        .filter(|(_, node)| node.operator_type == OperatorType::Sink)
+        .map(|(operator_id, _)| *operator_id)
+        .collect();
+    // the validation reports the graph without sink
+    if live.is_empty() {
+        return;
+    }
+
+    let mut visited = HashSet::new();
+    while let Some(operator_id) = live.pop() {
+        if visited.insert(operator_id) {
+            live.extend(nodes[&operator_id].parents.iter().cloned());
+        }
+    }
+
+    let dead: Vec<OperatorId> = nodes
+        .keys()
+        .filter(|operator_id| !visited.contains(operator_id))
+        .cloned()
+        .collect();
+    for operator_id in dead {
+        nodes.remove(&operator_id);
+        report.removed.push(operator_id);
+    }
+}
+
+fn push_down_filters(nodes: &mut BTreeMap<OperatorId, LogicalNode>, report: &mut OptimizeReport) {
+    loop {
+        let candidate = nodes.iter().find_map(|(filter_id, node)| {
+            if node.operator_type != OperatorType::Filter || node.parents.len() != 1 {
+                return None;
+            }
+            let map_id = node.parents[0];
+            let map = &nodes[&map_id];
+            let preserves_records = match &map.operator {
+                StreamOperator::StreamFlatMap(op) => op.operator_fn.preserves_records(),
+                _ => false,
+            };
+            if preserves_records && map.parents.len() == 1 {
+                Some((*filter_id, map_id))
+            } else {
+                None
+            }
+        });
+
+        let (filter_id, map_id) = match candidate {
+            Some(candidate) => candidate,
+            None => return,
+        };
+
+        // parent -> map -> filter -> children  =>  parent -> filter -> map -> children
+        let map_parents = nodes[&map_id].parents.clone();
+        reparent_children(nodes, filter_id, map_id);
+        nodes.get_mut(&filter_id).unwrap().parents = map_parents;
+        nodes.get_mut(&map_id).unwrap().parents = vec![filter_id];
+        report.pushed_down.push((filter_id, map_id));
+    }
+}
+
+fn merge_flat_maps(
+    nodes: &mut BTreeMap<OperatorId, LogicalNode>,
+    report: &mut OptimizeReport,
+) -> Result<(), DagError> {
+    loop {
+        let candidate = nodes.iter().find_map(|(map_id, node)| {
+            if node.operator_type != OperatorType::FlatMap || node.parents.len() != 1 {
+                return None;
+            }
+            let parent_id = node.parents[0];
+            let parent = &nodes[&parent_id];
+            let mergeable = parent.operator_type == OperatorType::FlatMap
+                && parent.operator.parallelism() == node.operator.parallelism()
+                && children(nodes, parent_id).len() == 1;
+            if mergeable {
+                Some((parent_id, *map_id))
+            } else {
+                None
+            }
+        });
+
+        let (first_id, second_id) = match candidate {
+            Some(candidate) => candidate,
+            None => return Ok(()),
+        };
+
+        let second = nodes.remove(&second_id).unwrap();
+        let first = nodes.remove(&first_id).unwrap();
+        let parallelism = first.operator.parallelism();
+        let (first_op, second_op) = match (first.operator, second.operator) {
+            (StreamOperator::StreamFlatMap(first_op), StreamOperator::StreamFlatMap(second_op)) => {
+                (first_op, second_op)
+            }
+            _ => return Err(DagError::NotCombineOperator),
+        };
+
+        let operator_fn = Box::new(ChainedFlatMapFunction::new(
+            first_op.operator_fn,
+            second_op.operator_fn,
+        ));
+        let operator = StreamOperator::StreamFlatMap(DefaultStreamOperator::new(
+            parallelism,
+            FunctionCreator::User,
+            operator_fn,
+        ));
+
+        // the larger channel wins as the operators in a job do
+        let channel_options =
+            if second.channel_options.channel_size > first.channel_options.channel_size {
+                second.channel_options
+            } else {
+                first.channel_options
+            };
+        let merged = LogicalNode {
+            parents: first.parents,
+            operator,
+            operator_type: OperatorType::FlatMap,
+            channel_options,
+            name: first.name.or(second.name),
+            description: first.description.or(second.description),
+            processing_timeout: first.processing_timeout.max(second.processing_timeout),
+            // the merged map is retried as a whole
+            retry_policy: first.retry_policy.or(second.retry_policy),
+            state_limit: first.state_limit.or(second.state_limit),
+            write_ahead_log: first.write_ahead_log.or(second.write_ahead_log),
+        };
+        nodes.insert(first_id, merged);
+        reparent_children(nodes, second_id, first_id);
+        report.merged.push((first_id, second_id));
+    }
+}
+
+/// the parents first, the ties are broken by the operator id
+fn topological_order(nodes: &BTreeMap<OperatorId, LogicalNode>) -> Vec<OperatorId> {
+    let mut order = Vec::new();
+    let mut added = HashSet::new();
+    while order.len() < nodes.len() {
+        let next = nodes
+            .iter()
+            .find(|(operator_id, node)| {
+                !added.contains(*operator_id) && node.parents.iter().all(|x| added.contains(x))
+            })
+            .map(|(operator_id, _)| *operator_id)
+            .expect("cycle in the stream graph");
+        added.insert(next);
+        order.push(next);
+    }
+    order
+}
@@ -1,14 +1,19 @@
 use std::cmp::max;
 use std::collections::HashMap;
 use std::ops::Index;
+use std::time::Duration;
 
 use daggy::{Dag, EdgeIndex, NodeIndex};
 
+use crate::channel::ChannelOptions;
 use crate::core::element::FnSchema;
 use crate::core::operator::{
     DefaultStreamOperator, FunctionCreator, StreamOperator, TStreamOperator, DEFAULT_PARALLELISM,
 };
+use crate::core::retry::RetryPolicy;
 use crate::core::runtime::OperatorId;
+use crate::core::state_limit::StateLimit;
+use crate::core::write_ahead_log::WriteAheadLog;
 use crate::dag::{DagError, OperatorType};
 use crate::functions::system::keyed_state_flat_map::KeyedStateFlatMapFunction;
 use crate::functions::system::system_input_format::SystemInputFormat;
@@ -22,12 +27,43 @@ pub struct StreamNode {
     pub(crate) input_schema: FnSchema,
     pub(crate) output_schema: FnSchema,
     pub(crate) daemon: bool,
+    /// the source is bounded, see `InputFormat::bounded`
+    #[serde(default)]
+    pub(crate) bounded: bool,
+    /// the options of the input channel of the job which the operator belongs to
+    #[serde(default)]
+    pub(crate) channel_options: ChannelOptions,
+    /// the user-defined name of the operator, shown in the metrics and the coordinator api
+    #[serde(default)]
+    pub(crate) name: Option<String>,
+    /// the user-defined description of the operator
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+    /// the processing timeout of a record in the operator, overrides the application default
+    #[serde(default)]
+    pub(crate) processing_timeout: Option<Duration>,
+    /// the retry of the user function in the operator
+    #[serde(default)]
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    /// the max size of the keyed state of the operator in a task
+    #[serde(default)]
+    pub(crate) state_limit: Option<StateLimit>,
+    /// the write-ahead log of the sink
+    #[serde(default)]
+    pub(crate) write_ahead_log: Option<WriteAheadLog>,
 
     pub(crate) operator_name: String,
     pub(crate) operator_type: OperatorType,
     pub(crate) fn_creator: FunctionCreator,
 }
 
+impl StreamNode {
+    /// the user-defined name, or the name of the operator if not set
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(self.operator_name.as_str())
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct StreamEdge {
     edge_id: String,
@@ -58,6 +94,8 @@ pub(crate) struct RawStreamGraph {
     stream_edges: Vec<EdgeIndex>,
 
     id_gen: OperatorId,
+    /// the id of the next user operator added, see `add_operator_with_id`
+    preserved_id: Option<OperatorId>,
     operators: HashMap<OperatorId, (NodeIndex, StreamOperator)>,
 
     pub(crate) sources: Vec<NodeIndex>,
@@ -74,6 +112,7 @@ impl RawStreamGraph {
             stream_nodes: Vec::new(),
             stream_edges: Vec::new(),
             id_gen: OperatorId::default(),
+            preserved_id: None,
             operators: HashMap::new(),
             sources: Vec::new(),
             user_sources: Vec::new(),
@@ -139,8 +178,18 @@ impl RawStreamGraph {
         parent_operator_ids: Vec<OperatorId>,
         parallelism: u16,
     ) -> Result<OperatorId, DagError> {
-        let operator_id = self.id_gen;
-        self.id_gen.0 = self.id_gen.0 + 1;
+        let preserved_id = match operator.fn_creator() {
+            FunctionCreator::User => self.preserved_id.take(),
+            FunctionCreator::System => None,
+        };
+        let operator_id = match preserved_id {
+            Some(operator_id) => operator_id,
+            None => {
+                let operator_id = self.id_gen;
+                self.id_gen.0 += 1;
+                operator_id
+            }
+        };
 
         let input_schema = match parent_operator_ids.len() {
             0 => FnSchema::Empty,
@@ -165,6 +214,14 @@ impl RawStreamGraph {
             input_schema: input_schema.clone(),
             output_schema: operator.schema(input_schema),
             daemon: operator.is_daemon(),
+            bounded: operator.is_bounded(),
+            channel_options: ChannelOptions::default(),
+            name: None,
+            description: None,
+            processing_timeout: None,
+            retry_policy: None,
+            state_limit: None,
+            write_ahead_log: None,
             operator_name: operator.operator_name().to_string(),
             operator_type: OperatorType::from(&operator),
             fn_creator: operator.fn_creator(),
@@ -205,6 +262,75 @@ impl RawStreamGraph {
         Ok(operator_id)
     }
 
+    fn stream_node_mut(&mut self, operator_id: OperatorId) -> Result<&mut StreamNode, DagError> {
+        let (node_index, _) = self
+            .operators
+            .get(&operator_id)
+            .ok_or(DagError::OperatorNotFound(operator_id))?;
+        self.dag
+            .node_weight_mut(*node_index)
+            .ok_or(DagError::OperatorNotFound(operator_id))
+    }
+
+    pub fn set_channel_options(
+        &mut self,
+        operator_id: OperatorId,
+        channel_options: ChannelOptions,
+    ) -> Result<(), DagError> {
+        self.stream_node_mut(operator_id)?.channel_options = channel_options;
+        Ok(())
+    }
+
+    pub fn set_name(&mut self, operator_id: OperatorId, name: &str) -> Result<(), DagError> {
+        self.stream_node_mut(operator_id)?.name = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn set_description(
+        &mut self,
+        operator_id: OperatorId,
+        description: &str,
+    ) -> Result<(), DagError> {
+        self.stream_node_mut(operator_id)?.description = Some(description.to_string());
+        Ok(())
+    }
+
+    pub fn set_processing_timeout(
+        &mut self,
+        operator_id: OperatorId,
+        timeout: Duration,
+    ) -> Result<(), DagError> {
+        self.stream_node_mut(operator_id)?.processing_timeout = Some(timeout);
+        Ok(())
+    }
+
+    pub fn set_retry_policy(
+        &mut self,
+        operator_id: OperatorId,
+        retry_policy: RetryPolicy,
+    ) -> Result<(), DagError> {
+        self.stream_node_mut(operator_id)?.retry_policy = Some(retry_policy);
+        Ok(())
+    }
+
+    pub fn set_state_limit(
+        &mut self,
+        operator_id: OperatorId,
+        state_limit: StateLimit,
+    ) -> Result<(), DagError> {
+        self.stream_node_mut(operator_id)?.state_limit = Some(state_limit);
+        Ok(())
+    }
+
+    pub fn set_write_ahead_log(
+        &mut self,
+        operator_id: OperatorId,
+        write_ahead_log: WriteAheadLog,
+    ) -> Result<(), DagError> {
+        self.stream_node_mut(operator_id)?.write_ahead_log = Some(write_ahead_log);
+        Ok(())
+    }
+
     pub fn add_operator(
         &mut self,
         operator: StreamOperator,
@@ -331,6 +457,38 @@ impl RawStreamGraph {
         };
     }
 
+    /// add a user operator with the `operator_id` of a previous graph, the virtual operators
+    /// are allocated from `first_virtual_id`, so the user operators keep their ids and the
+    /// checkpoints after the rebuilding, see `optimizer::optimize`
+    pub(crate) fn add_operator_with_id(
+        &mut self,
+        operator: StreamOperator,
+        parent_operator_ids: Vec<OperatorId>,
+        operator_id: OperatorId,
+        first_virtual_id: OperatorId,
+    ) -> Result<OperatorId, DagError> {
+        if self.id_gen.0 < first_virtual_id.0 {
+            self.id_gen = first_virtual_id;
+        }
+        self.preserved_id = Some(operator_id);
+        self.add_operator(operator, parent_operator_ids)
+    }
+
+    /// the operator and the node in the graph
+    pub(crate) fn pop_operator(
+        &mut self,
+        operator_id: OperatorId,
+    ) -> Option<(StreamNode, StreamOperator)> {
+        self.operators
+            .remove(&operator_id)
+            .map(|(node_index, operator)| (self.dag.index(node_index).clone(), operator))
+    }
+
+    /// the max id of all operators
+    pub(crate) fn max_operator_id(&self) -> OperatorId {
+        self.operators.keys().max().cloned().unwrap_or_default()
+    }
+
     fn is_pipeline(
         &self,
         operator_type: OperatorType,
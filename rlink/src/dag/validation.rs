@@ -0,0 +1,161 @@
+//! The dry-run validation of the stream graph at the submission, run before the resources
+//! are allocated, all the problems are reported at once instead of failing at the task opening.
+
+use std::collections::HashMap;
+
+use daggy::Walker;
+use thiserror::Error;
+
+use crate::core::element::FnSchema;
+use crate::core::operator::FunctionCreator;
+use crate::core::runtime::OperatorId;
+use crate::dag::stream_graph::StreamNode;
+use crate::dag::{OperatorType, RawStreamGraph};
+
+/// the default max parallelism of an operator
+pub const DEFAULT_MAX_PARALLELISM: u16 = 1024;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    #[error("the input of operator {0} has no schema, the output schema of upstream {1} is not declared")]
+    InputSchemaMissing(String, String),
+    #[error("operator {0} is not connected to any sink")]
+    SinkNotConnected(String),
+    #[error("the parallelism of source {0} must be positive")]
+    SourceParallelismMissing(String),
+    #[error("the parallelism {1} of operator {0} exceeds the max parallelism {2}")]
+    ParallelismExceeded(String, u16, u16),
+    #[error("the connectivity probe of operator {0} failed. {1}")]
+    ProbeFailed(String, String),
+}
+
+/// All the problems found in the validation
+#[derive(Error, Debug, Clone)]
+#[error("stream graph validation failed: {}", display_errors(.0))]
+pub struct ValidationErrors(pub Vec<ValidationError>);
+
+fn display_errors(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .enumerate()
+        .map(|(i, e)| format!("\n  {}. {}", i + 1, e))
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ValidationOptions {
+    pub max_parallelism: u16,
+    pub connector_probe: bool,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        ValidationOptions {
+            max_parallelism: DEFAULT_MAX_PARALLELISM,
+            connector_probe: false,
+        }
+    }
+}
+
+fn display_name(node: &StreamNode) -> String {
+    format!("{}({})", node.display_name(), node.id.0)
+}
+
+/// the nearest user operators upstream of the `node`, skip the system operators between them
+fn user_parents<'a>(
+    node: &StreamNode,
+    nodes: &HashMap<OperatorId, &'a StreamNode>,
+) -> Vec<&'a StreamNode> {
+    let mut parents = Vec::new();
+    let mut stack: Vec<OperatorId> = node.parent_ids.clone();
+    while let Some(parent_id) = stack.pop() {
+        if let Some(parent) = nodes.get(&parent_id) {
+            match parent.fn_creator {
+                FunctionCreator::User => parents.push(*parent),
+                FunctionCreator::System => stack.extend(parent.parent_ids.iter()),
+            }
+        }
+    }
+    parents
+}
+
+/// validate the user operators of the `raw_stream_graph`:
+/// - the input schema of the chained operators
+/// - every operator is connected to a sink
+/// - the parallelism limits
+/// - the connectivity of the sources and sinks, only if `connector_probe` is enabled
+pub(crate) fn validate(
+    raw_stream_graph: &RawStreamGraph,
+    options: &ValidationOptions,
+) -> Result<(), ValidationErrors> {
+    let dag = &raw_stream_graph.dag;
+    let nodes: HashMap<OperatorId, &StreamNode> = dag
+        .raw_nodes()
+        .iter()
+        .map(|node| (node.weight.id, &node.weight))
+        .collect();
+
+    let mut errors = Vec::new();
+    for node in dag.raw_nodes().iter().map(|node| &node.weight) {
+        if let FunctionCreator::System = node.fn_creator {
+            continue;
+        }
+
+        if node.operator_type == OperatorType::Source
+            && node.parent_ids.is_empty()
+            && node.parallelism == 0
+        {
+            errors.push(ValidationError::SourceParallelismMissing(display_name(
+                node,
+            )));
+        }
+
+        for parent in user_parents(node, &nodes) {
+            if let FnSchema::Empty = parent.output_schema {
+                errors.push(ValidationError::InputSchemaMissing(
+                    display_name(node),
+                    display_name(parent),
+                ));
+            }
+        }
+
+        if node.parallelism > options.max_parallelism {
+            errors.push(ValidationError::ParallelismExceeded(
+                display_name(node),
+                node.parallelism,
+                options.max_parallelism,
+            ));
+        }
+    }
+
+    // the operators without children must be sinks
+    for (i, node) in dag.raw_nodes().iter().enumerate() {
+        let node_index = daggy::NodeIndex::new(i);
+        let has_children = dag.children(node_index).walk_next(dag).is_some();
+        if !has_children && node.weight.operator_type != OperatorType::Sink {
+            errors.push(ValidationError::SinkNotConnected(display_name(
+                &node.weight,
+            )));
+        }
+    }
+
+    if options.connector_probe {
+        let mut operators: Vec<_> = raw_stream_graph.operators().into_iter().collect();
+        operators.sort_by_key(|(operator_id, _)| operator_id.0);
+        for (operator_id, operator) in operators {
+            if let Err(e) = operator.probe() {
+                let name = nodes
+                    .get(&operator_id)
+                    .map(|node| display_name(node))
+                    .unwrap_or_else(|| format!("{}", operator_id.0));
+                errors.push(ValidationError::ProbeFailed(name, e.to_string()));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationErrors(errors))
+    }
+}
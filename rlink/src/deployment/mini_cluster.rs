@@ -0,0 +1,124 @@
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::core::cluster::TaskResourceInfo;
+use crate::core::env::{StreamApp, StreamExecutionEnvironment};
+use crate::core::runtime::ClusterDescriptor;
+use crate::deployment::{Resource, TResourceManager};
+use crate::runtime::context::Context;
+use crate::runtime::ManagerType;
+
+/// the time of waiting for the worker process to exit by itself before killing
+const WORKER_EXIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `Local` mode with multiple workers.
+/// Each worker is launched as a child process of the current executable,
+/// so the network pub/sub, the serialization and the failover are exercised on a single machine.
+pub(crate) struct MiniClusterResourceManager {
+    context: Arc<Context>,
+    cluster_descriptor: Option<ClusterDescriptor>,
+    worker_processes: Mutex<Vec<(String, Child)>>,
+}
+
+impl MiniClusterResourceManager {
+    pub fn new(context: Arc<Context>) -> Self {
+        MiniClusterResourceManager {
+            context,
+            cluster_descriptor: None,
+            worker_processes: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl TResourceManager for MiniClusterResourceManager {
+    fn prepare(&mut self, _context: &Context, cluster_descriptor: &ClusterDescriptor) {
+        self.cluster_descriptor = Some(cluster_descriptor.clone());
+    }
+
+    fn worker_allocate<S>(
+        &self,
+        _stream_app: &S,
+        _stream_env: &StreamExecutionEnvironment,
+    ) -> anyhow::Result<Vec<TaskResourceInfo>>
+    where
+        S: StreamApp + 'static,
+    {
+        let cluster_descriptor = self.cluster_descriptor.as_ref().unwrap();
+        let program = std::env::current_exe()?;
+
+        let mut worker_processes = self.worker_processes.lock().unwrap();
+        let mut task_infos = Vec::new();
+        for task_manager_descriptor in &cluster_descriptor.worker_managers {
+            let resource = Resource::new(
+                cluster_descriptor.coordinator_manager.memory_mb,
+                cluster_descriptor.coordinator_manager.v_cores,
+            );
+            info!(
+                "TaskManager(id={}) allocate resource cpu:{}, memory:{}",
+                task_manager_descriptor.task_manager_id, resource.cpu_cores, resource.memory
+            );
+
+            // the worker's args are placed in front of the process args,
+            // they take precedence over the same keys in the process args
+            let child = Command::new(program.as_path())
+                .arg(format!("cluster_mode={}", self.context.cluster_mode))
+                .arg(format!("manager_type={}", ManagerType::Worker))
+                .arg(format!("application_id={}", self.context.application_id))
+                .arg(format!(
+                    "task_manager_id={}",
+                    task_manager_descriptor.task_manager_id
+                ))
+                .arg(format!(
+                    "coordinator_address={}",
+                    cluster_descriptor.coordinator_manager.web_address
+                ))
+                .args(std::env::args().skip(1))
+                .spawn()?;
+            info!(
+                "TaskManager(id={}) process launched, pid: {}",
+                task_manager_descriptor.task_manager_id,
+                child.id()
+            );
+
+            let mut task_info = TaskResourceInfo {
+                task_manager_id: task_manager_descriptor.task_manager_id.clone(),
+                resource_info: Default::default(),
+            };
+            task_info
+                .resource_info
+                .insert("pid".to_string(), child.id().to_string());
+            task_infos.push(task_info);
+
+            worker_processes.push((task_manager_descriptor.task_manager_id.clone(), child));
+        }
+
+        Ok(task_infos)
+    }
+
+    fn stop_workers(&self, _task_ids: Vec<TaskResourceInfo>) -> anyhow::Result<()> {
+        let mut worker_processes = self.worker_processes.lock().unwrap();
+
+        let begin = Instant::now();
+        for (task_manager_id, child) in worker_processes.iter_mut() {
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    info!("TaskManager(id={}) exit with {}", task_manager_id, status);
+                    break;
+                }
+
+                if begin.elapsed() > WORKER_EXIT_TIMEOUT {
+                    warn!("TaskManager(id={}) is alive, kill it", task_manager_id);
+                    child.kill()?;
+                    child.wait()?;
+                    break;
+                }
+
+                std::thread::sleep(Duration::from_millis(200));
+            }
+        }
+
+        worker_processes.clear();
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use crate::core::runtime::ClusterDescriptor;
 #[cfg(feature = "k8s")]
 use crate::deployment::kubernetes::KubernetesResourceManager;
 use crate::deployment::local::LocalResourceManager;
+use crate::deployment::mini_cluster::MiniClusterResourceManager;
 use crate::deployment::standalone::StandaloneResourceManager;
 use crate::deployment::yarn::YarnResourceManager;
 use crate::runtime::context::Context;
@@ -14,6 +15,7 @@ use crate::runtime::ClusterMode;
 #[cfg(feature = "k8s")]
 pub mod kubernetes;
 pub mod local;
+pub mod mini_cluster;
 pub mod standalone;
 pub mod yarn;
 
@@ -42,10 +44,15 @@ pub(crate) trait TResourceManager {
         S: StreamApp + 'static;
 
     fn stop_workers(&self, task_ids: Vec<TaskResourceInfo>) -> anyhow::Result<()>;
+
+    /// exclude the TaskManagers from the next `worker_allocate`, eg: the draining TaskManagers.
+    /// It's ignored by the resource managers always allocating the new hosts
+    fn exclude_task_managers(&mut self, _task_manager_addresses: Vec<String>) {}
 }
 
 pub(crate) enum ResourceManager {
     LocalResourceManager(LocalResourceManager),
+    MiniClusterResourceManager(MiniClusterResourceManager),
     StandaloneResourceManager(StandaloneResourceManager),
     YarnResourceManager(YarnResourceManager),
     #[cfg(feature = "k8s")]
@@ -56,7 +63,15 @@ impl ResourceManager {
     pub fn new(context: Arc<Context>) -> Self {
         match context.cluster_mode {
             ClusterMode::Local => {
-                ResourceManager::LocalResourceManager(LocalResourceManager::new(context.clone()))
+                if context.num_task_managers > 1 {
+                    ResourceManager::MiniClusterResourceManager(MiniClusterResourceManager::new(
+                        context.clone(),
+                    ))
+                } else {
+                    ResourceManager::LocalResourceManager(LocalResourceManager::new(
+                        context.clone(),
+                    ))
+                }
             }
             ClusterMode::Standalone => ResourceManager::StandaloneResourceManager(
                 StandaloneResourceManager::new(context.clone()),
@@ -78,6 +93,7 @@ impl TResourceManager for ResourceManager {
     fn prepare(&mut self, context: &Context, job_descriptor: &ClusterDescriptor) {
         match self {
             ResourceManager::LocalResourceManager(rm) => rm.prepare(context, job_descriptor),
+            ResourceManager::MiniClusterResourceManager(rm) => rm.prepare(context, job_descriptor),
             ResourceManager::StandaloneResourceManager(rm) => rm.prepare(context, job_descriptor),
             ResourceManager::YarnResourceManager(rm) => rm.prepare(context, job_descriptor),
             #[cfg(feature = "k8s")]
@@ -95,6 +111,9 @@ impl TResourceManager for ResourceManager {
     {
         match self {
             ResourceManager::LocalResourceManager(rm) => rm.worker_allocate(stream_app, stream_env),
+            ResourceManager::MiniClusterResourceManager(rm) => {
+                rm.worker_allocate(stream_app, stream_env)
+            }
             ResourceManager::StandaloneResourceManager(rm) => {
                 rm.worker_allocate(stream_app, stream_env)
             }
@@ -109,10 +128,17 @@ impl TResourceManager for ResourceManager {
     fn stop_workers(&self, task_ids: Vec<TaskResourceInfo>) -> anyhow::Result<()> {
         match self {
             ResourceManager::LocalResourceManager(rm) => rm.stop_workers(task_ids),
+            ResourceManager::MiniClusterResourceManager(rm) => rm.stop_workers(task_ids),
             ResourceManager::StandaloneResourceManager(rm) => rm.stop_workers(task_ids),
             ResourceManager::YarnResourceManager(rm) => rm.stop_workers(task_ids),
             #[cfg(feature = "k8s")]
             ResourceManager::KubernetesResourceManager(rm) => rm.stop_workers(task_ids),
         }
     }
+
+    fn exclude_task_managers(&mut self, task_manager_addresses: Vec<String>) {
+        if let ResourceManager::StandaloneResourceManager(rm) = self {
+            rm.exclude_task_managers(task_manager_addresses)
+        }
+    }
 }
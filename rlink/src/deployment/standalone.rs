@@ -13,6 +13,7 @@ use crate::utils::http;
 pub(crate) struct StandaloneResourceManager {
     context: Arc<Context>,
     cluster_descriptor: Option<ClusterDescriptor>,
+    excluded_task_managers: Vec<String>,
 }
 
 impl StandaloneResourceManager {
@@ -20,6 +21,7 @@ impl StandaloneResourceManager {
         StandaloneResourceManager {
             context,
             cluster_descriptor: None,
+            excluded_task_managers: Vec::new(),
         }
     }
 }
@@ -77,6 +79,12 @@ impl TResourceManager for StandaloneResourceManager {
                 "coordinator_address".to_string(),
                 cluster_descriptor.coordinator_manager.web_address.clone(),
             );
+            if !self.excluded_task_managers.is_empty() {
+                args.insert(
+                    "excluded_task_managers".to_string(),
+                    self.excluded_task_managers.join(","),
+                );
+            }
 
             task_args.push(args);
         }
@@ -92,6 +100,10 @@ impl TResourceManager for StandaloneResourceManager {
         );
         cluster_client.stop_all_workers(self.context.application_id.as_str(), task_ids)
     }
+
+    fn exclude_task_managers(&mut self, task_manager_addresses: Vec<String>) {
+        self.excluded_task_managers = task_manager_addresses;
+    }
 }
 
 struct StandaloneClusterClient {
@@ -145,6 +157,10 @@ impl StandaloneClusterClient {
         let body = serde_json::to_string(&request_body).unwrap();
         let result: StdResponse<String> = http::client::post_sync(url, body)?;
 
+        if let ResponseCode::ERR(msg) = result.code {
+            // eg: no enough slots in the session cluster
+            return Err(anyhow!("allocate worker error. {}", msg).into());
+        }
         info!("allocation success. `code`={:?}", result.code);
 
         let data: Vec<TaskResourceInfo> = serde_json::from_str(result.data.unwrap().as_str())?;
@@ -0,0 +1,479 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::Schema;
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{CoProcessFunction, Context, NamedFunction};
+use crate::functions::sink::record_to_strings;
+use crate::metrics::metric::{Counter, Gauge};
+use crate::metrics::{register_counter, register_gauge};
+use crate::utils::date_time::current_timestamp_millis;
+
+/// The decision of the canary, evaluated by the `CanaryThresholds`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CanaryDecision {
+    /// not enough records compared
+    Pending,
+    /// the divergence ratio is under the threshold, the canary can replace the baseline
+    Promote,
+    /// the divergence ratio is over the threshold, the canary should be stopped
+    Abort,
+}
+
+impl CanaryDecision {
+    fn as_gauge(&self) -> i64 {
+        match self {
+            CanaryDecision::Pending => 0,
+            CanaryDecision::Promote => 1,
+            CanaryDecision::Abort => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CanaryThresholds {
+    /// the min number of the compared records before a decision is made
+    pub min_samples: u64,
+    /// abort if the ratio of the mismatched and missing records is over it
+    pub max_divergence_ratio: f64,
+}
+
+impl Default for CanaryThresholds {
+    fn default() -> Self {
+        CanaryThresholds {
+            min_samples: 10000,
+            max_divergence_ratio: 0.001,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CanaryStats {
+    /// the records with the same key and the same values in both outputs
+    pub matched: u64,
+    /// the records with the same key but different values
+    pub mismatched: u64,
+    /// the records of the baseline without the canary record of the same key in the `match_timeout`
+    pub missing_in_canary: u64,
+    /// the records of the canary without the baseline record of the same key in the `match_timeout`
+    pub missing_in_baseline: u64,
+}
+
+impl CanaryStats {
+    pub fn samples(&self) -> u64 {
+        self.matched + self.mismatched + self.missing_in_canary + self.missing_in_baseline
+    }
+
+    pub fn divergence_ratio(&self) -> f64 {
+        let samples = self.samples();
+        if samples == 0 {
+            return 0f64;
+        }
+
+        (samples - self.matched) as f64 / samples as f64
+    }
+
+    pub fn decide(&self, thresholds: &CanaryThresholds) -> CanaryDecision {
+        if self.samples() < thresholds.min_samples {
+            CanaryDecision::Pending
+        } else if self.divergence_ratio() > thresholds.max_divergence_ratio {
+            CanaryDecision::Abort
+        } else {
+            CanaryDecision::Promote
+        }
+    }
+}
+
+/// invoked when the `CanaryDecision` changes
+pub type CanaryDecisionListener = Arc<dyn Fn(CanaryDecision, &CanaryStats) + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Side {
+    Baseline,
+    Canary,
+}
+
+#[derive(Debug)]
+struct PendingRecord {
+    seq: u64,
+    side: Side,
+    values: Vec<String>,
+    record: Record,
+}
+
+#[derive(Default)]
+struct DiffMetrics {
+    matched: Counter,
+    mismatched: Counter,
+    missing_in_canary: Counter,
+    missing_in_baseline: Counter,
+    /// the divergence ratio in basis points
+    divergence: Gauge,
+    decision: Gauge,
+}
+
+/// Diff the outputs of the baseline and the canary version of a pipeline that dual-run against
+/// the same sources(eg: the kafka sources with separate consumer groups).
+/// The baseline output is the left stream and the canary output is the first connected stream,
+/// the records are paired by the key columns and compared by all other columns.
+///
+/// The divergence is reported by metrics, and the `CanaryDecision` is made by the `CanaryThresholds`.
+/// The divergent records of both sides are emitted for troubleshooting.
+///
+/// The diff is made in each task, so both streams must be partitioned by the key,
+/// or run the function with parallelism 1.
+pub struct CanaryDiffFunction {
+    name: String,
+    key_columns: Vec<usize>,
+    ignore_columns: Vec<usize>,
+    match_timeout: Duration,
+    thresholds: CanaryThresholds,
+    decision_listener: Option<CanaryDecisionListener>,
+
+    schema: Schema,
+    seq: u64,
+    /// the records waiting for the other side, all records of a key are on the same side
+    pending: HashMap<Vec<String>, VecDeque<PendingRecord>>,
+    /// (arrival timestamp, key, seq), order by the arrival
+    expiration: VecDeque<(u64, Vec<String>, u64)>,
+    stats: CanaryStats,
+    decision: CanaryDecision,
+    metrics: DiffMetrics,
+}
+
+impl CanaryDiffFunction {
+    pub fn new(name: &str, key_columns: Vec<usize>) -> Self {
+        CanaryDiffFunction {
+            name: name.to_string(),
+            key_columns,
+            ignore_columns: Vec::new(),
+            match_timeout: Duration::from_secs(60),
+            thresholds: CanaryThresholds::default(),
+            decision_listener: None,
+            schema: Schema::empty(),
+            seq: 0,
+            pending: HashMap::new(),
+            expiration: VecDeque::new(),
+            stats: CanaryStats::default(),
+            decision: CanaryDecision::Pending,
+            metrics: DiffMetrics::default(),
+        }
+    }
+
+    /// the columns not compared, eg: the processing time
+    pub fn ignore_columns(mut self, ignore_columns: Vec<usize>) -> Self {
+        self.ignore_columns = ignore_columns;
+        self
+    }
+
+    /// the max processing time to wait the record of the other side, then the record is missing
+    pub fn match_timeout(mut self, match_timeout: Duration) -> Self {
+        self.match_timeout = match_timeout;
+        self
+    }
+
+    pub fn thresholds(mut self, thresholds: CanaryThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// invoked when the `CanaryDecision` of the task changes, eg: promote or abort the deployment
+    pub fn on_decision<F>(mut self, listener: F) -> Self
+    where
+        F: Fn(CanaryDecision, &CanaryStats) + Send + Sync + 'static,
+    {
+        self.decision_listener = Some(Arc::new(listener));
+        self
+    }
+
+    pub fn stats(&self) -> &CanaryStats {
+        &self.stats
+    }
+
+    /// the key and ignore columns must be in the input schema
+    fn check_columns(&self) -> crate::core::Result<()> {
+        let field_len = self.schema.fields().len();
+        match self
+            .key_columns
+            .iter()
+            .chain(self.ignore_columns.iter())
+            .find(|index| **index >= field_len)
+        {
+            Some(index) => Err(crate::core::Error::from(format!(
+                "canary `{}` column index {} out of the schema with {} fields",
+                self.name, index, field_len
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn diff(&mut self, side: Side, mut record: Record, now: u64) -> Vec<Record> {
+        let mut output = self.expire(now);
+
+        let values = match record_to_strings(&mut record, &self.schema) {
+            Ok(values) => values,
+            Err(e) => {
+                warn!("canary `{}` skip the unreadable record. {}", self.name, e);
+                return output;
+            }
+        };
+        let key: Vec<String> = self
+            .key_columns
+            .iter()
+            .map(|index| values[*index].clone())
+            .collect();
+
+        let queue = self.pending.entry(key.clone()).or_default();
+        match queue.front() {
+            Some(pending_record) if pending_record.side != side => {
+                let pending_record = queue.pop_front().unwrap();
+                if queue.is_empty() {
+                    self.pending.remove(&key);
+                }
+
+                if self.is_match(&pending_record.values, &values) {
+                    self.stats.matched += 1;
+                    self.metrics.matched.fetch_add(1);
+                } else {
+                    self.stats.mismatched += 1;
+                    self.metrics.mismatched.fetch_add(1);
+                    debug!(
+                        "canary mismatched, {:?}: {:?}, {:?}: {:?}",
+                        pending_record.side, pending_record.values, side, values
+                    );
+
+                    output.push(pending_record.record);
+                    output.push(record);
+                }
+            }
+            _ => {
+                self.seq += 1;
+                queue.push_back(PendingRecord {
+                    seq: self.seq,
+                    side,
+                    values,
+                    record,
+                });
+                self.expiration.push_back((now, key, self.seq));
+            }
+        }
+
+        self.update_decision();
+        output
+    }
+
+    fn is_match(&self, baseline: &[String], canary: &[String]) -> bool {
+        baseline
+            .iter()
+            .zip(canary.iter())
+            .enumerate()
+            .all(|(index, (b, c))| self.ignore_columns.contains(&index) || b.eq(c))
+    }
+
+    /// the records waiting over the `match_timeout` are missing in the other side
+    fn expire(&mut self, now: u64) -> Vec<Record> {
+        let timeout = self.match_timeout.as_millis() as u64;
+
+        let mut output = Vec::new();
+        while let Some((ts, _, _)) = self.expiration.front() {
+            if ts + timeout > now {
+                break;
+            }
+
+            let (_, key, seq) = self.expiration.pop_front().unwrap();
+            let queue = match self.pending.get_mut(&key) {
+                Some(queue) => queue,
+                None => continue,
+            };
+            // the record has been matched if it's not the oldest one
+            if queue.front().map(|x| x.seq != seq).unwrap_or(true) {
+                continue;
+            }
+
+            let pending_record = queue.pop_front().unwrap();
+            if queue.is_empty() {
+                self.pending.remove(&key);
+            }
+
+            match pending_record.side {
+                Side::Baseline => {
+                    self.stats.missing_in_canary += 1;
+                    self.metrics.missing_in_canary.fetch_add(1);
+                }
+                Side::Canary => {
+                    self.stats.missing_in_baseline += 1;
+                    self.metrics.missing_in_baseline.fetch_add(1);
+                }
+            }
+            output.push(pending_record.record);
+        }
+
+        output
+    }
+
+    fn update_decision(&mut self) {
+        self.metrics
+            .divergence
+            .store((self.stats.divergence_ratio() * 10000f64) as i64);
+
+        let decision = self.stats.decide(&self.thresholds);
+        if decision == self.decision {
+            return;
+        }
+
+        info!(
+            "canary `{}` decision changed from {:?} to {:?}, {:?}",
+            self.name, self.decision, decision, self.stats
+        );
+        self.decision = decision;
+        self.metrics.decision.store(decision.as_gauge());
+        if let Some(listener) = &self.decision_listener {
+            listener(decision, &self.stats);
+        }
+    }
+}
+
+impl CoProcessFunction for CanaryDiffFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.schema = context.input_schema.first().clone();
+
+        self.check_columns()?;
+
+        let tags = context.task_id.to_tags();
+        let metric_name = |name: &str| format!("Canary_{}_{}", name, self.name);
+        self.metrics = DiffMetrics {
+            matched: register_counter(metric_name("Matched"), tags.clone()),
+            mismatched: register_counter(metric_name("Mismatched"), tags.clone()),
+            missing_in_canary: register_counter(metric_name("MissingInCanary"), tags.clone()),
+            missing_in_baseline: register_counter(metric_name("MissingInBaseline"), tags.clone()),
+            divergence: register_gauge(metric_name("DivergenceBasisPoints"), tags.clone()),
+            decision: register_gauge(metric_name("Decision"), tags),
+        };
+
+        Ok(())
+    }
+
+    fn process_left(&mut self, record: Record) -> Box<dyn Iterator<Item = Record>> {
+        let output = self.diff(Side::Baseline, record, current_timestamp_millis());
+        Box::new(output.into_iter())
+    }
+
+    fn process_right(
+        &mut self,
+        stream_seq: usize,
+        record: Record,
+    ) -> Box<dyn Iterator<Item = Record>> {
+        if stream_seq != 0 {
+            warn!(
+                "canary diff only supports one connected stream, found {}",
+                stream_seq
+            );
+            return Box::new(vec![].into_iter());
+        }
+
+        let output = self.diff(Side::Canary, record, current_timestamp_millis());
+        Box::new(output.into_iter())
+    }
+
+    /// expire the pending records even if no more record arrives on both sides
+    fn on_watermark(&mut self, _watermark: u64) -> Box<dyn Iterator<Item = Record>> {
+        let output = self.expire(current_timestamp_millis());
+        self.update_decision();
+        Box::new(output.into_iter())
+    }
+
+    fn state_entries(&self) -> usize {
+        self.pending.values().map(|queue| queue.len()).sum()
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        info!(
+            "canary `{}` closed with decision {:?}, {:?}",
+            self.name, self.decision, self.stats
+        );
+        Ok(())
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        input_schema
+    }
+}
+
+impl NamedFunction for CanaryDiffFunction {
+    fn name(&self) -> &str {
+        "CanaryDiffFunction"
+    }
+}
+
+impl CheckpointFunction for CanaryDiffFunction {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::element::Record;
+    use crate::functions::canary::{CanaryDecision, CanaryDiffFunction, CanaryThresholds, Side};
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("key", DataType::String),
+            Field::new("value", DataType::Int64),
+        ])
+    }
+
+    fn record(key: &str, value: i64) -> Record {
+        let schema = schema();
+        let mut record = Record::new();
+        let mut writer = record.as_writer(schema.as_type_ids());
+        writer.set_str(key).unwrap();
+        writer.set_i64(value).unwrap();
+        record
+    }
+
+    #[test]
+    pub fn canary_diff_test() {
+        let decision = Arc::new(AtomicI64::new(0));
+        let decision_clone = decision.clone();
+        let mut diff = CanaryDiffFunction::new("test", vec![0])
+            .match_timeout(Duration::from_millis(100))
+            .thresholds(CanaryThresholds {
+                min_samples: 3,
+                max_divergence_ratio: 0.5,
+            })
+            .on_decision(move |d, _stats| decision_clone.store(d.as_gauge(), Ordering::SeqCst));
+        diff.schema = schema();
+
+        assert!(diff.diff(Side::Baseline, record("a", 1), 0).is_empty());
+        assert!(diff.diff(Side::Canary, record("a", 1), 10).is_empty());
+        assert!(diff.diff(Side::Canary, record("b", 2), 20).is_empty());
+        assert_eq!(diff.diff(Side::Baseline, record("b", 3), 30).len(), 2);
+        assert!(diff.diff(Side::Baseline, record("c", 4), 40).is_empty());
+        assert_eq!(decision.load(Ordering::SeqCst), 0);
+
+        // `c` is expired
+        assert_eq!(diff.diff(Side::Canary, record("d", 5), 200).len(), 1);
+        assert_eq!(diff.stats().matched, 1);
+        assert_eq!(diff.stats().mismatched, 1);
+        assert_eq!(diff.stats().missing_in_canary, 1);
+        assert_eq!(
+            decision.load(Ordering::SeqCst),
+            CanaryDecision::Abort.as_gauge()
+        );
+    }
+
+    #[test]
+    pub fn check_columns_test() {
+        let mut diff = CanaryDiffFunction::new("test", vec![0]).ignore_columns(vec![1]);
+        diff.schema = schema();
+        assert!(diff.check_columns().is_ok());
+
+        let mut diff = CanaryDiffFunction::new("test", vec![2]);
+        diff.schema = schema();
+        assert!(diff.check_columns().is_err());
+    }
+}
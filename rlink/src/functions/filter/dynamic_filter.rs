@@ -0,0 +1,396 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, UNIX_EPOCH};
+
+use rand::Rng;
+use serbuffer::types;
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::Schema;
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{CoProcessFunction, Context, FilterFunction, NamedFunction};
+use crate::functions::sink::field_to_string;
+use crate::metrics::metric::{Counter, Gauge};
+use crate::metrics::{register_counter, register_gauge};
+use crate::utils::http::client::get_sync;
+
+pub const DEFAULT_RULES_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The action of the records matched by a `FilterRule`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "param")]
+pub enum RuleAction {
+    /// drop the matched records, eg: the blocklist
+    Drop,
+    /// keep the fraction of the matched records, eg: the sampling override
+    Sample(f64),
+}
+
+/// Match the records whose `field` is one of the `values`, compared by the display string of
+/// the field, eg: `42` of an `Int64` field
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterRule {
+    pub field: String,
+    pub values: Vec<String>,
+    pub action: RuleAction,
+}
+
+/// The rules of the `dynamic_filter`, the first matched rule is applied and the records without
+/// any matched rule are kept. The json format:
+/// `{"rules": [{"field": "user_id", "values": ["42"], "action": {"type": "Drop"}}]}`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterRules {
+    pub rules: Vec<FilterRule>,
+}
+
+impl FilterRules {
+    pub fn parse(json: &str) -> anyhow::Result<Self> {
+        serde_json::from_str(json).map_err(|e| anyhow!("illegal filter rules. {}", e))
+    }
+}
+
+/// Where the `FilterRules` are loaded from, polled by the tasks and hot-reloaded once changed
+#[derive(Clone, Debug)]
+pub enum RulesSource {
+    /// the json file, reloaded when it's modified
+    File(PathBuf),
+    /// the url responding the json, reloaded when the body is changed
+    Http(String),
+}
+
+impl RulesSource {
+    /// the version of the source, the file modified time or the hash of the http body,
+    /// with the rules json
+    fn fetch(&self) -> anyhow::Result<(u64, String)> {
+        match self {
+            RulesSource::File(path) => {
+                let modified = std::fs::metadata(path)?
+                    .modified()?
+                    .duration_since(UNIX_EPOCH)?
+                    .as_millis() as u64;
+                let json = std::fs::read_to_string(path)?;
+                Ok((modified, json))
+            }
+            RulesSource::Http(url) => {
+                let json = get_sync(url.as_str()).map_err(|e| anyhow!(e.to_string()))?;
+                let mut hasher = DefaultHasher::new();
+                json.hash(&mut hasher);
+                Ok((hasher.finish(), json))
+            }
+        }
+    }
+}
+
+struct CompiledRule {
+    field_index: usize,
+    values: HashSet<String>,
+    action: RuleAction,
+}
+
+/// The rules with the fields resolved by the schema, shared by the task and its reloader
+#[derive(Clone)]
+struct DynamicRules {
+    schema: Schema,
+    rules: Arc<RwLock<Vec<CompiledRule>>>,
+    rules_gauge: Gauge,
+    dropped_counter: Counter,
+}
+
+impl DynamicRules {
+    fn new(name: &str, schema: Schema, context: &Context) -> Self {
+        let tags = context.task_id.to_tags();
+        DynamicRules {
+            schema,
+            rules: Arc::new(RwLock::new(Vec::new())),
+            rules_gauge: register_gauge(format!("DynamicFilterRules_{}", name), tags.clone()),
+            dropped_counter: register_counter(format!("DynamicFilterDropped_{}", name), tags),
+        }
+    }
+
+    /// replace the rules, the current rules are kept if any field is not in the schema
+    fn update(&self, filter_rules: FilterRules) -> anyhow::Result<()> {
+        let mut compiled_rules = Vec::with_capacity(filter_rules.rules.len());
+        for rule in filter_rules.rules {
+            let field_index = self
+                .schema
+                .index_of(rule.field.as_str())
+                .ok_or(anyhow!("the rule field `{}` not found", rule.field))?;
+            if let RuleAction::Sample(ratio) = rule.action {
+                if !(0f64..=1f64).contains(&ratio) {
+                    return Err(anyhow!("the sample ratio {} is out of [0, 1]", ratio));
+                }
+            }
+            compiled_rules.push(CompiledRule {
+                field_index,
+                values: rule.values.into_iter().collect(),
+                action: rule.action,
+            });
+        }
+
+        self.rules_gauge.store(compiled_rules.len() as i64);
+        *self.rules.write().unwrap() = compiled_rules;
+        Ok(())
+    }
+
+    fn filter(&self, record: &mut Record) -> bool {
+        let rules = self.rules.read().unwrap();
+        if rules.is_empty() {
+            return true;
+        }
+
+        let reader = record.as_buffer().as_reader(self.schema.as_type_ids());
+        for rule in rules.iter() {
+            let field = self.schema.field(rule.field_index);
+            let value = match field_to_string(&reader, field, rule.field_index) {
+                Ok(value) => value,
+                Err(e) => {
+                    warn!("read the field {} error. {}", rule.field_index, e);
+                    continue;
+                }
+            };
+            if !rule.values.contains(&value) {
+                continue;
+            }
+
+            let keep = match rule.action {
+                RuleAction::Drop => false,
+                RuleAction::Sample(ratio) => rand::thread_rng().gen_bool(ratio),
+            };
+            if !keep {
+                self.dropped_counter.fetch_add(1);
+            }
+            return keep;
+        }
+
+        true
+    }
+}
+
+/// Filter the records by the `FilterRules` of the `RulesSource`, the rules are polled every
+/// `reload_interval` and replaced without redeploying, eg: the on-call blocklists.
+/// The rules failed to load or to resolve are logged and the current rules are kept.
+pub struct DynamicFilterFunction {
+    name: String,
+    rules_source: RulesSource,
+    reload_interval: Duration,
+
+    rules: Option<DynamicRules>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl DynamicFilterFunction {
+    pub fn new(rules_source: RulesSource) -> Self {
+        DynamicFilterFunction {
+            name: "DynamicFilterFunction".to_string(),
+            rules_source,
+            reload_interval: DEFAULT_RULES_RELOAD_INTERVAL,
+            rules: None,
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// the name of the metrics `DynamicFilterRules_{name}` and `DynamicFilterDropped_{name}`
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_reload_interval(mut self, reload_interval: Duration) -> Self {
+        self.reload_interval = reload_interval;
+        self
+    }
+}
+
+impl FilterFunction for DynamicFilterFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        let rules = DynamicRules::new(
+            self.name.as_str(),
+            context.input_schema.first().clone(),
+            context,
+        );
+
+        // the initial rules must be loaded
+        let (mut version, json) = self.rules_source.fetch()?;
+        rules.update(FilterRules::parse(json.as_str())?)?;
+        info!("dynamic filter rules loaded from {:?}", self.rules_source);
+
+        let rules_source = self.rules_source.clone();
+        let reload_interval = self.reload_interval;
+        let stopped = self.stopped.clone();
+        let reloader = rules.clone();
+        crate::utils::thread::spawn(format!("rules-{}", self.name).as_str(), move || {
+            while !stopped.load(Ordering::Relaxed) {
+                std::thread::sleep(reload_interval);
+
+                let reloaded = rules_source.fetch().and_then(|(new_version, json)| {
+                    if new_version == version {
+                        return Ok(());
+                    }
+                    reloader.update(FilterRules::parse(json.as_str())?)?;
+                    version = new_version;
+                    info!("dynamic filter rules reloaded from {:?}", rules_source);
+                    Ok(())
+                });
+                if let Err(e) = reloaded {
+                    warn!("reload the rules from {:?} error. {}", rules_source, e);
+                }
+            }
+        });
+
+        self.rules = Some(rules);
+        Ok(())
+    }
+
+    fn filter(&self, record: &mut Record) -> bool {
+        self.rules.as_ref().unwrap().filter(record)
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        self.stopped.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl NamedFunction for DynamicFilterFunction {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl CheckpointFunction for DynamicFilterFunction {}
+
+/// Filter the left stream by the `FilterRules` of the broadcast rules stream, the records of
+/// the rules stream are a single `String` field of the rules json, and the latest replaces the
+/// current rules, eg:
+/// `data_stream.connect(vec![CoStream::from(rules_stream.flat_map(BroadcastFlagMapFunction::new()))], DynamicFilterCoProcessFunction::new())`
+pub struct DynamicFilterCoProcessFunction {
+    name: String,
+    rules: Option<DynamicRules>,
+}
+
+impl DynamicFilterCoProcessFunction {
+    pub fn new() -> Self {
+        DynamicFilterCoProcessFunction {
+            name: "DynamicFilterCoProcessFunction".to_string(),
+            rules: None,
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+}
+
+impl Default for DynamicFilterCoProcessFunction {
+    fn default() -> Self {
+        DynamicFilterCoProcessFunction::new()
+    }
+}
+
+impl CoProcessFunction for DynamicFilterCoProcessFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.rules = Some(DynamicRules::new(
+            self.name.as_str(),
+            context.input_schema.first().clone(),
+            context,
+        ));
+        Ok(())
+    }
+
+    fn process_left(&mut self, mut record: Record) -> Box<dyn Iterator<Item = Record>> {
+        if self.rules.as_ref().unwrap().filter(&mut record) {
+            Box::new(vec![record].into_iter())
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    fn process_right(
+        &mut self,
+        _stream_seq: usize,
+        mut record: Record,
+    ) -> Box<dyn Iterator<Item = Record>> {
+        let updated = record
+            .as_reader(&[types::STRING])
+            .get_str(0)
+            .map_err(|e| anyhow!("illegal rules record. {:?}", e))
+            .and_then(FilterRules::parse)
+            .and_then(|filter_rules| self.rules.as_ref().unwrap().update(filter_rules));
+        match updated {
+            Ok(()) => info!("dynamic filter rules updated by the broadcast stream"),
+            Err(e) => warn!("update the rules by the broadcast stream error. {}", e),
+        }
+        Box::new(std::iter::empty())
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        input_schema
+    }
+}
+
+impl NamedFunction for DynamicFilterCoProcessFunction {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl CheckpointFunction for DynamicFilterCoProcessFunction {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use serbuffer::types;
+
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::element::Record;
+    use crate::functions::filter::dynamic_filter::{DynamicRules, FilterRules};
+    use crate::metrics::metric::{Counter, Gauge};
+
+    fn record(user_id: i64) -> Record {
+        let mut record = Record::new();
+        record.as_writer(&[types::I64]).set_i64(user_id).unwrap();
+        record
+    }
+
+    #[test]
+    pub fn dynamic_rules_test() {
+        let rules = DynamicRules {
+            schema: Schema::new(vec![Field::new("user_id", DataType::Int64)]),
+            rules: Arc::new(RwLock::new(Vec::new())),
+            rules_gauge: Gauge::default(),
+            dropped_counter: Counter::default(),
+        };
+        assert!(rules.filter(&mut record(42)));
+
+        let filter_rules = FilterRules::parse(
+            r#"{"rules": [
+                {"field": "user_id", "values": ["42"], "action": {"type": "Drop"}},
+                {"field": "user_id", "values": ["7"], "action": {"type": "Sample", "param": 1.0}}
+            ]}"#,
+        )
+        .unwrap();
+        rules.update(filter_rules).unwrap();
+        assert!(!rules.filter(&mut record(42)));
+        assert!(rules.filter(&mut record(7)));
+        assert!(rules.filter(&mut record(1)));
+        assert_eq!(rules.dropped_counter.load(), 1);
+
+        // the rules of an unknown field are rejected, the current rules are kept
+        let unknown = FilterRules::parse(
+            r#"{"rules": [{"field": "uid", "values": ["1"], "action": {"type": "Drop"}}]}"#,
+        )
+        .unwrap();
+        assert!(rules.update(unknown).is_err());
+        assert!(!rules.filter(&mut record(42)));
+    }
+}
@@ -1 +1,7 @@
+pub mod dynamic_filter;
 pub mod range_window_filter;
+
+pub use dynamic_filter::{
+    DynamicFilterCoProcessFunction, DynamicFilterFunction, FilterRule, FilterRules, RuleAction,
+    RulesSource,
+};
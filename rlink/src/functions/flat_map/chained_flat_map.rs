@@ -0,0 +1,163 @@
+use crate::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction};
+use crate::core::runtime::CheckpointId;
+
+/// Two consecutive `FlatMapFunction`s merged by the stream graph optimizer, each output of the
+/// `first` is passed to the `second`.
+/// The checkpoint handle is the json array of both handles, a handle of the `first` saved
+/// before the merging is restored to the `first`.
+pub(crate) struct ChainedFlatMapFunction {
+    name: String,
+    first: Box<dyn FlatMapFunction>,
+    second: Box<dyn FlatMapFunction>,
+}
+
+impl ChainedFlatMapFunction {
+    pub fn new(first: Box<dyn FlatMapFunction>, second: Box<dyn FlatMapFunction>) -> Self {
+        ChainedFlatMapFunction {
+            name: format!("{}->{}", first.name(), second.name()),
+            first,
+            second,
+        }
+    }
+}
+
+impl FlatMapFunction for ChainedFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        let schema = self.first.schema(context.input_schema.clone());
+
+        let mut first_context = context.clone();
+        first_context.output_schema = schema.clone();
+        self.first.open(&first_context)?;
+
+        let mut second_context = context.clone();
+        second_context.input_schema = schema;
+        self.second.open(&second_context)
+    }
+
+    fn flat_map(&mut self, record: Record) -> Box<dyn Iterator<Item = Record>> {
+        let second = &mut self.second;
+        let records: Vec<Record> = self
+            .first
+            .flat_map(record)
+            .flat_map(|record| second.flat_map(record))
+            .collect();
+        Box::new(records.into_iter())
+    }
+
+    fn flat_map_element(&mut self, element: Element) -> Box<dyn Iterator<Item = Element>> {
+        let second = &mut self.second;
+        let elements: Vec<Element> = self
+            .first
+            .flat_map_element(element)
+            .flat_map(|element| second.flat_map_element(element))
+            .collect();
+        Box::new(elements.into_iter())
+    }
+
+    fn observe_element(&mut self, element: &Element) {
+        self.first.observe_element(element);
+        self.second.observe_element(element);
+    }
+
+    /// the records flushed by the `first` are passed to the `second` before flushing it
+    fn flush(&mut self, all: bool) -> Box<dyn Iterator<Item = Record>> {
+        let second = &mut self.second;
+        let mut records: Vec<Record> = self
+            .first
+            .flush(all)
+            .flat_map(|record| second.flat_map(record))
+            .collect();
+        records.extend(second.flush(all));
+        Box::new(records.into_iter())
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        self.first.close()?;
+        self.second.close()
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        self.second.schema(self.first.schema(input_schema))
+    }
+
+    fn preserves_records(&self) -> bool {
+        self.first.preserves_records() && self.second.preserves_records()
+    }
+}
+
+impl NamedFunction for ChainedFlatMapFunction {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+fn chain_handles(handles: Vec<Option<CheckpointHandle>>) -> Option<CheckpointHandle> {
+    if handles.iter().all(|handle| handle.is_none()) {
+        return None;
+    }
+
+    Some(CheckpointHandle {
+        handle: serde_json::to_string(&handles).unwrap(),
+    })
+}
+
+impl CheckpointFunction for ChainedFlatMapFunction {
+    fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        let handles = handle.as_ref().map(|handle| {
+            serde_json::from_str::<Vec<Option<CheckpointHandle>>>(handle.handle.as_str())
+                .unwrap_or_else(|_e| vec![Some(handle.clone())])
+        });
+        let mut handles = handles.unwrap_or_default().into_iter();
+
+        self.first
+            .initialize_state(context, &handles.next().unwrap_or_default());
+        self.second
+            .initialize_state(context, &handles.next().unwrap_or_default());
+    }
+
+    fn snapshot_state(&mut self, context: &FunctionSnapshotContext) -> Option<CheckpointHandle> {
+        let handles = vec![
+            self.first.snapshot_state(context),
+            self.second.snapshot_state(context),
+        ];
+        chain_handles(handles)
+    }
+
+    /// asynchronous if any of the functions is, the other one is snapshotted synchronously
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        let first = self.first.snapshot_state_async(context);
+        let second = self.second.snapshot_state_async(context);
+        if first.is_none() && second.is_none() {
+            return None;
+        }
+
+        let first = first.ok_or_else(|| self.first.snapshot_state(context));
+        let second = second.ok_or_else(|| self.second.snapshot_state(context));
+        Some(Box::new(move || {
+            let handles = vec![
+                match first {
+                    Ok(async_snapshot) => Some(async_snapshot()?),
+                    Err(handle) => handle,
+                },
+                match second {
+                    Ok(async_snapshot) => Some(async_snapshot()?),
+                    Err(handle) => handle,
+                },
+            ];
+            Ok(chain_handles(handles).unwrap_or_default())
+        }))
+    }
+
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        self.first.notify_checkpoint_complete(checkpoint_id);
+        self.second.notify_checkpoint_complete(checkpoint_id);
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::{DataType, Field, Schema};
+use crate::core::dynamic_record::{DynamicRecordBuilder, Value};
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction, OutputFormat};
+use crate::metrics::metric::Counter;
+use crate::metrics::register_counter;
+
+/// the max nesting depth of the pattern references, avoid the endless recursive patterns
+const MAX_PATTERN_DEPTH: usize = 16;
+
+/// the name of the fields of the failure record
+pub const GROK_FAILURE_LINE: &str = "line";
+pub const GROK_FAILURE_ERROR: &str = "error";
+
+/// the builtin patterns, a subset of the Logstash `grok-patterns`
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    ("USERNAME", r"[a-zA-Z0-9._-]+"),
+    ("USER", r"%{USERNAME}"),
+    ("INT", r"(?:[+-]?(?:[0-9]+))"),
+    ("BASE10NUM", r"(?:[+-]?(?:[0-9]+(?:\.[0-9]+)?|\.[0-9]+))"),
+    ("NUMBER", r"(?:%{BASE10NUM})"),
+    ("POSINT", r"\b(?:[1-9][0-9]*)\b"),
+    ("NONNEGINT", r"\b(?:[0-9]+)\b"),
+    ("WORD", r"\b\w+\b"),
+    ("NOTSPACE", r"\S+"),
+    ("SPACE", r"\s*"),
+    ("DATA", r".*?"),
+    ("GREEDYDATA", r".*"),
+    ("QUOTEDSTRING", r#""(?:[^"\\]|\\.)*""#),
+    (
+        "UUID",
+        r"[A-Fa-f0-9]{8}-(?:[A-Fa-f0-9]{4}-){3}[A-Fa-f0-9]{12}",
+    ),
+    (
+        "IPV4",
+        r"(?:(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.){3}(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)",
+    ),
+    ("IP", r"%{IPV4}"),
+    (
+        "HOSTNAME",
+        r"\b(?:[0-9A-Za-z][0-9A-Za-z-]{0,62})(?:\.(?:[0-9A-Za-z][0-9A-Za-z-]{0,62}))*\b",
+    ),
+    ("IPORHOST", r"(?:%{IP}|%{HOSTNAME})"),
+    ("URIPATH", r"(?:/[A-Za-z0-9$.+!*'(){},~:;=@#%&_\-]*)+"),
+    ("URIPARAM", r"\?[A-Za-z0-9$.+!*'|(){},~@#%&/=:;_?\-\[\]<>]*"),
+    ("URIPATHPARAM", r"%{URIPATH}(?:%{URIPARAM})?"),
+    (
+        "LOGLEVEL",
+        r"(?:[Tt]race|TRACE|[Dd]ebug|DEBUG|[Nn]otice|NOTICE|[Ii]nfo|INFO|[Ww]arn(?:ing)?|WARN(?:ING)?|[Ee]rr(?:or)?|ERR(?:OR)?|[Cc]rit(?:ical)?|CRIT(?:ICAL)?|[Ff]atal|FATAL)",
+    ),
+    (
+        "TIMESTAMP_ISO8601",
+        r"\d{4}-\d{2}-\d{2}[T ]\d{2}:?\d{2}(?::?\d{2}(?:[.,]\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?",
+    ),
+    ("HTTPDATE", r"\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4}"),
+];
+
+/// parse the log line by the grok `pattern`, see `GrokParseFlatMapFunction`
+pub fn grok_parse(pattern: &str) -> GrokParseFlatMapFunction {
+    GrokParseFlatMapFunction::new(pattern)
+}
+
+/// A compiled grok pattern.
+///
+/// The pattern is a regex with the references `%{NAME}`, `%{NAME:field}` or `%{NAME:field:type}`,
+/// `NAME` is a builtin or custom pattern, the `field` is captured to a nullable field
+/// of the output schema with the `type`: `int`(Int64), `float`(Float64) or `string`(default).
+#[derive(Debug)]
+pub struct Grok {
+    regex: Regex,
+    /// the capture group name and the output field
+    captures: Vec<(String, Field)>,
+    schema: Schema,
+}
+
+impl Grok {
+    pub fn compile(
+        pattern: &str,
+        custom_patterns: &HashMap<String, String>,
+    ) -> crate::core::Result<Self> {
+        let mut patterns: HashMap<&str, &str> = BUILTIN_PATTERNS.iter().cloned().collect();
+        for (name, definition) in custom_patterns {
+            patterns.insert(name.as_str(), definition.as_str());
+        }
+
+        let mut captures = Vec::new();
+        let expression = Grok::expand(pattern, &patterns, &mut captures, 0)?;
+        let regex = Regex::new(expression.as_str()).map_err(|e| {
+            crate::core::Error::from(format!("invalid grok pattern `{}`, {}", pattern, e))
+        })?;
+
+        let schema = Schema::new(
+            captures
+                .iter()
+                .map(|(_name, field)| field.clone())
+                .collect(),
+        );
+        Ok(Grok {
+            regex,
+            captures,
+            schema,
+        })
+    }
+
+    fn expand(
+        pattern: &str,
+        patterns: &HashMap<&str, &str>,
+        captures: &mut Vec<(String, Field)>,
+        depth: usize,
+    ) -> crate::core::Result<String> {
+        if depth > MAX_PATTERN_DEPTH {
+            return Err(crate::core::Error::from(format!(
+                "grok pattern nested too deep `{}`",
+                pattern
+            )));
+        }
+
+        let mut expression = String::new();
+        let mut rest = pattern;
+        while let Some(begin) = rest.find("%{") {
+            expression.push_str(&rest[..begin]);
+            let end = rest[begin..].find('}').ok_or_else(|| {
+                crate::core::Error::from(format!("unclosed `%{{` in grok pattern `{}`", pattern))
+            })? + begin;
+
+            let reference: Vec<&str> = rest[begin + 2..end].split(':').collect();
+            let definition = patterns.get(reference[0]).ok_or_else(|| {
+                crate::core::Error::from(format!("grok pattern `{}` not found", reference[0]))
+            })?;
+            let sub_expression = Grok::expand(definition, patterns, captures, depth + 1)?;
+
+            match reference.get(1) {
+                Some(field_name) => {
+                    let data_type = match reference.get(2).cloned().unwrap_or("string") {
+                        "int" | "long" => DataType::Int64,
+                        "float" | "double" => DataType::Float64,
+                        "string" => DataType::String,
+                        t => {
+                            return Err(crate::core::Error::from(format!(
+                                "unsupported grok capture type `{}`",
+                                t
+                            )))
+                        }
+                    };
+                    if captures.iter().any(|(_name, f)| f.name().eq(*field_name)) {
+                        return Err(crate::core::Error::from(format!(
+                            "duplicate grok capture `{}`",
+                            field_name
+                        )));
+                    }
+
+                    // the field name may contain the characters not allowed in the group name
+                    let group_name = format!("g{}", captures.len());
+                    expression.push_str(format!("(?P<{}>{})", group_name, sub_expression).as_str());
+                    captures.push((group_name, Field::new_nullable(field_name, data_type)));
+                }
+                None => expression.push_str(format!("(?:{})", sub_expression).as_str()),
+            }
+
+            rest = &rest[end + 1..];
+        }
+        expression.push_str(rest);
+
+        Ok(expression)
+    }
+
+    /// the nullable fields of the named captures
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// parse the `line` to a `Record` of the `schema`
+    pub fn parse(&self, line: &str) -> crate::core::Result<Record> {
+        let captures = self
+            .regex
+            .captures(line)
+            .ok_or_else(|| crate::core::Error::from("grok pattern not matched"))?;
+
+        let mut builder = DynamicRecordBuilder::new(&self.schema);
+        for (group_name, field) in &self.captures {
+            let text = match captures.name(group_name.as_str()) {
+                Some(m) => m.as_str(),
+                None => continue,
+            };
+            let value = match field.data_type() {
+                DataType::Int64 => Value::Int64(text.parse().map_err(|_e| {
+                    format!("capture `{}` value `{}` is not int", field.name(), text)
+                })?),
+                DataType::Float64 => Value::Float64(text.parse().map_err(|_e| {
+                    format!("capture `{}` value `{}` is not float", field.name(), text)
+                })?),
+                _ => Value::String(text.to_string()),
+            };
+            builder.set(field.name(), value)?;
+        }
+
+        builder.build()
+    }
+}
+
+/// Turn the raw log lines of a String field into structured records by a grok pattern,
+/// the output schema is the named captures(see `Grok`).
+/// The unmatched lines are written to the failure sink(side-output) as the records of
+/// `[line: String, error: String]` if `failure_sink` is set, or dropped.
+/// Both are counted by the metric `GrokParse_Failures_{field}`.
+pub struct GrokParseFlatMapFunction {
+    pattern: String,
+    custom_patterns: HashMap<String, String>,
+    field: Option<String>,
+
+    grok: Option<Grok>,
+    input_schema: Schema,
+    field_index: usize,
+    failure_schema: Schema,
+    failure_sink: Option<Box<dyn OutputFormat>>,
+    failure_counter: Counter,
+}
+
+impl GrokParseFlatMapFunction {
+    pub fn new(pattern: &str) -> Self {
+        GrokParseFlatMapFunction {
+            pattern: pattern.to_string(),
+            custom_patterns: HashMap::new(),
+            field: None,
+            grok: None,
+            input_schema: Schema::empty(),
+            field_index: 0,
+            failure_schema: Schema::new(vec![
+                Field::new(GROK_FAILURE_LINE, DataType::String),
+                Field::new(GROK_FAILURE_ERROR, DataType::String),
+            ]),
+            failure_sink: None,
+            failure_counter: Counter::default(),
+        }
+    }
+
+    /// the log line field, the first field of the input record by default
+    pub fn field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    /// define a custom pattern referenced by `%{name}`, it overrides the builtin one
+    pub fn pattern_definition(mut self, name: &str, definition: &str) -> Self {
+        self.custom_patterns
+            .insert(name.to_string(), definition.to_string());
+        self
+    }
+
+    /// the side-output of the unmatched lines
+    pub fn failure_sink<O>(mut self, output_format: O) -> Self
+    where
+        O: OutputFormat + 'static,
+    {
+        self.failure_sink = Some(Box::new(output_format));
+        self
+    }
+
+    fn parse(&self, record: &mut Record) -> Result<Record, (String, crate::core::Error)> {
+        let reader = record.as_reader(self.input_schema.as_type_ids());
+        let line = reader
+            .get_str(self.field_index)
+            .map_err(|e| (String::new(), crate::core::Error::wrap(e)))?;
+
+        self.grok
+            .as_ref()
+            .unwrap()
+            .parse(line)
+            .map_err(|e| (line.to_string(), e))
+    }
+}
+
+impl FlatMapFunction for GrokParseFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.grok = Some(Grok::compile(self.pattern.as_str(), &self.custom_patterns)?);
+
+        self.input_schema = context.input_schema.first().clone();
+        self.field_index = match &self.field {
+            Some(field) => self
+                .input_schema
+                .index_of(field.as_str())
+                .ok_or_else(|| format!("grok field `{}` not found", field))?,
+            None => 0,
+        };
+        let field = self.input_schema.field(self.field_index);
+        if !field.data_type().eq(&DataType::String) {
+            return Err(crate::core::Error::from(format!(
+                "grok field `{}` is not String",
+                field.name()
+            )));
+        }
+
+        self.failure_counter = register_counter(
+            format!("GrokParse_Failures_{}", field.name()),
+            context.task_id.to_tags(),
+        );
+
+        if let Some(failure_sink) = self.failure_sink.as_mut() {
+            let mut failure_context = context.clone();
+            failure_context.input_schema = FnSchema::from(&self.failure_schema);
+            failure_context.output_schema = FnSchema::Empty;
+            failure_sink.open(&failure_context)?;
+        }
+
+        Ok(())
+    }
+
+    fn flat_map(&mut self, mut record: Record) -> Box<dyn Iterator<Item = Record>> {
+        match self.parse(&mut record) {
+            Ok(record) => Box::new(vec![record].into_iter()),
+            Err((line, e)) => {
+                self.failure_counter.fetch_add(1);
+                if let Some(failure_sink) = self.failure_sink.as_mut() {
+                    let mut builder = DynamicRecordBuilder::new(&self.failure_schema);
+                    let failure_record = builder
+                        .set(GROK_FAILURE_LINE, line)
+                        .and_then(|_| builder.set(GROK_FAILURE_ERROR, e.to_string()))
+                        .and_then(|_| builder.build());
+                    match failure_record {
+                        Ok(failure_record) => failure_sink.write_record(failure_record),
+                        Err(e) => error!("build grok failure record error. {}", e),
+                    }
+                }
+                Box::new(std::iter::empty())
+            }
+        }
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        if let Some(failure_sink) = self.failure_sink.as_mut() {
+            failure_sink.close()?;
+        }
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        match Grok::compile(self.pattern.as_str(), &self.custom_patterns) {
+            Ok(grok) => FnSchema::from(grok.schema()),
+            Err(e) => panic!("compile grok pattern error. {}", e),
+        }
+    }
+}
+
+impl NamedFunction for GrokParseFlatMapFunction {
+    fn name(&self) -> &str {
+        "GrokParseFlatMapFunction"
+    }
+}
+
+impl CheckpointFunction for GrokParseFlatMapFunction {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::core::dynamic_record::DynamicRecord;
+    use crate::functions::flat_map::grok_flat_map::Grok;
+
+    #[test]
+    pub fn grok_test() {
+        let mut custom_patterns = HashMap::new();
+        custom_patterns.insert("METHOD".to_string(), "GET|POST".to_string());
+        let grok = Grok::compile(
+            "%{IP:client} \\[%{HTTPDATE}\\] \"%{METHOD:method} %{URIPATHPARAM:request}\" %{INT:status:int} %{NUMBER:cost:float}(?: %{GREEDYDATA:extra})?",
+            &custom_patterns,
+        )
+        .unwrap();
+        let schema = grok.schema().clone();
+        assert_eq!(schema.fields().len(), 6);
+
+        let mut record = grok
+            .parse("10.0.0.1 [10/Oct/2021:13:55:36 +0800] \"GET /index?a=1\" 200 0.25")
+            .unwrap();
+        let mut reader = DynamicRecord::new(&mut record, &schema);
+        assert_eq!(reader.get_str("client").unwrap(), "10.0.0.1");
+        assert_eq!(reader.get_str("request").unwrap(), "/index?a=1");
+        assert_eq!(reader.get_i64("status").unwrap(), 200);
+        assert_eq!(reader.get_f64("cost").unwrap(), 0.25);
+        assert!(reader.is_null("extra").unwrap());
+
+        assert!(grok.parse("not a log line").is_err());
+        assert!(Grok::compile("%{UNKNOWN:x}", &HashMap::new()).is_err());
+        assert!(Grok::compile("%{INT:x:date}", &HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::{DataType, Schema};
+use crate::core::dynamic_record::{DynamicRecord, DynamicRecordBuilder, Value};
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction};
+use crate::metrics::metric::Counter;
+use crate::metrics::register_counter;
+use crate::utils::json_path::JsonPath;
+
+/// extract the fields of the `schema` from the json string field `json_field`,
+/// see `JsonExtractFlatMapFunction`
+pub fn json_extract(
+    json_field: &str,
+    schema: Schema,
+    mapping: Vec<(&str, &str)>,
+) -> JsonExtractFlatMapFunction {
+    JsonExtractFlatMapFunction::new(json_field, schema, mapping)
+}
+
+/// Parse the json string field `json_field` of the input record, and extract the typed fields
+/// of the output `schema` by the json paths of the `mapping`(output field name -> json path).
+/// The output fields not in the `mapping` are extracted by the path `$.{field name}`.
+/// A missing or `null` value is null in the output record, so the field must be nullable.
+/// The records with invalid json or values are dropped and counted by the metric
+/// `JsonExtract_Invalid_{json_field}`.
+pub struct JsonExtractFlatMapFunction {
+    json_field: String,
+    schema: Schema,
+    paths: Vec<(String, String)>,
+
+    input_schema: Schema,
+    json_paths: Vec<JsonPath>,
+    invalid_counter: Counter,
+}
+
+impl JsonExtractFlatMapFunction {
+    pub fn new(json_field: &str, schema: Schema, mapping: Vec<(&str, &str)>) -> Self {
+        let paths = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let path = mapping
+                    .iter()
+                    .find(|(name, _path)| field.name().eq(*name))
+                    .map(|(_name, path)| path.to_string())
+                    .unwrap_or_else(|| format!("$.{}", field.name()));
+                (field.name().to_string(), path)
+            })
+            .collect();
+
+        JsonExtractFlatMapFunction {
+            json_field: json_field.to_string(),
+            schema,
+            paths,
+            input_schema: Schema::empty(),
+            json_paths: Vec::new(),
+            invalid_counter: Counter::default(),
+        }
+    }
+
+    fn extract(&self, record: &mut Record) -> crate::core::Result<Record> {
+        let mut reader = DynamicRecord::new(record, &self.input_schema);
+        let json = match reader.get(self.json_field.as_str())? {
+            Some(Value::String(json)) => serde_json::Value::from_str(json.as_str())
+                .map_err(|e| crate::core::Error::from(format!("invalid json, {}", e)))?,
+            _ => serde_json::Value::Null,
+        };
+
+        let mut builder = DynamicRecordBuilder::new(&self.schema);
+        for (index, json_path) in self.json_paths.iter().enumerate() {
+            let field = self.schema.field(index);
+            let value = match json_path.select(&json) {
+                Some(value) => Value::from_json(field.data_type(), value).map_err(|e| {
+                    crate::core::Error::from(format!("field `{}`, {}", field.name(), e))
+                })?,
+                None => None,
+            };
+
+            match value {
+                Some(value) => builder.set(field.name(), value)?,
+                None => builder.set_null(field.name())?,
+            }
+        }
+
+        builder.build()
+    }
+}
+
+impl FlatMapFunction for JsonExtractFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.input_schema = context.input_schema.first().clone();
+        let json_field = self
+            .input_schema
+            .field_with_name(self.json_field.as_str())
+            .ok_or_else(|| format!("json field `{}` not found", self.json_field))?;
+        if !json_field.data_type().eq(&DataType::String) {
+            return Err(crate::core::Error::from(format!(
+                "json field `{}` is not String",
+                self.json_field
+            )));
+        }
+
+        self.json_paths = self
+            .paths
+            .iter()
+            .map(|(_name, path)| JsonPath::from_str(path.as_str()))
+            .collect::<crate::core::Result<Vec<JsonPath>>>()?;
+
+        self.invalid_counter = register_counter(
+            format!("JsonExtract_Invalid_{}", self.json_field),
+            context.task_id.to_tags(),
+        );
+
+        Ok(())
+    }
+
+    fn flat_map(&mut self, mut record: Record) -> Box<dyn Iterator<Item = Record>> {
+        match self.extract(&mut record) {
+            Ok(record) => Box::new(vec![record].into_iter()),
+            Err(e) => {
+                debug!("drop the invalid record of `{}`. {}", self.json_field, e);
+                self.invalid_counter.fetch_add(1);
+                Box::new(std::iter::empty())
+            }
+        }
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&self.schema)
+    }
+}
+
+impl NamedFunction for JsonExtractFlatMapFunction {
+    fn name(&self) -> &str {
+        "JsonExtractFlatMapFunction"
+    }
+}
+
+impl CheckpointFunction for JsonExtractFlatMapFunction {}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::dynamic_record::{DynamicRecord, DynamicRecordBuilder};
+    use crate::functions::flat_map::json_extract_flat_map::json_extract;
+    use crate::utils::json_path::JsonPath;
+
+    #[test]
+    pub fn json_extract_test() {
+        let input_schema = Schema::new(vec![Field::new("body", DataType::String)]);
+        let schema = Schema::new(vec![
+            Field::new("name", DataType::String),
+            Field::new("age", DataType::Int32),
+            Field::new_nullable("tag", DataType::String),
+        ]);
+
+        let mut extract = json_extract("body", schema.clone(), vec![("age", "$.user.age")]);
+        extract.input_schema = input_schema.clone();
+        extract.json_paths = extract
+            .paths
+            .iter()
+            .map(|(_name, path)| JsonPath::from_str(path).unwrap())
+            .collect();
+
+        let mut builder = DynamicRecordBuilder::new(&input_schema);
+        builder
+            .set("body", r#"{"name": "rlink", "user": {"age": 3}}"#)
+            .unwrap();
+        let mut record = builder.build().unwrap();
+
+        let mut output = extract.extract(&mut record).unwrap();
+        let mut reader = DynamicRecord::new(&mut output, &schema);
+        assert_eq!(reader.get_str("name").unwrap(), "rlink");
+        assert_eq!(reader.get_i32("age").unwrap(), 3);
+        assert!(reader.is_null("tag").unwrap());
+
+        let mut builder = DynamicRecordBuilder::new(&input_schema);
+        builder.set("body", r#"{"name": "rlink"}"#).unwrap();
+        // `age` is not nullable
+        assert!(extract.extract(&mut builder.build().unwrap()).is_err());
+    }
+}
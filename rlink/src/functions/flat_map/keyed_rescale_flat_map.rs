@@ -0,0 +1,70 @@
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, KeySelectorFunction, NamedFunction};
+use crate::utils;
+
+/// rescale the stream to the next operator by the key, see `KeyedRescaleFlatMapFunction`
+pub fn keyed_rescale<K>(key_selector: K) -> KeyedRescaleFlatMapFunction
+where
+    K: KeySelectorFunction + 'static,
+{
+    KeyedRescaleFlatMapFunction::new(key_selector)
+}
+
+/// The key-preserving alternative of the `RoundRobinFlagMapFunction` before a parallelism change,
+/// the records of a key are always sent to the same task of the next operator as the `key_by`
+/// does, so the order of a key is preserved across the rescale. The load is balanced by the keys
+/// instead of the records, a hot key is not spread.
+pub struct KeyedRescaleFlatMapFunction {
+    key_selector: Box<dyn KeySelectorFunction>,
+    child_job_parallelism: u16,
+}
+
+impl KeyedRescaleFlatMapFunction {
+    pub fn new<K>(key_selector: K) -> Self
+    where
+        K: KeySelectorFunction + 'static,
+    {
+        KeyedRescaleFlatMapFunction {
+            key_selector: Box::new(key_selector),
+            child_job_parallelism: 0,
+        }
+    }
+}
+
+impl FlatMapFunction for KeyedRescaleFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.child_job_parallelism = context.children.len() as u16;
+        self.key_selector.open(context)
+    }
+
+    fn flat_map(&mut self, mut record: Record) -> Box<dyn Iterator<Item = Record>> {
+        if self.child_job_parallelism > 0 {
+            let key = self.key_selector.get_key(&mut record);
+            record.partition_num =
+                utils::hash::key_partition(key.values.as_slice(), self.child_job_parallelism);
+        }
+
+        Box::new(vec![record].into_iter())
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        self.key_selector.close()
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        input_schema
+    }
+
+    fn preserves_records(&self) -> bool {
+        true
+    }
+}
+
+impl NamedFunction for KeyedRescaleFlatMapFunction {
+    fn name(&self) -> &str {
+        "KeyedRescaleFlatMapFunction"
+    }
+}
+
+impl CheckpointFunction for KeyedRescaleFlatMapFunction {}
@@ -1,5 +1,30 @@
 pub mod broadcast_flat_map;
+pub(crate) mod chained_flat_map;
+
 pub use broadcast_flat_map::BroadcastFlagMapFunction;
 
 pub mod round_robin_flat_map;
 pub use round_robin_flat_map::RoundRobinFlagMapFunction;
+
+pub mod keyed_rescale_flat_map;
+pub use keyed_rescale_flat_map::{keyed_rescale, KeyedRescaleFlatMapFunction};
+
+pub mod resequence_flat_map;
+pub use resequence_flat_map::{resequence, ResequenceFlatMapFunction};
+
+pub mod tap_flat_map;
+pub use tap_flat_map::TapFlatMapFunction;
+
+pub mod uid_flat_map;
+pub use uid_flat_map::UidFlatMapFunction;
+
+pub mod sample_flat_map;
+pub use sample_flat_map::{
+    reservoir_sample, sample, ReservoirSampleFlatMapFunction, SampleFlatMapFunction,
+};
+
+pub mod json_extract_flat_map;
+pub use json_extract_flat_map::{json_extract, JsonExtractFlatMapFunction};
+
+pub mod grok_flat_map;
+pub use grok_flat_map::{grok_parse, Grok, GrokParseFlatMapFunction};
@@ -0,0 +1,265 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::{DataType, Schema};
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, KeySelectorFunction, NamedFunction};
+use crate::functions::column_locate::{ColumnLocate, ColumnLocateBuilder};
+use crate::metrics::metric::Counter;
+use crate::metrics::register_counter;
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the default max records buffered by a key waiting for a gap
+const DEFAULT_MAX_BUFFERED: usize = 10000;
+
+/// restore the order of the records by the `sequence` column, see `ResequenceFlatMapFunction`
+pub fn resequence<T: ColumnLocateBuilder>(
+    sequence: T,
+    timeout: Duration,
+) -> ResequenceFlatMapFunction {
+    ResequenceFlatMapFunction::new(sequence, timeout)
+}
+
+/// The sequence state of a key
+#[derive(Default)]
+struct KeySequence {
+    /// the next sequence to emit, the first record of the key starts the sequence
+    next: Option<u64>,
+    /// the out of order records waiting for a gap, value: (the arrival timestamp, the record)
+    buffered: BTreeMap<u64, (u64, Record)>,
+}
+
+impl KeySequence {
+    /// emit the buffered records from the `next` sequence until a gap
+    fn drain(&mut self, outputs: &mut Vec<Record>) {
+        while let Some(next) = self.next {
+            match self.buffered.remove(&next) {
+                Some((_arrival_timestamp, record)) => {
+                    outputs.push(record);
+                    self.next = Some(next + 1);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// skip the gap before the first buffered record
+    fn skip_gap(&mut self, outputs: &mut Vec<Record>) -> u64 {
+        let (first, next) = match (self.buffered.keys().next(), self.next) {
+            (Some(first), Some(next)) => (*first, next),
+            _ => return 0,
+        };
+        self.next = Some(first);
+        self.drain(outputs);
+        first - next
+    }
+
+    fn first_arrival_timestamp(&self) -> Option<u64> {
+        self.buffered.values().next().map(|(ts, _record)| *ts)
+    }
+}
+
+/// Restore the order of the records by a sequence number column of the producer after a fan-out
+/// and fan-in, eg: the `RoundRobinFlagMapFunction` before a parallelism change. The records are
+/// resequenced per key if the `key_selector` is set, otherwise per task, so the records of a key
+/// should be sent to the same task, eg: by a `key_by` or a `KeyedRescaleFlatMapFunction`.
+///
+/// The out of order records are buffered until the gap before them arrives, a gap is skipped
+/// if it's not filled in the `timeout` or the key buffers more than `max_buffered` records.
+/// A record with a sequence before the emitted ones is a late or duplicate record and is dropped.
+/// All buffered records are emitted in order before a `Barrier`, a gap spanning a checkpoint is
+/// not waited for.
+pub struct ResequenceFlatMapFunction {
+    sequence_locate: ColumnLocate,
+    sequence_index: usize,
+    signed: bool,
+    schema: Schema,
+    key_selector: Option<Box<dyn KeySelectorFunction>>,
+    timeout: Duration,
+    max_buffered: usize,
+
+    sequences: HashMap<Record, KeySequence>,
+    skipped_counter: Counter,
+    dropped_counter: Counter,
+}
+
+impl ResequenceFlatMapFunction {
+    pub fn new<T: ColumnLocateBuilder>(sequence: T, timeout: Duration) -> Self {
+        ResequenceFlatMapFunction {
+            sequence_locate: sequence.build(),
+            sequence_index: 0,
+            signed: false,
+            schema: Schema::empty(),
+            key_selector: None,
+            timeout,
+            max_buffered: DEFAULT_MAX_BUFFERED,
+            sequences: HashMap::new(),
+            skipped_counter: Counter::default(),
+            dropped_counter: Counter::default(),
+        }
+    }
+
+    /// resequence the records per key instead of per task
+    pub fn key_by<K>(mut self, key_selector: K) -> Self
+    where
+        K: KeySelectorFunction + 'static,
+    {
+        self.key_selector = Some(Box::new(key_selector));
+        self
+    }
+
+    pub fn max_buffered(mut self, max_buffered: usize) -> Self {
+        self.max_buffered = max_buffered.max(1);
+        self
+    }
+
+    fn sequence(&self, record: &mut Record) -> u64 {
+        let reader = record.as_reader(self.schema.as_type_ids());
+        if self.signed {
+            reader.get_i64(self.sequence_index).unwrap().max(0) as u64
+        } else {
+            reader.get_u64(self.sequence_index).unwrap()
+        }
+    }
+
+    fn resequence(&mut self, key: Record, sequence: u64, record: Record, now: u64) -> Vec<Record> {
+        let key_sequence = self.sequences.entry(key).or_default();
+        let next = *key_sequence.next.get_or_insert(sequence);
+        if sequence < next || key_sequence.buffered.contains_key(&sequence) {
+            self.dropped_counter.fetch_add(1);
+            return vec![];
+        }
+
+        let mut outputs = Vec::new();
+        key_sequence.buffered.insert(sequence, (now, record));
+        key_sequence.drain(&mut outputs);
+        if key_sequence.buffered.len() > self.max_buffered {
+            let skipped = key_sequence.skip_gap(&mut outputs);
+            self.skipped_counter.fetch_add(skipped);
+        }
+        outputs
+    }
+
+    fn flush0(&mut self, all: bool, now: u64) -> Vec<Record> {
+        let timeout = self.timeout.as_millis() as u64;
+        let mut outputs = Vec::new();
+        for key_sequence in self.sequences.values_mut() {
+            while let Some(arrival_timestamp) = key_sequence.first_arrival_timestamp() {
+                if !all && arrival_timestamp + timeout > now {
+                    break;
+                }
+                let skipped = key_sequence.skip_gap(&mut outputs);
+                self.skipped_counter.fetch_add(skipped);
+            }
+        }
+        outputs
+    }
+}
+
+impl FlatMapFunction for ResequenceFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.schema = context.input_schema.first().clone();
+        let (index, field) = self.sequence_locate.to_column(&self.schema);
+        self.sequence_index = index;
+        self.signed = match field.data_type() {
+            DataType::UInt64 => false,
+            DataType::Int64 => true,
+            data_type => {
+                return Err(crate::core::Error::from(format!(
+                    "un-support sequence DataType {:?}",
+                    data_type
+                )))
+            }
+        };
+
+        if let Some(key_selector) = self.key_selector.as_mut() {
+            key_selector.open(context)?;
+        }
+
+        let tags = context.task_id.to_tags();
+        self.skipped_counter = register_counter("Resequence_Skipped", tags.clone());
+        self.dropped_counter = register_counter("Resequence_Dropped", tags);
+        Ok(())
+    }
+
+    fn flat_map(&mut self, mut record: Record) -> Box<dyn Iterator<Item = Record>> {
+        let sequence = self.sequence(&mut record);
+        let key = match &self.key_selector {
+            Some(key_selector) => key_selector.get_key(&mut record),
+            None => Record::with_capacity(0),
+        };
+
+        let outputs = self.resequence(key, sequence, record, current_timestamp_millis());
+        Box::new(outputs.into_iter())
+    }
+
+    fn flush(&mut self, all: bool) -> Box<dyn Iterator<Item = Record>> {
+        let outputs = self.flush0(all, current_timestamp_millis());
+        Box::new(outputs.into_iter())
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        match self.key_selector.as_mut() {
+            Some(key_selector) => key_selector.close(),
+            None => Ok(()),
+        }
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        input_schema
+    }
+}
+
+impl NamedFunction for ResequenceFlatMapFunction {
+    fn name(&self) -> &str {
+        "ResequenceFlatMapFunction"
+    }
+}
+
+impl CheckpointFunction for ResequenceFlatMapFunction {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::element::Record;
+    use crate::functions::flat_map::ResequenceFlatMapFunction;
+
+    fn record(sequence: u64) -> Record {
+        let mut record = Record::new();
+        record.timestamp = sequence;
+        record
+    }
+
+    fn sequences(records: Vec<Record>) -> Vec<u64> {
+        records.iter().map(|x| x.timestamp).collect()
+    }
+
+    #[test]
+    pub fn resequence_test() {
+        let mut resequence = ResequenceFlatMapFunction::new(0, Duration::from_millis(100));
+        let mut call = |sequence: u64, now: u64| {
+            let outputs = resequence.resequence(Record::new(), sequence, record(sequence), now);
+            sequences(outputs)
+        };
+
+        assert_eq!(call(1, 0), vec![1]);
+        assert_eq!(call(3, 0), Vec::<u64>::new());
+        assert_eq!(call(4, 0), Vec::<u64>::new());
+        assert_eq!(call(2, 0), vec![2, 3, 4]);
+        // late or duplicate
+        assert_eq!(call(3, 0), Vec::<u64>::new());
+
+        // the gap of 5 is skipped after the timeout
+        assert_eq!(call(6, 0), Vec::<u64>::new());
+        assert!(resequence.flush0(false, 50).is_empty());
+        assert_eq!(sequences(resequence.flush0(false, 100)), vec![6]);
+
+        assert_eq!(
+            resequence.resequence(Record::new(), 9, record(9), 0).len(),
+            0
+        );
+        assert_eq!(sequences(resequence.flush0(true, 0)), vec![9]);
+    }
+}
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction};
+use crate::utils::date_time::current_timestamp_millis;
+
+pub const DEFAULT_SAMPLE_SEED: u64 = 0;
+
+/// keep a `fraction` of the records, see `SampleFlatMapFunction`
+pub fn sample(fraction: f64) -> SampleFlatMapFunction {
+    SampleFlatMapFunction::new(fraction)
+}
+
+/// keep at most `k` uniformly chosen records in every `window`, see `ReservoirSampleFlatMapFunction`
+pub fn reservoir_sample(k: usize, window: Duration) -> ReservoirSampleFlatMapFunction {
+    ReservoirSampleFlatMapFunction::new(k, window)
+}
+
+/// the random generator of the task, the same `seed` and task always produce the same sequence
+fn task_rng(seed: u64, task_number: u16) -> StdRng {
+    StdRng::seed_from_u64(seed.wrapping_add(task_number as u64))
+}
+
+/// Bernoulli sampling, each record is kept with the probability `fraction`.
+/// The random generator is seeded by the `seed` and the task number, so a replay of the same
+/// input produces the same sample.
+pub struct SampleFlatMapFunction {
+    fraction: f64,
+    seed: u64,
+    rng: StdRng,
+}
+
+impl SampleFlatMapFunction {
+    pub fn new(fraction: f64) -> Self {
+        SampleFlatMapFunction {
+            fraction: fraction.clamp(0f64, 1f64),
+            seed: DEFAULT_SAMPLE_SEED,
+            rng: task_rng(DEFAULT_SAMPLE_SEED, 0),
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.rng = task_rng(seed, 0);
+        self
+    }
+}
+
+impl FlatMapFunction for SampleFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.rng = task_rng(self.seed, context.task_id.task_number);
+        Ok(())
+    }
+
+    fn flat_map(&mut self, record: Record) -> Box<dyn Iterator<Item = Record>> {
+        if self.fraction > 0f64 && self.rng.gen_bool(self.fraction) {
+            Box::new(vec![record].into_iter())
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        input_schema
+    }
+}
+
+impl NamedFunction for SampleFlatMapFunction {
+    fn name(&self) -> &str {
+        "SampleFlatMapFunction"
+    }
+}
+
+impl CheckpointFunction for SampleFlatMapFunction {}
+
+/// Reservoir sampling(Algorithm R), keep at most `k` uniformly chosen records of each
+/// processing time `window`. The reservoir of a window is emitted by the first record arrived
+/// after the window ends, the records of the last window are dropped when the task closed.
+/// The random generator is seeded by the `seed` and the task number.
+pub struct ReservoirSampleFlatMapFunction {
+    k: usize,
+    window: u64,
+    seed: u64,
+    rng: StdRng,
+
+    window_end: u64,
+    /// the number of records seen in current window
+    seen: u64,
+    reservoir: Vec<Record>,
+}
+
+impl ReservoirSampleFlatMapFunction {
+    pub fn new(k: usize, window: Duration) -> Self {
+        ReservoirSampleFlatMapFunction {
+            k,
+            window: (window.as_millis() as u64).max(1),
+            seed: DEFAULT_SAMPLE_SEED,
+            rng: task_rng(DEFAULT_SAMPLE_SEED, 0),
+            window_end: 0,
+            seen: 0,
+            reservoir: Vec::with_capacity(k),
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.rng = task_rng(seed, 0);
+        self
+    }
+
+    fn sample(&mut self, record: Record, now: u64) -> Vec<Record> {
+        let mut output = Vec::new();
+        if now >= self.window_end {
+            output = std::mem::replace(&mut self.reservoir, Vec::with_capacity(self.k));
+            self.seen = 0;
+            self.window_end = now - now % self.window + self.window;
+        }
+
+        self.seen += 1;
+        if self.reservoir.len() < self.k {
+            self.reservoir.push(record);
+        } else {
+            let index = self.rng.gen_range(0..self.seen);
+            if index < self.k as u64 {
+                self.reservoir[index as usize] = record;
+            }
+        }
+
+        output
+    }
+}
+
+impl FlatMapFunction for ReservoirSampleFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.rng = task_rng(self.seed, context.task_id.task_number);
+        Ok(())
+    }
+
+    fn flat_map(&mut self, record: Record) -> Box<dyn Iterator<Item = Record>> {
+        let output = self.sample(record, current_timestamp_millis());
+        Box::new(output.into_iter())
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        input_schema
+    }
+}
+
+impl NamedFunction for ReservoirSampleFlatMapFunction {
+    fn name(&self) -> &str {
+        "ReservoirSampleFlatMapFunction"
+    }
+}
+
+impl CheckpointFunction for ReservoirSampleFlatMapFunction {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serbuffer::types;
+
+    use crate::core::element::Record;
+    use crate::core::function::FlatMapFunction;
+    use crate::functions::flat_map::sample_flat_map::{reservoir_sample, sample};
+
+    fn record(value: u64) -> Record {
+        let mut record = Record::new();
+        record.as_writer(&[types::U64]).set_u64(value).unwrap();
+        record
+    }
+
+    #[test]
+    pub fn sample_test() {
+        let sampled = |seed| {
+            let mut sample = sample(0.5).seed(seed);
+            (0..1000)
+                .map(|x| sample.flat_map(record(x)).count())
+                .sum::<usize>()
+        };
+        // deterministic with the same seed
+        assert_eq!(sampled(1), sampled(1));
+        assert!(sampled(1) > 400 && sampled(1) < 600);
+
+        let mut reservoir = reservoir_sample(3, Duration::from_millis(100));
+        for i in 0..50 {
+            assert!(reservoir.sample(record(i), 1010).is_empty());
+        }
+        let output = reservoir.sample(record(50), 1100);
+        assert_eq!(output.len(), 3);
+        for mut record in output {
+            assert!(record.as_reader(&[types::U64]).get_u64(0).unwrap() < 50);
+        }
+        assert_eq!(reservoir.reservoir.len(), 1);
+    }
+}
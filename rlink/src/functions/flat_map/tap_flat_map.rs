@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use rand::Rng;
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::Schema;
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction};
+use crate::functions::sink::record_to_strings;
+use crate::utils::date_time::current_timestamp_millis;
+
+pub const DEFAULT_TAP_SAMPLE_RATIO: f64 = 0.01;
+pub const DEFAULT_TAP_CAPACITY: usize = 100;
+
+lazy_static! {
+    static ref TAPS: dashmap::DashMap<(String, u16), Arc<Mutex<TapBuffer>>> =
+        dashmap::DashMap::new();
+}
+
+/// An element mirrored by the tap
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TapElement {
+    /// the timestamp of mirroring
+    pub timestamp: u64,
+    pub task_number: u16,
+    /// `Record`, `Watermark`, `Barrier` or `StreamStatus`
+    pub element_type: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TapSummary {
+    pub name: String,
+    pub task_number: u16,
+    pub capacity: usize,
+    pub size: usize,
+}
+
+/// Ring buffer of the latest mirrored elements
+#[derive(Debug)]
+pub(crate) struct TapBuffer {
+    capacity: usize,
+    elements: VecDeque<TapElement>,
+}
+
+impl TapBuffer {
+    fn new(capacity: usize) -> Self {
+        TapBuffer {
+            capacity,
+            elements: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn push(&mut self, element: TapElement) {
+        if self.elements.len() == self.capacity {
+            self.elements.pop_front();
+        }
+        self.elements.push_back(element);
+    }
+}
+
+/// all taps in the worker
+pub(crate) fn get_tap_summaries() -> Vec<TapSummary> {
+    let mut summaries: Vec<TapSummary> = TAPS
+        .iter()
+        .map(|entry| {
+            let (name, task_number) = entry.key();
+            let buffer = entry.value().lock().unwrap();
+            TapSummary {
+                name: name.clone(),
+                task_number: *task_number,
+                capacity: buffer.capacity,
+                size: buffer.elements.len(),
+            }
+        })
+        .collect();
+    summaries.sort_by(|x, y| (&x.name, x.task_number).cmp(&(&y.name, y.task_number)));
+    summaries
+}
+
+/// the mirrored elements of all tasks with the tap `name`, order by the timestamp of mirroring
+pub(crate) fn get_tap_elements(name: &str) -> Vec<TapElement> {
+    let mut elements: Vec<TapElement> = TAPS
+        .iter()
+        .filter(|entry| entry.key().0.eq(name))
+        .flat_map(|entry| {
+            let buffer = entry.value().lock().unwrap();
+            buffer.elements.iter().cloned().collect::<Vec<TapElement>>()
+        })
+        .collect();
+    elements.sort_by_key(|x| x.timestamp);
+    elements
+}
+
+/// Mirror a sampled fraction of the passing records, and all watermarks, barriers
+/// and stream status into a ring buffer, which can be queried by the worker's web api
+/// `/api/taps/{name}`. All elements are passed through without any change.
+pub struct TapFlatMapFunction {
+    name: String,
+    sample_ratio: f64,
+    capacity: usize,
+
+    task_number: u16,
+    schema: Schema,
+    buffer: Option<Arc<Mutex<TapBuffer>>>,
+}
+
+impl TapFlatMapFunction {
+    pub fn new(name: &str) -> Self {
+        TapFlatMapFunction {
+            name: name.to_string(),
+            sample_ratio: DEFAULT_TAP_SAMPLE_RATIO,
+            capacity: DEFAULT_TAP_CAPACITY,
+            task_number: 0,
+            schema: Schema::empty(),
+            buffer: None,
+        }
+    }
+
+    /// the fraction of records to mirror, in range `[0, 1]`
+    pub fn sample_ratio(mut self, sample_ratio: f64) -> Self {
+        self.sample_ratio = sample_ratio.clamp(0f64, 1f64);
+        self
+    }
+
+    /// the max number of elements kept in the ring buffer of each task
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    fn mirror(&self, element_type: &str, value: String) {
+        let tap_element = TapElement {
+            timestamp: current_timestamp_millis(),
+            task_number: self.task_number,
+            element_type: element_type.to_string(),
+            value,
+        };
+        self.buffer
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .push(tap_element);
+    }
+}
+
+impl FlatMapFunction for TapFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.task_number = context.task_id.task_number;
+        self.schema = context.input_schema.clone().into();
+
+        let buffer = Arc::new(Mutex::new(TapBuffer::new(self.capacity)));
+        TAPS.insert((self.name.clone(), self.task_number), buffer.clone());
+        self.buffer = Some(buffer);
+
+        Ok(())
+    }
+
+    fn flat_map(&mut self, mut record: Record) -> Box<dyn Iterator<Item = Record>> {
+        if self.sample_ratio > 0f64 && rand::thread_rng().gen_bool(self.sample_ratio) {
+            let value = match record_to_strings(&mut record, &self.schema) {
+                Ok(values) => values.join(", "),
+                Err(e) => format!("the record does not match the schema. {}", e),
+            };
+            self.mirror("Record", value);
+        }
+
+        Box::new(vec![record].into_iter())
+    }
+
+    fn observe_element(&mut self, element: &Element) {
+        match element {
+            Element::Watermark(watermark) => {
+                self.mirror("Watermark", format!("timestamp: {}", watermark.timestamp))
+            }
+            Element::Barrier(barrier) => self.mirror(
+                "Barrier",
+                format!("checkpoint_id: {}", barrier.checkpoint_id.0),
+            ),
+            Element::StreamStatus(stream_status) => self.mirror(
+                "StreamStatus",
+                format!(
+                    "timestamp: {}, end: {}",
+                    stream_status.timestamp, stream_status.end
+                ),
+            ),
+            Element::Record(_) => {}
+        }
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        input_schema
+    }
+}
+
+impl NamedFunction for TapFlatMapFunction {
+    fn name(&self) -> &str {
+        "TapFlatMapFunction"
+    }
+}
+
+impl CheckpointFunction for TapFlatMapFunction {}
+
+#[cfg(test)]
+mod tests {
+    use crate::functions::flat_map::tap_flat_map::{TapBuffer, TapElement};
+
+    #[test]
+    pub fn tap_buffer_test() {
+        let mut buffer = TapBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(TapElement {
+                timestamp: i,
+                task_number: 0,
+                element_type: "Record".to_string(),
+                value: i.to_string(),
+            });
+        }
+
+        let timestamps: Vec<u64> = buffer.elements.iter().map(|x| x.timestamp).collect();
+        assert_eq!(timestamps, vec![2, 3, 4]);
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use serbuffer::types;
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::{DataType, Field, Schema};
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction};
+use crate::utils::generator::{snowflake_generator, SnowflakeGenerator};
+
+/// Append a cluster-wide unique `UInt64` id field `field_name` to the records.
+/// The id is generated by the `SnowflakeGenerator` of the worker, the worker id is the index
+/// of the worker assigned by the coordinator, so no coordination is needed for each record.
+/// The generated time can be extracted by `utils::generator::snowflake_timestamp`.
+pub struct UidFlatMapFunction {
+    field_name: String,
+    name: String,
+    generator: Option<Arc<SnowflakeGenerator>>,
+    /// the number of fields of the input records
+    arity: usize,
+}
+
+impl UidFlatMapFunction {
+    pub fn new(field_name: &str) -> Self {
+        UidFlatMapFunction {
+            field_name: field_name.to_string(),
+            name: format!("Uid({})", field_name),
+            generator: None,
+            arity: 0,
+        }
+    }
+
+    fn assign(&self, mut record: Record) -> Record {
+        let id = self.generator.as_ref().unwrap().next_id();
+        let mut uid = Record::with_capacity(8);
+        uid.as_writer(&[types::U64]).set_u64(id).unwrap();
+        record.extend(uid, self.arity).unwrap();
+        record
+    }
+}
+
+impl FlatMapFunction for UidFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        let input_schema = context.input_schema.first();
+        if input_schema
+            .field_with_name(self.field_name.as_str())
+            .is_some()
+        {
+            return Err(crate::core::Error::from(format!(
+                "the uid field `{}` exists in the input schema",
+                self.field_name
+            )));
+        }
+
+        self.arity = input_schema.fields().len();
+        self.generator = Some(snowflake_generator(context.worker_id)?);
+        info!(
+            "uid `{}` of task {:?} generated by the worker {}",
+            self.field_name, context.task_id, context.worker_id
+        );
+        Ok(())
+    }
+
+    fn flat_map(&mut self, record: Record) -> Box<dyn Iterator<Item = Record>> {
+        Box::new(std::iter::once(self.assign(record)))
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        let mut fields = input_schema.first().fields().clone();
+        fields.push(Field::new(self.field_name.as_str(), DataType::UInt64));
+        FnSchema::from(&Schema::new(fields))
+    }
+}
+
+impl NamedFunction for UidFlatMapFunction {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl CheckpointFunction for UidFlatMapFunction {}
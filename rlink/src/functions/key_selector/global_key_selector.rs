@@ -0,0 +1,41 @@
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::Schema;
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, KeySelectorFunction, NamedFunction};
+
+/// Select the same empty key for all records, so all records are funneled to a single task,
+/// and the empty key adds no column to the output of the reduce.
+#[derive(Debug, Default)]
+pub struct GlobalKeySelector {}
+
+impl GlobalKeySelector {
+    pub fn new() -> Self {
+        GlobalKeySelector {}
+    }
+}
+
+impl KeySelectorFunction for GlobalKeySelector {
+    fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn get_key(&self, _record: &mut Record) -> Record {
+        Record::new()
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn key_schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Single(Schema::empty())
+    }
+}
+
+impl NamedFunction for GlobalKeySelector {
+    fn name(&self) -> &str {
+        "GlobalKeySelector"
+    }
+}
+
+impl CheckpointFunction for GlobalKeySelector {}
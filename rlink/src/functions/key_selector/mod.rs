@@ -1,2 +1,5 @@
+pub mod global_key_selector;
 pub mod schema_key_selector;
+
+pub use global_key_selector::GlobalKeySelector;
 pub use schema_key_selector::SchemaKeySelector;
@@ -0,0 +1,360 @@
+use std::convert::TryInto;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::functions::lookup::LookupDatabase;
+use crate::utils::mmap::Mmap;
+
+const METADATA_MARKER: &[u8] = b"\xAB\xCD\xEFMaxMind.com";
+/// the metadata is in the last 128KiB of the file
+const METADATA_MAX_SIZE: usize = 128 * 1024;
+/// the 16 bytes zero separator between the search tree and the data section
+const DATA_SECTION_SEPARATOR_SIZE: usize = 16;
+
+/// A reader of the MaxMind DB(mmdb) format, eg: GeoLite2-City.mmdb.
+/// The file is memory-mapped and the records are decoded to json on lookup,
+/// see <https://maxmind.github.io/MaxMind-DB/>
+pub struct MmdbReader {
+    buf: Mmap,
+    node_count: usize,
+    record_size: usize,
+    ip_version: u16,
+    /// the offset of the data section
+    data_offset: usize,
+    /// the node of `::/96` in the IPv6 tree, the root of the IPv4 addresses
+    ipv4_start: usize,
+}
+
+impl MmdbReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::core::Result<Self> {
+        let buf = Mmap::open(path).map_err(crate::core::Error::wrap)?;
+        MmdbReader::new(buf)
+    }
+
+    fn new(buf: Mmap) -> crate::core::Result<Self> {
+        let search_begin = buf.len().saturating_sub(METADATA_MAX_SIZE);
+        let metadata_offset = buf[search_begin..]
+            .windows(METADATA_MARKER.len())
+            .rposition(|x| x.eq(METADATA_MARKER))
+            .map(|x| search_begin + x + METADATA_MARKER.len())
+            .ok_or_else(|| crate::core::Error::from("mmdb metadata not found"))?;
+
+        let (metadata, _) = Decoder::new(&buf, metadata_offset).decode(metadata_offset)?;
+        let metadata_u64 = |name: &str| {
+            metadata.get(name).and_then(|x| x.as_u64()).ok_or_else(|| {
+                crate::core::Error::from(format!("mmdb metadata `{}` not found", name))
+            })
+        };
+        let node_count = metadata_u64("node_count")? as usize;
+        let record_size = metadata_u64("record_size")? as usize;
+        let ip_version = metadata_u64("ip_version")? as u16;
+        if record_size != 24 && record_size != 28 && record_size != 32 {
+            return Err(crate::core::Error::from(format!(
+                "unsupported mmdb record size {}",
+                record_size
+            )));
+        }
+
+        let search_tree_size = record_size * 2 / 8 * node_count;
+        let mut reader = MmdbReader {
+            buf,
+            node_count,
+            record_size,
+            ip_version,
+            data_offset: search_tree_size + DATA_SECTION_SEPARATOR_SIZE,
+            ipv4_start: 0,
+        };
+
+        if ip_version == 6 {
+            let mut node = 0;
+            for _ in 0..96 {
+                if node >= node_count {
+                    break;
+                }
+                node = reader.read_record(node, 0)?;
+            }
+            reader.ipv4_start = node;
+        }
+
+        Ok(reader)
+    }
+
+    fn read_record(&self, node: usize, bit: u8) -> crate::core::Result<usize> {
+        let node_size = self.record_size * 2 / 8;
+        let offset = node * node_size;
+        let bytes = self
+            .buf
+            .get(offset..offset + node_size)
+            .ok_or_else(|| crate::core::Error::from("mmdb search tree is corrupted"))?;
+
+        let be = |b: &[u8]| b.iter().fold(0usize, |acc, x| (acc << 8) | *x as usize);
+        let record = match (self.record_size, bit) {
+            (24, 0) => be(&bytes[0..3]),
+            (24, _) => be(&bytes[3..6]),
+            (28, 0) => ((bytes[3] as usize & 0xF0) << 20) | be(&bytes[0..3]),
+            (28, _) => ((bytes[3] as usize & 0x0F) << 24) | be(&bytes[4..7]),
+            (_, 0) => be(&bytes[0..4]),
+            (_, _) => be(&bytes[4..8]),
+        };
+        Ok(record)
+    }
+
+    /// the record of the network containing the `ip`
+    pub fn lookup_ip(&self, ip: IpAddr) -> crate::core::Result<Option<serde_json::Value>> {
+        let (bytes, mut node) = match ip {
+            IpAddr::V4(ip) => (ip.octets().to_vec(), self.ipv4_start),
+            IpAddr::V6(ip) => {
+                if self.ip_version == 4 {
+                    return Err(crate::core::Error::from(
+                        "lookup an IPv6 address in an IPv4-only database",
+                    ));
+                }
+                (ip.octets().to_vec(), 0)
+            }
+        };
+
+        for i in 0..bytes.len() * 8 {
+            if node >= self.node_count {
+                break;
+            }
+            let bit = (bytes[i / 8] >> (7 - i % 8)) & 1;
+            node = self.read_record(node, bit)?;
+        }
+
+        if node == self.node_count {
+            return Ok(None);
+        }
+        if node < self.node_count {
+            return Err(crate::core::Error::from(
+                "invalid mmdb node in the search tree",
+            ));
+        }
+
+        let offset = self.data_offset + node - self.node_count - DATA_SECTION_SEPARATOR_SIZE;
+        let (value, _) = Decoder::new(&self.buf, self.data_offset).decode(offset)?;
+        Ok(Some(value))
+    }
+}
+
+impl LookupDatabase for MmdbReader {
+    fn lookup(&self, key: &str) -> crate::core::Result<Option<serde_json::Value>> {
+        let ip = IpAddr::from_str(key.trim())
+            .map_err(|e| crate::core::Error::from(format!("invalid ip `{}`, {}", key, e)))?;
+        self.lookup_ip(ip)
+    }
+}
+
+/// The decoder of the mmdb data section
+struct Decoder<'a> {
+    buf: &'a [u8],
+    /// the base of the pointers
+    pointer_base: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(buf: &'a [u8], pointer_base: usize) -> Self {
+        Decoder { buf, pointer_base }
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> crate::core::Result<&'a [u8]> {
+        self.buf
+            .get(offset..offset + len)
+            .ok_or_else(|| crate::core::Error::from("mmdb data section is corrupted"))
+    }
+
+    fn uint(&self, offset: usize, len: usize) -> crate::core::Result<u128> {
+        let bytes = self.bytes(offset, len)?;
+        Ok(bytes.iter().fold(0u128, |acc, x| (acc << 8) | *x as u128))
+    }
+
+    /// decode the value at `offset`, return the value and the offset of the next value
+    fn decode(&self, offset: usize) -> crate::core::Result<(serde_json::Value, usize)> {
+        let ctrl = self.bytes(offset, 1)?[0];
+        let mut offset = offset + 1;
+        let mut data_type = ctrl >> 5;
+
+        if data_type == 1 {
+            // pointer, the size bits are part of the pointer value
+            let size = ((ctrl >> 3) & 0x3) as usize;
+            let value = (ctrl & 0x7) as usize;
+            let pointer = match size {
+                0 => (value << 8) | self.uint(offset, 1)? as usize,
+                1 => ((value << 16) | self.uint(offset, 2)? as usize) + 2048,
+                2 => ((value << 24) | self.uint(offset, 3)? as usize) + 526336,
+                _ => self.uint(offset, 4)? as usize,
+            };
+            let (value, _) = self.decode(self.pointer_base + pointer)?;
+            return Ok((value, offset + size + 1));
+        }
+
+        if data_type == 0 {
+            data_type = 7 + self.bytes(offset, 1)?[0];
+            offset += 1;
+        }
+
+        let mut size = (ctrl & 0x1f) as usize;
+        if size >= 29 {
+            let n = size - 28;
+            let extra = self.uint(offset, n)? as usize;
+            offset += n;
+            size = match n {
+                1 => 29 + extra,
+                2 => 285 + extra,
+                _ => 65821 + extra,
+            };
+        }
+
+        let value = match data_type {
+            2 => {
+                let s = std::str::from_utf8(self.bytes(offset, size)?)
+                    .map_err(crate::core::Error::wrap)?;
+                offset += size;
+                serde_json::Value::from(s)
+            }
+            3 => {
+                let v = f64::from_be_bytes(self.bytes(offset, 8)?.try_into().unwrap());
+                offset += 8;
+                serde_json::Value::from(v)
+            }
+            4 => {
+                let v = self.bytes(offset, size)?.to_vec();
+                offset += size;
+                serde_json::Value::from(v)
+            }
+            5 | 6 | 9 => {
+                let v = self.uint(offset, size)? as u64;
+                offset += size;
+                serde_json::Value::from(v)
+            }
+            8 => {
+                let v = self.uint(offset, size)? as u32 as i32;
+                offset += size;
+                serde_json::Value::from(v)
+            }
+            10 => {
+                let v = self.uint(offset, size)?;
+                offset += size;
+                serde_json::Value::from(v.to_string())
+            }
+            7 => {
+                let mut map = serde_json::Map::with_capacity(size);
+                for _ in 0..size {
+                    let (key, next) = self.decode(offset)?;
+                    let (value, next) = self.decode(next)?;
+                    offset = next;
+                    let key = key
+                        .as_str()
+                        .ok_or_else(|| crate::core::Error::from("mmdb map key is not string"))?;
+                    map.insert(key.to_string(), value);
+                }
+                serde_json::Value::Object(map)
+            }
+            11 => {
+                let mut values = Vec::with_capacity(size);
+                for _ in 0..size {
+                    let (value, next) = self.decode(offset)?;
+                    offset = next;
+                    values.push(value);
+                }
+                serde_json::Value::Array(values)
+            }
+            14 => serde_json::Value::from(size != 0),
+            15 => {
+                let v = f32::from_be_bytes(self.bytes(offset, 4)?.try_into().unwrap());
+                offset += 4;
+                serde_json::Value::from(v)
+            }
+            t => {
+                return Err(crate::core::Error::from(format!(
+                    "unsupported mmdb data type {}",
+                    t
+                )))
+            }
+        };
+
+        Ok((value, offset))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Write;
+    use std::net::IpAddr;
+    use std::str::FromStr;
+
+    use crate::functions::lookup::mmdb::{MmdbReader, METADATA_MARKER};
+    use crate::utils::mmap::Mmap;
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut bytes = vec![(2 << 5) | s.len() as u8];
+        bytes.extend_from_slice(s.as_bytes());
+        bytes
+    }
+
+    fn uint16(v: u16) -> Vec<u8> {
+        let mut bytes = vec![(5 << 5) | 2];
+        bytes.extend_from_slice(&v.to_be_bytes());
+        bytes
+    }
+
+    fn uint32(v: u32) -> Vec<u8> {
+        let mut bytes = vec![(6 << 5) | 4];
+        bytes.extend_from_slice(&v.to_be_bytes());
+        bytes
+    }
+
+    fn map(entries: Vec<(&str, Vec<u8>)>) -> Vec<u8> {
+        let mut bytes = vec![(7 << 5) | entries.len() as u8];
+        for (key, value) in entries {
+            bytes.extend(string(key));
+            bytes.extend(value);
+        }
+        bytes
+    }
+
+    /// an IPv4 database with one node, the addresses of `0.0.0.0/1` are found
+    pub(crate) fn write_test_mmdb(path: &std::path::Path) {
+        let mut bytes = Vec::new();
+        // search tree, record_size 32, left: the data at offset 0, right: not found
+        bytes.extend_from_slice(&(1u32 + 16).to_be_bytes());
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(&[0u8; 16]);
+        bytes.extend(map(vec![
+            ("country", map(vec![("iso_code", string("CN"))])),
+            ("asn", uint32(4134)),
+        ]));
+        bytes.extend_from_slice(METADATA_MARKER);
+        bytes.extend(map(vec![
+            ("node_count", uint32(1)),
+            ("record_size", uint16(32)),
+            ("ip_version", uint16(4)),
+        ]));
+
+        std::fs::File::create(path)
+            .unwrap()
+            .write_all(bytes.as_slice())
+            .unwrap();
+    }
+
+    #[test]
+    pub fn mmdb_test() {
+        let path = std::env::temp_dir().join("rlink_mmdb_test.mmdb");
+        write_test_mmdb(&path);
+
+        let reader = MmdbReader::new(Mmap::open(&path).unwrap()).unwrap();
+        let value = reader
+            .lookup_ip(IpAddr::from_str("10.0.0.1").unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(value["country"]["iso_code"], "CN");
+        assert_eq!(value["asn"], 4134);
+
+        assert!(reader
+            .lookup_ip(IpAddr::from_str("192.168.0.1").unwrap())
+            .unwrap()
+            .is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::core::checkpoint::CheckpointFunction;
+use crate::core::data_types::{DataType, Field, Schema};
+use crate::core::dynamic_record::{DynamicRecordBuilder, Value};
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction};
+use crate::metrics::metric::Counter;
+use crate::metrics::register_counter;
+use crate::utils::json_path::JsonPath;
+
+pub mod mmdb;
+pub mod sorted_file;
+
+pub use mmdb::MmdbReader;
+pub use sorted_file::SortedFileReader;
+
+lazy_static! {
+    static ref DATABASES: Mutex<HashMap<LookupSource, Weak<dyn LookupDatabase>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// A read-only lookup database, the row of the key is returned as json
+pub trait LookupDatabase: Send + Sync {
+    fn lookup(&self, key: &str) -> crate::core::Result<Option<serde_json::Value>>;
+}
+
+/// The file of the lookup database
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum LookupSource {
+    /// MaxMind DB file, the key is an IPv4 or IPv6 address
+    Mmdb(String),
+    /// tab-separated text file sorted by the key, see `SortedFileReader`
+    SortedFile(String),
+}
+
+impl LookupSource {
+    /// open the database, or share the opened one of the worker
+    pub(crate) fn open_shared(&self) -> crate::core::Result<Arc<dyn LookupDatabase>> {
+        let mut databases = DATABASES.lock().unwrap();
+        if let Some(database) = databases.get(self).and_then(|x| x.upgrade()) {
+            return Ok(database);
+        }
+
+        info!("open lookup database {:?}", self);
+        let database: Arc<dyn LookupDatabase> = match self {
+            LookupSource::Mmdb(path) => Arc::new(MmdbReader::open(path)?),
+            LookupSource::SortedFile(path) => Arc::new(SortedFileReader::open(path)?),
+        };
+        databases.insert(self.clone(), Arc::downgrade(&database));
+
+        Ok(database)
+    }
+}
+
+/// enrich the records by the lookup database, see `LookupFlatMapFunction`
+pub fn lookup_enrich(source: LookupSource, key_field: &str) -> LookupFlatMapFunction {
+    LookupFlatMapFunction::new(source, key_field)
+}
+
+/// Look up the String field `key_field` in a memory-mapped database opened on `open`,
+/// and append the `column`s selected from the found row by the json paths to the record.
+/// The appended fields are nullable, null if the key is not found or the path is missing.
+/// The database is shared by all tasks of the worker, so the parallel tasks don't duplicate
+/// the memory; it's closed when the last task closed.
+/// The not found and invalid keys are counted by the metric `Lookup_Misses_{key_field}`.
+pub struct LookupFlatMapFunction {
+    source: LookupSource,
+    key_field: String,
+    columns: Vec<(Field, String)>,
+
+    database: Option<Arc<dyn LookupDatabase>>,
+    input_schema: Schema,
+    key_index: usize,
+    columns_schema: Schema,
+    json_paths: Vec<JsonPath>,
+    miss_counter: Counter,
+}
+
+impl LookupFlatMapFunction {
+    pub fn new(source: LookupSource, key_field: &str) -> Self {
+        LookupFlatMapFunction {
+            source,
+            key_field: key_field.to_string(),
+            columns: Vec::new(),
+            database: None,
+            input_schema: Schema::empty(),
+            key_index: 0,
+            columns_schema: Schema::empty(),
+            json_paths: Vec::new(),
+            miss_counter: Counter::default(),
+        }
+    }
+
+    /// append the `field` with the value of the `path` in the found row, eg: `$.country.iso_code`
+    pub fn column(mut self, field: Field, path: &str) -> Self {
+        self.columns
+            .push((field.with_nullable(true), path.to_string()));
+        self
+    }
+
+    fn columns_schema(&self) -> Schema {
+        Schema::new(
+            self.columns
+                .iter()
+                .map(|(field, _path)| field.clone())
+                .collect(),
+        )
+    }
+
+    fn enrich(&self, mut record: Record) -> crate::core::Result<Record> {
+        let row = {
+            let reader = record.as_reader(self.input_schema.as_type_ids());
+            let key = reader
+                .get_str(self.key_index)
+                .map_err(crate::core::Error::wrap)?;
+            self.database
+                .as_ref()
+                .unwrap()
+                .lookup(key)
+                .unwrap_or_else(|e| {
+                    debug!("lookup `{}` error. {}", key, e);
+                    None
+                })
+        };
+        if row.is_none() {
+            self.miss_counter.fetch_add(1);
+        }
+
+        let mut builder = DynamicRecordBuilder::new(&self.columns_schema);
+        if let Some(row) = &row {
+            for (index, json_path) in self.json_paths.iter().enumerate() {
+                let field = self.columns_schema.field(index);
+                let value = json_path
+                    .select(row)
+                    .map(|json| Value::from_json(field.data_type(), json))
+                    .transpose()?
+                    .flatten();
+                if let Some(value) = value {
+                    builder.set(field.name(), value)?;
+                }
+            }
+        }
+        let columns = builder.build()?;
+
+        let arity = self.input_schema.fields().len();
+        record
+            .extend(columns, arity)
+            .map_err(crate::core::Error::wrap)?;
+
+        Ok(record)
+    }
+}
+
+impl FlatMapFunction for LookupFlatMapFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.input_schema = context.input_schema.first().clone();
+        self.key_index = self
+            .input_schema
+            .index_of(self.key_field.as_str())
+            .ok_or_else(|| format!("lookup key field `{}` not found", self.key_field))?;
+        if !self
+            .input_schema
+            .field(self.key_index)
+            .data_type()
+            .eq(&DataType::String)
+        {
+            return Err(crate::core::Error::from(format!(
+                "lookup key field `{}` is not String",
+                self.key_field
+            )));
+        }
+
+        self.columns_schema = self.columns_schema();
+        self.json_paths = self
+            .columns
+            .iter()
+            .map(|(_field, path)| JsonPath::from_str(path.as_str()))
+            .collect::<crate::core::Result<Vec<JsonPath>>>()?;
+
+        self.database = Some(self.source.open_shared()?);
+        self.miss_counter = register_counter(
+            format!("Lookup_Misses_{}", self.key_field),
+            context.task_id.to_tags(),
+        );
+
+        Ok(())
+    }
+
+    fn flat_map(&mut self, record: Record) -> Box<dyn Iterator<Item = Record>> {
+        match self.enrich(record) {
+            Ok(record) => Box::new(vec![record].into_iter()),
+            Err(e) => {
+                error!("lookup enrich error, the record is dropped. {}", e);
+                Box::new(std::iter::empty())
+            }
+        }
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        self.database = None;
+        Ok(())
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        let input_schema: Schema = input_schema.into();
+        let mut fields = input_schema.fields().to_vec();
+        fields.extend(self.columns_schema().fields().iter().cloned());
+        FnSchema::from(&Schema::new(fields))
+    }
+}
+
+impl NamedFunction for LookupFlatMapFunction {
+    fn name(&self) -> &str {
+        "LookupFlatMapFunction"
+    }
+}
+
+impl CheckpointFunction for LookupFlatMapFunction {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::dynamic_record::{DynamicRecord, DynamicRecordBuilder};
+    use crate::core::function::FlatMapFunction;
+    use crate::functions::lookup::mmdb::tests::write_test_mmdb;
+    use crate::functions::lookup::{lookup_enrich, LookupSource};
+
+    #[test]
+    pub fn lookup_test() {
+        let path = std::env::temp_dir().join("rlink_lookup_test.tsv");
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(b"id\tcity\n1001\tBeijing\n1002\tShanghai\n1003\tShenzhen\n")
+            .unwrap();
+        let source = LookupSource::SortedFile(path.to_str().unwrap().to_string());
+        let database = source.open_shared().unwrap();
+        // shared by the tasks
+        assert!(Arc::ptr_eq(&database, &source.open_shared().unwrap()));
+        let row = database.lookup("1002").unwrap().unwrap();
+        assert_eq!(row["city"], "Shanghai");
+        assert!(database.lookup("1004").unwrap().is_none());
+        std::fs::remove_file(path).unwrap();
+
+        let path = std::env::temp_dir().join("rlink_lookup_test.mmdb");
+        write_test_mmdb(&path);
+        let input_schema = Schema::new(vec![Field::new("ip", DataType::String)]);
+        let mut lookup =
+            lookup_enrich(LookupSource::Mmdb(path.to_str().unwrap().to_string()), "ip")
+                .column(
+                    Field::new("country", DataType::String),
+                    "$.country.iso_code",
+                )
+                .column(Field::new("asn", DataType::Int64), "$.asn");
+        lookup.input_schema = input_schema.clone();
+        lookup.columns_schema = lookup.columns_schema();
+        lookup.json_paths = vec![
+            "$.country.iso_code".parse().unwrap(),
+            "$.asn".parse().unwrap(),
+        ];
+        lookup.database = Some(lookup.source.open_shared().unwrap());
+
+        let schema: Schema = lookup.schema((&input_schema).into()).into();
+        for (ip, country) in vec![("10.0.0.1", Some("CN")), ("192.168.0.1", None)] {
+            let mut builder = DynamicRecordBuilder::new(&input_schema);
+            builder.set("ip", ip).unwrap();
+            let mut output = lookup.enrich(builder.build().unwrap()).unwrap();
+            let mut reader = DynamicRecord::new(&mut output, &schema);
+            assert_eq!(reader.get_str("ip").unwrap(), ip);
+            assert_eq!(reader.is_null("country").unwrap(), country.is_none());
+            if let Some(country) = country {
+                assert_eq!(reader.get_str("country").unwrap(), country);
+                assert_eq!(reader.get_i64("asn").unwrap(), 4134);
+            }
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
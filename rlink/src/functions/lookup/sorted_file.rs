@@ -0,0 +1,116 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use crate::functions::lookup::LookupDatabase;
+use crate::utils::mmap::Mmap;
+
+/// A memory-mapped tab-separated text file sorted by the key(the first column) in byte order,
+/// the first line is the header of the column names, eg: `key\tcountry\tcity`.
+/// The row is looked up by the binary search over the line offsets and returned as a json
+/// object of the header names and the string values.
+pub struct SortedFileReader {
+    buf: Mmap,
+    header: Vec<String>,
+    /// the begin offsets of the data lines
+    lines: Vec<usize>,
+}
+
+impl SortedFileReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::core::Result<Self> {
+        let buf = Mmap::open(path).map_err(crate::core::Error::wrap)?;
+
+        let mut offsets = Vec::new();
+        let mut begin = 0;
+        for (i, b) in buf.iter().enumerate() {
+            if *b == b'\n' {
+                if i > begin {
+                    offsets.push(begin);
+                }
+                begin = i + 1;
+            }
+        }
+        if begin < buf.len() {
+            offsets.push(begin);
+        }
+
+        if offsets.is_empty() {
+            return Err(crate::core::Error::from("the sorted file has no header"));
+        }
+        let header_offset = offsets.remove(0);
+        let header = SortedFileReader::columns(&buf, header_offset)
+            .into_iter()
+            .map(|x| String::from_utf8_lossy(x).to_string())
+            .collect();
+
+        let reader = SortedFileReader {
+            buf,
+            header,
+            lines: offsets,
+        };
+
+        for i in 1..reader.lines.len() {
+            if reader.key(i - 1) > reader.key(i) {
+                return Err(crate::core::Error::from(format!(
+                    "the sorted file is not sorted at line {}",
+                    i + 2
+                )));
+            }
+        }
+
+        Ok(reader)
+    }
+
+    fn line(buf: &[u8], offset: usize) -> &[u8] {
+        let line = &buf[offset..];
+        let end = line.iter().position(|x| *x == b'\n').unwrap_or(line.len());
+        let line = &line[..end];
+        line.strip_suffix(b"\r").unwrap_or(line)
+    }
+
+    fn columns(buf: &[u8], offset: usize) -> Vec<&[u8]> {
+        SortedFileReader::line(buf, offset)
+            .split(|x| *x == b'\t')
+            .collect()
+    }
+
+    fn key(&self, index: usize) -> &[u8] {
+        let line = SortedFileReader::line(&self.buf, self.lines[index]);
+        let end = line.iter().position(|x| *x == b'\t').unwrap_or(line.len());
+        &line[..end]
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+impl LookupDatabase for SortedFileReader {
+    fn lookup(&self, key: &str) -> crate::core::Result<Option<serde_json::Value>> {
+        let key = key.as_bytes();
+        let (mut low, mut high) = (0, self.lines.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.key(mid).cmp(key) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => {
+                    let mut row = serde_json::Map::new();
+                    let columns = SortedFileReader::columns(&self.buf, self.lines[mid]);
+                    for (name, value) in self.header.iter().zip(columns) {
+                        row.insert(
+                            name.clone(),
+                            serde_json::Value::from(String::from_utf8_lossy(value).to_string()),
+                        );
+                    }
+                    return Ok(Some(serde_json::Value::Object(row)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
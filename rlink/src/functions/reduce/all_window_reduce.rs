@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, NamedFunction, ReduceFunction};
+use crate::metrics::metric::Gauge;
+use crate::metrics::register_gauge;
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the input rate(records per second) of the single task that warns the bottleneck
+const INPUT_RATE_WARNING_THRESHOLD: u64 = 100_000;
+/// check the input rate every 1024 records
+const RATE_CHECK_MASK: u64 = 1024 - 1;
+const WARNING_INTERVAL_MILLIS: u64 = 60 * 1000;
+
+/// Wrap the `ReduceFunction` of the `window_all`, the reduce runs in a single task,
+/// and report the input rate by the `WindowAll_InputRate` gauge.
+pub struct AllWindowReduceFunction {
+    reduce: Box<dyn ReduceFunction>,
+
+    name: String,
+    input_rate_gauge: Gauge,
+    records: AtomicU64,
+    interval_start: AtomicU64,
+    last_warning: AtomicU64,
+}
+
+impl AllWindowReduceFunction {
+    pub fn new(reduce: Box<dyn ReduceFunction>) -> Self {
+        let name = format!("AllWindow({})", reduce.name());
+        AllWindowReduceFunction {
+            reduce,
+            name,
+            input_rate_gauge: Gauge::default(),
+            records: AtomicU64::new(0),
+            interval_start: AtomicU64::new(0),
+            last_warning: AtomicU64::new(0),
+        }
+    }
+
+    fn check_input_rate(&self) {
+        let records = self.records.fetch_add(1, Ordering::Relaxed) + 1;
+        if records & RATE_CHECK_MASK != 0 {
+            return;
+        }
+
+        let now = current_timestamp_millis();
+        let interval_start = self.interval_start.load(Ordering::Relaxed);
+        let elapsed = now.saturating_sub(interval_start);
+        if elapsed < 1000 {
+            return;
+        }
+
+        let rate = records * 1000 / elapsed;
+        self.input_rate_gauge.store(rate as i64);
+        self.records.store(0, Ordering::Relaxed);
+        self.interval_start.store(now, Ordering::Relaxed);
+
+        if rate > INPUT_RATE_WARNING_THRESHOLD
+            && now - self.last_warning.load(Ordering::Relaxed) > WARNING_INTERVAL_MILLIS
+        {
+            warn!(
+                "the input rate {}/s of `window_all` is over {}/s, the single task may be a bottleneck",
+                rate, INPUT_RATE_WARNING_THRESHOLD
+            );
+            self.last_warning.store(now, Ordering::Relaxed);
+        }
+    }
+}
+
+impl ReduceFunction for AllWindowReduceFunction {
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.input_rate_gauge = register_gauge("WindowAll_InputRate", context.task_id.to_tags());
+        self.interval_start
+            .store(current_timestamp_millis(), Ordering::Relaxed);
+
+        self.reduce.open(context)
+    }
+
+    fn reduce(&self, value: Option<&mut Record>, record: &mut Record) -> Record {
+        self.check_input_rate();
+        self.reduce.reduce(value, record)
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        self.reduce.close()
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        self.reduce.schema(input_schema)
+    }
+
+    /// all records are reduced in a single task
+    fn parallelism(&self) -> u16 {
+        1
+    }
+
+    fn combinable(&self) -> bool {
+        self.reduce.combinable()
+    }
+
+    fn combine(&self, value: Option<&mut Record>, partial: &mut Record) -> Record {
+        self.reduce.combine(value, partial)
+    }
+}
+
+impl NamedFunction for AllWindowReduceFunction {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
@@ -1,2 +1,5 @@
+pub mod all_window_reduce;
 pub mod schema_reduce;
+
+pub use all_window_reduce::AllWindowReduceFunction;
 pub use schema_reduce::*;
@@ -6,7 +6,7 @@ use crate::core::data_types::{DataType, Field, Schema};
 use crate::core::element::{BufferMutReader, BufferReader, BufferWriter, FnSchema, Record};
 use crate::core::function::{Context, NamedFunction, ReduceFunction};
 use crate::functions::column_locate::{ColumnLocate, ColumnLocateBuilder};
-use crate::functions::percentile::{get_percentile_capacity, PercentileWriter};
+use crate::functions::percentile::{get_percentile_capacity, PercentileReader, PercentileWriter};
 
 pub fn count() -> AggregationDescriptor {
     AggregationDescriptor::Count
@@ -75,6 +75,15 @@ pub trait Aggregation: Debug {
         value_index: usize,
         record_reader: &mut BufferReader,
     );
+    /// combine the partial aggregation of the `value_index` field in the `partial_reader`,
+    /// see `ReduceFunction::combine`
+    fn combine(
+        &self,
+        writer: &mut BufferWriter,
+        value_reader: Option<&mut BufferMutReader>,
+        value_index: usize,
+        partial_reader: &mut BufferReader,
+    );
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -118,6 +127,21 @@ impl Aggregation for CountAggregation {
         };
         writer.set_u64(agg_value).unwrap();
     }
+
+    fn combine(
+        &self,
+        writer: &mut BufferWriter,
+        value_reader: Option<&mut BufferMutReader>,
+        value_index: usize,
+        partial_reader: &mut BufferReader,
+    ) {
+        let partial_value = partial_reader.get_u64(value_index).unwrap();
+        let agg_value = match value_reader {
+            Some(value_reader) => value_reader.get_u64(value_index).unwrap() + partial_value,
+            None => partial_value,
+        };
+        writer.set_u64(agg_value).unwrap();
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -153,7 +177,8 @@ pub fn create_basic_agg(
             let basic_agg = BasicAggregation::<u32>::new(column_index, agg_type, input_field);
             Box::new(basic_agg)
         }
-        DataType::Int64 => {
+        DataType::Int64 | DataType::Decimal(_, _) | DataType::TimestampTz => {
+            // the unscaled value of the decimal and the millis of the timestamp are `i64`
             let basic_agg = BasicAggregation::<i64>::new(column_index, agg_type, input_field);
             Box::new(basic_agg)
         }
@@ -204,7 +229,8 @@ impl<T: ValueAgg> BasicAggregation<T> {
         let output_field = Field::new(
             format!("{}({})", agg_type, input_field.name()).as_str(),
             input_field.data_type().clone(),
-        );
+        )
+        .with_nullable(input_field.is_nullable());
 
         BasicAggregation {
             column_index,
@@ -214,6 +240,26 @@ impl<T: ValueAgg> BasicAggregation<T> {
             value_agg: T::default(),
         }
     }
+
+    fn aggregate(&self, basic_value: T, value: T) -> T {
+        match self.agg_type {
+            BasicAggType::Sum => basic_value + value,
+            BasicAggType::Max => {
+                if basic_value > value {
+                    basic_value
+                } else {
+                    value
+                }
+            }
+            BasicAggType::Min => {
+                if basic_value > value {
+                    value
+                } else {
+                    basic_value
+                }
+            }
+        }
+    }
 }
 
 impl<T: ValueAgg> Aggregation for BasicAggregation<T> {
@@ -237,28 +283,30 @@ impl<T: ValueAgg> Aggregation for BasicAggregation<T> {
         let agg_value = match value_reader {
             Some(value_reader) => {
                 let basic_value = self.value_agg.read_value(value_reader, value_index);
-                match self.agg_type {
-                    BasicAggType::Sum => basic_value + record_value,
-                    BasicAggType::Max => {
-                        if basic_value > record_value {
-                            basic_value
-                        } else {
-                            record_value
-                        }
-                    }
-                    BasicAggType::Min => {
-                        if basic_value > record_value {
-                            record_value
-                        } else {
-                            basic_value
-                        }
-                    }
-                }
+                self.aggregate(basic_value, record_value)
             }
             None => record_value,
         };
         self.value_agg.write_record(writer, agg_value)
     }
+
+    fn combine(
+        &self,
+        writer: &mut BufferWriter,
+        value_reader: Option<&mut BufferMutReader>,
+        value_index: usize,
+        partial_reader: &mut BufferReader,
+    ) {
+        let partial_value = self.value_agg.read_record(partial_reader, value_index);
+        let agg_value = match value_reader {
+            Some(value_reader) => {
+                let basic_value = self.value_agg.read_value(value_reader, value_index);
+                self.aggregate(basic_value, partial_value)
+            }
+            None => partial_value,
+        };
+        self.value_agg.write_record(writer, agg_value)
+    }
 }
 
 pub trait ValueAgg: Add<Output = Self> + PartialOrd + Default + Debug {
@@ -478,6 +526,27 @@ impl Aggregation for PctAggregation {
             }
         }
     }
+
+    fn combine(
+        &self,
+        writer: &mut BufferWriter,
+        value_reader: Option<&mut BufferMutReader>,
+        value_index: usize,
+        partial_reader: &mut BufferReader,
+    ) {
+        let partial_value = partial_reader.get_binary(value_index).unwrap();
+        match value_reader {
+            Some(value_reader) => {
+                let stat_value = value_reader.get_binary_mut(value_index).unwrap();
+
+                let mut percentile = PercentileWriter::new(self.scale, stat_value);
+                percentile.merge(&PercentileReader::new(self.scale, partial_value));
+
+                writer.set_binary(stat_value).unwrap();
+            }
+            None => writer.set_binary(partial_value).unwrap(),
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -584,6 +653,43 @@ impl ReduceFunction for SchemaReduceFunction {
     fn parallelism(&self) -> u16 {
         self.parallelism
     }
+
+    fn combinable(&self) -> bool {
+        true
+    }
+
+    fn combine(&self, value: Option<&mut Record>, partial: &mut Record) -> Record {
+        let mut record_rt = Record::with_capacity(self.val_len);
+        let mut writer = record_rt.as_writer(self.val_schema.as_type_ids());
+
+        let mut partial_reader = partial.as_reader(self.val_schema.as_type_ids());
+
+        match value {
+            Some(state_value) => {
+                let mut stat_reader = state_value.as_reader_mut(self.val_schema.as_type_ids());
+
+                for index in 0..self.agg_operators.len() {
+                    self.agg_operators[index].combine(
+                        writer.borrow_mut(),
+                        Some(stat_reader.borrow_mut()),
+                        index,
+                        partial_reader.borrow_mut(),
+                    )
+                }
+            }
+            None => {
+                for index in 0..self.agg_operators.len() {
+                    self.agg_operators[index].combine(
+                        writer.borrow_mut(),
+                        None,
+                        index,
+                        partial_reader.borrow_mut(),
+                    )
+                }
+            }
+        }
+        record_rt
+    }
 }
 
 impl NamedFunction for SchemaReduceFunction {
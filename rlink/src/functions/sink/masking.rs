@@ -0,0 +1,249 @@
+use crate::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
+use crate::core::data_types::Schema;
+use crate::core::dynamic_record::{DynamicRecord, DynamicRecordBuilder};
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{Context, NamedFunction, OutputFormat};
+use crate::core::masking::{MaskingAction, MaskingPolicy};
+use crate::core::properties::SystemProperties;
+use crate::core::runtime::CheckpointId;
+use crate::metrics::metric::Counter;
+use crate::metrics::register_counter;
+
+/// mask the records by the policy `policy_name` of the application properties
+/// before writing to the `output_format`, see `MaskingOutputFormat`
+pub fn masking_sink<O>(policy_name: &str, output_format: O) -> MaskingOutputFormat<O>
+where
+    O: OutputFormat,
+{
+    MaskingOutputFormat::new(policy_name, output_format)
+}
+
+/// Apply the `MaskingPolicy` to the records before they're written to the wrapped `OutputFormat`.
+/// The policy is loaded from the application properties on `open`, the sink fails to open if the
+/// policy is missing or doesn't match the input schema, so the unmasked data is never written.
+/// The record that can't be masked is dropped. The applied policy is logged for the audit and
+/// the masked records are counted by the metric `Masking_Records_{policy_name}`.
+pub struct MaskingOutputFormat<O>
+where
+    O: OutputFormat,
+{
+    policy_name: String,
+    policy: Option<MaskingPolicy>,
+    output_format: O,
+
+    schema: Schema,
+    actions: Vec<Option<MaskingAction>>,
+    masked_counter: Counter,
+}
+
+impl<O> MaskingOutputFormat<O>
+where
+    O: OutputFormat,
+{
+    pub fn new(policy_name: &str, output_format: O) -> Self {
+        MaskingOutputFormat {
+            policy_name: policy_name.to_string(),
+            policy: None,
+            output_format,
+            schema: Schema::empty(),
+            actions: Vec::new(),
+            masked_counter: Counter::default(),
+        }
+    }
+
+    /// use the `policy` instead of the one in the application properties
+    pub fn with_policy(mut self, policy: MaskingPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    fn mask(&self, mut record: Record) -> crate::core::Result<Record> {
+        let masked = {
+            let mut reader = DynamicRecord::new(&mut record, &self.schema);
+            let mut builder = DynamicRecordBuilder::new(&self.schema);
+            for (field, action) in self.schema.fields().iter().zip(self.actions.iter()) {
+                let value = reader.get(field.name())?;
+                let value = match action {
+                    Some(action) => action.apply(field.data_type(), value),
+                    None => value,
+                };
+                match value {
+                    Some(value) => builder.set(field.name(), value)?,
+                    None => builder.set_null(field.name())?,
+                }
+            }
+            builder.build()?
+        };
+
+        record.values = masked.values;
+        record.nulls = masked.nulls;
+        Ok(record)
+    }
+}
+
+impl<O> OutputFormat for MaskingOutputFormat<O>
+where
+    O: OutputFormat,
+{
+    fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        let policy = match &self.policy {
+            Some(policy) => policy.clone(),
+            None => context
+                .application_properties
+                .get_masking_policy(self.policy_name.as_str())
+                .map_err(|e| {
+                    format!("the masking policy `{}` not found. {}", self.policy_name, e)
+                })?,
+        };
+
+        self.schema = context.input_schema.clone().into();
+        self.actions = policy.resolve(&self.schema)?;
+        info!(
+            "apply the masking policy `{}` to the sink `{}` of task {:?}: [{}]",
+            self.policy_name,
+            self.output_format.name(),
+            context.task_id,
+            policy.describe()
+        );
+
+        self.masked_counter = register_counter(
+            format!("Masking_Records_{}", self.policy_name),
+            context.task_id.to_tags(),
+        );
+
+        self.output_format.open(context)
+    }
+
+    fn write_record(&mut self, record: Record) {
+        match self.mask(record) {
+            Ok(record) => {
+                self.masked_counter.fetch_add(1);
+                self.output_format.write_record(record);
+            }
+            Err(e) => error!("mask record error, the record is dropped. {}", e),
+        }
+    }
+
+    fn observe_element(&mut self, element: &Element) {
+        self.output_format.observe_element(element)
+    }
+
+    fn write_element(&mut self, element: Element) {
+        if element.is_record() {
+            self.write_record(element.into_record());
+        } else {
+            self.output_format.write_element(element);
+        }
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        self.output_format.close()
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        self.output_format.schema(input_schema)
+    }
+}
+
+impl<O> NamedFunction for MaskingOutputFormat<O>
+where
+    O: OutputFormat,
+{
+    fn name(&self) -> &str {
+        self.output_format.name()
+    }
+}
+
+impl<O> CheckpointFunction for MaskingOutputFormat<O>
+where
+    O: OutputFormat,
+{
+    fn consult_version(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) -> CheckpointId {
+        self.output_format.consult_version(context, handle)
+    }
+
+    fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        self.output_format.initialize_state(context, handle)
+    }
+
+    fn snapshot_state(&mut self, context: &FunctionSnapshotContext) -> Option<CheckpointHandle> {
+        self.output_format.snapshot_state(context)
+    }
+
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        self.output_format.snapshot_state_async(context)
+    }
+
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        self.output_format.notify_checkpoint_complete(checkpoint_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::dynamic_record::{DynamicRecord, DynamicRecordBuilder};
+    use crate::core::masking::MaskingPolicy;
+    use crate::core::properties::{Properties, SystemProperties};
+    use crate::functions::sink::{masking_sink, print_sink};
+
+    #[test]
+    pub fn masking_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("email", DataType::String),
+            Field::new("phone", DataType::String),
+            Field::new("card", DataType::String),
+            Field::new_nullable("address", DataType::String),
+        ]);
+
+        let policy = MaskingPolicy::new()
+            .hash("email", "s1")
+            .redact("phone")
+            .partial("card", 0, 4)
+            .nullify("address");
+        let mut properties = Properties::new();
+        properties.set_masking_policy("pii", &policy);
+        let policy = properties.get_masking_policy("pii").unwrap();
+        assert_eq!(
+            policy.describe(),
+            "email:hash, phone:redact, card:partial, address:nullify"
+        );
+
+        // `id` is not nullable
+        assert!(policy.clone().nullify("id").resolve(&schema).is_err());
+        assert!(policy.clone().hash("id", "s1").resolve(&schema).is_err());
+        assert!(policy.clone().redact("email").resolve(&schema).is_err());
+
+        let mut sink = masking_sink("pii", print_sink());
+        sink.schema = schema.clone();
+        sink.actions = policy.resolve(&schema).unwrap();
+
+        let mut builder = DynamicRecordBuilder::new(&schema);
+        builder.set("id", 1i64).unwrap();
+        builder.set("email", "rlink@example.com").unwrap();
+        builder.set("phone", "13800138000").unwrap();
+        builder.set("card", "6222021234567890").unwrap();
+        builder.set("address", "Beijing").unwrap();
+        let mut record = sink.mask(builder.build().unwrap()).unwrap();
+
+        let mut reader = DynamicRecord::new(&mut record, &schema);
+        assert_eq!(reader.get_i64("id").unwrap(), 1);
+        let email = reader.get_str("email").unwrap();
+        assert_eq!(email.len(), 64);
+        assert!(!email.contains("rlink"));
+        assert_eq!(reader.get_str("phone").unwrap(), "***");
+        assert_eq!(reader.get_str("card").unwrap(), "************7890");
+        assert!(reader.is_null("address").unwrap());
+    }
+}
@@ -1,2 +1,4 @@
+pub mod masking;
 pub mod print;
+pub use masking::*;
 pub use print::*;
@@ -1,12 +1,15 @@
 use std::time::Duration;
 
 use crate::core::checkpoint::CheckpointFunction;
-use crate::core::data_types::{DataType, Schema};
+use serbuffer::BufferReader;
+
+use crate::core::data_types::{DataType, Decimal, Field, Schema};
+use crate::core::dynamic_record::Value;
 use crate::core::element::{FnSchema, Record};
 use crate::core::function::{Context, NamedFunction, OutputFormat};
 use crate::core::runtime::TaskId;
 use crate::core::window::TWindow;
-use crate::utils::date_time::{current_timestamp_millis, fmt_date_time};
+use crate::utils::date_time::{current_timestamp_millis, fmt_date_time, fmt_rfc3339_millis};
 
 pub fn print_sink() -> PrintOutputFormat {
     PrintOutputFormat::new()
@@ -48,31 +51,16 @@ impl OutputFormat for PrintOutputFormat {
     }
 
     fn write_record(&mut self, mut record: Record) {
-        let reader = record.as_buffer().as_reader(self.schema.as_type_ids());
-        let mut field_str_vec = Vec::new();
-        for i in 0..self.schema.fields().len() {
-            let field = self.schema.field(i);
-            let field_str = match field.data_type() {
-                DataType::Boolean => reader.get_bool(i).unwrap().to_string(),
-                DataType::Int8 => reader.get_i8(i).unwrap().to_string(),
-                DataType::UInt8 => reader.get_u8(i).unwrap().to_string(),
-                DataType::Int16 => reader.get_i16(i).unwrap().to_string(),
-                DataType::UInt16 => reader.get_i16(i).unwrap().to_string(),
-                DataType::Int32 => reader.get_i32(i).unwrap().to_string(),
-                DataType::UInt32 => reader.get_u32(i).unwrap().to_string(),
-                DataType::Int64 => reader.get_i64(i).unwrap().to_string(),
-                DataType::UInt64 => reader.get_u64(i).unwrap().to_string(),
-                DataType::Float32 => reader.get_f32(i).unwrap().to_string(),
-                DataType::Float64 => reader.get_f64(i).unwrap().to_string(),
-                DataType::Binary => match reader.get_str(i) {
-                    Ok(s) => s.to_owned(),
-                    Err(_e) => format!("{:?}", reader.get_binary(i).unwrap()),
-                },
-                DataType::String => reader.get_str(i).unwrap().to_string(),
-            };
-
-            field_str_vec.push(format!("{}", field_str));
-        }
+        let field_str_vec = match record_to_strings(&mut record, &self.schema) {
+            Ok(field_str_vec) => field_str_vec,
+            Err(e) => {
+                warn!(
+                    "print the record error, the record does not match the schema. {}",
+                    e
+                );
+                return;
+            }
+        };
 
         let window_str = record
             .trigger_window()
@@ -110,6 +98,53 @@ impl OutputFormat for PrintOutputFormat {
     }
 }
 
+/// format each field of the record to string by the schema
+pub(crate) fn record_to_strings(
+    record: &mut Record,
+    schema: &Schema,
+) -> Result<Vec<String>, std::io::Error> {
+    let reader = record.as_buffer().as_reader(schema.as_type_ids());
+    (0..schema.fields().len())
+        .map(|i| field_to_string(&reader, schema.field(i), i))
+        .collect()
+}
+
+/// the display string of the `i`th field
+pub(crate) fn field_to_string(
+    reader: &BufferReader,
+    field: &Field,
+    i: usize,
+) -> Result<String, std::io::Error> {
+    let value = match field.data_type() {
+        DataType::Boolean => reader.get_bool(i)?.to_string(),
+        DataType::Int8 => reader.get_i8(i)?.to_string(),
+        DataType::UInt8 => reader.get_u8(i)?.to_string(),
+        DataType::Int16 => reader.get_i16(i)?.to_string(),
+        DataType::UInt16 => reader.get_u16(i)?.to_string(),
+        DataType::Int32 => reader.get_i32(i)?.to_string(),
+        DataType::UInt32 => reader.get_u32(i)?.to_string(),
+        DataType::Int64 => reader.get_i64(i)?.to_string(),
+        DataType::UInt64 => reader.get_u64(i)?.to_string(),
+        DataType::Float32 => reader.get_f32(i)?.to_string(),
+        DataType::Float64 => reader.get_f64(i)?.to_string(),
+        DataType::Binary => match reader.get_str(i) {
+            Ok(s) => s.to_owned(),
+            Err(_e) => format!("{:?}", reader.get_binary(i)?),
+        },
+        DataType::String => reader.get_str(i)?.to_string(),
+        DataType::Decimal(_, scale) => Decimal::new(reader.get_i64(i)?, *scale).to_string(),
+        DataType::TimestampTz => fmt_rfc3339_millis(reader.get_i64(i)?),
+        DataType::Struct(_) | DataType::List(_) | DataType::Map(_, _) => {
+            let mut bytes = reader.get_binary(i)?;
+            match Value::decode(field.data_type(), &mut bytes) {
+                Ok(value) => value.to_json(field.data_type()).to_string(),
+                Err(_e) => format!("{:?}", reader.get_binary(i)?),
+            }
+        }
+    };
+    Ok(value)
+}
+
 impl NamedFunction for PrintOutputFormat {
     fn name(&self) -> &str {
         "PrintOutputFormat"
@@ -117,3 +152,27 @@ impl NamedFunction for PrintOutputFormat {
 }
 
 impl CheckpointFunction for PrintOutputFormat {}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::element::Record;
+    use crate::functions::sink::print::record_to_strings;
+
+    #[test]
+    pub fn record_to_strings_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::UInt16),
+            Field::new("name", DataType::String),
+        ]);
+
+        let mut record = Record::new();
+        let mut writer = record.as_writer(schema.as_type_ids());
+        writer.set_u16(40000).unwrap();
+        writer.set_str("a").unwrap();
+        assert_eq!(
+            record_to_strings(&mut record, &schema).unwrap(),
+            vec!["40000".to_string(), "a".to_string()]
+        );
+    }
+}
@@ -0,0 +1,497 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serbuffer::types;
+
+use crate::core::checkpoint::{
+    AsyncSnapshot, CheckpointFunction, CheckpointHandle, FunctionSnapshotContext,
+};
+use crate::core::data_types::{DataType, Field, Schema};
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, NamedFunction, SourcePosition,
+};
+use crate::core::runtime::CheckpointId;
+use crate::metrics::metric::Counter;
+use crate::metrics::register_counter;
+use crate::utils::date_time::current_timestamp_millis;
+use crate::utils::hash::hash_code;
+
+/// read the lines of the files in the directory `dir`, see `FileInputFormat`
+pub fn file_source(dir: &str) -> FileInputFormat {
+    FileInputFormat::new(dir)
+}
+
+/// What to do with the files read to the end, applied after a checkpoint containing them completed
+#[derive(Clone, Debug)]
+pub enum ProcessedFileAction {
+    Keep,
+    Delete,
+    /// move the files into the directory
+    MoveTo(PathBuf),
+}
+
+/// the read state of the task, stored in the checkpoint
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+struct FileSourceState {
+    /// the files read to the end
+    processed: BTreeSet<String>,
+    /// the file being read and the number of the emitted lines
+    current: Option<(String, u64)>,
+}
+
+/// Read the lines of the files in the directory `dir` as the records of the `path` and `line`
+/// fields. The files are assigned to the tasks by the hash of the path, so a file is always read
+/// by the same task, and the processed files and the line offset of the file being read are
+/// stored in the checkpoint, so the lines are not re-emitted after a failover.
+///
+/// By default, the files existing on the start are read and the source ends. In the monitoring
+/// mode, the directory is scanned every `interval` for the new files and the source never ends.
+/// The hidden files starting with `.` or `_` are ignored, so the writers should create the files
+/// with a hidden name and rename them when completed.
+pub struct FileInputFormat {
+    dir: PathBuf,
+    parallelism: u16,
+    monitor_interval: Option<Duration>,
+    file_suffix: Option<String>,
+    processed_action: ProcessedFileAction,
+
+    task_number: u16,
+    num_tasks: u16,
+    state: Arc<Mutex<FileSourceState>>,
+    /// the files processed as of the checkpoints not completed yet
+    pending_files: BTreeMap<CheckpointId, Vec<String>>,
+    line_counter: Counter,
+}
+
+impl FileInputFormat {
+    pub fn new(dir: &str) -> Self {
+        FileInputFormat {
+            dir: PathBuf::from(dir),
+            parallelism: 1,
+            monitor_interval: None,
+            file_suffix: None,
+            processed_action: ProcessedFileAction::Keep,
+            task_number: 0,
+            num_tasks: 1,
+            state: Arc::new(Mutex::new(FileSourceState::default())),
+            pending_files: BTreeMap::new(),
+            line_counter: Counter::default(),
+        }
+    }
+
+    pub fn parallelism(mut self, parallelism: u16) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// scan the directory for the new files every `interval`, the source is unbounded
+    pub fn monitor(mut self, interval: Duration) -> Self {
+        self.monitor_interval = Some(interval);
+        self
+    }
+
+    /// only read the files with the suffix, eg: `.csv`
+    pub fn file_suffix(mut self, suffix: &str) -> Self {
+        self.file_suffix = Some(suffix.to_string());
+        self
+    }
+
+    /// delete or move the processed files, default `Keep`. The kept files are remembered in the
+    /// checkpoint state, so the directory should be cleaned up by other ways
+    pub fn processed_action(mut self, action: ProcessedFileAction) -> Self {
+        self.processed_action = action;
+        self
+    }
+
+    pub fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("path", DataType::String),
+            Field::new("line", DataType::String),
+        ])
+    }
+
+    fn apply_processed_action(&self, path: &str) -> std::io::Result<()> {
+        match &self.processed_action {
+            ProcessedFileAction::Keep => Ok(()),
+            ProcessedFileAction::Delete => std::fs::remove_file(path),
+            ProcessedFileAction::MoveTo(dir) => {
+                std::fs::create_dir_all(dir)?;
+                let file_name = Path::new(path).file_name().unwrap_or_default();
+                std::fs::rename(path, dir.join(file_name))
+            }
+        }
+    }
+}
+
+impl InputFormat for FileInputFormat {
+    fn open(&mut self, _input_split: InputSplit, context: &Context) -> crate::core::Result<()> {
+        self.task_number = context.task_id.task_number;
+        self.num_tasks = context.task_id.num_tasks;
+        self.line_counter = register_counter("FileSource_Lines", context.task_id.to_tags());
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle);
+        Ok(())
+    }
+
+    fn record_iter(&mut self) -> Box<dyn Iterator<Item = Record> + Send> {
+        let scanner = FileScanner {
+            dir: self.dir.clone(),
+            file_suffix: self.file_suffix.clone(),
+            task_number: self.task_number,
+            num_tasks: self.num_tasks,
+        };
+        Box::new(FileLineIterator::new(
+            scanner,
+            self.monitor_interval,
+            self.state.clone(),
+            self.line_counter.clone(),
+        ))
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn bounded(&self) -> bool {
+        self.monitor_interval.is_none()
+    }
+
+    fn probe(&self) -> crate::core::Result<()> {
+        if !self.dir.is_dir() {
+            return Err(crate::core::Error::from(format!(
+                "{:?} is not a directory",
+                self.dir
+            )));
+        }
+        Ok(())
+    }
+
+    fn position(&self) -> Option<SourcePosition> {
+        let state = self.state.lock().unwrap();
+        let mut position = SourcePosition::new();
+        position.put("processed_files", state.processed.len());
+        if let Some((path, lines)) = &state.current {
+            position.put("file", path);
+            position.put("lines", lines);
+        }
+        Some(position)
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&FileInputFormat::schema())
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+impl InputSplitSource for FileInputFormat {}
+
+impl NamedFunction for FileInputFormat {
+    fn name(&self) -> &str {
+        "FileInputFormat"
+    }
+}
+
+impl CheckpointFunction for FileInputFormat {
+    fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        let handle = match handle {
+            Some(handle) if !handle.handle.is_empty() => handle,
+            _ => return,
+        };
+
+        let state: FileSourceState = serde_json::from_str(handle.handle.as_str()).unwrap();
+        info!(
+            "restore {} processed files and the current file {:?} from checkpoint {:?}",
+            state.processed.len(),
+            state.current,
+            context.checkpoint_id
+        );
+        *self.state.lock().unwrap() = state;
+    }
+
+    /// the state is copied on the element loop and serialized on the snapshot pool
+    fn snapshot_state_async(&mut self, context: &FunctionSnapshotContext) -> Option<AsyncSnapshot> {
+        let state = self.state.lock().unwrap().clone();
+        if let ProcessedFileAction::Keep = self.processed_action {
+        } else {
+            let pending: BTreeSet<&String> = self.pending_files.values().flatten().collect();
+            let files = state
+                .processed
+                .iter()
+                .filter(|path| !pending.contains(path))
+                .cloned()
+                .collect();
+            self.pending_files.insert(context.checkpoint_id, files);
+        }
+
+        Some(Box::new(move || {
+            let handle = serde_json::to_string(&state)?;
+            Ok(CheckpointHandle { handle })
+        }))
+    }
+
+    /// the files processed as of the completed checkpoint are never read again after a failover,
+    /// delete or move them
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        let completed: Vec<CheckpointId> = self
+            .pending_files
+            .range(..=checkpoint_id)
+            .map(|(checkpoint_id, _)| *checkpoint_id)
+            .collect();
+        for completed_id in completed {
+            let files = self.pending_files.remove(&completed_id).unwrap_or_default();
+            for path in files {
+                match self.apply_processed_action(path.as_str()) {
+                    Ok(()) => {
+                        // the file is gone, no need to remember it
+                        self.state.lock().unwrap().processed.remove(&path);
+                    }
+                    Err(e) => warn!(
+                        "{:?} the processed file {} error. {}",
+                        self.processed_action, path, e
+                    ),
+                }
+            }
+        }
+    }
+}
+
+/// list the files of the task in the directory
+struct FileScanner {
+    dir: PathBuf,
+    file_suffix: Option<String>,
+    task_number: u16,
+    num_tasks: u16,
+}
+
+impl FileScanner {
+    fn is_task_file(&self, path: &str) -> bool {
+        hash_code(path.as_bytes()).unwrap() % self.num_tasks as u32 == self.task_number as u32
+    }
+
+    /// the files of the task order by the modified time
+    fn scan(&self) -> std::io::Result<Vec<String>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with('.') || file_name.starts_with('_') {
+                continue;
+            }
+            if let Some(suffix) = &self.file_suffix {
+                if !file_name.ends_with(suffix.as_str()) {
+                    continue;
+                }
+            }
+
+            let path = entry.path().to_string_lossy().to_string();
+            if !self.is_task_file(path.as_str()) {
+                continue;
+            }
+
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            files.push((modified, path));
+        }
+        files.sort();
+        Ok(files.into_iter().map(|(_modified, path)| path).collect())
+    }
+}
+
+struct FileLineIterator {
+    scanner: FileScanner,
+    monitor_interval: Option<Duration>,
+    state: Arc<Mutex<FileSourceState>>,
+    line_counter: Counter,
+
+    /// the files to read and the lines to skip
+    files: VecDeque<(String, u64)>,
+    reader: Option<(String, Lines<BufReader<File>>)>,
+    next_scan_time: u64,
+    scanned: bool,
+}
+
+impl FileLineIterator {
+    fn new(
+        scanner: FileScanner,
+        monitor_interval: Option<Duration>,
+        state: Arc<Mutex<FileSourceState>>,
+        line_counter: Counter,
+    ) -> Self {
+        // resume the file being read on the checkpoint
+        let files = state.lock().unwrap().current.clone().into_iter().collect();
+        FileLineIterator {
+            scanner,
+            monitor_interval,
+            state,
+            line_counter,
+            files,
+            reader: None,
+            next_scan_time: 0,
+            scanned: false,
+        }
+    }
+
+    fn open_next(&mut self) -> bool {
+        while let Some((path, skip_lines)) = self.files.pop_front() {
+            let file = match File::open(path.as_str()) {
+                Ok(file) => file,
+                Err(e) => {
+                    warn!("open {} error, skip it. {}", path, e);
+                    continue;
+                }
+            };
+
+            let mut lines = BufReader::new(file).lines();
+            for _ in 0..skip_lines {
+                lines.next();
+            }
+            self.state.lock().unwrap().current = Some((path.clone(), skip_lines));
+            self.reader = Some((path, lines));
+            return true;
+        }
+        false
+    }
+
+    /// scan the new files, return `false` if the source ends
+    fn scan(&mut self) -> bool {
+        match self.monitor_interval {
+            Some(interval) => {
+                let now = current_timestamp_millis();
+                if now < self.next_scan_time {
+                    std::thread::sleep(Duration::from_millis(self.next_scan_time - now));
+                }
+                self.next_scan_time = current_timestamp_millis() + interval.as_millis() as u64;
+            }
+            None if self.scanned => return false,
+            None => {}
+        }
+        self.scanned = true;
+
+        match self.scanner.scan() {
+            Ok(files) => {
+                let state = self.state.lock().unwrap();
+                let new_files = files
+                    .into_iter()
+                    .filter(|path| !state.processed.contains(path))
+                    .filter(|path| state.current.as_ref().map(|x| &x.0) != Some(path))
+                    .map(|path| (path, 0));
+                self.files.extend(new_files);
+            }
+            Err(e) => error!("scan {:?} error, retry later. {}", self.scanner.dir, e),
+        }
+        true
+    }
+}
+
+impl Iterator for FileLineIterator {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((path, lines)) = self.reader.as_mut() {
+                match lines.next() {
+                    Some(Ok(line)) => {
+                        let mut record = Record::new();
+                        let mut writer = record.as_writer(&[types::STRING, types::STRING]);
+                        writer.set_str(path.as_str()).unwrap();
+                        writer.set_str(line.as_str()).unwrap();
+
+                        if let Some((_path, lines)) = self.state.lock().unwrap().current.as_mut() {
+                            *lines += 1;
+                        }
+                        self.line_counter.fetch_add(1);
+                        return Some(record);
+                    }
+                    Some(Err(e)) => {
+                        error!("read {} error, skip the rest of the file. {}", path, e);
+                    }
+                    None => {}
+                }
+
+                let mut state = self.state.lock().unwrap();
+                state.processed.insert(path.clone());
+                state.current = None;
+                drop(state);
+                self.reader = None;
+            }
+
+            if self.open_next() {
+                continue;
+            }
+            if !self.scan() {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serbuffer::types;
+
+    use crate::core::element::Record;
+    use crate::functions::source::file_input_format::{
+        FileLineIterator, FileScanner, FileSourceState,
+    };
+    use crate::metrics::metric::Counter;
+
+    fn line(mut record: Record) -> String {
+        let reader = record.as_reader(&[types::STRING, types::STRING]);
+        reader.get_str(1).unwrap().to_string()
+    }
+
+    #[test]
+    pub fn file_line_iterator_test() {
+        let dir = std::env::temp_dir().join(format!("file_source_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "a1\na2\na3\n").unwrap();
+        std::fs::write(dir.join(".b.txt"), "in progress\n").unwrap();
+        let path = dir.join("a.txt").to_string_lossy().to_string();
+
+        let scanner = || FileScanner {
+            dir: dir.clone(),
+            file_suffix: Some(".txt".to_string()),
+            task_number: 0,
+            num_tasks: 1,
+        };
+
+        // resume after the first line
+        let state = FileSourceState {
+            processed: Default::default(),
+            current: Some((path.clone(), 1)),
+        };
+        let state = Arc::new(Mutex::new(state));
+        let iter = FileLineIterator::new(scanner(), None, state.clone(), Counter::default());
+        let lines: Vec<String> = iter.map(line).collect();
+        assert_eq!(lines, vec!["a2".to_string(), "a3".to_string()]);
+        assert!(state.lock().unwrap().processed.contains(&path));
+        assert!(state.lock().unwrap().current.is_none());
+
+        // the processed file is not read again
+        let iter = FileLineIterator::new(scanner(), None, state, Counter::default());
+        assert_eq!(iter.count(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,551 @@
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::{Body, Client, Request, StatusCode};
+use tokio::runtime::Handle;
+use url::Url;
+
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::data_types::Schema;
+use crate::core::dynamic_record::DynamicRecordBuilder;
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, InputFormat, InputSplit, InputSplitSource, NamedFunction};
+use crate::metrics::metric::Counter;
+use crate::metrics::register_counter;
+use crate::utils::date_time::current_timestamp_millis;
+use crate::utils::hash::hash_code;
+use crate::utils::json_path::JsonPath;
+
+/// the max pages of a poll, to stop the pagination of a misconfigured endpoint
+const MAX_PAGES: usize = 10000;
+
+/// poll the items of the json `url` periodically, see `HttpPollingInputFormat`
+pub fn http_polling_source(url: &str, schema: Schema) -> HttpPollingInputFormat {
+    HttpPollingInputFormat::new(url, schema)
+}
+
+/// The authentication of the polling requests
+#[derive(Clone, Debug)]
+pub enum HttpAuth {
+    None,
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer(String),
+    /// the api key header, eg: `X-Api-Key`
+    Header {
+        name: String,
+        value: String,
+    },
+}
+
+impl HttpAuth {
+    fn header(&self) -> Option<(String, String)> {
+        match self {
+            HttpAuth::None => None,
+            HttpAuth::Basic { username, password } => Some((
+                "Authorization".to_string(),
+                format!(
+                    "Basic {}",
+                    base64::encode(format!("{}:{}", username, password))
+                ),
+            )),
+            HttpAuth::Bearer(token) => {
+                Some(("Authorization".to_string(), format!("Bearer {}", token)))
+            }
+            HttpAuth::Header { name, value } => Some((name.clone(), value.clone())),
+        }
+    }
+}
+
+/// How to request the next page of a poll, the poll stops at the page without items
+#[derive(Clone, Debug)]
+pub enum Pagination {
+    None,
+    /// the page number query parameter `param` starts from `start`
+    PageNumber {
+        param: String,
+        start: u64,
+    },
+    /// the cursor query parameter `param` is selected from the response by `next_cursor_path`,
+    /// the poll stops if it's missing, `null` or empty
+    Cursor {
+        param: String,
+        next_cursor_path: String,
+    },
+    /// the url of the next page is selected from the response by `next_url_path`
+    NextLink {
+        next_url_path: String,
+    },
+}
+
+/// the dedup state of the polled items, stored in the checkpoint
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+struct HttpPollingState {
+    /// the `ETag` of the latest response of the first page
+    etag: Option<String>,
+    /// the hash of the latest emitted content of the items, by the dedup key
+    items: HashMap<String, u32>,
+}
+
+impl HttpPollingState {
+    fn content_hash(item: &serde_json::Value) -> u32 {
+        hash_code(item.to_string().as_bytes()).unwrap()
+    }
+
+    /// the item is new or changed since the last emit
+    fn is_changed(&self, key: &str, hash: u32) -> bool {
+        self.items.get(key) != Some(&hash)
+    }
+}
+
+/// a polled item and its dedup key and content hash
+type PolledItem = (Option<(String, u32)>, serde_json::Value);
+
+/// Poll a json http endpoint every `interval` and emit the items of the response as records,
+/// for the slowly changing reference feeds. The items are selected from the response by
+/// `items_path`, and each item is a json object mapped to the `schema` by the field names.
+/// The item is emitted when it's new or changed since the last emit by the `dedup_key` path,
+/// the dedup state is stored in the checkpoint, so the items are not re-emitted after a failover.
+/// The requests carry `If-None-Match` with the latest `ETag`, and a `304 Not Modified` response
+/// emits nothing. The source runs in one task.
+pub struct HttpPollingInputFormat {
+    url: String,
+    schema: Schema,
+    interval: Duration,
+    headers: Vec<(String, String)>,
+    auth: HttpAuth,
+    pagination: Pagination,
+    items_path: Option<String>,
+    dedup_key: Option<String>,
+
+    state: Arc<Mutex<HttpPollingState>>,
+    error_counter: Counter,
+    runtime: Option<Handle>,
+}
+
+impl HttpPollingInputFormat {
+    pub fn new(url: &str, schema: Schema) -> Self {
+        HttpPollingInputFormat {
+            url: url.to_string(),
+            schema,
+            interval: Duration::from_secs(60),
+            headers: Vec::new(),
+            auth: HttpAuth::None,
+            pagination: Pagination::None,
+            items_path: None,
+            dedup_key: None,
+            state: Arc::new(Mutex::new(HttpPollingState::default())),
+            error_counter: Counter::default(),
+            runtime: None,
+        }
+    }
+
+    /// the interval between the polls, default 60s
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    pub fn pagination(mut self, pagination: Pagination) -> Self {
+        self.pagination = pagination;
+        self
+    }
+
+    /// the json path of the items array in the response, default the response itself
+    pub fn items_path(mut self, path: &str) -> Self {
+        self.items_path = Some(path.to_string());
+        self
+    }
+
+    /// the json path of the item's key, the items are emitted on every poll without it
+    pub fn dedup_key(mut self, path: &str) -> Self {
+        self.dedup_key = Some(path.to_string());
+        self
+    }
+}
+
+impl InputFormat for HttpPollingInputFormat {
+    fn open(&mut self, _input_split: InputSplit, context: &Context) -> crate::core::Result<()> {
+        Url::parse(self.url.as_str()).map_err(|e| anyhow!("invalid url {}. {}", self.url, e))?;
+        self.error_counter = register_counter("HttpPolling_Error", context.task_id.to_tags());
+        self.runtime = Some(context.async_runtime());
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle);
+        Ok(())
+    }
+
+    fn record_iter(&mut self) -> Box<dyn Iterator<Item = Record> + Send> {
+        let poller = HttpPoller {
+            url: self.url.clone(),
+            headers: self
+                .headers
+                .iter()
+                .cloned()
+                .chain(self.auth.header())
+                .collect(),
+            pagination: self.pagination.clone(),
+            items_path: self
+                .items_path
+                .as_ref()
+                .map(|path| JsonPath::from_str(path.as_str()).unwrap()),
+            dedup_key: self
+                .dedup_key
+                .as_ref()
+                .map(|path| JsonPath::from_str(path.as_str()).unwrap()),
+        };
+        Box::new(HttpPollingIterator {
+            poller,
+            schema: self.schema.clone(),
+            interval: self.interval,
+            state: self.state.clone(),
+            error_counter: self.error_counter.clone(),
+            runtime: self.runtime.clone().unwrap(),
+            next_poll_time: 0,
+            items: VecDeque::new(),
+            etag: None,
+        })
+    }
+
+    fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    /// check the items path and the dedup key
+    fn probe(&self) -> crate::core::Result<()> {
+        for path in self.items_path.iter().chain(self.dedup_key.iter()) {
+            JsonPath::from_str(path.as_str())?;
+        }
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&self.schema)
+    }
+
+    fn parallelism(&self) -> u16 {
+        1
+    }
+}
+
+impl InputSplitSource for HttpPollingInputFormat {}
+
+impl NamedFunction for HttpPollingInputFormat {
+    fn name(&self) -> &str {
+        "HttpPollingInputFormat"
+    }
+}
+
+impl CheckpointFunction for HttpPollingInputFormat {
+    fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        let handle = match handle {
+            Some(handle) if !handle.handle.is_empty() => handle,
+            _ => return,
+        };
+
+        let state: HttpPollingState = serde_json::from_str(handle.handle.as_str()).unwrap();
+        info!(
+            "restore {} polled items from checkpoint {:?}",
+            state.items.len(),
+            context.checkpoint_id
+        );
+        *self.state.lock().unwrap() = state;
+    }
+
+    fn snapshot_state(&mut self, _context: &FunctionSnapshotContext) -> Option<CheckpointHandle> {
+        let handle = serde_json::to_string(&*self.state.lock().unwrap()).unwrap();
+        Some(CheckpointHandle { handle })
+    }
+}
+
+/// the response of a page, `None` if not modified
+type PageResponse = Option<(serde_json::Value, Option<String>)>;
+
+struct HttpPoller {
+    url: String,
+    headers: Vec<(String, String)>,
+    pagination: Pagination,
+    items_path: Option<JsonPath>,
+    dedup_key: Option<JsonPath>,
+}
+
+impl HttpPoller {
+    async fn get(&self, url: &str, etag: Option<&str>) -> anyhow::Result<PageResponse> {
+        let client = Client::builder().build::<_, Body>(hyper_tls::HttpsConnector::new());
+
+        let mut builder = Request::builder()
+            .method("GET")
+            .uri(url)
+            .header("Accept", "application/json");
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(etag) = etag {
+            builder = builder.header("If-None-Match", etag);
+        }
+        let res = client.request(builder.body(Body::empty())?).await?;
+
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !res.status().is_success() {
+            return Err(anyhow!(
+                "unexpected http status {} of {}",
+                res.status(),
+                url
+            ));
+        }
+
+        let etag = res
+            .headers()
+            .get("ETag")
+            .and_then(|x| x.to_str().ok())
+            .map(|x| x.to_string());
+        let bytes = hyper::body::to_bytes(res.into_body()).await?;
+        let json = serde_json::from_slice(bytes.as_ref())?;
+        Ok(Some((json, etag)))
+    }
+
+    fn page_items(&self, json: &serde_json::Value) -> Vec<serde_json::Value> {
+        let items = match &self.items_path {
+            Some(path) => path.select(json),
+            None => Some(json),
+        };
+        items
+            .and_then(|x| x.as_array())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// the url of the next page, `None` if the poll is finished
+    fn next_url(
+        &self,
+        url: &str,
+        page: usize,
+        json: &serde_json::Value,
+    ) -> anyhow::Result<Option<String>> {
+        let select_str = |path: &str| -> anyhow::Result<Option<String>> {
+            let path = JsonPath::from_str(path)?;
+            Ok(path
+                .select(json)
+                .and_then(|x| x.as_str())
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string()))
+        };
+
+        match &self.pagination {
+            Pagination::None => Ok(None),
+            Pagination::PageNumber { param, start } => {
+                let page = (*start + page as u64 + 1).to_string();
+                Ok(Some(with_query_param(
+                    self.url.as_str(),
+                    param,
+                    page.as_str(),
+                )?))
+            }
+            Pagination::Cursor {
+                param,
+                next_cursor_path,
+            } => match select_str(next_cursor_path)? {
+                Some(cursor) => Ok(Some(with_query_param(
+                    self.url.as_str(),
+                    param,
+                    cursor.as_str(),
+                )?)),
+                None => Ok(None),
+            },
+            Pagination::NextLink { next_url_path } => match select_str(next_url_path)? {
+                Some(next_url) => Ok(Some(Url::parse(url)?.join(next_url.as_str())?.to_string())),
+                None => Ok(None),
+            },
+        }
+    }
+
+    fn first_url(&self) -> anyhow::Result<String> {
+        match &self.pagination {
+            Pagination::PageNumber { param, start } => {
+                with_query_param(self.url.as_str(), param, start.to_string().as_str())
+            }
+            _ => Ok(self.url.clone()),
+        }
+    }
+
+    /// poll all pages, return the changed items and the `ETag`, `None` if not modified
+    async fn poll(
+        &self,
+        state: &Mutex<HttpPollingState>,
+    ) -> anyhow::Result<Option<(Vec<PolledItem>, Option<String>)>> {
+        let etag = state.lock().unwrap().etag.clone();
+
+        let mut items = Vec::new();
+        let mut url = self.first_url()?;
+        let mut first_etag = None;
+        for page in 0..MAX_PAGES {
+            // only the first page is conditional, the pages are not cached separately
+            let response = self
+                .get(url.as_str(), if page == 0 { etag.as_deref() } else { None })
+                .await?;
+            let (json, etag) = match response {
+                Some(response) => response,
+                None => return Ok(None),
+            };
+            if page == 0 {
+                first_etag = etag;
+            }
+
+            let page_items = self.page_items(&json);
+            if page_items.is_empty() {
+                break;
+            }
+            items.extend(page_items);
+
+            url = match self.next_url(url.as_str(), page, &json)? {
+                Some(url) => url,
+                None => break,
+            };
+        }
+
+        let state = state.lock().unwrap();
+        let items = items
+            .into_iter()
+            .map(|item| {
+                let key = self.dedup_key.as_ref().and_then(|dedup_key| {
+                    dedup_key
+                        .select(&item)
+                        .map(|key| (key.to_string(), HttpPollingState::content_hash(&item)))
+                });
+                (key, item)
+            })
+            .filter(|(key, _item)| match key {
+                Some((key, hash)) => state.is_changed(key.as_str(), *hash),
+                None => true,
+            })
+            .collect();
+        Ok(Some((items, first_etag)))
+    }
+}
+
+fn with_query_param(url: &str, name: &str, value: &str) -> anyhow::Result<String> {
+    let mut url = Url::parse(url)?;
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| k.ne(name))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(name, value);
+    Ok(url.to_string())
+}
+
+struct HttpPollingIterator {
+    poller: HttpPoller,
+    schema: Schema,
+    interval: Duration,
+    state: Arc<Mutex<HttpPollingState>>,
+    error_counter: Counter,
+    runtime: Handle,
+
+    next_poll_time: u64,
+    items: VecDeque<PolledItem>,
+    /// the `ETag` of the poll, recorded after all items of the poll are emitted
+    etag: Option<String>,
+}
+
+impl HttpPollingIterator {
+    fn to_record(&self, item: &serde_json::Value) -> crate::core::Result<Record> {
+        let mut builder = DynamicRecordBuilder::new(&self.schema);
+        builder.set_json(item)?;
+        builder.build()
+    }
+}
+
+impl Iterator for HttpPollingIterator {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // the dedup state is updated on emitting, so the items polled but not emitted
+            // before a checkpoint are polled again after the failover
+            while let Some((key, item)) = self.items.pop_front() {
+                match self.to_record(&item) {
+                    Ok(record) => {
+                        if let Some((key, hash)) = key {
+                            self.state.lock().unwrap().items.insert(key, hash);
+                        }
+                        return Some(record);
+                    }
+                    Err(e) => {
+                        warn!("drop the invalid item {}. {}", item, e);
+                        self.error_counter.fetch_add(1);
+                    }
+                }
+            }
+            if let Some(etag) = self.etag.take() {
+                self.state.lock().unwrap().etag = Some(etag);
+            }
+
+            let now = current_timestamp_millis();
+            if now < self.next_poll_time {
+                std::thread::sleep(Duration::from_millis(self.next_poll_time - now));
+            }
+            self.next_poll_time = current_timestamp_millis() + self.interval.as_millis() as u64;
+
+            let result = self.runtime.block_on(self.poller.poll(self.state.as_ref()));
+            match result {
+                Ok(Some((items, etag))) => {
+                    debug!(
+                        "poll {} changed items from {}",
+                        items.len(),
+                        self.poller.url
+                    );
+                    self.items.extend(items);
+                    self.etag = etag;
+                }
+                Ok(None) => debug!("{} is not modified", self.poller.url),
+                Err(e) => {
+                    error!("poll {} error, retry later. {}", self.poller.url, e);
+                    self.error_counter.fetch_add(1);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::functions::source::http_polling_input_format::{with_query_param, HttpPollingState};
+
+    #[test]
+    pub fn http_polling_state_test() {
+        assert_eq!(
+            with_query_param("http://x/items?page=1&size=10", "page", "2").unwrap(),
+            "http://x/items?size=10&page=2"
+        );
+
+        let mut state = HttpPollingState::default();
+        let hash = HttpPollingState::content_hash(&serde_json::json!({"id": 1, "name": "a"}));
+        assert!(state.is_changed("1", hash));
+        state.items.insert("1".to_string(), hash);
+        assert!(!state.is_changed("1", hash));
+        let hash = HttpPollingState::content_hash(&serde_json::json!({"id": 1, "name": "b"}));
+        assert!(state.is_changed("1", hash));
+    }
+}
@@ -1,2 +1,6 @@
+pub mod file_input_format;
+pub mod http_polling_input_format;
 pub mod vec_input_format;
+pub use file_input_format::*;
+pub use http_polling_input_format::*;
 pub use vec_input_format::*;
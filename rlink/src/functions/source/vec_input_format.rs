@@ -89,6 +89,10 @@ where
         vec_builder(input_split, context)
     }
 
+    fn bounded(&self) -> bool {
+        true
+    }
+
     fn close(&mut self) -> crate::core::Result<()> {
         Ok(())
     }
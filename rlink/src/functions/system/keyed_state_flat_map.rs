@@ -5,6 +5,8 @@ use crate::core::element::{Element, FnSchema, Record};
 use crate::core::function::{Context, FlatMapFunction, NamedFunction};
 use crate::core::properties::SystemProperties;
 use crate::core::runtime::JobId;
+use crate::dag::OperatorType;
+use crate::storage::keyed_state::sorted_reducing_state::SortedReducingState;
 use crate::storage::keyed_state::{ReducingState, StateKey, TReducingState};
 
 pub(crate) struct KeyedStateFlatMapFunction {
@@ -12,6 +14,13 @@ pub(crate) struct KeyedStateFlatMapFunction {
     task_number: u16,
 
     state_mode: KeyedStateBackend,
+    /// read the `SortedReducingState` of the bounded job
+    bounded: bool,
+
+    /// the number of the key fields of the parent reduce
+    key_arity: usize,
+    /// the number of the key and value fields of the parent reduce
+    arity: usize,
 }
 
 impl KeyedStateFlatMapFunction {
@@ -20,6 +29,9 @@ impl KeyedStateFlatMapFunction {
             parent_job_id: JobId::default(),
             task_number: 0,
             state_mode: KeyedStateBackend::Memory,
+            bounded: false,
+            key_arity: 0,
+            arity: 0,
         }
     }
 }
@@ -32,6 +44,19 @@ impl FlatMapFunction for KeyedStateFlatMapFunction {
 
         self.parent_job_id = context.parents[0].0.task_id.job_id;
         self.task_number = context.task_id.task_number;
+        self.bounded = context.bounded;
+
+        let reduce_node = context.parents[0]
+            .0
+            .stream_nodes
+            .iter()
+            .find(|stream_node| stream_node.operator_type == OperatorType::Reduce)
+            .expect("the reduce of the KeyedStateMap job not found");
+        if let FnSchema::Tuple(_record_schema, key_schema) = &reduce_node.input_schema {
+            self.key_arity = key_schema.fields().len();
+        }
+        let (_, reduce_schema): (Schema, Schema) = reduce_node.output_schema.clone().into();
+        self.arity = reduce_schema.fields().len();
 
         if let Ok(state_mode) = context.application_properties.get_keyed_state_backend() {
             self.state_mode = state_mode;
@@ -45,22 +70,39 @@ impl FlatMapFunction for KeyedStateFlatMapFunction {
     }
 
     fn flat_map_element(&mut self, element: Element) -> Box<dyn Iterator<Item = Element>> {
-        let record = element.into_record();
-        if record.len() > 0 {
-            panic!("drop window's Record is no value");
-        }
-        if record.trigger_window.is_none() {
-            panic!("drop window not found");
-        }
-
-        let window = record.trigger_window.unwrap();
+        let mut record = element.into_record();
+        let window = match record.trigger_window.take() {
+            Some(window) => window,
+            None => panic!("drop window not found"),
+        };
+        // the values of the firing, eg: the final flag of the `WindowEmitMode::Both`,
+        // are appended to each result of the window
+        let fire_values = record;
 
         let state_key = StateKey::new(window.clone(), self.parent_job_id, self.task_number);
-        let reducing_state = ReducingState::new(&state_key, self.state_mode);
-        match reducing_state {
-            Some(reducing_state) => {
-                let state_iter = reducing_state.iter();
-                Box::new(state_iter.map(|record| Element::Record(record)))
+        let state_iter = if self.bounded {
+            SortedReducingState::from(&state_key).map(|state| {
+                state
+                    .iter(self.key_arity)
+                    .unwrap_or_else(|e| panic!("read sorted state {:?} error. {}", state_key, e))
+            })
+        } else {
+            ReducingState::new(&state_key, self.state_mode).map(|state| state.iter(self.key_arity))
+        };
+        match state_iter {
+            Some(state_iter) => {
+                if fire_values.len() == 0 {
+                    return Box::new(state_iter.map(Element::Record));
+                }
+                // the fire values are the final flag of the `WindowEmitMode::Both`, which is
+                // the last field of the reduce's schema
+                let state_arity = self.arity - 1;
+                Box::new(state_iter.map(move |mut record| {
+                    record
+                        .extend(fire_values.clone(), state_arity)
+                        .expect("append the values of the window firing error");
+                    Element::Record(record)
+                }))
                 // Box::new(BatchIterator::new(state_iter, window))
             }
             None => Box::new(vec![].into_iter()),
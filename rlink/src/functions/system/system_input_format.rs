@@ -1,13 +1,18 @@
+use std::collections::HashMap;
+
 use crate::channel::select::ChannelSelect;
 use crate::channel::{ElementReceiver, TryRecvError};
 use crate::core;
 use crate::core::checkpoint::CheckpointFunction;
-use crate::core::element::{Element, FnSchema, Record};
+use crate::core::element::{Element, FnSchema, Record, StreamStatus, Watermark};
 use crate::core::function::{Context, InputFormat, InputSplit, InputSplitSource, NamedFunction};
 use crate::core::properties::{ChannelBaseOn, SystemProperties};
-use crate::core::runtime::TaskId;
+use crate::core::runtime::{ChannelKey, JobId, TaskId};
+use crate::core::watermark::MAX_WATERMARK;
 use crate::dag::execution_graph::ExecutionEdge;
-use crate::pub_sub::{memory, network, DEFAULT_CHANNEL_SIZE};
+use crate::metrics::metric::Gauge;
+use crate::metrics::register_gauge;
+use crate::pub_sub::{memory, network, shuffle, DEFAULT_CHANNEL_SIZE};
 use crate::runtime::worker::heart_beat::get_coordinator_status;
 
 pub(crate) struct SystemInputFormat {
@@ -15,6 +20,12 @@ pub(crate) struct SystemInputFormat {
     network_receiver: Option<ElementReceiver>,
 
     task_id: TaskId,
+    /// key: parent's JobId
+    /// value: whether the task of the parent job is subscribed by the current task
+    parent_jobs: HashMap<JobId, Vec<bool>>,
+    /// the latest reached watermark of each parent task
+    watermark_gauges: HashMap<TaskId, Gauge>,
+    control_element_priority: bool,
 }
 
 impl SystemInputFormat {
@@ -23,6 +34,9 @@ impl SystemInputFormat {
             memory_receiver: None,
             network_receiver: None,
             task_id: TaskId::default(),
+            parent_jobs: HashMap::new(),
+            watermark_gauges: HashMap::new(),
+            control_element_priority: false,
         }
     }
 
@@ -47,15 +61,41 @@ impl InputFormat for SystemInputFormat {
         self.subscribe_log(context);
 
         self.task_id = context.task_id.clone();
-
-        let channel_size = context
-            .application_properties
-            .get_pub_sub_channel_size()
-            .unwrap_or(DEFAULT_CHANNEL_SIZE);
-        let channel_base_on = context
+        self.control_element_priority = context
             .application_properties
-            .get_pub_sub_channel_base()
-            .unwrap_or(ChannelBaseOn::Unbounded);
+            .get_control_element_priority()
+            .unwrap_or(false);
+
+        let (channel_size, channel_base_on) = context.channel_options.unwrap_or(
+            context
+                .application_properties
+                .get_pub_sub_channel_size()
+                .unwrap_or(DEFAULT_CHANNEL_SIZE),
+            context
+                .application_properties
+                .get_pub_sub_channel_base()
+                .unwrap_or(ChannelBaseOn::Unbounded),
+        );
+
+        for (execution_node, _execution_edge) in &context.parents {
+            let parent_task_id = execution_node.task_id;
+            let tasks = self
+                .parent_jobs
+                .entry(parent_task_id.job_id)
+                .or_insert_with(|| vec![false; parent_task_id.num_tasks as usize]);
+            tasks[parent_task_id.task_number as usize] = true;
+
+            let channel_key = ChannelKey {
+                source_task_id: parent_task_id,
+                target_task_id: context.task_id,
+            };
+            let gauge = register_gauge("ChannelWatermark", channel_key.to_tags());
+            self.watermark_gauges.insert(parent_task_id, gauge);
+        }
+        info!(
+            "current job: {:?},parent jobs: {:?}",
+            self.task_id, self.parent_jobs
+        );
 
         let mut memory_jobs = Vec::new();
         let mut network_jobs = Vec::new();
@@ -76,13 +116,23 @@ impl InputFormat for SystemInputFormat {
             );
             self.memory_receiver = Some(rx);
         }
-        if network_jobs.len() > 0 {
-            let rx = network::subscribe(
-                &network_jobs,
-                &context.task_id,
-                channel_size,
-                channel_base_on,
-            );
+        if !network_jobs.is_empty() {
+            let rx = match &context.shuffle_service {
+                Some(shuffle_service) => shuffle::subscribe(
+                    shuffle_service,
+                    context.application_id.as_str(),
+                    &network_jobs,
+                    &context.task_id,
+                    channel_size,
+                    channel_base_on,
+                )?,
+                None => network::subscribe(
+                    &network_jobs,
+                    &context.task_id,
+                    channel_size,
+                    channel_base_on,
+                ),
+            };
             self.network_receiver = Some(rx);
         }
 
@@ -102,11 +152,20 @@ impl InputFormat for SystemInputFormat {
             receivers.push(n.clone());
         }
 
-        match receivers.len() {
+        let element_iter: Box<dyn Iterator<Item = Element> + Send> = match receivers.len() {
             0 => panic!("unsupported"),
             1 => Box::new(ChannelIterator::new(receivers.remove(0))),
-            _ => Box::new(MultiChannelIterator::new(receivers)),
-        }
+            _ => Box::new(MultiChannelIterator::new(
+                receivers,
+                self.control_element_priority,
+            )),
+        };
+
+        Box::new(WatermarkAlignIterator::new(
+            element_iter,
+            WatermarkManager::new(self.parent_jobs.clone()),
+            self.watermark_gauges.clone(),
+        ))
     }
 
     fn close(&mut self) -> crate::core::Result<()> {
@@ -162,13 +221,59 @@ impl Iterator for ChannelIterator {
     }
 }
 
+/// Receive the elements from multiple channels.
+/// The ready channels are polled in round-robin so a busy channel can't starve the others,
+/// and in `priority` mode the control elements at the head of any channel are emitted first.
+/// The order of the elements in a channel is always kept.
 pub struct MultiChannelIterator {
     receivers: Vec<ElementReceiver>,
+    /// the received but not emitted element of each channel
+    pending: Vec<Option<Element>>,
+    /// the channel to be polled first in next round
+    next_index: usize,
+    priority: bool,
 }
 
 impl MultiChannelIterator {
-    pub fn new(receivers: Vec<ElementReceiver>) -> Self {
-        MultiChannelIterator { receivers }
+    pub fn new(receivers: Vec<ElementReceiver>, priority: bool) -> Self {
+        let pending = receivers.iter().map(|_| None).collect();
+        MultiChannelIterator {
+            receivers,
+            pending,
+            next_index: 0,
+            priority,
+        }
+    }
+
+    fn fill_pending(&mut self) {
+        for (receiver, pending) in self.receivers.iter().zip(self.pending.iter_mut()) {
+            if pending.is_some() {
+                continue;
+            }
+
+            match receiver.try_recv() {
+                Ok(element) => *pending = Some(element),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => panic!("the channel is Disconnected"),
+            }
+        }
+    }
+
+    fn select_pending(&self) -> Option<usize> {
+        let len = self.pending.len();
+        let mut indies = (0..len).map(|i| (self.next_index + i) % len);
+
+        if self.priority {
+            let control_index = indies.clone().find(|i| match &self.pending[*i] {
+                Some(element) => !element.is_record(),
+                None => false,
+            });
+            if control_index.is_some() {
+                return control_index;
+            }
+        }
+
+        indies.find(|i| self.pending[*i].is_some())
     }
 }
 
@@ -176,28 +281,351 @@ impl Iterator for MultiChannelIterator {
     type Item = Element;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Build a list of operations.
-        let mut sel = ChannelSelect::new();
-        for r in &self.receivers {
-            sel.recv(r);
+        loop {
+            self.fill_pending();
+
+            if let Some(index) = self.select_pending() {
+                let element = self.pending[index].take().unwrap();
+                self.next_index = (index + 1) % self.pending.len();
+
+                if get_coordinator_status().is_terminated() {
+                    info!("MultiChannelIterator finish");
+                    return None;
+                }
+                return Some(element);
+            }
+
+            // Wait until a receive operation becomes ready.
+            let mut sel = ChannelSelect::new();
+            for r in &self.receivers {
+                sel.recv(r);
+            }
+            sel.ready();
+        }
+    }
+}
+
+/// Track the latest `Watermark` of each input channel,
+/// and emit the min watermark of all inputs instead of the channel's watermark.
+struct WatermarkAlignIterator {
+    element_iter: Box<dyn Iterator<Item = Element> + Send>,
+    watermark_manager: WatermarkManager,
+    watermark_gauges: HashMap<TaskId, Gauge>,
+}
+
+impl WatermarkAlignIterator {
+    pub fn new(
+        element_iter: Box<dyn Iterator<Item = Element> + Send>,
+        watermark_manager: WatermarkManager,
+        watermark_gauges: HashMap<TaskId, Gauge>,
+    ) -> Self {
+        WatermarkAlignIterator {
+            element_iter,
+            watermark_manager,
+            watermark_gauges,
+        }
+    }
+}
+
+impl Iterator for WatermarkAlignIterator {
+    type Item = Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for element in self.element_iter.by_ref() {
+            match element {
+                Element::Watermark(watermark) => {
+                    if let Some(gauge) = self
+                        .watermark_gauges
+                        .get(&watermark.channel_key.source_task_id)
+                    {
+                        gauge.store(watermark.timestamp as i64);
+                    }
+
+                    if let Some(min_watermark) = self.watermark_manager.apply(watermark) {
+                        debug!(
+                            "Watermark aligned, status_timestamp: {}",
+                            min_watermark.timestamp
+                        );
+                        return Some(Element::Watermark(min_watermark));
+                    }
+                }
+                Element::StreamStatus(stream_status) => {
+                    self.watermark_manager.watermark_job_check(&stream_status);
+                    return Some(Element::StreamStatus(stream_status));
+                }
+                _ => return Some(element),
+            }
         }
 
-        loop {
-            // Wait until a receive operation becomes ready and try executing it.
-            let index = sel.ready();
-            let res = self.receivers[index].try_recv();
-
-            match res {
-                Ok(element) => {
-                    if get_coordinator_status().is_terminated() {
-                        info!("MultiChannelIterator finish");
+        None
+    }
+}
+
+#[derive(Debug)]
+struct ParentWatermark {
+    latest_watermark: Option<Watermark>,
+    is_dependency: bool,
+}
+
+impl ParentWatermark {
+    pub fn new(latest_watermark: Option<Watermark>, is_dependency: bool) -> Self {
+        ParentWatermark {
+            latest_watermark,
+            is_dependency,
+        }
+    }
+
+    pub fn update_watermark(&mut self, watermark: Watermark) {
+        match &self.latest_watermark {
+            Some(w) => {
+                if watermark.timestamp > MAX_WATERMARK.timestamp {
+                    // special `Watermark`, don't need to compare, just assign
+                    self.latest_watermark = Some(watermark);
+                } else if watermark.timestamp >= w.timestamp {
+                    self.latest_watermark = Some(watermark);
+                } else {
+                    error!(
+                        "low level watermark {:?} reached. current: {:?}",
+                        watermark, self.latest_watermark
+                    )
+                }
+            }
+            None => self.latest_watermark = Some(watermark),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct WatermarkManager {
+    /// key: parent's JobId
+    /// value: reached watermarks from parent job
+    reached_watermarks: HashMap<JobId, Vec<ParentWatermark>>,
+}
+
+impl WatermarkManager {
+    pub fn new(parent_jobs: HashMap<JobId, Vec<bool>>) -> Self {
+        let mut reached_watermarks = HashMap::new();
+        for (job_id, tasks) in parent_jobs {
+            let watermarks: Vec<ParentWatermark> = tasks
+                .into_iter()
+                .map(|x| ParentWatermark::new(None, x))
+                .collect();
+
+            reached_watermarks.insert(job_id, watermarks);
+        }
+
+        WatermarkManager { reached_watermarks }
+    }
+
+    pub fn apply(&mut self, watermark: Watermark) -> Option<Watermark> {
+        // no parent jobs
+        if self.reached_watermarks.is_empty() {
+            return Some(watermark);
+        }
+
+        let source_task_id = &watermark.channel_key.source_task_id;
+        match self.reached_watermarks.get_mut(&source_task_id.job_id) {
+            Some(watermarks) => {
+                let task_number = source_task_id.task_number as usize;
+                if task_number >= watermarks.len() {
+                    panic!(
+                        "unreached! parent job's parallelism is {}, but reached `task_number` {}",
+                        watermarks.len(),
+                        task_number
+                    );
+                }
+                if !watermarks[task_number].is_dependency {
+                    panic!("Does not depend on the task, {:?}", watermark.channel_key);
+                }
+
+                watermarks[task_number].update_watermark(watermark);
+            }
+            None => panic!("unreached! the JobId of `Watermark` is not in the parent jobs list, or the job key has removed"),
+        }
+
+        self.min_watermark()
+    }
+
+    /// find the min watermark
+    pub fn min_watermark(&self) -> Option<Watermark> {
+        let mut min_watermark: Option<&Watermark> = None;
+        for watermarks in self.reached_watermarks.values() {
+            for p_watermark in watermarks {
+                // skip placeholder `Watermark`
+                if !p_watermark.is_dependency {
+                    continue;
+                }
+
+                match &p_watermark.latest_watermark {
+                    Some(watermark) => {
+                        if let Some(w) = min_watermark {
+                            if watermark.timestamp < w.timestamp {
+                                min_watermark = Some(watermark);
+                            }
+                        } else {
+                            min_watermark = Some(watermark);
+                        }
+                    }
+                    None => {
                         return None;
                     }
-                    return Some(element);
                 }
-                Err(TryRecvError::Empty) => continue,
-                Err(TryRecvError::Disconnected) => panic!("the channel is Disconnected"),
             }
         }
+
+        min_watermark.cloned()
+    }
+
+    pub fn watermark_job_check(&mut self, stream_status: &StreamStatus) {
+        let source_task_id = &stream_status.channel_key.source_task_id;
+        let non_watermark_job = match self.reached_watermarks.get(&source_task_id.job_id) {
+            Some(watermarks) => {
+                let task_number = source_task_id.task_number as usize;
+                if task_number >= watermarks.len() {
+                    panic!(
+                        "unreached! parent job's parallelism is {}, but reached `task_number` {}",
+                        watermarks.len(),
+                        task_number
+                    );
+                }
+                watermarks[task_number].latest_watermark.is_none()
+            }
+            None => false,
+        };
+
+        if non_watermark_job {
+            self.reached_watermarks.remove(&source_task_id.job_id);
+            info!(
+                "remove non watermark job_id={:?} from WatermarkManager",
+                source_task_id.job_id
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::channel::receiver::ChannelReceiver;
+    use crate::core::element::Record;
+    use crate::core::element::{Element, StreamStatus, Watermark};
+    use crate::core::runtime::{ChannelKey, JobId, TaskId};
+    use crate::functions::system::system_input_format::{
+        MultiChannelIterator, WatermarkAlignIterator, WatermarkManager,
+    };
+    use crate::metrics::metric::{Counter, Gauge};
+
+    fn gen_watermark(timestamp: u64, job_id: u32, task_number: u16, num_tasks: u16) -> Watermark {
+        let mut watermark = Watermark::new(timestamp);
+        watermark.channel_key = ChannelKey {
+            source_task_id: TaskId {
+                job_id: JobId(job_id),
+                task_number,
+                num_tasks,
+            },
+            target_task_id: Default::default(),
+        };
+
+        watermark
+    }
+
+    fn gen_stream_status(
+        timestamp: u64,
+        job_id: u32,
+        task_number: u16,
+        num_tasks: u16,
+    ) -> StreamStatus {
+        let mut stream_status = StreamStatus::new(timestamp, false);
+        stream_status.channel_key = ChannelKey {
+            source_task_id: TaskId {
+                job_id: JobId(job_id),
+                task_number,
+                num_tasks,
+            },
+            target_task_id: Default::default(),
+        };
+
+        stream_status
+    }
+
+    #[test]
+    pub fn watermark_manager_test() {
+        let mut parent_jobs = HashMap::new();
+        parent_jobs.insert(JobId(1), vec![true, true]);
+        parent_jobs.insert(JobId(2), vec![true, true, true]);
+
+        let mut watermark_manager = WatermarkManager::new(parent_jobs);
+
+        {
+            let watermark = gen_watermark(10, 1, 0, 2);
+            let w = watermark_manager.apply(watermark);
+            assert_eq!(w, None);
+        }
+
+        {
+            let stream_status = gen_stream_status(20, 2, 0, 3);
+            watermark_manager.watermark_job_check(&stream_status);
+        }
+
+        {
+            let watermark = gen_watermark(9, 1, 1, 2);
+            let w = watermark_manager.apply(watermark);
+            assert_eq!(w.unwrap().timestamp, 9);
+        }
+    }
+
+    #[test]
+    pub fn watermark_align_iterator_test() {
+        let mut parent_jobs = HashMap::new();
+        parent_jobs.insert(JobId(1), vec![true, true]);
+
+        let elements = vec![
+            Element::Watermark(gen_watermark(10, 1, 0, 2)),
+            Element::Watermark(gen_watermark(20, 1, 1, 2)),
+            Element::Watermark(gen_watermark(30, 1, 0, 2)),
+        ];
+        let iter = WatermarkAlignIterator::new(
+            Box::new(elements.into_iter()),
+            WatermarkManager::new(parent_jobs),
+            HashMap::new(),
+        );
+
+        let timestamps: Vec<u64> = iter
+            .map(|element| element.as_watermark().timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![10, 20]);
+    }
+
+    fn element_kinds(priority: bool) -> String {
+        let (sender_a, receiver_a) = crate::channel::unbounded();
+        let (sender_b, receiver_b) = crate::channel::unbounded();
+        for _ in 0..3 {
+            sender_a.send(Element::Record(Record::new())).unwrap();
+        }
+        sender_b
+            .send(Element::Watermark(Watermark::new(100)))
+            .unwrap();
+        sender_b.send(Element::Record(Record::new())).unwrap();
+
+        let receiver_a =
+            ChannelReceiver::new("a", receiver_a, Gauge::default(), Counter::default());
+        let receiver_b =
+            ChannelReceiver::new("b", receiver_b, Gauge::default(), Counter::default());
+        MultiChannelIterator::new(vec![receiver_a, receiver_b], priority)
+            .take(5)
+            .map(|element| match element {
+                Element::Record(_) => 'r',
+                _ => 'c',
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn multi_channel_iterator_test() {
+        // round-robin between the ready channels
+        assert_eq!(element_kinds(false), "rcrrr");
+        // the control element is drained first
+        assert_eq!(element_kinds(true), "crrrr");
     }
 }
@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 
-use crate::channel::ElementSender;
+use crate::channel::{ChannelOptions, ElementSender};
 use crate::core::checkpoint::CheckpointFunction;
 use crate::core::element::{Element, FnSchema, Partition, Record, StreamStatus};
 use crate::core::function::{Context, NamedFunction, OutputFormat};
 use crate::core::properties::{ChannelBaseOn, SystemProperties};
 use crate::core::runtime::{ChannelKey, JobId, TaskId};
+use crate::core::shuffle::ShuffleWriter;
 use crate::dag::execution_graph::ExecutionEdge;
-use crate::pub_sub::{memory, network, ChannelType, DEFAULT_CHANNEL_SIZE};
+use crate::pub_sub::{memory, network, shuffle, ChannelType, DEFAULT_CHANNEL_SIZE};
 
 /// support job's Multiplexing, but only one channel mode(memory/network) support
 pub(crate) struct SystemOutputFormat {
@@ -15,6 +16,8 @@ pub(crate) struct SystemOutputFormat {
     channel_type: ChannelType,
     // Vec<JobId(self), Vec<(TaskId(child), ElementSender)>)>
     job_senders: Vec<(JobId, Vec<(TaskId, ElementSender)>)>,
+    // Vec<JobId(child), Vec<TaskId(child)>, ShuffleWriter>, replace the network channels
+    job_writers: Vec<(JobId, Vec<TaskId>, Box<dyn ShuffleWriter>)>,
 }
 
 impl SystemOutputFormat {
@@ -23,6 +26,7 @@ impl SystemOutputFormat {
             task_id: TaskId::default(),
             channel_type: ChannelType::Memory,
             job_senders: Vec::new(),
+            job_writers: Vec::new(),
         }
     }
 }
@@ -54,14 +58,25 @@ impl OutputFormat for SystemOutputFormat {
 
         let mut memory_jobs = Vec::new();
         let mut network_jobs = Vec::new();
+        // the input channel options of each child task
+        let mut channel_options: HashMap<TaskId, ChannelOptions> = HashMap::new();
 
         context
             .children
             .iter()
-            .for_each(|(execution_node, execution_edge)| match execution_edge {
-                ExecutionEdge::Memory => memory_jobs.push(execution_node.task_id.clone()),
-                ExecutionEdge::Network => network_jobs.push(execution_node.task_id.clone()),
+            .for_each(|(execution_node, execution_edge)| {
+                match execution_edge {
+                    ExecutionEdge::Memory => memory_jobs.push(execution_node.task_id),
+                    ExecutionEdge::Network => network_jobs.push(execution_node.task_id),
+                }
+                channel_options.insert(execution_node.task_id, execution_node.channel_options());
             });
+        let child_channel = |target_task_id: &TaskId| {
+            channel_options
+                .get(target_task_id)
+                .map(|options| options.unwrap_or(channel_size, channel_base_on))
+                .unwrap_or((channel_size, channel_base_on))
+        };
 
         if memory_jobs.len() == 0 && network_jobs.len() == 0 {
             panic!("child job not found");
@@ -74,12 +89,18 @@ impl OutputFormat for SystemOutputFormat {
         if memory_jobs.len() > 0 {
             self.channel_type = ChannelType::Memory;
 
-            let task_senders = memory::publish(
-                &context.task_id,
-                &memory_jobs,
-                channel_size,
-                channel_base_on,
-            );
+            let task_senders: Vec<(ChannelKey, ElementSender)> = memory_jobs
+                .iter()
+                .flat_map(|target_task_id| {
+                    let (channel_size, channel_base_on) = child_channel(target_task_id);
+                    memory::publish(
+                        &context.task_id,
+                        &vec![*target_task_id],
+                        channel_size,
+                        channel_base_on,
+                    )
+                })
+                .collect();
 
             let mut job_senders = HashMap::new();
             for (channel_key, sender) in task_senders {
@@ -103,14 +124,45 @@ impl OutputFormat for SystemOutputFormat {
             }
         }
 
-        if network_jobs.len() > 0 {
+        let shuffle_service = context
+            .shuffle_service
+            .as_ref()
+            .filter(|_| !network_jobs.is_empty());
+        if let Some(shuffle_service) = shuffle_service {
+            self.channel_type = ChannelType::Shuffle;
+
+            let mut job_tasks: HashMap<JobId, Vec<TaskId>> = HashMap::new();
+            for target_task_id in network_jobs {
+                job_tasks
+                    .entry(target_task_id.job_id)
+                    .or_default()
+                    .push(target_task_id);
+            }
+
+            for (job_id, mut task_ids) in job_tasks {
+                task_ids.sort_by_key(|task_id| task_id.task_number);
+                let writer = shuffle::publish(
+                    shuffle_service,
+                    context.application_id.as_str(),
+                    &context.task_id,
+                    task_ids.as_slice(),
+                )?;
+                self.job_writers.push((job_id, task_ids, writer));
+            }
+        } else if !network_jobs.is_empty() {
             self.channel_type = ChannelType::Network;
-            let task_senders = network::publish(
-                &context.task_id,
-                &network_jobs,
-                channel_size,
-                channel_base_on,
-            );
+            let task_senders: Vec<(ChannelKey, ElementSender)> = network_jobs
+                .iter()
+                .flat_map(|target_task_id| {
+                    let (channel_size, channel_base_on) = child_channel(target_task_id);
+                    network::publish(
+                        &context.task_id,
+                        &vec![*target_task_id],
+                        channel_size,
+                        channel_base_on,
+                    )
+                })
+                .collect();
 
             let child_parallelism = task_senders[0].0.target_task_id.num_tasks;
 
@@ -129,6 +181,17 @@ impl OutputFormat for SystemOutputFormat {
             }
 
             for (job_id, mut task_senders) in job_senders {
+                // `Forward` on the network channel when `force_network_edge`
+                if task_senders.len() == 1 && child_parallelism > 1 {
+                    let target_task_id = task_senders[0].0;
+                    if context.task_id.task_number != target_task_id.task_number {
+                        panic!("the task `task_number` conflict in forward network channel");
+                    }
+
+                    self.job_senders.push((job_id, task_senders));
+                    continue;
+                }
+
                 if task_senders.len() != child_parallelism as usize {
                     panic!("the job `num_tasks` conflict in network channel");
                 }
@@ -177,21 +240,38 @@ impl OutputFormat for SystemOutputFormat {
             ChannelType::Network => {
                 if self.job_senders.len() == 1 {
                     let (_job_id, task_senders) = &self.job_senders[0];
-                    let (_task_id, sender) =
-                        task_senders.get(element.partition() as usize).unwrap();
+                    let (_task_id, sender) = network_sender(task_senders, &element);
                     sender.send(element).unwrap();
                 } else {
                     for (_job, task_senders) in &self.job_senders {
-                        let (_task_id, sender) =
-                            task_senders.get(element.partition() as usize).unwrap();
+                        let (_task_id, sender) = network_sender(task_senders, &element);
                         sender.send(element.clone()).unwrap()
                     }
                 }
             }
+            ChannelType::Shuffle => {
+                for (_job_id, task_ids, writer) in &mut self.job_writers {
+                    // the only one task is a `Forward` channel
+                    let partition = if task_ids.len() == 1 {
+                        task_ids[0].task_number
+                    } else {
+                        element.partition()
+                    };
+                    writer.write(partition, &element).unwrap();
+                }
+            }
         }
     }
 
     fn close(&mut self) -> crate::core::Result<()> {
+        for (_job_id, task_ids, writer) in &mut self.job_writers {
+            for task_id in task_ids.iter() {
+                let stream_status = Element::StreamStatus(StreamStatus::new(0, true));
+                writer.write(task_id.task_number, &stream_status)?;
+            }
+            writer.finish()?;
+        }
+
         let stream_status = Element::StreamStatus(StreamStatus::new(0, true));
         self.job_senders.iter().for_each(|(_job_id, task_senders)| {
             task_senders.iter().for_each(|(_task_id, sender)| {
@@ -207,6 +287,18 @@ impl OutputFormat for SystemOutputFormat {
     }
 }
 
+/// the only one sender is a `Forward` channel, otherwise select by the element's partition
+fn network_sender<'a>(
+    task_senders: &'a [(TaskId, ElementSender)],
+    element: &Element,
+) -> &'a (TaskId, ElementSender) {
+    if task_senders.len() == 1 {
+        &task_senders[0]
+    } else {
+        task_senders.get(element.partition() as usize).unwrap()
+    }
+}
+
 impl NamedFunction for SystemOutputFormat {
     fn name(&self) -> &str {
         "SystemOutputFormat"
@@ -1,38 +1,69 @@
 use std::borrow::BorrowMut;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 
 use crate::core::backend::KeyedStateBackend;
 use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use serbuffer::types;
+
+use crate::core::data_types::{DataType, Field, Schema};
 use crate::core::element::{FnSchema, Record};
 use crate::core::function::{BaseReduceFunction, Context, NamedFunction, ReduceFunction};
 use crate::core::properties::SystemProperties;
 use crate::core::runtime::CheckpointId;
-use crate::core::window::{TWindow, Window};
+use crate::core::window::{TWindow, Window, WindowEmitMode, WindowEmitStrategy};
+use crate::metrics::keyed_state::KeyGroupStats;
 use crate::metrics::metric::Gauge;
 use crate::metrics::register_gauge;
 use crate::runtime::worker::runnable::reduce_runnable::ReduceCheckpointHandle;
+use crate::storage::keyed_state::pane_window_state::PaneWindowState;
+use crate::storage::keyed_state::sorted_window_state::SortedWindowState;
 use crate::storage::keyed_state::{TWindowState, WindowState};
-use crate::utils::date_time::timestamp_str;
+use crate::utils::date_time::{current_timestamp_millis, timestamp_str};
+use crate::utils::external_sort::DEFAULT_SORT_MEMORY_LIMIT;
 
 pub(crate) struct WindowBaseReduceFunction {
     reduce: Box<dyn ReduceFunction>,
 
     state: Option<WindowState>,
+    /// the sort-based grouping instead of the `state` if the job is bounded
+    sorted_state: Option<SortedWindowState>,
+    /// the panes instead of the `state` if the windows overlap, see `WindowAssigner::pane_size`
+    pane_state: Option<PaneWindowState>,
+    pane_size: Option<u64>,
 
     window_checkpoints: BTreeMap<CheckpointId, HashMap<Window, bool>>,
     skip_windows: Vec<Window>,
 
+    emit_strategy: WindowEmitStrategy,
+    /// the windows updated since the latest firing, tracked if the emission is incremental
+    dirty_windows: HashSet<Window>,
+    /// the timestamp of the latest firing of the open windows
+    fire_timestamp: u64,
+
     windows_gauge: Gauge,
+    state_entries_gauge: Gauge,
 }
 
 impl WindowBaseReduceFunction {
-    pub fn new(reduce: Box<dyn ReduceFunction>) -> Self {
+    pub fn new(
+        reduce: Box<dyn ReduceFunction>,
+        emit_strategy: WindowEmitStrategy,
+        pane_size: Option<u64>,
+    ) -> Self {
         WindowBaseReduceFunction {
             reduce,
             state: None,
+            sorted_state: None,
+            pane_state: None,
+            pane_size,
             window_checkpoints: BTreeMap::new(),
             skip_windows: Vec::new(),
+            emit_strategy,
+            dirty_windows: HashSet::new(),
+            fire_timestamp: 0,
             windows_gauge: Gauge::default(),
+            state_entries_gauge: Gauge::default(),
         }
     }
 
@@ -53,6 +84,99 @@ impl WindowBaseReduceFunction {
             })
             .is_some()
     }
+
+    fn windows(&self) -> Vec<Window> {
+        if let Some(pane_state) = &self.pane_state {
+            return pane_state.windows();
+        }
+        match &self.sorted_state {
+            Some(sorted_state) => sorted_state.windows(),
+            None => self.state.as_ref().unwrap().windows(),
+        }
+    }
+
+    fn drop_window(&mut self, window: &Window) -> usize {
+        if let Some(pane_state) = self.pane_state.as_mut() {
+            let reduce_func = &self.reduce;
+            return pane_state.drop_window(window, |val1, val2| reduce_func.combine(val1, val2));
+        }
+        match self.sorted_state.as_mut() {
+            Some(sorted_state) => {
+                let reduce_func = &self.reduce;
+                sorted_state
+                    .drop_window(window, |val1, val2| reduce_func.reduce(val1, val2))
+                    .unwrap_or_else(|e| {
+                        panic!("reduce the sorted window {:?} error. {}", window, e)
+                    })
+            }
+            None => self.state.as_mut().unwrap().drop_window(window),
+        }
+    }
+
+    /// the event record of the window firing, the values are appended to the window's results
+    /// by the `KeyedStateFlatMapFunction`
+    fn fire_record(&self, window: Window, is_final: bool) -> Record {
+        let mut fire_record = match self.emit_strategy.mode {
+            WindowEmitMode::Both => {
+                let mut record = Record::with_capacity(1);
+                record.as_writer(&[types::BOOL]).set_bool(is_final).unwrap();
+                record
+            }
+            _ => Record::new(),
+        };
+        fire_record.trigger_window = Some(window);
+        fire_record
+    }
+
+    /// fire the open windows updated since the latest firing, the bounded job only emits
+    /// on the close because the watermark jumps to the end
+    fn fire_open_windows(&mut self) -> Vec<Record> {
+        let now = current_timestamp_millis();
+        if self.emit_strategy.mode == WindowEmitMode::OnClose
+            || self.sorted_state.is_some()
+            || now < self.fire_timestamp + self.emit_strategy.fire_interval
+        {
+            return vec![];
+        }
+        self.fire_timestamp = now;
+
+        let mut fire_windows: Vec<Window> = self.dirty_windows.drain().collect();
+        fire_windows.sort_by_key(|w| w.max_timestamp());
+
+        let purge = self.emit_strategy.purge;
+        let state = self.state.as_mut().unwrap();
+        for window in &fire_windows {
+            if purge {
+                state.drop_window(window);
+            } else {
+                state.fire_window(window);
+            }
+        }
+
+        fire_windows
+            .into_iter()
+            .map(|window| self.fire_record(window, false))
+            .collect()
+    }
+
+    /// the panes only hold the partial results of the closed windows, so the windows fired
+    /// before the close or held after the watermark are reduced into the `state`
+    fn pane_state_enabled(&self) -> bool {
+        self.pane_size.is_some()
+            && self.reduce.combinable()
+            && self.emit_strategy.mode == WindowEmitMode::OnClose
+            && self.emit_strategy.watermark_hold == 0
+    }
+
+    fn entries(&self) -> usize {
+        if let Some(pane_state) = &self.pane_state {
+            return pane_state.entries();
+        }
+        match &self.sorted_state {
+            Some(sorted_state) => sorted_state.entries(),
+            None => self.state.as_ref().unwrap().entries(),
+        }
+    }
 }
 
 impl BaseReduceFunction for WindowBaseReduceFunction {
@@ -62,23 +186,62 @@ impl BaseReduceFunction for WindowBaseReduceFunction {
 
         self.windows_gauge =
             register_gauge(format!("ReduceWindow_{}", self.name()), task_id.to_tags());
+        self.state_entries_gauge = register_gauge(
+            format!("ReduceStateEntries_{}", self.name()),
+            task_id.to_tags(),
+        );
 
         let state_mode = context
             .application_properties
             .get_keyed_state_backend()
             .unwrap_or(KeyedStateBackend::Memory);
-        self.state = Some(WindowState::new(
-            application_id,
-            task_id.job_id(),
-            task_id.task_number(),
-            state_mode,
-        ));
+        if context.bounded {
+            let properties = &context.application_properties;
+            let spill_dir = properties
+                .get_sort_spill_dir()
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| std::env::temp_dir().join("rlink_sort"));
+            let memory_limit = properties
+                .get_sort_memory_limit()
+                .unwrap_or(DEFAULT_SORT_MEMORY_LIMIT);
+            info!(
+                "the job is bounded, reduce by the sort-based grouping. spill dir: {:?}, memory limit: {}",
+                spill_dir, memory_limit
+            );
+
+            self.sorted_state = Some(SortedWindowState::new(
+                application_id,
+                task_id.job_id(),
+                task_id.task_number(),
+                spill_dir,
+                memory_limit,
+            ));
+        } else if self.pane_state_enabled() {
+            info!("the windows overlap, reduce the records into the panes");
+            self.pane_state = Some(PaneWindowState::new(
+                task_id.job_id(),
+                task_id.task_number(),
+            ));
+        } else {
+            self.state = Some(WindowState::new(
+                application_id,
+                task_id.job_id(),
+                task_id.task_number(),
+                state_mode,
+            ));
+        }
         self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle);
 
         self.reduce.open(context)
     }
 
     fn reduce(&mut self, key: Record, mut record: Record) {
+        // the pane is located by all windows of the record, including the skipped ones
+        let pane_start = match &self.pane_state {
+            Some(_) => PaneWindowState::pane_start(record.location_windows()),
+            None => None,
+        };
+
         // check skip window
         if self.skip_windows.len() > 0 {
             if let Some(windows) = record.location_windows.borrow_mut() {
@@ -91,24 +254,72 @@ impl BaseReduceFunction for WindowBaseReduceFunction {
             }
         }
 
-        let state = self.state.as_mut().unwrap();
-        let reduce_func = &self.reduce;
-        let window_count = state.merge(key, record, |val1, val2| reduce_func.reduce(val1, val2));
+        if self.emit_strategy.mode != WindowEmitMode::OnClose {
+            for window in record.location_windows() {
+                if !self.dirty_windows.contains(window) {
+                    self.dirty_windows.insert(window.clone());
+                }
+            }
+        }
+
+        if let Some(pane_state) = self.pane_state.as_mut() {
+            if let Some(pane_start) = pane_start {
+                let reduce_func = &self.reduce;
+                let window_count = pane_state.merge(pane_start, key, record, |val1, val2| {
+                    reduce_func.reduce(val1, val2)
+                });
+                self.windows_gauge.store(window_count as i64);
+            }
+            return;
+        }
+
+        let window_count = match self.sorted_state.as_mut() {
+            Some(sorted_state) => sorted_state
+                .merge(key, record)
+                .unwrap_or_else(|e| panic!("sort the reduce records error. {}", e)),
+            None => {
+                let state = self.state.as_mut().unwrap();
+                let reduce_func = &self.reduce;
+                state.merge(key, record, |val1, val2| reduce_func.reduce(val1, val2))
+            }
+        };
         self.windows_gauge.store(window_count as i64);
     }
 
     fn drop_state(&mut self, watermark_timestamp: u64) -> Vec<Record> {
-        let state = self.state.as_mut().unwrap();
+        // the held windows are closed by the later watermarks
+        let watermark_timestamp =
+            watermark_timestamp.saturating_sub(self.emit_strategy.watermark_hold);
+
         let mut drop_windows = Vec::new();
         let mut window_count = 0;
-        for window in state.windows() {
+        for window in self.windows() {
             if window.max_timestamp() <= watermark_timestamp {
-                drop_windows.push(window.clone());
-                window_count = state.drop_window(&window);
+                // the incremental emission skips the windows not updated since the latest firing
+                let updated = self.emit_strategy.mode != WindowEmitMode::Incremental
+                    || self.sorted_state.is_some()
+                    || self.dirty_windows.remove(&window);
+                if updated {
+                    drop_windows.push(window.clone());
+                    window_count = self.drop_window(&window);
+                } else {
+                    window_count = self.state.as_mut().unwrap().purge_window(&window);
+                }
             }
         }
+        self.dirty_windows
+            .retain(|w| w.max_timestamp() > watermark_timestamp);
 
-        self.windows_gauge.store(window_count as i64);
+        // the purged windows may be absent from the state, so the closed windows are completed
+        // by the watermark instead of the dropped windows
+        self.window_checkpoints
+            .iter_mut()
+            .for_each(|(_checkpoint_id, windows)| {
+                windows
+                    .iter_mut()
+                    .filter(|(w, _)| w.max_timestamp() <= watermark_timestamp)
+                    .for_each(|(_, x)| *x = true);
+            });
 
         if drop_windows.len() > 0 {
             debug!(
@@ -116,28 +327,19 @@ impl BaseReduceFunction for WindowBaseReduceFunction {
                 timestamp_str(watermark_timestamp),
                 drop_windows.len()
             );
+        }
+        drop_windows.sort_by_key(|w| w.max_timestamp());
 
-            self.window_checkpoints
-                .iter_mut()
-                .for_each(|(_checkpoint_id, windows)| {
-                    drop_windows.iter().for_each(|w| {
-                        windows.get_mut(w).map(|x| *x = true);
-                    });
-                });
+        let mut fire_records: Vec<Record> = drop_windows
+            .into_iter()
+            .map(|drop_window| self.fire_record(drop_window, true))
+            .collect();
+        fire_records.extend(self.fire_open_windows());
 
-            drop_windows.sort_by_key(|w| w.max_timestamp());
+        self.windows_gauge.store(window_count as i64);
+        self.state_entries_gauge.store(self.entries() as i64);
 
-            drop_windows
-                .into_iter()
-                .map(|drop_window| {
-                    let mut drop_record = Record::new();
-                    drop_record.trigger_window = Some(drop_window);
-                    drop_record
-                })
-                .collect()
-        } else {
-            vec![]
-        }
+        fire_records
     }
 
     fn close(&mut self) -> crate::core::Result<()> {
@@ -145,7 +347,16 @@ impl BaseReduceFunction for WindowBaseReduceFunction {
     }
 
     fn value_schema(&self, input_schema: FnSchema) -> FnSchema {
-        self.reduce.schema(input_schema)
+        let value_schema = self.reduce.schema(input_schema);
+        if self.emit_strategy.mode == WindowEmitMode::Both {
+            let mut fields = value_schema.first().fields().clone();
+            fields.push(Field::new(
+                self.emit_strategy.final_field.as_str(),
+                DataType::Boolean,
+            ));
+            return FnSchema::from(&Schema::new(fields));
+        }
+        value_schema
         // let value_schema = self.reduce.schema();
         // match input_schema {
         //     Schema::Single(_record_schema) => value_schema,
@@ -157,31 +368,24 @@ impl BaseReduceFunction for WindowBaseReduceFunction {
         //     Schema::Empty => panic!("unreached!"),
         // }
     }
-}
 
-impl NamedFunction for WindowBaseReduceFunction {
-    fn name(&self) -> &str {
-        "WindowBaseReduceFunction"
+    fn watermark_hold(&self) -> u64 {
+        self.emit_strategy.watermark_hold
     }
-}
 
-impl CheckpointFunction for WindowBaseReduceFunction {
-    fn initialize_state(
-        &mut self,
-        _context: &FunctionSnapshotContext,
-        handle: &Option<CheckpointHandle>,
-    ) {
-        if let Some(handle) = handle {
-            let handle = ReduceCheckpointHandle::from(handle.handle.as_str());
-            let current_windows = handle.into_windows();
-
-            self.skip_windows = current_windows;
-            info!("skip windows: {:?}", self.skip_windows)
+    fn key_group_stats(&self) -> Option<KeyGroupStats> {
+        if let Some(pane_state) = &self.pane_state {
+            return Some(pane_state.key_group_stats());
+        }
+        // the bounded sorted state is spilled to the disk
+        match &self.sorted_state {
+            Some(_) => None,
+            None => self.state.as_ref().map(|state| state.key_group_stats()),
         }
     }
 
-    fn snapshot_state(&mut self, context: &FunctionSnapshotContext) -> Option<CheckpointHandle> {
-        let windows = self.state.as_ref().unwrap().windows();
+    fn snapshot_windows(&mut self, context: &FunctionSnapshotContext) -> ReduceCheckpointHandle {
+        let windows = self.windows();
         let mut windows_map = HashMap::with_capacity(windows.len());
         windows.iter().for_each(|w| {
             windows_map.insert(w.clone(), false);
@@ -224,8 +428,190 @@ impl CheckpointFunction for WindowBaseReduceFunction {
             .iter()
             .max_by_key(|x| x.0)
             .map(|x| *x);
-        let handle = ReduceCheckpointHandle::new(max_checkpoint_id, windows).to_string();
+        ReduceCheckpointHandle::new(max_checkpoint_id, windows)
+    }
+}
+
+impl NamedFunction for WindowBaseReduceFunction {
+    fn name(&self) -> &str {
+        "WindowBaseReduceFunction"
+    }
+}
+
+impl CheckpointFunction for WindowBaseReduceFunction {
+    fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        if let Some(handle) = handle {
+            let handle = ReduceCheckpointHandle::from(handle.handle.as_str());
+            let current_windows = handle.into_windows();
+
+            self.skip_windows = current_windows;
+            info!("skip windows: {:?}", self.skip_windows)
+        }
+    }
 
+    fn snapshot_state(&mut self, context: &FunctionSnapshotContext) -> Option<CheckpointHandle> {
+        let handle = self.snapshot_windows(context).to_string();
         Some(CheckpointHandle { handle })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serbuffer::types;
+
+    use crate::core::backend::KeyedStateBackend;
+    use crate::core::checkpoint::CheckpointFunction;
+    use crate::core::element::{FnSchema, Record};
+    use crate::core::function::{BaseReduceFunction, Context, NamedFunction, ReduceFunction};
+    use crate::core::runtime::JobId;
+    use crate::core::window::{TWindow, TimeWindow, Window, WindowEmitStrategy};
+    use crate::functions::system::window_base_reduce::WindowBaseReduceFunction;
+    use crate::runtime::worker::runnable::reduce_runnable::is_acceptable;
+    use crate::storage::keyed_state::mem_storage::remove_drop_window;
+    use crate::storage::keyed_state::{TReducingState, WindowState};
+
+    struct CountReduceFunction {}
+
+    impl ReduceFunction for CountReduceFunction {
+        fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+            Ok(())
+        }
+
+        fn reduce(&self, value: Option<&mut Record>, _record: &mut Record) -> Record {
+            count(value, 1)
+        }
+
+        fn close(&mut self) -> crate::core::Result<()> {
+            Ok(())
+        }
+
+        fn schema(&self, input_schema: FnSchema) -> FnSchema {
+            input_schema
+        }
+
+        fn parallelism(&self) -> u16 {
+            1
+        }
+
+        fn combinable(&self) -> bool {
+            true
+        }
+
+        fn combine(&self, value: Option<&mut Record>, partial: &mut Record) -> Record {
+            let n = partial.as_reader(&[types::U64]).get_u64(0).unwrap();
+            count(value, n)
+        }
+    }
+
+    impl NamedFunction for CountReduceFunction {
+        fn name(&self) -> &str {
+            "CountReduceFunction"
+        }
+    }
+
+    impl CheckpointFunction for CountReduceFunction {}
+
+    fn count(value: Option<&mut Record>, n: u64) -> Record {
+        let base = value
+            .map(|value| value.as_reader(&[types::U64]).get_u64(0).unwrap())
+            .unwrap_or(0);
+        let mut record = Record::with_capacity(8);
+        record.as_writer(&[types::U64]).set_u64(base + n).unwrap();
+        record
+    }
+
+    fn window(start: u64) -> Window {
+        Window::TimeWindow(TimeWindow::new(start, start + 3000))
+    }
+
+    fn key() -> Record {
+        let mut key = Record::with_capacity(8);
+        key.as_writer(&[types::U64]).set_u64(1).unwrap();
+        key
+    }
+
+    fn record(window: &Window) -> Record {
+        let mut record = Record::new();
+        record.set_location_windows(vec![window.clone()]);
+        record
+    }
+
+    fn is_final(fire_record: &mut Record) -> bool {
+        fire_record.as_reader(&[types::BOOL]).get_bool(0).unwrap()
+    }
+
+    fn fired_count(job_id: JobId, window: &Window) -> u64 {
+        let mut fired = remove_drop_window(job_id, 0, window.clone()).unwrap();
+        let value = fired.get_mut(&key()).unwrap();
+        value.as_reader(&[types::U64]).get_u64(0).unwrap()
+    }
+
+    #[test]
+    pub fn watermark_hold_test() {
+        let job_id = JobId(97);
+        let emit_strategy =
+            WindowEmitStrategy::both(Duration::from_secs(0)).watermark_hold(Duration::from_secs(2));
+        let mut reduce_fn =
+            WindowBaseReduceFunction::new(Box::new(CountReduceFunction {}), emit_strategy, None);
+        reduce_fn.state = Some(WindowState::new(
+            "app".to_string(),
+            job_id,
+            0,
+            KeyedStateBackend::Memory,
+        ));
+        let hold = reduce_fn.watermark_hold();
+
+        let closing_window = window(0);
+        reduce_fn.reduce(key(), record(&closing_window));
+
+        // the watermark passes the end of the window, the held window only fires the early result
+        let watermark_window = window(3000);
+        let mut fire_records = reduce_fn.drop_state(watermark_window.min_timestamp());
+        assert_eq!(fire_records.len(), 1);
+        assert!(!is_final(&mut fire_records[0]));
+        assert_eq!(fired_count(job_id, &closing_window), 1);
+
+        // the late record in the hold is accepted
+        let late_record = record(&closing_window);
+        assert!(is_acceptable(&late_record, &watermark_window, hold));
+        reduce_fn.reduce(key(), late_record);
+
+        // the watermark passes the hold, the close emits the corrected result
+        let watermark_window = window(6000);
+        let mut fire_records = reduce_fn.drop_state(watermark_window.min_timestamp());
+        assert_eq!(fire_records.len(), 1);
+        assert!(is_final(&mut fire_records[0]));
+        assert_eq!(fired_count(job_id, &closing_window), 2);
+        assert!(remove_drop_window(job_id, 0, closing_window.clone()).is_none());
+
+        // the record later than the hold is dropped
+        let late_record = record(&closing_window);
+        assert!(!is_acceptable(&late_record, &watermark_window, hold));
+        // without the hold the window is closed by the first watermark
+        assert!(!is_acceptable(&late_record, &window(3000), 0));
+    }
+
+    #[test]
+    pub fn watermark_hold_pane_state_test() {
+        let reduce_fn = WindowBaseReduceFunction::new(
+            Box::new(CountReduceFunction {}),
+            WindowEmitStrategy::on_close(),
+            Some(1000),
+        );
+        assert!(reduce_fn.pane_state_enabled());
+
+        // the panes can't be updated after the close, so the held windows are kept in the state
+        let reduce_fn = WindowBaseReduceFunction::new(
+            Box::new(CountReduceFunction {}),
+            WindowEmitStrategy::on_close().watermark_hold(Duration::from_secs(2)),
+            Some(1000),
+        );
+        assert!(!reduce_fn.pane_state_enabled());
+    }
+}
@@ -3,11 +3,14 @@ use std::time::Duration;
 
 use crate::core::checkpoint::CheckpointFunction;
 use crate::core::function::NamedFunction;
-use crate::core::watermark::{TimestampAssigner, WatermarkGenerator, WatermarkStrategy};
+use crate::core::watermark::{
+    PunctuatedWatermarkAssigner, TimestampAssigner, WatermarkGenerator, WatermarkStrategy,
+};
 use crate::functions::column_locate::ColumnLocateBuilder;
 use crate::functions::watermark::watermarks_with_idleness::WatermarksWithIdleness;
 use crate::functions::watermark::{
-    BoundedOutOfOrdernessWatermarks, SchemaTimestampAssigner, TimePeriodicWatermarks,
+    BoundedOutOfOrdernessWatermarks, IngestionTimeAssigner, PunctuatedWatermarks,
+    SchemaTimestampAssigner, TimePeriodicWatermarks,
 };
 
 pub struct DefaultWatermarkStrategy {
@@ -55,11 +58,35 @@ impl DefaultWatermarkStrategy {
         }
     }
 
+    /// emit the watermarks of the `PunctuatedWatermarkAssigner` per record,
+    /// in addition to the periodic watermarks
+    pub fn wrap_punctuated<T>(mut self, assigner: T) -> Self
+    where
+        T: PunctuatedWatermarkAssigner + 'static,
+    {
+        if let Some(watermarks) = self.watermark_generator.take() {
+            self.watermark_generator = Some(Box::new(PunctuatedWatermarks::new(
+                watermarks,
+                Box::new(assigner),
+            )));
+            self
+        } else {
+            panic!("no WatermarkGenerator for wrapper");
+        }
+    }
+
     pub fn for_schema_timestamp_assigner<T: ColumnLocateBuilder>(mut self, column: T) -> Self {
         self.timestamp_assigner = Some(Box::new(SchemaTimestampAssigner::new(column)));
         self
     }
 
+    /// the ingestion time mode, the records are stamped by the coordinated clock,
+    /// see `IngestionTimeAssigner`
+    pub fn for_ingestion_time(mut self) -> Self {
+        self.timestamp_assigner = Some(Box::new(IngestionTimeAssigner::new()));
+        self
+    }
+
     pub fn for_watermark_generator<T>(mut self, generator: T) -> Self
     where
         T: WatermarkGenerator + 'static,
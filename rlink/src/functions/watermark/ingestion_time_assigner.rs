@@ -0,0 +1,33 @@
+use crate::core::element::Record;
+use crate::core::function::Context;
+use crate::core::watermark::TimestampAssigner;
+use crate::utils::date_time::coordinated_timestamp_millis;
+
+/// Assign the ingestion time to the records, the time the record enters the stream. The time is
+/// read from the coordinator's clock synced by the heartbeats instead of the host's clock, so the
+/// records ingested by the tasks of the different workers get the consistent timestamps even if
+/// the clocks of the hosts are skewed.
+/// The timestamps of a task never go backwards, the clock may be adjusted by the sync.
+#[derive(Debug, Default)]
+pub struct IngestionTimeAssigner {
+    latest_timestamp: u64,
+}
+
+impl IngestionTimeAssigner {
+    pub fn new() -> Self {
+        IngestionTimeAssigner {
+            latest_timestamp: 0,
+        }
+    }
+}
+
+impl TimestampAssigner for IngestionTimeAssigner {
+    fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn extract_timestamp(&mut self, _row: &mut Record, _previous_element_timestamp: u64) -> u64 {
+        self.latest_timestamp = coordinated_timestamp_millis().max(self.latest_timestamp);
+        self.latest_timestamp
+    }
+}
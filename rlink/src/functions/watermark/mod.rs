@@ -1,6 +1,9 @@
 pub mod schema_timestamp_assigner;
 pub use schema_timestamp_assigner::SchemaTimestampAssigner;
 
+pub mod ingestion_time_assigner;
+pub use ingestion_time_assigner::IngestionTimeAssigner;
+
 pub mod bounded_out_of_orderness_watermarks;
 pub use bounded_out_of_orderness_watermarks::BoundedOutOfOrdernessWatermarks;
 
@@ -9,5 +12,8 @@ pub use time_periodic_watermarks::TimePeriodicWatermarks;
 
 pub mod watermarks_with_idleness;
 
+pub mod punctuated_watermarks;
+pub use punctuated_watermarks::PunctuatedWatermarks;
+
 pub mod default_watermark_strategy;
 pub use default_watermark_strategy::DefaultWatermarkStrategy;
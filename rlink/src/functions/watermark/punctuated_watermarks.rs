@@ -0,0 +1,119 @@
+use crate::core::element::Record;
+use crate::core::watermark::{
+    PunctuatedWatermarkAssigner, Watermark, WatermarkGenerator, MAX_WATERMARK,
+};
+
+/// A `WatermarkGenerator` that adds punctuated watermarks to another `WatermarkGenerator`.
+/// The `PunctuatedWatermarkAssigner` is called for every record and its watermark is emitted
+/// immediately, the periodic watermarks are still generated by the wrapped generator
+/// and never fall behind the latest punctuated watermark.
+#[derive(Debug)]
+pub struct PunctuatedWatermarks {
+    watermarks: Box<dyn WatermarkGenerator>,
+    assigner: Box<dyn PunctuatedWatermarkAssigner>,
+    punctuated_watermark: Option<Watermark>,
+}
+
+impl PunctuatedWatermarks {
+    pub fn new(
+        watermarks: Box<dyn WatermarkGenerator>,
+        assigner: Box<dyn PunctuatedWatermarkAssigner>,
+    ) -> Self {
+        PunctuatedWatermarks {
+            watermarks,
+            assigner,
+            punctuated_watermark: None,
+        }
+    }
+
+    fn max_watermark(&self, watermark: Option<Watermark>) -> Option<Watermark> {
+        match (watermark, self.punctuated_watermark) {
+            // special `Watermark`, don't need to compare
+            (Some(w), _) if w.timestamp > MAX_WATERMARK.timestamp => Some(w),
+            (Some(w), Some(p)) if p.timestamp > w.timestamp => Some(p),
+            (Some(w), _) => Some(w),
+            (None, p) => p,
+        }
+    }
+}
+
+impl WatermarkGenerator for PunctuatedWatermarks {
+    fn on_event(&mut self, record: &mut Record, event_timestamp: u64) -> Option<Watermark> {
+        let watermark = self.watermarks.on_event(record, event_timestamp);
+
+        let punctuated = self
+            .assigner
+            .check_and_get_next_watermark(record, event_timestamp)
+            .filter(|p| match &self.punctuated_watermark {
+                Some(w) => p.timestamp > w.timestamp,
+                None => true,
+            });
+
+        if punctuated.is_some() {
+            self.punctuated_watermark = punctuated;
+        } else if watermark.is_none() {
+            return None;
+        }
+
+        self.max_watermark(watermark)
+    }
+
+    fn on_periodic_emit(&mut self) -> Option<Watermark> {
+        let watermark = self.watermarks.on_periodic_emit();
+        self.max_watermark(watermark)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::element::Record;
+    use crate::core::watermark::{PunctuatedWatermarkAssigner, Watermark, WatermarkGenerator};
+    use crate::functions::watermark::punctuated_watermarks::PunctuatedWatermarks;
+    use crate::functions::watermark::BoundedOutOfOrdernessWatermarks;
+
+    /// emit a watermark for every record with the timestamp multiple of 100
+    #[derive(Debug)]
+    struct HundredPunctuatedAssigner {}
+
+    impl PunctuatedWatermarkAssigner for HundredPunctuatedAssigner {
+        fn check_and_get_next_watermark(
+            &mut self,
+            _record: &mut Record,
+            extracted_timestamp: u64,
+        ) -> Option<Watermark> {
+            if extracted_timestamp % 100 == 0 {
+                Some(Watermark::new(extracted_timestamp))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    pub fn punctuated_watermarks_test() {
+        let mut watermarks = PunctuatedWatermarks::new(
+            Box::new(BoundedOutOfOrdernessWatermarks::new(Duration::from_millis(
+                50,
+            ))),
+            Box::new(HundredPunctuatedAssigner {}),
+        );
+
+        let mut record = Record::new();
+        assert_eq!(watermarks.on_event(&mut record, 90), None);
+        assert_eq!(
+            watermarks.on_event(&mut record, 100),
+            Some(Watermark::new(100))
+        );
+        assert_eq!(watermarks.on_event(&mut record, 120), None);
+        // the periodic watermark `120 - 50 - 1` is behind the punctuated watermark
+        assert_eq!(watermarks.on_periodic_emit(), Some(Watermark::new(100)));
+
+        assert_eq!(
+            watermarks.on_event(&mut record, 300),
+            Some(Watermark::new(300))
+        );
+        assert_eq!(watermarks.on_periodic_emit(), Some(Watermark::new(300)));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::core::data_types::Schema;
+use crate::core::data_types::{DataType, Schema};
 use crate::core::element::Record;
 use crate::core::function::Context;
 use crate::core::watermark::TimestampAssigner;
@@ -9,6 +9,8 @@ pub struct SchemaTimestampAssigner {
     schema: Schema,
     column_locate: ColumnLocate,
     column_index: usize,
+    /// the `UInt64` millis, or the `Int64`/`TimestampTz` millis since the unix epoch
+    signed: bool,
 }
 
 impl SchemaTimestampAssigner {
@@ -17,6 +19,7 @@ impl SchemaTimestampAssigner {
             schema: Schema::empty(),
             column_locate: column.build(),
             column_index: 0,
+            signed: false,
         }
     }
 }
@@ -25,14 +28,28 @@ impl TimestampAssigner for SchemaTimestampAssigner {
     fn open(&mut self, context: &Context) -> crate::core::Result<()> {
         self.schema = context.input_schema.first().clone();
 
-        let (index, _field) = self.column_locate.to_column(&self.schema);
+        let (index, field) = self.column_locate.to_column(&self.schema);
         self.column_index = index;
+        self.signed = match field.data_type() {
+            DataType::UInt64 => false,
+            DataType::Int64 | DataType::TimestampTz => true,
+            data_type => {
+                return Err(crate::core::Error::from(format!(
+                    "un-support timestamp DataType {:?}",
+                    data_type
+                )))
+            }
+        };
 
         Ok(())
     }
 
     fn extract_timestamp(&mut self, row: &mut Record, _previous_element_timestamp: u64) -> u64 {
         let reader = row.as_reader(self.schema.as_type_ids());
-        reader.get_u64(self.column_index).unwrap()
+        if self.signed {
+            reader.get_i64(self.column_index).unwrap().max(0) as u64
+        } else {
+            reader.get_u64(self.column_index).unwrap()
+        }
     }
 }
@@ -75,6 +75,15 @@ impl WindowAssigner for SlidingEventTimeWindows {
         windows.sort_by_key(|x| x.min_timestamp());
         windows
     }
+
+    /// the windows are split by the slide if the size is a multiple of it
+    fn pane_size(&self) -> Option<u64> {
+        if self.size > self.slide && self.size.is_multiple_of(self.slide) {
+            Some(self.slide)
+        } else {
+            None
+        }
+    }
 }
 
 impl NamedFunction for SlidingEventTimeWindows {
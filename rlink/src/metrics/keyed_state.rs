@@ -0,0 +1,184 @@
+//! The metrics of the keyed state for the skew debugging, published by the reduce tasks and
+//! surfaced by the worker's `/api/state/keyed`
+
+use crate::core::element::Record;
+use crate::core::runtime::{OperatorId, TaskId};
+use crate::utils;
+
+/// the number of the metric buckets of the key groups, a bucket is a contiguous range of the
+/// key groups, see `utils::hash::key_group`
+pub const KEY_GROUPS: u16 = 128;
+
+lazy_static! {
+    static ref KEYED_STATE_SUMMARIES: dashmap::DashMap<(OperatorId, TaskId), KeyedStateSummary> =
+        dashmap::DashMap::new();
+}
+
+pub fn key_group(key: &Record) -> u16 {
+    let key_group = utils::hash::key_group(key.values.as_slice());
+    utils::hash::key_group_partition(key_group, KEY_GROUPS)
+}
+
+/// The number of the distinct keys and the state bytes of each key group
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyGroupStats {
+    pub keys: usize,
+    /// the bytes of the keys and values, indexed by the key group
+    pub key_group_bytes: Vec<u64>,
+}
+
+impl Default for KeyGroupStats {
+    fn default() -> Self {
+        KeyGroupStats {
+            keys: 0,
+            key_group_bytes: vec![0; KEY_GROUPS as usize],
+        }
+    }
+}
+
+impl KeyGroupStats {
+    pub fn state_bytes(&self) -> u64 {
+        self.key_group_bytes.iter().sum()
+    }
+
+    pub fn max_key_group_bytes(&self) -> u64 {
+        self.key_group_bytes
+            .iter()
+            .max()
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HotKey {
+    pub key: String,
+    /// the estimated number of the records since the previous publishing
+    pub count: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyedStateSummary {
+    pub operator_id: OperatorId,
+    pub task_id: TaskId,
+    pub stats: KeyGroupStats,
+    pub hot_keys: Vec<HotKey>,
+    /// the timestamp of publishing
+    pub timestamp: u64,
+}
+
+pub(crate) fn publish_keyed_state_summary(summary: KeyedStateSummary) {
+    KEYED_STATE_SUMMARIES.insert((summary.operator_id, summary.task_id), summary);
+}
+
+/// the summaries of all reduce tasks in the worker, sorted by the operator and the task
+pub fn get_keyed_state_summaries() -> Vec<KeyedStateSummary> {
+    let mut summaries: Vec<KeyedStateSummary> = KEYED_STATE_SUMMARIES
+        .iter()
+        .map(|entry| entry.value().clone())
+        .collect();
+    summaries.sort_by_key(|x| (x.operator_id, x.task_id.job_id, x.task_id.task_number));
+    summaries
+}
+
+const SKETCH_DEPTH: usize = 4;
+const SKETCH_WIDTH: usize = 1024;
+
+/// Sample the top-K most frequent keys with a count-min sketch, the counters are halved at
+/// each `decay`, so the sample follows the recent traffic
+#[derive(Debug)]
+pub(crate) struct HotKeySketch {
+    counters: Vec<u32>,
+    top_k: usize,
+    hot_keys: Vec<(Record, u32)>,
+}
+
+impl HotKeySketch {
+    pub fn new(top_k: usize) -> Self {
+        HotKeySketch {
+            counters: vec![0; SKETCH_DEPTH * SKETCH_WIDTH],
+            top_k,
+            hot_keys: Vec::with_capacity(top_k),
+        }
+    }
+
+    /// count the key and return the estimated frequency
+    fn increment(&mut self, key: &Record) -> u32 {
+        let hash_code = utils::hash::hash_code(key.values.as_slice()).unwrap_or(0);
+        // the double hashing, the rows are indexed by `h1 + row * h2`
+        let h1 = hash_code as usize;
+        let h2 = (hash_code.rotate_left(16) | 1) as usize;
+
+        let mut estimate = u32::MAX;
+        for row in 0..SKETCH_DEPTH {
+            let column = h1.wrapping_add(row.wrapping_mul(h2)) % SKETCH_WIDTH;
+            let counter = &mut self.counters[row * SKETCH_WIDTH + column];
+            *counter = counter.saturating_add(1);
+            estimate = estimate.min(*counter);
+        }
+        estimate
+    }
+
+    pub fn add(&mut self, key: &Record) {
+        let estimate = self.increment(key);
+
+        if let Some(hot_key) = self.hot_keys.iter_mut().find(|(x, _)| x.eq(key)) {
+            hot_key.1 = estimate;
+        } else if self.hot_keys.len() < self.top_k {
+            self.hot_keys.push((key.clone(), estimate));
+        } else if let Some(coldest) = self.hot_keys.iter_mut().min_by_key(|(_, count)| *count) {
+            if estimate > coldest.1 {
+                *coldest = (key.clone(), estimate);
+            }
+        }
+    }
+
+    /// the sampled keys in the descending order of the frequency
+    pub fn hot_keys(&self) -> Vec<(Record, u32)> {
+        let mut hot_keys = self.hot_keys.clone();
+        hot_keys.sort_by_key(|x| std::cmp::Reverse(x.1));
+        hot_keys
+    }
+
+    pub fn decay(&mut self) {
+        self.counters.iter_mut().for_each(|x| *x >>= 1);
+        self.hot_keys.iter_mut().for_each(|x| x.1 >>= 1);
+        self.hot_keys.retain(|x| x.1 > 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serbuffer::types;
+
+    use crate::core::element::Record;
+    use crate::metrics::keyed_state::HotKeySketch;
+
+    fn key(value: u64) -> Record {
+        let mut key = Record::with_capacity(8);
+        key.as_writer(&[types::U64]).set_u64(value).unwrap();
+        key
+    }
+
+    #[test]
+    pub fn hot_key_sketch_test() {
+        let mut sketch = HotKeySketch::new(2);
+        for i in 0..1000 {
+            sketch.add(&key(i % 100));
+            if i % 4 == 0 {
+                sketch.add(&key(1000));
+            }
+            if i % 8 == 0 {
+                sketch.add(&key(2000));
+            }
+        }
+
+        let hot_keys = sketch.hot_keys();
+        assert_eq!(hot_keys[0].0, key(1000));
+        assert_eq!(hot_keys[1].0, key(2000));
+        assert!(hot_keys[0].1 >= 250);
+
+        sketch.decay();
+        assert!(sketch.hot_keys()[0].1 >= 125);
+    }
+}
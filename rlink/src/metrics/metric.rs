@@ -198,6 +198,7 @@ pub trait Exporter {
 lazy_static! {
     static ref RECORDER: Recorder = Recorder::new();
     static ref MANAGER_ID: RwLock<Option<String>> = RwLock::new(None);
+    static ref GLOBAL_TAGS: RwLock<Vec<Tag>> = RwLock::new(Vec::new());
 }
 
 pub(crate) fn set_manager_id(manager_id: String) {
@@ -212,6 +213,21 @@ fn get_manager_id() -> String {
     (*n).as_ref().unwrap().clone()
 }
 
+/// the tags attached to all metrics of the process on the export, eg: the application labels.
+/// the metrics registered before are tagged as well
+pub(crate) fn set_global_tags(tags: Vec<Tag>) {
+    let mut global_tags = GLOBAL_TAGS.write().unwrap();
+    *global_tags = tags;
+}
+
+pub(crate) fn global_labels() -> Vec<(String, String)> {
+    let global_tags = GLOBAL_TAGS.read().unwrap();
+    global_tags
+        .iter()
+        .map(|Tag(field_name, field_value)| (field_name.clone(), field_value.clone()))
+        .collect()
+}
+
 pub fn register_counter<K>(name: K, tags: Vec<Tag>) -> Counter
 where
     K: ToString,
@@ -240,14 +256,16 @@ where
     recorder.register_gauge(name, tags)
 }
 
-struct MetricsExporter {}
+struct MetricsExporter {
+    global_labels: Vec<(String, String)>,
+}
 
 impl Exporter for MetricsExporter {
     fn render_counters(&mut self, counters: Vec<(KeyTags, u64)>) {
         for (key_tags, value) in counters {
             let KeyTags { name, tags } = key_tags;
 
-            let mut labels = Vec::new();
+            let mut labels = self.global_labels.clone();
             for tag in tags {
                 let Tag(field_name, field_value) = tag;
                 labels.push((field_name, field_value));
@@ -260,7 +278,7 @@ impl Exporter for MetricsExporter {
         for (key_tags, value) in guavas {
             let KeyTags { name, tags } = key_tags;
 
-            let mut labels = Vec::new();
+            let mut labels = self.global_labels.clone();
             for tag in tags {
                 let Tag(field_name, field_value) = tag;
                 labels.push((field_name, field_value));
@@ -279,7 +297,9 @@ pub(crate) fn export() {
     });
 
     let recorder: &Recorder = &*RECORDER;
-    recorder.export(MetricsExporter {});
+    recorder.export(MetricsExporter {
+        global_labels: global_labels(),
+    });
 }
 
 //use std::collections::hash_map::Iter;
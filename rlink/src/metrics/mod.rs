@@ -7,6 +7,7 @@ use rand::prelude::*;
 
 use crate::metrics::prometheus_exporter::PrometheusBuilder;
 
+pub mod keyed_state;
 pub mod metric;
 mod prometheus_exporter;
 mod worker_proxy;
@@ -1,10 +1,15 @@
+use std::time::Duration;
+
 pub mod memory;
 pub mod network;
+pub mod shuffle;
 
 pub(crate) const DEFAULT_CHANNEL_SIZE: usize = 10240;
+pub(crate) const DEFAULT_BUFFER_TIMEOUT: Duration = Duration::from_secs(3);
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum ChannelType {
     Memory = 1,
     Network = 2,
+    Shuffle = 3,
 }
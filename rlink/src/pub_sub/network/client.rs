@@ -21,12 +21,14 @@ use crate::channel::{
     TryRecvError, TrySendError,
 };
 use crate::core::element::Element;
-use crate::core::properties::ChannelBaseOn;
+use crate::core::properties::{ChannelBaseOn, SystemProperties};
 use crate::core::runtime::{ChannelKey, ClusterDescriptor, TaskId};
 use crate::metrics::{register_counter, Tag};
 use crate::pub_sub::network::{
-    new_framed_read, new_framed_write, ElementRequest, ElementResponse, ResponseCode,
+    network_channel_base, new_framed_read, new_framed_write, ElementRequest, ElementResponse,
+    ResponseCode,
 };
+use crate::pub_sub::DEFAULT_BUFFER_TIMEOUT;
 use crate::runtime::worker::heart_beat::get_coordinator_status;
 use crate::utils::thread::{async_runtime_multi, async_sleep};
 
@@ -48,6 +50,18 @@ pub(crate) fn disable_log() {
 }
 
 const BATCH_PULL_SIZE: u16 = 6000;
+/// the batch with fewer elements is partially-filled, the next pull is delayed by the `buffer_timeout`
+const MIN_BATCH_SIZE: usize = 100;
+
+/// the delay of the next pull, the partially-filled batch is followed by a `buffer_timeout` delay
+/// to accumulate the elements, unless the batch is cut by a control element
+fn pull_delay(len: usize, control_cut: bool, buffer_timeout: Duration) -> Option<Duration> {
+    if len < MIN_BATCH_SIZE && !control_cut && !buffer_timeout.is_zero() {
+        Some(buffer_timeout)
+    } else {
+        None
+    }
+}
 
 lazy_static! {
     static ref C: (
@@ -70,7 +84,7 @@ pub(crate) fn subscribe(
             Tag::new("target_task_number", target_task_id.task_number),
         ],
         channel_size,
-        channel_base_on,
+        network_channel_base(channel_base_on),
     );
 
     for source_task_id in source_task_ids {
@@ -104,6 +118,21 @@ async fn subscribe_listen(cluster_descriptor: Arc<ClusterDescriptor>) {
         Receiver<(ChannelKey, ElementSender)>,
     ) = &*C;
 
+    let buffer_timeout = cluster_descriptor
+        .coordinator_manager
+        .application_properties
+        .get_buffer_timeout()
+        .unwrap_or(DEFAULT_BUFFER_TIMEOUT);
+    let control_priority = cluster_descriptor
+        .coordinator_manager
+        .application_properties
+        .get_network_control_priority()
+        .unwrap_or(false);
+    info!(
+        "network subscribe with buffer timeout {:?}, control priority {}",
+        buffer_timeout, control_priority
+    );
+
     let delay = Duration::from_millis(50);
     let mut idle_counter = 0usize;
     let mut join_handles = Vec::new();
@@ -117,7 +146,15 @@ async fn subscribe_listen(cluster_descriptor: Arc<ClusterDescriptor>) {
                     .expect("parse address error");
 
                 let join_handle = tokio::spawn(async move {
-                    loop_client_task(channel_key.clone(), sender, addr, BATCH_PULL_SIZE).await;
+                    loop_client_task(
+                        channel_key,
+                        sender,
+                        addr,
+                        BATCH_PULL_SIZE,
+                        buffer_timeout,
+                        control_priority,
+                    )
+                    .await;
                     channel_key
                 });
                 join_handles.push(join_handle);
@@ -153,9 +190,20 @@ async fn loop_client_task(
     sender: ElementSender,
     addr: SocketAddr,
     batch_pull_size: u16,
+    buffer_timeout: Duration,
+    control_priority: bool,
 ) {
     loop {
-        match client_task(channel_key, sender.clone(), addr, batch_pull_size).await {
+        match client_task(
+            channel_key,
+            sender.clone(),
+            addr,
+            batch_pull_size,
+            buffer_timeout,
+            control_priority,
+        )
+        .await
+        {
             Ok(_) => {
                 info!("client close({:?})", channel_key);
                 break;
@@ -177,8 +225,18 @@ async fn client_task(
     sender: ElementSender,
     addr: SocketAddr,
     batch_pull_size: u16,
+    buffer_timeout: Duration,
+    control_priority: bool,
 ) -> anyhow::Result<()> {
-    let mut client = Client::new(channel_key, sender.clone(), addr, batch_pull_size).await?;
+    let mut client = Client::new(
+        channel_key,
+        sender.clone(),
+        addr,
+        batch_pull_size,
+        buffer_timeout,
+        control_priority,
+    )
+    .await?;
     let rt = client.send().await;
     client.close_rough().await;
 
@@ -191,6 +249,8 @@ pub(crate) struct Client {
 
     pub(crate) addr: SocketAddr,
     batch_pull_size: u16,
+    buffer_timeout: Duration,
+    control_priority: bool,
     stream: TcpStream,
 }
 
@@ -200,6 +260,8 @@ impl Client {
         sender: ElementSender,
         addr: SocketAddr,
         batch_pull_size: u16,
+        buffer_timeout: Duration,
+        control_priority: bool,
     ) -> anyhow::Result<Self> {
         let std_stream = std::net::TcpStream::connect(addr)?;
         std_stream.set_nonblocking(true)?;
@@ -213,6 +275,8 @@ impl Client {
             sender,
             addr,
             batch_pull_size,
+            buffer_timeout,
+            control_priority,
             stream,
         })
     }
@@ -238,6 +302,7 @@ impl Client {
                 channel_key: self.channel_key.clone(),
                 batch_pull_size: self.batch_pull_size,
                 batch_id,
+                control_priority: self.control_priority,
             };
 
             let (n, _) = batch_id.overflowing_add(1);
@@ -261,6 +326,9 @@ impl Client {
             .await??;
 
             let len = element_list.len();
+            // the batch cut by a control element is followed by the pending elements
+            let control_cut = self.control_priority
+                && element_list.back().map(|x| !x.is_record()).unwrap_or(false);
             if len > 0 {
                 for element in element_list {
                     debug!("receive remote element: {:?}", element);
@@ -272,8 +340,8 @@ impl Client {
 
                 counter.fetch_add(len as u64);
             }
-            if len < 100 {
-                async_sleep(Duration::from_secs(3)).await;
+            if let Some(delay) = pull_delay(len, control_cut, self.buffer_timeout) {
+                async_sleep(delay).await;
             }
         }
     }
@@ -392,10 +460,12 @@ async fn send_to_channel(sender: &ElementSender, element: Element) -> anyhow::Re
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use crate::channel::named_channel;
     use crate::core::element::Element;
     use crate::core::runtime::{ChannelKey, JobId, TaskId};
-    use crate::pub_sub::network::client::Client;
+    use crate::pub_sub::network::client::{pull_delay, Client, MIN_BATCH_SIZE};
 
     #[tokio::test]
     pub async fn client_test() {
@@ -420,8 +490,33 @@ mod tests {
 
         let addr = "127.0.0.1:28820".parse().unwrap();
 
-        let mut client = Client::new(channel_key, sender, addr, 100).await.unwrap();
+        let mut client = Client::new(
+            channel_key,
+            sender,
+            addr,
+            100,
+            Duration::from_secs(3),
+            false,
+        )
+        .await
+        .unwrap();
         client.send().await.unwrap();
         client.close().await.unwrap();
     }
+
+    #[test]
+    pub fn pull_delay_test() {
+        let buffer_timeout = Duration::from_millis(50);
+
+        // the partial batch is flushed after the `buffer_timeout`
+        assert_eq!(pull_delay(0, false, buffer_timeout), Some(buffer_timeout));
+        assert_eq!(
+            pull_delay(MIN_BATCH_SIZE - 1, false, buffer_timeout),
+            Some(buffer_timeout)
+        );
+
+        assert_eq!(pull_delay(MIN_BATCH_SIZE, false, buffer_timeout), None);
+        assert_eq!(pull_delay(1, true, buffer_timeout), None);
+        assert_eq!(pull_delay(1, false, Duration::from_millis(0)), None);
+    }
 }
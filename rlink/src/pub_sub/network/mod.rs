@@ -6,6 +6,7 @@ use tokio::net::tcp::{ReadHalf, WriteHalf};
 use tokio_util::codec::{BytesCodec, FramedRead, FramedWrite, LengthDelimitedCodec};
 
 use crate::core::element::{Element, Serde};
+use crate::core::properties::ChannelBaseOn;
 use crate::core::runtime::ChannelKey;
 
 pub(crate) mod client;
@@ -16,14 +17,25 @@ pub(crate) use client::subscribe;
 pub(crate) use server::publish;
 pub(crate) use server::Server;
 
+/// the `Ring` is for the memory edges only, the network channels are fed by the async tasks
+/// without a fixed cardinality, fallback to the `Bounded`
+pub(crate) fn network_channel_base(channel_base_on: ChannelBaseOn) -> ChannelBaseOn {
+    match channel_base_on {
+        ChannelBaseOn::Ring => ChannelBaseOn::Bounded,
+        _ => channel_base_on,
+    }
+}
+
 const HEADER_LEN: usize = 4usize;
-const REQUEST_BODY_LEN: usize = 20;
+const REQUEST_BODY_LEN: usize = 21;
 
 #[derive(Clone, Debug)]
 pub struct ElementRequest {
     channel_key: ChannelKey,
     batch_pull_size: u16,
     batch_id: u16,
+    /// cut the batch after a control element, see `SystemProperties::set_network_control_priority`
+    control_priority: bool,
 }
 
 impl Into<BytesMut> for ElementRequest {
@@ -35,6 +47,7 @@ impl Into<BytesMut> for ElementRequest {
         self.channel_key.serialize(buffer.borrow_mut());
         buffer.put_u16(self.batch_pull_size);
         buffer.put_u16(self.batch_id);
+        buffer.put_u8(self.control_priority as u8);
 
         assert_eq!(buffer.len(), PACKAGE_LEN);
         buffer
@@ -59,11 +72,13 @@ impl TryFrom<BytesMut> for ElementRequest {
         let channel_key = ChannelKey::deserialize(buffer.borrow_mut());
         let batch_pull_size = buffer.get_u16();
         let batch_id = buffer.get_u16();
+        let control_priority = buffer.get_u8() != 0;
 
         Ok(ElementRequest {
             channel_key,
             batch_pull_size,
             batch_id,
+            control_priority,
         })
     }
 }
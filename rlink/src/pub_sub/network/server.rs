@@ -21,7 +21,8 @@ use crate::core::element::Element;
 use crate::core::properties::ChannelBaseOn;
 use crate::core::runtime::{ChannelKey, TaskId};
 use crate::pub_sub::network::{
-    new_framed_read, new_framed_write, ElementRequest, ElementResponse, ResponseCode,
+    network_channel_base, new_framed_read, new_framed_write, ElementRequest, ElementResponse,
+    ResponseCode,
 };
 use crate::utils::thread::{async_runtime, async_runtime_single};
 
@@ -64,7 +65,7 @@ pub(crate) fn publish(
             "NetworkPublish",
             channel_key.to_tags(),
             channel_size,
-            channel_base_on,
+            network_channel_base(channel_base_on),
         );
 
         senders.push((channel_key.clone(), sender));
@@ -244,11 +245,17 @@ impl Server {
             channel_key,
             batch_pull_size,
             batch_id: _,
+            control_priority,
         } = request;
 
-        let element_list = self.batch_get(&channel_key, batch_pull_size);
+        let element_list = self.batch_get(&channel_key, batch_pull_size, control_priority);
         let len = self
-            .batch_send(element_list, batch_pull_size, framed_write)
+            .batch_send(
+                element_list,
+                batch_pull_size,
+                control_priority,
+                framed_write,
+            )
             .await?;
 
         if is_enable_log() {
@@ -262,9 +269,16 @@ impl Server {
     }
 
     /// batch get elements by `ChannelKey`
-    fn batch_get(&self, channel_key: &ChannelKey, batch_pull_size: u16) -> LinkedList<Element> {
+    fn batch_get(
+        &self,
+        channel_key: &ChannelKey,
+        batch_pull_size: u16,
+        control_priority: bool,
+    ) -> LinkedList<Element> {
         match get_network_channel(&channel_key) {
-            Some(receiver) => self.batch_receive(channel_key, batch_pull_size, receiver),
+            Some(receiver) => {
+                self.batch_receive(channel_key, batch_pull_size, control_priority, receiver)
+            }
             None => {
                 // The child's job are subscribed to before the channel is initialized.
                 // This is not a mistake, the child's job will retry repeatedly.
@@ -277,18 +291,24 @@ impl Server {
         }
     }
 
-    /// batch receive from `ElementReceiver`
+    /// batch receive from `ElementReceiver`,
+    /// the batch is cut after a control element if `control_priority`
     fn batch_receive(
         &self,
         channel_key: &ChannelKey,
         batch_pull_size: u16,
+        control_priority: bool,
         receiver: ElementReceiver,
     ) -> LinkedList<Element> {
         let mut element_list = LinkedList::new();
         for _ in 0..batch_pull_size {
             match receiver.try_recv() {
                 Ok(element) => {
+                    let is_control = !element.is_record();
                     element_list.push_back(element);
+                    if control_priority && is_control {
+                        break;
+                    }
                 }
                 Err(TryRecvError::Empty) => {
                     break;
@@ -303,17 +323,24 @@ impl Server {
         element_list
     }
 
-    /// send batch response to client
+    /// send batch response to client. with `control_priority` the records are buffered and
+    /// a control element is flushed at once, otherwise every element is flushed
     async fn batch_send(
         &self,
         element_list: LinkedList<Element>,
         batch_pull_size: u16,
+        control_priority: bool,
         framed_write: &mut FramedWrite<WriteHalf<'_>, BytesCodec>,
     ) -> Result<usize, std::io::Error> {
         let len = element_list.len();
         for element in element_list {
-            self.send(ElementResponse::ok(element), framed_write)
-                .await?;
+            if control_priority && element.is_record() {
+                let req: BytesMut = ElementResponse::ok(element).into();
+                framed_write.feed(req.freeze()).await?;
+            } else {
+                self.send(ElementResponse::ok(element), framed_write)
+                    .await?;
+            }
         }
 
         let status_code_response = if len == batch_pull_size as usize {
@@ -340,3 +367,37 @@ impl Server {
         format!("{}:{}", addr.ip().to_string(), addr.port())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::channel::named_channel;
+    use crate::core::element::{Element, Record};
+    use crate::core::runtime::{ChannelKey, CheckpointId, JobId, TaskId};
+    use crate::metrics::metric::set_manager_id;
+    use crate::pub_sub::network::server::Server;
+
+    #[test]
+    pub fn batch_receive_control_priority_test() {
+        set_manager_id("test".to_string());
+        let (sender, receiver) = named_channel::<Element>("test", vec![], 10);
+        sender.send(Element::Record(Record::new())).unwrap();
+        sender.send(Element::new_barrier(CheckpointId(1))).unwrap();
+        sender.send(Element::Record(Record::new())).unwrap();
+
+        let channel_key = ChannelKey {
+            source_task_id: TaskId::default(),
+            target_task_id: TaskId {
+                job_id: JobId(1),
+                task_number: 0,
+                num_tasks: 1,
+            },
+        };
+        let server = Server::new("127.0.0.1".to_string());
+        let batch = server.batch_receive(&channel_key, 100, true, receiver.clone());
+        assert_eq!(batch.len(), 2);
+        assert!(batch.back().unwrap().is_barrier());
+
+        let batch = server.batch_receive(&channel_key, 100, true, receiver);
+        assert_eq!(batch.len(), 1);
+    }
+}
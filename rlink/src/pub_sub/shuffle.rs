@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::channel::{named_channel_with_base, ElementReceiver};
+use crate::core::properties::ChannelBaseOn;
+use crate::core::runtime::{ChannelKey, TaskId};
+use crate::core::shuffle::{ShuffleService, ShuffleWriter};
+use crate::metrics::Tag;
+use crate::pub_sub::network::network_channel_base;
+
+/// the interval of polling the unfinished upstream tasks
+const READ_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// create a writer for each child job of the `source_task_id`
+pub(crate) fn publish(
+    shuffle_service: &Arc<dyn ShuffleService>,
+    application_id: &str,
+    source_task_id: &TaskId,
+    target_task_ids: &[TaskId],
+) -> crate::core::Result<Box<dyn ShuffleWriter>> {
+    let target_job_id = target_task_ids[0].job_id;
+    let num_partitions = target_task_ids[0].num_tasks;
+    info!(
+        "shuffle publish {:?} to {:?} with {} partitions",
+        source_task_id, target_job_id, num_partitions
+    );
+    shuffle_service.create_writer(
+        application_id,
+        *source_task_id,
+        target_job_id,
+        num_partitions,
+    )
+}
+
+/// read the outputs of the `source_task_ids` one by one in a dedicated thread,
+/// waiting for the unfinished upstream tasks
+pub(crate) fn subscribe(
+    shuffle_service: &Arc<dyn ShuffleService>,
+    application_id: &str,
+    source_task_ids: &[TaskId],
+    target_task_id: &TaskId,
+    channel_size: usize,
+    channel_base_on: ChannelBaseOn,
+) -> crate::core::Result<ElementReceiver> {
+    let (sender, receiver) = named_channel_with_base(
+        "ShuffleSubscribe",
+        vec![
+            Tag::new("source_job_id", source_task_ids[0].job_id.0),
+            Tag::new("target_job_id", target_task_id.job_id.0),
+            Tag::new("target_task_number", target_task_id.task_number),
+        ],
+        channel_size,
+        network_channel_base(channel_base_on),
+    );
+
+    let mut readers = Vec::new();
+    for source_task_id in source_task_ids {
+        let channel_key = ChannelKey {
+            source_task_id: *source_task_id,
+            target_task_id: *target_task_id,
+        };
+        let reader =
+            shuffle_service.create_reader(application_id, *source_task_id, *target_task_id)?;
+        readers.push((channel_key, reader));
+    }
+
+    crate::utils::thread::spawn("shuffle-subscribe", move || {
+        for (channel_key, mut reader) in readers {
+            let elements = loop {
+                match reader.read() {
+                    Ok(Some(elements)) => break elements,
+                    Ok(None) => std::thread::sleep(READ_RETRY_INTERVAL),
+                    Err(e) => panic!("read shuffle {:?} error. {}", channel_key, e),
+                }
+            };
+
+            info!("shuffle subscribe {:?} begin", channel_key);
+            for mut element in elements {
+                element.set_channel_key(channel_key);
+                sender.send(element).unwrap();
+            }
+            info!("shuffle subscribe {:?} finish", channel_key);
+        }
+    });
+
+    Ok(receiver)
+}
@@ -1,22 +1,31 @@
 use std::borrow::{Borrow, BorrowMut};
 use std::net::SocketAddr;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Duration;
 
 use crate::core::env::{StreamApp, StreamExecutionEnvironment};
+use crate::core::properties::SystemProperties;
 use crate::core::runtime::{ClusterDescriptor, ManagerStatus, WorkerManagerDescriptor};
 use crate::dag::metadata::DagMetadata;
 use crate::pub_sub::network;
 use crate::runtime::context::Context;
 use crate::runtime::timer::{start_window_timer, WindowTimer};
+use crate::runtime::worker::cache::prepare_cached_files;
+use crate::runtime::worker::cancellation::{self, DEFAULT_CANCELLATION_GRACE_PERIOD};
 use crate::runtime::worker::checkpoint::start_report_checkpoint;
-use crate::runtime::worker::heart_beat::{start_heartbeat_timer, submit_heartbeat};
+use crate::runtime::worker::drain::{start_drain_on_sigterm, DEFAULT_DRAIN_TIMEOUT};
+use crate::runtime::worker::executor::TaskExecutor;
+use crate::runtime::worker::heart_beat::{
+    start_heartbeat_timer, submit_heartbeat, HEARTBEAT_INTERVAL,
+};
 use crate::runtime::worker::web_server::web_launch;
-use crate::runtime::{worker, HeartBeatStatus, HeartbeatItem};
+use crate::runtime::{init_application_labels, worker, HeartBeatStatus, HeartbeatItem};
 use crate::storage::metadata::MetadataLoader;
 use crate::utils;
+use crate::utils::process::ResourceUsageCollector;
 use crate::utils::thread::async_runtime_single;
 
 pub(crate) fn run<S>(
@@ -32,6 +41,37 @@ where
     let cluster_descriptor = metadata_loader.get_cluster_descriptor();
     info!("preload `ClusterDescriptor`");
 
+    init_application_labels(
+        &cluster_descriptor
+            .coordinator_manager
+            .application_properties
+            .get_application_labels()
+            .unwrap_or_default(),
+    );
+
+    let drain_timeout = cluster_descriptor
+        .coordinator_manager
+        .application_properties
+        .get_worker_drain_timeout()
+        .unwrap_or(DEFAULT_DRAIN_TIMEOUT);
+    start_drain_on_sigterm(drain_timeout);
+    info!("hook SIGTERM to drain the worker");
+
+    cancellation::set_grace_period(
+        cluster_descriptor
+            .coordinator_manager
+            .application_properties
+            .get_cancellation_grace_period()
+            .unwrap_or(DEFAULT_CANCELLATION_GRACE_PERIOD),
+    );
+    utils::hash::set_key_hasher(
+        cluster_descriptor
+            .coordinator_manager
+            .application_properties
+            .get_key_hasher()
+            .unwrap_or_default(),
+    );
+
     let server_addr = bootstrap_publish_serve(context.bind_ip.to_string());
     info!("bootstrap publish server, listen: {}", server_addr);
 
@@ -50,6 +90,9 @@ where
     let dag_metadata = load_dag_metadata(metadata_loader.borrow_mut());
     info!("load dag metadata success");
 
+    prepare_cached_files(cluster_descriptor.deref())?;
+    info!("prepare cached files success");
+
     bootstrap_subscribe_client(cluster_descriptor.clone());
     info!("bootstrap subscribe client");
 
@@ -64,7 +107,11 @@ where
     info!("all task has bootstrap");
 
     join_handles.into_iter().for_each(|join_handle| {
-        join_handle.join().unwrap();
+        if let Err(e) = join_handle.join() {
+            // wait the failure of the task and the `Panic` status reported to the coordinator
+            std::thread::sleep(HEARTBEAT_INTERVAL);
+            std::panic::resume_unwind(e);
+        }
     });
 
     stop_heartbeat_timer();
@@ -120,6 +167,20 @@ fn start_timing_task(
     submit_heartbeat(HeartbeatItem::WorkerManagerAddress(bind_addr.to_string()));
     submit_heartbeat(HeartbeatItem::MetricsAddress(context.metric_addr.clone()));
 
+    let spill_dir = cluster_descriptor
+        .coordinator_manager
+        .application_properties
+        .get_sort_spill_dir()
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| std::env::temp_dir());
+    crate::utils::thread::spawn("resource_usage", move || {
+        let mut collector = ResourceUsageCollector::new(vec![spill_dir]);
+        loop {
+            submit_heartbeat(HeartbeatItem::ResourceUsage(collector.collect()));
+            std::thread::sleep(HEARTBEAT_INTERVAL);
+        }
+    });
+
     let coordinator_address = cluster_descriptor.coordinator_manager.web_address.clone();
     let task_manager_id = context.task_manager_id.clone();
 
@@ -175,6 +236,12 @@ where
     let task_manager_descriptors =
         get_worker_manager_descriptor(task_manager_id, cluster_descriptor.borrow()).unwrap();
 
+    let task_executor = TaskExecutor::new(
+        &cluster_descriptor
+            .coordinator_manager
+            .application_properties,
+    );
+
     task_manager_descriptors
         .task_descriptors
         .iter()
@@ -187,6 +254,7 @@ where
                 stream_app.clone(),
                 &stream_env,
                 window_timer.clone(),
+                &task_executor,
             )
         })
         .collect()
@@ -1,7 +1,9 @@
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use crate::core::checkpoint::SavepointPath;
 use crate::core::cluster::{load_config, ClusterConfig, MetadataStorageType};
 use crate::metrics::metric::set_manager_id;
 use crate::metrics::ProxyAddressLoader;
@@ -15,13 +17,21 @@ use crate::utils::process::{parse_arg, work_space};
 /// `manager_type`: `Coordinator` or `Worker`, generated by `StandaloneResourceManager`
 ///
 /// `Local` and `Coordinator` process args:
+///     `application_id`: optional, generated with timestamp if absent,
+///                       restart with the same `application_id` to recover from checkpoint
 ///     `bind_ip`: ignore, default with "0.0.0.0"
 ///     `task_manager_id`: ignore
-///     `num_task_managers`: ignore task manager size
+///     `num_task_managers`: optional, default 1. if more than 1, run as a mini cluster,
+///                          each task manager is launched as a child process
 ///     `cluster_config`: ignore
+///     `from_savepoint`: optional, `{application_id}/{checkpoint_id}`,
+///                       restore from the savepoint if there is no checkpoint of the application
+///     `labels`: optional, the application labels, eg: `tenant:acme,team:ads`,
+///               see `SystemProperties::set_application_labels`
 /// `Local` and `Worker` process args:
 ///     `bind_ip`: ignore, default with "0.0.0.0"
 ///     `task_manager_id`: task manager process id, generated by `Coordinator`
+///     `coordinator_address`: only for mini cluster, generated by `Coordinator`
 ///     `num_task_managers`: ignore
 ///     `cluster_config`: ignore
 ///
@@ -35,6 +45,8 @@ use crate::utils::process::{parse_arg, work_space};
 ///         `job_id`: job id, generated by `JobManager`
 ///         `task_manager_id`: ignore
 ///         `cluster_config`: cluster config path, generated by `TaskManager`
+///         `from_savepoint`: optional, same as `Local` mode, generated by `JobManager` when upgrade
+///         `labels`: optional, same as `Local` mode
 ///     `Worker` process args:
 ///         `cluster_mode`: must be `Standalone`
 ///         `manager_type`: must be `Worker`
@@ -60,6 +72,10 @@ pub(crate) struct Context {
     /// effective only in `Worker` mode
     pub coordinator_address: String,
     pub dashboard_path: String,
+    /// effective only in `Coordinator` mode
+    pub from_savepoint: Option<SavepointPath>,
+    /// effective only in `Coordinator` mode, the labels of the submission
+    pub labels: BTreeMap<String, String>,
 
     /// on yarn args
     pub yarn_manager_main_class: String,
@@ -84,6 +100,8 @@ impl Context {
         metric_addr: String,
         coordinator_address: String,
         dashboard_path: String,
+        from_savepoint: Option<SavepointPath>,
+        labels: BTreeMap<String, String>,
         yarn_manager_main_class: String,
         worker_process_path: String,
         memory_mb: u32,
@@ -102,6 +120,8 @@ impl Context {
             metric_addr,
             coordinator_address,
             dashboard_path,
+            from_savepoint,
+            labels,
             yarn_manager_main_class,
             worker_process_path,
             memory_mb,
@@ -125,7 +145,9 @@ impl Context {
         };
 
         let application_id = match cluster_mode {
-            ClusterMode::Local => utils::generator::gen_with_ts(),
+            ClusterMode::Local => {
+                parse_arg("application_id").unwrap_or_else(|_e| utils::generator::gen_with_ts())
+            }
             ClusterMode::Standalone | ClusterMode::YARN | ClusterMode::Kubernetes => {
                 parse_arg("application_id")?
             }
@@ -139,20 +161,12 @@ impl Context {
 
         let num_task_managers = match manager_type {
             ManagerType::Coordinator => match cluster_mode {
-                ClusterMode::Local => 1,
+                ClusterMode::Local => match parse_arg("num_task_managers") {
+                    Ok(_) => parse_num_task_managers()?,
+                    Err(_e) => 1,
+                },
                 ClusterMode::Standalone | ClusterMode::YARN | ClusterMode::Kubernetes => {
-                    let num_task_managers = parse_arg("num_task_managers")?;
-                    let num_task_managers =
-                        u32::from_str(num_task_managers.as_str()).map_err(|_e| {
-                            anyhow!(
-                                "parse `num_task_managers`=`{}` to u32 error",
-                                num_task_managers
-                            )
-                        })?;
-                    if num_task_managers < 1 {
-                        return Err(anyhow!("`num_task_managers` must the [value > 1]"));
-                    }
-                    num_task_managers
+                    parse_num_task_managers()?
                 }
             },
             _ => 0,
@@ -255,6 +269,22 @@ impl Context {
             _ => parse_arg("coordinator_address")?,
         };
 
+        let from_savepoint = match manager_type {
+            ManagerType::Coordinator => match parse_arg("from_savepoint") {
+                Ok(path) => Some(SavepointPath::try_from(path.as_str())?),
+                Err(_e) => None,
+            },
+            _ => None,
+        };
+
+        let labels = match manager_type {
+            ManagerType::Coordinator => match parse_arg("labels") {
+                Ok(labels) => parse_labels(labels.as_str())?,
+                Err(_e) => BTreeMap::new(),
+            },
+            _ => BTreeMap::new(),
+        };
+
         let image_path = match cluster_mode {
             ClusterMode::Kubernetes => match manager_type {
                 ManagerType::Coordinator => parse_arg("image_path")?,
@@ -274,6 +304,8 @@ impl Context {
             metric_addr,
             coordinator_address,
             dashboard_path,
+            from_savepoint,
+            labels,
             yarn_manager_main_class,
             worker_process_path,
             memory_mb,
@@ -284,6 +316,36 @@ impl Context {
     }
 }
 
+/// parse the `labels` argument, eg: `tenant:acme,team:ads`
+fn parse_labels(labels: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut label_map = BTreeMap::new();
+    for label in labels.split(',').filter(|x| !x.trim().is_empty()) {
+        let (name, value) = label
+            .split_once(':')
+            .ok_or(anyhow!("illegal label `{}`, expect `name:value`", label))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("the name of the label `{}` is empty", label));
+        }
+        label_map.insert(name.to_string(), value.trim().to_string());
+    }
+    Ok(label_map)
+}
+
+fn parse_num_task_managers() -> anyhow::Result<u32> {
+    let num_task_managers = parse_arg("num_task_managers")?;
+    let num_task_managers = u32::from_str(num_task_managers.as_str()).map_err(|_e| {
+        anyhow!(
+            "parse `num_task_managers`=`{}` to u32 error",
+            num_task_managers
+        )
+    })?;
+    if num_task_managers < 1 {
+        return Err(anyhow!("`num_task_managers` must the [value > 1]"));
+    }
+    Ok(num_task_managers)
+}
+
 fn metrics_serve(
     bind_ip: &str,
     cluster_mode: &ClusterMode,
@@ -333,3 +395,19 @@ impl ProxyAddressLoader for MetadataProxyAddressLoader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::context::parse_labels;
+
+    #[test]
+    pub fn parse_labels_test() {
+        let labels = parse_labels("tenant:acme, team:ads").unwrap();
+        assert_eq!(labels.get("tenant").map(|x| x.as_str()), Some("acme"));
+        assert_eq!(labels.get("team").map(|x| x.as_str()), Some("ads"));
+
+        assert!(parse_labels("").unwrap().is_empty());
+        assert!(parse_labels("tenant").is_err());
+        assert!(parse_labels(":acme").is_err());
+    }
+}
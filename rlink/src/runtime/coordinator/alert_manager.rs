@@ -0,0 +1,333 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::channel::{unbounded, Sender};
+use crate::core::alert::{Alert, AlertKind, AlertNotifier, AlertRules};
+use crate::core::runtime::{OperatorId, TaskId};
+use crate::core::watermark::MAX_WATERMARK;
+use crate::runtime::coordinator::event_log::{Event, EventKind};
+use crate::utils::date_time::current_timestamp_millis;
+
+lazy_static! {
+    static ref ALERT_MANAGER: Mutex<Option<(AlertManager, Sender<Alert>)>> = Mutex::new(None);
+}
+
+/// start the alerting if there is any `AlertNotifier`,
+/// the alerts are sent by the notifiers in a dedicated thread
+pub(crate) fn init(
+    application_id: String,
+    application_name: String,
+    alert_rules: AlertRules,
+    alert_notifiers: Vec<Box<dyn AlertNotifier>>,
+) {
+    if alert_notifiers.is_empty() {
+        return;
+    }
+
+    info!(
+        "start alerting with rules {:?}, notifiers {:?}",
+        alert_rules, alert_notifiers
+    );
+
+    let (sender, receiver) = unbounded::<Alert>();
+    std::thread::Builder::new()
+        .name("AlertNotifier".to_string())
+        .spawn(move || {
+            while let Ok(alert) = receiver.recv() {
+                for alert_notifier in &alert_notifiers {
+                    if let Err(e) = alert_notifier.notify(&alert) {
+                        error!("{:?} notify alert error. {}", alert_notifier, e);
+                    }
+                }
+            }
+        })
+        .unwrap();
+
+    let alert_manager = AlertManager::new(application_id, application_name, alert_rules);
+    *ALERT_MANAGER.lock().unwrap() = Some((alert_manager, sender));
+}
+
+pub(crate) fn on_event(event: &Event) {
+    if let Some((alert_manager, sender)) = ALERT_MANAGER.lock().unwrap().as_mut() {
+        if let Some(alert) = alert_manager.on_event(event) {
+            sender.send(alert).unwrap();
+        }
+    }
+}
+
+/// the watermark of the task reported by the heartbeat
+pub(crate) fn on_watermark(task_id: TaskId, watermark: u64) {
+    if let Some((alert_manager, sender)) = ALERT_MANAGER.lock().unwrap().as_mut() {
+        let now = current_timestamp_millis();
+        if let Some(alert) = alert_manager.on_watermark(task_id, watermark, now) {
+            sender.send(alert).unwrap();
+        }
+    }
+}
+
+/// the keyed state of the operator in the task exceeds its `StateLimit`, reported by the heartbeat
+pub(crate) fn on_state_limit_exceeded(task_id: TaskId, operator_id: OperatorId, message: &str) {
+    if let Some((alert_manager, sender)) = ALERT_MANAGER.lock().unwrap().as_mut() {
+        let now = current_timestamp_millis();
+        let alert = alert_manager.try_alert(
+            AlertKind::StateLimitExceeded,
+            format!(
+                "StateLimitExceeded-{}-{}-{}",
+                operator_id.0, task_id.job_id.0, task_id.task_number
+            ),
+            message.to_string(),
+            now,
+        );
+        if let Some(alert) = alert {
+            sender.send(alert).unwrap();
+        }
+    }
+}
+
+/// Evaluate the `AlertRules` by the lifecycle events and the watermarks
+struct AlertManager {
+    application_id: String,
+    application_name: String,
+    alert_rules: AlertRules,
+
+    /// the number of the consecutive failed checkpoints
+    failed_checkpoints: u32,
+    restart_timestamps: VecDeque<u64>,
+    /// key: task_id, value: (the latest watermark, the timestamp when it advanced)
+    watermarks: HashMap<TaskId, (u64, u64)>,
+    /// key: dedup_key, value: the timestamp of the latest sent alert
+    sent_alerts: HashMap<String, u64>,
+}
+
+impl AlertManager {
+    fn new(application_id: String, application_name: String, alert_rules: AlertRules) -> Self {
+        AlertManager {
+            application_id,
+            application_name,
+            alert_rules,
+            failed_checkpoints: 0,
+            restart_timestamps: VecDeque::new(),
+            watermarks: HashMap::new(),
+            sent_alerts: HashMap::new(),
+        }
+    }
+
+    fn on_event(&mut self, event: &Event) -> Option<Alert> {
+        let rules = &self.alert_rules;
+        let (kind, dedup_key, message) = match &event.kind {
+            EventKind::CheckpointCompleted { .. } => {
+                self.failed_checkpoints = 0;
+                return None;
+            }
+            EventKind::CheckpointFailed {
+                checkpoint_id,
+                reason,
+            } => {
+                self.failed_checkpoints += 1;
+                if rules.checkpoint_failure_threshold == 0
+                    || self.failed_checkpoints < rules.checkpoint_failure_threshold
+                {
+                    return None;
+                }
+
+                let message = format!(
+                    "{} consecutive checkpoints failed, the latest checkpoint_id={}, {}",
+                    self.failed_checkpoints, checkpoint_id.0, reason
+                );
+                (
+                    AlertKind::CheckpointFailures,
+                    "CheckpointFailures".to_string(),
+                    message,
+                )
+            }
+            EventKind::WorkerLost {
+                task_manager_address,
+                heartbeat_lag_secs,
+            } => {
+                if !rules.worker_lost {
+                    return None;
+                }
+
+                let message = format!(
+                    "the worker {} is lost, heartbeat lag {}s",
+                    task_manager_address, heartbeat_lag_secs
+                );
+                (
+                    AlertKind::WorkerLost,
+                    format!("WorkerLost-{}", task_manager_address),
+                    message,
+                )
+            }
+            EventKind::ApplicationRestart { startup_number } => {
+                self.restart_timestamps.push_back(event.timestamp);
+                while let Some(ts) = self.restart_timestamps.front() {
+                    if ts + rules.restart_storm_window_ms < event.timestamp {
+                        self.restart_timestamps.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+
+                if rules.restart_storm_threshold == 0
+                    || (self.restart_timestamps.len() as u32) < rules.restart_storm_threshold
+                {
+                    return None;
+                }
+
+                let message = format!(
+                    "restart {} times in {}s, startup_number={}",
+                    self.restart_timestamps.len(),
+                    rules.restart_storm_window_ms / 1000,
+                    startup_number
+                );
+                (AlertKind::RestartStorm, "RestartStorm".to_string(), message)
+            }
+            EventKind::TaskFailed {
+                task_id,
+                class,
+                code,
+                message,
+            } => {
+                if !rules.task_failure_classes.contains(class) {
+                    return None;
+                }
+
+                let message = format!(
+                    "the task(job_id={}, task_number={}) failed with {} error {}, {}",
+                    task_id.job_id.0, task_id.task_number, class, code, message
+                );
+                (
+                    AlertKind::TaskFailure,
+                    format!("TaskFailure-{}-{}", class, code),
+                    message,
+                )
+            }
+            EventKind::CrashLoopDetected {
+                restarts,
+                interval_secs,
+                ..
+            } => {
+                let message = format!(
+                    "failed again after {} restarts in {}s, terminated for the crash loop",
+                    restarts, interval_secs
+                );
+                (AlertKind::CrashLoop, "CrashLoop".to_string(), message)
+            }
+            _ => return None,
+        };
+
+        self.try_alert(kind, dedup_key, message, event.timestamp)
+    }
+
+    fn on_watermark(&mut self, task_id: TaskId, watermark: u64, now: u64) -> Option<Alert> {
+        let timeout = self.alert_rules.watermark_stall_timeout_ms;
+        // the stream is end
+        if timeout == 0 || watermark >= MAX_WATERMARK.timestamp {
+            return None;
+        }
+
+        let (latest_watermark, advanced_ts) =
+            self.watermarks.entry(task_id).or_insert((watermark, now));
+        if watermark > *latest_watermark {
+            *latest_watermark = watermark;
+            *advanced_ts = now;
+            return None;
+        }
+
+        if now - *advanced_ts < timeout {
+            return None;
+        }
+
+        let message = format!(
+            "the watermark {} of the task(job_id={}, task_number={}) stalled for {}s",
+            watermark,
+            task_id.job_id.0,
+            task_id.task_number,
+            (now - *advanced_ts) / 1000
+        );
+        self.try_alert(
+            AlertKind::WatermarkStalled,
+            format!(
+                "WatermarkStalled-{}-{}",
+                task_id.job_id.0, task_id.task_number
+            ),
+            message,
+            now,
+        )
+    }
+
+    /// drop the alert if the same one has been sent in the cooldown period
+    fn try_alert(
+        &mut self,
+        kind: AlertKind,
+        dedup_key: String,
+        message: String,
+        timestamp: u64,
+    ) -> Option<Alert> {
+        if let Some(sent_ts) = self.sent_alerts.get(&dedup_key) {
+            if timestamp < sent_ts + self.alert_rules.cooldown_ms {
+                debug!("alert {} is in the cooldown period", dedup_key);
+                return None;
+            }
+        }
+        self.sent_alerts.insert(dedup_key.clone(), timestamp);
+
+        warn!("alert: {}", message);
+        Some(Alert {
+            kind,
+            dedup_key,
+            application_id: self.application_id.clone(),
+            application_name: self.application_name.clone(),
+            message,
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::alert::{AlertKind, AlertRules};
+    use crate::core::runtime::{CheckpointId, JobId, TaskId};
+    use crate::runtime::coordinator::alert_manager::AlertManager;
+    use crate::runtime::coordinator::event_log::{Event, EventKind};
+
+    fn checkpoint_failed(timestamp: u64) -> Event {
+        Event {
+            id: 0,
+            timestamp,
+            kind: EventKind::CheckpointFailed {
+                checkpoint_id: CheckpointId(timestamp),
+                reason: "".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    pub fn alert_manager_test() {
+        let rules = AlertRules {
+            checkpoint_failure_threshold: 2,
+            watermark_stall_timeout_ms: 1000,
+            cooldown_ms: 5000,
+            ..Default::default()
+        };
+        let mut alert_manager = AlertManager::new("app".to_string(), "app".to_string(), rules);
+
+        assert!(alert_manager.on_event(&checkpoint_failed(1000)).is_none());
+        let alert = alert_manager.on_event(&checkpoint_failed(2000)).unwrap();
+        assert_eq!(alert.kind, AlertKind::CheckpointFailures);
+        // deduplicated in the cooldown period
+        assert!(alert_manager.on_event(&checkpoint_failed(3000)).is_none());
+        assert!(alert_manager.on_event(&checkpoint_failed(7000)).is_some());
+
+        let task_id = TaskId {
+            job_id: JobId(1),
+            task_number: 0,
+            num_tasks: 1,
+        };
+        assert!(alert_manager.on_watermark(task_id, 100, 1000).is_none());
+        assert!(alert_manager.on_watermark(task_id, 200, 1500).is_none());
+        assert!(alert_manager.on_watermark(task_id, 200, 2000).is_none());
+        let alert = alert_manager.on_watermark(task_id, 200, 2600).unwrap();
+        assert_eq!(alert.kind, AlertKind::WatermarkStalled);
+    }
+}
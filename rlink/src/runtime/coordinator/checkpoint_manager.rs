@@ -1,14 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use crate::channel::{bounded, Receiver, Sender};
-use crate::core::checkpoint::Checkpoint;
-use crate::core::properties::SystemProperties;
-use crate::core::runtime::{CheckpointId, ClusterDescriptor, JobId, OperatorId};
+use crate::core::checkpoint::{
+    Checkpoint, CheckpointRetention, CheckpointStats, CheckpointTriggerState,
+    CheckpointTriggerStatus, SavepointPath, SavepointStatus,
+};
+use crate::core::encryption::KeyProvider;
+use crate::core::error::CheckpointError;
+use crate::core::properties::{Properties, SystemProperties, TENANT_LABEL};
+use crate::core::runtime::{CheckpointId, ClusterDescriptor, JobId, OperatorId, TaskId};
 use crate::dag::metadata::DagMetadata;
 use crate::runtime::context::Context;
+use crate::runtime::coordinator::event_log::{self, EventKind};
 use crate::storage::checkpoint::{CheckpointStorage, TCheckpointStorage};
+use crate::utils::date_time::current_timestamp_millis;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct OperatorCheckpoint {
@@ -68,15 +75,184 @@ impl Clone for OperatorCheckpoint {
     }
 }
 
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+const STATS_HISTORY_SIZE: usize = 30;
+
+/// the statistics of an operator's task in a checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TaskCheckpointStats {
+    operator_id: OperatorId,
+    task_id: TaskId,
+    stats: CheckpointStats,
+}
+
+/// the statistics of a checkpoint, summarized from all the tasks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CheckpointStatsSummary {
+    checkpoint_id: CheckpointId,
+    completed: bool,
+    /// the sum of all tasks' state size
+    state_size: u64,
+    max_sync_duration: u64,
+    /// 0 if no task snapshots asynchronously
+    max_async_duration: u64,
+    max_alignment_duration: u64,
+    /// the sum of all tasks' alignment bytes
+    alignment_bytes: u64,
+    tasks: Vec<TaskCheckpointStats>,
+}
+
+impl CheckpointStatsSummary {
+    fn new(checkpoint_id: CheckpointId) -> Self {
+        CheckpointStatsSummary {
+            checkpoint_id,
+            completed: false,
+            state_size: 0,
+            max_sync_duration: 0,
+            max_async_duration: 0,
+            max_alignment_duration: 0,
+            alignment_bytes: 0,
+            tasks: Vec::new(),
+        }
+    }
+
+    fn apply(&mut self, ck: &Checkpoint) {
+        let stats = &ck.stats;
+        self.state_size += stats.state_size;
+        self.max_sync_duration = self.max_sync_duration.max(stats.sync_duration);
+        self.max_async_duration = self.max_async_duration.max(stats.async_duration);
+        self.max_alignment_duration = self.max_alignment_duration.max(stats.alignment_duration);
+        self.alignment_bytes += stats.alignment_bytes;
+
+        self.tasks.push(TaskCheckpointStats {
+            operator_id: ck.operator_id,
+            task_id: ck.task_id,
+            stats: stats.clone(),
+        });
+    }
+}
+
+/// a completed checkpoint tracked by the `CheckpointRetention`
+#[derive(Debug, Clone, Default)]
+struct CompletedCheckpoint {
+    savepoint: bool,
+    /// the `completed_checkpoint_id`s referenced by the checkpoint's operators,
+    /// the referenced checkpoints are loaded at recovery, so they must be retained together
+    references: HashSet<CheckpointId>,
+}
+
+impl CompletedCheckpoint {
+    fn new(savepoint: bool, checkpoints: &[Checkpoint]) -> Self {
+        let references = checkpoints
+            .iter()
+            .filter_map(|ck| ck.completed_checkpoint_id)
+            .filter(|ck_id| !ck_id.is_default())
+            .collect();
+        CompletedCheckpoint {
+            savepoint,
+            references,
+        }
+    }
+}
+
+/// the application name the checkpoints are stored by, prefixed with the tenant label if present,
+/// eg: `acme/my_app`, so the checkpoints of the tenants are stored in their own namespaces
+fn checkpoint_namespace(application_properties: &Properties) -> String {
+    let application_name = application_properties.get_application_name();
+    let tenant = application_properties
+        .get_application_labels()
+        .ok()
+        .and_then(|labels| labels.get(TENANT_LABEL).cloned());
+    match tenant {
+        Some(tenant) => format!("{}/{}", tenant, application_name),
+        None => application_name,
+    }
+}
+
+/// find out the checkpoints that can be deleted safely by the `retention` policy.
+/// the `CheckpointId` is the barrier timestamp, so it's also used to group savepoints by day
+fn expired_checkpoints(
+    retention: &CheckpointRetention,
+    completed_cks: &BTreeMap<CheckpointId, CompletedCheckpoint>,
+) -> Vec<CheckpointId> {
+    let mut retained: HashSet<CheckpointId> = completed_cks
+        .keys()
+        .rev()
+        .take(retention.retained_checkpoints)
+        .copied()
+        .collect();
+
+    if retention.retain_daily_savepoint {
+        let mut daily_savepoints = HashMap::new();
+        completed_cks
+            .iter()
+            .filter(|(_, ck)| ck.savepoint)
+            .for_each(|(ck_id, _)| {
+                daily_savepoints.insert(ck_id.0 / DAY_MILLIS, *ck_id);
+            });
+        retained.extend(daily_savepoints.values());
+    }
+
+    // keep the whole reference chain of retained checkpoints
+    let mut stack: Vec<CheckpointId> = retained.iter().copied().collect();
+    while let Some(ck_id) = stack.pop() {
+        if let Some(ck) = completed_cks.get(&ck_id) {
+            for reference in &ck.references {
+                if retained.insert(*reference) {
+                    stack.push(*reference);
+                }
+            }
+        }
+    }
+
+    completed_cks
+        .keys()
+        .filter(|ck_id| !retained.contains(ck_id))
+        .copied()
+        .collect()
+}
+
+/// the delay of the stop-with-savepoint barrier, longer than the heartbeat interval
+const STOP_BARRIER_DELAY: Duration = Duration::from_secs(30);
+
 #[derive(Serialize, Deserialize)]
 pub(crate) struct CheckpointAlignManager {
     application_name: String,
     application_id: String,
     checkpoint_ttl: Duration,
+    checkpoint_retention: CheckpointRetention,
 
     current_ck_id: CheckpointId,
     operator_cks: HashMap<OperatorId, OperatorCheckpoint>,
     finish_operator_cks: HashMap<OperatorId, OperatorCheckpoint>,
+    /// the latest completed savepoint
+    #[serde(default)]
+    savepoint_id: Option<CheckpointId>,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    stats_history: VecDeque<CheckpointStatsSummary>,
+    #[serde(skip_serializing, skip_deserializing)]
+    savepoint_ids: HashSet<CheckpointId>,
+    /// the latest checkpoint triggered on demand, the sources emit its barrier immediately
+    #[serde(skip_serializing, skip_deserializing)]
+    triggered_checkpoint_id: Option<CheckpointId>,
+    /// the latest checkpoint requested by a source, see `CheckpointRequester`
+    #[serde(skip_serializing, skip_deserializing)]
+    requested_checkpoint_id: Option<CheckpointId>,
+    /// the savepoint of the stop-with-savepoint
+    #[serde(skip_serializing, skip_deserializing)]
+    stop_checkpoint_id: Option<CheckpointId>,
+    /// restore from the savepoint if there is no checkpoint of the application
+    #[serde(skip_serializing, skip_deserializing)]
+    from_savepoint: Option<SavepointPath>,
+    #[serde(skip_serializing, skip_deserializing)]
+    completed_cks: BTreeMap<CheckpointId, CompletedCheckpoint>,
+    /// the latest checkpoint that all operators acknowledged and persisted
+    #[serde(skip_serializing, skip_deserializing)]
+    completed_checkpoint_id: Option<CheckpointId>,
+    /// the latest checkpoint declined by a task, it's never completed
+    #[serde(skip_serializing, skip_deserializing)]
+    declined_checkpoint_id: Option<CheckpointId>,
 
     #[serde(skip_serializing, skip_deserializing)]
     storage: Option<CheckpointStorage>,
@@ -88,6 +264,8 @@ impl CheckpointAlignManager {
         context: &Context,
         cluster_descriptor: &ClusterDescriptor,
         checkpoint_ttl: Duration,
+        checkpoint_retention: CheckpointRetention,
+        key_provider: Option<Arc<dyn KeyProvider>>,
     ) -> Self {
         let checkpoint_backend = cluster_descriptor
             .coordinator_manager
@@ -97,7 +275,7 @@ impl CheckpointAlignManager {
             .unwrap_or(None);
         let storage = checkpoint_backend
             .as_ref()
-            .map(|ck_backend| CheckpointStorage::new(ck_backend));
+            .map(|ck_backend| CheckpointStorage::new(ck_backend).with_key_provider(key_provider));
 
         let mut operator_cks = HashMap::new();
         for node in dag_manager.job_graph().nodes() {
@@ -117,16 +295,27 @@ impl CheckpointAlignManager {
         }
 
         CheckpointAlignManager {
-            application_name: cluster_descriptor
-                .coordinator_manager
-                .application_properties
-                .get_application_name()
-                .clone(),
+            application_name: checkpoint_namespace(
+                &cluster_descriptor
+                    .coordinator_manager
+                    .application_properties,
+            ),
             application_id: context.application_id.clone(),
             checkpoint_ttl,
+            checkpoint_retention,
             current_ck_id: CheckpointId::default(),
             operator_cks,
             finish_operator_cks: HashMap::new(),
+            savepoint_id: None,
+            stats_history: VecDeque::with_capacity(STATS_HISTORY_SIZE),
+            savepoint_ids: HashSet::new(),
+            triggered_checkpoint_id: None,
+            requested_checkpoint_id: None,
+            stop_checkpoint_id: None,
+            from_savepoint: context.from_savepoint.clone(),
+            completed_cks: BTreeMap::new(),
+            completed_checkpoint_id: None,
+            declined_checkpoint_id: None,
             storage,
         }
     }
@@ -143,7 +332,18 @@ impl CheckpointAlignManager {
         } else if self.current_ck_id.0 < checkpoint_id.0 {
             if !self.current_ck_id.is_default() {
                 let unreached_operators = self.unreached_operators();
-                if unreached_operators.len() > 0 {
+                // the failure of a declined checkpoint is recorded on the declining
+                if !unreached_operators.is_empty()
+                    && self.declined_checkpoint_id != Some(self.current_ck_id)
+                {
+                    event_log::record(EventKind::CheckpointFailed {
+                        checkpoint_id: self.current_ck_id,
+                        reason: format!(
+                            "{} operators un-aligned when the checkpoint_id={:?} reached",
+                            unreached_operators.len(),
+                            checkpoint_id
+                        ),
+                    });
                     warn!(
                         "the new checkpoint reached, found un-align checkpoint_id={:?}",
                         self.current_ck_id,
@@ -156,14 +356,40 @@ impl CheckpointAlignManager {
             }
 
             self.next_checkpoint(checkpoint_id);
+            event_log::record(EventKind::CheckpointTriggered { checkpoint_id });
+        }
+
+        if let Some(reason) = ck.declined.as_ref() {
+            warn!(
+                "checkpoint_id={:?} declined by operator={:?}, task_id={:?}. {}",
+                checkpoint_id, ck.operator_id, ck.task_id, reason
+            );
+            if self.declined_checkpoint_id != Some(checkpoint_id) {
+                self.declined_checkpoint_id = Some(checkpoint_id);
+                event_log::record(EventKind::CheckpointFailed {
+                    checkpoint_id,
+                    reason: format!(
+                        "declined by the task {} of operator {:?}. {}",
+                        ck.task_id.task_number, ck.operator_id, reason
+                    ),
+                });
+            }
+            return Ok(());
         }
 
         match self.operator_cks.get_mut(&ck.operator_id) {
             Some(operator_checkpoint) => {
+                if let Some(summary) = self.stats_history.back_mut() {
+                    summary.apply(&ck);
+                }
                 operator_checkpoint.apply(ck);
             }
             None => {
-                return Err(anyhow!("operator not found, checkpoint={:?}", ck));
+                return Err(CheckpointError::Align(format!(
+                    "operator not found, checkpoint={:?}",
+                    ck
+                ))
+                .into());
             }
         }
 
@@ -177,6 +403,10 @@ impl CheckpointAlignManager {
             );
             self.finish_operator_cks = complete_operator_cks;
 
+            if let Some(summary) = self.stats_history.back_mut() {
+                summary.completed = true;
+            }
+
             match self.storage.as_mut() {
                 Some(storage) => {
                     let cks = {
@@ -189,6 +419,16 @@ impl CheckpointAlignManager {
                         cks
                     };
 
+                    let savepoint = self.savepoint_ids.remove(&complete_checkpoint_id);
+                    let cks: Vec<Checkpoint> = cks
+                        .into_iter()
+                        .map(|mut ck| {
+                            ck.savepoint = savepoint;
+                            ck
+                        })
+                        .collect();
+                    let completed_ck = CompletedCheckpoint::new(savepoint, cks.as_slice());
+
                     storage.save(
                         self.application_name.as_str(),
                         self.application_id.as_str(),
@@ -196,11 +436,211 @@ impl CheckpointAlignManager {
                         cks,
                         self.checkpoint_ttl.as_millis() as u64,
                     )?;
+
+                    if self.checkpoint_retention.is_enable() {
+                        self.completed_cks
+                            .insert(complete_checkpoint_id, completed_ck);
+                        self.apply_retention()?;
+                    }
+
+                    if savepoint {
+                        self.savepoint_id = Some(complete_checkpoint_id);
+                        event_log::record(EventKind::SavepointCompleted {
+                            checkpoint_id: complete_checkpoint_id,
+                        });
+                    }
                 }
                 None => {}
             }
+
+            if self.completed_checkpoint_id != Some(complete_checkpoint_id) {
+                event_log::record(EventKind::CheckpointCompleted {
+                    checkpoint_id: complete_checkpoint_id,
+                });
+            }
+            self.completed_checkpoint_id = Some(complete_checkpoint_id);
+        }
+
+        Ok(())
+    }
+
+    pub fn completed_checkpoint_id(&self) -> Option<CheckpointId> {
+        self.completed_checkpoint_id
+    }
+
+    /// the statistics of the latest checkpoints, order by `checkpoint_id` desc
+    pub fn stats(&self) -> Vec<CheckpointStatsSummary> {
+        self.stats_history.iter().rev().cloned().collect()
+    }
+
+    pub fn savepoint_status(&self) -> SavepointStatus {
+        SavepointStatus {
+            savepoint_id: self.savepoint_id,
+            completed_checkpoint_id: self.completed_checkpoint_id,
+        }
+    }
+
+    /// mark the checkpoint as a savepoint, it must be called before the checkpoint completed
+    pub fn mark_savepoint(&mut self, checkpoint_id: CheckpointId) {
+        self.savepoint_ids.insert(checkpoint_id);
+    }
+
+    /// trigger a checkpoint and mark it as a savepoint, the savepoint can be restored by
+    /// another application. a triggered checkpoint not started yet is reused
+    pub fn request_savepoint(&mut self) -> anyhow::Result<CheckpointId> {
+        if self.storage.is_none() {
+            return Err(CheckpointError::Storage(
+                "savepoint is unsupported without checkpoint backend".to_string(),
+            )
+            .into());
+        }
+
+        let checkpoint_id = self.trigger_checkpoint();
+        self.mark_savepoint(checkpoint_id);
+        info!("checkpoint_id={:?} is marked as a savepoint", checkpoint_id);
+        Ok(checkpoint_id)
+    }
+
+    /// trigger a checkpoint immediately, the `checkpoint_id` is the current timestamp, so it's
+    /// later than the checkpoints triggered by the interval timers
+    pub fn trigger_checkpoint(&mut self) -> CheckpointId {
+        if let Some(checkpoint_id) = self.pending_checkpoint_id() {
+            return checkpoint_id;
+        }
+
+        let checkpoint_id = CheckpointId(current_timestamp_millis().max(self.current_ck_id.0 + 1));
+        self.triggered_checkpoint_id = Some(checkpoint_id);
+        info!("checkpoint_id={:?} is triggered", checkpoint_id);
+        checkpoint_id
+    }
+
+    /// trigger the checkpoint requested by a source, the source has emitted its barrier,
+    /// ignored if a later checkpoint is triggered
+    pub fn request_checkpoint(&mut self, checkpoint_id: CheckpointId) {
+        if self.triggered_checkpoint_id >= Some(checkpoint_id) || self.current_ck_id > checkpoint_id
+        {
+            return;
+        }
+        self.triggered_checkpoint_id = Some(checkpoint_id);
+        self.requested_checkpoint_id = Some(checkpoint_id);
+    }
+
+    /// the triggered checkpoint that has not been started, it's delivered to the workers by the
+    /// heartbeat response. a requested checkpoint is started by the barrier of the requesting
+    /// source before the others receive it, so it's delivered until completed
+    pub fn pending_checkpoint_id(&self) -> Option<CheckpointId> {
+        self.triggered_checkpoint_id.filter(|checkpoint_id| {
+            checkpoint_id.0 > self.current_ck_id.0
+                || (self.requested_checkpoint_id == Some(*checkpoint_id)
+                    && *checkpoint_id == self.current_ck_id
+                    && self.completed_checkpoint_id < Some(*checkpoint_id))
+        })
+    }
+
+    pub fn trigger_status(&self, checkpoint_id: CheckpointId) -> CheckpointTriggerStatus {
+        let completed = self
+            .completed_checkpoint_id
+            .map(|x| x.0 >= checkpoint_id.0)
+            .unwrap_or(false);
+        let state = if completed {
+            CheckpointTriggerState::Completed
+        } else if self.current_ck_id.0 < checkpoint_id.0 {
+            CheckpointTriggerState::Pending
+        } else if self.current_ck_id.0 == checkpoint_id.0 {
+            CheckpointTriggerState::InProgress
+        } else {
+            CheckpointTriggerState::Aborted
+        };
+
+        CheckpointTriggerStatus {
+            checkpoint_id,
+            state,
+            completed_checkpoint_id: self.completed_checkpoint_id,
+        }
+    }
+
+    /// take a savepoint and end the streams after it completed, so no record is processed
+    /// after the savepoint. The `checkpoint_id` is ahead of the current timestamp by
+    /// `STOP_BARRIER_DELAY`, so it's later than the barriers the sources emitted before
+    /// receiving it by the heartbeat.
+    pub fn stop_with_savepoint(&mut self) -> anyhow::Result<CheckpointId> {
+        if let Some(checkpoint_id) = self.stop_checkpoint_id {
+            return Ok(checkpoint_id);
+        }
+        if self.storage.is_none() {
+            return Err(CheckpointError::Storage(
+                "savepoint is unsupported without checkpoint backend".to_string(),
+            )
+            .into());
+        }
+
+        let checkpoint_id = CheckpointId(
+            (current_timestamp_millis() + STOP_BARRIER_DELAY.as_millis() as u64)
+                .max(self.current_ck_id.0 + 1),
+        );
+        self.mark_savepoint(checkpoint_id);
+        self.stop_checkpoint_id = Some(checkpoint_id);
+        info!("stop with savepoint, checkpoint_id={:?}", checkpoint_id);
+        Ok(checkpoint_id)
+    }
+
+    pub fn stop_checkpoint_id(&self) -> Option<CheckpointId> {
+        self.stop_checkpoint_id
+    }
+
+    /// the application is redeployed after stopped with the savepoint
+    pub fn clear_stop_checkpoint_id(&mut self) {
+        self.stop_checkpoint_id = None;
+    }
+
+    /// track all the checkpoints in the storage, include the ones completed before
+    /// the `Coordinator` restarted, and delete the expired ones right now
+    fn load_completed_checkpoints(&mut self) -> anyhow::Result<()> {
+        if let Some(storage) = self.storage.as_mut() {
+            let checkpoints =
+                storage.load_all(self.application_name.as_str(), self.application_id.as_str())?;
+
+            let mut checkpoint_groups: BTreeMap<CheckpointId, Vec<Checkpoint>> = BTreeMap::new();
+            for ck in checkpoints {
+                checkpoint_groups
+                    .entry(ck.checkpoint_id)
+                    .or_default()
+                    .push(ck);
+            }
+
+            for (ck_id, cks) in checkpoint_groups {
+                let savepoint = cks.iter().any(|ck| ck.savepoint);
+                if savepoint {
+                    self.savepoint_id = Some(ck_id);
+                }
+                self.completed_cks
+                    .insert(ck_id, CompletedCheckpoint::new(savepoint, cks.as_slice()));
+            }
+        }
+
+        self.apply_retention()
+    }
+
+    /// delete the expired checkpoints by the `CheckpointRetention` policy
+    fn apply_retention(&mut self) -> anyhow::Result<()> {
+        let expired_ck_ids = expired_checkpoints(&self.checkpoint_retention, &self.completed_cks);
+        if expired_ck_ids.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(storage) = self.storage.as_mut() {
+            storage.delete(
+                self.application_name.as_str(),
+                self.application_id.as_str(),
+                expired_ck_ids.as_slice(),
+            )?;
+        }
+
+        for ck_id in &expired_ck_ids {
+            self.completed_cks.remove(ck_id);
         }
 
+        debug!("checkpoint retention delete {:?}", expired_ck_ids);
         Ok(())
     }
 
@@ -221,6 +661,13 @@ impl CheckpointAlignManager {
 
     fn next_checkpoint(&mut self, checkpoint_id: CheckpointId) {
         self.current_ck_id = checkpoint_id;
+
+        if self.stats_history.len() == STATS_HISTORY_SIZE {
+            self.stats_history.pop_front();
+        }
+        self.stats_history
+            .push_back(CheckpointStatsSummary::new(checkpoint_id));
+
         self.operator_cks = {
             let mut operator_cks = HashMap::new();
             for (operator_id, operator_checkpoint) in &self.operator_cks {
@@ -241,28 +688,51 @@ impl CheckpointAlignManager {
     pub fn load(&mut self) -> anyhow::Result<HashMap<OperatorId, Vec<Checkpoint>>> {
         let mut operator_checkpoints = HashMap::new();
 
+        if self.checkpoint_retention.is_enable() {
+            self.load_completed_checkpoints()?;
+        }
+
         if let Some(storage) = self.storage.as_mut() {
             let mut checkpoints =
                 storage.load(self.application_name.as_str(), self.application_id.as_str())?;
-            let completed_checkpoint_id = checkpoints
-                .iter()
-                .filter(|c| {
-                    if let Some(completed_checkpoint_id) = c.completed_checkpoint_id {
-                        completed_checkpoint_id.0 > 0
-                    } else {
-                        false
+            if checkpoints.is_empty() {
+                // the savepoint is a completed checkpoint, so it's loaded directly
+                if let Some(savepoint) = &self.from_savepoint {
+                    checkpoints = storage.load_by_checkpoint_id(
+                        self.application_name.as_str(),
+                        savepoint.application_id.as_str(),
+                        savepoint.checkpoint_id,
+                    )?;
+                    if checkpoints.is_empty() {
+                        return Err(CheckpointError::Restore(format!(
+                            "savepoint {} not found",
+                            savepoint
+                        ))
+                        .into());
                     }
-                })
-                .min_by_key(|c| c.completed_checkpoint_id.unwrap_or_default())
-                .map(|c| c.completed_checkpoint_id.unwrap_or_default())
-                .unwrap_or_default();
-
-            if !completed_checkpoint_id.is_default() {
-                checkpoints = storage.load_by_checkpoint_id(
-                    self.application_name.as_str(),
-                    self.application_id.as_str(),
-                    completed_checkpoint_id,
-                )?;
+                    info!("restore from savepoint {}", savepoint);
+                }
+            } else {
+                let completed_checkpoint_id = checkpoints
+                    .iter()
+                    .filter(|c| {
+                        if let Some(completed_checkpoint_id) = c.completed_checkpoint_id {
+                            completed_checkpoint_id.0 > 0
+                        } else {
+                            false
+                        }
+                    })
+                    .min_by_key(|c| c.completed_checkpoint_id.unwrap_or_default())
+                    .map(|c| c.completed_checkpoint_id.unwrap_or_default())
+                    .unwrap_or_default();
+
+                if !completed_checkpoint_id.is_default() {
+                    checkpoints = storage.load_by_checkpoint_id(
+                        self.application_name.as_str(),
+                        self.application_id.as_str(),
+                        completed_checkpoint_id,
+                    )?;
+                }
             }
 
             for checkpoint in checkpoints {
@@ -283,9 +753,20 @@ impl Clone for CheckpointAlignManager {
             application_name: self.application_name.clone(),
             application_id: self.application_id.to_string(),
             checkpoint_ttl: self.checkpoint_ttl,
+            checkpoint_retention: self.checkpoint_retention.clone(),
             current_ck_id: CheckpointId::default(),
             operator_cks: self.operator_cks.clone(),
             finish_operator_cks: self.finish_operator_cks.clone(),
+            savepoint_id: self.savepoint_id,
+            stats_history: VecDeque::new(),
+            savepoint_ids: HashSet::new(),
+            triggered_checkpoint_id: self.triggered_checkpoint_id,
+            requested_checkpoint_id: self.requested_checkpoint_id,
+            stop_checkpoint_id: self.stop_checkpoint_id,
+            from_savepoint: None,
+            completed_cks: BTreeMap::new(),
+            completed_checkpoint_id: self.completed_checkpoint_id,
+            declined_checkpoint_id: self.declined_checkpoint_id,
             storage: None,
         }
     }
@@ -305,6 +786,8 @@ impl CheckpointManager {
         context: &Context,
         cluster_descriptor: &ClusterDescriptor,
         checkpoint_ttl: Duration,
+        checkpoint_retention: CheckpointRetention,
+        key_provider: Option<Arc<dyn KeyProvider>>,
     ) -> Self {
         let (sender, receiver) = bounded(100);
         CheckpointManager {
@@ -313,6 +796,8 @@ impl CheckpointManager {
                 context,
                 cluster_descriptor,
                 checkpoint_ttl,
+                checkpoint_retention,
+                key_provider,
             ))),
             sender,
             receiver,
@@ -347,8 +832,258 @@ impl CheckpointManager {
         ck_align_manager.clone()
     }
 
+    pub fn get_stats(&self) -> Vec<CheckpointStatsSummary> {
+        let ck_align_manager = self.ck_align_manager_task.read().unwrap();
+        ck_align_manager.stats()
+    }
+
+    pub fn get_completed_checkpoint_id(&self) -> Option<CheckpointId> {
+        let ck_align_manager = self.ck_align_manager_task.read().unwrap();
+        ck_align_manager.completed_checkpoint_id()
+    }
+
     pub fn load(&mut self) -> anyhow::Result<HashMap<OperatorId, Vec<Checkpoint>>> {
         let mut ck_align_manager = self.ck_align_manager_task.write().unwrap();
         ck_align_manager.load()
     }
+
+    pub fn request_savepoint(&self) -> anyhow::Result<CheckpointId> {
+        let mut ck_align_manager = self.ck_align_manager_task.write().unwrap();
+        ck_align_manager.request_savepoint()
+    }
+
+    pub fn get_savepoint_status(&self) -> SavepointStatus {
+        let ck_align_manager = self.ck_align_manager_task.read().unwrap();
+        ck_align_manager.savepoint_status()
+    }
+
+    pub fn stop_with_savepoint(&self) -> anyhow::Result<CheckpointId> {
+        let mut ck_align_manager = self.ck_align_manager_task.write().unwrap();
+        ck_align_manager.stop_with_savepoint()
+    }
+
+    pub fn get_stop_checkpoint_id(&self) -> Option<CheckpointId> {
+        let ck_align_manager = self.ck_align_manager_task.read().unwrap();
+        ck_align_manager.stop_checkpoint_id()
+    }
+
+    pub fn clear_stop_checkpoint_id(&self) {
+        let mut ck_align_manager = self.ck_align_manager_task.write().unwrap();
+        ck_align_manager.clear_stop_checkpoint_id()
+    }
+
+    pub fn trigger_checkpoint(&self) -> CheckpointId {
+        let mut ck_align_manager = self.ck_align_manager_task.write().unwrap();
+        ck_align_manager.trigger_checkpoint()
+    }
+
+    pub fn request_checkpoint(&self, checkpoint_id: CheckpointId) {
+        let mut ck_align_manager = self.ck_align_manager_task.write().unwrap();
+        ck_align_manager.request_checkpoint(checkpoint_id)
+    }
+
+    pub fn get_pending_checkpoint_id(&self) -> Option<CheckpointId> {
+        let ck_align_manager = self.ck_align_manager_task.read().unwrap();
+        ck_align_manager.pending_checkpoint_id()
+    }
+
+    pub fn get_trigger_status(&self, checkpoint_id: CheckpointId) -> CheckpointTriggerStatus {
+        let ck_align_manager = self.ck_align_manager_task.read().unwrap();
+        ck_align_manager.trigger_status(checkpoint_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use crate::core::backend::CheckpointBackend;
+    use crate::core::checkpoint::{Checkpoint, CheckpointHandle, CheckpointRetention};
+    use crate::core::runtime::{CheckpointId, JobId, OperatorId, TaskId};
+    use crate::runtime::coordinator::checkpoint_manager::{
+        expired_checkpoints, CheckpointAlignManager, CheckpointStatsSummary, CompletedCheckpoint,
+        OperatorCheckpoint, DAY_MILLIS,
+    };
+    use crate::storage::checkpoint::{CheckpointStorage, TCheckpointStorage};
+
+    fn completed_ck(savepoint: bool, references: Vec<u64>) -> CompletedCheckpoint {
+        CompletedCheckpoint {
+            savepoint,
+            references: references.into_iter().map(CheckpointId).collect(),
+        }
+    }
+
+    fn align_manager(parallelism: u16) -> CheckpointAlignManager {
+        let mut manager: CheckpointAlignManager = serde_json::from_value(serde_json::json!({
+            "application_name": "test",
+            "application_id": "test",
+            "checkpoint_ttl": { "secs": 60, "nanos": 0 },
+            "checkpoint_retention": CheckpointRetention::new(0, false),
+            "current_ck_id": 0,
+            "operator_cks": {},
+            "finish_operator_cks": {},
+        }))
+        .unwrap();
+        let operator_ck =
+            OperatorCheckpoint::new(JobId(0), OperatorId(1), "op".to_string(), parallelism);
+        manager.operator_cks.insert(OperatorId(1), operator_ck);
+        manager
+    }
+
+    fn task_ck(checkpoint_id: u64, task_number: u16, declined: Option<&str>) -> Checkpoint {
+        Checkpoint {
+            operator_id: OperatorId(1),
+            task_id: TaskId {
+                job_id: JobId(0),
+                task_number,
+                num_tasks: 2,
+            },
+            checkpoint_id: CheckpointId(checkpoint_id),
+            completed_checkpoint_id: None,
+            handle: CheckpointHandle::default(),
+            stats: Default::default(),
+            declined: declined.map(|x| x.to_string()),
+            savepoint: false,
+        }
+    }
+
+    #[test]
+    pub fn declined_checkpoint_test() {
+        let mut manager = align_manager(2);
+
+        // the declined checkpoint is never completed
+        manager
+            .apply(task_ck(100, 0, Some("upload error")))
+            .unwrap();
+        manager.apply(task_ck(100, 1, None)).unwrap();
+        assert_eq!(manager.declined_checkpoint_id, Some(CheckpointId(100)));
+        assert_eq!(manager.completed_checkpoint_id(), None);
+
+        manager.apply(task_ck(200, 0, None)).unwrap();
+        manager.apply(task_ck(200, 1, None)).unwrap();
+        assert_eq!(manager.completed_checkpoint_id(), Some(CheckpointId(200)));
+    }
+
+    #[test]
+    pub fn checkpoint_stats_summary_test() {
+        let mut summary = CheckpointStatsSummary::new(CheckpointId(100));
+
+        let mut ck = task_ck(100, 0, None);
+        ck.stats.state_size = 10;
+        ck.stats.sync_duration = 5;
+        ck.stats.alignment_duration = 30;
+        ck.stats.alignment_bytes = 1000;
+        summary.apply(&ck);
+
+        let mut ck = task_ck(100, 1, None);
+        ck.stats.state_size = 20;
+        ck.stats.sync_duration = 3;
+        ck.stats.async_duration = 50;
+        ck.stats.alignment_duration = 10;
+        ck.stats.alignment_bytes = 500;
+        summary.apply(&ck);
+
+        assert_eq!(summary.state_size, 30);
+        assert_eq!(summary.max_sync_duration, 5);
+        assert_eq!(summary.max_async_duration, 50);
+        assert_eq!(summary.max_alignment_duration, 30);
+        assert_eq!(summary.alignment_bytes, 1500);
+        assert_eq!(summary.tasks.len(), 2);
+        assert!(!summary.completed);
+    }
+
+    #[test]
+    pub fn request_savepoint_test() {
+        let mut manager = align_manager(2);
+        assert!(manager.request_savepoint().is_err());
+
+        manager.storage = Some(CheckpointStorage::new(&CheckpointBackend::Memory));
+        let checkpoint_id = manager.request_savepoint().unwrap();
+        assert_eq!(manager.pending_checkpoint_id(), Some(checkpoint_id));
+        // the triggered checkpoint is reused by another request
+        assert_eq!(manager.request_savepoint().unwrap(), checkpoint_id);
+
+        // the marked checkpoint is the completed savepoint, not the checkpoints after it
+        manager.apply(task_ck(checkpoint_id.0, 0, None)).unwrap();
+        manager.apply(task_ck(checkpoint_id.0, 1, None)).unwrap();
+        manager
+            .apply(task_ck(checkpoint_id.0 + 1, 0, None))
+            .unwrap();
+        manager
+            .apply(task_ck(checkpoint_id.0 + 1, 1, None))
+            .unwrap();
+        let status = manager.savepoint_status();
+        assert_eq!(status.savepoint_id, Some(checkpoint_id));
+        assert_eq!(
+            status.completed_checkpoint_id,
+            Some(CheckpointId(checkpoint_id.0 + 1))
+        );
+    }
+
+    #[test]
+    pub fn expired_checkpoints_test() {
+        let mut completed_cks = BTreeMap::new();
+        completed_cks.insert(CheckpointId(100), completed_ck(true, vec![]));
+        completed_cks.insert(CheckpointId(200), completed_ck(true, vec![]));
+        completed_cks.insert(CheckpointId(300), completed_ck(false, vec![]));
+        completed_cks.insert(CheckpointId(DAY_MILLIS + 100), completed_ck(false, vec![]));
+        completed_cks.insert(
+            CheckpointId(DAY_MILLIS + 200),
+            completed_ck(false, vec![300]),
+        );
+        completed_cks.insert(CheckpointId(DAY_MILLIS + 300), completed_ck(false, vec![]));
+
+        let retention = CheckpointRetention::new(2, true);
+        let expired: HashSet<CheckpointId> = expired_checkpoints(&retention, &completed_cks)
+            .into_iter()
+            .collect();
+        let expect: HashSet<CheckpointId> = vec![CheckpointId(100), CheckpointId(DAY_MILLIS + 100)]
+            .into_iter()
+            .collect();
+        assert_eq!(expired, expect);
+
+        let retention = CheckpointRetention::new(1, false);
+        let expired = expired_checkpoints(&retention, &completed_cks);
+        assert_eq!(expired.len(), 5);
+    }
+
+    #[test]
+    pub fn load_completed_checkpoints_test() {
+        let mut storage = CheckpointStorage::new(&CheckpointBackend::Memory);
+        let savepoints = vec![100, 200];
+        for ck_id in vec![100, 200, 300, DAY_MILLIS + 100, DAY_MILLIS + 200] {
+            let mut ck = task_ck(ck_id, 0, None);
+            ck.savepoint = savepoints.contains(&ck_id);
+            storage
+                .save("test", "test", CheckpointId(ck_id), vec![ck], u64::MAX)
+                .unwrap();
+        }
+
+        // the checkpoints completed before the restart are pruned on startup
+        let mut manager = align_manager(2);
+        manager.checkpoint_retention = CheckpointRetention::new(1, true);
+        manager.storage = Some(storage);
+        manager.load().unwrap();
+
+        let retained: Vec<CheckpointId> = manager.completed_cks.keys().cloned().collect();
+        assert_eq!(
+            retained,
+            vec![CheckpointId(200), CheckpointId(DAY_MILLIS + 200)]
+        );
+        assert_eq!(
+            manager.savepoint_status().savepoint_id,
+            Some(CheckpointId(200))
+        );
+
+        let stored: Vec<CheckpointId> = manager
+            .storage
+            .as_mut()
+            .unwrap()
+            .load_all("test", "test")
+            .unwrap()
+            .into_iter()
+            .map(|ck| ck.checkpoint_id)
+            .collect();
+        assert_eq!(stored, retained);
+    }
 }
@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::core::cluster::TaskResourceInfo;
+use crate::core::error::ErrorClass;
+use crate::core::runtime::{CheckpointId, TaskId};
+use crate::runtime::coordinator::alert_manager;
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the max number of the latest events kept by the coordinator
+const EVENT_LOG_SIZE: usize = 10000;
+
+lazy_static! {
+    static ref EVENT_LOG: Mutex<EventLog> = Mutex::new(EventLog::new(EVENT_LOG_SIZE));
+}
+
+/// The lifecycle events of the application
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[serde(tag = "type")]
+pub enum EventKind {
+    ApplicationSubmitted {
+        application_id: String,
+        application_name: String,
+    },
+    WorkersAllocated {
+        num_tasks: usize,
+    },
+    WorkersRegistered {
+        task_manager_ids: Vec<String>,
+    },
+    WorkerLost {
+        task_manager_address: String,
+        heartbeat_lag_secs: u64,
+    },
+    WorkersStopped,
+    /// the worker received `SIGTERM`, the application is stopping with a savepoint
+    WorkerDraining {
+        task_manager_id: String,
+    },
+    /// the application is stopped with the savepoint for the draining workers,
+    /// it's redeployed from the savepoint and not counted as a restart
+    WorkersDrained {
+        task_manager_ids: Vec<String>,
+    },
+    ApplicationRestart {
+        startup_number: u64,
+    },
+    ApplicationTerminated,
+    CheckpointTriggered {
+        checkpoint_id: CheckpointId,
+    },
+    CheckpointCompleted {
+        checkpoint_id: CheckpointId,
+    },
+    CheckpointFailed {
+        checkpoint_id: CheckpointId,
+        reason: String,
+    },
+    SavepointCompleted {
+        checkpoint_id: CheckpointId,
+    },
+    TaskFailed {
+        task_id: TaskId,
+        class: ErrorClass,
+        code: u16,
+        message: String,
+    },
+    /// the application restarts too many times, it's terminated and the workers of the
+    /// last attempt are kept for the logs
+    CrashLoopDetected {
+        restarts: usize,
+        interval_secs: u64,
+        workers: Vec<TaskResourceInfo>,
+    },
+}
+
+impl EventKind {
+    pub fn name(&self) -> &str {
+        match self {
+            EventKind::ApplicationSubmitted { .. } => "ApplicationSubmitted",
+            EventKind::WorkersAllocated { .. } => "WorkersAllocated",
+            EventKind::WorkersRegistered { .. } => "WorkersRegistered",
+            EventKind::WorkerLost { .. } => "WorkerLost",
+            EventKind::WorkersStopped => "WorkersStopped",
+            EventKind::WorkerDraining { .. } => "WorkerDraining",
+            EventKind::WorkersDrained { .. } => "WorkersDrained",
+            EventKind::ApplicationRestart { .. } => "ApplicationRestart",
+            EventKind::ApplicationTerminated => "ApplicationTerminated",
+            EventKind::CheckpointTriggered { .. } => "CheckpointTriggered",
+            EventKind::CheckpointCompleted { .. } => "CheckpointCompleted",
+            EventKind::CheckpointFailed { .. } => "CheckpointFailed",
+            EventKind::SavepointCompleted { .. } => "SavepointCompleted",
+            EventKind::TaskFailed { .. } => "TaskFailed",
+            EventKind::CrashLoopDetected { .. } => "CrashLoopDetected",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Event {
+    /// the sequence number of the event, increase monotonically
+    pub id: u64,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+/// The query conditions of the events, all conditions are optional
+#[derive(Clone, Debug, Default)]
+pub struct EventQuery {
+    /// the min timestamp(inclusive) of the events
+    pub start: Option<u64>,
+    /// the max timestamp(exclusive) of the events
+    pub end: Option<u64>,
+    /// the name of the `EventKind`
+    pub kind: Option<String>,
+    /// the max number of the latest events returned
+    pub limit: Option<usize>,
+}
+
+impl EventQuery {
+    fn matches(&self, event: &Event) -> bool {
+        self.start
+            .map(|start| event.timestamp >= start)
+            .unwrap_or(true)
+            && self.end.map(|end| event.timestamp < end).unwrap_or(true)
+            && self
+                .kind
+                .as_ref()
+                .map(|kind| kind.eq(event.kind.name()))
+                .unwrap_or(true)
+    }
+}
+
+/// An append-only event store, the oldest events are dropped when it's full
+struct EventLog {
+    capacity: usize,
+    next_id: u64,
+    events: VecDeque<Event>,
+}
+
+impl EventLog {
+    fn new(capacity: usize) -> Self {
+        EventLog {
+            capacity,
+            next_id: 0,
+            events: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn append(&mut self, timestamp: u64, kind: EventKind) -> Event {
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+
+        let event = Event {
+            id: self.next_id,
+            timestamp,
+            kind,
+        };
+        self.events.push_back(event.clone());
+        self.next_id += 1;
+
+        event
+    }
+
+    /// the events matched the query, order by `id` asc
+    fn query(&self, query: &EventQuery) -> Vec<Event> {
+        let mut events: Vec<Event> = self
+            .events
+            .iter()
+            .rev()
+            .filter(|event| query.matches(event))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        events.reverse();
+        events
+    }
+}
+
+/// record a lifecycle event of the application
+pub(crate) fn record(kind: EventKind) {
+    info!("event: {:?}", kind);
+    let event = EVENT_LOG
+        .lock()
+        .unwrap()
+        .append(current_timestamp_millis(), kind);
+    alert_manager::on_event(&event);
+}
+
+pub(crate) fn query(query: &EventQuery) -> Vec<Event> {
+    EVENT_LOG.lock().unwrap().query(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::runtime::CheckpointId;
+    use crate::runtime::coordinator::event_log::{EventKind, EventLog, EventQuery};
+
+    #[test]
+    pub fn event_log_test() {
+        let mut event_log = EventLog::new(3);
+        event_log.append(1000, EventKind::WorkersAllocated { num_tasks: 2 });
+        event_log.append(
+            2000,
+            EventKind::CheckpointTriggered {
+                checkpoint_id: CheckpointId(1),
+            },
+        );
+        event_log.append(
+            3000,
+            EventKind::CheckpointFailed {
+                checkpoint_id: CheckpointId(1),
+                reason: "timeout".to_string(),
+            },
+        );
+        event_log.append(
+            4000,
+            EventKind::CheckpointTriggered {
+                checkpoint_id: CheckpointId(2),
+            },
+        );
+
+        // the oldest event is dropped
+        let events = event_log.query(&EventQuery::default());
+        assert_eq!(
+            events.iter().map(|x| x.id).collect::<Vec<u64>>(),
+            vec![1, 2, 3]
+        );
+
+        let query = EventQuery {
+            start: Some(2000),
+            end: Some(4000),
+            ..Default::default()
+        };
+        assert_eq!(event_log.query(&query).len(), 2);
+
+        let query = EventQuery {
+            kind: Some("CheckpointTriggered".to_string()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let events = event_log.query(&query);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, 3);
+
+        let json = serde_json::to_string(&events[0]).unwrap();
+        assert!(json.contains("\"type\":\"CheckpointTriggered\""));
+    }
+}
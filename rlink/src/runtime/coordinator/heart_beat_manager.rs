@@ -1,13 +1,47 @@
+use std::sync::Mutex;
 use std::time::Duration;
 
-use crate::core::cluster::MetadataStorageType;
+use crate::core::cluster::{MetadataStorageType, TaskResourceInfo};
 use crate::core::runtime::ManagerStatus;
+use crate::runtime::coordinator::event_log::{self, EventKind};
 use crate::storage::metadata::{loop_read_cluster_descriptor, MetadataStorage};
 use crate::utils;
 
+lazy_static! {
+    /// the task managers reported the `HeartBeatStatus::Draining`
+    static ref DRAINING_WORKERS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
 pub enum HeartbeatResult {
     Timeout,
     End,
+    /// the application is stopped for the draining workers, see `runtime::worker::drain`
+    Drained(Vec<String>),
+}
+
+/// mark the worker draining, return `false` if it's already marked
+pub(crate) fn on_worker_draining(task_manager_id: &str) -> bool {
+    let mut draining_workers = DRAINING_WORKERS.lock().unwrap();
+    if draining_workers.iter().any(|x| x.eq(task_manager_id)) {
+        return false;
+    }
+    draining_workers.push(task_manager_id.to_string());
+    true
+}
+
+/// the TaskManagers running the drained workers, they are excluded from the redeployment
+pub(crate) fn drained_task_managers(
+    worker_task_ids: &[TaskResourceInfo],
+    task_manager_ids: &[String],
+) -> Vec<String> {
+    let mut task_managers: Vec<String> = worker_task_ids
+        .iter()
+        .filter(|x| task_manager_ids.contains(&x.task_manager_id))
+        .filter_map(|x| x.task_manager_address().cloned())
+        .collect();
+    task_managers.sort();
+    task_managers.dedup();
+    task_managers
 }
 
 /// heartbeat timeout check
@@ -19,6 +53,10 @@ pub(crate) fn start_heartbeat_timer(metadata_storage_mode: MetadataStorageType)
         let cluster_descriptor = loop_read_cluster_descriptor(&metadata_storage);
 
         if cluster_descriptor.coordinator_manager.status == ManagerStatus::Terminated {
+            let draining_workers = std::mem::take(&mut *DRAINING_WORKERS.lock().unwrap());
+            if !draining_workers.is_empty() {
+                return HeartbeatResult::Drained(draining_workers);
+            }
             return HeartbeatResult::End;
         }
 
@@ -49,6 +87,12 @@ pub(crate) fn start_heartbeat_timer(metadata_storage_mode: MetadataStorageType)
                     dur.as_secs(),
                     task_manager_descriptor.task_manager_address
                 );
+                event_log::record(EventKind::WorkerLost {
+                    task_manager_address: task_manager_descriptor.task_manager_address.clone(),
+                    heartbeat_lag_secs: dur.as_secs(),
+                });
+                // the draining is interrupted by the failure
+                DRAINING_WORKERS.lock().unwrap().clear();
                 return HeartbeatResult::Timeout;
             }
         }
@@ -59,3 +103,38 @@ pub(crate) fn start_heartbeat_timer(metadata_storage_mode: MetadataStorageType)
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::core::cluster::TaskResourceInfo;
+    use crate::runtime::coordinator::heart_beat_manager::{
+        drained_task_managers, on_worker_draining, DRAINING_WORKERS,
+    };
+
+    #[test]
+    pub fn on_worker_draining_test() {
+        assert!(on_worker_draining("draining-test-1"));
+        assert!(!on_worker_draining("draining-test-1"));
+        assert!(on_worker_draining("draining-test-2"));
+
+        DRAINING_WORKERS
+            .lock()
+            .unwrap()
+            .retain(|x| !x.starts_with("draining-test-"));
+    }
+
+    #[test]
+    pub fn drained_task_managers_test() {
+        let worker_task_ids = vec![
+            TaskResourceInfo::new("1".to_string(), "tm-1".to_string(), "w-0".to_string()),
+            TaskResourceInfo::new("2".to_string(), "tm-2".to_string(), "w-1".to_string()),
+            TaskResourceInfo::new("3".to_string(), "tm-1".to_string(), "w-2".to_string()),
+        ];
+
+        let task_managers =
+            drained_task_managers(&worker_task_ids, &["w-0".to_string(), "w-2".to_string()]);
+        assert_eq!(task_managers, vec!["tm-1".to_string()]);
+
+        assert!(drained_task_managers(&worker_task_ids, &["w-3".to_string()]).is_empty());
+    }
+}
@@ -1,5 +1,4 @@
 use std::borrow::BorrowMut;
-use std::convert::TryFrom;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
@@ -8,29 +7,98 @@ use crate::core::checkpoint::CheckpointHandle;
 use crate::core::cluster::MetadataStorageType;
 use crate::core::cluster::TaskResourceInfo;
 use crate::core::env::{StreamApp, StreamExecutionEnvironment};
+use crate::core::error::ErrorClass;
+use crate::core::function::InputSplit;
+use crate::core::lineage::DEFAULT_LINEAGE_NAMESPACE;
+use crate::core::operator::{FunctionCreator, StreamOperator, TStreamOperator};
 use crate::core::properties::{InnerSystemProperties, Properties, SystemProperties};
-use crate::core::runtime::{ClusterDescriptor, ManagerStatus};
+use crate::core::runtime::{ClusterDescriptor, ManagerStatus, TaskDescriptor};
+use crate::dag::lineage::start_event;
 use crate::dag::metadata::DagMetadata;
+use crate::dag::validation::{validate, ValidationOptions, DEFAULT_MAX_PARALLELISM};
 use crate::dag::DagManager;
 use crate::deployment::TResourceManager;
 use crate::metrics::metric::Gauge;
 use crate::metrics::register_gauge;
 use crate::runtime::context::Context;
 use crate::runtime::coordinator::checkpoint_manager::CheckpointManager;
+use crate::runtime::coordinator::event_log::{EventKind, EventQuery};
 use crate::runtime::coordinator::heart_beat_manager::HeartbeatResult;
 use crate::runtime::coordinator::task_distribution::build_cluster_descriptor;
 use crate::runtime::coordinator::web_server::web_launch;
+use crate::runtime::init_application_labels;
+use crate::runtime::worker::heart_beat::HEARTBEAT_INTERVAL;
 use crate::storage::metadata::{
     loop_read_cluster_descriptor, loop_save_cluster_descriptor, loop_update_application_status,
     MetadataStorage,
 };
-use crate::utils::date_time::timestamp_str;
+use crate::utils::date_time::{current_timestamp_millis, timestamp_str};
 
+pub mod alert_manager;
 pub mod checkpoint_manager;
+pub mod event_log;
 pub mod heart_beat_manager;
+pub mod rate_budget_manager;
+pub mod source_position_manager;
+pub mod split_manager;
 pub mod task_distribution;
+pub mod watermark_manager;
+pub mod watermark_skew_manager;
 pub mod web_server;
 
+/// the default max restarts in the `DEFAULT_CRASH_LOOP_INTERVAL` before terminating,
+/// `0` means the crash loop detection is disabled and the application restarts forever
+pub const DEFAULT_CRASH_LOOP_MAX_RESTARTS: u32 = 0;
+/// the default period of counting the restarts for the crash loop detection
+pub const DEFAULT_CRASH_LOOP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// record the failure at `now` to the `failure_timestamps` of the coordinator, return the number
+/// of the restarts in the `interval` before `now` if the application is in a crash loop:
+/// it fails again after `max_restarts` restarts in the `interval`
+fn crash_loop_restarts(
+    failure_timestamps: &mut Vec<u64>,
+    max_restarts: u32,
+    interval: Duration,
+    now: u64,
+) -> Option<usize> {
+    if max_restarts == 0 {
+        return None;
+    }
+
+    let start = now.saturating_sub(interval.as_millis() as u64);
+    failure_timestamps.retain(|timestamp| *timestamp >= start);
+    failure_timestamps.push(now);
+
+    // the restarts are the failures before the current one
+    let restarts = failure_timestamps.len() - 1;
+    if restarts >= max_restarts as usize {
+        Some(restarts)
+    } else {
+        None
+    }
+}
+
+/// the class of the first task failure since the `start` timestamp which is in the
+/// `fatal_error_classes`
+fn fatal_task_failure(fatal_error_classes: &[ErrorClass], start: u64) -> Option<ErrorClass> {
+    if fatal_error_classes.is_empty() {
+        return None;
+    }
+
+    let query = EventQuery {
+        start: Some(start),
+        kind: Some("TaskFailed".to_string()),
+        ..Default::default()
+    };
+    event_log::query(&query)
+        .into_iter()
+        .filter_map(|event| match event.kind {
+            EventKind::TaskFailed { class, .. } => Some(class),
+            _ => None,
+        })
+        .find(|class| fatal_error_classes.contains(class))
+}
+
 pub(crate) struct CoordinatorTask<S, R>
 where
     S: StreamApp + 'static,
@@ -74,16 +142,62 @@ where
         info!("coordinator start with mode {}", self.context.manager_type);
 
         let application_properties = self.prepare_properties();
+        init_application_labels(
+            &application_properties
+                .get_application_labels()
+                .unwrap_or_default(),
+        );
 
         self.stream_app
             .build_stream(&application_properties, self.stream_env.borrow_mut());
 
+        if application_properties
+            .get_stream_graph_optimizer()
+            .unwrap_or(false)
+        {
+            let report = self.stream_env.stream_manager.optimize()?;
+            info!("stream graph optimized: {:?}", report);
+        }
+
         let dag_manager = {
             let raw_stream_graph = self.stream_env.stream_manager.stream_graph.borrow();
-            DagManager::try_from(raw_stream_graph.deref())?
+            let validation_options = ValidationOptions {
+                max_parallelism: application_properties
+                    .get_max_parallelism()
+                    .unwrap_or(DEFAULT_MAX_PARALLELISM),
+                connector_probe: application_properties
+                    .get_connector_probe()
+                    .unwrap_or(false),
+            };
+            validate(raw_stream_graph.deref(), &validation_options)?;
+            info!("stream graph validation success");
+
+            let force_network_edge = application_properties
+                .get_force_network_edge()
+                .unwrap_or(false);
+            DagManager::new(raw_stream_graph.deref(), force_network_edge)?
         };
         info!("DagManager build success");
 
+        if application_properties.get_dry_run().unwrap_or(false) {
+            info!("dry-run validation success, exit without allocating the resources");
+            return Ok(());
+        }
+
+        alert_manager::init(
+            self.context.application_id.clone(),
+            application_properties.get_application_name(),
+            application_properties.get_alert_rules().unwrap_or_default(),
+            std::mem::take(&mut self.stream_env.alert_notifiers),
+        );
+
+        event_log::record(EventKind::ApplicationSubmitted {
+            application_id: self.context.application_id.clone(),
+            application_name: application_properties.get_application_name(),
+        });
+
+        self.emit_lineage(&application_properties);
+
         let dag_metadata = DagMetadata::from(&dag_manager);
         debug!("DagMetadata: {}", dag_metadata.to_string());
 
@@ -98,7 +212,11 @@ where
         ck_manager.run_align_task();
         info!("start CheckpointManager align task");
 
-        self.web_serve(cluster_descriptor.borrow_mut(), ck_manager, dag_metadata);
+        self.web_serve(
+            cluster_descriptor.borrow_mut(),
+            ck_manager.clone(),
+            dag_metadata,
+        );
         info!(
             "serve coordinator web ui {}",
             &cluster_descriptor.coordinator_manager.web_address
@@ -110,9 +228,33 @@ where
 
         self.gauge_startup(&cluster_descriptor);
 
+        let fatal_error_classes = application_properties
+            .get_fatal_error_classes()
+            .unwrap_or_default();
+        let crash_loop_max_restarts = application_properties
+            .get_crash_loop_max_restarts()
+            .unwrap_or(DEFAULT_CRASH_LOOP_MAX_RESTARTS);
+        let crash_loop_interval = application_properties
+            .get_crash_loop_interval()
+            .unwrap_or(DEFAULT_CRASH_LOOP_INTERVAL);
+        let split_reassignment = application_properties
+            .get_split_reassignment()
+            .unwrap_or(false);
+
+        // the application is redeployed for the draining workers, it's not a failure restart
+        let mut drained = false;
+        // the TaskManagers of the drained workers, they are terminating
+        let mut excluded_task_managers = Vec::new();
+        // the timestamps of the failures for the crash loop detection
+        let mut failure_timestamps = Vec::new();
         // loop restart all tasks when some task is failure
         loop {
+            let startup_timestamp = current_timestamp_millis();
             self.gauge_startup_number(cluster_descriptor.borrow_mut());
+            let startup_number = cluster_descriptor.coordinator_manager.startup_number;
+            if startup_number > 1 && !drained {
+                event_log::record(EventKind::ApplicationRestart { startup_number });
+            }
 
             // save metadata to storage
             self.save_metadata(&cluster_descriptor);
@@ -121,9 +263,17 @@ where
             self.stream_app.pre_worker_startup(&cluster_descriptor);
             info!("pre-worker startup event");
 
-            // allocate all worker's resources
+            self.register_input_splits(&cluster_descriptor, split_reassignment);
+            info!("register the input splits and the split enumerators");
+
+            // allocate all worker's resources, the drained TaskManagers are excluded
+            self.resource_manager
+                .exclude_task_managers(std::mem::take(&mut excluded_task_managers));
             let worker_task_ids = self.allocate_worker();
             info!("allocate workers success");
+            event_log::record(EventKind::WorkersAllocated {
+                num_tasks: worker_task_ids.len(),
+            });
 
             // blocking util all worker's status is `Register` status
             self.waiting_worker_status_fine();
@@ -134,13 +284,59 @@ where
                 heart_beat_manager::start_heartbeat_timer(self.metadata_storage_mode.clone());
             info!("heartbeat timer has interrupted");
 
+            if let HeartbeatResult::Timeout = heartbeat_result {
+                if let Some(restarts) = crash_loop_restarts(
+                    &mut failure_timestamps,
+                    crash_loop_max_restarts,
+                    crash_loop_interval,
+                    current_timestamp_millis(),
+                ) {
+                    self.terminate_crash_loop(&worker_task_ids);
+                    event_log::record(EventKind::CrashLoopDetected {
+                        restarts,
+                        interval_secs: crash_loop_interval.as_secs(),
+                        workers: worker_task_ids,
+                    });
+                    event_log::record(EventKind::ApplicationTerminated);
+                    return Err(anyhow!(
+                        "failed again after {} restarts in {}s, terminate for the crash loop",
+                        restarts,
+                        crash_loop_interval.as_secs()
+                    ));
+                }
+            }
+
             // heartbeat timeout and stop all worker's tasks
-            self.stop_all_worker_tasks(worker_task_ids);
+            self.stop_all_worker_tasks(worker_task_ids.clone());
             info!("stop all workers");
+            event_log::record(EventKind::WorkersStopped);
 
             if let HeartbeatResult::End = heartbeat_result {
+                event_log::record(EventKind::ApplicationTerminated);
                 return Ok(());
             }
+
+            drained = false;
+            if let HeartbeatResult::Drained(task_manager_ids) = heartbeat_result {
+                info!(
+                    "workers {:?} drained, redeploy from the savepoint",
+                    task_manager_ids
+                );
+                excluded_task_managers =
+                    heart_beat_manager::drained_task_managers(&worker_task_ids, &task_manager_ids);
+                event_log::record(EventKind::WorkersDrained { task_manager_ids });
+                ck_manager.clear_stop_checkpoint_id();
+                drained = true;
+                continue;
+            }
+
+            if let Some(error_class) = fatal_task_failure(&fatal_error_classes, startup_timestamp) {
+                event_log::record(EventKind::ApplicationTerminated);
+                return Err(anyhow!(
+                    "task failed with the fatal {} error, terminate without restarting",
+                    error_class
+                ));
+            }
         }
     }
 
@@ -150,6 +346,17 @@ where
 
         self.stream_app
             .prepare_properties(application_properties.borrow_mut());
+        self.stream_env
+            .config
+            .apply(application_properties.borrow_mut());
+
+        if !self.context.labels.is_empty() {
+            let mut labels = application_properties
+                .get_application_labels()
+                .unwrap_or_default();
+            labels.extend(self.context.labels.clone());
+            application_properties.set_application_labels(&labels);
+        }
 
         let mut keys: Vec<&str> = application_properties
             .as_map()
@@ -166,6 +373,38 @@ where
         application_properties
     }
 
+    /// emit the lineage of the application to the registered emitters in a dedicated thread,
+    /// a failed emitting is logged and doesn't fail the submission
+    fn emit_lineage(&mut self, application_properties: &Properties) {
+        let lineage_emitters = std::mem::take(&mut self.stream_env.lineage_emitters);
+        if lineage_emitters.is_empty() {
+            return;
+        }
+
+        let run_event = {
+            let namespace = application_properties
+                .get_lineage_namespace()
+                .unwrap_or_else(|_| DEFAULT_LINEAGE_NAMESPACE.to_string());
+            let raw_stream_graph = self.stream_env.stream_manager.stream_graph.borrow();
+            start_event(
+                raw_stream_graph.deref(),
+                namespace.as_str(),
+                application_properties.get_application_name().as_str(),
+                uuid::Uuid::new_v4().to_string().as_str(),
+                current_timestamp_millis(),
+            )
+        };
+
+        crate::utils::thread::spawn("lineage-emitter", move || {
+            for lineage_emitter in lineage_emitters {
+                match lineage_emitter.emit(&run_event) {
+                    Ok(_) => info!("lineage emitted by {:?}", lineage_emitter),
+                    Err(e) => error!("emit lineage by {:?} error. {}", lineage_emitter, e),
+                }
+            }
+        });
+    }
+
     fn build_metadata(
         &mut self,
         dag_manager: &DagManager,
@@ -197,12 +436,17 @@ where
         let checkpoint_ttl = application_properties
             .get_checkpoint_ttl()
             .unwrap_or_else(|_e| Duration::from_secs(1 * 60 * 60));
+        let checkpoint_retention = application_properties
+            .get_checkpoint_retention()
+            .unwrap_or_default();
 
         let mut ck_manager = CheckpointManager::new(
             dag_manager,
             &self.context,
             cluster_descriptor,
             checkpoint_ttl,
+            checkpoint_retention,
+            self.stream_env.checkpoint_key_provider.clone(),
         );
         let operator_checkpoints = ck_manager.load().expect("load checkpoints error");
         if operator_checkpoints.len() == 0 {
@@ -213,16 +457,19 @@ where
             for task_descriptor in &mut task_manager_descriptor.task_descriptors {
                 let task_number = task_descriptor.task_id.task_number;
                 for operator in &mut task_descriptor.operators {
-                    let cks = operator_checkpoints.get(&operator.operator_id).unwrap();
-                    if cks.len() == 0 {
-                        debug!("operator {:?} checkpoint not found", operator.operator_id);
-                        continue;
-                    }
-
-                    let ck = cks
-                        .iter()
-                        .find(|ck| ck.task_id.task_number == task_number)
-                        .unwrap();
+                    // the operator may be absent in the savepoint of the previous application
+                    let ck = operator_checkpoints
+                        .get(&operator.operator_id)
+                        .and_then(|cks| {
+                            cks.iter().find(|ck| ck.task_id.task_number == task_number)
+                        });
+                    let ck = match ck {
+                        Some(ck) => ck,
+                        None => {
+                            debug!("operator {:?} checkpoint not found", operator.operator_id);
+                            continue;
+                        }
+                    };
                     operator.checkpoint_id = ck.checkpoint_id;
                     operator.checkpoint_handle = Some(CheckpointHandle {
                         handle: ck.handle.handle.clone(),
@@ -232,9 +479,109 @@ where
             }
         }
 
+        self.restore_input_splits(cluster_descriptor);
+
         ck_manager
     }
 
+    /// let the sources reassign the `InputSplit`s by the restored checkpoint handles
+    fn restore_input_splits(&self, cluster_descriptor: &mut ClusterDescriptor) {
+        let raw_stream_graph = self.stream_env.stream_manager.stream_graph.borrow();
+        for (operator_id, operator) in raw_stream_graph.operators() {
+            let op = match operator {
+                StreamOperator::StreamSource(op) => op,
+                _ => continue,
+            };
+
+            let mut task_descriptors: Vec<&mut TaskDescriptor> = cluster_descriptor
+                .worker_managers
+                .iter_mut()
+                .flat_map(|x| x.task_descriptors.iter_mut())
+                .filter(|x| x.operators[0].operator_id == operator_id)
+                .collect();
+            task_descriptors.sort_by_key(|x| x.task_id.task_number);
+
+            let input_splits: Vec<InputSplit> = task_descriptors
+                .iter()
+                .map(|x| x.input_split.clone())
+                .collect();
+            let checkpoint_handles: Vec<Option<CheckpointHandle>> = task_descriptors
+                .iter()
+                .map(|x| x.operators[0].checkpoint_handle.clone())
+                .collect();
+
+            let input_splits = op
+                .operator_fn
+                .restore_input_splits(input_splits, checkpoint_handles.as_slice())
+                .expect("restore input splits error");
+            if input_splits.len() != task_descriptors.len() {
+                panic!(
+                    "{}'s restored input_splits size = {}, but tasks size = {}",
+                    op.operator_fn.name(),
+                    input_splits.len(),
+                    task_descriptors.len()
+                );
+            }
+
+            for (task_descriptor, input_split) in task_descriptors.into_iter().zip(input_splits) {
+                task_descriptor.input_split = input_split;
+            }
+        }
+    }
+
+    /// start the split enumerators of the user sources, see `InputSplitSource::split_enumerator`,
+    /// and track the splits of the other bounded user sources if `split_reassignment`,
+    /// see `SystemProperties::set_split_reassignment`
+    fn register_input_splits(
+        &self,
+        cluster_descriptor: &ClusterDescriptor,
+        split_reassignment: bool,
+    ) {
+        let raw_stream_graph = self.stream_env.stream_manager.stream_graph.borrow();
+        for (operator_id, operator) in raw_stream_graph.operators() {
+            let op = match operator {
+                StreamOperator::StreamSource(op) => op,
+                _ => continue,
+            };
+            if !matches!(op.fn_creator(), FunctionCreator::User) {
+                continue;
+            }
+
+            if let Some(enumerator) = op.operator_fn.split_enumerator() {
+                let job_id = cluster_descriptor
+                    .worker_managers
+                    .iter()
+                    .flat_map(|x| x.task_descriptors.iter())
+                    .find(|x| x.operators[0].operator_id == operator_id)
+                    .map(|x| x.task_id.job_id);
+                if let Some(job_id) = job_id {
+                    split_manager::register_enumerator(job_id, enumerator);
+                }
+                continue;
+            }
+
+            if !split_reassignment || !op.operator_fn.bounded() {
+                continue;
+            }
+
+            let mut task_descriptors: Vec<&TaskDescriptor> = cluster_descriptor
+                .worker_managers
+                .iter()
+                .flat_map(|x| x.task_descriptors.iter())
+                .filter(|x| x.operators[0].operator_id == operator_id)
+                .collect();
+            task_descriptors.sort_by_key(|x| x.task_id.task_number);
+
+            if let Some(task_descriptor) = task_descriptors.first() {
+                let input_splits = task_descriptors
+                    .iter()
+                    .map(|x| (x.task_id, x.input_split.clone()))
+                    .collect();
+                split_manager::register(task_descriptor.task_id.job_id, input_splits);
+            }
+        }
+    }
+
     fn web_serve(
         &self,
         cluster_descriptor: &mut ClusterDescriptor,
@@ -277,6 +624,13 @@ where
                     ManagerStatus::Registered,
                 );
                 info!("all workers status fine and Job update state to `Registered`");
+                event_log::record(EventKind::WorkersRegistered {
+                    task_manager_ids: job_descriptor
+                        .worker_managers
+                        .iter()
+                        .map(|tm| tm.task_manager_id.clone())
+                        .collect(),
+                });
                 job_descriptor.worker_managers.iter().for_each(|tm| {
                     info!(
                         "Registered List: `{}` registered at {}",
@@ -291,6 +645,23 @@ where
         }
     }
 
+    /// terminate the application in a crash loop without stopping the workers by the resource
+    /// manager, so the failed workers(eg: the pods, the containers and the workspaces) and their
+    /// logs are kept. The alive workers cancel the tasks and exit once they see the `Terminated`
+    /// coordinator in the heartbeat
+    fn terminate_crash_loop(&self, worker_task_ids: &[TaskResourceInfo]) {
+        let mut metadata_storage = MetadataStorage::new(&self.metadata_storage_mode);
+        loop_update_application_status(metadata_storage.borrow_mut(), ManagerStatus::Terminated);
+
+        error!(
+            "crash loop detected, the workers are kept for the logs: {:?}",
+            worker_task_ids
+        );
+
+        // wait the alive workers seeing the `Terminated` status in the heartbeat response
+        std::thread::sleep(HEARTBEAT_INTERVAL * 2);
+    }
+
     fn stop_all_worker_tasks(&self, worker_task_ids: Vec<TaskResourceInfo>) {
         // loop stop all workers util all are success
         loop {
@@ -329,3 +700,47 @@ where
             .store(cluster_descriptor.coordinator_manager.startup_number as i64);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::runtime::coordinator::crash_loop_restarts;
+
+    #[test]
+    pub fn crash_loop_restarts_test() {
+        let interval = Duration::from_secs(60);
+
+        // disabled by default
+        let mut failure_timestamps = Vec::new();
+        for n in 0..10 {
+            assert_eq!(
+                crash_loop_restarts(&mut failure_timestamps, 0, interval, n * 1000),
+                None
+            );
+        }
+        assert!(failure_timestamps.is_empty());
+
+        let mut failure_timestamps = Vec::new();
+        assert_eq!(
+            crash_loop_restarts(&mut failure_timestamps, 2, interval, 1000),
+            None
+        );
+        assert_eq!(
+            crash_loop_restarts(&mut failure_timestamps, 2, interval, 2000),
+            None
+        );
+        assert_eq!(
+            crash_loop_restarts(&mut failure_timestamps, 2, interval, 3000),
+            Some(2)
+        );
+
+        // the failures out of the interval are not counted
+        let mut failure_timestamps = vec![1000, 2000];
+        assert_eq!(
+            crash_loop_restarts(&mut failure_timestamps, 2, interval, 61_500),
+            None
+        );
+        assert_eq!(failure_timestamps, vec![2000, 61_500]);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::core::rate_budget::{allocate_rate_caps, RateBudget, TaskRateCap};
+use crate::core::runtime::TaskId;
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the task report is expired if it's not refreshed in three report intervals
+const TASK_RATE_TTL_MS: u64 = 3 * crate::core::rate_budget::RATE_REPORT_INTERVAL_MS;
+
+lazy_static! {
+    static ref RATE_BUDGET_MANAGER: Mutex<RateBudgetManager> =
+        Mutex::new(RateBudgetManager::default());
+}
+
+/// the consumption rate of the task reported by the heartbeat
+pub(crate) fn on_task_rate(
+    task_id: TaskId,
+    budget: &RateBudget,
+    records_per_second: f64,
+    cap: f64,
+) {
+    let now = current_timestamp_millis();
+    RATE_BUDGET_MANAGER
+        .lock()
+        .unwrap()
+        .on_task_rate(task_id, budget, records_per_second, cap, now);
+}
+
+/// the rate caps of the tasks, the unknown tasks are ignored
+pub(crate) fn rate_caps(task_ids: &[(String, TaskId)]) -> Vec<TaskRateCap> {
+    let now = current_timestamp_millis();
+    RATE_BUDGET_MANAGER.lock().unwrap().rate_caps(task_ids, now)
+}
+
+#[derive(Debug)]
+struct TaskRate {
+    records_per_second: f64,
+    cap: f64,
+    timestamp: u64,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    records_per_second: f64,
+    tasks: HashMap<TaskId, TaskRate>,
+}
+
+/// Split the `RateBudget`s into the per-task caps by the rates reported by the tasks,
+/// the caps are recomputed on every query so they follow the latest reports of all workers
+#[derive(Debug, Default)]
+struct RateBudgetManager {
+    budgets: HashMap<String, BudgetState>,
+}
+
+impl RateBudgetManager {
+    fn on_task_rate(
+        &mut self,
+        task_id: TaskId,
+        budget: &RateBudget,
+        records_per_second: f64,
+        cap: f64,
+        now: u64,
+    ) {
+        let state = self
+            .budgets
+            .entry(budget.name.clone())
+            .or_insert_with(|| BudgetState {
+                records_per_second: budget.records_per_second,
+                tasks: HashMap::new(),
+            });
+        // the latest declared budget wins, eg: a restarted job with the new configuration
+        state.records_per_second = budget.records_per_second;
+        state.tasks.insert(
+            task_id,
+            TaskRate {
+                records_per_second,
+                cap,
+                timestamp: now,
+            },
+        );
+    }
+
+    fn rate_caps(&mut self, task_ids: &[(String, TaskId)], now: u64) -> Vec<TaskRateCap> {
+        let mut rate_caps = Vec::new();
+        for (budget, state) in &mut self.budgets {
+            state
+                .tasks
+                .retain(|_task_id, task_rate| task_rate.timestamp + TASK_RATE_TTL_MS > now);
+            if !task_ids.iter().any(|(name, _task_id)| name.eq(budget)) {
+                continue;
+            }
+
+            let tasks: Vec<(&TaskId, &TaskRate)> = state.tasks.iter().collect();
+            let rates: Vec<(f64, f64)> = tasks
+                .iter()
+                .map(|(_task_id, task_rate)| (task_rate.records_per_second, task_rate.cap))
+                .collect();
+            let caps = allocate_rate_caps(state.records_per_second, rates.as_slice());
+
+            for ((task_id, _task_rate), cap) in tasks.into_iter().zip(caps) {
+                if task_ids
+                    .iter()
+                    .any(|(name, id)| name.eq(budget) && id.eq(task_id))
+                {
+                    rate_caps.push(TaskRateCap {
+                        task_id: *task_id,
+                        budget: budget.clone(),
+                        records_per_second: cap,
+                    });
+                }
+            }
+        }
+
+        rate_caps
+    }
+}
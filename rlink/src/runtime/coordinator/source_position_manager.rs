@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::core::function::SourcePosition;
+use crate::core::runtime::TaskId;
+use crate::utils::date_time::current_timestamp_millis;
+
+lazy_static! {
+    static ref SOURCE_POSITIONS: Mutex<HashMap<TaskId, TaskPosition>> = Mutex::new(HashMap::new());
+}
+
+/// the latest read position of the source task reported by the heartbeat
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TaskPosition {
+    pub task_id: TaskId,
+    pub position: SourcePosition,
+    /// the timestamp of the coordinator receiving the report
+    pub timestamp: u64,
+}
+
+pub(crate) fn on_task_position(task_id: TaskId, position: SourcePosition) {
+    let timestamp = current_timestamp_millis();
+    SOURCE_POSITIONS.lock().unwrap().insert(
+        task_id,
+        TaskPosition {
+            task_id,
+            position,
+            timestamp,
+        },
+    );
+}
+
+/// the positions of all source tasks, ordered by the operator and the task number
+pub(crate) fn positions() -> Vec<TaskPosition> {
+    let mut positions: Vec<TaskPosition> =
+        SOURCE_POSITIONS.lock().unwrap().values().cloned().collect();
+    positions.sort_by_key(|x| (x.task_id.job_id.0, x.task_id.task_number));
+    positions
+}
@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::core::function::{InputSplit, SplitEnumerator};
+use crate::core::runtime::{JobId, TaskId};
+use crate::runtime::{SplitRequest, SplitResponse, SplitStatus};
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the attempts of a split before the application is failed, see `SplitManager::next_split`
+pub const DEFAULT_SPLIT_MAX_ATTEMPTS: u32 = 3;
+
+lazy_static! {
+    static ref SPLIT_MANAGER: Mutex<SplitManager> = Mutex::new(SplitManager::default());
+}
+
+/// track the `input_splits` of the bounded source job, indexed by the task number,
+/// the previous state of the job is dropped by the restarted application
+pub(crate) fn register(job_id: JobId, input_splits: Vec<(TaskId, InputSplit)>) {
+    SPLIT_MANAGER.lock().unwrap().register(job_id, input_splits);
+}
+
+/// hand out the splits discovered by the `enumerator` to the tasks of the job on request,
+/// the previous state of the job is dropped by the restarted application
+pub(crate) fn register_enumerator(job_id: JobId, enumerator: Box<dyn SplitEnumerator>) {
+    SPLIT_MANAGER
+        .lock()
+        .unwrap()
+        .register_enumerator(job_id, enumerator);
+}
+
+pub(crate) fn on_split_status(task_id: TaskId, split_number: u16, status: SplitStatus) {
+    SPLIT_MANAGER
+        .lock()
+        .unwrap()
+        .on_split_status(task_id, split_number, status);
+}
+
+pub(crate) fn on_task_failed(task_id: TaskId) {
+    SPLIT_MANAGER.lock().unwrap().on_task_failed(task_id);
+}
+
+pub(crate) fn next_split(request: &SplitRequest) -> anyhow::Result<SplitResponse> {
+    SPLIT_MANAGER.lock().unwrap().next_split(request)
+}
+
+/// the splits of all tracked jobs, ordered by the job and the split number
+pub(crate) fn splits() -> Vec<TrackedSplit> {
+    SPLIT_MANAGER.lock().unwrap().splits()
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TrackedSplit {
+    pub job_id: JobId,
+    pub input_split: InputSplit,
+    pub status: SplitStatus,
+    /// the task assigned or processing the split, `None` if it's `Pending`
+    pub task_id: Option<TaskId>,
+    /// the failed attempts of the split
+    pub failures: u32,
+}
+
+struct EnumeratorState {
+    enumerator: Box<dyn SplitEnumerator>,
+    last_discovery_timestamp: u64,
+}
+
+impl EnumeratorState {
+    /// discover the new splits if the `discovery_interval` elapsed since the last discovery
+    fn discover(&mut self, job_id: JobId) -> Vec<InputSplit> {
+        let now = current_timestamp_millis();
+        let interval = self.enumerator.discovery_interval().as_millis() as u64;
+        if self.last_discovery_timestamp > 0 && now < self.last_discovery_timestamp + interval {
+            return Vec::new();
+        }
+        self.last_discovery_timestamp = now;
+
+        match self.enumerator.discover() {
+            Ok(input_splits) => input_splits,
+            Err(e) => {
+                error!("discover the splits of job {:?} error. {}", job_id, e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrackedJob {
+    splits: Vec<TrackedSplit>,
+    enumerator: Option<EnumeratorState>,
+}
+
+impl TrackedJob {
+    fn discover(&mut self, job_id: JobId) {
+        let enumerator = match self.enumerator.as_mut() {
+            Some(enumerator) => enumerator,
+            None => return,
+        };
+        for input_split in enumerator.discover(job_id) {
+            let split_number = input_split.split_number();
+            if self
+                .splits
+                .iter()
+                .any(|split| split.input_split.split_number() == split_number)
+            {
+                continue;
+            }
+            info!("discover the split {} of job {:?}", split_number, job_id);
+            self.splits.push(TrackedSplit {
+                job_id,
+                input_split,
+                status: SplitStatus::Pending,
+                task_id: None,
+                failures: 0,
+            });
+        }
+    }
+
+    /// no more splits will be discovered
+    fn is_exhausted(&self) -> bool {
+        self.enumerator
+            .as_ref()
+            .map(|x| x.enumerator.is_exhausted())
+            .unwrap_or(true)
+    }
+}
+
+/// The lifecycle of the `InputSplit`s of the bounded sources. A split is `Assigned` to a task,
+/// `Processing` after the task opened it and `Completed` after the task read it to the end.
+/// The unfinished splits of a failed task are `Pending` again and handed out to the tasks of
+/// the same job requesting the next split, so the other splits are not read again.
+///
+/// The splits of the sources with a `SplitEnumerator` are `Pending` after discovered and all of
+/// them are pulled by the tasks, the earliest discovered split is handed out first.
+struct SplitManager {
+    jobs: HashMap<JobId, TrackedJob>,
+    max_attempts: u32,
+}
+
+impl Default for SplitManager {
+    fn default() -> Self {
+        SplitManager {
+            jobs: HashMap::new(),
+            max_attempts: DEFAULT_SPLIT_MAX_ATTEMPTS,
+        }
+    }
+}
+
+impl SplitManager {
+    fn register(&mut self, job_id: JobId, input_splits: Vec<(TaskId, InputSplit)>) {
+        let splits = input_splits
+            .into_iter()
+            .map(|(task_id, input_split)| TrackedSplit {
+                job_id,
+                input_split,
+                status: SplitStatus::Assigned,
+                task_id: Some(task_id),
+                failures: 0,
+            })
+            .collect();
+        self.jobs.insert(
+            job_id,
+            TrackedJob {
+                splits,
+                enumerator: None,
+            },
+        );
+    }
+
+    fn register_enumerator(&mut self, job_id: JobId, enumerator: Box<dyn SplitEnumerator>) {
+        let mut job = TrackedJob {
+            splits: Vec::new(),
+            enumerator: Some(EnumeratorState {
+                enumerator,
+                last_discovery_timestamp: 0,
+            }),
+        };
+        job.discover(job_id);
+        self.jobs.insert(job_id, job);
+    }
+
+    fn split_mut(&mut self, job_id: JobId, split_number: u16) -> Option<&mut TrackedSplit> {
+        self.jobs.get_mut(&job_id).and_then(|job| {
+            job.splits
+                .iter_mut()
+                .find(|split| split.input_split.split_number() == split_number)
+        })
+    }
+
+    fn on_split_status(&mut self, task_id: TaskId, split_number: u16, status: SplitStatus) {
+        match self.split_mut(task_id.job_id, split_number) {
+            Some(split) => {
+                // the late report of a reassigned split is ignored
+                if split.task_id == Some(task_id) && split.status != SplitStatus::Completed {
+                    split.status = status;
+                }
+            }
+            None => warn!(
+                "the split {} of task {:?} is not tracked",
+                split_number, task_id
+            ),
+        }
+    }
+
+    fn on_task_failed(&mut self, task_id: TaskId) {
+        if let Some(job) = self.jobs.get_mut(&task_id.job_id) {
+            for split in job.splits.iter_mut() {
+                if split.task_id == Some(task_id) && split.status != SplitStatus::Completed {
+                    info!(
+                        "release the split {} of the failed task {:?}",
+                        split.input_split.split_number(),
+                        task_id
+                    );
+                    split.status = SplitStatus::Pending;
+                    split.task_id = None;
+                    split.failures += 1;
+                }
+            }
+        }
+    }
+
+    /// complete or fail the previous split of the task and assign a `Pending` split to it,
+    /// `Wait` if the other splits are not completed yet, they may fail and be reassigned,
+    /// `End` if all splits are completed and no more splits will be discovered.
+    /// Error if a split failed `max_attempts` times.
+    fn next_split(&mut self, request: &SplitRequest) -> anyhow::Result<SplitResponse> {
+        let task_id = request.task_id;
+        if let Some(split_number) = request.completed {
+            self.on_split_status(task_id, split_number, SplitStatus::Completed);
+        }
+        if let Some(split_number) = request.failed {
+            if let Some(split) = self.split_mut(task_id.job_id, split_number) {
+                if split.task_id == Some(task_id) && split.status != SplitStatus::Completed {
+                    split.status = SplitStatus::Pending;
+                    split.task_id = None;
+                    split.failures += 1;
+                }
+            }
+        }
+
+        let max_attempts = self.max_attempts;
+        let job = match self.jobs.get_mut(&task_id.job_id) {
+            Some(job) => job,
+            None => return Ok(SplitResponse::End),
+        };
+        if let Some(split) = job
+            .splits
+            .iter()
+            .find(|split| split.failures >= max_attempts)
+        {
+            return Err(anyhow!(
+                "the split {} of job {:?} failed {} times",
+                split.input_split.split_number(),
+                task_id.job_id,
+                split.failures
+            ));
+        }
+
+        if !job
+            .splits
+            .iter()
+            .any(|split| split.status == SplitStatus::Pending)
+        {
+            job.discover(task_id.job_id);
+        }
+
+        if let Some(split) = job
+            .splits
+            .iter_mut()
+            .find(|split| split.status == SplitStatus::Pending)
+        {
+            info!(
+                "assign the split {} to task {:?}",
+                split.input_split.split_number(),
+                task_id
+            );
+            split.status = SplitStatus::Assigned;
+            split.task_id = Some(task_id);
+            return Ok(SplitResponse::Assigned(split.input_split.clone()));
+        }
+
+        if job.is_exhausted()
+            && job
+                .splits
+                .iter()
+                .all(|split| split.status == SplitStatus::Completed)
+        {
+            Ok(SplitResponse::End)
+        } else {
+            Ok(SplitResponse::Wait)
+        }
+    }
+
+    fn splits(&self) -> Vec<TrackedSplit> {
+        let mut splits: Vec<TrackedSplit> = self
+            .jobs
+            .values()
+            .flat_map(|job| job.splits.iter())
+            .cloned()
+            .collect();
+        splits.sort_by_key(|split| (split.job_id.0, split.input_split.split_number()));
+        splits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::function::{InputSplit, SplitEnumerator};
+    use crate::core::properties::Properties;
+    use crate::core::runtime::{JobId, TaskId};
+    use crate::runtime::coordinator::split_manager::SplitManager;
+    use crate::runtime::{SplitRequest, SplitResponse, SplitStatus};
+
+    fn task_id(task_number: u16) -> TaskId {
+        TaskId {
+            job_id: JobId(1),
+            task_number,
+            num_tasks: 2,
+        }
+    }
+
+    fn request(task_number: u16, completed: Option<u16>, failed: Option<u16>) -> SplitRequest {
+        SplitRequest {
+            task_id: task_id(task_number),
+            completed,
+            failed,
+        }
+    }
+
+    /// discover a split on each call, exhausted after 3 splits
+    struct MockEnumerator {
+        next_split_number: u16,
+    }
+
+    impl SplitEnumerator for MockEnumerator {
+        fn discover(&mut self) -> crate::core::Result<Vec<InputSplit>> {
+            if self.is_exhausted() {
+                return Ok(Vec::new());
+            }
+            let input_split = InputSplit::new(self.next_split_number, Properties::new());
+            self.next_split_number += 1;
+            Ok(vec![input_split])
+        }
+
+        fn is_exhausted(&self) -> bool {
+            self.next_split_number >= 3
+        }
+
+        fn discovery_interval(&self) -> Duration {
+            Duration::from_millis(0)
+        }
+    }
+
+    #[test]
+    pub fn split_reassignment_test() {
+        let mut manager = SplitManager::default();
+        manager.register(
+            JobId(1),
+            vec![
+                (task_id(0), InputSplit::new(0, Properties::new())),
+                (task_id(1), InputSplit::new(1, Properties::new())),
+            ],
+        );
+        manager.on_split_status(task_id(0), 0, SplitStatus::Processing);
+        manager.on_split_status(task_id(1), 1, SplitStatus::Processing);
+
+        // task 0 completed, task 1 is processing
+        let response = manager.next_split(&request(0, Some(0), None)).unwrap();
+        assert!(matches!(response, SplitResponse::Wait));
+
+        // task 1 failed, its split is reassigned to task 0
+        manager.on_task_failed(task_id(1));
+        match manager.next_split(&request(0, None, None)).unwrap() {
+            SplitResponse::Assigned(input_split) => assert_eq!(input_split.split_number(), 1),
+            response => panic!("unexpected {:?}", response),
+        }
+
+        let response = manager.next_split(&request(0, Some(1), None)).unwrap();
+        assert!(matches!(response, SplitResponse::End));
+
+        // fail the application after the max attempts
+        manager.register(
+            JobId(1),
+            vec![(task_id(0), InputSplit::new(0, Properties::new()))],
+        );
+        for _ in 0..2 {
+            let response = manager.next_split(&request(0, None, Some(0))).unwrap();
+            assert!(matches!(response, SplitResponse::Assigned(_)));
+        }
+        assert!(manager.next_split(&request(0, None, Some(0))).is_err());
+    }
+
+    #[test]
+    pub fn split_enumerator_test() {
+        let mut manager = SplitManager::default();
+        manager.register_enumerator(
+            JobId(1),
+            Box::new(MockEnumerator {
+                next_split_number: 0,
+            }),
+        );
+
+        let assigned = |response: SplitResponse| match response {
+            SplitResponse::Assigned(input_split) => input_split.split_number(),
+            response => panic!("unexpected {:?}", response),
+        };
+
+        // the fast task 0 pulls the splits discovered on demand
+        assert_eq!(
+            assigned(manager.next_split(&request(0, None, None)).unwrap()),
+            0
+        );
+        assert_eq!(
+            assigned(manager.next_split(&request(1, None, None)).unwrap()),
+            1
+        );
+        assert_eq!(
+            assigned(manager.next_split(&request(0, Some(0), None)).unwrap()),
+            2
+        );
+
+        let response = manager.next_split(&request(0, Some(2), None)).unwrap();
+        assert!(matches!(response, SplitResponse::Wait));
+        let response = manager.next_split(&request(1, Some(1), None)).unwrap();
+        assert!(matches!(response, SplitResponse::End));
+    }
+}
@@ -51,6 +51,7 @@ pub(crate) fn build_cluster_descriptor(
             metrics_address: "".to_string(),
             web_address: "".to_string(),
             task_descriptors,
+            resource_usage: None,
         };
         worker_managers.push(task_manager_descriptor);
     }
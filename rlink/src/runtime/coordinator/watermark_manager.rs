@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::core::runtime::{JobId, OperatorId, TaskId};
+use crate::core::watermark::{MAX_WATERMARK, MIN_WATERMARK};
+use crate::dag::metadata::DagMetadata;
+use crate::dag::OperatorType;
+use crate::utils::date_time::current_timestamp_millis;
+
+lazy_static! {
+    static ref TASK_WATERMARKS: Mutex<HashMap<TaskId, u64>> = Mutex::new(HashMap::new());
+}
+
+/// the watermark of the task reported by the heartbeat, the watermark of the assigner in the
+/// source job or the aligned input watermark of the downstream job
+pub(crate) fn on_watermark(task_id: TaskId, watermark: u64) {
+    if watermark > MIN_WATERMARK.timestamp {
+        TASK_WATERMARKS.lock().unwrap().insert(task_id, watermark);
+    }
+}
+
+/// the low watermarks of the jobs and the operators, and the event time progress of the
+/// application, all records before `event_time` are processed by the sink jobs
+pub(crate) fn watermarks(dag_metadata: &DagMetadata) -> WatermarkReport {
+    let task_watermarks = TASK_WATERMARKS.lock().unwrap().clone();
+
+    let mut jobs = Vec::new();
+    let mut operators = Vec::new();
+    let mut sink_watermarks = Vec::new();
+    for job_node in dag_metadata.job_graph().nodes() {
+        let job_node = job_node.detail();
+        let job_id = job_node.job_id;
+
+        let mut tasks: Vec<TaskWatermark> = task_watermarks
+            .iter()
+            .filter(|(task_id, _)| task_id.job_id == job_id)
+            .map(|(task_id, watermark)| TaskWatermark {
+                task_number: task_id.task_number,
+                watermark: *watermark,
+            })
+            .collect();
+        tasks.sort_by_key(|x| x.task_number);
+        let low_watermark = low_watermark(tasks.as_slice(), job_node.parallelism);
+
+        // the operators before the assigner of the source job have no watermark
+        let mut assigned = !job_node.parent_job_ids.is_empty();
+        for stream_node in &job_node.stream_nodes {
+            if stream_node.operator_type == OperatorType::WatermarkAssigner {
+                assigned = true;
+            }
+            operators.push(OperatorWatermark {
+                operator_id: stream_node.id,
+                job_id,
+                name: stream_node.display_name().to_string(),
+                low_watermark: low_watermark.filter(|_| assigned),
+            });
+        }
+
+        if job_node.child_job_ids.is_empty() {
+            sink_watermarks.push(low_watermark);
+        }
+        jobs.push(JobWatermark {
+            job_id,
+            parallelism: job_node.parallelism,
+            low_watermark,
+            tasks,
+        });
+    }
+
+    let now = current_timestamp_millis();
+    let event_time = event_time(sink_watermarks.as_slice());
+    WatermarkReport {
+        timestamp: now,
+        event_time,
+        event_time_lag: event_time
+            .filter(|x| *x < MAX_WATERMARK.timestamp)
+            .map(|x| now.saturating_sub(x)),
+        jobs,
+        operators,
+    }
+}
+
+/// the min watermark of the job's tasks, `None` until all tasks reported
+fn low_watermark(tasks: &[TaskWatermark], parallelism: u16) -> Option<u64> {
+    if tasks.len() < parallelism as usize {
+        return None;
+    }
+    tasks.iter().map(|x| x.watermark).min()
+}
+
+/// the min low watermark of the sink jobs, `None` if any of them is unknown
+fn event_time(sink_watermarks: &[Option<u64>]) -> Option<u64> {
+    sink_watermarks
+        .iter()
+        .try_fold(MAX_WATERMARK.timestamp, |min, x| x.map(|x| min.min(x)))
+        .filter(|_| !sink_watermarks.is_empty())
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct WatermarkReport {
+    /// the processing time of the report
+    pub timestamp: u64,
+    /// the records with the event time before it are completed, `MAX_WATERMARK` if all jobs end
+    pub event_time: Option<u64>,
+    /// the processing time minus the `event_time`
+    pub event_time_lag: Option<u64>,
+    pub jobs: Vec<JobWatermark>,
+    pub operators: Vec<OperatorWatermark>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct JobWatermark {
+    pub job_id: JobId,
+    pub parallelism: u16,
+    /// the min watermark of the tasks, `None` until all tasks reported
+    pub low_watermark: Option<u64>,
+    pub tasks: Vec<TaskWatermark>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TaskWatermark {
+    pub task_number: u16,
+    pub watermark: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct OperatorWatermark {
+    pub operator_id: OperatorId,
+    pub job_id: JobId,
+    pub name: String,
+    pub low_watermark: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::watermark::MAX_WATERMARK;
+    use crate::runtime::coordinator::watermark_manager::{
+        event_time, low_watermark, TaskWatermark,
+    };
+
+    #[test]
+    pub fn low_watermark_test() {
+        let tasks = vec![
+            TaskWatermark {
+                task_number: 0,
+                watermark: 2000,
+            },
+            TaskWatermark {
+                task_number: 1,
+                watermark: 1000,
+            },
+        ];
+        assert_eq!(low_watermark(&tasks[..1], 2), None);
+        assert_eq!(low_watermark(tasks.as_slice(), 2), Some(1000));
+
+        assert_eq!(event_time(&[Some(1000), Some(500)]), Some(500));
+        assert_eq!(event_time(&[Some(1000), None]), None);
+        assert_eq!(event_time(&[]), None);
+        let ended = MAX_WATERMARK.timestamp;
+        assert_eq!(event_time(&[Some(ended)]), Some(ended));
+    }
+}
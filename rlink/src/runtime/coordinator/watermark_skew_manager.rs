@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::core::runtime::{JobId, TaskId};
+use crate::core::watermark::{MAX_WATERMARK, MIN_WATERMARK};
+use crate::metrics::metric::Gauge;
+use crate::metrics::{register_gauge, Tag};
+use crate::utils::date_time::current_timestamp_millis;
+
+lazy_static! {
+    static ref WATERMARK_SKEWS: Mutex<WatermarkSkews> = Mutex::new(WatermarkSkews::default());
+}
+
+/// the watermark of the task reported by the heartbeat, gauge the skew between the tasks
+/// of the same source job:
+/// - `WatermarkSkew`: the max watermark minus the min watermark of the tasks
+/// - `WatermarkMaxLag`: the processing time minus the min watermark of the tasks
+pub(crate) fn on_watermark(task_id: TaskId, watermark: u64) {
+    let now = current_timestamp_millis();
+    let mut watermark_skews = WATERMARK_SKEWS.lock().unwrap();
+    watermark_skews.on_watermark(task_id, watermark);
+    watermark_skews.gauge(task_id.job_id, now);
+}
+
+#[derive(Default)]
+struct WatermarkSkews {
+    /// key: the source job, value: key: task_number, value: the latest watermark
+    watermarks: HashMap<JobId, HashMap<u16, u64>>,
+    /// key: the source job, value: (skew gauge, max lag gauge)
+    gauges: HashMap<JobId, (Gauge, Gauge)>,
+}
+
+impl WatermarkSkews {
+    fn on_watermark(&mut self, task_id: TaskId, watermark: u64) {
+        let watermarks = self.watermarks.entry(task_id.job_id).or_default();
+        if watermark >= MAX_WATERMARK.timestamp {
+            // the stream of the task is end, it's no more a laggard
+            watermarks.remove(&task_id.task_number);
+        } else if watermark > MIN_WATERMARK.timestamp {
+            watermarks.insert(task_id.task_number, watermark);
+        }
+    }
+
+    /// (the max watermark minus the min watermark, the min watermark) of the job's tasks
+    fn skew(&self, job_id: JobId) -> Option<(u64, u64)> {
+        let watermarks = self.watermarks.get(&job_id)?;
+        let min = watermarks.values().min()?;
+        let max = watermarks.values().max()?;
+        Some((max - min, *min))
+    }
+
+    fn gauge(&mut self, job_id: JobId, now: u64) {
+        let (skew, min_watermark) = match self.skew(job_id) {
+            Some(skew) => skew,
+            None => return,
+        };
+
+        let (skew_gauge, lag_gauge) = self.gauges.entry(job_id).or_insert_with(|| {
+            let tags = vec![Tag::new("job_id", job_id.0)];
+            (
+                register_gauge("WatermarkSkew", tags.clone()),
+                register_gauge("WatermarkMaxLag", tags),
+            )
+        });
+        skew_gauge.store(skew as i64);
+        lag_gauge.store(now.saturating_sub(min_watermark) as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::runtime::{JobId, TaskId};
+    use crate::core::watermark::MAX_WATERMARK;
+    use crate::runtime::coordinator::watermark_skew_manager::WatermarkSkews;
+
+    fn task_id(task_number: u16) -> TaskId {
+        TaskId {
+            job_id: JobId(1),
+            task_number,
+            num_tasks: 3,
+        }
+    }
+
+    #[test]
+    pub fn watermark_skew_test() {
+        let mut watermark_skews = WatermarkSkews::default();
+        watermark_skews.on_watermark(task_id(0), 1000);
+        watermark_skews.on_watermark(task_id(1), 1500);
+        // not started
+        watermark_skews.on_watermark(task_id(2), 0);
+        assert_eq!(watermark_skews.skew(JobId(1)), Some((500, 1000)));
+
+        watermark_skews.on_watermark(task_id(0), MAX_WATERMARK.timestamp);
+        assert_eq!(watermark_skews.skew(JobId(1)), Some((0, 1500)));
+        assert_eq!(watermark_skews.skew(JobId(2)), None);
+    }
+}
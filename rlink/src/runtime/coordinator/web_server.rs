@@ -15,13 +15,26 @@ use rand::Rng;
 use crate::channel::{bounded, Sender};
 use crate::core::checkpoint::Checkpoint;
 use crate::core::cluster::{MetadataStorageType, StdResponse};
-use crate::core::runtime::ManagerStatus;
+use crate::core::properties::SystemProperties;
+use crate::core::runtime::{CheckpointId, HeartBeatStatus, ManagerStatus, ResourceUsage};
 use crate::dag::metadata::DagMetadata;
+use crate::runtime::coordinator::alert_manager;
 use crate::runtime::coordinator::checkpoint_manager::CheckpointManager;
-use crate::runtime::HeartbeatRequest;
+use crate::runtime::coordinator::event_log::{self, EventKind, EventQuery};
+use crate::runtime::coordinator::heart_beat_manager;
+use crate::runtime::coordinator::rate_budget_manager;
+use crate::runtime::coordinator::source_position_manager;
+use crate::runtime::coordinator::split_manager;
+use crate::runtime::coordinator::watermark_manager;
+use crate::runtime::coordinator::watermark_skew_manager;
+use crate::runtime::{
+    HeartbeatItem, HeartbeatRequest, HeartbeatResponse, SplitRequest, SplitResponse,
+};
 use crate::storage::metadata::{MetadataStorage, TMetadataStorage};
+use crate::utils::date_time::current_timestamp_millis;
 use crate::utils::fs::read_binary;
 use crate::utils::http::server::{as_ok_json, page_not_found};
+use crate::utils::object_store::object_store;
 use crate::utils::thread::async_runtime_multi;
 
 pub(crate) fn web_launch(
@@ -113,23 +126,36 @@ async fn route(req: Request<Body>, web_context: Arc<WebContext>) -> anyhow::Resu
     let path = req.uri().path();
     let method = req.method();
 
-    if path.starts_with("/api/") {
+    if path.starts_with("/applications/") {
+        application_route(req, web_context).await
+    } else if path.starts_with("/api/") {
         if Method::GET.eq(method) {
             match path {
                 "/api/context" => get_context(req, web_context).await,
                 "/api/cluster_metadata" => get_cluster_metadata(req, web_context).await,
                 "/api/checkpoints" => get_checkpoint(req, web_context).await,
+                "/api/checkpoints/stats" => get_checkpoint_stats(req, web_context).await,
                 "/api/dag_metadata" => get_dag_metadata(req, web_context).await,
                 "/api/dag/stream_graph" => get_stream_graph(req, web_context).await,
                 "/api/dag/job_graph" => get_job_graph(req, web_context).await,
                 "/api/dag/execution_graph" => get_execution_graph(req, web_context).await,
+                "/api/dag/operators" => get_operators(req, web_context).await,
                 "/api/threads" => get_thread_infos(req, web_context).await,
+                "/api/events" => get_events(req, web_context).await,
+                "/api/source/positions" => get_source_positions(req, web_context).await,
+                "/api/splits" => get_splits(req, web_context).await,
+                "/api/watermarks" => get_watermarks(req, web_context).await,
+                "/api/workers/resources" => get_worker_resources(req, web_context).await,
+                "/api/savepoint" => get_savepoint(req, web_context).await,
+                path if path.starts_with("/api/cache/") => get_cached_file(req, web_context).await,
                 _ => page_not_found().await,
             }
         } else if Method::POST.eq(method) {
             match path {
                 "/api/heartbeat" => heartbeat(req, web_context).await,
                 "/api/checkpoint" => checkpoint(req, web_context).await,
+                "/api/savepoint" => trigger_savepoint(req, web_context).await,
+                "/api/splits/next" => next_split(req, web_context).await,
                 _ => page_not_found().await,
             }
         } else {
@@ -144,6 +170,39 @@ async fn route(req: Request<Body>, web_context: Arc<WebContext>) -> anyhow::Resu
     }
 }
 
+/// the application scoped api, the `{id}` must be the application id of the coordinator
+/// - `POST /applications/{id}/checkpoints`: trigger a checkpoint, response the `checkpoint_id`
+/// - `GET /applications/{id}/checkpoints/{checkpoint_id}`: the progress of the triggered checkpoint
+/// - `POST /applications/{id}/stop-with-savepoint`: take a savepoint and then end the streams,
+///   response the `checkpoint_id` of the savepoint
+async fn application_route(
+    req: Request<Body>,
+    web_context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let segments: Vec<&str> = req.uri().path()["/applications/".len()..]
+        .split('/')
+        .filter(|x| !x.is_empty())
+        .collect();
+    if segments.first() != Some(&web_context.context.application_id.as_str()) {
+        return page_not_found().await;
+    }
+
+    let method = req.method();
+    match &segments[1..] {
+        ["checkpoints"] if Method::POST.eq(method) => trigger_checkpoint(req, web_context).await,
+        ["stop-with-savepoint"] if Method::POST.eq(method) => {
+            stop_with_savepoint(req, web_context).await
+        }
+        ["checkpoints", checkpoint_id] if Method::GET.eq(method) => match checkpoint_id.parse() {
+            Ok(checkpoint_id) => {
+                get_checkpoint_trigger_status(CheckpointId(checkpoint_id), web_context).await
+            }
+            Err(_) => page_not_found().await,
+        },
+        _ => page_not_found().await,
+    }
+}
+
 async fn get_context(
     _req: Request<Body>,
     context: Arc<WebContext>,
@@ -169,6 +228,14 @@ async fn get_checkpoint(
     as_ok_json(&StdResponse::ok(Some(cks)))
 }
 
+async fn get_checkpoint_stats(
+    _req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let stats = context.checkpoint_manager.get_stats();
+    as_ok_json(&StdResponse::ok(Some(stats)))
+}
+
 async fn get_dag_metadata(
     _req: Request<Body>,
     context: Arc<WebContext>,
@@ -201,6 +268,14 @@ async fn get_execution_graph(
     as_ok_json(&StdResponse::ok(Some(json_dag)))
 }
 
+async fn get_operators(
+    _req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let operators = context.dag_metadata.operator_infos();
+    as_ok_json(&StdResponse::ok(Some(operators)))
+}
+
 async fn get_thread_infos(
     _req: Request<Body>,
     _context: Arc<WebContext>,
@@ -209,6 +284,124 @@ async fn get_thread_infos(
     as_ok_json(&StdResponse::ok(Some(c)))
 }
 
+/// query the lifecycle events, eg: `/api/events?start=1620000000000&end=1620003600000&type=WorkerLost&limit=100`
+async fn get_events(
+    req: Request<Body>,
+    _context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let mut query = EventQuery::default();
+    for pair in req.uri().query().unwrap_or("").split('&') {
+        let (key, value) = match pair.split_once('=') {
+            Some(kv) => kv,
+            None => continue,
+        };
+        match key {
+            "start" => query.start = Some(value.parse()?),
+            "end" => query.end = Some(value.parse()?),
+            "type" => query.kind = Some(value.to_string()),
+            "limit" => query.limit = Some(value.parse()?),
+            _ => {}
+        }
+    }
+
+    let events = event_log::query(&query);
+    as_ok_json(&StdResponse::ok(Some(events)))
+}
+
+async fn get_source_positions(
+    _req: Request<Body>,
+    _context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let positions = source_position_manager::positions();
+    as_ok_json(&StdResponse::ok(Some(positions)))
+}
+
+/// the lifecycle of the splits of the bounded sources, see `SystemProperties::set_split_reassignment`
+async fn get_splits(
+    _req: Request<Body>,
+    _context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let splits = split_manager::splits();
+    as_ok_json(&StdResponse::ok(Some(splits)))
+}
+
+/// the low watermarks of the jobs and the operators, and the event time progress of the sinks
+async fn get_watermarks(
+    _req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let watermarks = watermark_manager::watermarks(&context.dag_metadata);
+    as_ok_json(&StdResponse::ok(Some(watermarks)))
+}
+
+/// the next split of the source task requesting it, see `SplitRequest`
+async fn next_split(
+    req: Request<Body>,
+    _context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let whole_body = hyper::body::aggregate(req).await?;
+    let request: SplitRequest = serde_json::from_reader(whole_body.reader())?;
+
+    let resp: StdResponse<SplitResponse> = split_manager::next_split(&request).into();
+    as_ok_json(&resp)
+}
+
+#[derive(Serialize)]
+struct WorkerResourceUsage {
+    task_manager_id: String,
+    resource_usage: Option<ResourceUsage>,
+}
+
+/// the latest resource usage of the workers reported by the heartbeat
+async fn get_worker_resources(
+    _req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let metadata_storage = MetadataStorage::new(&context.metadata_mode);
+    let cluster_descriptor = metadata_storage.load()?;
+    let resources: Vec<WorkerResourceUsage> = cluster_descriptor
+        .worker_managers
+        .into_iter()
+        .map(|worker_manager| WorkerResourceUsage {
+            task_manager_id: worker_manager.task_manager_id,
+            resource_usage: worker_manager.resource_usage,
+        })
+        .collect();
+    as_ok_json(&StdResponse::ok(Some(resources)))
+}
+
+/// serve the file registered by `SystemProperties::register_cached_file`
+async fn get_cached_file(
+    req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let name = &req.uri().path()["/api/cache/".len()..];
+    let metadata_storage = MetadataStorage::new(&context.metadata_mode);
+    let cluster_descriptor = metadata_storage.load()?;
+    let cached_file = cluster_descriptor
+        .coordinator_manager
+        .application_properties
+        .get_cached_files()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|x| x.name.eq(name));
+    let cached_file = match cached_file {
+        Some(cached_file) => cached_file,
+        None => return page_not_found().await,
+    };
+
+    let (store, path) = object_store(cached_file.uri.as_str())?;
+    let data = tokio::task::spawn_blocking(move || store.get(path.as_str())).await??;
+    match data {
+        Some(data) => Response::builder()
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .status(StatusCode::OK)
+            .body(Body::from(data))
+            .map_err(|e| anyhow!(e)),
+        None => page_not_found().await,
+    }
+}
+
 async fn heartbeat(req: Request<Body>, context: Arc<WebContext>) -> anyhow::Result<Response<Body>> {
     let whole_body = hyper::body::aggregate(req).await?;
     let HeartbeatRequest {
@@ -216,6 +409,89 @@ async fn heartbeat(req: Request<Body>, context: Arc<WebContext>) -> anyhow::Resu
         change_items,
     } = serde_json::from_reader(whole_body.reader())?;
 
+    let mut rate_tasks = Vec::new();
+    for change_item in &change_items {
+        match change_item {
+            HeartbeatItem::TaskWatermark { task_id, watermark } => {
+                alert_manager::on_watermark(*task_id, *watermark);
+                watermark_skew_manager::on_watermark(*task_id, *watermark);
+                watermark_manager::on_watermark(*task_id, *watermark);
+            }
+            HeartbeatItem::TaskInputWatermark { task_id, watermark } => {
+                watermark_manager::on_watermark(*task_id, *watermark);
+            }
+            HeartbeatItem::TaskCheckpointRequested {
+                task_id,
+                checkpoint_id,
+            } => {
+                info!(
+                    "checkpoint_id={:?} is requested by the source {:?}",
+                    checkpoint_id, task_id
+                );
+                context
+                    .checkpoint_manager
+                    .request_checkpoint(*checkpoint_id);
+            }
+            HeartbeatItem::TaskStateLimitExceeded {
+                task_id,
+                operator_id,
+                message,
+            } => {
+                alert_manager::on_state_limit_exceeded(*task_id, *operator_id, message.as_str());
+            }
+            HeartbeatItem::TaskRate {
+                task_id,
+                budget,
+                records_per_second,
+                cap,
+            } => {
+                rate_budget_manager::on_task_rate(*task_id, budget, *records_per_second, *cap);
+                rate_tasks.push((budget.name.clone(), *task_id));
+            }
+            HeartbeatItem::TaskPosition { task_id, position } => {
+                source_position_manager::on_task_position(*task_id, position.clone());
+            }
+            // the worker keeps reporting `Draining`, only request the savepoint once
+            HeartbeatItem::HeartBeatStatus(HeartBeatStatus::Draining)
+                if heart_beat_manager::on_worker_draining(task_manager_id.as_str()) =>
+            {
+                match context.checkpoint_manager.stop_with_savepoint() {
+                    Ok(checkpoint_id) => {
+                        info!(
+                            "worker {} is draining, stop with savepoint {:?}",
+                            task_manager_id, checkpoint_id
+                        );
+                        event_log::record(EventKind::WorkerDraining {
+                            task_manager_id: task_manager_id.clone(),
+                        });
+                    }
+                    Err(e) => error!(
+                        "worker {} is draining, stop with savepoint error. {}",
+                        task_manager_id, e
+                    ),
+                }
+            }
+            HeartbeatItem::TaskSplit {
+                task_id,
+                split_number,
+                status,
+            } => {
+                split_manager::on_split_status(*task_id, *split_number, *status);
+            }
+            HeartbeatItem::TaskFailure { task_id, error } => {
+                split_manager::on_task_failed(*task_id);
+                event_log::record(EventKind::TaskFailed {
+                    task_id: *task_id,
+                    class: error.class,
+                    code: error.code,
+                    message: error.message.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+    let rate_caps = rate_budget_manager::rate_caps(rate_tasks.as_slice());
+
     let metadata_storage = MetadataStorage::new(&context.metadata_mode);
     let coordinator_status = metadata_storage.update_worker_status(
         task_manager_id,
@@ -223,7 +499,19 @@ async fn heartbeat(req: Request<Body>, context: Arc<WebContext>) -> anyhow::Resu
         ManagerStatus::Registered,
     );
 
-    let resp: StdResponse<ManagerStatus> = coordinator_status.into();
+    let completed_checkpoint_id = context.checkpoint_manager.get_completed_checkpoint_id();
+    let triggered_checkpoint_id = context.checkpoint_manager.get_pending_checkpoint_id();
+    let stop_checkpoint_id = context.checkpoint_manager.get_stop_checkpoint_id();
+    let resp: StdResponse<HeartbeatResponse> = coordinator_status
+        .map(|coordinator_status| HeartbeatResponse {
+            coordinator_status,
+            completed_checkpoint_id,
+            rate_caps,
+            triggered_checkpoint_id,
+            stop_checkpoint_id,
+            coordinator_timestamp: current_timestamp_millis(),
+        })
+        .into();
     as_ok_json(&resp)
 }
 
@@ -247,6 +535,50 @@ async fn checkpoint(
     as_ok_json(&StdResponse::ok(Some(resp.to_string())))
 }
 
+/// the savepoint progress, the savepoint is completed when `savepoint_id` is the
+/// `checkpoint_id` responded by the trigger
+async fn get_savepoint(
+    _req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let status = context.checkpoint_manager.get_savepoint_status();
+    as_ok_json(&StdResponse::ok(Some(status)))
+}
+
+/// trigger a checkpoint as a savepoint, response the `checkpoint_id` of the savepoint
+async fn trigger_savepoint(
+    _req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let resp: StdResponse<CheckpointId> = context.checkpoint_manager.request_savepoint().into();
+    as_ok_json(&resp)
+}
+
+/// trigger a checkpoint immediately, a triggered checkpoint not started yet is reused
+async fn trigger_checkpoint(
+    _req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let checkpoint_id = context.checkpoint_manager.trigger_checkpoint();
+    as_ok_json(&StdResponse::ok(Some(checkpoint_id)))
+}
+
+async fn stop_with_savepoint(
+    _req: Request<Body>,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let resp: StdResponse<CheckpointId> = context.checkpoint_manager.stop_with_savepoint().into();
+    as_ok_json(&resp)
+}
+
+async fn get_checkpoint_trigger_status(
+    checkpoint_id: CheckpointId,
+    context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let status = context.checkpoint_manager.get_trigger_status(checkpoint_id);
+    as_ok_json(&StdResponse::ok(Some(status)))
+}
+
 async fn static_file(
     req: Request<Body>,
     context: Arc<WebContext>,
@@ -1,4 +1,6 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 use log::LevelFilter;
 use log::LevelFilter::Warn;
@@ -6,18 +8,51 @@ use log4rs::append::console::{ConsoleAppender, Target};
 use log4rs::append::Append;
 use log4rs::config::{Appender, Config, Logger, Root};
 use log4rs::encode::pattern::PatternEncoder;
+use log4rs::Handle;
+
+const DEFAULT_PATTERN_PREFIX: &str = "{d(%Y-%m-%d %H:%M:%S%.3f)} {level} [{thread}]";
+const DEFAULT_PATTERN_SUFFIX: &str = "{target} - {m}{n}";
+
+lazy_static! {
+    /// the handle of the default config, `None` if the config is loaded from the file
+    static ref DEFAULT_LOG_HANDLE: Mutex<Option<Handle>> = Mutex::new(None);
+}
 
 pub(crate) fn init_log(log_config_path: Option<String>) -> anyhow::Result<()> {
-    let config = match log_config_path {
+    let (config, default_config) = match log_config_path {
         Some(log_config_path) => {
             let path = PathBuf::from(log_config_path);
-            load_config_from_file(path)?
+            (load_config_from_file(path)?, false)
         }
-        None => init_default()?,
+        None => (init_default("")?, true),
     };
 
     println!("{:?}", &config);
-    log4rs::init_config(config)?;
+    let handle = log4rs::init_config(config)?;
+    if default_config {
+        *DEFAULT_LOG_HANDLE.lock().unwrap() = Some(handle);
+    }
+
+    Ok(())
+}
+
+/// tag the logs of the default config with the application labels, eg: `[tenant=acme]`.
+/// the config loaded from the file is kept as it is
+pub(crate) fn set_log_labels(labels: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    if labels.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(handle) = DEFAULT_LOG_HANDLE.lock().unwrap().as_ref() {
+        let labels = labels
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<String>>()
+            .join(",");
+        // the braces are the pattern specifiers
+        let labels = labels.replace('{', "{{").replace('}', "}}");
+        handle.set_config(init_default(format!("[{}] ", labels).as_str())?);
+    }
 
     Ok(())
 }
@@ -26,11 +61,14 @@ fn load_config_from_file(path: PathBuf) -> anyhow::Result<Config> {
     log4rs::config::load_config_file(path, Default::default())
 }
 
-fn init_default() -> Result<Config, log4rs::config::runtime::ConfigErrors> {
+fn init_default(labels: &str) -> Result<Config, log4rs::config::runtime::ConfigErrors> {
     let name = "console";
     let default_level = LevelFilter::Info;
-    let encoder =
-        PatternEncoder::new("{d(%Y-%m-%d %H:%M:%S%.3f)} {level} [{thread}] {target} - {m}{n}");
+    let pattern = format!(
+        "{} {}{}",
+        DEFAULT_PATTERN_PREFIX, labels, DEFAULT_PATTERN_SUFFIX
+    );
+    let encoder = PatternEncoder::new(pattern.as_str());
     let appender = create_console_appender(encoder);
     Config::builder()
         .appender(Appender::builder().build(name, appender))
@@ -1,8 +1,16 @@
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
 
 use crate::core::env::{StreamApp, StreamExecutionEnvironment};
-use crate::core::runtime::{HeartBeatStatus, TaskId};
+use crate::core::error::ErrorReport;
+use crate::core::function::{InputSplit, SourcePosition};
+use crate::core::rate_budget::{RateBudget, TaskRateCap};
+use crate::core::runtime::{
+    CheckpointId, HeartBeatStatus, ManagerStatus, OperatorId, ResourceUsage, TaskId,
+};
+use crate::metrics::metric::set_global_tags;
+use crate::metrics::Tag;
 use crate::utils::panic::panic_notify;
 
 pub mod cluster;
@@ -101,8 +109,88 @@ pub enum HeartbeatItem {
     WorkerManagerWebAddress(String),
     MetricsAddress(String),
     HeartBeatStatus(HeartBeatStatus),
-    TaskThreadId { task_id: TaskId, thread_id: u64 },
-    TaskEnd { task_id: TaskId },
+    TaskThreadId {
+        task_id: TaskId,
+        thread_id: u64,
+    },
+    TaskEnd {
+        task_id: TaskId,
+    },
+    /// the task is failed with the error, reported before the task thread panic
+    TaskFailure {
+        task_id: TaskId,
+        error: ErrorReport,
+    },
+    TaskWatermark {
+        task_id: TaskId,
+        watermark: u64,
+    },
+    /// the source of the task emitted the barrier of a requested checkpoint, the other sources
+    /// emit the same barrier, see `CheckpointRequester`
+    TaskCheckpointRequested {
+        task_id: TaskId,
+        checkpoint_id: CheckpointId,
+    },
+    /// the keyed state of the operator in the task exceeds its `StateLimit`
+    TaskStateLimitExceeded {
+        task_id: TaskId,
+        operator_id: OperatorId,
+        message: String,
+    },
+    /// the aligned input watermark of the task of a downstream job
+    TaskInputWatermark {
+        task_id: TaskId,
+        watermark: u64,
+    },
+    /// the consumption rate of the task in the `RateBudget`
+    TaskRate {
+        task_id: TaskId,
+        budget: RateBudget,
+        records_per_second: f64,
+        cap: f64,
+    },
+    /// the read position of the source task, see `InputFormat::position`
+    TaskPosition {
+        task_id: TaskId,
+        position: SourcePosition,
+    },
+    /// the resource usage of the worker process
+    ResourceUsage(ResourceUsage),
+    /// the lifecycle of the split read by the source task, see `SystemProperties::set_split_reassignment`
+    TaskSplit {
+        task_id: TaskId,
+        split_number: u16,
+        status: SplitStatus,
+    },
+}
+
+/// the lifecycle of an `InputSplit` of the bounded source tracked by the coordinator
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub enum SplitStatus {
+    /// the split of a failed task waiting for the reassignment
+    Pending,
+    Assigned,
+    Processing,
+    Completed,
+}
+
+/// the request of a source task for the next split after the previous one is read
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub(crate) struct SplitRequest {
+    pub task_id: TaskId,
+    /// the split read to the end
+    pub completed: Option<u16>,
+    /// the split failed in the middle, it's reassigned to a task of the job
+    pub failed: Option<u16>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub(crate) enum SplitResponse {
+    Assigned(InputSplit),
+    /// no split to assign now, but the other splits are not completed and may be reassigned
+    Wait,
+    /// all splits of the job are completed
+    End,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -111,6 +199,45 @@ pub(crate) struct HeartbeatRequest {
     pub change_items: Vec<HeartbeatItem>,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub(crate) struct HeartbeatResponse {
+    pub coordinator_status: ManagerStatus,
+    /// the latest completed checkpoint of the application
+    pub completed_checkpoint_id: Option<CheckpointId>,
+    /// the rate caps of the tasks reported `TaskRate` in the heartbeat
+    #[serde(default)]
+    pub rate_caps: Vec<TaskRateCap>,
+    /// the checkpoint triggered on demand, the sources emit its barrier without waiting for
+    /// the interval timer
+    #[serde(default)]
+    pub triggered_checkpoint_id: Option<CheckpointId>,
+    /// the savepoint of the stop-with-savepoint, the sources stop reading after its barrier
+    /// and end the stream after it completed
+    #[serde(default)]
+    pub stop_checkpoint_id: Option<CheckpointId>,
+    /// the coordinator's clock when responding, the workers sync the ingestion time clock by it
+    #[serde(default)]
+    pub coordinator_timestamp: u64,
+}
+
+/// tag all metrics and logs of the process with the application labels
+pub(crate) fn init_application_labels(labels: &BTreeMap<String, String>) {
+    if labels.is_empty() {
+        return;
+    }
+
+    info!("application labels: {:?}", labels);
+    set_global_tags(
+        labels
+            .iter()
+            .map(|(name, value)| Tag::new(name, value))
+            .collect(),
+    );
+    if let Err(e) = logger::set_log_labels(labels) {
+        warn!("tag the logs with the labels error. {}", e);
+    }
+}
+
 pub fn run<S>(stream_env: StreamExecutionEnvironment, stream_app: S) -> anyhow::Result<()>
 where
     S: StreamApp + 'static,
@@ -0,0 +1,179 @@
+//! The assertion mode, see `SystemProperties::set_assertion_mode`.
+//!
+//! The sources stamp each record with a continuous sequence per task, the sinks check the stamps
+//! against the `ProcessingGuarantee`. The duplicates are checked on arrival, the gaps are checked
+//! when the last source and sink of the process are closed, and only for the sources whose
+//! records reach the sinks, so the checks are effective in the `ClusterMode::Local` where all
+//! tasks run in one process. All stamps seen are kept in memory until the end of the run.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Mutex;
+
+use crate::core::checkpoint::ProcessingGuarantee;
+use crate::core::element::RecordSequence;
+
+lazy_static! {
+    static ref SEQUENCE_CHECKER: Mutex<SequenceChecker> = Mutex::new(SequenceChecker::default());
+}
+
+/// a source task, (the operator id of the source, the task number)
+type SourceTask = (u32, u16);
+
+#[derive(Debug, Default)]
+pub(crate) struct SequenceChecker {
+    open_sources: usize,
+    open_sinks: usize,
+    guarantee: Option<ProcessingGuarantee>,
+    /// the number of the records stamped by each closed source task
+    stamped: HashMap<SourceTask, u64>,
+    /// the (sequence, fanout) seen by the sinks of each source task
+    seen: HashMap<SourceTask, HashSet<(u64, u16)>>,
+}
+
+impl SequenceChecker {
+    pub fn on_source_open(&mut self) {
+        self.open_sources += 1;
+    }
+
+    pub fn on_source_close(&mut self, source: u32, task_number: u16, stamped: u64) {
+        self.open_sources -= 1;
+        self.stamped.insert((source, task_number), stamped);
+        self.try_check_gaps();
+    }
+
+    pub fn on_sink_open(&mut self, guarantee: ProcessingGuarantee) {
+        self.open_sinks += 1;
+        self.guarantee = Some(guarantee);
+    }
+
+    pub fn on_sink_close(&mut self) {
+        self.open_sinks -= 1;
+        self.try_check_gaps();
+    }
+
+    /// panic if the record is a duplicate and the guarantee doesn't allow it
+    pub fn on_record(&mut self, guarantee: ProcessingGuarantee, sequence: &RecordSequence) {
+        let source_task = (sequence.source, sequence.task_number);
+        let first_seen = self
+            .seen
+            .entry(source_task)
+            .or_default()
+            .insert((sequence.sequence, sequence.fanout));
+        if !first_seen && !guarantee.allow_duplicate() {
+            panic!(
+                "assertion mode {:?} violated, duplicate record {:?}",
+                guarantee, sequence
+            );
+        }
+    }
+
+    fn try_check_gaps(&mut self) {
+        if self.open_sources > 0 || self.open_sinks > 0 {
+            return;
+        }
+
+        if let Some(guarantee) = self.guarantee.take() {
+            let gaps = self.gaps();
+            if !gaps.is_empty() && !guarantee.allow_gap() {
+                panic!(
+                    "assertion mode {:?} violated, the missing sequences of (source, task_number): {:?}",
+                    guarantee, gaps
+                );
+            }
+        }
+        self.seen.clear();
+        self.stamped.clear();
+    }
+
+    /// the missing sequences of the source tasks whose records reach the sinks
+    fn gaps(&self) -> HashMap<SourceTask, Vec<u64>> {
+        let mut gaps = HashMap::new();
+        for (source_task, seen) in &self.seen {
+            let sequences: BTreeSet<u64> = seen.iter().map(|(sequence, _)| *sequence).collect();
+            let stamped = self.stamped.get(source_task).cloned().unwrap_or_else(|| {
+                sequences
+                    .iter()
+                    .next_back()
+                    .map(|x| x + 1)
+                    .unwrap_or_default()
+            });
+            let missing: Vec<u64> = (0..stamped).filter(|x| !sequences.contains(x)).collect();
+            if !missing.is_empty() {
+                gaps.insert(*source_task, missing);
+            }
+        }
+        gaps
+    }
+}
+
+pub(crate) fn on_source_open() {
+    SEQUENCE_CHECKER.lock().unwrap().on_source_open();
+}
+
+pub(crate) fn on_source_close(source: u32, task_number: u16, stamped: u64) {
+    SEQUENCE_CHECKER
+        .lock()
+        .unwrap()
+        .on_source_close(source, task_number, stamped);
+}
+
+pub(crate) fn on_sink_open(guarantee: ProcessingGuarantee) {
+    SEQUENCE_CHECKER.lock().unwrap().on_sink_open(guarantee);
+}
+
+pub(crate) fn on_sink_close() {
+    SEQUENCE_CHECKER.lock().unwrap().on_sink_close();
+}
+
+pub(crate) fn on_record(guarantee: ProcessingGuarantee, sequence: &RecordSequence) {
+    SEQUENCE_CHECKER
+        .lock()
+        .unwrap()
+        .on_record(guarantee, sequence);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::checkpoint::ProcessingGuarantee;
+    use crate::core::element::RecordSequence;
+    use crate::runtime::worker::assertion::SequenceChecker;
+
+    #[test]
+    pub fn sequence_checker_test() {
+        let guarantee = ProcessingGuarantee::AtLeastOnce;
+        let mut checker = SequenceChecker::default();
+        checker.on_source_open();
+        checker.on_sink_open(guarantee);
+
+        let mut fanout = RecordSequence::new(1, 0, 1);
+        fanout.fanout = 1;
+        for sequence in [
+            RecordSequence::new(1, 0, 0),
+            RecordSequence::new(1, 0, 1),
+            fanout,
+            RecordSequence::new(1, 0, 1),
+            RecordSequence::new(1, 0, 3),
+        ] {
+            checker.on_record(guarantee, &sequence);
+        }
+
+        checker.on_source_close(1, 0, 5);
+        let gaps = checker.gaps();
+        assert_eq!(gaps.get(&(1, 0)), Some(&vec![2, 4]));
+
+        let result = std::panic::catch_unwind(move || {
+            checker.on_sink_close();
+        });
+        assert!(result.is_err());
+
+        let mut checker = SequenceChecker::default();
+        checker.on_record(guarantee, &RecordSequence::new(1, 0, 0));
+        let result = std::panic::catch_unwind(move || {
+            checker.on_record(
+                ProcessingGuarantee::ExactlyOnce,
+                &RecordSequence::new(1, 0, 0),
+            );
+        });
+        assert!(result.is_err());
+    }
+}
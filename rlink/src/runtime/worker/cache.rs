@@ -0,0 +1,84 @@
+//! Download the files of the distributed cache from the coordinator, see `core::cache`
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::core::cache::{cache_dir, CachedFile};
+use crate::core::properties::SystemProperties;
+use crate::core::runtime::ClusterDescriptor;
+use crate::utils::http::client::get_bytes_sync;
+
+/// download and unpack the cached files of the application before opening the tasks,
+/// the files existing in the cache directory are reused, eg: the workers in the same host
+pub(crate) fn prepare_cached_files(cluster_descriptor: &ClusterDescriptor) -> anyhow::Result<()> {
+    let coordinator_manager = &cluster_descriptor.coordinator_manager;
+    let cached_files = coordinator_manager
+        .application_properties
+        .get_cached_files()
+        .unwrap_or_default();
+    if cached_files.is_empty() {
+        return Ok(());
+    }
+
+    let dir = cache_dir(coordinator_manager.application_id.as_str());
+    std::fs::create_dir_all(dir.as_path())?;
+    for cached_file in &cached_files {
+        let path = dir.join(cached_file.name.as_str());
+        if path.exists() {
+            info!("cached file `{}` exists, {:?}", cached_file.name, path);
+            continue;
+        }
+
+        let url = format!(
+            "{}/api/cache/{}",
+            coordinator_manager.web_address, cached_file.name
+        );
+        let data = get_bytes_sync(url.as_str())
+            .map_err(|e| anyhow!("download cached file `{}` error. {}", cached_file.name, e))?;
+
+        // written to a temporary path and then renamed, so a partial file is never visible
+        let tmp_path = dir.join(format!(".{}.{}", cached_file.name, std::process::id()));
+        std::fs::write(tmp_path.as_path(), data)?;
+        if cached_file.archive {
+            let unpack_path = dir.join(format!(".{}.{}.d", cached_file.name, std::process::id()));
+            std::fs::create_dir_all(unpack_path.as_path())?;
+            unpack(cached_file, tmp_path.as_path(), unpack_path.as_path())?;
+            std::fs::remove_file(tmp_path.as_path())?;
+            std::fs::rename(unpack_path, path.as_path())?;
+        } else {
+            std::fs::rename(tmp_path, path.as_path())?;
+        }
+        info!("cached file `{}` is prepared, {:?}", cached_file.name, path);
+    }
+
+    Ok(())
+}
+
+/// unpack by the `tar` or `unzip` command, the format is detected by the extension of the uri
+fn unpack(cached_file: &CachedFile, archive: &Path, dir: &Path) -> anyhow::Result<()> {
+    let uri = cached_file.uri.to_lowercase();
+    let mut command = if uri.ends_with(".zip") {
+        let mut command = Command::new("unzip");
+        command.arg("-q").arg(archive).arg("-d").arg(dir);
+        command
+    } else if uri.ends_with(".tar") || uri.ends_with(".tar.gz") || uri.ends_with(".tgz") {
+        let mut command = Command::new("tar");
+        command.arg("-xf").arg(archive).arg("-C").arg(dir);
+        command
+    } else {
+        return Err(anyhow!(
+            "unsupported archive `{}`, expect `.tar`, `.tar.gz`, `.tgz` or `.zip`",
+            cached_file.uri
+        ));
+    };
+
+    let status = command.status()?;
+    if !status.success() {
+        return Err(anyhow!(
+            "unpack cached file `{}` error, {}",
+            cached_file.name,
+            status
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! Cancel the `CancellationToken` of the tasks, see `Context::cancellation_token`.
+//!
+//! The token of a task is cancelled when the task ends or fails, so the threads started by its
+//! functions exit with it, and the tokens of all tasks are cancelled when the coordinator stops
+//! the application. A task still running after the grace period of the stop is stuck, eg: a
+//! connector blocked in a network call without observing the token. Rust can't interrupt a
+//! thread, so the worker dumps the stacks of the stuck tasks and exits, the same as a failover.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+
+use crate::core::cancellation::CancellationToken;
+use crate::core::runtime::TaskId;
+use crate::utils::thread::stack_dump;
+
+pub(crate) const DEFAULT_CANCELLATION_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// the exit code of the worker aborted by the stuck tasks after the cancellation
+const CANCELLATION_TIMEOUT_EXIT_CODE: i32 = 134;
+/// the max time of waiting the stuck thread to dump its stack
+const STACK_DUMP_TIMEOUT: Duration = Duration::from_secs(3);
+
+lazy_static! {
+    /// the token and the `thread_id::get()` of the running tasks
+    static ref TASK_TOKENS: Mutex<HashMap<TaskId, (CancellationToken, usize)>> =
+        Mutex::new(HashMap::new());
+}
+
+static GRACE_PERIOD_MILLIS: AtomicU64 =
+    AtomicU64::new(DEFAULT_CANCELLATION_GRACE_PERIOD.as_millis() as u64);
+static ESCALATION_START: Once = Once::new();
+
+pub(crate) fn set_grace_period(grace_period: Duration) {
+    GRACE_PERIOD_MILLIS.store(grace_period.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// The registration of the running task, the token is cancelled and the task is unregistered
+/// on dropping, including the unwinding of a panic
+pub(crate) struct TaskCancellation {
+    task_id: TaskId,
+    token: CancellationToken,
+}
+
+impl TaskCancellation {
+    /// register the task running in the current thread
+    pub fn register(task_id: TaskId) -> Self {
+        let token = CancellationToken::new();
+        TASK_TOKENS
+            .lock()
+            .unwrap()
+            .insert(task_id, (token.clone(), thread_id::get()));
+        TaskCancellation { task_id, token }
+    }
+
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+}
+
+impl Drop for TaskCancellation {
+    fn drop(&mut self) {
+        self.token.cancel("task ended");
+        TASK_TOKENS.lock().unwrap().remove(&self.task_id);
+    }
+}
+
+/// cancel the tokens of all running tasks, the worker is aborted if any of them is still
+/// running after the grace period
+pub(crate) fn cancel_all(reason: &str) {
+    let cancelled = {
+        let task_tokens = TASK_TOKENS.lock().unwrap();
+        task_tokens
+            .values()
+            .filter(|(token, _pthread)| token.cancel(reason))
+            .count()
+    };
+    if cancelled == 0 {
+        return;
+    }
+
+    info!("{} tasks cancelled, reason: {}", cancelled, reason);
+    ESCALATION_START.call_once(|| {
+        let grace_period = Duration::from_millis(GRACE_PERIOD_MILLIS.load(Ordering::Relaxed));
+        crate::utils::thread::spawn("cancellation", move || escalate(grace_period));
+    });
+}
+
+fn escalate(grace_period: Duration) {
+    std::thread::sleep(grace_period);
+
+    let stuck_tasks: Vec<(TaskId, usize)> = TASK_TOKENS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(task_id, (_token, pthread))| (*task_id, *pthread))
+        .collect();
+    if stuck_tasks.is_empty() {
+        return;
+    }
+
+    for (task_id, pthread) in stuck_tasks {
+        let stack = stack_dump(pthread, STACK_DUMP_TIMEOUT).unwrap_or_default();
+        error!(
+            "task {:?} is still running {:?} after the cancellation, stack: \n{}",
+            task_id, grace_period, stack
+        );
+    }
+    error!("abort the worker with the stuck tasks");
+    std::process::exit(CANCELLATION_TIMEOUT_EXIT_CODE);
+}
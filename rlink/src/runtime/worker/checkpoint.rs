@@ -1,12 +1,19 @@
 use std::time::Duration;
 
-use crate::channel::{bounded, Receiver, Sender, TryRecvError, TrySendError};
-use crate::core::checkpoint::Checkpoint;
+use crate::channel::{bounded, unbounded, Receiver, Sender, TryRecvError, TrySendError};
+use crate::core::checkpoint::{
+    AsyncSnapshot, Checkpoint, CheckpointFunction, CheckpointHandle, CheckpointStats,
+    FunctionSnapshotContext,
+};
 use crate::core::cluster::StdResponse;
 use crate::utils::date_time;
+use crate::utils::date_time::current_timestamp_millis;
 use crate::utils::http::client::post;
 use crate::utils::thread::async_sleep;
 
+/// the threads of the pool running the `AsyncSnapshot`s of the worker
+const SNAPSHOT_POOL_THREADS: usize = 2;
+
 pub struct CheckpointChannel {
     sender: Sender<Checkpoint>,
     receiver: Receiver<Checkpoint>,
@@ -23,17 +30,147 @@ lazy_static! {
     static ref CK_CHANNEL: CheckpointChannel = CheckpointChannel::new();
 }
 
-pub(crate) fn submit_checkpoint(ck: Checkpoint) -> Option<Checkpoint> {
-    let ck_channel = &*CK_CHANNEL;
-
+fn submit_checkpoint(sender: &Sender<Checkpoint>, ck: Checkpoint) -> Option<Checkpoint> {
     debug!("report checkpoint: {:?}", &ck);
-    match ck_channel.sender.try_send(ck) {
+    match sender.try_send(ck) {
         Ok(_) => None,
         Err(TrySendError::Full(ck)) => Some(ck),
         Err(TrySendError::Disconnected(_ck)) => panic!("the Checkpoint channel is disconnected"),
     }
 }
 
+/// an `AsyncSnapshot` with the context and the stats of its synchronous part, the checkpoint
+/// is submitted to the `Sender`
+type SnapshotTask = (
+    FunctionSnapshotContext,
+    AsyncSnapshot,
+    CheckpointStats,
+    Sender<Checkpoint>,
+);
+
+lazy_static! {
+    static ref SNAPSHOT_POOL: Sender<SnapshotTask> = start_snapshot_pool();
+}
+
+fn start_snapshot_pool() -> Sender<SnapshotTask> {
+    let (sender, receiver) = unbounded();
+    for index in 0..SNAPSHOT_POOL_THREADS {
+        let receiver: Receiver<SnapshotTask> = receiver.clone();
+        crate::utils::thread::spawn(format!("snapshot-{}", index).as_str(), move || {
+            while let Ok((snapshot_context, async_snapshot, mut stats, sender)) = receiver.recv() {
+                let begin_time = current_timestamp_millis();
+                match async_snapshot() {
+                    Ok(handle) => {
+                        stats.async_duration = current_timestamp_millis() - begin_time;
+                        stats.state_size = handle.handle.len() as u64;
+                        submit(&sender, &snapshot_context, handle, stats, None);
+                    }
+                    Err(e) => {
+                        error!(
+                            "{:?} async snapshot error, the checkpoint {:?} is declined. {}",
+                            snapshot_context.operator_id, snapshot_context.checkpoint_id, e
+                        );
+                        stats.async_duration = current_timestamp_millis() - begin_time;
+                        let declined = Some(format!("async snapshot error. {}", e));
+                        let handle = CheckpointHandle::default();
+                        submit(&sender, &snapshot_context, handle, stats, declined);
+                    }
+                }
+            }
+        });
+    }
+    sender
+}
+
+/// snapshot the `function` and submit the checkpoint with the `stats`. the synchronous part
+/// runs on the element loop, the `AsyncSnapshot` of `CheckpointFunction::snapshot_state_async`
+/// runs on the snapshot pool and the checkpoint is submitted after it's done
+pub(crate) fn snapshot_checkpoint<F>(
+    function: &mut F,
+    snapshot_context: &FunctionSnapshotContext,
+    stats: CheckpointStats,
+) where
+    F: CheckpointFunction + ?Sized,
+{
+    snapshot_checkpoint_to(&CK_CHANNEL.sender, function, snapshot_context, stats)
+}
+
+fn snapshot_checkpoint_to<F>(
+    sender: &Sender<Checkpoint>,
+    function: &mut F,
+    snapshot_context: &FunctionSnapshotContext,
+    mut stats: CheckpointStats,
+) where
+    F: CheckpointFunction + ?Sized,
+{
+    let begin_time = current_timestamp_millis();
+    match function.snapshot_state_async(snapshot_context) {
+        Some(async_snapshot) => {
+            stats.sync_duration = current_timestamp_millis() - begin_time;
+            submit_async_snapshot_to(sender, snapshot_context, async_snapshot, stats);
+        }
+        None => {
+            let handle = function
+                .snapshot_state(snapshot_context)
+                .unwrap_or_default();
+            stats.sync_duration = current_timestamp_millis() - begin_time;
+            stats.state_size = handle.handle.len() as u64;
+            submit(sender, snapshot_context, handle, stats, None);
+        }
+    }
+}
+
+/// run the `async_snapshot` on the snapshot pool and submit the checkpoint after it's done,
+/// the checkpoint is declined if it fails
+pub(crate) fn submit_async_snapshot(
+    snapshot_context: &FunctionSnapshotContext,
+    async_snapshot: AsyncSnapshot,
+    stats: CheckpointStats,
+) {
+    submit_async_snapshot_to(&CK_CHANNEL.sender, snapshot_context, async_snapshot, stats)
+}
+
+fn submit_async_snapshot_to(
+    sender: &Sender<Checkpoint>,
+    snapshot_context: &FunctionSnapshotContext,
+    async_snapshot: AsyncSnapshot,
+    stats: CheckpointStats,
+) {
+    SNAPSHOT_POOL
+        .send((
+            snapshot_context.clone(),
+            async_snapshot,
+            stats,
+            sender.clone(),
+        ))
+        .unwrap();
+}
+
+fn submit(
+    sender: &Sender<Checkpoint>,
+    snapshot_context: &FunctionSnapshotContext,
+    handle: CheckpointHandle,
+    stats: CheckpointStats,
+    declined: Option<String>,
+) {
+    let ck = Checkpoint {
+        operator_id: snapshot_context.operator_id,
+        task_id: snapshot_context.task_id,
+        checkpoint_id: snapshot_context.checkpoint_id,
+        completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
+        handle,
+        stats,
+        declined,
+        savepoint: false,
+    };
+    if let Some(ck) = submit_checkpoint(sender, ck) {
+        error!(
+            "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
+            snapshot_context.operator_id, ck
+        )
+    }
+}
+
 pub(crate) async fn start_report_checkpoint(coordinator_address: String) {
     info!("checkpoint loop starting...");
 
@@ -78,3 +215,82 @@ pub(crate) async fn report_checkpoint(coordinator_address: &str, ck: Checkpoint)
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::core::checkpoint::{
+        AsyncSnapshot, CheckpointFunction, CheckpointHandle, CheckpointStats,
+        FunctionSnapshotContext,
+    };
+    use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
+    use crate::runtime::worker::checkpoint::{snapshot_checkpoint_to, CheckpointChannel};
+
+    struct AsyncStateFunction {
+        state: std::sync::Arc<Vec<u64>>,
+        fail: bool,
+    }
+
+    impl CheckpointFunction for AsyncStateFunction {
+        fn snapshot_state_async(
+            &mut self,
+            _context: &FunctionSnapshotContext,
+        ) -> Option<AsyncSnapshot> {
+            let state = self.state.clone();
+            let fail = self.fail;
+            Some(Box::new(move || {
+                if fail {
+                    return Err(anyhow!("upload error"));
+                }
+                Ok(CheckpointHandle {
+                    handle: serde_json::to_string(state.as_ref())?,
+                })
+            }))
+        }
+    }
+
+    #[test]
+    pub fn async_snapshot_test() {
+        let channel = CheckpointChannel::new();
+        let mut function = AsyncStateFunction {
+            state: std::sync::Arc::new(vec![1, 2, 3]),
+            fail: false,
+        };
+        let context =
+            FunctionSnapshotContext::new(OperatorId(7), TaskId::default(), CheckpointId(100), None);
+        snapshot_checkpoint_to(
+            &channel.sender,
+            &mut function,
+            &context,
+            CheckpointStats::default(),
+        );
+
+        let ck = channel.receiver.recv().unwrap();
+        assert_eq!(ck.operator_id, OperatorId(7));
+        assert_eq!(ck.checkpoint_id, CheckpointId(100));
+        assert_eq!(ck.handle.handle, "[1,2,3]");
+        assert_eq!(ck.stats.state_size, 7);
+        assert!(ck.declined.is_none());
+    }
+
+    #[test]
+    pub fn async_snapshot_declined_test() {
+        let channel = CheckpointChannel::new();
+        let mut function = AsyncStateFunction {
+            state: std::sync::Arc::new(vec![1, 2, 3]),
+            fail: true,
+        };
+        let context =
+            FunctionSnapshotContext::new(OperatorId(7), TaskId::default(), CheckpointId(101), None);
+        snapshot_checkpoint_to(
+            &channel.sender,
+            &mut function,
+            &context,
+            CheckpointStats::default(),
+        );
+
+        // the failed snapshot is reported, so the coordinator fails the checkpoint
+        let ck = channel.receiver.recv().unwrap();
+        assert_eq!(ck.checkpoint_id, CheckpointId(101));
+        assert!(ck.declined.unwrap().contains("upload error"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Drain the worker on `SIGTERM`, eg: the rolling restart of kubernetes.
+//!
+//! The worker reports the `HeartBeatStatus::Draining` to the coordinator, which stops the
+//! application with a savepoint: the sources stop after the savepoint barrier, the sinks are
+//! flushed by closing the ended streams, and the workers deregister by the `TaskEnd` and
+//! `HeartBeatStatus::End` heartbeats. Then the coordinator redeploys the application from the
+//! savepoint without counting it as a failure, the drained TaskManagers are excluded from the
+//! redeployment. The worker exits as soon as the savepoint is completed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::core::runtime::{CheckpointId, HeartBeatStatus};
+use crate::runtime::worker::heart_beat::{
+    get_completed_checkpoint_id, get_stop_checkpoint_id, submit_heartbeat, HEARTBEAT_INTERVAL,
+};
+use crate::runtime::HeartbeatItem;
+
+pub(crate) const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// the exit code of the worker killed by the `SIGTERM`, `128 + SIGTERM`
+const DRAIN_TIMEOUT_EXIT_CODE: i32 = 143;
+
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn is_draining() -> bool {
+    DRAINING.load(Ordering::Relaxed)
+}
+
+/// the `SIGTERM` handler, only mark the draining, it's handled by the `drain` thread
+#[cfg(unix)]
+extern "C" fn sigterm_handler(_signal: libc::c_int) {
+    DRAINING.store(true, Ordering::SeqCst);
+}
+
+/// hook the `SIGTERM` and start the thread draining the worker once received it,
+/// the worker exits if it isn't drained in the `drain_timeout`
+pub(crate) fn start_drain_on_sigterm(drain_timeout: Duration) {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(
+            libc::SIGTERM,
+            sigterm_handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+
+    crate::utils::thread::spawn("drain", move || {
+        while !is_draining() {
+            std::thread::sleep(Duration::from_millis(200));
+        }
+
+        info!(
+            "SIGTERM received, draining the worker in {:?}",
+            drain_timeout
+        );
+        submit_heartbeat(HeartbeatItem::HeartBeatStatus(HeartBeatStatus::Draining));
+
+        let begin = Instant::now();
+        while begin.elapsed() < drain_timeout {
+            std::thread::sleep(Duration::from_millis(200));
+            if is_drained() {
+                // wait the tasks ending and the `End` reported to the coordinator
+                std::thread::sleep(HEARTBEAT_INTERVAL);
+                info!(
+                    "the worker is drained with the savepoint {:?} in {:?}",
+                    get_stop_checkpoint_id(),
+                    begin.elapsed()
+                );
+                std::process::exit(0);
+            }
+        }
+
+        error!(
+            "the worker is not drained in {:?}, exit without the savepoint",
+            drain_timeout
+        );
+        std::process::exit(DRAIN_TIMEOUT_EXIT_CODE);
+    });
+}
+
+/// the worker is drained once the savepoint requested for the draining is completed
+pub(crate) fn is_drained() -> bool {
+    is_draining() && savepoint_completed(get_stop_checkpoint_id(), get_completed_checkpoint_id())
+}
+
+fn savepoint_completed(
+    stop_checkpoint_id: Option<CheckpointId>,
+    completed_checkpoint_id: Option<CheckpointId>,
+) -> bool {
+    match (stop_checkpoint_id, completed_checkpoint_id) {
+        (Some(stop_checkpoint_id), Some(completed_checkpoint_id)) => {
+            completed_checkpoint_id.0 >= stop_checkpoint_id.0
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::runtime::CheckpointId;
+    use crate::runtime::worker::drain::savepoint_completed;
+
+    #[test]
+    pub fn savepoint_completed_test() {
+        assert!(!savepoint_completed(None, None));
+        assert!(!savepoint_completed(None, Some(CheckpointId(5))));
+        assert!(!savepoint_completed(Some(CheckpointId(5)), None));
+        assert!(!savepoint_completed(
+            Some(CheckpointId(5)),
+            Some(CheckpointId(4))
+        ));
+        assert!(savepoint_completed(
+            Some(CheckpointId(5)),
+            Some(CheckpointId(5))
+        ));
+        assert!(savepoint_completed(
+            Some(CheckpointId(5)),
+            Some(CheckpointId(6))
+        ));
+    }
+}
@@ -0,0 +1,102 @@
+use std::panic::AssertUnwindSafe;
+use std::thread::JoinHandle;
+
+use crate::core::error::ErrorReport;
+use crate::core::properties::{Properties, SystemProperties};
+use crate::core::runtime::TaskId;
+use crate::dag::metadata::DagMetadata;
+use crate::dag::OperatorType;
+use crate::metrics::metric::Gauge;
+use crate::metrics::register_gauge;
+use crate::runtime::worker::heart_beat::submit_heartbeat;
+use crate::runtime::HeartbeatItem;
+use crate::utils::panic::panic_message;
+use crate::utils::thread::{set_thread_info, ThreadInfo};
+
+/// Spawn the task threads of the worker. The threads are named by the operator and the task
+/// number, eg: `kafka_source(1)#3`, and spawned with the stack size of
+/// `SystemProperties::set_task_stack_size`. The error or panic of a task is reported to the
+/// coordinator as a `TaskFailure` before unwinding the thread.
+#[derive(Clone)]
+pub(crate) struct TaskExecutor {
+    stack_size: Option<usize>,
+    running_tasks: Gauge,
+}
+
+impl TaskExecutor {
+    pub fn new(application_properties: &Properties) -> Self {
+        let stack_size = application_properties.get_task_stack_size().ok();
+        info!("task executor with stack size {:?}", stack_size);
+
+        TaskExecutor {
+            stack_size,
+            running_tasks: register_gauge("RunningTasks", vec![]),
+        }
+    }
+
+    pub fn spawn<F>(&self, dag_metadata: &DagMetadata, task_id: TaskId, f: F) -> JoinHandle<()>
+    where
+        F: FnOnce() -> anyhow::Result<()>,
+        F: Send + 'static,
+    {
+        let mut builder = std::thread::Builder::new().name(task_thread_name(dag_metadata, task_id));
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+
+        let running_tasks = self.running_tasks.clone();
+        builder
+            .spawn(move || {
+                set_thread_info(ThreadInfo::current());
+                submit_heartbeat(HeartbeatItem::TaskThreadId {
+                    task_id,
+                    thread_id: thread_id::get() as u64,
+                });
+
+                running_tasks.fetch_add(1);
+                let run_result = std::panic::catch_unwind(AssertUnwindSafe(f));
+                running_tasks.fetch_sub(1);
+
+                match run_result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        submit_heartbeat(HeartbeatItem::TaskFailure {
+                            task_id,
+                            error: ErrorReport::from(&e),
+                        });
+                        panic!("task {:?} run error. {:?}", task_id, e);
+                    }
+                    Err(payload) => {
+                        let e = anyhow!("task panicked. {}", panic_message(payload.as_ref()));
+                        submit_heartbeat(HeartbeatItem::TaskFailure {
+                            task_id,
+                            error: ErrorReport::from(&e),
+                        });
+                        std::panic::resume_unwind(payload);
+                    }
+                }
+            })
+            .expect("failed to spawn task thread")
+    }
+}
+
+/// `{operator name}({job_id})#{task_number}`, the operator is the first one of the job
+/// except the system input of the network/memory edges
+fn task_thread_name(dag_metadata: &DagMetadata, task_id: TaskId) -> String {
+    let operator_name = dag_metadata.job_node(task_id.job_id).and_then(|job_node| {
+        job_node
+            .stream_nodes
+            .iter()
+            .find(|stream_node| {
+                stream_node.operator_type != OperatorType::Source
+                    || stream_node.parent_ids.is_empty()
+            })
+            .map(|stream_node| stream_node.display_name().to_string())
+    });
+    format!(
+        "{}({})#{}",
+        operator_name.unwrap_or_else(|| "Task".to_string()),
+        task_id.job_id.0,
+        task_id.task_number
+    )
+}
@@ -1,9 +1,14 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
 use std::time::Duration;
 
 use crate::channel::{unbounded, Receiver, Sender, TrySendError};
 use crate::core::cluster::StdResponse;
-use crate::core::runtime::{HeartBeatStatus, ManagerStatus};
-use crate::runtime::{HeartbeatItem, HeartbeatRequest};
+use crate::core::rate_budget::TaskRateCap;
+use crate::core::runtime::{CheckpointId, HeartBeatStatus, ManagerStatus, TaskId};
+use crate::runtime::worker::{cancellation, drain};
+use crate::runtime::{HeartbeatItem, HeartbeatRequest, HeartbeatResponse};
 use crate::utils::http::client::post;
 use crate::utils::thread::async_sleep;
 use crate::utils::{date_time, panic};
@@ -20,6 +25,73 @@ pub(crate) fn get_coordinator_status() -> ManagerStatus {
     unsafe { COORDINATOR_STATUS }
 }
 
+/// the latest completed checkpoint_id reported by the coordinator, `0` if not any completed
+static COMPLETED_CHECKPOINT_ID: AtomicU64 = AtomicU64::new(0);
+
+fn update_completed_checkpoint_id(checkpoint_id: CheckpointId) {
+    COMPLETED_CHECKPOINT_ID.fetch_max(checkpoint_id.0, Ordering::Relaxed);
+}
+
+pub(crate) fn get_completed_checkpoint_id() -> Option<CheckpointId> {
+    let checkpoint_id = COMPLETED_CHECKPOINT_ID.load(Ordering::Relaxed);
+    if checkpoint_id == 0 {
+        None
+    } else {
+        Some(CheckpointId(checkpoint_id))
+    }
+}
+
+/// the latest checkpoint triggered on demand by the coordinator, `0` if not any triggered
+static TRIGGERED_CHECKPOINT_ID: AtomicU64 = AtomicU64::new(0);
+
+fn update_triggered_checkpoint_id(checkpoint_id: CheckpointId) {
+    TRIGGERED_CHECKPOINT_ID.fetch_max(checkpoint_id.0, Ordering::Relaxed);
+}
+
+pub(crate) fn get_triggered_checkpoint_id() -> Option<CheckpointId> {
+    let checkpoint_id = TRIGGERED_CHECKPOINT_ID.load(Ordering::Relaxed);
+    if checkpoint_id == 0 {
+        None
+    } else {
+        Some(CheckpointId(checkpoint_id))
+    }
+}
+
+/// the savepoint of the stop-with-savepoint requested by the coordinator, `0` if not requested
+static STOP_CHECKPOINT_ID: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn get_stop_checkpoint_id() -> Option<CheckpointId> {
+    let checkpoint_id = STOP_CHECKPOINT_ID.load(Ordering::Relaxed);
+    if checkpoint_id == 0 {
+        None
+    } else {
+        Some(CheckpointId(checkpoint_id))
+    }
+}
+
+lazy_static! {
+    /// key: (budget name, task_id), value: the rate cap assigned by the coordinator
+    static ref RATE_CAPS: RwLock<HashMap<(String, TaskId), f64>> = RwLock::new(HashMap::new());
+}
+
+fn update_rate_caps(rate_caps: Vec<TaskRateCap>) {
+    let mut caps = RATE_CAPS.write().unwrap();
+    for rate_cap in rate_caps {
+        caps.insert(
+            (rate_cap.budget, rate_cap.task_id),
+            rate_cap.records_per_second,
+        );
+    }
+}
+
+pub(crate) fn get_rate_cap(budget: &str, task_id: TaskId) -> Option<f64> {
+    RATE_CAPS
+        .read()
+        .unwrap()
+        .get(&(budget.to_string(), task_id))
+        .cloned()
+}
+
 pub struct HeartbeatChannel {
     sender: Sender<HeartbeatItem>,
     receiver: Receiver<HeartbeatItem>,
@@ -32,6 +104,9 @@ impl HeartbeatChannel {
     }
 }
 
+/// the interval of reporting the heartbeat to the coordinator
+pub(crate) const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
 lazy_static! {
     static ref HB_CHANNEL: HeartbeatChannel = HeartbeatChannel::new();
 }
@@ -69,7 +144,17 @@ pub(crate) async fn start_heartbeat_timer(coordinator_address: String, task_mana
         )
         .await;
 
-        async_sleep(Duration::from_secs(10)).await;
+        async_sleep(HEARTBEAT_INTERVAL).await;
+    }
+}
+
+fn heartbeat_status(panic_captured: bool) -> HeartBeatStatus {
+    if panic_captured {
+        HeartBeatStatus::Panic
+    } else if drain::is_draining() && !drain::is_drained() {
+        HeartBeatStatus::Draining
+    } else {
+        HeartBeatStatus::Ok
     }
 }
 
@@ -88,13 +173,7 @@ pub(crate) async fn report_heartbeat(
         })
         .is_some();
     if !exist_status_item {
-        let status = {
-            if panic::is_panic() {
-                HeartBeatStatus::Panic
-            } else {
-                HeartBeatStatus::Ok
-            }
-        };
+        let status = heartbeat_status(panic::is_panic());
         change_items.push(HeartbeatItem::HeartBeatStatus(status));
     }
 
@@ -105,7 +184,7 @@ pub(crate) async fn report_heartbeat(
     let body = serde_json::to_string(&request).unwrap();
 
     let begin_time = date_time::current_timestamp_millis();
-    let resp = post::<StdResponse<ManagerStatus>>(url, body).await;
+    let resp = post::<StdResponse<HeartbeatResponse>>(url, body).await;
     let end_time = date_time::current_timestamp_millis();
     let elapsed = end_time - begin_time;
 
@@ -115,10 +194,35 @@ pub(crate) async fn report_heartbeat(
                 warn!("heartbeat success. {:?}, elapsed: {}ms > 1s", resp, elapsed);
             }
 
-            if let Some(coordinator_status) = resp.data {
+            if let Some(HeartbeatResponse {
+                coordinator_status,
+                completed_checkpoint_id,
+                rate_caps,
+                triggered_checkpoint_id,
+                stop_checkpoint_id,
+                coordinator_timestamp,
+            }) = resp.data
+            {
+                date_time::sync_coordinator_clock(begin_time, end_time, coordinator_timestamp);
+
+                if let Some(stop_checkpoint_id) = stop_checkpoint_id {
+                    STOP_CHECKPOINT_ID.store(stop_checkpoint_id.0, Ordering::Relaxed);
+                }
+
+                update_rate_caps(rate_caps);
+
+                if let Some(triggered_checkpoint_id) = triggered_checkpoint_id {
+                    update_triggered_checkpoint_id(triggered_checkpoint_id);
+                }
+
+                if let Some(completed_checkpoint_id) = completed_checkpoint_id {
+                    update_completed_checkpoint_id(completed_checkpoint_id);
+                }
+
                 match coordinator_status {
                     ManagerStatus::Terminating | ManagerStatus::Terminated => {
-                        info!("coordinator status: {:?}", coordinator_status)
+                        info!("coordinator status: {:?}", coordinator_status);
+                        cancellation::cancel_all("the application is stopped");
                     }
                     _ => {}
                 }
@@ -131,3 +235,88 @@ pub(crate) async fn report_heartbeat(
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::panic::PanicHookInfo;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::core::element::Record;
+    use crate::core::retry::RetryPolicy;
+    use crate::core::runtime::{HeartBeatStatus, OperatorId};
+    use crate::runtime::worker::heart_beat::heartbeat_status;
+    use crate::runtime::worker::runnable::call_with_retry;
+    use crate::utils::panic::on_panic;
+
+    type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+    /// restore the original hook when the test ends
+    struct RestoreHookGuard(Arc<PanicHook>);
+
+    impl Drop for RestoreHookGuard {
+        fn drop(&mut self) {
+            // the hook can't be replaced by a panicking thread, the test thread filter is kept
+            if std::thread::panicking() {
+                return;
+            }
+            let original_hook = self.0.clone();
+            let _ = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |panic_info| original_hook(panic_info)));
+        }
+    }
+
+    /// capture the panics of the current thread by `on_panic` with the `capture`,
+    /// the other tests panic concurrently and are passed to the original hook
+    fn capture_thread_panics(capture: Arc<AtomicBool>) -> RestoreHookGuard {
+        let test_thread = std::thread::current().id();
+        let original_hook: Arc<PanicHook> = Arc::new(std::panic::take_hook());
+        let hook = original_hook.clone();
+        std::panic::set_hook(Box::new(move |panic_info| {
+            if std::thread::current().id() == test_thread {
+                on_panic(capture.as_ref(), panic_info);
+            } else {
+                hook(panic_info);
+            }
+        }));
+        RestoreHookGuard(original_hook)
+    }
+
+    #[test]
+    pub fn retry_heartbeat_status_test() {
+        let capture = Arc::new(AtomicBool::new(false));
+        let _guard = capture_thread_panics(capture.clone());
+
+        let retry_policy = RetryPolicy::new(3)
+            .backoff(Duration::from_millis(1), Duration::from_millis(1))
+            .retry_on("timed out");
+        let mut attempts = 0;
+        let mut record = Record::new();
+        call_with_retry(OperatorId(1), Some(&retry_policy), &mut record, |_| {
+            attempts += 1;
+            if attempts < 2 {
+                panic!("request timed out");
+            }
+        });
+
+        // the recovered panic doesn't flag the worker
+        assert_eq!(attempts, 2);
+        assert!(matches!(
+            heartbeat_status(capture.load(Ordering::SeqCst)),
+            HeartBeatStatus::Ok
+        ));
+
+        // the worker is flagged once the retries are exhausted
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            call_with_retry(OperatorId(1), Some(&retry_policy), &mut record, |_| {
+                panic!("request timed out")
+            })
+        }));
+        assert!(result.is_err());
+        assert!(matches!(
+            heartbeat_status(capture.load(Ordering::SeqCst)),
+            HeartBeatStatus::Panic
+        ));
+    }
+}
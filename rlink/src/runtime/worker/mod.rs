@@ -1,28 +1,43 @@
+use std::any::Any;
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use std::thread::JoinHandle;
 
+use crate::core::cancellation::CancellationToken;
+use crate::core::checkpoint::CheckpointRequester;
 use crate::core::element::{Element, Record};
 use crate::core::env::{StreamApp, StreamExecutionEnvironment};
+use crate::core::error::UserFunctionError;
 use crate::core::function::KeySelectorFunction;
 use crate::core::operator::{DefaultStreamOperator, StreamOperator};
+use crate::core::properties::SystemProperties;
 use crate::core::runtime::{ClusterDescriptor, JobId, OperatorId, TaskDescriptor};
 use crate::dag::metadata::DagMetadata;
 use crate::dag::OperatorType;
 use crate::runtime::context::Context;
 use crate::runtime::timer::WindowTimer;
-use crate::runtime::worker::heart_beat::submit_heartbeat;
+use crate::runtime::worker::cancellation::TaskCancellation;
+use crate::runtime::worker::executor::TaskExecutor;
 use crate::runtime::worker::runnable::co_process_runnable::CoProcessRunnable;
 use crate::runtime::worker::runnable::{
     FilterRunnable, FlatMapRunnable, KeyByRunnable, ReduceRunnable, Runnable, RunnableContext,
-    SinkRunnable, SourceRunnable, WatermarkAssignerRunnable, WindowAssignerRunnable,
+    SinkRunnable, SourceRunnable, UserFunctionPanic, WatermarkAssignerRunnable,
+    WindowAssignerRunnable,
 };
-use crate::runtime::HeartbeatItem;
+use crate::utils::panic::panic_message;
 
+pub(crate) mod assertion;
+pub(crate) mod cache;
+pub(crate) mod cancellation;
 pub mod checkpoint;
+pub(crate) mod drain;
+pub(crate) mod executor;
 pub mod heart_beat;
 pub mod runnable;
+pub(crate) mod split;
+pub mod watchdog;
 pub mod web_server;
 
 pub(crate) type FunctionContext = crate::core::function::Context;
@@ -35,34 +50,36 @@ pub(crate) fn run<S>(
     stream_app: S,
     _stream_env: &StreamExecutionEnvironment,
     window_timer: WindowTimer,
+    task_executor: &TaskExecutor,
 ) -> JoinHandle<()>
 where
     S: StreamApp + 'static,
 {
-    std::thread::Builder::new()
-        .name(format!(
-            "RM-Task-{}-{}",
-            task_descriptor.task_id.job_id.0, task_descriptor.task_id.task_number,
-        ))
-        .spawn(move || {
-            submit_heartbeat(HeartbeatItem::TaskThreadId {
-                task_id: task_descriptor.task_id.clone(),
-                thread_id: thread_id::get() as u64,
-            });
-
-            let stream_env = StreamExecutionEnvironment::new();
-            let worker_task = WorkerTask::new(
-                dag_metadata,
-                cluster_descriptor,
-                task_descriptor,
-                stream_app,
-                stream_env,
-                window_timer,
-            );
-            // todo error handle
-            worker_task.run().unwrap();
-        })
-        .unwrap()
+    let task_id = task_descriptor.task_id;
+    task_executor.spawn(dag_metadata.clone().as_ref(), task_id, move || {
+        let timeouts = watchdog::processing_timeouts(
+            dag_metadata.as_ref(),
+            cluster_descriptor.as_ref(),
+            &task_id,
+        );
+        watchdog::watch(task_id, timeouts, dag_metadata.clone());
+        let task_cancellation = TaskCancellation::register(task_id);
+
+        let stream_env = StreamExecutionEnvironment::new();
+        let worker_task = WorkerTask::new(
+            dag_metadata,
+            cluster_descriptor,
+            task_descriptor,
+            stream_app,
+            stream_env,
+            window_timer,
+            task_cancellation.token(),
+        );
+        let run_result = worker_task.run();
+        watchdog::unwatch();
+        drop(task_cancellation);
+        run_result
+    })
 }
 
 pub struct WorkerTask<S>
@@ -76,6 +93,7 @@ where
     stream_app: S,
     stream_env: StreamExecutionEnvironment,
     window_timer: WindowTimer,
+    cancellation_token: CancellationToken,
 }
 
 impl<S> WorkerTask<S>
@@ -90,6 +108,7 @@ where
         stream_app: S,
         stream_env: StreamExecutionEnvironment,
         window_timer: WindowTimer,
+        cancellation_token: CancellationToken,
     ) -> Self {
         WorkerTask {
             // context,
@@ -99,6 +118,7 @@ where
             stream_app,
             stream_env,
             window_timer,
+            cancellation_token,
         }
     }
 
@@ -110,6 +130,14 @@ where
         self.stream_app
             .build_stream(application_properties, self.stream_env.borrow_mut());
 
+        // the same optimization as the coordinator, the operator ids are matched
+        if application_properties
+            .get_stream_graph_optimizer()
+            .unwrap_or(false)
+        {
+            self.stream_env.stream_manager.optimize()?;
+        }
+
         let mut raw_stream_graph = self.stream_env.stream_manager.stream_graph.borrow_mut();
         let operators = raw_stream_graph.pop_operators();
 
@@ -121,13 +149,21 @@ where
             cluster_descriptor: self.cluster_descriptor.clone(),
             task_descriptor: self.task_descriptor.clone(),
             window_timer: self.window_timer.clone(),
+            shuffle_service: self.stream_env.shuffle_service.clone(),
+            cancellation_token: self.cancellation_token.clone(),
+            checkpoint_requester: CheckpointRequester::default(),
         };
 
         info!("open Operator Chain");
         operator_invoke_chain.open(&runnable_context)?;
 
         info!("run Operator Chain");
-        operator_invoke_chain.run(Element::Record(Record::new()));
+        let run_result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            operator_invoke_chain.run(Element::Record(Record::new()))
+        }));
+        if let Err(payload) = run_result {
+            return Err(self.panic_error(payload));
+        }
 
         info!("close Operator Chain");
         operator_invoke_chain.close()?;
@@ -135,6 +171,35 @@ where
         Ok(())
     }
 
+    /// convert the panic of the operator chain to a task failure,
+    /// the `UserFunctionPanic` is reported as a `UserFunctionError::Process` with the diagnostics,
+    /// a `UserFunctionError` payload raised by the runtime is reported as is
+    fn panic_error(&self, payload: Box<dyn Any + Send>) -> anyhow::Error {
+        match payload.downcast::<UserFunctionPanic>() {
+            Ok(user_function_panic) => {
+                let UserFunctionPanic {
+                    operator_id,
+                    message,
+                    record,
+                } = *user_function_panic;
+                let operator_name = self
+                    .dag_metadata
+                    .stream_node(operator_id)
+                    .map(|stream_node| stream_node.display_name().to_string())
+                    .unwrap_or_default();
+                UserFunctionError::Process(format!(
+                    "operator `{}`({}) panicked: {}, record: {}",
+                    operator_name, operator_id.0, message, record
+                ))
+                .into()
+            }
+            Err(payload) => match payload.downcast::<UserFunctionError>() {
+                Ok(user_function_error) => (*user_function_error).into(),
+                Err(payload) => anyhow!("task panicked. {}", panic_message(payload.as_ref())),
+            },
+        }
+    }
+
     fn build_invoke_chain(
         &self,
         mut operators: HashMap<OperatorId, StreamOperator>,
@@ -1,12 +1,17 @@
 use std::collections::HashMap;
 
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::checkpoint::{CheckpointStats, FunctionSnapshotContext};
 use crate::core::element::Element;
+use crate::core::error::UserFunctionError;
 use crate::core::function::CoProcessFunction;
 use crate::core::operator::DefaultStreamOperator;
-use crate::core::runtime::{JobId, OperatorId};
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::core::runtime::{CheckpointId, JobId, OperatorId};
+use crate::metrics::metric::Gauge;
+use crate::metrics::register_gauge;
+use crate::runtime::worker::checkpoint::snapshot_checkpoint;
+use crate::runtime::worker::runnable::{
+    catch_user_panic, notify_chained_checkpoint_complete, RecordMeta, Runnable, RunnableContext,
+};
 
 pub(crate) struct CoProcessRunnable {
     operator_id: OperatorId,
@@ -18,6 +23,8 @@ pub(crate) struct CoProcessRunnable {
     /// key: JobId,
     /// value: DataStream index  
     parent_jobs: HashMap<JobId, usize>,
+
+    state_entries_gauge: Gauge,
 }
 
 impl CoProcessRunnable {
@@ -34,6 +41,7 @@ impl CoProcessRunnable {
             next_runnable,
             context: None,
             parent_jobs: HashMap::new(),
+            state_entries_gauge: Gauge::default(),
         }
     }
 }
@@ -64,37 +72,76 @@ impl Runnable for CoProcessRunnable {
         }
 
         let fun_context = context.to_fun_context(self.operator_id);
-        self.stream_co_process.operator_fn.open(&fun_context)?;
+        self.stream_co_process
+            .operator_fn
+            .open(&fun_context)
+            .map_err(|e| e.or_classify(UserFunctionError::Open))?;
+
+        self.state_entries_gauge = register_gauge(
+            format!(
+                "CoProcess_StateEntries_{}",
+                self.stream_co_process.operator_fn.name()
+            ),
+            context.operator_tags(self.operator_id),
+        );
 
         Ok(())
     }
 
     fn run(&mut self, element: Element) {
         match element {
-            Element::Record(record) => {
+            Element::Record(mut record) => {
+                if record.is_dropped() {
+                    // the lineage of the assertion mode ends at the connect
+                    return;
+                }
+                record.sequence = None;
+
                 let stream_seq = *self
                     .parent_jobs
                     .get(&record.channel_key.source_task_id.job_id)
                     .expect("parent job not found");
 
-                let records = if stream_seq == self.parent_jobs.len() - 1 {
-                    self.stream_co_process
-                        .operator_fn
-                        .as_mut()
-                        .process_left(record)
-                } else {
-                    self.stream_co_process
-                        .operator_fn
+                let mut input = RecordMeta::of(&record);
+                let is_left = stream_seq == self.parent_jobs.len() - 1;
+                let operator_fn = self.stream_co_process.operator_fn.as_mut();
+                let mut records = catch_user_panic(self.operator_id, &mut input, |_| {
+                    if is_left {
+                        operator_fn.process_left(record)
+                    } else {
+                        operator_fn.process_right(stream_seq, record)
+                    }
+                });
+
+                while let Some(record) =
+                    catch_user_panic(self.operator_id, &mut input, |_| records.next())
+                {
+                    self.next_runnable
                         .as_mut()
-                        .process_right(stream_seq, record)
-                };
-
+                        .unwrap()
+                        .run(Element::Record(record));
+                }
+            }
+            Element::Watermark(watermark) => {
+                let records = self
+                    .stream_co_process
+                    .operator_fn
+                    .as_mut()
+                    .on_watermark(watermark.timestamp);
                 for record in records {
                     self.next_runnable
                         .as_mut()
                         .unwrap()
                         .run(Element::Record(record));
                 }
+
+                let state_entries = self.stream_co_process.operator_fn.state_entries();
+                self.state_entries_gauge.store(state_entries as i64);
+
+                self.next_runnable
+                    .as_mut()
+                    .unwrap()
+                    .run(Element::Watermark(watermark));
             }
             Element::Barrier(barrier) => {
                 let checkpoint_id = barrier.checkpoint_id;
@@ -116,7 +163,10 @@ impl Runnable for CoProcessRunnable {
     }
 
     fn close(&mut self) -> anyhow::Result<()> {
-        self.stream_co_process.operator_fn.close()?;
+        self.stream_co_process
+            .operator_fn
+            .close()
+            .map_err(|e| e.or_classify(UserFunctionError::Close))?;
         self.next_runnable.as_mut().unwrap().close()
     }
 
@@ -124,25 +174,19 @@ impl Runnable for CoProcessRunnable {
         self.next_runnable = next_runnable;
     }
 
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        notify_chained_checkpoint_complete(
+            self.stream_co_process.operator_fn.as_mut(),
+            self.next_runnable.as_mut(),
+            checkpoint_id,
+        );
+    }
+
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
-            .stream_co_process
-            .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
-            completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
-            handle,
-        };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
-        });
+        snapshot_checkpoint(
+            self.stream_co_process.operator_fn.as_mut(),
+            &snapshot_context,
+            CheckpointStats::default(),
+        );
     }
 }
@@ -1,12 +1,15 @@
 use std::borrow::BorrowMut;
 
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
-use crate::core::element::Element;
+use crate::core::checkpoint::{CheckpointStats, FunctionSnapshotContext};
+use crate::core::element::{Element, Record};
+use crate::core::error::UserFunctionError;
 use crate::core::function::FilterFunction;
 use crate::core::operator::DefaultStreamOperator;
-use crate::core::runtime::OperatorId;
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::core::runtime::{CheckpointId, OperatorId};
+use crate::runtime::worker::checkpoint::snapshot_checkpoint;
+use crate::runtime::worker::runnable::{
+    catch_user_panic, notify_chained_checkpoint_complete, Runnable, RunnableContext,
+};
 
 pub(crate) struct FilterRunnable {
     operator_id: OperatorId,
@@ -40,7 +43,10 @@ impl Runnable for FilterRunnable {
         self.context = Some(context.clone());
 
         let fun_context = context.to_fun_context(self.operator_id);
-        self.stream_filter.operator_fn.open(&fun_context)?;
+        self.stream_filter
+            .operator_fn
+            .open(&fun_context)
+            .map_err(|e| e.or_classify(UserFunctionError::Open))?;
 
         Ok(())
     }
@@ -48,8 +54,20 @@ impl Runnable for FilterRunnable {
     fn run(&mut self, mut element: Element) {
         match element.borrow_mut() {
             Element::Record(record) => {
-                if self.stream_filter.operator_fn.as_mut().filter(record) {
+                if record.is_dropped() {
+                    self.next_runnable.as_mut().unwrap().run(element);
+                    return;
+                }
+
+                let operator_fn = self.stream_filter.operator_fn.as_mut();
+                if catch_user_panic(self.operator_id, record, |record| {
+                    operator_fn.filter(record)
+                }) {
                     self.next_runnable.as_mut().unwrap().run(element);
+                } else if let Some(sequence) = record.sequence {
+                    // forward the stamp of the filtered record, the sinks see no gap
+                    let dropped = Element::Record(Record::dropped(sequence));
+                    self.next_runnable.as_mut().unwrap().run(dropped);
                 }
             }
             Element::Barrier(barrier) => {
@@ -69,7 +87,10 @@ impl Runnable for FilterRunnable {
     }
 
     fn close(&mut self) -> anyhow::Result<()> {
-        self.stream_filter.operator_fn.close()?;
+        self.stream_filter
+            .operator_fn
+            .close()
+            .map_err(|e| e.or_classify(UserFunctionError::Close))?;
         self.next_runnable.as_mut().unwrap().close()
     }
 
@@ -77,25 +98,115 @@ impl Runnable for FilterRunnable {
         self.next_runnable = next_runnable;
     }
 
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        notify_chained_checkpoint_complete(
+            self.stream_filter.operator_fn.as_mut(),
+            self.next_runnable.as_mut(),
+            checkpoint_id,
+        );
+    }
+
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
-            .stream_filter
-            .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
-            completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
-            handle,
-        };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
-        });
+        snapshot_checkpoint(
+            self.stream_filter.operator_fn.as_mut(),
+            &snapshot_context,
+            CheckpointStats::default(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::checkpoint::{CheckpointFunction, FunctionSnapshotContext};
+    use crate::core::element::{Element, Record};
+    use crate::core::function::{Context, FilterFunction, NamedFunction};
+    use crate::core::operator::{DefaultStreamOperator, FunctionCreator};
+    use crate::core::runtime::{CheckpointId, OperatorId};
+    use crate::runtime::worker::runnable::{FilterRunnable, Runnable, RunnableContext};
+
+    type Notified = Arc<Mutex<Vec<CheckpointId>>>;
+
+    struct RecordingFilter {
+        notified: Notified,
+    }
+
+    impl FilterFunction for RecordingFilter {
+        fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+            Ok(())
+        }
+
+        fn filter(&self, _record: &mut Record) -> bool {
+            true
+        }
+
+        fn close(&mut self) -> crate::core::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl NamedFunction for RecordingFilter {
+        fn name(&self) -> &str {
+            "RecordingFilter"
+        }
+    }
+
+    impl CheckpointFunction for RecordingFilter {
+        fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+            self.notified.lock().unwrap().push(checkpoint_id);
+        }
+    }
+
+    struct RecordingRunnable {
+        notified: Notified,
+    }
+
+    impl Runnable for RecordingRunnable {
+        fn open(&mut self, _context: &RunnableContext) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn run(&mut self, _element: Element) {}
+
+        fn close(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn set_next_runnable(&mut self, _next_runnable: Option<Box<dyn Runnable>>) {}
+
+        fn checkpoint(&mut self, _snapshot_context: FunctionSnapshotContext) {}
+
+        fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+            self.notified.lock().unwrap().push(checkpoint_id);
+        }
+    }
+
+    #[test]
+    pub fn notify_chained_checkpoint_complete_test() {
+        let filter_notified = Notified::default();
+        let chained_notified = Notified::default();
+
+        let stream_filter: DefaultStreamOperator<dyn FilterFunction> = DefaultStreamOperator::new(
+            1,
+            FunctionCreator::User,
+            Box::new(RecordingFilter {
+                notified: filter_notified.clone(),
+            }),
+        );
+        let mut runnable = FilterRunnable::new(
+            OperatorId(1),
+            stream_filter,
+            Some(Box::new(RecordingRunnable {
+                notified: chained_notified.clone(),
+            })),
+        );
+
+        runnable.notify_checkpoint_complete(CheckpointId(100));
+        runnable.notify_checkpoint_complete(CheckpointId(200));
+
+        let expect = vec![CheckpointId(100), CheckpointId(200)];
+        assert_eq!(*filter_notified.lock().unwrap(), expect);
+        assert_eq!(*chained_notified.lock().unwrap(), expect);
     }
 }
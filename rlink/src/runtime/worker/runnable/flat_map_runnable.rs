@@ -1,12 +1,17 @@
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
-use crate::core::element::Element;
+use crate::core::checkpoint::{CheckpointStats, FunctionSnapshotContext};
+use crate::core::element::{Element, Record};
+use crate::core::error::UserFunctionError;
 use crate::core::function::FlatMapFunction;
 use crate::core::operator::DefaultStreamOperator;
-use crate::core::runtime::{OperatorId, TaskId};
+use crate::core::retry::RetryPolicy;
+use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
 use crate::metrics::metric::Counter;
 use crate::metrics::register_counter;
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::runtime::worker::checkpoint::snapshot_checkpoint;
+use crate::runtime::worker::runnable::{
+    call_with_retry, catch_user_panic, notify_chained_checkpoint_complete, RecordMeta, Runnable,
+    RunnableContext,
+};
 use std::borrow::BorrowMut;
 
 pub(crate) struct FlatMapRunnable {
@@ -17,6 +22,7 @@ pub(crate) struct FlatMapRunnable {
     next_runnable: Option<Box<dyn Runnable>>,
 
     context: Option<RunnableContext>,
+    retry_policy: Option<RetryPolicy>,
 
     counter: Counter,
 }
@@ -35,10 +41,31 @@ impl FlatMapRunnable {
             stream_map,
             next_runnable,
             context: None,
+            retry_policy: None,
             counter: Counter::default(),
         }
     }
 }
+impl FlatMapRunnable {
+    /// emit the records buffered by the function, see `FlatMapFunction::flush`
+    fn flush(&mut self, all: bool) {
+        let operator_fn = self.stream_map.operator_fn.as_mut();
+        let mut input = Record::new();
+        let records: Vec<Record> = catch_user_panic(self.operator_id, &mut input, |_| {
+            operator_fn.flush(all).collect()
+        });
+
+        let len = records.len();
+        for record in records {
+            self.next_runnable
+                .as_mut()
+                .unwrap()
+                .run(Element::Record(record));
+        }
+        self.counter.fetch_add(len as u64);
+    }
+}
+
 impl Runnable for FlatMapRunnable {
     fn open(&mut self, context: &RunnableContext) -> anyhow::Result<()> {
         self.next_runnable.as_mut().unwrap().open(context)?;
@@ -46,13 +73,17 @@ impl Runnable for FlatMapRunnable {
         self.context = Some(context.clone());
 
         self.task_id = context.task_descriptor.task_id;
+        self.retry_policy = context.retry_policy(self.operator_id);
 
         let fun_context = context.to_fun_context(self.operator_id);
-        self.stream_map.operator_fn.open(&fun_context)?;
+        self.stream_map
+            .operator_fn
+            .open(&fun_context)
+            .map_err(|e| e.or_classify(UserFunctionError::Open))?;
 
         self.counter = register_counter(
             format!("FlatMap_{}", self.stream_map.operator_fn.as_ref().name()),
-            self.task_id.to_tags(),
+            context.operator_tags(self.operator_id),
         );
 
         Ok(())
@@ -60,23 +91,60 @@ impl Runnable for FlatMapRunnable {
 
     fn run(&mut self, mut element: Element) {
         match element.borrow_mut() {
-            Element::Record(_record) => {
-                let elements = self
-                    .stream_map
-                    .operator_fn
-                    .as_mut()
-                    .flat_map_element(element);
+            Element::Record(record) => {
+                if record.is_dropped() {
+                    self.next_runnable.as_mut().unwrap().run(element);
+                    return;
+                }
+
+                let sequence = record.sequence;
+                let mut input = RecordMeta::of(record);
+                let operator_fn = self.stream_map.operator_fn.as_mut();
+                let mut elements: Box<dyn Iterator<Item = Element>> = match &self.retry_policy {
+                    // the outputs are collected in the attempt, a failed attempt emits nothing
+                    Some(retry_policy) => {
+                        let outputs: Vec<Element> = call_with_retry(
+                            self.operator_id,
+                            Some(retry_policy),
+                            &mut input,
+                            |_| operator_fn.flat_map_element(element.clone()).collect(),
+                        );
+                        Box::new(outputs.into_iter())
+                    }
+                    None => catch_user_panic(self.operator_id, &mut input, |_| {
+                        operator_fn.flat_map_element(element)
+                    }),
+                };
 
                 let mut len = 0;
-                for ele in elements {
+                // the records are produced lazily by the user function
+                while let Some(mut ele) =
+                    catch_user_panic(self.operator_id, &mut input, |_| elements.next())
+                {
+                    if let (Some(mut sequence), Element::Record(output)) = (sequence, &mut ele) {
+                        sequence.fanout = len as u16;
+                        output.sequence = Some(sequence);
+                    }
                     self.next_runnable.as_mut().unwrap().run(ele);
                     len += 1;
                 }
 
+                if len == 0 {
+                    if let Some(sequence) = sequence {
+                        // forward the stamp of the record without output, the sinks see no gap
+                        let dropped = Element::Record(Record::dropped(sequence));
+                        self.next_runnable.as_mut().unwrap().run(dropped);
+                    }
+                }
+
                 self.counter.fetch_add(len);
             }
             Element::Barrier(barrier) => {
                 let checkpoint_id = barrier.checkpoint_id;
+                // the buffered records are emitted before the barrier, they are not in the state
+                self.flush(true);
+                self.stream_map.operator_fn.observe_element(&element);
+
                 let snapshot_context = {
                     let context = self.context.as_ref().unwrap();
                     context.checkpoint_context(self.operator_id, checkpoint_id, None)
@@ -85,14 +153,23 @@ impl Runnable for FlatMapRunnable {
 
                 self.next_runnable.as_mut().unwrap().run(element);
             }
+            Element::StreamStatus(stream_status) => {
+                self.flush(stream_status.end);
+                self.stream_map.operator_fn.observe_element(&element);
+                self.next_runnable.as_mut().unwrap().run(element);
+            }
             _ => {
+                self.stream_map.operator_fn.observe_element(&element);
                 self.next_runnable.as_mut().unwrap().run(element);
             }
         }
     }
 
     fn close(&mut self) -> anyhow::Result<()> {
-        self.stream_map.operator_fn.close()?;
+        self.stream_map
+            .operator_fn
+            .close()
+            .map_err(|e| e.or_classify(UserFunctionError::Close))?;
         self.next_runnable.as_mut().unwrap().close()
     }
 
@@ -100,25 +177,19 @@ impl Runnable for FlatMapRunnable {
         self.next_runnable = next_runnable;
     }
 
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        notify_chained_checkpoint_complete(
+            self.stream_map.operator_fn.as_mut(),
+            self.next_runnable.as_mut(),
+            checkpoint_id,
+        );
+    }
+
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
-            .stream_map
-            .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
-            completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
-            handle,
-        };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
-        });
+        snapshot_checkpoint(
+            self.stream_map.operator_fn.as_mut(),
+            &snapshot_context,
+            CheckpointStats::default(),
+        );
     }
 }
@@ -1,14 +1,17 @@
 use std::borrow::BorrowMut;
 
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::checkpoint::{CheckpointStats, FunctionSnapshotContext};
 use crate::core::element::{Element, Partition};
+use crate::core::error::UserFunctionError;
 use crate::core::function::KeySelectorFunction;
 use crate::core::operator::DefaultStreamOperator;
-use crate::core::runtime::{OperatorId, TaskId};
+use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
 use crate::metrics::metric::Counter;
 use crate::metrics::register_counter;
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::runtime::worker::checkpoint::snapshot_checkpoint;
+use crate::runtime::worker::runnable::{
+    catch_user_panic, notify_chained_checkpoint_complete, Runnable, RunnableContext,
+};
 use crate::utils;
 
 pub(crate) struct KeyByRunnable {
@@ -51,14 +54,17 @@ impl Runnable for KeyByRunnable {
         self.task_id = context.task_descriptor.task_id;
 
         let fun_context = context.to_fun_context(self.operator_id);
-        self.stream_key_by.operator_fn.open(&fun_context)?;
+        self.stream_key_by
+            .operator_fn
+            .open(&fun_context)
+            .map_err(|e| e.or_classify(UserFunctionError::Open))?;
 
         // todo set self.partition_size = Reduce.partition
         self.partition_size = context.child_parallelism() as u16;
 
         self.counter = register_counter(
             format!("KeyBy_{}", self.stream_key_by.operator_fn.as_ref().name()),
-            self.task_id.to_tags(),
+            context.operator_tags(self.operator_id),
         );
 
         Ok(())
@@ -67,21 +73,19 @@ impl Runnable for KeyByRunnable {
     fn run(&mut self, mut element: Element) {
         match element.borrow_mut() {
             Element::Record(record) => {
-                let key_row = self
-                    .stream_key_by
-                    .operator_fn
-                    .as_mut()
-                    .get_key(record.borrow_mut());
-
-                let hash_code = utils::hash::hash_code(key_row.values.as_slice()).unwrap_or(0);
-                let partition_num = hash_code % self.partition_size as u32;
-                // info!(
-                //     "partition: {}, hash code: {}, partition_size: {}",
-                //     partition_num,
-                //     hash_code,
-                //     self.partition_size,
-                // );
-                record.set_partition(partition_num as u16);
+                if record.is_dropped() {
+                    // the lineage of the assertion mode ends at the reduce
+                    return;
+                }
+
+                let operator_fn = self.stream_key_by.operator_fn.as_mut();
+                let key_row = catch_user_panic(self.operator_id, record, |record| {
+                    operator_fn.get_key(record)
+                });
+
+                let partition_num =
+                    utils::hash::key_partition(key_row.values.as_slice(), self.partition_size);
+                record.set_partition(partition_num);
 
                 self.next_runnable.as_mut().unwrap().run(element);
 
@@ -104,7 +108,10 @@ impl Runnable for KeyByRunnable {
     }
 
     fn close(&mut self) -> anyhow::Result<()> {
-        self.stream_key_by.operator_fn.close()?;
+        self.stream_key_by
+            .operator_fn
+            .close()
+            .map_err(|e| e.or_classify(UserFunctionError::Close))?;
         self.next_runnable.as_mut().unwrap().close()
     }
 
@@ -112,25 +119,19 @@ impl Runnable for KeyByRunnable {
         self.next_runnable = next_runnable;
     }
 
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        notify_chained_checkpoint_complete(
+            self.stream_key_by.operator_fn.as_mut(),
+            self.next_runnable.as_mut(),
+            checkpoint_id,
+        );
+    }
+
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
-            .stream_key_by
-            .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
-            completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
-            handle,
-        };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
-        });
+        snapshot_checkpoint(
+            self.stream_key_by.operator_fn.as_mut(),
+            &snapshot_context,
+            CheckpointStats::default(),
+        );
     }
 }
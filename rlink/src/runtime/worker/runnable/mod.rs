@@ -1,16 +1,32 @@
+use std::fmt::{Debug, Formatter};
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::core::checkpoint::FunctionSnapshotContext;
-use crate::core::element::Element;
+use crate::core::cancellation::CancellationToken;
+use crate::core::checkpoint::{
+    CheckpointFunction, CheckpointRequester, FunctionSnapshotContext, ProcessingGuarantee,
+};
+use crate::core::element::{Element, Record};
 use crate::core::properties::SystemProperties;
-use crate::core::runtime::{CheckpointId, ClusterDescriptor, OperatorId, TaskDescriptor, TaskId};
+use crate::core::retry::RetryPolicy;
+use crate::core::runtime::{
+    ChannelKey, CheckpointId, ClusterDescriptor, OperatorId, TaskDescriptor, TaskId,
+};
+use crate::core::shuffle::ShuffleService;
+use crate::core::state_limit::StateLimit;
+use crate::core::write_ahead_log::WriteAheadLog;
 use crate::dag::execution_graph::{ExecutionEdge, ExecutionNode};
 use crate::dag::job_graph::{JobEdge, JobNode};
 use crate::dag::metadata::DagMetadata;
 use crate::dag::stream_graph::StreamNode;
+use crate::metrics::Tag;
 use crate::runtime::timer::WindowTimer;
+use crate::runtime::worker::watchdog::enter_operator;
 use crate::runtime::worker::FunctionContext;
+use crate::utils::panic::{
+    discard_suppressed_panic, panic_message, report_suppressed_panic, suppress_panic_report,
+};
 
 pub mod co_process_runnable;
 pub mod filter_runnable;
@@ -37,6 +53,11 @@ pub(crate) struct RunnableContext {
     pub(crate) cluster_descriptor: Arc<ClusterDescriptor>,
     pub(crate) task_descriptor: TaskDescriptor,
     pub(crate) window_timer: WindowTimer,
+    pub(crate) shuffle_service: Option<Arc<dyn ShuffleService>>,
+    /// cancelled when the task ends or the application stops
+    pub(crate) cancellation_token: CancellationToken,
+    /// the checkpoint requested by the source of the task
+    pub(crate) checkpoint_requester: CheckpointRequester,
 }
 
 impl RunnableContext {
@@ -55,6 +76,11 @@ impl RunnableContext {
             .map(|(node, edge)| (node.clone(), edge.clone()))
             .collect();
         let stream_node = self.dag_metadata.stream_node(operator_id).unwrap();
+        let channel_options = self
+            .dag_metadata
+            .execution_node(&self.task_descriptor.task_id)
+            .map(|execution_node| execution_node.channel_options())
+            .unwrap_or_default();
 
         let operator = self
             .task_descriptor
@@ -77,9 +103,40 @@ impl RunnableContext {
 
             parents,
             children,
+            channel_options,
+            shuffle_service: self.shuffle_service.clone(),
+            bounded: self.dag_metadata.is_bounded(),
+            worker_id: self.worker_id(),
+            cancellation_token: self.cancellation_token.clone(),
+            checkpoint_requester: self.checkpoint_requester.clone(),
         }
     }
 
+    /// the index of the worker which the task is distributed to
+    pub(crate) fn worker_id(&self) -> u16 {
+        let task_id = &self.task_descriptor.task_id;
+        self.cluster_descriptor
+            .worker_managers
+            .iter()
+            .position(|worker_manager| {
+                worker_manager
+                    .task_descriptors
+                    .iter()
+                    .any(|task_descriptor| task_descriptor.task_id.eq(task_id))
+            })
+            .unwrap_or_default() as u16
+    }
+
+    /// the metric tags of the task, with the id and the display name of the operator
+    pub(crate) fn operator_tags(&self, operator_id: OperatorId) -> Vec<Tag> {
+        let mut tags = self.task_descriptor.task_id.to_tags();
+        tags.push(Tag::new("operator_id", operator_id.0));
+        if let Some(stream_node) = self.dag_metadata.stream_node(operator_id) {
+            tags.push(Tag::new("operator_name", stream_node.display_name()));
+        }
+        tags
+    }
+
     pub(crate) fn checkpoint_context(
         &self,
         operator_id: OperatorId,
@@ -94,6 +151,36 @@ impl RunnableContext {
         )
     }
 
+    /// the retry of the user function in the operator, see `DataStream::retry_policy`
+    pub(crate) fn retry_policy(&self, operator_id: OperatorId) -> Option<RetryPolicy> {
+        self.dag_metadata
+            .stream_node(operator_id)
+            .and_then(|stream_node| stream_node.retry_policy.clone())
+    }
+
+    /// the max size of the keyed state of the operator, see `DataStream::state_limit`
+    pub(crate) fn state_limit(&self, operator_id: OperatorId) -> Option<StateLimit> {
+        self.dag_metadata
+            .stream_node(operator_id)
+            .and_then(|stream_node| stream_node.state_limit.clone())
+    }
+
+    /// the write-ahead log of the sink, see `SinkStream::write_ahead_log`
+    pub(crate) fn write_ahead_log(&self, operator_id: OperatorId) -> Option<WriteAheadLog> {
+        self.dag_metadata
+            .stream_node(operator_id)
+            .and_then(|stream_node| stream_node.write_ahead_log.clone())
+    }
+
+    /// the guarantee to verify if the assertion mode is enabled
+    pub(crate) fn assertion_mode(&self) -> Option<ProcessingGuarantee> {
+        self.cluster_descriptor
+            .coordinator_manager
+            .application_properties
+            .get_assertion_mode()
+            .ok()
+    }
+
     pub(crate) fn checkpoint_interval(&self, default_value: Duration) -> Duration {
         self.cluster_descriptor
             .coordinator_manager
@@ -172,4 +259,249 @@ pub(crate) trait Runnable {
     fn close(&mut self) -> anyhow::Result<()>;
     fn set_next_runnable(&mut self, next_runnable: Option<Box<dyn Runnable>>);
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext);
+    /// notify the operator and the chained operators that the checkpoint is completed
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId);
+}
+
+/// notify the operator's function and then the chained operators that the checkpoint is completed
+pub(crate) fn notify_chained_checkpoint_complete<F>(
+    operator_fn: &mut F,
+    next_runnable: Option<&mut Box<dyn Runnable>>,
+    checkpoint_id: CheckpointId,
+) where
+    F: CheckpointFunction + ?Sized,
+{
+    operator_fn.notify_checkpoint_complete(checkpoint_id);
+    if let Some(next_runnable) = next_runnable {
+        next_runnable.notify_checkpoint_complete(checkpoint_id);
+    }
+}
+
+/// the max length of the offending record attached to the `UserFunctionPanic`
+const PANIC_RECORD_MAX_LEN: usize = 512;
+
+/// The diagnostics of a panic in a user function, it's the payload of the re-raised panic,
+/// so the task runner can convert the panic to a task failure
+#[derive(Debug)]
+pub(crate) struct UserFunctionPanic {
+    pub operator_id: OperatorId,
+    pub message: String,
+    /// the debug string of the offending record, or of its `RecordMeta` if the record is
+    /// consumed by the function, truncated to `PANIC_RECORD_MAX_LEN`
+    pub record: String,
+}
+
+/// The metadata of a record moved into the user function, it's attached to the
+/// `UserFunctionPanic` in place of the record, so the record isn't copied for the failure path
+pub(crate) struct RecordMeta {
+    pub timestamp: u64,
+    pub channel_key: ChannelKey,
+    pub partition_num: u16,
+    /// the length of the values in bytes
+    pub len: usize,
+}
+
+impl RecordMeta {
+    pub fn of(record: &Record) -> Self {
+        RecordMeta {
+            timestamp: record.timestamp,
+            channel_key: record.channel_key,
+            partition_num: record.partition_num,
+            len: record.len(),
+        }
+    }
+}
+
+impl Debug for RecordMeta {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordMeta")
+            .field("timestamp", &self.timestamp)
+            .field("channel_key", &self.channel_key)
+            .field("partition_num", &self.partition_num)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+fn truncate(mut s: String, max_len: usize) -> String {
+    if s.len() > max_len {
+        let mut len = max_len;
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        s.truncate(len);
+        s.push_str("...");
+    }
+    s
+}
+
+/// call the user function `f` with the `record`, a panic in it is re-raised with
+/// a `UserFunctionPanic` payload, the `UserFunctionPanic` raised by the chained operators
+/// is passed through. the function consuming the record is called with the `RecordMeta`
+/// of the record. the call is marked in the task's progress, so the watchdog can find the
+/// stuck operator
+pub(crate) fn catch_user_panic<T, F, R>(operator_id: OperatorId, record: &mut T, f: F) -> R
+where
+    T: Debug,
+    F: FnOnce(&mut T) -> R,
+{
+    let _progress_guard = enter_operator(operator_id);
+    match std::panic::catch_unwind(AssertUnwindSafe(|| f(record))) {
+        Ok(r) => r,
+        Err(payload) => {
+            if payload.is::<UserFunctionPanic>() {
+                std::panic::resume_unwind(payload);
+            }
+
+            let user_function_panic = UserFunctionPanic {
+                operator_id,
+                message: panic_message(payload.as_ref()),
+                record: truncate(format!("{:?}", record), PANIC_RECORD_MAX_LEN),
+            };
+            error!("user function panicked. {:?}", user_function_panic);
+            std::panic::resume_unwind(Box::new(user_function_panic))
+        }
+    }
+}
+
+/// call the user function `f` by `catch_user_panic`, the call is repeated after the backoff
+/// if it panics with a message retryable by the `retry_policy`. The panics of the chained
+/// operators are passed through without the retry. The retried panics don't flag the worker,
+/// only the panic given up is reported.
+pub(crate) fn call_with_retry<T, F, R>(
+    operator_id: OperatorId,
+    retry_policy: Option<&RetryPolicy>,
+    record: &mut T,
+    mut f: F,
+) -> R
+where
+    T: Debug,
+    F: FnMut(&mut T) -> R,
+{
+    let retry_policy = match retry_policy {
+        Some(retry_policy) => retry_policy,
+        None => return catch_user_panic(operator_id, record, f),
+    };
+
+    let mut attempt = 0;
+    loop {
+        let payload = match suppress_panic_report(|| {
+            std::panic::catch_unwind(AssertUnwindSafe(|| {
+                catch_user_panic(operator_id, record, &mut f)
+            }))
+        }) {
+            Ok(r) => return r,
+            Err(payload) => payload,
+        };
+        let retryable = match payload.downcast_ref::<UserFunctionPanic>() {
+            Some(user_function_panic) => {
+                user_function_panic.operator_id == operator_id
+                    && retry_policy.should_retry(attempt, user_function_panic.message.as_str())
+            }
+            None => false,
+        };
+        if !retryable {
+            // raise the given up panic again out of the suppressed scope, so it's reported by the hook
+            match payload.downcast::<UserFunctionPanic>() {
+                Ok(user_function_panic) => {
+                    discard_suppressed_panic();
+                    std::panic::panic_any(*user_function_panic);
+                }
+                Err(payload) => {
+                    report_suppressed_panic();
+                    std::panic::resume_unwind(payload);
+                }
+            }
+        }
+
+        let backoff = retry_policy.backoff_of(attempt);
+        warn!(
+            "retry the operator {:?} at attempt {} after {:?}",
+            operator_id,
+            attempt + 1,
+            backoff
+        );
+        std::thread::sleep(backoff);
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::element::Record;
+    use crate::core::retry::RetryPolicy;
+    use crate::core::runtime::OperatorId;
+    use crate::runtime::worker::runnable::{
+        call_with_retry, catch_user_panic, RecordMeta, UserFunctionPanic,
+    };
+
+    #[test]
+    pub fn catch_user_panic_test() {
+        let mut record = Record::new();
+        let payload = std::panic::catch_unwind(move || {
+            catch_user_panic(OperatorId(1), &mut record, |_| {
+                let mut record = Record::new();
+                // the panic of the chained operator is passed through
+                catch_user_panic(OperatorId(2), &mut record, |_| panic!("bad record"))
+            })
+        })
+        .unwrap_err();
+
+        let user_function_panic = payload.downcast::<UserFunctionPanic>().unwrap();
+        assert_eq!(user_function_panic.operator_id, OperatorId(2));
+        assert_eq!(user_function_panic.message, "bad record");
+        assert!(user_function_panic.record.starts_with("Record"));
+    }
+
+    #[test]
+    pub fn consumed_record_panic_test() {
+        let mut record = Record::new();
+        record.timestamp = 1000;
+        let mut input = RecordMeta::of(&record);
+        let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            catch_user_panic(OperatorId(1), &mut input, |_| {
+                // the record is moved into the function
+                let _record = record;
+                panic!("bad record")
+            })
+        }))
+        .unwrap_err();
+
+        let user_function_panic = payload.downcast::<UserFunctionPanic>().unwrap();
+        assert!(user_function_panic.record.starts_with("RecordMeta"));
+        assert!(user_function_panic.record.contains("timestamp: 1000"));
+    }
+
+    #[test]
+    pub fn call_with_retry_test() {
+        let retry_policy = RetryPolicy::new(3)
+            .backoff(Duration::from_millis(1), Duration::from_millis(1))
+            .retry_on("timed out");
+
+        // the transient panic is retried
+        let mut attempts = 0;
+        let mut record = Record::new();
+        let r = call_with_retry(OperatorId(1), Some(&retry_policy), &mut record, |_| {
+            attempts += 1;
+            if attempts < 3 {
+                panic!("request timed out");
+            }
+            attempts
+        });
+        assert_eq!(r, 3);
+
+        // the non-retryable panic fails at once
+        let mut attempts = 0;
+        let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            call_with_retry(OperatorId(1), Some(&retry_policy), &mut record, |_| {
+                attempts += 1;
+                panic!("bad record")
+            })
+        }))
+        .unwrap_err();
+        assert!(payload.is::<UserFunctionPanic>());
+        assert_eq!(attempts, 1);
+    }
 }
@@ -1,15 +1,35 @@
-use std::borrow::BorrowMut;
+use std::time::Duration;
 
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::checkpoint::{
+    AsyncSnapshot, CheckpointHandle, CheckpointStats, FunctionSnapshotContext,
+};
+use crate::core::data_types::Schema;
 use crate::core::element::{Element, Record};
+use crate::core::error::UserFunctionError;
 use crate::core::function::{BaseReduceFunction, KeySelectorFunction};
 use crate::core::operator::DefaultStreamOperator;
 use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
+use crate::core::state_limit::{StateLimit, StateLimitLevel};
 use crate::core::window::{TWindow, Window};
-use crate::metrics::metric::Counter;
-use crate::metrics::register_counter;
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::functions::sink::print::record_to_strings;
+use crate::metrics::keyed_state::{
+    publish_keyed_state_summary, HotKey, HotKeySketch, KeyGroupStats, KeyedStateSummary,
+};
+use crate::metrics::metric::{Counter, Gauge};
+use crate::metrics::{register_counter, register_gauge};
+use crate::runtime::worker::checkpoint::submit_async_snapshot;
+use crate::runtime::worker::heart_beat::submit_heartbeat;
+use crate::runtime::worker::runnable::{
+    catch_user_panic, notify_chained_checkpoint_complete, RecordMeta, Runnable, RunnableContext,
+};
+use crate::runtime::HeartbeatItem;
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the number of the sampled hot keys
+const HOT_KEYS: usize = 10;
+const STATE_STATS_INTERVAL: Duration = Duration::from_secs(30);
+/// the state is scanned more frequently if it's limited, a growing state is caught earlier
+const STATE_LIMIT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 
 pub(crate) struct ReduceRunnable {
     operator_id: OperatorId,
@@ -27,6 +47,19 @@ pub(crate) struct ReduceRunnable {
 
     counter: Counter,
     expire_counter: Counter,
+
+    /// the schema to render the hot keys
+    key_schema: Schema,
+    hot_key_sketch: HotKeySketch,
+    /// the timestamp of the latest published `KeyedStateSummary`
+    stats_timestamp: u64,
+    state_keys_gauge: Gauge,
+    state_bytes_gauge: Gauge,
+    max_key_group_bytes_gauge: Gauge,
+
+    state_limit: Option<StateLimit>,
+    /// the number of the checks the state reaches the warning level of the `state_limit`
+    state_limit_warnings: Counter,
 }
 
 impl ReduceRunnable {
@@ -47,6 +80,106 @@ impl ReduceRunnable {
             completed_checkpoint_id: None,
             counter: Counter::default(),
             expire_counter: Counter::default(),
+            key_schema: Schema::empty(),
+            hot_key_sketch: HotKeySketch::new(HOT_KEYS),
+            stats_timestamp: 0,
+            state_keys_gauge: Gauge::default(),
+            state_bytes_gauge: Gauge::default(),
+            max_key_group_bytes_gauge: Gauge::default(),
+            state_limit: None,
+            state_limit_warnings: Counter::default(),
+        }
+    }
+}
+
+impl ReduceRunnable {
+    /// scan the keyed state and publish the `KeyedStateSummary` at most once per
+    /// `STATE_STATS_INTERVAL`, the scanning is in the task thread so it's throttled
+    fn try_publish_state_stats(&mut self) {
+        let timestamp = current_timestamp_millis();
+        let interval = if self.state_limit.is_some() {
+            STATE_LIMIT_CHECK_INTERVAL
+        } else {
+            STATE_STATS_INTERVAL
+        };
+        if timestamp - self.stats_timestamp < interval.as_millis() as u64 {
+            return;
+        }
+        self.stats_timestamp = timestamp;
+
+        let stats = match self.stream_reduce.operator_fn.key_group_stats() {
+            Some(stats) => stats,
+            None => return,
+        };
+        self.state_keys_gauge.store(stats.keys as i64);
+        self.state_bytes_gauge.store(stats.state_bytes() as i64);
+        self.max_key_group_bytes_gauge
+            .store(stats.max_key_group_bytes() as i64);
+        self.check_state_limit(&stats);
+
+        let hot_keys = self
+            .hot_key_sketch
+            .hot_keys()
+            .into_iter()
+            .map(|(mut key, count)| HotKey {
+                key: record_to_strings(&mut key, &self.key_schema)
+                    .map(|values| values.join(", "))
+                    .unwrap_or_else(|e| format!("unreadable key. {}", e)),
+                count: count as u64,
+            })
+            .collect();
+        self.hot_key_sketch.decay();
+
+        publish_keyed_state_summary(KeyedStateSummary {
+            operator_id: self.operator_id,
+            task_id: self.task_id,
+            stats,
+            hot_keys,
+            timestamp,
+        });
+    }
+
+    /// warn by the metric, then alert by the coordinator once the state exceeds the limit,
+    /// and fail the task if the `StateLimit::fail_task` is enabled
+    fn check_state_limit(&mut self, stats: &KeyGroupStats) {
+        let state_limit = match &self.state_limit {
+            Some(state_limit) => state_limit,
+            None => return,
+        };
+
+        let bytes = stats.state_bytes();
+        let entries = stats.keys as u64;
+        match state_limit.check(bytes, entries) {
+            StateLimitLevel::Normal => {}
+            StateLimitLevel::Warning => {
+                self.state_limit_warnings.fetch_add(1);
+                warn!(
+                    "the state of the operator {:?} in task {:?} is close to the limit({}), {} bytes, {} entries",
+                    self.operator_id, self.task_id, state_limit, bytes, entries
+                );
+            }
+            StateLimitLevel::Exceeded => {
+                self.state_limit_warnings.fetch_add(1);
+                let message = format!(
+                    "the state of the operator {} in the task(job_id={}, task_number={}) exceeds the limit({}), {} bytes, {} entries",
+                    self.operator_id.0,
+                    self.task_id.job_id.0,
+                    self.task_id.task_number,
+                    state_limit,
+                    bytes,
+                    entries
+                );
+                error!("{}", message);
+                submit_heartbeat(HeartbeatItem::TaskStateLimitExceeded {
+                    task_id: self.task_id,
+                    operator_id: self.operator_id,
+                    message: message.clone(),
+                });
+
+                if state_limit.fail_task {
+                    std::panic::panic_any(UserFunctionError::StateLimit(message));
+                }
+            }
         }
     }
 }
@@ -60,17 +193,46 @@ impl Runnable for ReduceRunnable {
         self.context = Some(context.clone());
 
         let fun_context = context.to_fun_context(self.operator_id);
-        self.stream_reduce.operator_fn.open(&fun_context)?;
+        self.stream_reduce
+            .operator_fn
+            .open(&fun_context)
+            .map_err(|e| e.or_classify(UserFunctionError::Open))?;
         self.stream_key_by
             .as_mut()
             .map(|s| s.operator_fn.open(&fun_context));
 
-        let fn_name = self.stream_reduce.operator_fn.as_ref().name();
+        if let Some(stream_key_by) = &self.stream_key_by {
+            self.key_schema = stream_key_by
+                .operator_fn
+                .key_schema(fun_context.input_schema.clone())
+                .into();
+        }
 
-        self.counter = register_counter(format!("Reduce_{}", fn_name), self.task_id.to_tags());
+        let fn_name = self.stream_reduce.operator_fn.as_ref().name();
 
-        self.expire_counter =
-            register_counter(format!("Reduce_Expire_{}", fn_name), self.task_id.to_tags());
+        self.counter = register_counter(
+            format!("Reduce_{}", fn_name),
+            context.operator_tags(self.operator_id),
+        );
+
+        self.expire_counter = register_counter(
+            format!("Reduce_Expire_{}", fn_name),
+            context.operator_tags(self.operator_id),
+        );
+
+        let tags = context.operator_tags(self.operator_id);
+        self.state_keys_gauge =
+            register_gauge(format!("ReduceStateKeys_{}", fn_name), tags.clone());
+        self.state_bytes_gauge =
+            register_gauge(format!("ReduceStateBytes_{}", fn_name), tags.clone());
+        self.max_key_group_bytes_gauge =
+            register_gauge(format!("ReduceMaxKeyGroupBytes_{}", fn_name), tags.clone());
+
+        self.state_limit = context.state_limit(self.operator_id);
+        if self.state_limit.is_some() {
+            self.state_limit_warnings =
+                register_counter(format!("ReduceStateLimitWarnings_{}", fn_name), tags);
+        }
 
         info!("ReduceRunnable Opened. task_id={:?}", self.task_id);
         Ok(())
@@ -79,12 +241,17 @@ impl Runnable for ReduceRunnable {
     fn run(&mut self, element: Element) {
         match element {
             Element::Record(mut record) => {
-                // Record expiration check
-                let min_window_timestamp = self.limited_watermark_window.min_timestamp();
-                let acceptable = record
-                    .max_location_window()
-                    .map(|window| window.min_timestamp() >= min_window_timestamp)
-                    .unwrap_or(true);
+                if record.is_dropped() {
+                    // the lineage of the assertion mode ends at the reduce
+                    return;
+                }
+
+                // Record expiration check, the held windows accept the late records
+                let acceptable = is_acceptable(
+                    &record,
+                    &self.limited_watermark_window,
+                    self.stream_reduce.operator_fn.watermark_hold(),
+                );
                 if !acceptable {
                     let n = self.expire_counter.fetch_add(1);
                     if n & 1048575 == 1 {
@@ -98,11 +265,19 @@ impl Runnable for ReduceRunnable {
                 }
 
                 let key = match &self.stream_key_by {
-                    Some(stream_key_by) => stream_key_by.operator_fn.get_key(record.borrow_mut()),
+                    Some(stream_key_by) => catch_user_panic(self.operator_id, &mut record, |r| {
+                        stream_key_by.operator_fn.get_key(r)
+                    }),
                     None => Record::with_capacity(0),
                 };
 
-                self.stream_reduce.operator_fn.as_mut().reduce(key, record);
+                self.hot_key_sketch.add(&key);
+
+                let mut input = RecordMeta::of(&record);
+                let operator_fn = self.stream_reduce.operator_fn.as_mut();
+                catch_user_panic(self.operator_id, &mut input, |_| {
+                    operator_fn.reduce(key, record)
+                });
 
                 self.counter.fetch_add(1);
             }
@@ -144,6 +319,8 @@ impl Runnable for ReduceRunnable {
                     .run(Element::Barrier(barrier));
             }
             Element::StreamStatus(stream_status) => {
+                self.try_publish_state_stats();
+
                 self.next_runnable
                     .as_mut()
                     .unwrap()
@@ -154,7 +331,10 @@ impl Runnable for ReduceRunnable {
 
     fn close(&mut self) -> anyhow::Result<()> {
         self.stream_key_by.as_mut().map(|s| s.operator_fn.close());
-        self.stream_reduce.operator_fn.close()?;
+        self.stream_reduce
+            .operator_fn
+            .close()
+            .map_err(|e| e.or_classify(UserFunctionError::Close))?;
         self.next_runnable.as_mut().unwrap().close()
     }
 
@@ -162,31 +342,61 @@ impl Runnable for ReduceRunnable {
         self.next_runnable = next_runnable;
     }
 
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        if let Some(stream_key_by) = self.stream_key_by.as_mut() {
+            stream_key_by
+                .operator_fn
+                .notify_checkpoint_complete(checkpoint_id);
+        }
+        notify_chained_checkpoint_complete(
+            self.stream_reduce.operator_fn.as_mut(),
+            self.next_runnable.as_mut(),
+            checkpoint_id,
+        );
+    }
+
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
+        let begin_time = current_timestamp_millis();
+        let fn_handle = self
             .stream_reduce
             .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let fn_handle = ReduceCheckpointHandle::from(handle.handle.as_str());
+            .snapshot_windows(&snapshot_context);
         self.completed_checkpoint_id = fn_handle.completed_checkpoint_id;
 
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
+        let snapshot_context = FunctionSnapshotContext {
             completed_checkpoint_id: self.completed_checkpoint_id,
-            handle: CheckpointHandle {
-                handle: fn_handle.to_windows_string(),
-            },
+            ..snapshot_context
         };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
+        let async_snapshot: AsyncSnapshot = Box::new(move || {
+            let handle = serde_json::to_string(&fn_handle.into_windows())?;
+            Ok(CheckpointHandle { handle })
         });
+        let stats = CheckpointStats {
+            sync_duration: current_timestamp_millis() - begin_time,
+            ..Default::default()
+        };
+        submit_async_snapshot(&snapshot_context, async_snapshot, stats);
+    }
+}
+
+/// the record is acceptable if its windows are not closed by the watermark, the windows held
+/// after the watermark still accept the late records, see `WindowEmitStrategy::watermark_hold`
+pub(crate) fn is_acceptable(
+    record: &Record,
+    watermark_window: &Window,
+    watermark_hold: u64,
+) -> bool {
+    match record.max_location_window() {
+        // the held window is closed once the watermark passes its end by the hold,
+        // see `WindowBaseReduceFunction::drop_state`
+        Some(window) if watermark_hold > 0 => {
+            window.max_timestamp()
+                > watermark_window
+                    .min_timestamp()
+                    .saturating_sub(watermark_hold)
+        }
+        Some(window) => window.min_timestamp() >= watermark_window.min_timestamp(),
+        None => true,
     }
 }
 
@@ -209,10 +419,6 @@ impl ReduceCheckpointHandle {
         }
     }
 
-    pub fn to_windows_string(&self) -> String {
-        serde_json::to_string(&self.current_windows).unwrap()
-    }
-
     pub fn into_windows(self) -> Vec<Window> {
         self.current_windows
     }
@@ -1,13 +1,19 @@
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::checkpoint::{CheckpointStats, FunctionSnapshotContext, ProcessingGuarantee};
 use crate::core::element::{Element, Partition};
+use crate::core::error::{CheckpointError, NetworkError, UserFunctionError};
 use crate::core::function::OutputFormat;
 use crate::core::operator::{DefaultStreamOperator, FunctionCreator, TStreamOperator};
-use crate::core::runtime::{OperatorId, TaskId};
+use crate::core::retry::RetryPolicy;
+use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
+use crate::core::write_ahead_log::SinkWal;
 use crate::dag::job_graph::JobEdge;
 use crate::metrics::metric::Counter;
 use crate::metrics::register_counter;
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::runtime::worker::assertion;
+use crate::runtime::worker::checkpoint::snapshot_checkpoint;
+use crate::runtime::worker::runnable::{
+    call_with_retry, catch_user_panic, RecordMeta, Runnable, RunnableContext,
+};
 
 pub(crate) struct SinkRunnable {
     operator_id: OperatorId,
@@ -18,6 +24,11 @@ pub(crate) struct SinkRunnable {
     context: Option<RunnableContext>,
 
     stream_sink: DefaultStreamOperator<dyn OutputFormat>,
+    /// verify the records of the user sink, see `SystemProperties::set_assertion_mode`
+    assertion_mode: Option<ProcessingGuarantee>,
+    retry_policy: Option<RetryPolicy>,
+    /// the records are appended to it before written, see `SinkStream::write_ahead_log`
+    wal: Option<SinkWal>,
 
     counter: Counter,
 }
@@ -34,11 +45,50 @@ impl SinkRunnable {
             child_parallelism: 0,
             context: None,
             stream_sink,
+            assertion_mode: None,
+            retry_policy: None,
+            wal: None,
             counter: Counter::default(),
         }
     }
 }
 
+impl SinkRunnable {
+    /// open the write-ahead log and write the records of its sealed segments to the sink again,
+    /// the replayed records are retained in the log until the next checkpoints complete
+    fn open_wal(&mut self, context: &RunnableContext) -> anyhow::Result<()> {
+        let write_ahead_log = match context.write_ahead_log(self.operator_id) {
+            Some(write_ahead_log) => write_ahead_log,
+            None => return Ok(()),
+        };
+
+        let application_id = context
+            .cluster_descriptor
+            .coordinator_manager
+            .application_id
+            .as_str();
+        let dir = write_ahead_log.path(application_id, self.operator_id, self.task_id);
+        let (wal, records) = SinkWal::open(dir.as_path(), write_ahead_log.retained_checkpoints)
+            .map_err(|e| {
+                CheckpointError::Restore(format!("open the write-ahead log {:?} error. {}", dir, e))
+            })?;
+        self.wal = Some(wal);
+
+        info!(
+            "replay {} records of the write-ahead log {:?}",
+            records.len(),
+            dir
+        );
+        for record in records {
+            self.run(Element::Record(record));
+        }
+        self.wal.as_mut().unwrap().seal_replayed().map_err(|e| {
+            CheckpointError::Restore(format!("seal the replayed records error. {}", e))
+        })?;
+        Ok(())
+    }
+}
+
 impl Runnable for SinkRunnable {
     fn open(&mut self, context: &RunnableContext) -> anyhow::Result<()> {
         self.context = Some(context.clone());
@@ -63,27 +113,84 @@ impl Runnable for SinkRunnable {
         );
 
         let fun_context = context.to_fun_context(self.operator_id);
-        self.stream_sink.operator_fn.open(&fun_context)?;
+        let fn_creator = self.stream_sink.fn_creator();
+        self.stream_sink
+            .operator_fn
+            .open(&fun_context)
+            .map_err(|e| match fn_creator {
+                FunctionCreator::User => e.or_classify(UserFunctionError::Open),
+                FunctionCreator::System => e.or_classify(NetworkError::Publish),
+            })?;
+
+        if let FunctionCreator::User = fn_creator {
+            self.retry_policy = context.retry_policy(self.operator_id);
+            self.assertion_mode = context.assertion_mode();
+            if let Some(guarantee) = self.assertion_mode {
+                assertion::on_sink_open(guarantee);
+            }
+        }
 
         self.counter = register_counter(
             format!("Sink_{}", self.stream_sink.operator_fn.as_ref().name()),
-            self.task_id.to_tags(),
+            context.operator_tags(self.operator_id),
         );
 
+        if let FunctionCreator::User = fn_creator {
+            self.open_wal(context)?;
+        }
+
         Ok(())
     }
 
     fn run(&mut self, element: Element) {
         match element {
             Element::Record(record) => {
-                self.stream_sink
-                    .operator_fn
-                    .write_element(Element::Record(record));
+                let fn_creator = self.stream_sink.fn_creator();
+                let operator_fn = self.stream_sink.operator_fn.as_mut();
+                match fn_creator {
+                    FunctionCreator::User => {
+                        if let (Some(guarantee), Some(sequence)) =
+                            (self.assertion_mode, &record.sequence)
+                        {
+                            assertion::on_record(guarantee, sequence);
+                            if sequence.dropped {
+                                return;
+                            }
+                        }
+
+                        if let Some(wal) = self.wal.as_mut() {
+                            if let Err(e) = wal.append(&record) {
+                                panic!("append the write-ahead log of the sink error. {}", e);
+                            }
+                        }
+
+                        let mut input = RecordMeta::of(&record);
+                        match &self.retry_policy {
+                            Some(retry_policy) => call_with_retry(
+                                self.operator_id,
+                                Some(retry_policy),
+                                &mut input,
+                                |_| operator_fn.write_element(Element::Record(record.clone())),
+                            ),
+                            None => catch_user_panic(self.operator_id, &mut input, |_| {
+                                operator_fn.write_element(Element::Record(record))
+                            }),
+                        }
+                    }
+                    FunctionCreator::System => operator_fn.write_element(Element::Record(record)),
+                }
 
                 self.counter.fetch_add(1);
             }
             _ => {
                 if element.is_barrier() {
+                    if let Some(wal) = self.wal.as_mut() {
+                        let checkpoint_id = element.as_barrier().checkpoint_id;
+                        if let Err(e) = wal.seal(checkpoint_id) {
+                            panic!("seal the write-ahead log of the sink error. {}", e);
+                        }
+                    }
+
                     let snapshot_context = {
                         let checkpoint_id = element.as_barrier().checkpoint_id;
                         let completed_checkpoint_id =
@@ -114,14 +221,21 @@ impl Runnable for SinkRunnable {
                             self.stream_sink.operator_fn.write_element(element);
                         }
                     }
-                    FunctionCreator::User => {}
+                    FunctionCreator::User => self.stream_sink.operator_fn.observe_element(&element),
                 }
             }
         }
     }
 
     fn close(&mut self) -> anyhow::Result<()> {
-        self.stream_sink.operator_fn.close()?;
+        self.stream_sink
+            .operator_fn
+            .close()
+            .map_err(|e| e.or_classify(UserFunctionError::Close))?;
+
+        if self.assertion_mode.is_some() {
+            assertion::on_sink_close();
+        }
         Ok(())
     }
 
@@ -129,25 +243,23 @@ impl Runnable for SinkRunnable {
         unimplemented!()
     }
 
-    fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
-            .stream_sink
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        if let Some(wal) = self.wal.as_mut() {
+            if let Err(e) = wal.truncate(checkpoint_id) {
+                error!("truncate the write-ahead log of the sink error. {}", e);
+            }
+        }
+
+        self.stream_sink
             .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
-            completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
-            handle,
-        };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
-        });
+            .notify_checkpoint_complete(checkpoint_id);
+    }
+
+    fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
+        snapshot_checkpoint(
+            self.stream_sink.operator_fn.as_mut(),
+            &snapshot_context,
+            CheckpointStats::default(),
+        );
     }
 }
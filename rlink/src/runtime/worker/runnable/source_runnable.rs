@@ -1,25 +1,39 @@
-use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use crate::channel::named_channel;
 use crate::channel::sender::ChannelSender;
 use crate::channel::utils::iter::ChannelIterator;
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
-use crate::core::element::{Element, StreamStatus, Watermark};
+use crate::channel::{bounded, named_channel, Receiver, Sender};
+use crate::core::cancellation::CancellationToken;
+use crate::core::checkpoint::{CheckpointRequester, CheckpointStats, FunctionSnapshotContext};
+use crate::core::element::{Element, RecordSequence, Serde};
+use crate::core::error::{NetworkError, SourceError};
 use crate::core::function::InputFormat;
 use crate::core::operator::{DefaultStreamOperator, FunctionCreator, TStreamOperator};
-use crate::core::runtime::{CheckpointId, JobId, OperatorId, TaskId};
-use crate::core::watermark::MAX_WATERMARK;
+use crate::core::properties::SystemProperties;
+use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
 use crate::metrics::metric::Counter;
 use crate::metrics::register_counter;
 use crate::runtime::timer::TimerChannel;
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::heart_beat::{get_coordinator_status, submit_heartbeat};
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
-use crate::runtime::HeartbeatItem;
+use crate::runtime::worker::assertion;
+use crate::runtime::worker::checkpoint::snapshot_checkpoint;
+use crate::runtime::worker::heart_beat::{
+    get_completed_checkpoint_id, get_coordinator_status, get_stop_checkpoint_id,
+    get_triggered_checkpoint_id, submit_heartbeat,
+};
+use crate::runtime::worker::runnable::watermark_assigner_runnable::WATERMARK_REPORT_INTERVAL_MS;
+use crate::runtime::worker::runnable::{
+    notify_chained_checkpoint_complete, Runnable, RunnableContext,
+};
+use crate::runtime::worker::split::next_split;
+use crate::runtime::{HeartbeatItem, SplitRequest, SplitResponse, SplitStatus};
+use crate::utils::date_time::current_timestamp_millis;
+use crate::utils::panic::panic_message;
+
+type ElementIterator = Box<dyn Iterator<Item = Element> + Send>;
 
 pub(crate) struct SourceRunnable {
     operator_id: OperatorId,
@@ -36,8 +50,21 @@ pub(crate) struct SourceRunnable {
 
     waiting_end_flags: usize,
     barrier_alignment: AlignManager,
+    /// the size of records processed during the `Barrier` alignment
+    alignment_bytes: u64,
     stream_status_alignment: AlignManager,
-    watermark_manager: WatermarkManager,
+    /// the latest completed checkpoint notified to the chained operators
+    notified_checkpoint_id: CheckpointId,
+
+    /// the next sequence to stamp in the assertion mode, see `SystemProperties::set_assertion_mode`
+    assertion_sequence: Option<u64>,
+    /// the split switching with the poll thread, see `SystemProperties::set_split_reassignment`
+    split_switch: Option<SplitSwitch>,
+    /// the latest timestamp of reporting the input watermark
+    watermark_report_ts: u64,
+    /// the `InputFormat` is opened, it's opened by the first pulled split if the splits are
+    /// handed out by the `SplitEnumerator`
+    source_opened: bool,
 
     counter: Counter,
 }
@@ -62,21 +89,57 @@ impl SourceRunnable {
 
             waiting_end_flags: 0,
             barrier_alignment: AlignManager::default(),
+            alignment_bytes: 0,
             stream_status_alignment: AlignManager::default(),
-            watermark_manager: WatermarkManager::default(),
+            notified_checkpoint_id: CheckpointId::default(),
+            assertion_sequence: None,
+            split_switch: None,
+            watermark_report_ts: 0,
+            source_opened: false,
             counter: Counter::default(),
         }
     }
 
+    /// notify the chained operators if a newer checkpoint is completed by the coordinator
+    fn try_notify_checkpoint_complete(&mut self) {
+        if let Some(checkpoint_id) = get_completed_checkpoint_id() {
+            if checkpoint_id.0 > self.notified_checkpoint_id.0 {
+                self.notified_checkpoint_id = checkpoint_id;
+                self.notify_checkpoint_complete(checkpoint_id);
+            }
+        }
+    }
+
     fn poll_input_element(
         &mut self,
         sender: ChannelSender<Element>,
         running: Arc<AtomicBool>,
+        barrier_state: Arc<Mutex<BarrierState>>,
         daemon_task: bool,
     ) {
-        let iterator = self.stream_source.operator_fn.element_iter();
+        // the poll thread asks for the first split immediately if the splits are pulled
+        let iterator: ElementIterator = if self.source_opened {
+            self.stream_source.operator_fn.element_iter()
+        } else {
+            Box::new(std::iter::empty())
+        };
+        let split_handover = self.split_switch.as_ref().map(|x| x.handover());
+        let context = self.context.as_ref().unwrap();
+        let poll_context = PollContext {
+            task_id: self.task_id,
+            daemon_task,
+            cancellation_token: context.cancellation_token.clone(),
+            checkpoint_requester: context.checkpoint_requester.clone(),
+        };
         crate::utils::thread::spawn("poll_input_element", move || {
-            match SourceRunnable::poll_input_element0(iterator, sender, running, daemon_task) {
+            match SourceRunnable::poll_input_element0(
+                iterator,
+                sender,
+                running,
+                barrier_state,
+                split_handover,
+                poll_context,
+            ) {
                 Ok(_) => info!("poll input_element task finish"),
                 Err(e) => panic!("poll_input_element thread error. {}", e),
             }
@@ -84,17 +147,52 @@ impl SourceRunnable {
     }
 
     fn poll_input_element0(
-        iterator: Box<dyn Iterator<Item = Element> + Send>,
+        mut iterator: ElementIterator,
         sender: ChannelSender<Element>,
         running: Arc<AtomicBool>,
-        daemon_task: bool,
+        barrier_state: Arc<Mutex<BarrierState>>,
+        split_handover: Option<SplitHandover>,
+        poll_context: PollContext,
     ) -> anyhow::Result<()> {
-        for record in iterator {
-            sender.send(record).map_err(|e| anyhow!(e))?;
-
-            if daemon_task && get_coordinator_status().is_terminating() {
-                info!("daemon source stop by coordinator stop");
-                break;
+        loop {
+            let poll_end = match &split_handover {
+                // the panic of reading the split is caught, the split is reassigned
+                Some(_) => match std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    SourceRunnable::poll_split(
+                        iterator.as_mut(),
+                        &sender,
+                        &barrier_state,
+                        &poll_context,
+                    )
+                })) {
+                    Ok(poll_end) => poll_end?,
+                    Err(payload) => {
+                        error!("read split error. {}", panic_message(payload.as_ref()));
+                        PollEnd::Failed
+                    }
+                },
+                None => SourceRunnable::poll_split(
+                    iterator.as_mut(),
+                    &sender,
+                    &barrier_state,
+                    &poll_context,
+                )?,
+            };
+
+            let split_handover = match (poll_end, &split_handover) {
+                (PollEnd::Stopped, _) => return Ok(()),
+                (PollEnd::Terminated, _) | (_, None) => break,
+                (_, Some(split_handover)) => split_handover,
+            };
+
+            // wait for the next split opened by the task thread
+            split_handover
+                .split_ends
+                .send(poll_end == PollEnd::Failed)
+                .map_err(|e| anyhow!(e))?;
+            match split_handover.iterators.recv() {
+                Ok(Some(next_iterator)) => iterator = next_iterator,
+                _ => break,
             }
         }
 
@@ -102,6 +200,121 @@ impl SourceRunnable {
         Ok(())
     }
 
+    fn poll_split(
+        iterator: &mut (dyn Iterator<Item = Element> + Send),
+        sender: &ChannelSender<Element>,
+        barrier_state: &Mutex<BarrierState>,
+        poll_context: &PollContext,
+    ) -> anyhow::Result<PollEnd> {
+        let cancellation_token = &poll_context.cancellation_token;
+        for record in &mut *iterator {
+            // the records after the stop barrier are dropped, they are read again by
+            // the application restored from the savepoint
+            {
+                let barrier_state = barrier_state.lock().unwrap();
+                if barrier_state.stopped {
+                    info!("source stop by the stop-with-savepoint");
+                    return Ok(PollEnd::Stopped);
+                }
+                sender.send(record).map_err(|e| anyhow!(e))?;
+            }
+
+            if poll_context.checkpoint_requester.take() {
+                SourceRunnable::send_requested_barrier(
+                    sender,
+                    barrier_state,
+                    poll_context.task_id,
+                )?;
+            }
+
+            if poll_context.daemon_task && get_coordinator_status().is_terminating() {
+                info!("daemon source stop by coordinator stop");
+                return Ok(PollEnd::Terminated);
+            }
+            if cancellation_token.is_cancelled() {
+                info!(
+                    "source stop by the cancellation, reason: {}",
+                    cancellation_token.reason().unwrap_or_default()
+                );
+                return Ok(PollEnd::Terminated);
+            }
+        }
+        Ok(PollEnd::Ended)
+    }
+
+    /// request the next split from the coordinator after the poll thread read the split to the
+    /// end or failed, retried on the next `StreamStatus` if the coordinator asks for waiting
+    fn try_next_split(&mut self) {
+        let split_switch = match self.split_switch.as_mut() {
+            Some(split_switch) => split_switch,
+            None => return,
+        };
+        if split_switch.request.is_none() {
+            match split_switch.split_ends.try_recv() {
+                Ok(failed) => {
+                    let split_number = split_switch.split_number;
+                    split_switch.request = Some(if failed {
+                        (None, split_number)
+                    } else {
+                        (split_number, None)
+                    });
+                }
+                Err(_) => return,
+            }
+        }
+
+        let (completed, failed) = split_switch.request.unwrap();
+        let request = SplitRequest {
+            task_id: self.task_id,
+            completed,
+            failed,
+        };
+        let context = self.context.as_ref().unwrap();
+        let coordinator_address = &context.cluster_descriptor.coordinator_manager.web_address;
+        let response = match next_split(coordinator_address.as_str(), &request) {
+            Ok(Some(response)) => response,
+            Ok(None) => return,
+            Err(e) => panic!("{}", e),
+        };
+
+        match response {
+            SplitResponse::Assigned(input_split) => {
+                let split_number = input_split.split_number();
+                info!("open the assigned split {}", split_number);
+
+                // the checkpoint of the task is not the state of the assigned split
+                let mut fun_context = context.to_fun_context(self.operator_id);
+                fun_context.checkpoint_handle = None;
+                let source_func = self.stream_source.operator_fn.as_mut();
+                if self.source_opened {
+                    source_func.close().expect("close the split error");
+                }
+                self.source_opened = true;
+                source_func
+                    .open(input_split, &fun_context)
+                    .expect("open the assigned split error");
+                let iterator = source_func.element_iter();
+
+                split_switch.split_number = Some(split_number);
+                split_switch.request = None;
+                split_switch
+                    .iterators
+                    .send(Some(iterator))
+                    .expect("send the split iterator error");
+                report_split_processing(self.task_id, split_number);
+            }
+            SplitResponse::Wait => {
+                // the previous split has been reported
+                split_switch.request = Some((None, None));
+            }
+            SplitResponse::End => {
+                info!("all splits are completed");
+                let _ = split_switch.iterators.send(None);
+                self.split_switch = None;
+            }
+        }
+    }
+
     fn poll_stream_status(&mut self, sender: ChannelSender<Element>, running: Arc<AtomicBool>) {
         let stream_status_timer = self.stream_status_timer.as_ref().unwrap().clone();
         crate::utils::thread::spawn("poll_stream_status", move || {
@@ -134,25 +347,46 @@ impl SourceRunnable {
         Ok(())
     }
 
-    fn poll_checkpoint(&mut self, sender: ChannelSender<Element>, running: Arc<AtomicBool>) {
+    fn poll_checkpoint(
+        &mut self,
+        sender: ChannelSender<Element>,
+        running: Arc<AtomicBool>,
+        barrier_state: Arc<Mutex<BarrierState>>,
+    ) {
         let checkpoint_timer = self.checkpoint_timer.as_ref().unwrap().clone();
+
+        let timer_sender = sender.clone();
+        let timer_running = running.clone();
+        let timer_barrier_state = barrier_state.clone();
         crate::utils::thread::spawn("poll_checkpoint", move || {
-            match SourceRunnable::poll_checkpoint0(checkpoint_timer, sender, running) {
+            match SourceRunnable::poll_checkpoint0(
+                checkpoint_timer,
+                timer_sender,
+                timer_running,
+                timer_barrier_state,
+            ) {
                 Ok(_) => info!("poll checkpoint task finish"),
                 Err(e) => warn!("poll checkpoint thread error. {}", e),
             }
         });
+
+        crate::utils::thread::spawn("poll_triggered_checkpoint", move || {
+            match SourceRunnable::poll_triggered_checkpoint0(sender, running, barrier_state) {
+                Ok(_) => info!("poll triggered checkpoint task finish"),
+                Err(e) => warn!("poll triggered checkpoint thread error. {}", e),
+            }
+        });
     }
 
     fn poll_checkpoint0(
         checkpoint_timer: TimerChannel,
         sender: ChannelSender<Element>,
         running: Arc<AtomicBool>,
+        barrier_state: Arc<Mutex<BarrierState>>,
     ) -> anyhow::Result<()> {
         loop {
             let window_time = checkpoint_timer.recv().map_err(|e| anyhow!(e))?;
-            let barrier = Element::new_barrier(CheckpointId(window_time));
-            sender.send(barrier).map_err(|e| anyhow!(e))?;
+            SourceRunnable::send_barrier(&sender, &barrier_state, CheckpointId(window_time))?;
 
             let running = running.load(Ordering::Relaxed);
             if !running {
@@ -165,6 +399,152 @@ impl SourceRunnable {
         Ok(())
     }
 
+    /// emit the barriers of the checkpoint triggered on demand and the stop-with-savepoint,
+    /// see `HeartbeatResponse`. After the stop barrier is emitted, the stream is ended once
+    /// the savepoint is completed, so no record is processed after the savepoint.
+    fn poll_triggered_checkpoint0(
+        sender: ChannelSender<Element>,
+        running: Arc<AtomicBool>,
+        barrier_state: Arc<Mutex<BarrierState>>,
+    ) -> anyhow::Result<()> {
+        let mut stop_checkpoint_id: Option<CheckpointId> = None;
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+
+            let completed_checkpoint_id = get_completed_checkpoint_id().unwrap_or_default();
+            if let Some(checkpoint_id) = get_triggered_checkpoint_id() {
+                if checkpoint_id > completed_checkpoint_id {
+                    SourceRunnable::send_barrier(&sender, &barrier_state, checkpoint_id)?;
+                }
+            }
+
+            match stop_checkpoint_id {
+                None => {
+                    if let Some(checkpoint_id) = get_stop_checkpoint_id() {
+                        SourceRunnable::send_stop_barrier(&sender, &barrier_state, checkpoint_id)?;
+                        stop_checkpoint_id = Some(checkpoint_id);
+                    }
+                }
+                Some(checkpoint_id) => {
+                    let stopped = barrier_state.lock().unwrap().stopped;
+                    if stopped
+                        && completed_checkpoint_id >= checkpoint_id
+                        && running.load(Ordering::Relaxed)
+                    {
+                        info!("savepoint {:?} completed, end the stream", checkpoint_id);
+                        running.store(false, Ordering::Relaxed);
+                    }
+                }
+            }
+
+            if !running.load(Ordering::Relaxed) && get_coordinator_status().is_terminated() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// the barriers of a task are emitted in the ascending order of the `checkpoint_id`,
+    /// the downstream alignment drops the delayed ones
+    fn send_barrier(
+        sender: &ChannelSender<Element>,
+        barrier_state: &Mutex<BarrierState>,
+        checkpoint_id: CheckpointId,
+    ) -> anyhow::Result<()> {
+        let mut barrier_state = barrier_state.lock().unwrap();
+        if !barrier_state.stopped && checkpoint_id > barrier_state.checkpoint_id {
+            barrier_state.checkpoint_id = checkpoint_id;
+            let barrier = Element::new_barrier(checkpoint_id);
+            sender.send(barrier).map_err(|e| anyhow!(e))?;
+        }
+        Ok(())
+    }
+
+    /// emit the barrier of the checkpoint requested by the source right after the record read,
+    /// then report it to the coordinator to trigger the same checkpoint in the other sources
+    fn send_requested_barrier(
+        sender: &ChannelSender<Element>,
+        barrier_state: &Mutex<BarrierState>,
+        task_id: TaskId,
+    ) -> anyhow::Result<()> {
+        let checkpoint_id = {
+            let mut barrier_state = barrier_state.lock().unwrap();
+            if barrier_state.stopped {
+                return Ok(());
+            }
+            let checkpoint_id =
+                CheckpointId(current_timestamp_millis().max(barrier_state.checkpoint_id.0 + 1));
+            barrier_state.checkpoint_id = checkpoint_id;
+            sender
+                .send(Element::new_barrier(checkpoint_id))
+                .map_err(|e| anyhow!(e))?;
+            checkpoint_id
+        };
+
+        info!("requested checkpoint barrier {:?} emitted", checkpoint_id);
+        submit_heartbeat(HeartbeatItem::TaskCheckpointRequested {
+            task_id,
+            checkpoint_id,
+        });
+        Ok(())
+    }
+
+    /// emit the barrier of the savepoint and stop reading, the stop is ignored if a later
+    /// barrier has been emitted, because the savepoint can't be aligned
+    fn send_stop_barrier(
+        sender: &ChannelSender<Element>,
+        barrier_state: &Mutex<BarrierState>,
+        checkpoint_id: CheckpointId,
+    ) -> anyhow::Result<()> {
+        let mut barrier_state = barrier_state.lock().unwrap();
+        if checkpoint_id > barrier_state.checkpoint_id {
+            barrier_state.checkpoint_id = checkpoint_id;
+            barrier_state.stopped = true;
+            let barrier = Element::new_barrier(checkpoint_id);
+            sender.send(barrier).map_err(|e| anyhow!(e))?;
+            info!("stop barrier {:?} emitted", checkpoint_id);
+        } else {
+            error!(
+                "stop-with-savepoint ignored, the stop barrier {:?} is earlier than the emitted {:?}",
+                checkpoint_id, barrier_state.checkpoint_id
+            );
+        }
+        Ok(())
+    }
+
+    /// report the read position of the user source for the position query of the coordinator,
+    /// reported on the aligned `StreamStatus` which is as frequent as the heartbeat
+    fn report_position(&self) {
+        if let FunctionCreator::System = self.stream_source.fn_creator() {
+            return;
+        }
+
+        if let Some(position) = self.stream_source.operator_fn.position() {
+            submit_heartbeat(HeartbeatItem::TaskPosition {
+                task_id: self.task_id,
+                position,
+            });
+        }
+    }
+
+    /// report the aligned watermark of the downstream job for the watermark query of the
+    /// coordinator, the source job is reported by the `WatermarkAssignerRunnable`
+    fn report_input_watermark(&mut self, watermark: u64) {
+        if let FunctionCreator::User = self.stream_source.fn_creator() {
+            return;
+        }
+
+        let current_ts = current_timestamp_millis();
+        if current_ts < self.watermark_report_ts + WATERMARK_REPORT_INTERVAL_MS {
+            return;
+        }
+        self.watermark_report_ts = current_ts;
+        submit_heartbeat(HeartbeatItem::TaskInputWatermark {
+            task_id: self.task_id,
+            watermark,
+        });
+    }
+
     fn report_end_status(&self) {
         submit_heartbeat(HeartbeatItem::TaskEnd {
             task_id: self.task_id,
@@ -182,10 +562,22 @@ impl Runnable for SourceRunnable {
         // first open next, then open self
         self.next_runnable.as_mut().unwrap().open(context)?;
 
-        let input_split = context.task_descriptor.input_split.clone();
-        let fun_context = context.to_fun_context(self.operator_id);
-        let source_func = self.stream_source.operator_fn.as_mut();
-        source_func.open(input_split, &fun_context)?;
+        let fn_creator = self.stream_source.fn_creator();
+        // the static split is ignored, the splits are pulled from the coordinator
+        let pull_splits = matches!(fn_creator, FunctionCreator::User)
+            && self.stream_source.operator_fn.split_enumerator().is_some();
+        if !pull_splits {
+            let input_split = context.task_descriptor.input_split.clone();
+            let fun_context = context.to_fun_context(self.operator_id);
+            let source_func = self.stream_source.operator_fn.as_mut();
+            source_func
+                .open(input_split, &fun_context)
+                .map_err(|e| match fn_creator {
+                    FunctionCreator::User => e.or_classify(SourceError::Open),
+                    FunctionCreator::System => e.or_classify(NetworkError::Subscribe),
+                })?;
+            self.source_opened = true;
+        }
 
         if let FunctionCreator::User = self.stream_source.fn_creator() {
             let stream_status_timer = context
@@ -200,6 +592,25 @@ impl Runnable for SourceRunnable {
                 .register("Checkpoint Event Timer", checkpoint_period)
                 .expect("register Checkpoint timer error");
             self.checkpoint_timer = Some(checkpoint_timer);
+
+            if context.assertion_mode().is_some() {
+                assertion::on_source_open();
+                self.assertion_sequence = Some(0);
+            }
+
+            let split_reassignment = context
+                .cluster_descriptor
+                .coordinator_manager
+                .application_properties
+                .get_split_reassignment()
+                .unwrap_or(false);
+            if pull_splits {
+                self.split_switch = Some(SplitSwitch::new(None));
+            } else if split_reassignment && self.stream_source.operator_fn.bounded() {
+                let split_number = context.task_descriptor.input_split.split_number();
+                self.split_switch = Some(SplitSwitch::new(Some(split_number)));
+                report_split_processing(self.task_id, split_number);
+            }
         }
 
         let parent_execution_size = context.parent_executions(&self.task_id).len();
@@ -209,26 +620,8 @@ impl Runnable for SourceRunnable {
             parent_execution_size
         };
 
-        let mut parent_jobs = HashMap::new();
-        for (p_node, _edge) in context.parent_executions(&self.task_id) {
-            let tasks = parent_jobs.entry(p_node.task_id.job_id).or_insert_with(|| {
-                let tasks: Vec<bool> = (0..p_node.task_id.num_tasks)
-                    .into_iter()
-                    .map(|_| false)
-                    .collect();
-                tasks
-            });
-
-            tasks[p_node.task_id.task_number as usize] = true;
-        }
-        info!(
-            "current job: {:?},parent jobs: {:?}",
-            self.task_id, parent_jobs
-        );
-
         self.barrier_alignment = AlignManager::new(parent_execution_size);
         self.stream_status_alignment = AlignManager::new(parent_execution_size);
-        self.watermark_manager = WatermarkManager::new(parent_jobs);
         info!(
             "SourceRunnable Opened, operator_id={:?}, task_id={:?}, ElementEventAlign parent_execution_size={:?}",
             self.operator_id, self.task_id, parent_execution_size,
@@ -236,7 +629,7 @@ impl Runnable for SourceRunnable {
 
         self.counter = register_counter(
             format!("Source_{}", self.stream_source.operator_fn.as_ref().name()),
-            self.task_id.to_tags(),
+            context.operator_tags(self.operator_id),
         );
 
         Ok(())
@@ -253,11 +646,17 @@ impl Runnable for SourceRunnable {
                     10240,
                 );
                 let running = Arc::new(AtomicBool::new(true));
+                let barrier_state = Arc::new(Mutex::new(BarrierState::default()));
 
-                self.poll_input_element(sender.clone(), running.clone(), self.daemon_task);
+                self.poll_input_element(
+                    sender.clone(),
+                    running.clone(),
+                    barrier_state.clone(),
+                    self.daemon_task,
+                );
 
                 self.poll_stream_status(sender.clone(), running.clone());
-                self.poll_checkpoint(sender.clone(), running.clone());
+                self.poll_checkpoint(sender.clone(), running.clone(), barrier_state);
 
                 let element_iter: Box<dyn Iterator<Item = Element> + Send> =
                     Box::new(ChannelIterator::new(receiver));
@@ -269,7 +668,20 @@ impl Runnable for SourceRunnable {
         let mut end_flags = 0;
         while let Some(element) = element_iter.next() {
             match element {
-                Element::Record(_) => {
+                Element::Record(mut record) => {
+                    if let Some(sequence) = self.assertion_sequence.as_mut() {
+                        record.sequence = Some(RecordSequence::new(
+                            self.operator_id.0,
+                            self.task_id.task_number,
+                            *sequence,
+                        ));
+                        *sequence += 1;
+                    }
+
+                    let element = Element::Record(record);
+                    if self.barrier_alignment.is_aligning() {
+                        self.alignment_bytes += element.capacity() as u64;
+                    }
                     self.next_runnable.as_mut().unwrap().run(element);
                     self.counter.fetch_add(1);
                 }
@@ -290,19 +702,14 @@ impl Runnable for SourceRunnable {
                             .run(Element::Barrier(barrier));
                     }
                 }
-                Element::Watermark(watermark) => match self.watermark_manager.apply(watermark) {
-                    Some(min_watermark) => {
-                        debug!(
-                            "Watermark aligned, status_timestamp: {}",
-                            min_watermark.timestamp
-                        );
-                        self.next_runnable
-                            .as_mut()
-                            .unwrap()
-                            .run(Element::Watermark(min_watermark.clone()))
-                    }
-                    None => {}
-                },
+                Element::Watermark(watermark) => {
+                    // the watermarks of multiple parents are aligned by the `SystemInputFormat`
+                    self.report_input_watermark(watermark.timestamp);
+                    self.next_runnable
+                        .as_mut()
+                        .unwrap()
+                        .run(Element::Watermark(watermark));
+                }
                 Element::StreamStatus(stream_status) => {
                     let parent_job_terminated = if stream_status.end {
                         end_flags += 1;
@@ -311,8 +718,6 @@ impl Runnable for SourceRunnable {
                         false
                     };
 
-                    self.watermark_manager.watermark_job_check(&stream_status);
-
                     let is_align = self.stream_status_alignment.apply(stream_status.timestamp);
                     if is_align {
                         debug!("stream_status align");
@@ -321,7 +726,11 @@ impl Runnable for SourceRunnable {
                             parent_job_terminated,
                         );
                         self.next_runnable.as_mut().unwrap().run(stream_status);
+
+                        self.try_notify_checkpoint_complete();
+                        self.report_position();
                     }
+                    self.try_next_split();
 
                     if parent_job_terminated {
                         info!("all parents job stop on stream_status event");
@@ -333,8 +742,14 @@ impl Runnable for SourceRunnable {
     }
 
     fn close(&mut self) -> anyhow::Result<()> {
-        let source_func = self.stream_source.operator_fn.as_mut();
-        source_func.close()?;
+        if self.source_opened {
+            let source_func = self.stream_source.operator_fn.as_mut();
+            source_func.close()?;
+        }
+
+        if let Some(sequence) = self.assertion_sequence {
+            assertion::on_source_close(self.operator_id.0, self.task_id.task_number, sequence);
+        }
 
         // first close self, then close next
         self.next_runnable.as_mut().unwrap().close()
@@ -344,43 +759,151 @@ impl Runnable for SourceRunnable {
         self.next_runnable = next_runnable;
     }
 
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        notify_chained_checkpoint_complete(
+            self.stream_source.operator_fn.as_mut(),
+            self.next_runnable.as_mut(),
+            checkpoint_id,
+        );
+    }
+
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
-            .stream_source
-            .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
-            completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
-            handle,
+        let stats = CheckpointStats {
+            alignment_duration: self.barrier_alignment.align_duration(),
+            alignment_bytes: self.alignment_bytes,
+            ..Default::default()
         };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
-        });
+        self.alignment_bytes = 0;
+
+        snapshot_checkpoint(
+            self.stream_source.operator_fn.as_mut(),
+            &snapshot_context,
+            stats,
+        );
     }
 }
 
+fn report_split_processing(task_id: TaskId, split_number: u16) {
+    submit_heartbeat(HeartbeatItem::TaskSplit {
+        task_id,
+        split_number,
+        status: SplitStatus::Processing,
+    });
+}
+
+/// how the poll thread stopped reading a split
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum PollEnd {
+    /// read to the end
+    Ended,
+    /// the reading panicked
+    Failed,
+    /// stopped by the stop-with-savepoint
+    Stopped,
+    /// the daemon task is terminated by the coordinator
+    Terminated,
+}
+
+/// The settings of the poll thread reading the source
+struct PollContext {
+    task_id: TaskId,
+    daemon_task: bool,
+    cancellation_token: CancellationToken,
+    checkpoint_requester: CheckpointRequester,
+}
+
+/// The poll thread side of the `SplitSwitch`
+struct SplitHandover {
+    /// `true` if the split is failed
+    split_ends: Sender<bool>,
+    /// the iterator of the next split, `None` if all splits are completed
+    iterators: Receiver<Option<ElementIterator>>,
+}
+
+/// Switch the split read by the poll thread, the `InputFormat` is reopened by the task thread
+/// with the split assigned by the coordinator
+struct SplitSwitch {
+    /// the split read by the poll thread, `None` before the first split is pulled
+    split_number: Option<u16>,
+    /// the `(completed, failed)` split to report in the next request
+    request: Option<(Option<u16>, Option<u16>)>,
+    split_ends: Receiver<bool>,
+    iterators: Sender<Option<ElementIterator>>,
+    handover: SplitHandover,
+}
+
+impl SplitSwitch {
+    fn new(split_number: Option<u16>) -> Self {
+        let (split_ends_sender, split_ends) = bounded(1);
+        let (iterators, iterators_receiver) = bounded(1);
+        SplitSwitch {
+            split_number,
+            request: None,
+            split_ends,
+            iterators,
+            handover: SplitHandover {
+                split_ends: split_ends_sender,
+                iterators: iterators_receiver,
+            },
+        }
+    }
+
+    fn handover(&self) -> SplitHandover {
+        SplitHandover {
+            split_ends: self.handover.split_ends.clone(),
+            iterators: self.handover.iterators.clone(),
+        }
+    }
+}
+
+/// The latest barrier emitted by the source task, shared by the threads emitting the records
+/// and the barriers
+#[derive(Debug, Default)]
+struct BarrierState {
+    checkpoint_id: CheckpointId,
+    /// the stop barrier is emitted, no records are emitted after it
+    stopped: bool,
+}
+
 #[derive(Debug, Default)]
 struct AlignManager {
     parent_execution_size: usize,
 
     batch_id: u64,
     reached_size: usize,
+
+    /// the timestamp of the first element of current batch reached
+    begin_time: u64,
+    /// the timestamp of all elements of current batch reached
+    align_time: u64,
 }
 
 impl AlignManager {
+    fn check_align(&mut self) -> bool {
+        let is_align = self.reached_size == self.parent_execution_size;
+        if is_align {
+            self.align_time = current_timestamp_millis();
+        }
+        is_align
+    }
+
+    /// whether some elements of current batch have been reached, but not all
+    pub fn is_aligning(&self) -> bool {
+        self.reached_size > 0 && self.reached_size < self.parent_execution_size
+    }
+
+    /// the duration(ms) from the first element to the last element of the latest batch reached
+    pub fn align_duration(&self) -> u64 {
+        self.align_time.saturating_sub(self.begin_time)
+    }
+
     pub fn new(parent_execution_size: usize) -> Self {
         AlignManager {
             parent_execution_size,
             batch_id: 0,
             reached_size: 0,
+            begin_time: 0,
+            align_time: 0,
         }
     }
 
@@ -397,12 +920,13 @@ impl AlignManager {
                 unreachable!()
             }
 
-            self.reached_size == self.parent_execution_size
+            self.check_align()
         } else if self.batch_id < batch_id {
             self.batch_id = batch_id;
             self.reached_size = 1;
+            self.begin_time = current_timestamp_millis();
 
-            self.reached_size == self.parent_execution_size
+            self.check_align()
         } else {
             error!(
                 "barrier delay, current {}, reached {}",
@@ -412,213 +936,3 @@ impl AlignManager {
         }
     }
 }
-
-#[derive(Debug)]
-struct ParentWatermark {
-    latest_watermark: Option<Watermark>,
-    is_dependency: bool,
-}
-
-impl ParentWatermark {
-    pub fn new(latest_watermark: Option<Watermark>, is_dependency: bool) -> Self {
-        ParentWatermark {
-            latest_watermark,
-            is_dependency,
-        }
-    }
-
-    pub fn update_watermark(&mut self, watermark: Watermark) {
-        match &self.latest_watermark {
-            Some(w) => {
-                if watermark.timestamp > MAX_WATERMARK.timestamp {
-                    // special `Watermark`, don't need to compare, just assign
-                    self.latest_watermark = Some(watermark);
-                } else if watermark.timestamp >= w.timestamp {
-                    self.latest_watermark = Some(watermark);
-                } else {
-                    error!(
-                        "low level watermark {:?} reached. current: {:?}",
-                        watermark, self.latest_watermark
-                    )
-                }
-            }
-            None => self.latest_watermark = Some(watermark),
-        }
-    }
-}
-
-#[derive(Debug, Default)]
-struct WatermarkManager {
-    /// key: parent's JobId
-    /// value: reached watermarks from parent job
-    reached_watermarks: HashMap<JobId, Vec<ParentWatermark>>,
-}
-
-impl WatermarkManager {
-    pub fn new(parent_jobs: HashMap<JobId, Vec<bool>>) -> Self {
-        let mut reached_watermarks = HashMap::new();
-        for (job_id, tasks) in parent_jobs {
-            let watermarks: Vec<ParentWatermark> = tasks
-                .into_iter()
-                .map(|x| ParentWatermark::new(None, x))
-                .collect();
-
-            reached_watermarks.insert(job_id, watermarks);
-        }
-
-        WatermarkManager { reached_watermarks }
-    }
-
-    pub fn apply(&mut self, watermark: Watermark) -> Option<Watermark> {
-        // no parent jobs
-        if self.reached_watermarks.is_empty() {
-            return Some(watermark);
-        }
-
-        let source_task_id = &watermark.channel_key.source_task_id;
-        match self.reached_watermarks.get_mut(&source_task_id.job_id) {
-            Some(watermarks) => {
-                let task_number = source_task_id.task_number as usize;
-                if task_number >= watermarks.len() {
-                    panic!(
-                        "unreached! parent job's parallelism is {}, but reached `task_number` {}",
-                        watermarks.len(),
-                        task_number
-                    );
-                }
-                if !watermarks[task_number].is_dependency {
-                    panic!("Does not depend on the task, {:?}", watermark.channel_key);
-                }
-
-                watermarks[task_number].update_watermark(watermark);
-            }
-            None => panic!("unreached! the JobId of `Watermark` is not in the parent jobs list, or the job key has removed"),
-        }
-
-        self.min_watermark()
-    }
-
-    /// find the min watermark
-    pub fn min_watermark(&self) -> Option<Watermark> {
-        let mut min_watermark: Option<&Watermark> = None;
-        for (_job_id, watermarks) in &self.reached_watermarks {
-            for p_watermark in watermarks {
-                // skip placeholder `Watermark`
-                if !p_watermark.is_dependency {
-                    continue;
-                }
-
-                match &p_watermark.latest_watermark {
-                    Some(watermark) => {
-                        if let Some(w) = min_watermark {
-                            if watermark.timestamp < w.timestamp {
-                                min_watermark = Some(watermark);
-                            }
-                        } else {
-                            min_watermark = Some(watermark);
-                        }
-                    }
-                    None => {
-                        return None;
-                    }
-                }
-            }
-        }
-
-        min_watermark.map(|w| w.clone())
-    }
-
-    pub fn watermark_job_check(&mut self, stream_status: &StreamStatus) {
-        let source_task_id = &stream_status.channel_key.source_task_id;
-        let non_watermark_job = match self.reached_watermarks.get(&source_task_id.job_id) {
-            Some(watermarks) => {
-                let task_number = source_task_id.task_number as usize;
-                if task_number >= watermarks.len() {
-                    panic!(
-                        "unreached! parent job's parallelism is {}, but reached `task_number` {}",
-                        watermarks.len(),
-                        task_number
-                    );
-                }
-                watermarks[task_number].latest_watermark.is_none()
-            }
-            None => false,
-        };
-
-        if non_watermark_job {
-            self.reached_watermarks.remove(&source_task_id.job_id);
-            info!(
-                "remove non watermark job_id={:?} from WatermarkManager",
-                source_task_id.job_id
-            );
-        }
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
-
-    use crate::core::element::{StreamStatus, Watermark};
-    use crate::core::runtime::{ChannelKey, JobId, TaskId};
-    use crate::runtime::worker::runnable::source_runnable::WatermarkManager;
-
-    fn gen_watermark(timestamp: u64, job_id: u32, task_number: u16, num_tasks: u16) -> Watermark {
-        let mut watermark = Watermark::new(timestamp);
-        watermark.channel_key = ChannelKey {
-            source_task_id: TaskId {
-                job_id: JobId(job_id),
-                task_number,
-                num_tasks,
-            },
-            target_task_id: Default::default(),
-        };
-
-        watermark
-    }
-
-    fn gen_stream_status(
-        timestamp: u64,
-        job_id: u32,
-        task_number: u16,
-        num_tasks: u16,
-    ) -> StreamStatus {
-        let mut stream_status = StreamStatus::new(timestamp, false);
-        stream_status.channel_key = ChannelKey {
-            source_task_id: TaskId {
-                job_id: JobId(job_id),
-                task_number,
-                num_tasks,
-            },
-            target_task_id: Default::default(),
-        };
-
-        stream_status
-    }
-
-    #[test]
-    pub fn watermark_manager_test() {
-        let mut parent_jobs = HashMap::new();
-        parent_jobs.insert(JobId(1), vec![true, true]);
-        parent_jobs.insert(JobId(2), vec![true, true, true]);
-
-        let mut watermark_manager = WatermarkManager::new(parent_jobs);
-
-        {
-            let watermark = gen_watermark(10, 1, 0, 2);
-            let w = watermark_manager.apply(watermark);
-            assert_eq!(w, None);
-        }
-
-        {
-            let stream_status = gen_stream_status(20, 2, 0, 3);
-            watermark_manager.watermark_job_check(&stream_status);
-        }
-
-        {
-            let watermark = gen_watermark(9, 1, 1, 2);
-            let w = watermark_manager.apply(watermark);
-            assert_eq!(w.unwrap().timestamp, 9);
-        }
-    }
-}
@@ -1,17 +1,26 @@
 use std::borrow::BorrowMut;
 
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
-use crate::core::element::Element;
+use crate::core::checkpoint::{CheckpointStats, FunctionSnapshotContext};
+use crate::core::element::{Element, Record};
+use crate::core::error::UserFunctionError;
 use crate::core::operator::DefaultStreamOperator;
-use crate::core::runtime::{OperatorId, TaskId};
+use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
 use crate::core::watermark::{
     TimestampAssigner, Watermark, WatermarkGenerator, WatermarkStrategy, MAX_WATERMARK,
     MIN_WATERMARK,
 };
 use crate::metrics::metric::{Counter, Gauge};
 use crate::metrics::{register_counter, register_gauge};
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::runtime::worker::checkpoint::snapshot_checkpoint;
+use crate::runtime::worker::heart_beat::submit_heartbeat;
+use crate::runtime::worker::runnable::{
+    notify_chained_checkpoint_complete, Runnable, RunnableContext,
+};
+use crate::runtime::HeartbeatItem;
+use crate::utils::date_time::current_timestamp_millis;
+
+/// the min interval of reporting the watermark to the coordinator by the heartbeat
+pub(crate) const WATERMARK_REPORT_INTERVAL_MS: u64 = 10 * 1000;
 
 pub(crate) struct WatermarkAssignerRunnable {
     operator_id: OperatorId,
@@ -27,7 +36,10 @@ pub(crate) struct WatermarkAssignerRunnable {
     context: Option<RunnableContext>,
 
     watermark_gauge: Gauge,
+    /// the processing time minus the watermark
+    watermark_lag_gauge: Gauge,
     expire_counter: Counter,
+    watermark_report_ts: u64,
 }
 
 impl WatermarkAssignerRunnable {
@@ -48,7 +60,9 @@ impl WatermarkAssignerRunnable {
             watermark: MIN_WATERMARK,
             context: None,
             watermark_gauge: Gauge::default(),
+            watermark_lag_gauge: Gauge::default(),
             expire_counter: Counter::default(),
+            watermark_report_ts: 0,
         }
     }
 
@@ -59,6 +73,24 @@ impl WatermarkAssignerRunnable {
 
         self.watermark = watermark;
         self.watermark_gauge.store(self.watermark.timestamp as i64);
+        if self.watermark.timestamp > MIN_WATERMARK.timestamp {
+            let lag = current_timestamp_millis().saturating_sub(self.watermark.timestamp);
+            self.watermark_lag_gauge.store(lag as i64);
+        }
+    }
+
+    /// report the watermark for the stall alerting of the coordinator
+    fn report_watermark(&mut self) {
+        let current_ts = current_timestamp_millis();
+        if current_ts < self.watermark_report_ts + WATERMARK_REPORT_INTERVAL_MS {
+            return;
+        }
+
+        self.watermark_report_ts = current_ts;
+        submit_heartbeat(HeartbeatItem::TaskWatermark {
+            task_id: self.task_id,
+            watermark: self.watermark.timestamp,
+        });
     }
 }
 
@@ -71,16 +103,25 @@ impl Runnable for WatermarkAssignerRunnable {
         self.task_id = context.task_descriptor.task_id;
 
         let fn_name = self.watermark_strategy.operator_fn.as_ref().name();
-        self.watermark_gauge =
-            register_gauge(format!("Watermark_{}", fn_name), self.task_id.to_tags());
+        self.watermark_gauge = register_gauge(
+            format!("Watermark_{}", fn_name),
+            context.operator_tags(self.operator_id),
+        );
+
+        self.watermark_lag_gauge = register_gauge(
+            format!("WatermarkLag_{}", fn_name),
+            context.operator_tags(self.operator_id),
+        );
 
         self.expire_counter = register_counter(
             format!("Watermark_Expire_{}", fn_name),
-            self.task_id.to_tags(),
+            context.operator_tags(self.operator_id),
         );
 
         let fun_context = context.to_fun_context(self.operator_id);
-        self.timestamp_assigner.open(&fun_context)?;
+        self.timestamp_assigner
+            .open(&fun_context)
+            .map_err(|e| e.or_classify(UserFunctionError::Open))?;
 
         Ok(())
     }
@@ -88,6 +129,11 @@ impl Runnable for WatermarkAssignerRunnable {
     fn run(&mut self, mut element: Element) {
         match element.borrow_mut() {
             Element::Record(record) => {
+                if record.is_dropped() {
+                    self.next_runnable.as_mut().unwrap().run(element);
+                    return;
+                }
+
                 let timestamp = self.timestamp_assigner.extract_timestamp(record, 0);
                 record.timestamp = timestamp;
 
@@ -104,6 +150,10 @@ impl Runnable for WatermarkAssignerRunnable {
                             record.timestamp, self.watermark.timestamp
                         );
                     }
+                    if let Some(sequence) = record.sequence {
+                        let dropped = Element::Record(Record::dropped(sequence));
+                        self.next_runnable.as_mut().unwrap().run(dropped);
+                    }
                     return;
                 }
 
@@ -126,6 +176,7 @@ impl Runnable for WatermarkAssignerRunnable {
                         .on_periodic_emit()
                         .unwrap_or(MIN_WATERMARK.clone());
                     self.update_watermark_progress(watermark);
+                    self.report_watermark();
 
                     let watermark_ele = Element::new_watermark(self.watermark.timestamp);
                     self.next_runnable.as_mut().unwrap().run(watermark_ele);
@@ -159,25 +210,19 @@ impl Runnable for WatermarkAssignerRunnable {
         self.next_runnable = next_runnable;
     }
 
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        notify_chained_checkpoint_complete(
+            self.watermark_strategy.operator_fn.as_mut(),
+            self.next_runnable.as_mut(),
+            checkpoint_id,
+        );
+    }
+
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
-            .watermark_strategy
-            .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
-            completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
-            handle,
-        };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
-        });
+        snapshot_checkpoint(
+            self.watermark_strategy.operator_fn.as_mut(),
+            &snapshot_context,
+            CheckpointStats::default(),
+        );
     }
 }
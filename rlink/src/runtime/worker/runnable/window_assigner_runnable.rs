@@ -1,12 +1,14 @@
 use std::borrow::BorrowMut;
 
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::checkpoint::{CheckpointStats, FunctionSnapshotContext};
 use crate::core::element::Element;
 use crate::core::operator::DefaultStreamOperator;
-use crate::core::runtime::OperatorId;
+use crate::core::runtime::{CheckpointId, OperatorId};
 use crate::core::window::{WindowAssigner, WindowAssignerContext};
-use crate::runtime::worker::checkpoint::submit_checkpoint;
-use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::runtime::worker::checkpoint::snapshot_checkpoint;
+use crate::runtime::worker::runnable::{
+    notify_chained_checkpoint_complete, Runnable, RunnableContext,
+};
 
 pub(crate) struct WindowAssignerRunnable {
     operator_id: OperatorId,
@@ -49,6 +51,11 @@ impl Runnable for WindowAssignerRunnable {
     fn run(&mut self, mut element: Element) {
         match element.borrow_mut() {
             Element::Record(record) => {
+                if record.is_dropped() {
+                    self.next_runnable.as_mut().unwrap().run(element);
+                    return;
+                }
+
                 let windows = self
                     .stream_window
                     .operator_fn
@@ -91,25 +98,19 @@ impl Runnable for WindowAssignerRunnable {
         self.next_runnable = next_runnable;
     }
 
+    fn notify_checkpoint_complete(&mut self, checkpoint_id: CheckpointId) {
+        notify_chained_checkpoint_complete(
+            self.stream_window.operator_fn.as_mut(),
+            self.next_runnable.as_mut(),
+            checkpoint_id,
+        );
+    }
+
     fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
-        let handle = self
-            .stream_window
-            .operator_fn
-            .snapshot_state(&snapshot_context)
-            .unwrap_or(CheckpointHandle::default());
-
-        let ck = Checkpoint {
-            operator_id: snapshot_context.operator_id,
-            task_id: snapshot_context.task_id,
-            checkpoint_id: snapshot_context.checkpoint_id,
-            completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
-            handle,
-        };
-        submit_checkpoint(ck).map(|ck| {
-            error!(
-                "{:?} submit checkpoint error. maybe report channel is full, checkpoint: {:?}",
-                snapshot_context.operator_id, ck
-            )
-        });
+        snapshot_checkpoint(
+            self.stream_window.operator_fn.as_mut(),
+            &snapshot_context,
+            CheckpointStats::default(),
+        );
     }
 }
@@ -0,0 +1,28 @@
+//! Request the next split of the bounded source from the coordinator,
+//! see `SystemProperties::set_split_reassignment`
+
+use crate::core::cluster::{ResponseCode, StdResponse};
+use crate::runtime::{SplitRequest, SplitResponse};
+use crate::utils::http::client::post_sync;
+
+/// `Ok(None)` if the coordinator is unreachable, the request should be sent again later.
+/// Error if the coordinator refused it, eg: a split failed too many times
+pub(crate) fn next_split(
+    coordinator_address: &str,
+    request: &SplitRequest,
+) -> anyhow::Result<Option<SplitResponse>> {
+    let url = format!("{}/api/splits/next", coordinator_address);
+    let body = serde_json::to_string(request)?;
+
+    match post_sync::<StdResponse<SplitResponse>>(url, body) {
+        Ok(StdResponse {
+            code: ResponseCode::OK,
+            data: Some(response),
+        }) => Ok(Some(response)),
+        Ok(StdResponse { code, .. }) => Err(anyhow!("request the next split error. {:?}", code)),
+        Err(e) => {
+            warn!("request the next split error, retry later. {}", e);
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+
+use crate::core::error::{ErrorReport, UserFunctionError};
+use crate::core::properties::SystemProperties;
+use crate::core::runtime::{ClusterDescriptor, OperatorId, TaskId};
+use crate::dag::metadata::DagMetadata;
+use crate::runtime::worker::heart_beat::{submit_heartbeat, HEARTBEAT_INTERVAL};
+use crate::runtime::HeartbeatItem;
+use crate::utils::date_time::current_timestamp_millis;
+use crate::utils::panic::mark_panic;
+use crate::utils::thread::stack_dump;
+
+/// the interval of checking the progress of the tasks
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+/// the max time of waiting the stuck thread to dump its stack
+const STACK_DUMP_TIMEOUT: Duration = Duration::from_secs(3);
+
+lazy_static! {
+    static ref TASK_PROGRESSES: Mutex<Vec<Arc<TaskProgress>>> = Mutex::new(Vec::new());
+}
+
+static WATCHDOG_START: Once = Once::new();
+
+thread_local! {
+    static CURRENT_PROGRESS: RefCell<Option<Arc<TaskProgress>>> = const { RefCell::new(None) };
+}
+
+/// The progress of a task, the element loop marks the user function being called with
+/// the record, so the watchdog can find the task which makes no progress
+pub(crate) struct TaskProgress {
+    task_id: TaskId,
+    thread_name: String,
+    /// the `thread_id::get()` of the task thread, to dump its stack
+    pthread: usize,
+    /// the processing timeout of the operators, the operators without timeout are not watched
+    timeouts: HashMap<OperatorId, Duration>,
+
+    operator_id: AtomicU32,
+    /// the timestamp of calling the user function of `operator_id`, `0` if not in any
+    busy_since: AtomicU64,
+    stuck: AtomicBool,
+}
+
+impl TaskProgress {
+    fn new(task_id: TaskId, timeouts: HashMap<OperatorId, Duration>) -> Self {
+        TaskProgress {
+            task_id,
+            thread_name: std::thread::current().name().unwrap_or("").to_string(),
+            pthread: thread_id::get(),
+            timeouts,
+            operator_id: AtomicU32::new(0),
+            busy_since: AtomicU64::new(0),
+            stuck: AtomicBool::new(false),
+        }
+    }
+
+    /// the operator and its elapsed millis if the task is stuck in it at `now`
+    fn stuck_operator(&self, now: u64) -> Option<(OperatorId, u64)> {
+        let busy_since = self.busy_since.load(Ordering::SeqCst);
+        if busy_since == 0 {
+            return None;
+        }
+
+        let operator_id = OperatorId(self.operator_id.load(Ordering::SeqCst));
+        let timeout = self.timeouts.get(&operator_id)?;
+        let elapsed = now.saturating_sub(busy_since);
+        if elapsed >= timeout.as_millis() as u64 {
+            Some((operator_id, elapsed))
+        } else {
+            None
+        }
+    }
+}
+
+/// Mark the task thread is in the user function of an operator until it's dropped,
+/// the previous mark is restored for the chained calls
+pub(crate) struct ProgressGuard {
+    previous: Option<(u32, u64)>,
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        if let Some((operator_id, busy_since)) = self.previous {
+            CURRENT_PROGRESS.with(|progress| {
+                if let Some(progress) = progress.borrow().as_ref() {
+                    progress.operator_id.store(operator_id, Ordering::SeqCst);
+                    progress.busy_since.store(busy_since, Ordering::SeqCst);
+                }
+            });
+        }
+    }
+}
+
+/// the heartbeat of the element loop before calling the user function of the `operator_id`,
+/// it's a no-op if the task is not watched
+pub(crate) fn enter_operator(operator_id: OperatorId) -> ProgressGuard {
+    let previous = CURRENT_PROGRESS.with(|progress| {
+        progress.borrow().as_ref().map(|progress| {
+            let previous = (
+                progress.operator_id.swap(operator_id.0, Ordering::SeqCst),
+                progress.busy_since.load(Ordering::SeqCst),
+            );
+            progress
+                .busy_since
+                .store(current_timestamp_millis(), Ordering::SeqCst);
+            previous
+        })
+    });
+    ProgressGuard { previous }
+}
+
+/// the processing timeout of the operators of the task's job,
+/// the operator's `processing_timeout` or the application default
+pub(crate) fn processing_timeouts(
+    dag_metadata: &DagMetadata,
+    cluster_descriptor: &ClusterDescriptor,
+    task_id: &TaskId,
+) -> HashMap<OperatorId, Duration> {
+    let default_timeout = cluster_descriptor
+        .coordinator_manager
+        .application_properties
+        .get_processing_timeout()
+        .ok();
+    dag_metadata
+        .job_node(task_id.job_id)
+        .map(|job_node| {
+            job_node
+                .stream_nodes
+                .iter()
+                .filter_map(|stream_node| {
+                    stream_node
+                        .processing_timeout
+                        .or(default_timeout)
+                        .map(|timeout| (stream_node.id, timeout))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// watch the task running in the current thread, the task is not watched if no operator
+/// has the processing timeout
+pub(crate) fn watch(
+    task_id: TaskId,
+    timeouts: HashMap<OperatorId, Duration>,
+    dag_metadata: Arc<DagMetadata>,
+) {
+    if timeouts.is_empty() {
+        return;
+    }
+
+    let progress = Arc::new(TaskProgress::new(task_id, timeouts));
+    CURRENT_PROGRESS.with(|current| *current.borrow_mut() = Some(progress.clone()));
+    TASK_PROGRESSES.lock().unwrap().push(progress);
+
+    WATCHDOG_START.call_once(move || {
+        crate::utils::thread::spawn("watchdog", move || loop {
+            std::thread::sleep(WATCHDOG_INTERVAL);
+            check_progresses(dag_metadata.as_ref());
+        });
+    });
+}
+
+/// stop watching the task running in the current thread
+pub(crate) fn unwatch() {
+    if let Some(progress) = CURRENT_PROGRESS.with(|current| current.borrow_mut().take()) {
+        TASK_PROGRESSES
+            .lock()
+            .unwrap()
+            .retain(|x| !Arc::ptr_eq(x, &progress));
+    }
+}
+
+fn check_progresses(dag_metadata: &DagMetadata) {
+    let now = current_timestamp_millis();
+    let stuck_tasks: Vec<(Arc<TaskProgress>, OperatorId, u64)> = TASK_PROGRESSES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|progress| !progress.stuck.load(Ordering::SeqCst))
+        .filter_map(|progress| {
+            progress
+                .stuck_operator(now)
+                .map(|(operator_id, elapsed)| (progress.clone(), operator_id, elapsed))
+        })
+        .collect();
+    if stuck_tasks.is_empty() {
+        return;
+    }
+
+    for (progress, operator_id, elapsed) in stuck_tasks {
+        progress.stuck.store(true, Ordering::SeqCst);
+
+        let operator_name = dag_metadata
+            .stream_node(operator_id)
+            .map(|stream_node| stream_node.display_name().to_string())
+            .unwrap_or_default();
+        let dump = stack_dump(progress.pthread, STACK_DUMP_TIMEOUT)
+            .unwrap_or_else(|| "<stack dump is not available>".to_string());
+        error!(
+            "task {:?} is stuck in operator `{}`({}) for {}ms, thread `{}`(0x{:x}), stack:\n{}",
+            progress.task_id,
+            operator_name,
+            operator_id.0,
+            elapsed,
+            progress.thread_name,
+            progress.pthread,
+            dump
+        );
+
+        let error = UserFunctionError::Timeout(format!(
+            "operator `{}`({}) makes no progress for {}ms",
+            operator_name, operator_id.0, elapsed
+        ));
+        submit_heartbeat(HeartbeatItem::TaskFailure {
+            task_id: progress.task_id,
+            error: ErrorReport::from(&anyhow::Error::from(error)),
+        });
+    }
+
+    // the stuck thread can't be unwound, so the worker is failed over by the coordinator
+    mark_panic();
+    std::thread::sleep(HEARTBEAT_INTERVAL);
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use crate::core::runtime::{JobId, OperatorId, TaskId};
+    use crate::runtime::worker::watchdog::{
+        enter_operator, unwatch, TaskProgress, CURRENT_PROGRESS,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    pub fn task_progress_test() {
+        let task_id = TaskId {
+            job_id: JobId(0),
+            task_number: 0,
+            num_tasks: 1,
+        };
+        let mut timeouts = HashMap::new();
+        timeouts.insert(OperatorId(2), Duration::from_millis(100));
+        let progress = Arc::new(TaskProgress::new(task_id, timeouts));
+        CURRENT_PROGRESS.with(|current| *current.borrow_mut() = Some(progress.clone()));
+
+        {
+            let _guard = enter_operator(OperatorId(2));
+            let busy_since = progress.busy_since.load(Ordering::SeqCst);
+            assert!(progress.stuck_operator(busy_since + 99).is_none());
+            assert_eq!(
+                progress.stuck_operator(busy_since + 100),
+                Some((OperatorId(2), 100))
+            );
+
+            // the operator without timeout is not watched, the mark is restored after it
+            {
+                let _guard = enter_operator(OperatorId(3));
+                assert!(progress.stuck_operator(busy_since + 1000).is_none());
+            }
+            assert_eq!(progress.operator_id.load(Ordering::SeqCst), 2);
+        }
+        assert_eq!(progress.busy_since.load(Ordering::SeqCst), 0);
+
+        unwatch();
+    }
+}
@@ -8,6 +8,7 @@ use hyper::http::header;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response};
 use hyper::{Server, StatusCode};
+use percent_encoding::percent_decode_str;
 use rand::Rng;
 
 use crate::channel::{bounded, Sender};
@@ -100,6 +101,9 @@ async fn route(req: Request<Body>, web_context: Arc<WebContext>) -> anyhow::Resu
                 "/api/client/log/disable" => disable_client_log(req, web_context).await,
                 "/api/server/log/enable" => enable_server_log(req, web_context).await,
                 "/api/server/log/disable" => disable_server_log(req, web_context).await,
+                "/api/taps" => get_taps(req, web_context).await,
+                "/api/state/keyed" => get_keyed_state(req, web_context).await,
+                _ if path.starts_with("/api/taps/") => get_tap_elements(req, web_context).await,
                 _ => page_not_found().await,
             }
         } else {
@@ -154,6 +158,37 @@ async fn get_thread_infos(
     as_ok_json(&StdResponse::ok(Some(c)))
 }
 
+async fn get_taps(
+    _req: Request<Body>,
+    _context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let taps = crate::functions::flat_map::tap_flat_map::get_tap_summaries();
+    as_ok_json(&StdResponse::ok(Some(taps)))
+}
+
+async fn get_keyed_state(
+    _req: Request<Body>,
+    _context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let summaries = crate::metrics::keyed_state::get_keyed_state_summaries();
+    as_ok_json(&StdResponse::ok(Some(summaries)))
+}
+
+async fn get_tap_elements(
+    req: Request<Body>,
+    _context: Arc<WebContext>,
+) -> anyhow::Result<Response<Body>> {
+    let name = tap_name(req.uri().path())?;
+    let elements = crate::functions::flat_map::tap_flat_map::get_tap_elements(name.as_str());
+    as_ok_json(&StdResponse::ok(Some(elements)))
+}
+
+/// the percent-decoded tap name of the path, eg: `/api/taps/clean%20records`
+fn tap_name(path: &str) -> anyhow::Result<String> {
+    let name = percent_decode_str(&path["/api/taps/".len()..]).decode_utf8()?;
+    Ok(name.into_owned())
+}
+
 async fn static_file(
     req: Request<Body>,
     context: Arc<WebContext>,
@@ -209,3 +244,18 @@ async fn static_file(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::worker::web_server::tap_name;
+
+    #[test]
+    pub fn tap_name_test() {
+        assert_eq!(tap_name("/api/taps/clean").unwrap(), "clean");
+        assert_eq!(
+            tap_name("/api/taps/clean%20records").unwrap(),
+            "clean records"
+        );
+        assert!(tap_name("/api/taps/%FF").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::core::checkpoint::Checkpoint;
+use crate::core::encryption::{open, random_bytes, seal, KeyProvider, KEY_LEN};
+use crate::core::runtime::CheckpointId;
+use crate::storage::checkpoint::{CheckpointStorage, TCheckpointStorage};
+
+/// the prefix of the encrypted handle, the handles without it are loaded as plaintext,
+/// so the checkpoints saved before the encryption enabled can still be restored
+pub const ENCRYPTED_HANDLE_PREFIX: &str = "enc:aes-256-gcm:";
+
+/// The envelope of an encrypted handle
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Envelope {
+    /// the id of the master key
+    key_id: String,
+    /// base64 of the data key wrapped by the master key
+    wrapped_key: String,
+    /// base64 of the handle sealed by the data key
+    payload: String,
+}
+
+/// Envelope encryption of the `CheckpointHandle`s at rest. A random data key is generated for
+/// each saved checkpoint, every handle is sealed by AES-256-GCM with the data key, and the data
+/// key is wrapped by the `KeyProvider`. The handles are decrypted transparently on load.
+pub struct EncryptedCheckpointStorage {
+    storage: CheckpointStorage,
+    key_provider: Arc<dyn KeyProvider>,
+}
+
+impl EncryptedCheckpointStorage {
+    pub fn new(storage: CheckpointStorage, key_provider: Arc<dyn KeyProvider>) -> Self {
+        EncryptedCheckpointStorage {
+            storage,
+            key_provider,
+        }
+    }
+
+    fn encrypt(&self, checkpoints: &mut [Checkpoint]) -> anyhow::Result<()> {
+        let data_key = random_bytes(KEY_LEN)?;
+        let (key_id, wrapped_key) = self.key_provider.wrap_key(data_key.as_slice())?;
+        let wrapped_key = base64::encode(wrapped_key);
+
+        for checkpoint in checkpoints {
+            let aad = associated_data(checkpoint)?;
+            let payload = seal(
+                data_key.as_slice(),
+                checkpoint.handle.handle.as_bytes(),
+                aad.as_slice(),
+            )?;
+            let envelope = Envelope {
+                key_id: key_id.clone(),
+                wrapped_key: wrapped_key.clone(),
+                payload: base64::encode(payload),
+            };
+            checkpoint.handle.handle = format!(
+                "{}{}",
+                ENCRYPTED_HANDLE_PREFIX,
+                serde_json::to_string(&envelope)?
+            );
+        }
+
+        Ok(())
+    }
+
+    fn decrypt(&self, checkpoints: &mut [Checkpoint]) -> anyhow::Result<()> {
+        // the handles of a checkpoint share the data key, unwrap it once
+        let mut data_keys: HashMap<(String, String), Vec<u8>> = HashMap::new();
+        for checkpoint in checkpoints {
+            let envelope = match checkpoint
+                .handle
+                .handle
+                .strip_prefix(ENCRYPTED_HANDLE_PREFIX)
+            {
+                Some(envelope) => serde_json::from_str::<Envelope>(envelope)?,
+                None => continue,
+            };
+
+            let cache_key = (envelope.key_id, envelope.wrapped_key);
+            if !data_keys.contains_key(&cache_key) {
+                let wrapped_key = base64::decode(cache_key.1.as_str())?;
+                let data_key = self
+                    .key_provider
+                    .unwrap_key(cache_key.0.as_str(), wrapped_key.as_slice())?;
+                data_keys.insert(cache_key.clone(), data_key);
+            }
+            let data_key = data_keys.get(&cache_key).unwrap();
+
+            let payload = base64::decode(envelope.payload.as_str())?;
+            let aad = associated_data(checkpoint)?;
+            let handle =
+                open(data_key.as_slice(), payload.as_slice(), aad.as_slice()).map_err(|e| {
+                    anyhow!(
+                        "decrypt the checkpoint handle of operator {:?} task {:?} error. {}",
+                        checkpoint.operator_id,
+                        checkpoint.task_id,
+                        e
+                    )
+                })?;
+            checkpoint.handle.handle = String::from_utf8(handle)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The handle is bound to its checkpoint, so a handle moved to another operator, task or
+/// checkpoint fails to decrypt
+fn associated_data(checkpoint: &Checkpoint) -> anyhow::Result<Vec<u8>> {
+    let aad = serde_json::to_vec(&(
+        &checkpoint.operator_id,
+        &checkpoint.task_id,
+        &checkpoint.checkpoint_id,
+    ))?;
+    Ok(aad)
+}
+
+impl TCheckpointStorage for EncryptedCheckpointStorage {
+    fn save(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_id: CheckpointId,
+        mut finish_cks: Vec<Checkpoint>,
+        ttl: u64,
+    ) -> anyhow::Result<()> {
+        self.encrypt(finish_cks.as_mut_slice())?;
+        self.storage.save(
+            application_name,
+            application_id,
+            checkpoint_id,
+            finish_cks,
+            ttl,
+        )
+    }
+
+    fn load(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let mut checkpoints = self.storage.load(application_name, application_id)?;
+        self.decrypt(checkpoints.as_mut_slice())?;
+        Ok(checkpoints)
+    }
+
+    fn load_by_checkpoint_id(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_id: CheckpointId,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let mut checkpoints =
+            self.storage
+                .load_by_checkpoint_id(application_name, application_id, checkpoint_id)?;
+        self.decrypt(checkpoints.as_mut_slice())?;
+        Ok(checkpoints)
+    }
+
+    fn load_all(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let mut checkpoints = self.storage.load_all(application_name, application_id)?;
+        self.decrypt(checkpoints.as_mut_slice())?;
+        Ok(checkpoints)
+    }
+
+    fn delete(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_ids: &[CheckpointId],
+    ) -> anyhow::Result<()> {
+        self.storage
+            .delete(application_name, application_id, checkpoint_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::core::checkpoint::{Checkpoint, CheckpointHandle};
+    use crate::core::encryption::{EnvKeyProvider, KeyProvider};
+    use crate::core::runtime::{CheckpointId, OperatorId};
+    use crate::storage::checkpoint::encrypted_checkpoint_storage::{
+        EncryptedCheckpointStorage, ENCRYPTED_HANDLE_PREFIX,
+    };
+    use crate::storage::checkpoint::memory_checkpoint_storage::MemoryCheckpointStorage;
+    use crate::storage::checkpoint::CheckpointStorage;
+
+    #[test]
+    pub fn encrypted_checkpoint_storage_test() {
+        std::env::set_var(
+            "RLINK_TEST_CHECKPOINT_KEY",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        );
+        let key_provider: Arc<dyn KeyProvider> =
+            Arc::new(EnvKeyProvider::new("RLINK_TEST_CHECKPOINT_KEY"));
+        let storage = EncryptedCheckpointStorage::new(
+            CheckpointStorage::MemoryCheckpointStorage(MemoryCheckpointStorage::new()),
+            key_provider.clone(),
+        );
+
+        let checkpoint = |handle: &str| Checkpoint {
+            operator_id: Default::default(),
+            task_id: Default::default(),
+            checkpoint_id: Default::default(),
+            completed_checkpoint_id: None,
+            handle: CheckpointHandle {
+                handle: handle.to_string(),
+            },
+            stats: Default::default(),
+            declined: None,
+            savepoint: false,
+        };
+
+        let mut checkpoints = vec![checkpoint("offset=100"), checkpoint("offset=200")];
+        storage.encrypt(checkpoints.as_mut_slice()).unwrap();
+        assert!(checkpoints[0]
+            .handle
+            .handle
+            .starts_with(ENCRYPTED_HANDLE_PREFIX));
+        assert!(!checkpoints[0].handle.handle.contains("offset"));
+
+        // the plaintext handle saved before the encryption enabled
+        checkpoints.push(checkpoint("offset=300"));
+        storage.decrypt(checkpoints.as_mut_slice()).unwrap();
+        let handles: Vec<&str> = checkpoints
+            .iter()
+            .map(|x| x.handle.handle.as_str())
+            .collect();
+        assert_eq!(handles, vec!["offset=100", "offset=200", "offset=300"]);
+
+        // tampered
+        let mut checkpoints = vec![checkpoint("offset=100")];
+        storage.encrypt(checkpoints.as_mut_slice()).unwrap();
+        let handle = checkpoints[0].handle.handle.clone();
+        let tampered = handle.replacen("\"payload\":\"", "\"payload\":\"AAAA", 1);
+        checkpoints[0].handle.handle = tampered;
+        assert!(storage.decrypt(checkpoints.as_mut_slice()).is_err());
+
+        // swapped between the operators
+        let mut checkpoints = vec![checkpoint("offset=100"), checkpoint("offset=200")];
+        checkpoints[1].operator_id = OperatorId(1);
+        storage.encrypt(checkpoints.as_mut_slice()).unwrap();
+        let handle = checkpoints[0].handle.handle.clone();
+        checkpoints[0].handle.handle = checkpoints[1].handle.handle.clone();
+        checkpoints[1].handle.handle = handle;
+        assert!(storage.decrypt(checkpoints.as_mut_slice()).is_err());
+
+        // moved to another checkpoint
+        let mut checkpoints = vec![checkpoint("offset=100")];
+        storage.encrypt(checkpoints.as_mut_slice()).unwrap();
+        checkpoints[0].checkpoint_id = CheckpointId(1);
+        assert!(storage.decrypt(checkpoints.as_mut_slice()).is_err());
+    }
+}
@@ -75,4 +75,30 @@ impl TCheckpointStorage for MemoryCheckpointStorage {
     ) -> anyhow::Result<Vec<Checkpoint>> {
         Ok(vec![])
     }
+
+    fn load_all(
+        &mut self,
+        _application_name: &str,
+        _application_id: &str,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let mut checkpoint_ids: Vec<&CheckpointId> = self.history_cks.keys().collect();
+        checkpoint_ids.sort_by_key(|x| x.0);
+        let checkpoints = checkpoint_ids
+            .into_iter()
+            .flat_map(|ck_id| self.history_cks[ck_id].clone())
+            .collect();
+        Ok(checkpoints)
+    }
+
+    fn delete(
+        &mut self,
+        _application_name: &str,
+        _application_id: &str,
+        checkpoint_ids: &[CheckpointId],
+    ) -> anyhow::Result<()> {
+        for checkpoint_id in checkpoint_ids {
+            self.history_cks.remove(checkpoint_id);
+        }
+        Ok(())
+    }
 }
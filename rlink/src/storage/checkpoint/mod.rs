@@ -1,11 +1,18 @@
+use std::sync::Arc;
+
 use crate::core::backend::CheckpointBackend;
 use crate::core::checkpoint::Checkpoint;
+use crate::core::encryption::KeyProvider;
 use crate::core::runtime::CheckpointId;
+use crate::storage::checkpoint::encrypted_checkpoint_storage::EncryptedCheckpointStorage;
 use crate::storage::checkpoint::memory_checkpoint_storage::MemoryCheckpointStorage;
 use crate::storage::checkpoint::mysql_checkpoint_storage::MySqlCheckpointStorage;
+use crate::storage::checkpoint::object_store_checkpoint_storage::ObjectStoreCheckpointStorage;
 
+pub mod encrypted_checkpoint_storage;
 pub mod memory_checkpoint_storage;
 pub mod mysql_checkpoint_storage;
+pub mod object_store_checkpoint_storage;
 
 pub trait TCheckpointStorage {
     fn save(
@@ -29,11 +36,29 @@ pub trait TCheckpointStorage {
         application_id: &str,
         checkpoint_id: CheckpointId,
     ) -> anyhow::Result<Vec<Checkpoint>>;
+
+    /// load all `Checkpoint`s of the application ordered by the `checkpoint_id`,
+    /// used by the `CheckpointRetention` to find out the checkpoints completed before a restart
+    fn load_all(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+    ) -> anyhow::Result<Vec<Checkpoint>>;
+
+    /// delete all `Checkpoint`s belonging to the `checkpoint_ids`
+    fn delete(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_ids: &[CheckpointId],
+    ) -> anyhow::Result<()>;
 }
 
 pub enum CheckpointStorage {
     MemoryCheckpointStorage(MemoryCheckpointStorage),
     MySqlCheckpointStorage(MySqlCheckpointStorage),
+    ObjectStoreCheckpointStorage(ObjectStoreCheckpointStorage),
+    Encrypted(Box<EncryptedCheckpointStorage>),
 }
 
 impl CheckpointStorage {
@@ -48,6 +73,21 @@ impl CheckpointStorage {
                     table.clone(),
                 ))
             }
+            CheckpointBackend::ObjectStore { uri } => {
+                CheckpointStorage::ObjectStoreCheckpointStorage(ObjectStoreCheckpointStorage::new(
+                    uri.clone(),
+                ))
+            }
+        }
+    }
+
+    /// encrypt the checkpoint handles at rest if the `key_provider` is set
+    pub fn with_key_provider(self, key_provider: Option<Arc<dyn KeyProvider>>) -> Self {
+        match key_provider {
+            Some(key_provider) => CheckpointStorage::Encrypted(Box::new(
+                EncryptedCheckpointStorage::new(self, key_provider),
+            )),
+            None => self,
         }
     }
 }
@@ -76,6 +116,20 @@ impl TCheckpointStorage for CheckpointStorage {
                 finish_cks,
                 ttl,
             ),
+            CheckpointStorage::ObjectStoreCheckpointStorage(storage) => storage.save(
+                application_name,
+                application_id,
+                checkpoint_id,
+                finish_cks,
+                ttl,
+            ),
+            CheckpointStorage::Encrypted(storage) => storage.save(
+                application_name,
+                application_id,
+                checkpoint_id,
+                finish_cks,
+                ttl,
+            ),
         }
     }
 
@@ -91,6 +145,10 @@ impl TCheckpointStorage for CheckpointStorage {
             CheckpointStorage::MySqlCheckpointStorage(storage) => {
                 storage.load(application_name, application_id)
             }
+            CheckpointStorage::ObjectStoreCheckpointStorage(storage) => {
+                storage.load(application_name, application_id)
+            }
+            CheckpointStorage::Encrypted(storage) => storage.load(application_name, application_id),
         }
     }
 
@@ -107,6 +165,55 @@ impl TCheckpointStorage for CheckpointStorage {
             CheckpointStorage::MySqlCheckpointStorage(storage) => {
                 storage.load_by_checkpoint_id(application_name, application_id, checkpoint_id)
             }
+            CheckpointStorage::ObjectStoreCheckpointStorage(storage) => {
+                storage.load_by_checkpoint_id(application_name, application_id, checkpoint_id)
+            }
+            CheckpointStorage::Encrypted(storage) => {
+                storage.load_by_checkpoint_id(application_name, application_id, checkpoint_id)
+            }
+        }
+    }
+
+    fn load_all(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        match self {
+            CheckpointStorage::MemoryCheckpointStorage(storage) => {
+                storage.load_all(application_name, application_id)
+            }
+            CheckpointStorage::MySqlCheckpointStorage(storage) => {
+                storage.load_all(application_name, application_id)
+            }
+            CheckpointStorage::ObjectStoreCheckpointStorage(storage) => {
+                storage.load_all(application_name, application_id)
+            }
+            CheckpointStorage::Encrypted(storage) => {
+                storage.load_all(application_name, application_id)
+            }
+        }
+    }
+
+    fn delete(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_ids: &[CheckpointId],
+    ) -> anyhow::Result<()> {
+        match self {
+            CheckpointStorage::MemoryCheckpointStorage(storage) => {
+                storage.delete(application_name, application_id, checkpoint_ids)
+            }
+            CheckpointStorage::MySqlCheckpointStorage(storage) => {
+                storage.delete(application_name, application_id, checkpoint_ids)
+            }
+            CheckpointStorage::ObjectStoreCheckpointStorage(storage) => {
+                storage.delete(application_name, application_id, checkpoint_ids)
+            }
+            CheckpointStorage::Encrypted(storage) => {
+                storage.delete(application_name, application_id, checkpoint_ids)
+            }
         }
     }
 }
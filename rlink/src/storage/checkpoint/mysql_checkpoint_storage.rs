@@ -1,13 +1,48 @@
 use mysql::prelude::*;
 use mysql::*;
 
-use crate::core::checkpoint::{Checkpoint, CheckpointHandle};
+use crate::core::checkpoint::{Checkpoint, CheckpointHandle, CheckpointStats};
 use crate::core::runtime::{CheckpointId, JobId, OperatorId, TaskId};
 use crate::storage::checkpoint::TCheckpointStorage;
 use crate::utils::date_time::{current_timestamp, fmt_date_time};
 
 const DEFAULT_TABLE_NAME: &'static str = "rlink_ck";
 
+type CheckpointRow = (u32, u16, u16, u32, u64, u64, String, bool);
+
+fn to_checkpoint(row: CheckpointRow) -> Checkpoint {
+    let (
+        job_id,
+        task_number,
+        num_tasks,
+        operator_id,
+        checkpoint_id,
+        completed_checkpoint_id,
+        handle,
+        savepoint,
+    ) = row;
+    let completed_checkpoint_id = if completed_checkpoint_id == 0 {
+        None
+    } else {
+        Some(CheckpointId(completed_checkpoint_id))
+    };
+
+    Checkpoint {
+        operator_id: OperatorId(operator_id),
+        task_id: TaskId {
+            job_id: JobId(job_id),
+            task_number,
+            num_tasks,
+        },
+        checkpoint_id: CheckpointId(checkpoint_id),
+        completed_checkpoint_id,
+        handle: CheckpointHandle { handle },
+        stats: CheckpointStats::default(),
+        declined: None,
+        savepoint,
+    }
+}
+
 pub struct MySqlCheckpointStorage {
     url: String,
     table: String,
@@ -37,9 +72,9 @@ impl TCheckpointStorage for MySqlCheckpointStorage {
         conn.exec_batch(
             r"
 insert into rlink_ck 
-  (application_name, application_id, job_id, task_number, num_tasks, operator_id, checkpoint_id, completed_checkpoint_id, handle, create_time)
+  (application_name, application_id, job_id, task_number, num_tasks, operator_id, checkpoint_id, completed_checkpoint_id, handle, savepoint, create_time)
 values 
-  (:application_name, :application_id, :job_id, :task_number, :num_tasks, :operator_id, :checkpoint_id, :completed_checkpoint_id, :handle, :create_time)"
+  (:application_name, :application_id, :job_id, :task_number, :num_tasks, :operator_id, :checkpoint_id, :completed_checkpoint_id, :handle, :savepoint, :create_time)"
                 .replace("rlink_ck", self.table.as_str()),
             finish_cks.iter().map(|p| {
                 let completed_checkpoint_id = p.completed_checkpoint_id.unwrap_or_default();
@@ -53,6 +88,7 @@ values
                     "checkpoint_id" => checkpoint_id.0,
                     "completed_checkpoint_id" => completed_checkpoint_id.0,
                     "handle" => &p.handle.handle,
+                    "savepoint" => p.savepoint,
                     "create_time" => fmt_date_time(current_timestamp(), "%Y-%m-%d %T"),
                 }
             }),
@@ -97,7 +133,7 @@ where application_name = :application_name
         let stmt = conn.prep(
             r"
 SELECT  ck.job_id, ck.task_number, ck.num_tasks, ck.operator_id, 
-        ck.checkpoint_id, ck.completed_checkpoint_id, ck.handle
+        ck.checkpoint_id, ck.completed_checkpoint_id, ck.handle, ck.savepoint
 from rlink_ck as ck
         inner join (
     SELECT max(checkpoint_id) as checkpoint_id
@@ -116,33 +152,7 @@ and ck.application_id = :application_id"
             "application_name" => application_name,
             "application_id" => application_id,
              },
-            |(
-                job_id,
-                task_number,
-                num_tasks,
-                operator_id,
-                checkpoint_id,
-                completed_checkpoint_id,
-                handle,
-            )| {
-                let completed_checkpoint_id = if completed_checkpoint_id == 0 {
-                    None
-                } else {
-                    Some(CheckpointId(completed_checkpoint_id))
-                };
-
-                Checkpoint {
-                    operator_id: OperatorId(operator_id),
-                    task_id: TaskId {
-                        job_id: JobId(job_id),
-                        task_number,
-                        num_tasks,
-                    },
-                    checkpoint_id: CheckpointId(checkpoint_id),
-                    completed_checkpoint_id,
-                    handle: CheckpointHandle { handle },
-                }
-            },
+            to_checkpoint,
         )?;
 
         info!("checkpoint load success");
@@ -162,7 +172,7 @@ and ck.application_id = :application_id"
         let stmt = conn.prep(
             r"
 SELECT  ck.job_id, ck.task_number, ck.num_tasks, ck.operator_id, 
-        ck.checkpoint_id, ck.completed_checkpoint_id, ck.handle
+        ck.checkpoint_id, ck.completed_checkpoint_id, ck.handle, ck.savepoint
 from rlink_ck as ck
 where ck.application_name = :application_name
     and ck.application_id = :application_id
@@ -176,43 +186,87 @@ where ck.application_name = :application_name
             "application_name" => application_name,
             "application_id" => application_id,
             "checkpoint_id" => checkpoint_id.0},
-            |(
-                job_id,
-                task_number,
-                num_tasks,
-                operator_id,
-                checkpoint_id,
-                completed_checkpoint_id,
-                handle,
-            )| {
-                let completed_checkpoint_id = if completed_checkpoint_id == 0 {
-                    None
-                } else {
-                    Some(CheckpointId(completed_checkpoint_id))
-                };
-
-                Checkpoint {
-                    operator_id: OperatorId(operator_id),
-                    task_id: TaskId {
-                        job_id: JobId(job_id),
-                        task_number,
-                        num_tasks,
-                    },
-                    checkpoint_id: CheckpointId(checkpoint_id),
-                    completed_checkpoint_id,
-                    handle: CheckpointHandle { handle },
-                }
-            },
+            to_checkpoint,
         )?;
 
         info!("checkpoint load success");
         Ok(selected_payments)
     }
+
+    fn load_all(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let pool = Pool::new(self.url.as_str())?;
+
+        let mut conn = pool.get_conn()?;
+
+        let stmt = conn.prep(
+            r"
+SELECT  ck.job_id, ck.task_number, ck.num_tasks, ck.operator_id, 
+        ck.checkpoint_id, ck.completed_checkpoint_id, ck.handle, ck.savepoint
+from rlink_ck as ck
+where ck.application_name = :application_name
+    and ck.application_id = :application_id
+order by ck.checkpoint_id"
+                .replace("rlink_ck", self.table.as_str()),
+        )?;
+
+        let checkpoints = conn.exec_map(
+            &stmt,
+            params! {
+            "application_name" => application_name,
+            "application_id" => application_id,
+             },
+            to_checkpoint,
+        )?;
+
+        info!("checkpoint load all success");
+        Ok(checkpoints)
+    }
+
+    fn delete(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_ids: &[CheckpointId],
+    ) -> anyhow::Result<()> {
+        if checkpoint_ids.is_empty() {
+            return Ok(());
+        }
+
+        let pool = Pool::new(self.url.as_str())?;
+
+        let mut conn = pool.get_conn()?;
+        conn.exec_batch(
+            r"
+delete
+from rlink_ck
+where application_name = :application_name
+  and application_id = :application_id
+  and checkpoint_id = :checkpoint_id"
+                .replace("rlink_ck", self.table.as_str()),
+            checkpoint_ids.iter().map(|checkpoint_id| {
+                params! {
+                    "application_name" => application_name,
+                    "application_id" => application_id,
+                    "checkpoint_id" => checkpoint_id.0,
+                }
+            }),
+        )?;
+
+        info!(
+            "checkpoint delete success, application_name={:?}, checkpoint_ids={:?}",
+            application_name, checkpoint_ids
+        );
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::core::checkpoint::{Checkpoint, CheckpointHandle};
+    use crate::core::checkpoint::{Checkpoint, CheckpointHandle, CheckpointStats};
     use crate::core::runtime::{CheckpointId, JobId, OperatorId, TaskId};
     use crate::storage::checkpoint::mysql_checkpoint_storage::MySqlCheckpointStorage;
     use crate::storage::checkpoint::TCheckpointStorage;
@@ -253,6 +307,9 @@ mod tests {
                         handle: CheckpointHandle {
                             handle: "h0".to_string(),
                         },
+                        stats: CheckpointStats::default(),
+                        declined: None,
+                        savepoint: false,
                     },
                     Checkpoint {
                         operator_id,
@@ -262,6 +319,9 @@ mod tests {
                         handle: CheckpointHandle {
                             handle: "h1".to_string(),
                         },
+                        stats: CheckpointStats::default(),
+                        declined: None,
+                        savepoint: false,
                     },
                 ],
                 1000 * 60 * 60 * 24 * 3,
@@ -0,0 +1,230 @@
+use std::sync::Arc;
+
+use crate::core::checkpoint::Checkpoint;
+use crate::core::runtime::CheckpointId;
+use crate::storage::checkpoint::TCheckpointStorage;
+use crate::utils::object_store::{join_path, ObjectStore, ObjectStoreUrl};
+
+const CHECKPOINT_FILE_SUFFIX: &str = ".json";
+
+/// Save the `Checkpoint`s of each checkpoint id as a json object
+/// `{uri}/{application_name}/{application_id}/{checkpoint_id}.json`
+pub struct ObjectStoreCheckpointStorage {
+    uri: String,
+    /// the store is created on the first use, so the missing credentials fail the calls
+    /// instead of the startup of the coordinator
+    store: Option<(Arc<dyn ObjectStore>, String)>,
+}
+
+impl ObjectStoreCheckpointStorage {
+    pub fn new(uri: String) -> Self {
+        ObjectStoreCheckpointStorage { uri, store: None }
+    }
+
+    fn store(&mut self) -> anyhow::Result<(Arc<dyn ObjectStore>, String)> {
+        if self.store.is_none() {
+            let url = ObjectStoreUrl::parse(self.uri.as_str())?;
+            self.store = Some((url.object_store()?, url.path));
+        }
+        Ok(self.store.clone().unwrap())
+    }
+
+    fn application_path(base: &str, application_name: &str, application_id: &str) -> String {
+        join_path(
+            join_path(base, application_name).as_str(),
+            format!("{}/", application_id).as_str(),
+        )
+    }
+
+    fn checkpoint_path(application_path: &str, checkpoint_id: CheckpointId) -> String {
+        // fixed width, the object paths are sorted by the checkpoint id
+        join_path(
+            application_path,
+            format!("{:020}{}", checkpoint_id.0, CHECKPOINT_FILE_SUFFIX).as_str(),
+        )
+    }
+
+    /// all checkpoint ids of the application in ascending order
+    fn checkpoint_ids(
+        store: &dyn ObjectStore,
+        application_path: &str,
+    ) -> anyhow::Result<Vec<CheckpointId>> {
+        let mut checkpoint_ids: Vec<CheckpointId> = store
+            .list(application_path)?
+            .iter()
+            .filter_map(|object| {
+                object.path[application_path.len()..]
+                    .strip_suffix(CHECKPOINT_FILE_SUFFIX)
+                    .and_then(|x| x.parse().ok())
+                    .map(CheckpointId)
+            })
+            .collect();
+        checkpoint_ids.sort_by_key(|x| x.0);
+        Ok(checkpoint_ids)
+    }
+
+    fn get(
+        store: &dyn ObjectStore,
+        application_path: &str,
+        checkpoint_id: CheckpointId,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let path = Self::checkpoint_path(application_path, checkpoint_id);
+        match store.get(path.as_str())? {
+            Some(data) => Ok(serde_json::from_slice(data.as_slice())?),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+impl TCheckpointStorage for ObjectStoreCheckpointStorage {
+    fn save(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_id: CheckpointId,
+        finish_cks: Vec<Checkpoint>,
+        ttl: u64,
+    ) -> anyhow::Result<()> {
+        let (store, base) = self.store()?;
+        let application_path =
+            Self::application_path(base.as_str(), application_name, application_id);
+
+        let path = Self::checkpoint_path(application_path.as_str(), checkpoint_id);
+        store.put(path.as_str(), serde_json::to_vec(&finish_cks)?)?;
+
+        if checkpoint_id.0 < ttl {
+            return Ok(());
+        }
+
+        let checkpoint_id_ttl = checkpoint_id.0 - ttl;
+        for ck_id in Self::checkpoint_ids(store.as_ref(), application_path.as_str())? {
+            if ck_id.0 >= checkpoint_id_ttl {
+                break;
+            }
+            let path = Self::checkpoint_path(application_path.as_str(), ck_id);
+            store.delete(path.as_str())?;
+        }
+
+        Ok(())
+    }
+
+    fn load(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let (store, base) = self.store()?;
+        let application_path =
+            Self::application_path(base.as_str(), application_name, application_id);
+
+        match Self::checkpoint_ids(store.as_ref(), application_path.as_str())?.last() {
+            Some(ck_id) => Self::get(store.as_ref(), application_path.as_str(), *ck_id),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn load_by_checkpoint_id(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_id: CheckpointId,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let (store, base) = self.store()?;
+        let application_path =
+            Self::application_path(base.as_str(), application_name, application_id);
+
+        Self::get(store.as_ref(), application_path.as_str(), checkpoint_id)
+    }
+
+    fn load_all(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        let (store, base) = self.store()?;
+        let application_path =
+            Self::application_path(base.as_str(), application_name, application_id);
+
+        let mut checkpoints = Vec::new();
+        for ck_id in Self::checkpoint_ids(store.as_ref(), application_path.as_str())? {
+            checkpoints.extend(Self::get(store.as_ref(), application_path.as_str(), ck_id)?);
+        }
+        Ok(checkpoints)
+    }
+
+    fn delete(
+        &mut self,
+        application_name: &str,
+        application_id: &str,
+        checkpoint_ids: &[CheckpointId],
+    ) -> anyhow::Result<()> {
+        let (store, base) = self.store()?;
+        let application_path =
+            Self::application_path(base.as_str(), application_name, application_id);
+
+        for checkpoint_id in checkpoint_ids {
+            let path = Self::checkpoint_path(application_path.as_str(), *checkpoint_id);
+            store.delete(path.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::checkpoint::{Checkpoint, CheckpointHandle};
+    use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
+    use crate::storage::checkpoint::object_store_checkpoint_storage::ObjectStoreCheckpointStorage;
+    use crate::storage::checkpoint::TCheckpointStorage;
+
+    fn checkpoint(checkpoint_id: u64) -> Checkpoint {
+        Checkpoint {
+            operator_id: OperatorId(1),
+            task_id: TaskId::default(),
+            checkpoint_id: CheckpointId(checkpoint_id),
+            completed_checkpoint_id: None,
+            handle: CheckpointHandle {
+                handle: checkpoint_id.to_string(),
+            },
+            stats: Default::default(),
+            declined: None,
+            savepoint: false,
+        }
+    }
+
+    #[test]
+    pub fn object_store_checkpoint_storage_test() {
+        let dir = std::env::temp_dir().join("rlink-object-store-ck-test");
+        let _ = std::fs::remove_dir_all(dir.as_path());
+
+        let uri = format!("file://{}", dir.to_str().unwrap());
+        let mut storage = ObjectStoreCheckpointStorage::new(uri);
+
+        for ck_id in [100, 200, 300] {
+            storage
+                .save(
+                    "app",
+                    "id",
+                    CheckpointId(ck_id),
+                    vec![checkpoint(ck_id)],
+                    150,
+                )
+                .unwrap();
+        }
+
+        // the checkpoint 100 is expired by the ttl
+        let cks = storage
+            .load_by_checkpoint_id("app", "id", CheckpointId(100))
+            .unwrap();
+        assert!(cks.is_empty());
+
+        let cks = storage.load("app", "id").unwrap();
+        assert_eq!(cks[0].handle.handle, "300");
+
+        storage.delete("app", "id", &[CheckpointId(300)]).unwrap();
+        let cks = storage.load("app", "id").unwrap();
+        assert_eq!(cks[0].handle.handle, "200");
+
+        std::fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+}
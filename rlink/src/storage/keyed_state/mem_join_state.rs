@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use bytes::BytesMut;
+
+use crate::core::element::{Record, Serde};
+use crate::metrics::keyed_state::{key_group, KeyGroupStats};
+use crate::storage::keyed_state::{JoinSide, TJoinState};
+
+#[derive(Clone, Debug)]
+struct JoinEntry {
+    records: Vec<Record>,
+    /// the watermark to expire the entry, `u64::MAX` if the state has no ttl
+    expire_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JoinEntrySnapshot {
+    side: JoinSide,
+    /// the base64 of the serialized key
+    key: String,
+    /// the base64 of the serialized records
+    records: Vec<String>,
+    expire_at: u64,
+}
+
+/// The buffered records of the joined streams in the memory, grouped by the side and the key
+#[derive(Clone, Debug)]
+pub struct MemoryJoinState {
+    ttl: Option<u64>,
+    sides: BTreeMap<JoinSide, BTreeMap<Record, JoinEntry>>,
+    /// key: the expire watermark, value: the entries registered with it. an entry is removed
+    /// only if its `expire_at` is still the timestamp, the entries updated later are skipped
+    timers: BTreeMap<u64, Vec<(JoinSide, Record)>>,
+}
+
+impl MemoryJoinState {
+    pub fn new(ttl: Option<u64>) -> Self {
+        MemoryJoinState {
+            ttl,
+            sides: BTreeMap::new(),
+            timers: BTreeMap::new(),
+        }
+    }
+}
+
+fn encode(record: &Record) -> String {
+    let mut bytes = BytesMut::with_capacity(record.capacity());
+    record.serialize(&mut bytes);
+    base64::encode(bytes.as_ref())
+}
+
+fn decode(value: &str) -> anyhow::Result<Record> {
+    let mut bytes = BytesMut::from(base64::decode(value)?.as_slice());
+    Ok(Record::deserialize(&mut bytes))
+}
+
+impl TJoinState for MemoryJoinState {
+    fn add(&mut self, side: JoinSide, key: Record, record: Record, timestamp: u64) {
+        let expire_at = self
+            .ttl
+            .map(|ttl| timestamp.saturating_add(ttl))
+            .unwrap_or(u64::MAX);
+
+        let entry = self
+            .sides
+            .entry(side)
+            .or_default()
+            .entry(key.clone())
+            .or_insert_with(|| JoinEntry {
+                records: Vec::new(),
+                expire_at: 0,
+            });
+        entry.records.push(record);
+        if expire_at > entry.expire_at {
+            entry.expire_at = expire_at;
+            if expire_at != u64::MAX {
+                self.timers.entry(expire_at).or_default().push((side, key));
+            }
+        }
+    }
+
+    fn get(&self, side: JoinSide, key: &Record) -> &[Record] {
+        self.sides
+            .get(&side)
+            .and_then(|entries| entries.get(key))
+            .map(|entry| entry.records.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn remove(&mut self, side: JoinSide, key: &Record) -> Vec<Record> {
+        self.sides
+            .get_mut(&side)
+            .and_then(|entries| entries.remove(key))
+            .map(|entry| entry.records)
+            .unwrap_or_default()
+    }
+
+    fn expire(&mut self, watermark: u64) -> Vec<(JoinSide, Record, Vec<Record>)> {
+        let mut expired = Vec::new();
+        while let Some((timestamp, _)) = self.timers.iter().next() {
+            if *timestamp > watermark {
+                break;
+            }
+
+            let timestamp = *timestamp;
+            for (side, key) in self.timers.remove(&timestamp).unwrap() {
+                let entries = match self.sides.get_mut(&side) {
+                    Some(entries) => entries,
+                    None => continue,
+                };
+                let is_expired = entries
+                    .get(&key)
+                    .map(|entry| entry.expire_at == timestamp)
+                    .unwrap_or(false);
+                if is_expired {
+                    let entry = entries.remove(&key).unwrap();
+                    expired.push((side, key, entry.records));
+                }
+            }
+        }
+        expired
+    }
+
+    fn len(&self) -> usize {
+        self.sides
+            .values()
+            .flat_map(|entries| entries.values())
+            .map(|entry| entry.records.len())
+            .sum()
+    }
+
+    fn key_group_stats(&self) -> KeyGroupStats {
+        let mut stats = KeyGroupStats::default();
+        for (key, entry) in self.sides.values().flat_map(|entries| entries.iter()) {
+            let bytes: usize = entry.records.iter().map(|x| x.capacity()).sum();
+            stats.keys += 1;
+            stats.key_group_bytes[key_group(key) as usize] += (key.capacity() + bytes) as u64;
+        }
+        stats
+    }
+
+    fn snapshot(&self) -> String {
+        let entries: Vec<JoinEntrySnapshot> = self
+            .sides
+            .iter()
+            .flat_map(|(side, entries)| entries.iter().map(move |x| (side, x)))
+            .map(|(side, (key, entry))| JoinEntrySnapshot {
+                side: *side,
+                key: encode(key),
+                records: entry.records.iter().map(encode).collect(),
+                expire_at: entry.expire_at,
+            })
+            .collect();
+        serde_json::to_string(&entries).unwrap()
+    }
+
+    fn restore(&mut self, snapshot: &str) -> anyhow::Result<()> {
+        let entries: Vec<JoinEntrySnapshot> = serde_json::from_str(snapshot)?;
+
+        self.sides.clear();
+        self.timers.clear();
+        for entry in entries {
+            let key = decode(entry.key.as_str())?;
+            let records = entry
+                .records
+                .iter()
+                .map(|x| decode(x.as_str()))
+                .collect::<anyhow::Result<Vec<Record>>>()?;
+            if entry.expire_at != u64::MAX {
+                self.timers
+                    .entry(entry.expire_at)
+                    .or_default()
+                    .push((entry.side, key.clone()));
+            }
+            self.sides.entry(entry.side).or_default().insert(
+                key,
+                JoinEntry {
+                    records,
+                    expire_at: entry.expire_at,
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serbuffer::types;
+
+    use crate::core::element::Record;
+    use crate::storage::keyed_state::mem_join_state::MemoryJoinState;
+    use crate::storage::keyed_state::{JoinSide, TJoinState};
+
+    fn record(value: u64) -> Record {
+        let mut record = Record::with_capacity(8);
+        record.as_writer(&[types::U64]).set_u64(value).unwrap();
+        record
+    }
+
+    #[test]
+    pub fn join_state_test() {
+        let mut state = MemoryJoinState::new(Some(100));
+        state.add(JoinSide::Left, record(1), record(10), 1000);
+        state.add(JoinSide::Left, record(1), record(11), 1050);
+        state.add(JoinSide::Right(0), record(2), record(20), 1000);
+        assert_eq!(state.get(JoinSide::Left, &record(1)).len(), 2);
+        assert!(state.get(JoinSide::Right(0), &record(1)).is_empty());
+        assert_eq!(state.len(), 3);
+
+        let mut restored = MemoryJoinState::new(Some(100));
+        restored.restore(state.snapshot().as_str()).unwrap();
+        assert_eq!(
+            restored.get(JoinSide::Left, &record(1)),
+            &[record(10), record(11)]
+        );
+
+        // the left key is extended to 1150 by the second record
+        let expired = restored.expire(1100);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0, JoinSide::Right(0));
+        assert_eq!(expired[0].2, vec![record(20)]);
+
+        assert_eq!(restored.expire(1150).len(), 1);
+        assert!(restored.is_empty());
+    }
+}
@@ -35,6 +35,10 @@ impl MemoryReducingState {
 
         state
     }
+
+    pub(crate) fn entries(&self) -> std::collections::btree_map::Iter<'_, Record, Record> {
+        self.kv.iter()
+    }
 }
 
 impl TReducingState for MemoryReducingState {
@@ -54,8 +58,8 @@ impl TReducingState for MemoryReducingState {
 
     fn destroy(self) {}
 
-    fn iter(self) -> StateIterator {
-        StateIterator::BTreeMap(self.state_key.window, self.kv.into_iter())
+    fn iter(self, key_arity: usize) -> StateIterator {
+        StateIterator::BTreeMap(self.state_key.window, self.kv.into_iter(), key_arity)
     }
 
     fn len(&self) -> usize {
@@ -1,12 +1,17 @@
+use std::collections::VecDeque;
+
 use dashmap::DashMap;
 
 use crate::core::runtime::JobId;
 use crate::core::window::Window;
 use crate::storage::keyed_state::mem_reducing_state::MemoryReducingState;
 
+type WindowStates = DashMap<Window, VecDeque<MemoryReducingState>>;
+
 lazy_static! {
-    static ref DROP_WINDOW_STATE_STORAGE: DashMap<StorageKey, DashMap<Window, MemoryReducingState>> =
-        DashMap::new();
+    /// the states of the fired windows waiting for reading, a window is fired multiple times by
+    /// the incremental `WindowEmitStrategy`, the states are read in the order of the firings
+    static ref DROP_WINDOW_STATE_STORAGE: DashMap<StorageKey, WindowStates> = DashMap::new();
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -29,13 +34,16 @@ pub(crate) fn append_drop_window(
     window: Window,
     state: MemoryReducingState,
 ) {
-    let drop_window_states: &DashMap<StorageKey, DashMap<Window, MemoryReducingState>> =
-        &*DROP_WINDOW_STATE_STORAGE;
+    let drop_window_states: &DashMap<StorageKey, WindowStates> = &DROP_WINDOW_STATE_STORAGE;
 
     let task_storage = drop_window_states
         .entry(storage_key)
         .or_insert_with(|| DashMap::new());
-    task_storage.value().insert(window, state);
+    task_storage
+        .value()
+        .entry(window)
+        .or_default()
+        .push_back(state);
 }
 
 pub(crate) fn remove_drop_window(
@@ -43,12 +51,16 @@ pub(crate) fn remove_drop_window(
     task_number: u16,
     window: Window,
 ) -> Option<MemoryReducingState> {
-    let drop_window_states: &DashMap<StorageKey, DashMap<Window, MemoryReducingState>> =
-        &*DROP_WINDOW_STATE_STORAGE;
+    let drop_window_states: &DashMap<StorageKey, WindowStates> = &DROP_WINDOW_STATE_STORAGE;
 
     let key = StorageKey::new(job_id, task_number);
-    match drop_window_states.get(&key) {
-        Some(task_storage) => task_storage.value().remove(&window).map(|(_k, v)| v),
-        None => None,
-    }
+    let task_storage = drop_window_states.get(&key)?;
+    let state = task_storage
+        .value()
+        .get_mut(&window)
+        .and_then(|mut states| states.pop_front());
+    task_storage
+        .value()
+        .remove_if(&window, |_window, states| states.is_empty());
+    state
 }
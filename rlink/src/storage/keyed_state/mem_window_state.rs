@@ -1,9 +1,10 @@
 use std::borrow::BorrowMut;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::core::element::{Barrier, Record};
+use crate::core::element::{Barrier, Record, Serde};
 use crate::core::runtime::JobId;
 use crate::core::window::Window;
+use crate::metrics::keyed_state::{key_group, KeyGroupStats};
 use crate::storage::keyed_state::mem_reducing_state::MemoryReducingState;
 use crate::storage::keyed_state::mem_storage::{append_drop_window, StorageKey};
 use crate::storage::keyed_state::{StateKey, TReducingState, TWindowState};
@@ -99,5 +100,78 @@ impl TWindowState for MemoryWindowState {
         self.windows.len()
     }
 
+    fn fire_window(&mut self, window: &Window) {
+        if let Some(state) = self.windows.get(window) {
+            let state_key = StorageKey::new(self.job_id, self.task_number);
+            append_drop_window(state_key, window.clone(), state.clone());
+        }
+    }
+
+    fn purge_window(&mut self, window: &Window) -> usize {
+        self.windows.remove(window);
+        self.windows.len()
+    }
+
+    fn entries(&self) -> usize {
+        self.windows.values().map(|state| state.len()).sum()
+    }
+
+    fn key_group_stats(&self) -> KeyGroupStats {
+        let mut stats = KeyGroupStats::default();
+        let mut keys = HashSet::new();
+        for state in self.windows.values() {
+            for (key, value) in state.entries() {
+                let key_group = key_group(key) as usize;
+                stats.key_group_bytes[key_group] += (key.capacity() + value.capacity()) as u64;
+                keys.insert(key);
+            }
+        }
+        stats.keys = keys.len();
+        stats
+    }
+
     fn snapshot(&mut self, _barrier: Barrier) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use serbuffer::types;
+
+    use crate::core::element::Record;
+    use crate::core::runtime::JobId;
+    use crate::core::window::{TimeWindow, Window};
+    use crate::storage::keyed_state::mem_storage::remove_drop_window;
+    use crate::storage::keyed_state::mem_window_state::MemoryWindowState;
+    use crate::storage::keyed_state::{TReducingState, TWindowState};
+
+    fn record(key: u64, window: &Window) -> (Record, Record) {
+        let mut key_record = Record::with_capacity(8);
+        key_record.as_writer(&[types::U64]).set_u64(key).unwrap();
+        let mut record = key_record.clone();
+        record.set_location_windows(vec![window.clone()]);
+        (key_record, record)
+    }
+
+    #[test]
+    pub fn fire_window_test() {
+        let job_id = JobId(99);
+        let window = Window::TimeWindow(TimeWindow::new(0, 1000));
+        let mut state = MemoryWindowState::new("app".to_string(), job_id, 0);
+
+        let (key, value) = record(1, &window);
+        state.merge(key, value, |_, record| record.clone());
+        // the window is kept open after the firing
+        state.fire_window(&window);
+
+        let (key, value) = record(2, &window);
+        state.merge(key, value, |_, record| record.clone());
+        assert_eq!(state.drop_window(&window), 0);
+
+        // the states are read in the order of the firings
+        let fired = remove_drop_window(job_id, 0, window.clone()).unwrap();
+        assert_eq!(fired.len(), 1);
+        let dropped = remove_drop_window(job_id, 0, window.clone()).unwrap();
+        assert_eq!(dropped.len(), 2);
+        assert!(remove_drop_window(job_id, 0, window).is_none());
+    }
+}
@@ -1,16 +1,24 @@
 use std::collections::btree_map::IntoIter;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use crate::core::backend::KeyedStateBackend;
 use crate::core::element::{Barrier, Record};
 use crate::core::runtime::JobId;
 use crate::core::window::Window;
+use crate::metrics::keyed_state::KeyGroupStats;
+use crate::storage::keyed_state::mem_join_state::MemoryJoinState;
 use crate::storage::keyed_state::mem_reducing_state::MemoryReducingState;
 use crate::storage::keyed_state::mem_window_state::MemoryWindowState;
+use crate::utils::external_sort::RunReader;
 
+pub mod mem_join_state;
 pub mod mem_reducing_state;
 pub mod mem_storage;
 pub mod mem_window_state;
+pub mod pane_window_state;
+pub mod sorted_reducing_state;
+pub mod sorted_window_state;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct StateKey {
@@ -29,22 +37,25 @@ impl StateKey {
     }
 }
 
+/// iterate the key-value entries of a window's state as the records of the key fields followed
+/// by the value fields, the `usize` is the number of the key fields
 pub enum StateIterator {
-    BTreeMap(Window, IntoIter<Record, Record>),
+    BTreeMap(Window, IntoIter<Record, Record>, usize),
+    /// the sorted run of the `SortedReducingState`
+    Sorted(Window, RunReader, usize),
 }
 
 impl Iterator for StateIterator {
     type Item = Record;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            StateIterator::BTreeMap(window, iter) => iter.next().map(|(key, val)| {
-                let mut key = key.clone();
-                key.extend(val.clone()).expect("key value merge error");
-                key.trigger_window = Some(window.clone());
-                key
-            }),
-        }
+        let (window, (mut key, val), key_arity) = match self {
+            StateIterator::BTreeMap(window, iter, key_arity) => (window, iter.next()?, *key_arity),
+            StateIterator::Sorted(window, iter, key_arity) => (window, iter.next()?, *key_arity),
+        };
+        key.extend(val, key_arity).expect("key value merge error");
+        key.trigger_window = Some(window.clone());
+        Some(key)
     }
 }
 
@@ -56,7 +67,8 @@ pub trait TReducingState {
     fn snapshot(&mut self);
     fn close(self);
     fn destroy(self);
-    fn iter(self) -> StateIterator;
+    /// `key_arity` is the number of the key fields
+    fn iter(self, key_arity: usize) -> StateIterator;
     fn len(&self) -> usize;
 }
 
@@ -110,9 +122,9 @@ impl TReducingState for ReducingState {
         }
     }
 
-    fn iter(self) -> StateIterator {
+    fn iter(self, key_arity: usize) -> StateIterator {
         match self {
-            ReducingState::MemoryReducingState(state) => state.iter(),
+            ReducingState::MemoryReducingState(state) => state.iter(key_arity),
         }
     }
 
@@ -132,6 +144,18 @@ pub trait TWindowState {
 
     fn drop_window(&mut self, window: &Window) -> usize;
 
+    /// emit a copy of the window's state as `drop_window` does, the window is kept open
+    fn fire_window(&mut self, window: &Window);
+
+    /// remove the window's state without emitting
+    fn purge_window(&mut self, window: &Window) -> usize;
+
+    /// the number of the retained keyed entries of all windows
+    fn entries(&self) -> usize;
+
+    /// scan the state of all windows, a key in multiple windows is counted once
+    fn key_group_stats(&self) -> KeyGroupStats;
+
     fn snapshot(&mut self, barrier: Barrier);
 }
 
@@ -178,9 +202,125 @@ impl TWindowState for WindowState {
         }
     }
 
+    fn fire_window(&mut self, window: &Window) {
+        match self {
+            WindowState::MemoryWindowState(state) => state.fire_window(window),
+        }
+    }
+
+    fn purge_window(&mut self, window: &Window) -> usize {
+        match self {
+            WindowState::MemoryWindowState(state) => state.purge_window(window),
+        }
+    }
+
+    fn entries(&self) -> usize {
+        match self {
+            WindowState::MemoryWindowState(state) => state.entries(),
+        }
+    }
+
+    fn key_group_stats(&self) -> KeyGroupStats {
+        match self {
+            WindowState::MemoryWindowState(state) => state.key_group_stats(),
+        }
+    }
+
     fn snapshot(&mut self, barrier: Barrier) {
         match self {
             WindowState::MemoryWindowState(state) => state.snapshot(barrier),
         }
     }
 }
+
+/// The input of a `CoProcessFunction`, `Right(stream_seq)` for the right streams
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JoinSide {
+    Left,
+    Right(usize),
+}
+
+/// The buffered records of a streaming join grouped by the side and the key, see
+/// `Context::join_state`. The state is captured by the checkpoint of the function,
+/// eg: `snapshot` in `CheckpointFunction::snapshot_state` and `restore` in
+/// `CheckpointFunction::initialize_state`
+pub trait TJoinState {
+    /// buffer the `record` of the `key`, the entry expires when the watermark passes
+    /// `timestamp + ttl`, adding a record extends the expiration of the entry
+    fn add(&mut self, side: JoinSide, key: Record, record: Record, timestamp: u64);
+    fn get(&self, side: JoinSide, key: &Record) -> &[Record];
+    fn remove(&mut self, side: JoinSide, key: &Record) -> Vec<Record>;
+    /// remove and return the expired entries, eg: to emit the unmatched records of an outer join
+    fn expire(&mut self, watermark: u64) -> Vec<(JoinSide, Record, Vec<Record>)>;
+    /// the number of the buffered records
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn key_group_stats(&self) -> KeyGroupStats;
+    fn snapshot(&self) -> String;
+    fn restore(&mut self, snapshot: &str) -> anyhow::Result<()>;
+}
+
+pub enum JoinState {
+    MemoryJoinState(MemoryJoinState),
+}
+
+impl JoinState {
+    pub fn new(mode: KeyedStateBackend, ttl: Option<Duration>) -> Self {
+        let ttl = ttl.map(|x| x.as_millis() as u64);
+        match mode {
+            KeyedStateBackend::Memory => JoinState::MemoryJoinState(MemoryJoinState::new(ttl)),
+        }
+    }
+}
+
+impl TJoinState for JoinState {
+    fn add(&mut self, side: JoinSide, key: Record, record: Record, timestamp: u64) {
+        match self {
+            JoinState::MemoryJoinState(state) => state.add(side, key, record, timestamp),
+        }
+    }
+
+    fn get(&self, side: JoinSide, key: &Record) -> &[Record] {
+        match self {
+            JoinState::MemoryJoinState(state) => state.get(side, key),
+        }
+    }
+
+    fn remove(&mut self, side: JoinSide, key: &Record) -> Vec<Record> {
+        match self {
+            JoinState::MemoryJoinState(state) => state.remove(side, key),
+        }
+    }
+
+    fn expire(&mut self, watermark: u64) -> Vec<(JoinSide, Record, Vec<Record>)> {
+        match self {
+            JoinState::MemoryJoinState(state) => state.expire(watermark),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            JoinState::MemoryJoinState(state) => state.len(),
+        }
+    }
+
+    fn key_group_stats(&self) -> KeyGroupStats {
+        match self {
+            JoinState::MemoryJoinState(state) => state.key_group_stats(),
+        }
+    }
+
+    fn snapshot(&self) -> String {
+        match self {
+            JoinState::MemoryJoinState(state) => state.snapshot(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: &str) -> anyhow::Result<()> {
+        match self {
+            JoinState::MemoryJoinState(state) => state.restore(snapshot),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::core::element::{Record, Serde};
+use crate::core::runtime::JobId;
+use crate::core::window::{TWindow, Window};
+use crate::metrics::keyed_state::{key_group, KeyGroupStats};
+use crate::storage::keyed_state::mem_reducing_state::MemoryReducingState;
+use crate::storage::keyed_state::mem_storage::{append_drop_window, StorageKey};
+use crate::storage::keyed_state::{StateKey, TReducingState};
+
+/// The state of the overlapping windows split into the panes, a record is reduced into its
+/// pane only instead of every window it's located in. The windows are assembled by combining
+/// the partial results of their panes when dropped, see `ReduceFunction::combine`
+pub struct PaneWindowState {
+    job_id: JobId,
+    task_number: u16,
+
+    /// the partial results indexed by the start of the pane and the key
+    panes: BTreeMap<u64, BTreeMap<Record, Record>>,
+    /// the open windows located by the records
+    windows: HashSet<Window>,
+}
+
+impl PaneWindowState {
+    pub fn new(job_id: JobId, task_number: u16) -> Self {
+        PaneWindowState {
+            job_id,
+            task_number,
+            panes: BTreeMap::new(),
+            windows: HashSet::new(),
+        }
+    }
+
+    /// the pane is the slide of the latest window located by the record
+    pub fn pane_start(location_windows: &[Window]) -> Option<u64> {
+        location_windows.iter().map(|w| w.min_timestamp()).max()
+    }
+
+    pub fn windows(&self) -> Vec<Window> {
+        self.windows.iter().cloned().collect()
+    }
+
+    /// reduce the `record` into the pane starting at `pane_start`,
+    /// the windows of the `record` are kept open
+    pub fn merge<F>(
+        &mut self,
+        pane_start: u64,
+        key: Record,
+        mut record: Record,
+        reduce_fun: F,
+    ) -> usize
+    where
+        F: Fn(Option<&mut Record>, &mut Record) -> Record,
+    {
+        for window in record.location_windows() {
+            if !self.windows.contains(window) {
+                self.windows.insert(window.clone());
+            }
+        }
+
+        let pane = self.panes.entry(pane_start).or_default();
+        match pane.get_mut(&key) {
+            Some(value) => {
+                let new_val = reduce_fun(Some(value), &mut record);
+                *value = new_val;
+            }
+            None => {
+                let new_val = reduce_fun(None, &mut record);
+                pane.insert(key, new_val);
+            }
+        }
+        self.windows.len()
+    }
+
+    /// combine the panes of the `window` and emit it as `TWindowState::drop_window` does,
+    /// the panes not located in the open windows are removed
+    pub fn drop_window<F>(&mut self, window: &Window, combine_fun: F) -> usize
+    where
+        F: Fn(Option<&mut Record>, &mut Record) -> Record,
+    {
+        if !self.windows.remove(window) {
+            return self.windows.len();
+        }
+
+        let state_key = StateKey::new(window.clone(), self.job_id, self.task_number);
+        let mut state = MemoryReducingState::new(&state_key);
+        let panes = self
+            .panes
+            .range(window.min_timestamp()..window.max_timestamp());
+        for (_pane_start, pane) in panes {
+            for (key, partial) in pane {
+                let mut partial = partial.clone();
+                match state.get_mut(key) {
+                    Some(value) => {
+                        let new_val = combine_fun(Some(value), &mut partial);
+                        *value = new_val;
+                    }
+                    None => {
+                        let new_val = combine_fun(None, &mut partial);
+                        state.insert(key.clone(), new_val);
+                    }
+                }
+            }
+        }
+        let storage_key = StorageKey::new(self.job_id, self.task_number);
+        append_drop_window(storage_key, window.clone(), state);
+
+        // the panes of a record are located in its open windows
+        match self.windows.iter().map(|w| w.min_timestamp()).min() {
+            Some(min_start) => self.panes = self.panes.split_off(&min_start),
+            None => self.panes.clear(),
+        }
+        self.windows.len()
+    }
+
+    /// the number of the retained keyed entries of all panes
+    pub fn entries(&self) -> usize {
+        self.panes.values().map(|pane| pane.len()).sum()
+    }
+
+    pub fn key_group_stats(&self) -> KeyGroupStats {
+        let mut stats = KeyGroupStats::default();
+        let mut keys = HashSet::new();
+        for pane in self.panes.values() {
+            for (key, value) in pane {
+                let key_group = key_group(key) as usize;
+                stats.key_group_bytes[key_group] += (key.capacity() + value.capacity()) as u64;
+                keys.insert(key);
+            }
+        }
+        stats.keys = keys.len();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serbuffer::types;
+
+    use crate::core::element::Record;
+    use crate::core::runtime::JobId;
+    use crate::core::window::{TimeWindow, Window};
+    use crate::storage::keyed_state::mem_storage::remove_drop_window;
+    use crate::storage::keyed_state::pane_window_state::PaneWindowState;
+    use crate::storage::keyed_state::TReducingState;
+
+    fn window(start: u64) -> Window {
+        Window::TimeWindow(TimeWindow::new(start, start + 3000))
+    }
+
+    fn count(value: Option<&mut Record>, n: u64) -> Record {
+        let base = value
+            .map(|value| value.as_reader(&[types::U64]).get_u64(0).unwrap())
+            .unwrap_or(0);
+        let mut record = Record::with_capacity(8);
+        record.as_writer(&[types::U64]).set_u64(base + n).unwrap();
+        record
+    }
+
+    #[test]
+    pub fn pane_window_state_test() {
+        let job_id = JobId(98);
+        let mut key = Record::with_capacity(8);
+        key.as_writer(&[types::U64]).set_u64(1).unwrap();
+
+        // windows of 3s sliding by 1s, a record per second
+        let mut state = PaneWindowState::new(job_id, 0);
+        for ts in 0..5u64 {
+            let windows: Vec<Window> = (0..3)
+                .filter(|n| ts >= *n)
+                .map(|n| window((ts - n) * 1000))
+                .collect();
+            let pane_start = PaneWindowState::pane_start(windows.as_slice()).unwrap();
+            let mut record = Record::new();
+            record.set_location_windows(windows);
+            state.merge(pane_start, key.clone(), record, |value, _| count(value, 1));
+        }
+        // a partial result per pane instead of a result per window and pane
+        assert_eq!(state.entries(), 5);
+
+        let combine = |value: Option<&mut Record>, partial: &mut Record| {
+            let n = partial.as_reader(&[types::U64]).get_u64(0).unwrap();
+            count(value, n)
+        };
+        state.drop_window(&window(0), combine);
+        state.drop_window(&window(1000), combine);
+        assert_eq!(state.entries(), 3);
+
+        for start in [0, 1000] {
+            let mut fired = remove_drop_window(job_id, 0, window(start)).unwrap();
+            let value = fired.get_mut(&key).unwrap();
+            assert_eq!(value.as_reader(&[types::U64]).get_u64(0).unwrap(), 3);
+        }
+    }
+}
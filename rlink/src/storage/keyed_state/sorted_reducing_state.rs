@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use dashmap::DashMap;
+
+use crate::storage::keyed_state::{StateIterator, StateKey};
+use crate::utils::external_sort::RunReader;
+
+lazy_static! {
+    static ref SORTED_WINDOW_STATE_STORAGE: DashMap<StateKey, SortedReducingState> = DashMap::new();
+}
+
+/// The reduced `(key, value)` of a dropped window in the sort-based grouping of the bounded jobs,
+/// stored in a run file sorted by the key instead of the memory, read once by the downstream
+/// `KeyedStateFlatMapFunction` and removed after reading.
+pub struct SortedReducingState {
+    state_key: StateKey,
+    path: PathBuf,
+    len: usize,
+}
+
+impl SortedReducingState {
+    pub fn new(state_key: &StateKey, path: PathBuf, len: usize) -> Self {
+        SortedReducingState {
+            state_key: state_key.clone(),
+            path,
+            len,
+        }
+    }
+
+    /// publish the state of a dropped window to the downstream task
+    pub(crate) fn append(self) {
+        debug!(
+            "append sorted state {:?}, {} entries",
+            self.state_key, self.len
+        );
+        SORTED_WINDOW_STATE_STORAGE.insert(self.state_key.clone(), self);
+    }
+
+    /// take the state of a dropped window
+    pub fn from(state_key: &StateKey) -> Option<SortedReducingState> {
+        let state = SORTED_WINDOW_STATE_STORAGE
+            .remove(state_key)
+            .map(|(_k, v)| v);
+        if state.is_none() {
+            error!("can not found sorted state {:?}", state_key);
+        }
+        state
+    }
+
+    /// `key_arity` is the number of the key fields
+    pub fn iter(self, key_arity: usize) -> anyhow::Result<StateIterator> {
+        let reader = RunReader::open(&self.path)?;
+        Ok(StateIterator::Sorted(
+            self.state_key.window,
+            reader,
+            key_arity,
+        ))
+    }
+}
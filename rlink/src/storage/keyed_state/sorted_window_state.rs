@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::core::element::Record;
+use crate::core::runtime::JobId;
+use crate::core::window::{TWindow, Window};
+use crate::storage::keyed_state::sorted_reducing_state::SortedReducingState;
+use crate::storage::keyed_state::StateKey;
+use crate::utils::external_sort::{ExternalSorter, RunWriter};
+
+/// The sort-based window state of the bounded jobs. The records are not reduced on arrival,
+/// but appended to an `ExternalSorter` of each window, all sorters share the `memory_limit`
+/// and the largest one is spilled when it's reached. When the window is dropped, the records
+/// are merged in the order of the key and the adjacent records of the same key are reduced,
+/// so only one key is in the memory at a time whatever the key cardinality is.
+pub struct SortedWindowState {
+    application_id: String,
+    job_id: JobId,
+    task_number: u16,
+
+    spill_dir: PathBuf,
+    memory_limit: usize,
+
+    windows: HashMap<Window, ExternalSorter>,
+}
+
+impl SortedWindowState {
+    pub fn new(
+        application_id: String,
+        job_id: JobId,
+        task_number: u16,
+        spill_dir: PathBuf,
+        memory_limit: usize,
+    ) -> Self {
+        let spill_dir = spill_dir.join(application_id.as_str());
+        SortedWindowState {
+            application_id,
+            job_id,
+            task_number,
+            spill_dir,
+            memory_limit,
+            windows: HashMap::new(),
+        }
+    }
+
+    fn window_name(&self, window: &Window) -> String {
+        format!(
+            "{}-{}-{}-{}",
+            self.job_id.0,
+            self.task_number,
+            window.min_timestamp(),
+            window.max_timestamp()
+        )
+    }
+
+    pub fn windows(&self) -> Vec<Window> {
+        self.windows.keys().cloned().collect()
+    }
+
+    pub fn merge(&mut self, key: Record, record: Record) -> anyhow::Result<usize> {
+        for window in record.location_windows() {
+            if !self.windows.contains_key(window) {
+                let name = self.window_name(window);
+                let sorter = ExternalSorter::new(&self.spill_dir, name.as_str(), usize::MAX);
+                self.windows.insert(window.clone(), sorter);
+            }
+            self.windows
+                .get_mut(window)
+                .unwrap()
+                .push(key.clone(), record.clone())?;
+        }
+
+        let buffered_bytes: usize = self.windows.values().map(|x| x.buffered_bytes()).sum();
+        if buffered_bytes >= self.memory_limit {
+            if let Some(sorter) = self
+                .windows
+                .values_mut()
+                .max_by_key(|sorter| sorter.buffered_bytes())
+            {
+                sorter.spill()?;
+            }
+        }
+
+        Ok(self.windows.len())
+    }
+
+    /// reduce the records of the `window` by the key and publish the result to the downstream
+    /// `KeyedStateFlatMapFunction` as a `SortedReducingState`
+    pub fn drop_window<F>(&mut self, window: &Window, reduce_fun: F) -> anyhow::Result<usize>
+    where
+        F: Fn(Option<&mut Record>, &mut Record) -> Record,
+    {
+        let sorter = match self.windows.remove(window) {
+            Some(sorter) => sorter,
+            None => return Ok(self.windows.len()),
+        };
+        let spilled_runs = sorter.spilled_runs();
+
+        let path = self
+            .spill_dir
+            .join(format!("{}.reduced", self.window_name(window)));
+        let mut writer = RunWriter::create(&path)?;
+        let mut len = 0;
+        let mut current: Option<(Record, Record)> = None;
+        for (key, mut record) in sorter.finish()? {
+            if let Some((current_key, value)) = current.as_mut() {
+                if key.eq(current_key) {
+                    *value = reduce_fun(Some(value), &mut record);
+                    continue;
+                }
+            }
+
+            let value = reduce_fun(None, &mut record);
+            if let Some((key, value)) = current.replace((key, value)) {
+                writer.write(&key, &value)?;
+                len += 1;
+            }
+        }
+        if let Some((key, value)) = current {
+            writer.write(&key, &value)?;
+            len += 1;
+        }
+        writer.finish()?;
+
+        debug!(
+            "reduce sorted window {:?} of {} application, {} keys from {} spilled runs",
+            window, self.application_id, len, spilled_runs
+        );
+        let state_key = StateKey::new(window.clone(), self.job_id, self.task_number);
+        SortedReducingState::new(&state_key, path, len).append();
+
+        Ok(self.windows.len())
+    }
+
+    /// the number of the buffered records of all windows
+    pub fn entries(&self) -> usize {
+        self.windows.values().map(|x| x.entries()).sum()
+    }
+}
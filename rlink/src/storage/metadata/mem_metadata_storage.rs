@@ -92,6 +92,25 @@ impl TMetadataStorage for MemoryMetadataStorage {
                         }
                     }
                 }
+                HeartbeatItem::ResourceUsage(resource_usage) => {
+                    task_manager_descriptor.resource_usage = Some(resource_usage);
+                }
+                // only for the alerting of the coordinator
+                HeartbeatItem::TaskWatermark { .. } => {}
+                // only for the alerting of the coordinator
+                HeartbeatItem::TaskStateLimitExceeded { .. } => {}
+                // only for the checkpoint trigger of the coordinator
+                HeartbeatItem::TaskCheckpointRequested { .. } => {}
+                // only for the watermark query of the coordinator
+                HeartbeatItem::TaskInputWatermark { .. } => {}
+                // only for the rate budget of the coordinator
+                HeartbeatItem::TaskRate { .. } => {}
+                // only for the source position query of the coordinator
+                HeartbeatItem::TaskPosition { .. } => {}
+                // only for the event log and the restart policy of the coordinator
+                HeartbeatItem::TaskFailure { .. } => {}
+                // only for the split reassignment of the coordinator
+                HeartbeatItem::TaskSplit { .. } => {}
                 HeartbeatItem::TaskEnd { task_id } => {
                     for task_descriptor in &mut task_manager_descriptor.task_descriptors {
                         if task_descriptor.task_id.eq(&task_id) {
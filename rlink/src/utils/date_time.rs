@@ -1,6 +1,7 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use chrono::{DateTime, Local, Utc};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
 
 pub const FMT_TIME: &str = "%T%z";
 pub const FMT_DATE_TIME: &str = "%Y-%m-%d %T";
@@ -18,6 +19,60 @@ pub fn current_timestamp_millis() -> u64 {
     current_timestamp().as_millis() as u64
 }
 
+/// the samples of the clock sync with a longer round trip are too inaccurate to use
+const MAX_CLOCK_SYNC_RTT_MILLIS: u64 = 1000;
+
+/// the offset of the coordinator's clock to the local clock, synced by the heartbeat
+static COORDINATOR_CLOCK_OFFSET: AtomicI64 = AtomicI64::new(0);
+static COORDINATOR_CLOCK_SYNCED: AtomicBool = AtomicBool::new(false);
+
+/// current timestamp of the coordinator's clock as millis, the workers assign the consistent
+/// ingestion timestamps by it even if the clocks of their hosts are skewed.
+/// it's the local clock until the first heartbeat of the worker is responded
+pub fn coordinated_timestamp_millis() -> u64 {
+    let offset = COORDINATOR_CLOCK_OFFSET.load(Ordering::Relaxed);
+    (current_timestamp_millis() as i64 + offset).max(0) as u64
+}
+
+/// sync the clock by a heartbeat sent at `request_timestamp` and responded at
+/// `response_timestamp` of the local clock, `coordinator_timestamp` is the coordinator's clock
+/// when responding, it's assumed at the middle of the round trip
+pub(crate) fn sync_coordinator_clock(
+    request_timestamp: u64,
+    response_timestamp: u64,
+    coordinator_timestamp: u64,
+) {
+    let offset = match clock_offset(request_timestamp, response_timestamp, coordinator_timestamp) {
+        Some(offset) => offset,
+        None => return,
+    };
+
+    if COORDINATOR_CLOCK_SYNCED.swap(true, Ordering::Relaxed) {
+        // smooth the jitter of the round trips
+        let previous = COORDINATOR_CLOCK_OFFSET.load(Ordering::Relaxed);
+        COORDINATOR_CLOCK_OFFSET.store(previous + (offset - previous) / 4, Ordering::Relaxed);
+    } else {
+        info!("the clock offset to the coordinator is {}ms", offset);
+        COORDINATOR_CLOCK_OFFSET.store(offset, Ordering::Relaxed);
+    }
+}
+
+fn clock_offset(
+    request_timestamp: u64,
+    response_timestamp: u64,
+    coordinator_timestamp: u64,
+) -> Option<i64> {
+    if coordinator_timestamp == 0 || response_timestamp < request_timestamp {
+        return None;
+    }
+    if response_timestamp - request_timestamp > MAX_CLOCK_SYNC_RTT_MILLIS {
+        return None;
+    }
+
+    let local_timestamp = request_timestamp + (response_timestamp - request_timestamp) / 2;
+    Some(coordinator_timestamp as i64 - local_timestamp as i64)
+}
+
 /// format timestamp to string
 pub fn fmt_date_time(dur: Duration, fmt: &str) -> String {
     let utl_dt: DateTime<Utc> = (UNIX_EPOCH + dur).into();
@@ -34,13 +89,38 @@ pub fn timestamp_str(timestamp: u64) -> String {
     )
 }
 
+/// parse the RFC 3339 date time with any offset, eg: `2021-06-01T08:00:00.123+08:00`,
+/// to the milliseconds since the unix epoch in UTC
+pub fn parse_rfc3339_millis(s: &str) -> Result<i64, chrono::ParseError> {
+    DateTime::parse_from_rfc3339(s).map(|dt| dt.timestamp_millis())
+}
+
+/// format the milliseconds since the unix epoch to the RFC 3339 date time in UTC
+pub fn fmt_rfc3339_millis(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(timestamp)
+        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::utils::date_time::{current_timestamp, fmt_date_time, FMT_DATE_TIME_1};
+    use crate::utils::date_time::{
+        clock_offset, current_timestamp, fmt_date_time, FMT_DATE_TIME_1,
+    };
 
     #[test]
     pub fn fmt_date_time_test() {
         let s = fmt_date_time(current_timestamp(), FMT_DATE_TIME_1);
         println!("{}", s);
     }
+
+    #[test]
+    pub fn clock_offset_test() {
+        // the coordinator's clock is 500ms ahead, the round trip is 100ms
+        assert_eq!(clock_offset(1000, 1100, 1550), Some(500));
+        assert_eq!(clock_offset(1000, 1100, 550), Some(-500));
+        // the coordinator doesn't report its clock, or the round trip is too long
+        assert_eq!(clock_offset(1000, 1100, 0), None);
+        assert_eq!(clock_offset(1000, 5000, 3000), None);
+    }
 }
@@ -0,0 +1,313 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::core::element::{Record, Serde};
+
+/// the default memory of a sorter before spilling
+pub const DEFAULT_SORT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+type SortedSource = Box<dyn Iterator<Item = (Record, Record)> + Send>;
+
+/// the approximate heap bytes of a buffered `(key, value)`
+fn entry_bytes(key: &Record, value: &Record) -> usize {
+    key.capacity() + value.capacity() + 2 * std::mem::size_of::<Record>()
+}
+
+/// Write the `(key, value)` pairs to a run file, each record is a `u32` length and the
+/// serialized `Record`
+pub(crate) struct RunWriter {
+    writer: BufWriter<File>,
+    buffer: BytesMut,
+}
+
+impl RunWriter {
+    pub fn create<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(RunWriter {
+            writer: BufWriter::new(File::create(path)?),
+            buffer: BytesMut::new(),
+        })
+    }
+
+    pub fn write(&mut self, key: &Record, value: &Record) -> anyhow::Result<()> {
+        self.buffer.clear();
+        for record in &[key, value] {
+            self.buffer.put_u32(record.capacity() as u32);
+            record.serialize(&mut self.buffer);
+        }
+        self.writer.write_all(self.buffer.as_ref())?;
+        Ok(())
+    }
+
+    pub fn finish(self) -> anyhow::Result<()> {
+        self.writer
+            .into_inner()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .sync_all()?;
+        Ok(())
+    }
+}
+
+/// Read the `(key, value)` pairs of a run file, the file is removed when the reader is dropped
+pub struct RunReader {
+    path: PathBuf,
+    reader: BufReader<File>,
+}
+
+impl RunReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let reader = BufReader::new(File::open(&path)?);
+        Ok(RunReader { path, reader })
+    }
+
+    fn read_record(&mut self) -> std::io::Result<Record> {
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len)?;
+        let mut bytes = vec![0u8; u32::from_be_bytes(len) as usize];
+        self.reader.read_exact(bytes.as_mut_slice())?;
+        Ok(Record::deserialize(&mut BytesMut::from(bytes.as_slice())))
+    }
+}
+
+impl Iterator for RunReader {
+    type Item = (Record, Record);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = match self.read_record() {
+            Ok(key) => key,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(e) => panic!("read sorted run {:?} error. {}", self.path, e),
+        };
+        let value = self
+            .read_record()
+            .unwrap_or_else(|e| panic!("read sorted run {:?} error. {}", self.path, e));
+        Some((key, value))
+    }
+}
+
+impl Drop for RunReader {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!("remove sorted run {:?} error. {}", self.path, e);
+        }
+    }
+}
+
+/// Sort the `(key, value)` pairs by the key in a fixed memory. The pairs are buffered until
+/// `memory_limit`, then sorted and spilled to a run file named `{name}.run.{n}` in the `dir`,
+/// the runs are merged on `finish`. The pairs of the same key keep the order of the `push`.
+pub(crate) struct ExternalSorter {
+    dir: PathBuf,
+    name: String,
+    memory_limit: usize,
+
+    buffer: Vec<(Record, Record)>,
+    buffer_bytes: usize,
+    runs: Vec<PathBuf>,
+    entries: usize,
+}
+
+impl ExternalSorter {
+    pub fn new<P: AsRef<Path>>(dir: P, name: &str, memory_limit: usize) -> Self {
+        ExternalSorter {
+            dir: dir.as_ref().to_path_buf(),
+            name: name.to_string(),
+            memory_limit,
+            buffer: Vec::new(),
+            buffer_bytes: 0,
+            runs: Vec::new(),
+            entries: 0,
+        }
+    }
+
+    /// the number of the spilled runs
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// the number of the pushed pairs
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// the approximate bytes of the buffered pairs
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffer_bytes
+    }
+
+    pub fn push(&mut self, key: Record, value: Record) -> anyhow::Result<()> {
+        self.buffer_bytes += entry_bytes(&key, &value);
+        self.buffer.push((key, value));
+        self.entries += 1;
+        if self.buffer_bytes >= self.memory_limit {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// sort the buffered pairs and write them to a new run
+    pub fn spill(&mut self) -> anyhow::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let path = self
+            .dir
+            .join(format!("{}.run.{}", self.name, self.runs.len()));
+        // the stable sort keeps the order of the same key
+        self.buffer.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut writer = RunWriter::create(&path)?;
+        for (key, value) in self.buffer.drain(..) {
+            writer.write(&key, &value)?;
+        }
+        writer.finish()?;
+
+        debug!(
+            "spill sorted run {:?}, {} bytes in memory",
+            path, self.buffer_bytes
+        );
+        self.buffer_bytes = 0;
+        self.runs.push(path);
+        Ok(())
+    }
+
+    /// merge the spilled runs and the buffered pairs in the order of the key
+    pub fn finish(mut self) -> anyhow::Result<SortedIterator> {
+        self.buffer.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut sources: Vec<SortedSource> = Vec::new();
+        for run in self.runs.drain(..) {
+            sources.push(Box::new(RunReader::open(run)?));
+        }
+        // the buffered pairs are pushed after the spilled ones
+        sources.push(Box::new(std::mem::take(&mut self.buffer).into_iter()));
+
+        Ok(SortedIterator::new(sources))
+    }
+}
+
+impl Drop for ExternalSorter {
+    fn drop(&mut self) {
+        for run in &self.runs {
+            let _ = std::fs::remove_file(run);
+        }
+    }
+}
+
+struct HeapEntry {
+    key: Record,
+    value: Record,
+    source: usize,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    /// reversed for the min-heap, the earlier source first for the same key
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .key
+            .cmp(&self.key)
+            .then_with(|| other.source.cmp(&self.source))
+    }
+}
+
+/// The k-way merge of the sorted sources
+pub(crate) struct SortedIterator {
+    sources: Vec<SortedSource>,
+    heap: BinaryHeap<HeapEntry>,
+}
+
+impl SortedIterator {
+    fn new(mut sources: Vec<SortedSource>) -> Self {
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        for (source, iter) in sources.iter_mut().enumerate() {
+            if let Some((key, value)) = iter.next() {
+                heap.push(HeapEntry { key, value, source });
+            }
+        }
+        SortedIterator { sources, heap }
+    }
+}
+
+impl Iterator for SortedIterator {
+    type Item = (Record, Record);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let HeapEntry { key, value, source } = self.heap.pop()?;
+        if let Some((key, value)) = self.sources[source].next() {
+            self.heap.push(HeapEntry { key, value, source });
+        }
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serbuffer::types;
+
+    use crate::core::element::Record;
+    use crate::utils::external_sort::ExternalSorter;
+
+    fn record(value: u32) -> Record {
+        let mut record = Record::new();
+        record.as_writer(&[types::U32]).set_u32(value).unwrap();
+        record
+    }
+
+    fn value(record: &mut Record) -> u32 {
+        record.as_reader(&[types::U32]).get_u32(0).unwrap()
+    }
+
+    #[test]
+    pub fn external_sort_test() {
+        let dir = std::env::temp_dir().join("rlink_external_sort_test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // spill every ~10 pairs
+        let memory_limit = super::entry_bytes(&record(0), &record(0)) * 10;
+        let mut sorter = ExternalSorter::new(&dir, "test", memory_limit);
+        for i in 0..100u32 {
+            sorter.push(record(i * 7 % 13), record(i)).unwrap();
+        }
+        assert_eq!(sorter.spilled_runs(), 10);
+
+        let mut sorted: Vec<(Record, Record)> = sorter.finish().unwrap().collect();
+        assert_eq!(sorted.len(), 100);
+        assert!(sorted.windows(2).all(|x| x[0].0 <= x[1].0));
+        // the values of the same key keep the order of the push
+        let values: Vec<(Record, u32)> = sorted
+            .iter_mut()
+            .map(|(key, val)| (key.clone(), value(val)))
+            .collect();
+        assert!(values
+            .windows(2)
+            .filter(|x| x[0].0 == x[1].0)
+            .all(|x| x[0].1 < x[1].1));
+
+        // the runs are removed after they're read
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
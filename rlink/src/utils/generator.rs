@@ -1,5 +1,130 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
 use crate::utils::date_time::current_timestamp_millis;
 
 pub fn gen_with_ts() -> String {
     format!("{}", current_timestamp_millis())
 }
+
+/// the epoch of the snowflake timestamp, 2021-01-01T00:00:00Z
+pub const SNOWFLAKE_EPOCH_MILLIS: u64 = 1609459200000;
+/// the max worker id of the snowflake, 10 bits
+pub const SNOWFLAKE_MAX_WORKER_ID: u16 = (1 << WORKER_ID_BITS) - 1;
+
+const WORKER_ID_BITS: u64 = 10;
+const SEQUENCE_BITS: u64 = 12;
+const SEQUENCE_MASK: u64 = (1 << SEQUENCE_BITS) - 1;
+const TIMESTAMP_SHIFT: u64 = WORKER_ID_BITS + SEQUENCE_BITS;
+
+lazy_static! {
+    static ref SNOWFLAKE_GENERATORS: Mutex<HashMap<u16, Arc<SnowflakeGenerator>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// the generator of the `worker_id` shared by all tasks of the worker
+pub(crate) fn snowflake_generator(worker_id: u16) -> anyhow::Result<Arc<SnowflakeGenerator>> {
+    let mut generators = SNOWFLAKE_GENERATORS.lock().unwrap();
+    if let Some(generator) = generators.get(&worker_id) {
+        return Ok(generator.clone());
+    }
+
+    let generator = Arc::new(SnowflakeGenerator::new(worker_id)?);
+    generators.insert(worker_id, generator.clone());
+    Ok(generator)
+}
+
+/// A snowflake-style unique id generator without coordination: 41 bits of the milliseconds
+/// since `SNOWFLAKE_EPOCH_MILLIS`, 10 bits of the worker id and 12 bits of the sequence.
+/// The ids of a generator are increasing, when the sequence of a millisecond is exhausted or
+/// the clock goes backwards, the timestamp is borrowed from the next millisecond instead of
+/// blocking, so the timestamp of the id may be slightly ahead of the wall clock.
+#[derive(Debug)]
+pub struct SnowflakeGenerator {
+    worker_id: u64,
+    last_id: AtomicU64,
+}
+
+impl SnowflakeGenerator {
+    pub fn new(worker_id: u16) -> anyhow::Result<Self> {
+        if worker_id > SNOWFLAKE_MAX_WORKER_ID {
+            return Err(anyhow!(
+                "the snowflake worker id {} is over {}",
+                worker_id,
+                SNOWFLAKE_MAX_WORKER_ID
+            ));
+        }
+        Ok(SnowflakeGenerator {
+            worker_id: worker_id as u64,
+            last_id: AtomicU64::new(0),
+        })
+    }
+
+    pub fn next_id(&self) -> u64 {
+        self.next_id_at(current_timestamp_millis())
+    }
+
+    fn next_id_at(&self, timestamp: u64) -> u64 {
+        let timestamp = timestamp.saturating_sub(SNOWFLAKE_EPOCH_MILLIS);
+        let min_id = (timestamp << TIMESTAMP_SHIFT) | (self.worker_id << SEQUENCE_BITS);
+
+        let mut last_id = self.last_id.load(Ordering::Relaxed);
+        loop {
+            let next_id = if last_id & SEQUENCE_MASK == SEQUENCE_MASK {
+                // borrow the next millisecond
+                (((last_id >> TIMESTAMP_SHIFT) + 1) << TIMESTAMP_SHIFT)
+                    | (self.worker_id << SEQUENCE_BITS)
+            } else {
+                last_id + 1
+            };
+            let next_id = next_id.max(min_id);
+
+            match self.last_id.compare_exchange_weak(
+                last_id,
+                next_id,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return next_id,
+                Err(current) => last_id = current,
+            }
+        }
+    }
+}
+
+/// the milliseconds timestamp of the snowflake `id`
+pub fn snowflake_timestamp(id: u64) -> u64 {
+    (id >> TIMESTAMP_SHIFT) + SNOWFLAKE_EPOCH_MILLIS
+}
+
+/// the worker id of the snowflake `id`
+pub fn snowflake_worker_id(id: u64) -> u16 {
+    ((id >> SEQUENCE_BITS) & SNOWFLAKE_MAX_WORKER_ID as u64) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::generator::{snowflake_timestamp, snowflake_worker_id, SnowflakeGenerator};
+
+    #[test]
+    pub fn snowflake_test() {
+        assert!(SnowflakeGenerator::new(1024).is_err());
+
+        let generator = SnowflakeGenerator::new(5).unwrap();
+        let now = 1700000000000;
+        let ids: Vec<u64> = (0..5000).map(|_| generator.next_id_at(now)).collect();
+        assert!(ids.windows(2).all(|x| x[0] < x[1]));
+        assert!(ids.iter().all(|id| snowflake_worker_id(*id) == 5));
+        assert_eq!(snowflake_timestamp(ids[0]), now);
+        // the sequence of a millisecond is exhausted
+        assert_eq!(snowflake_timestamp(ids[4999]), now + 1);
+
+        // the clock goes backwards
+        let id = generator.next_id_at(now - 1000);
+        assert!(id > ids[4999]);
+
+        let other = SnowflakeGenerator::new(6).unwrap().next_id_at(now);
+        assert!(!ids.contains(&other));
+    }
+}
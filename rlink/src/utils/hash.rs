@@ -1,8 +1,100 @@
+//! The hashing of the keys, see `KeyHasher`.
+//!
+//! A key is routed by its key group, the groups are fixed regardless of the parallelism and
+//! assigned to the tasks in contiguous ranges, so the routing is stable across the versions and
+//! can be predicted by the external systems in other languages:
+//! ```text
+//! hash        = murmur3_x86_32(key_bytes, seed = 0x19264330)    // or XXH32 with the same seed
+//! key_group   = hash % KEY_GROUPS                               // KEY_GROUPS = 32768
+//! partition   = key_group * parallelism / KEY_GROUPS
+//! ```
+//! The `key_bytes` are the serialized bytes of the key `Record`, ie: `Record::values`.
+
+use std::hash::Hasher;
 use std::io::Cursor;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 use murmur3::*;
 
+/// the seed of the hash functions
+pub const HASH_SEED: u32 = 0x19264330;
+
+/// the fixed number of the key groups, the max parallelism of the keyed operators
+pub const KEY_GROUPS: u16 = 32768;
+
+static KEY_HASHER: AtomicU8 = AtomicU8::new(KeyHasher::Murmur3 as u8);
+
+/// The hash function of the keys, all tasks of an application must use the same one and it
+/// can't be changed when restoring from a checkpoint, the state is partitioned by the key groups.
+/// set by `SystemProperties::set_key_hasher`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyHasher {
+    /// the 32-bit murmur3 of x86, `MurmurHash3_x86_32`
+    #[default]
+    Murmur3 = 0,
+    /// the 32-bit xxHash, `XXH32`
+    XxHash32 = 1,
+}
+
+impl KeyHasher {
+    pub fn hash(&self, v: &[u8]) -> u32 {
+        match self {
+            KeyHasher::Murmur3 => hash_code(v).unwrap_or(0),
+            KeyHasher::XxHash32 => {
+                let mut hasher = twox_hash::XxHash32::with_seed(HASH_SEED);
+                hasher.write(v);
+                hasher.finish() as u32
+            }
+        }
+    }
+
+    /// the hasher of the worker, see `set_key_hasher`
+    pub fn current() -> Self {
+        match KEY_HASHER.load(Ordering::Relaxed) {
+            1 => KeyHasher::XxHash32,
+            _ => KeyHasher::Murmur3,
+        }
+    }
+}
+
+/// set the hasher of the worker, the worker sets it from the application properties on starting
+pub(crate) fn set_key_hasher(key_hasher: KeyHasher) {
+    KEY_HASHER.store(key_hasher as u8, Ordering::Relaxed);
+}
+
 pub fn hash_code(v: &[u8]) -> std::io::Result<u32> {
     let mut cursor = Cursor::new(v);
-    murmur3_32(&mut cursor, 0x19264330)
+    murmur3_32(&mut cursor, HASH_SEED)
+}
+
+/// the key group of the serialized key by the hasher of the worker
+pub fn key_group(key: &[u8]) -> u16 {
+    (KeyHasher::current().hash(key) % KEY_GROUPS as u32) as u16
+}
+
+/// the partition of the key group, the key groups are assigned to the partitions in contiguous
+/// ranges, so the groups of a partition are kept together when rescaling
+pub fn key_group_partition(key_group: u16, parallelism: u16) -> u16 {
+    (key_group as u32 * parallelism as u32 / KEY_GROUPS as u32) as u16
+}
+
+/// the partition of the serialized key
+pub fn key_partition(key: &[u8], parallelism: u16) -> u16 {
+    key_group_partition(key_group(key), parallelism)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::hash::{key_group_partition, KeyHasher, KEY_GROUPS};
+
+    #[test]
+    pub fn key_hasher_test() {
+        // the well-known values of the hash functions keep the routing stable across versions
+        assert_eq!(KeyHasher::Murmur3.hash(b"rlink"), 454138966);
+        assert_eq!(KeyHasher::XxHash32.hash(b"rlink"), 3879239500);
+
+        assert_eq!(key_group_partition(0, 3), 0);
+        assert_eq!(key_group_partition(KEY_GROUPS - 1, 3), 2);
+        assert_eq!(key_group_partition(KEY_GROUPS / 2, 2), 1);
+    }
 }
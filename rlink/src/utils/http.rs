@@ -118,4 +118,56 @@ pub mod client {
 
         Ok(s)
     }
+
+    pub fn get_bytes_sync(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = url.to_string();
+        async_runtime_single().block_on(get_bytes(url.as_str()))
+    }
+
+    /// get the binary body, the unsuccessful status is an error
+    pub async fn get_bytes(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let client = Client::new();
+
+        let req = Request::builder()
+            .method("GET")
+            .uri(url)
+            .body(Body::default())?;
+        let res = client.request(req).await?;
+
+        if !res.status().is_success() {
+            return Err(format!("unexpected http status {}", res.status()).into());
+        }
+
+        let result = hyper::body::to_bytes(res).await?;
+        Ok(result.to_vec())
+    }
+
+    /// post the json body to a http or https url, the response body is ignored
+    pub fn post_json_sync(
+        url: &str,
+        body: String,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let url = url.to_string();
+        async_runtime_single().block_on(post_json(url.as_str(), body))
+    }
+
+    pub async fn post_json(
+        url: &str,
+        body: String,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let client = Client::builder().build::<_, Body>(hyper_tls::HttpsConnector::new());
+
+        let req = Request::builder()
+            .method("POST")
+            .uri(url)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body))?;
+        let res = client.request(req).await?;
+
+        if !res.status().is_success() {
+            return Err(format!("unexpected http status {}", res.status()).into());
+        }
+
+        Ok(())
+    }
 }
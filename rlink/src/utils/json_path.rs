@@ -0,0 +1,130 @@
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// A subset of the JSONPath to select one value, support the object member `.name`,
+/// `['name']` and the array index `[0]`, eg: `$.user.tags[0]`, `$['first name']`.
+/// The leading `$` is optional.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// the selected value, `None` if any segment of the path is missing
+    pub fn select<'a>(&self, json: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+        let mut value = json;
+        for segment in &self.segments {
+            value = match segment {
+                Segment::Key(key) => value.as_object()?.get(key)?,
+                Segment::Index(index) => value.as_array()?.get(*index)?,
+            };
+        }
+        Some(value)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = crate::core::Error;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            crate::core::Error::from(format!("invalid json path `{}`, {}", path, reason))
+        };
+
+        let mut chars = path.strip_prefix('$').unwrap_or(path).chars().peekable();
+        let mut segments = Vec::new();
+        // a path without `$` can start with a member name
+        let mut expect_member = !path.starts_with('$');
+        while let Some(c) = chars.peek().cloned() {
+            if expect_member || c == '.' {
+                if !expect_member {
+                    chars.next();
+                }
+                expect_member = false;
+
+                let mut key = String::new();
+                while let Some(c) = chars.peek().cloned() {
+                    if c == '.' || c == '[' {
+                        break;
+                    }
+                    key.push(c);
+                    chars.next();
+                }
+                if key.is_empty() {
+                    return Err(invalid("empty member name"));
+                }
+                segments.push(Segment::Key(key));
+            } else if c == '[' {
+                chars.next();
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']')
+                            if !token.starts_with('\'')
+                                || (token.len() > 1 && token.ends_with('\'')) =>
+                        {
+                            break
+                        }
+                        Some(c) => token.push(c),
+                        None => return Err(invalid("unclosed `[`")),
+                    }
+                }
+
+                let segment =
+                    if token.len() >= 2 && token.starts_with('\'') && token.ends_with('\'') {
+                        Segment::Key(token[1..token.len() - 1].to_string())
+                    } else {
+                        let index = usize::from_str(token.trim())
+                            .map_err(|_e| invalid("invalid array index"))?;
+                        Segment::Index(index)
+                    };
+                segments.push(segment);
+            } else {
+                return Err(invalid("unexpected character"));
+            }
+        }
+
+        Ok(JsonPath { segments })
+    }
+}
+
+impl<'a> TryFrom<&'a str> for JsonPath {
+    type Error = crate::core::Error;
+
+    fn try_from(path: &'a str) -> Result<Self, Self::Error> {
+        JsonPath::from_str(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::utils::json_path::JsonPath;
+
+    #[test]
+    pub fn json_path_test() {
+        let json = serde_json::json!({
+            "user": {"name": "rlink", "tags": ["a", "b"]},
+            "first name": "x",
+        });
+
+        let select = |path: &str| JsonPath::from_str(path).unwrap().select(&json).cloned();
+        assert_eq!(select("$.user.name"), Some(serde_json::json!("rlink")));
+        assert_eq!(select("user.tags[1]"), Some(serde_json::json!("b")));
+        assert_eq!(select("$['first name']"), Some(serde_json::json!("x")));
+        assert_eq!(select("$"), Some(json.clone()));
+        assert_eq!(select("$.user.tags[2]"), None);
+        assert_eq!(select("$.user.name.first"), None);
+
+        assert!(JsonPath::from_str("$.user[").is_err());
+        assert!(JsonPath::from_str("$.user..name").is_err());
+        assert!(JsonPath::from_str("$.user[x]").is_err());
+    }
+}
@@ -1,9 +1,14 @@
+pub mod aws;
 pub mod date_time;
+pub mod external_sort;
 pub mod fs;
 pub mod generator;
 pub mod hash;
 pub mod http;
 pub mod ip;
+pub mod json_path;
+pub mod mmap;
+pub mod object_store;
 pub mod panic;
 pub mod process;
 pub mod thread;
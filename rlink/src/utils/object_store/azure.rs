@@ -0,0 +1,125 @@
+use hyper::header::HeaderValue;
+use hyper::{Body, Request};
+
+use crate::utils::object_store::{
+    uri_encode, xml_elements, xml_value, HttpClient, ObjectMeta, ObjectStore,
+};
+
+const API_VERSION: &str = "2020-04-08";
+
+/// Azure Blob Storage authorized by a SAS token of the container
+pub struct AzureBlobObjectStore {
+    container: String,
+    /// the SAS token without the leading `?`
+    sas_token: String,
+    endpoint: String,
+    client: HttpClient,
+}
+
+impl AzureBlobObjectStore {
+    /// the `endpoint` is `https://{account}.blob.core.windows.net` or the emulator's
+    pub fn new(container: &str, sas_token: &str, endpoint: &str) -> Self {
+        AzureBlobObjectStore {
+            container: container.to_string(),
+            sas_token: sas_token.trim_start_matches('?').to_string(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            client: HttpClient::new(),
+        }
+    }
+
+    /// read the SAS token from `AZURE_STORAGE_SAS_TOKEN`, the endpoint from
+    /// `AZURE_STORAGE_ENDPOINT` or the account from `AZURE_STORAGE_ACCOUNT`
+    pub fn from_env(container: &str) -> anyhow::Result<Self> {
+        let sas_token = std::env::var("AZURE_STORAGE_SAS_TOKEN")
+            .map_err(|_e| anyhow!("env `AZURE_STORAGE_SAS_TOKEN` not found"))?;
+        let endpoint = match std::env::var("AZURE_STORAGE_ENDPOINT") {
+            Ok(endpoint) => endpoint,
+            Err(_e) => {
+                let account = std::env::var("AZURE_STORAGE_ACCOUNT")
+                    .map_err(|_e| anyhow!("env `AZURE_STORAGE_ACCOUNT` not found"))?;
+                format!("https://{}.blob.core.windows.net", account)
+            }
+        };
+        Ok(AzureBlobObjectStore::new(
+            container,
+            sas_token.as_str(),
+            endpoint.as_str(),
+        ))
+    }
+
+    fn blob_url(&self, path: &str) -> String {
+        format!(
+            "{}/{}/{}?{}",
+            self.endpoint,
+            self.container,
+            uri_encode(path, true),
+            self.sas_token
+        )
+    }
+
+    fn request(&self, method: &str, uri: String, body: Vec<u8>) -> anyhow::Result<Request<Body>> {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-ms-version", API_VERSION)
+            .body(Body::from(body))?;
+        Ok(req)
+    }
+}
+
+impl ObjectStore for AzureBlobObjectStore {
+    fn get(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let req = self.request("GET", self.blob_url(path), vec![])?;
+        self.client.send_checked("get", req)
+    }
+
+    fn put(&self, path: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let mut req = self.request("PUT", self.blob_url(path), data)?;
+        req.headers_mut()
+            .insert("x-ms-blob-type", HeaderValue::from_static("BlockBlob"));
+        self.client.send_checked("put", req).map(|_| ())
+    }
+
+    fn list(&self, prefix: &str) -> anyhow::Result<Vec<ObjectMeta>> {
+        let mut objects = Vec::new();
+        let mut marker: Option<String> = None;
+        loop {
+            let mut uri = format!(
+                "{}/{}?restype=container&comp=list&prefix={}&{}",
+                self.endpoint,
+                self.container,
+                uri_encode(prefix, false),
+                self.sas_token
+            );
+            if let Some(marker) = &marker {
+                uri = format!("{}&marker={}", uri, uri_encode(marker, false));
+            }
+
+            let req = self.request("GET", uri, vec![])?;
+            let body = match self.client.send_checked("list", req)? {
+                Some(body) => body,
+                None => return Err(anyhow!("azure container {} not found", self.container)),
+            };
+            let xml = String::from_utf8(body)?;
+
+            for blob in xml_elements(xml.as_str(), "Blob") {
+                let path = xml_value(blob, "Name").unwrap_or_default();
+                let size = xml_value(blob, "Content-Length")
+                    .and_then(|x| x.parse().ok())
+                    .unwrap_or_default();
+                objects.push(ObjectMeta { path, size });
+            }
+
+            // the `<NextMarker />` is empty at the last page
+            marker = xml_value(xml.as_str(), "NextMarker").filter(|x| !x.is_empty());
+            if marker.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    fn delete(&self, path: &str) -> anyhow::Result<()> {
+        let req = self.request("DELETE", self.blob_url(path), vec![])?;
+        self.client.send_checked("delete", req).map(|_| ())
+    }
+}
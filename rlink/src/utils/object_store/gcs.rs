@@ -0,0 +1,129 @@
+use hyper::{Body, Request};
+
+use crate::utils::object_store::{uri_encode, HttpClient, ObjectMeta, ObjectStore};
+
+const DEFAULT_ENDPOINT: &str = "https://storage.googleapis.com";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsObject {
+    name: String,
+    /// the uint64 is formatted as a string in the json api
+    #[serde(default)]
+    size: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsObjectList {
+    #[serde(default)]
+    items: Vec<GcsObject>,
+    next_page_token: Option<String>,
+}
+
+/// Google Cloud Storage by the json api with an OAuth2 access token
+pub struct GcsObjectStore {
+    bucket: String,
+    access_token: String,
+    endpoint: String,
+    client: HttpClient,
+}
+
+impl GcsObjectStore {
+    pub fn new(bucket: &str, access_token: &str, endpoint: Option<String>) -> Self {
+        GcsObjectStore {
+            bucket: bucket.to_string(),
+            access_token: access_token.to_string(),
+            endpoint: endpoint
+                .map(|x| x.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string()),
+            client: HttpClient::new(),
+        }
+    }
+
+    /// read the access token from `GOOGLE_OAUTH_ACCESS_TOKEN` and the endpoint from
+    /// `GCS_ENDPOINT`, eg: `gcloud auth print-access-token`
+    pub fn from_env(bucket: &str) -> anyhow::Result<Self> {
+        let access_token = std::env::var("GOOGLE_OAUTH_ACCESS_TOKEN")
+            .map_err(|_e| anyhow!("env `GOOGLE_OAUTH_ACCESS_TOKEN` not found"))?;
+        let endpoint = std::env::var("GCS_ENDPOINT").ok();
+        Ok(GcsObjectStore::new(bucket, access_token.as_str(), endpoint))
+    }
+
+    fn object_url(&self, path: &str) -> String {
+        format!(
+            "{}/storage/v1/b/{}/o/{}",
+            self.endpoint,
+            self.bucket,
+            uri_encode(path, false)
+        )
+    }
+
+    fn request(&self, method: &str, uri: String, body: Vec<u8>) -> anyhow::Result<Request<Body>> {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", format!("Bearer {}", self.access_token))
+            .body(Body::from(body))?;
+        Ok(req)
+    }
+}
+
+impl ObjectStore for GcsObjectStore {
+    fn get(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let uri = format!("{}?alt=media", self.object_url(path));
+        let req = self.request("GET", uri, vec![])?;
+        self.client.send_checked("get", req)
+    }
+
+    fn put(&self, path: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let uri = format!(
+            "{}/upload/storage/v1/b/{}/o?uploadType=media&name={}",
+            self.endpoint,
+            self.bucket,
+            uri_encode(path, false)
+        );
+        let req = self.request("POST", uri, data)?;
+        self.client.send_checked("put", req).map(|_| ())
+    }
+
+    fn list(&self, prefix: &str) -> anyhow::Result<Vec<ObjectMeta>> {
+        let mut objects = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut uri = format!(
+                "{}/storage/v1/b/{}/o?prefix={}",
+                self.endpoint,
+                self.bucket,
+                uri_encode(prefix, false)
+            );
+            if let Some(token) = &page_token {
+                uri = format!("{}&pageToken={}", uri, uri_encode(token, false));
+            }
+
+            let req = self.request("GET", uri, vec![])?;
+            let body = self.client.send_checked("list", req)?;
+            let list: GcsObjectList = match body {
+                Some(body) => serde_json::from_slice(body.as_slice())?,
+                None => return Err(anyhow!("gcs bucket {} not found", self.bucket)),
+            };
+
+            for item in list.items {
+                objects.push(ObjectMeta {
+                    path: item.name,
+                    size: item.size.parse().unwrap_or_default(),
+                });
+            }
+
+            page_token = list.next_page_token;
+            if page_token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    fn delete(&self, path: &str) -> anyhow::Result<()> {
+        let req = self.request("DELETE", self.object_url(path), vec![])?;
+        self.client.send_checked("delete", req).map(|_| ())
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::utils::object_store::{ObjectMeta, ObjectStore};
+
+/// The local file system as an object store, the object paths are the file paths
+#[derive(Default)]
+pub struct LocalObjectStore {}
+
+impl LocalObjectStore {
+    pub fn new() -> Self {
+        LocalObjectStore {}
+    }
+
+    fn walk(dir: &Path, files: &mut Vec<ObjectMeta>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                Self::walk(entry.path().as_path(), files)?;
+            } else {
+                files.push(ObjectMeta {
+                    path: entry.path().to_string_lossy().to_string(),
+                    size: metadata.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ObjectStore for LocalObjectStore {
+    fn get(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match std::fs::read(path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow!(e)),
+        }
+    }
+
+    fn put(&self, path: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        if let Some(dir) = Path::new(path).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        // write to a temporary file and rename, the readers never see the partial object
+        let tmp_path = format!("{}.tmp", path);
+        std::fs::write(tmp_path.as_str(), data)?;
+        std::fs::rename(tmp_path.as_str(), path)?;
+        Ok(())
+    }
+
+    fn list(&self, prefix: &str) -> anyhow::Result<Vec<ObjectMeta>> {
+        let prefix_path = PathBuf::from(prefix);
+        let dir = if prefix_path.is_dir() {
+            prefix_path.as_path()
+        } else {
+            match prefix_path.parent() {
+                Some(dir) => dir,
+                None => return Ok(vec![]),
+            }
+        };
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut files = Vec::new();
+        Self::walk(dir, &mut files)?;
+        Ok(files
+            .into_iter()
+            .filter(|x| x.path.starts_with(prefix))
+            .collect())
+    }
+
+    fn delete(&self, path: &str) -> anyhow::Result<()> {
+        match std::fs::remove_file(path) {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(anyhow!(e)),
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! A minimal blocking object storage abstraction, the backend is chosen by the scheme of the uri:
+//! - `file:///path/to/dir` or `/path/to/dir`, the local file system
+//! - `s3://bucket/path`, AWS S3 or the S3 compatible storages
+//! - `gs://bucket/path`, Google Cloud Storage
+//! - `az://container/path`, Azure Blob Storage
+//!
+//! The calls block the current thread, don't call them in the async context.
+
+use std::sync::Arc;
+
+use hyper::{Body, Client, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+
+use crate::utils::object_store::azure::AzureBlobObjectStore;
+use crate::utils::object_store::gcs::GcsObjectStore;
+use crate::utils::object_store::local::LocalObjectStore;
+use crate::utils::object_store::s3::S3ObjectStore;
+use crate::utils::thread::{shared_runtime, DEFAULT_SHARED_RUNTIME_THREADS};
+
+pub mod azure;
+pub mod gcs;
+pub mod local;
+pub mod s3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectMeta {
+    /// the full path of the object in the bucket
+    pub path: String,
+    pub size: u64,
+}
+
+pub trait ObjectStore: Send + Sync {
+    /// get the content of the object, `None` if the object not found
+    fn get(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// create or overwrite the object
+    fn put(&self, path: &str, data: Vec<u8>) -> anyhow::Result<()>;
+
+    /// list all objects whose path start with the `prefix`
+    fn list(&self, prefix: &str) -> anyhow::Result<Vec<ObjectMeta>>;
+
+    /// delete the object, not found is ok
+    fn delete(&self, path: &str) -> anyhow::Result<()>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectStoreScheme {
+    Local,
+    S3,
+    Gcs,
+    Azure,
+}
+
+/// The parsed object store uri, eg: `s3://bucket/path/to/dir`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectStoreUrl {
+    pub scheme: ObjectStoreScheme,
+    /// the bucket of S3 and GCS, the container of Azure, empty for the local file system
+    pub bucket: String,
+    /// the path in the bucket without the leading and trailing `/`,
+    /// the local path is kept as it is except the trailing `/`
+    pub path: String,
+}
+
+impl ObjectStoreUrl {
+    pub fn parse(uri: &str) -> anyhow::Result<Self> {
+        let (scheme, rest) = match uri.find("://") {
+            Some(pos) => (&uri[..pos], &uri[pos + 3..]),
+            None => ("file", uri),
+        };
+
+        let scheme = match scheme {
+            "file" => {
+                return Ok(ObjectStoreUrl {
+                    scheme: ObjectStoreScheme::Local,
+                    bucket: "".to_string(),
+                    path: rest.trim_end_matches('/').to_string(),
+                });
+            }
+            "s3" | "s3a" => ObjectStoreScheme::S3,
+            "gs" | "gcs" => ObjectStoreScheme::Gcs,
+            "az" | "azure" => ObjectStoreScheme::Azure,
+            _ => return Err(anyhow!("unsupported object store scheme `{}`", scheme)),
+        };
+
+        let (bucket, path) = match rest.find('/') {
+            Some(pos) => (&rest[..pos], &rest[pos + 1..]),
+            None => (rest, ""),
+        };
+        if bucket.is_empty() {
+            return Err(anyhow!("the bucket of `{}` not found", uri));
+        }
+
+        Ok(ObjectStoreUrl {
+            scheme,
+            bucket: bucket.to_string(),
+            path: path.trim_matches('/').to_string(),
+        })
+    }
+
+    /// build the store of the bucket, the credentials are read from the env of each backend
+    pub fn object_store(&self) -> anyhow::Result<Arc<dyn ObjectStore>> {
+        let store: Arc<dyn ObjectStore> = match self.scheme {
+            ObjectStoreScheme::Local => Arc::new(LocalObjectStore::new()),
+            ObjectStoreScheme::S3 => Arc::new(S3ObjectStore::from_env(self.bucket.as_str())?),
+            ObjectStoreScheme::Gcs => Arc::new(GcsObjectStore::from_env(self.bucket.as_str())?),
+            ObjectStoreScheme::Azure => {
+                Arc::new(AzureBlobObjectStore::from_env(self.bucket.as_str())?)
+            }
+        };
+        Ok(store)
+    }
+}
+
+/// parse the `uri` and build its store, return the store and the path of the `uri` in the store
+pub fn object_store(uri: &str) -> anyhow::Result<(Arc<dyn ObjectStore>, String)> {
+    let url = ObjectStoreUrl::parse(uri)?;
+    let store = url.object_store()?;
+    Ok((store, url.path))
+}
+
+/// join the `name` to the `path` by `/`
+pub fn join_path(path: &str, name: &str) -> String {
+    let path = path.trim_end_matches('/');
+    let name = name.trim_start_matches('/');
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", path, name)
+    }
+}
+
+/// percent-encode all characters except the unreserved `A-Za-z0-9-_.~`, and `/` if `keep_slash`
+pub(crate) fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(format!("%{:02X}", b).as_str()),
+        }
+    }
+    encoded
+}
+
+/// the raw content of all `<tag>` elements in the xml
+pub(crate) fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let begin = format!("<{}>", tag);
+    let end = format!("</{}>", tag);
+
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(pos) = rest.find(begin.as_str()) {
+        rest = &rest[pos + begin.len()..];
+        match rest.find(end.as_str()) {
+            Some(pos) => {
+                elements.push(&rest[..pos]);
+                rest = &rest[pos + end.len()..];
+            }
+            None => break,
+        }
+    }
+    elements
+}
+
+/// the unescaped text of the first `<tag>` element in the xml
+pub(crate) fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    xml_elements(xml, tag).first().map(|x| xml_unescape(x))
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The blocking http client shared by the cloud stores, the requests are run on the
+/// shared async runtime of the worker
+pub(crate) struct HttpClient {
+    client: Client<HttpsConnector<hyper::client::HttpConnector>, Body>,
+    runtime: tokio::runtime::Handle,
+}
+
+impl HttpClient {
+    pub fn new() -> Self {
+        HttpClient {
+            client: Client::builder().build::<_, Body>(HttpsConnector::new()),
+            runtime: shared_runtime(DEFAULT_SHARED_RUNTIME_THREADS),
+        }
+    }
+
+    pub fn send(&self, req: Request<Body>) -> anyhow::Result<(StatusCode, Vec<u8>)> {
+        self.runtime.block_on(async {
+            let res = self.client.request(req).await?;
+            let status = res.status();
+            let bytes = hyper::body::to_bytes(res.into_body()).await?;
+            Ok((status, bytes.to_vec()))
+        })
+    }
+
+    /// send the request and check the status is success, `None` if the object not found
+    pub fn send_checked(
+        &self,
+        operation: &str,
+        req: Request<Body>,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let (status, body) = self.send(req)?;
+        if status == StatusCode::NOT_FOUND {
+            Ok(None)
+        } else if status.is_success() {
+            Ok(Some(body))
+        } else {
+            Err(anyhow!(
+                "object store {} error, status {}, {}",
+                operation,
+                status,
+                String::from_utf8_lossy(body.as_slice())
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::object_store::{
+        join_path, uri_encode, xml_elements, xml_value, ObjectStoreScheme, ObjectStoreUrl,
+    };
+
+    #[test]
+    pub fn object_store_url_test() {
+        let url = ObjectStoreUrl::parse("s3://bucket/ck/app/").unwrap();
+        assert_eq!(url.scheme, ObjectStoreScheme::S3);
+        assert_eq!(url.bucket, "bucket");
+        assert_eq!(url.path, "ck/app");
+
+        let url = ObjectStoreUrl::parse("gs://bucket").unwrap();
+        assert_eq!(url.scheme, ObjectStoreScheme::Gcs);
+        assert_eq!(url.path, "");
+
+        let url = ObjectStoreUrl::parse("/tmp/ck").unwrap();
+        assert_eq!(url.scheme, ObjectStoreScheme::Local);
+        assert_eq!(url.path, "/tmp/ck");
+
+        assert!(ObjectStoreUrl::parse("hdfs://nn/ck").is_err());
+        assert!(ObjectStoreUrl::parse("az:///ck").is_err());
+
+        assert_eq!(join_path("ck/", "/a.json"), "ck/a.json");
+        assert_eq!(join_path("", "a.json"), "a.json");
+        assert_eq!(uri_encode("a b/c=", true), "a%20b/c%3D");
+
+        let xml = "<R><C><Key>a&amp;b</Key></C><C><Key>c</Key></C></R>";
+        let elements = xml_elements(xml, "C");
+        assert_eq!(elements.len(), 2);
+        assert_eq!(xml_value(elements[0], "Key"), Some("a&b".to_string()));
+    }
+}
@@ -0,0 +1,179 @@
+use chrono::Utc;
+use hyper::{Body, Request};
+use openssl::sha::sha256;
+
+use crate::utils::aws::{sign_v4, AwsCredentials};
+use crate::utils::object_store::{
+    uri_encode, xml_elements, xml_value, HttpClient, ObjectMeta, ObjectStore,
+};
+
+const S3_SERVICE: &str = "s3";
+
+/// AWS S3 or the S3 compatible storages, eg: MinIO.
+/// The requests are signed by the AWS Signature Version 4.
+pub struct S3ObjectStore {
+    bucket: String,
+    region: String,
+    credentials: AwsCredentials,
+    /// the path-style endpoint of the S3 compatible storages,
+    /// the virtual-hosted-style `https://{bucket}.s3.{region}.amazonaws.com` if `None`
+    endpoint: Option<String>,
+    client: HttpClient,
+}
+
+impl S3ObjectStore {
+    pub fn new(
+        bucket: &str,
+        region: &str,
+        credentials: AwsCredentials,
+        endpoint: Option<String>,
+    ) -> Self {
+        S3ObjectStore {
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            credentials,
+            endpoint: endpoint.map(|x| x.trim_end_matches('/').to_string()),
+            client: HttpClient::new(),
+        }
+    }
+
+    /// read the credentials by `AwsCredentials::from_env`, the region from `AWS_REGION`
+    /// (default `us-east-1`) and the endpoint from `AWS_ENDPOINT_URL`
+    pub fn from_env(bucket: &str) -> anyhow::Result<Self> {
+        let credentials = AwsCredentials::from_env()?;
+        let region = std::env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let endpoint = std::env::var("AWS_ENDPOINT_URL").ok();
+        Ok(S3ObjectStore::new(
+            bucket,
+            region.as_str(),
+            credentials,
+            endpoint,
+        ))
+    }
+
+    /// return the base url and the host
+    fn base_url(&self) -> (String, String) {
+        match &self.endpoint {
+            Some(endpoint) => {
+                let host = endpoint.split("://").nth(1).unwrap_or(endpoint.as_str());
+                (format!("{}/{}", endpoint, self.bucket), host.to_string())
+            }
+            None => {
+                let host = format!("{}.s3.{}.amazonaws.com", self.bucket, self.region);
+                (format!("https://{}", host), host)
+            }
+        }
+    }
+
+    /// build the signed request, the `query` pairs are sorted by the name
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        query: &[(&str, &str)],
+        payload: Vec<u8>,
+    ) -> anyhow::Result<Request<Body>> {
+        let (base_url, host) = self.base_url();
+        let path = format!("/{}", uri_encode(path, true));
+        let query = query
+            .iter()
+            .map(|(name, value)| {
+                format!("{}={}", uri_encode(name, false), uri_encode(value, false))
+            })
+            .collect::<Vec<String>>()
+            .join("&");
+        let canonical_path = match &self.endpoint {
+            Some(_) => format!("/{}{}", self.bucket, path),
+            None => path.clone(),
+        };
+
+        let now = Utc::now();
+        let mut headers = vec![
+            ("host".to_string(), host),
+            (
+                "x-amz-content-sha256".to_string(),
+                hex::encode(sha256(payload.as_slice())),
+            ),
+            (
+                "x-amz-date".to_string(),
+                now.format("%Y%m%dT%H%M%SZ").to_string(),
+            ),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+
+        let authorization = sign_v4(
+            &self.credentials,
+            self.region.as_str(),
+            S3_SERVICE,
+            method,
+            canonical_path.as_str(),
+            query.as_str(),
+            headers.as_slice(),
+            payload.as_slice(),
+            now,
+        );
+
+        let uri = if query.is_empty() {
+            format!("{}{}", base_url, path)
+        } else {
+            format!("{}{}?{}", base_url, path, query)
+        };
+        let mut builder = Request::builder().method(method).uri(uri);
+        for (name, value) in headers.iter().filter(|(name, _)| name.ne("host")) {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let req = builder
+            .header("authorization", authorization)
+            .body(Body::from(payload))?;
+        Ok(req)
+    }
+}
+
+impl ObjectStore for S3ObjectStore {
+    fn get(&self, path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let req = self.request("GET", path, &[], vec![])?;
+        self.client.send_checked("get", req)
+    }
+
+    fn put(&self, path: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let req = self.request("PUT", path, &[], data)?;
+        self.client.send_checked("put", req).map(|_| ())
+    }
+
+    fn list(&self, prefix: &str) -> anyhow::Result<Vec<ObjectMeta>> {
+        let mut objects = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = Vec::new();
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            query.push(("list-type", "2"));
+            query.push(("prefix", prefix));
+
+            let req = self.request("GET", "", query.as_slice(), vec![])?;
+            let body = self.client.send_checked("list", req)?.unwrap_or_default();
+            let xml = String::from_utf8(body)?;
+
+            for contents in xml_elements(xml.as_str(), "Contents") {
+                let path = xml_value(contents, "Key").unwrap_or_default();
+                let size = xml_value(contents, "Size")
+                    .and_then(|x| x.parse().ok())
+                    .unwrap_or_default();
+                objects.push(ObjectMeta { path, size });
+            }
+
+            continuation_token = xml_value(xml.as_str(), "NextContinuationToken");
+            if continuation_token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    fn delete(&self, path: &str) -> anyhow::Result<()> {
+        let req = self.request("DELETE", path, &[], vec![])?;
+        self.client.send_checked("delete", req).map(|_| ())
+    }
+}
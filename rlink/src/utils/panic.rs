@@ -1,35 +1,111 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::panic::PanicHookInfo;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 static PANIC_CAPTURE: AtomicBool = AtomicBool::new(false);
 
+thread_local! {
+    /// the depth of the `suppress_panic_report` scopes of the thread
+    static SUPPRESS_DEPTH: Cell<u32> = const { Cell::new(0) };
+    /// the report of the last panic suppressed in the thread
+    static SUPPRESSED_REPORT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
 pub fn is_panic() -> bool {
     PANIC_CAPTURE.load(Ordering::SeqCst)
 }
 
+/// report the `Panic` status to the coordinator without unwinding, eg: a task is stuck
+pub(crate) fn mark_panic() {
+    PANIC_CAPTURE.store(true, Ordering::SeqCst);
+}
+
 pub fn panic_notify() {
-    std::panic::set_hook(Box::new(|panic_info| {
-        PANIC_CAPTURE.store(true, Ordering::SeqCst);
-
-        eprintln!(
-            "thread: {:?}\npanic_info: {:?}\nbacktrace: \n{:?}",
-            std::thread::current().name(),
-            panic_info,
-            backtrace::Backtrace::new()
-        );
-        if let Some(s) = panic_info.payload().downcast_ref::<&str>() {
-            eprintln!("panic occurred: {:?}", s);
-        } else {
-            eprintln!("panic occurred:");
-        }
-
-        if let Some(location) = panic_info.location() {
-            eprintln!(
-                "panic occurred in file '{}' at line {}",
+    std::panic::set_hook(Box::new(|panic_info| on_panic(&PANIC_CAPTURE, panic_info)));
+}
+
+/// the hook of the panics, the panic is reported and flags the worker by the `capture`,
+/// unless it's raised in a `suppress_panic_report` scope
+pub(crate) fn on_panic(capture: &AtomicBool, panic_info: &PanicHookInfo) {
+    let report = panic_report(panic_info);
+    if SUPPRESS_DEPTH.with(|depth| depth.get()) > 0 {
+        SUPPRESSED_REPORT.with(|suppressed| *suppressed.borrow_mut() = Some(report));
+    } else {
+        capture.store(true, Ordering::SeqCst);
+        eprintln!("{}", report);
+    }
+}
+
+fn panic_report(panic_info: &PanicHookInfo) -> String {
+    let mut report = format!(
+        "thread: {:?}\npanic_info: {:?}\nbacktrace: \n{:?}",
+        std::thread::current().name(),
+        panic_info,
+        backtrace::Backtrace::new()
+    );
+    if let Some(s) = panic_info.payload().downcast_ref::<&str>() {
+        report.push_str(format!("\npanic occurred: {:?}", s).as_str());
+    } else {
+        report.push_str("\npanic occurred:");
+    }
+
+    if let Some(location) = panic_info.location() {
+        report.push_str(
+            format!(
+                "\npanic occurred in file '{}' at line {}",
                 location.file(),
                 location.line()
-            );
-        } else {
-            eprintln!("panic occurred but can't get location information...");
-        }
-    }));
+            )
+            .as_str(),
+        );
+    } else {
+        report.push_str("\npanic occurred but can't get location information...");
+    }
+    report
+}
+
+struct SuppressGuard;
+
+impl Drop for SuppressGuard {
+    fn drop(&mut self) {
+        SUPPRESS_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// call `f` with its panics kept from the hook, so the panics handled by the caller,
+/// eg: the transient failures of a retried call, don't flag the worker. The caller reports
+/// the last one by `report_suppressed_panic` if it gives up
+pub(crate) fn suppress_panic_report<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    SUPPRESS_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let _guard = SuppressGuard;
+    f()
+}
+
+/// report the last panic suppressed in the thread and flag the worker
+pub(crate) fn report_suppressed_panic() {
+    if let Some(report) = discard_suppressed_panic() {
+        eprintln!("{}", report);
+    }
+    mark_panic();
+}
+
+/// take the report of the last panic suppressed in the thread, eg: the panic is raised again
+/// and reported by the hook
+pub(crate) fn discard_suppressed_panic() -> Option<String> {
+    SUPPRESSED_REPORT.with(|suppressed| suppressed.borrow_mut().take())
+}
+
+/// the message of the panic `payload`, the payload of `panic!` is a `&str` or a `String`
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
 }
@@ -1,10 +1,13 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use metrics::gauge;
 use sysinfo::{ProcessExt, SystemExt};
 
+use crate::core::runtime::{AllocatorStats, DiskUsage, ResourceUsage};
+use crate::metrics::metric::global_labels;
 use crate::metrics::Tag;
+use crate::utils::date_time::current_timestamp_millis;
 
 pub fn work_space() -> PathBuf {
     std::env::current_dir().expect("Get current dir error")
@@ -39,13 +42,14 @@ pub fn parse_arg_to_u64(arg_key: &str) -> anyhow::Result<u64> {
 }
 
 pub(crate) fn sys_info_metric_task(global_tag: Tag) {
-    let mut labels = Vec::new();
     let Tag(field_name, field_value) = global_tag.clone();
-    labels.push((field_name, field_value));
 
     let mut system = sysinfo::System::new();
     let pid = sysinfo::get_current_pid().unwrap();
     loop {
+        let mut labels = global_labels();
+        labels.push((field_name.clone(), field_value.clone()));
+
         system.refresh_process(pid);
         // system.refresh_cpu();
         system.refresh_memory();
@@ -90,3 +94,116 @@ pub(crate) fn sys_info_metric_task(global_tag: Tag) {
         std::thread::sleep(std::time::Duration::from_secs(5));
     }
 }
+
+/// Collect the `ResourceUsage` of the current process, the cpu usage is measured between
+/// two collections, so the collector is kept by the caller
+pub(crate) struct ResourceUsageCollector {
+    system: sysinfo::System,
+    pid: sysinfo::Pid,
+    dirs: Vec<PathBuf>,
+}
+
+impl ResourceUsageCollector {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        ResourceUsageCollector {
+            system: sysinfo::System::new(),
+            pid: sysinfo::get_current_pid().unwrap(),
+            dirs,
+        }
+    }
+
+    pub fn collect(&mut self) -> ResourceUsage {
+        self.system.refresh_process(self.pid);
+        let (cpu_usage, memory_kb) = self
+            .system
+            .get_process(self.pid)
+            .map(|p| (p.cpu_usage(), p.memory()))
+            .unwrap_or_default();
+
+        let disks = self
+            .dirs
+            .iter()
+            .map(|dir| DiskUsage {
+                path: dir.to_string_lossy().to_string(),
+                used_bytes: dir_size(dir),
+                available_bytes: available_space(dir).unwrap_or_default(),
+            })
+            .collect();
+
+        ResourceUsage {
+            cpu_usage,
+            rss_bytes: memory_kb * 1024,
+            allocator: allocator_stats(),
+            disks,
+            timestamp: current_timestamp_millis(),
+        }
+    }
+}
+
+/// the size of the files in the directory recursively, `0` if the directory doesn't exist
+fn dir_size(dir: &Path) -> u64 {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(entry.path().as_path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(unix)]
+fn available_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn allocator_stats() -> Option<AllocatorStats> {
+    let info = unsafe { libc::mallinfo2() };
+    Some(AllocatorStats {
+        allocated_bytes: info.uordblks as u64,
+        free_bytes: info.fordblks as u64,
+        mapped_bytes: info.hblkhd as u64,
+    })
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+fn allocator_stats() -> Option<AllocatorStats> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::process::ResourceUsageCollector;
+
+    #[test]
+    pub fn resource_usage_test() {
+        let dir = std::env::temp_dir().join(format!("rlink_resource_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("run"), vec![0u8; 1024]).unwrap();
+
+        let mut collector = ResourceUsageCollector::new(vec![dir.clone()]);
+        let usage = collector.collect();
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert!(usage.rss_bytes > 0);
+        assert_eq!(usage.disks[0].used_bytes, 1024);
+    }
+}
@@ -1,7 +1,20 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::metrics::register_gauge;
 
 static ATOMIC_ID: AtomicUsize = AtomicUsize::new(0);
 
+/// the default worker threads of the shared async runtime
+pub const DEFAULT_SHARED_RUNTIME_THREADS: usize = 4;
+/// the interval of refreshing the metrics of the shared async runtime
+const SHARED_RUNTIME_METRICS_INTERVAL: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref SHARED_RUNTIME: Mutex<Option<tokio::runtime::Runtime>> = Mutex::new(None);
+}
+
 fn gen_thread_name(thread_name: &'static str) -> String {
     let id = ATOMIC_ID.fetch_add(1, Ordering::SeqCst);
     format!("A-{}-{}", thread_name, id)
@@ -77,6 +90,38 @@ pub fn async_runtime_multi(thread_name: &'static str, threads: usize) -> tokio::
         .unwrap()
 }
 
+/// The async runtime shared by all tasks of the worker process, so the connectors don't create
+/// a runtime(and its threads) per task. The runtime is created with `threads` worker threads
+/// on the first call, the later calls return the same runtime whatever the `threads` is.
+/// The runtime is reported by the gauges `SharedRuntime_Workers`, `SharedRuntime_AliveTasks`
+/// and `SharedRuntime_QueueDepth`(the tasks pending in the global queue).
+/// Usually obtained by `Context::async_runtime`.
+pub fn shared_runtime(threads: usize) -> tokio::runtime::Handle {
+    let mut shared_runtime = SHARED_RUNTIME.lock().unwrap();
+    if let Some(runtime) = shared_runtime.as_ref() {
+        return runtime.handle().clone();
+    }
+
+    info!("create the shared async runtime with {} threads", threads);
+    let runtime = async_runtime_multi("shared", threads.max(1));
+    runtime.spawn(async {
+        let workers_gauge = register_gauge("SharedRuntime_Workers", vec![]);
+        let alive_tasks_gauge = register_gauge("SharedRuntime_AliveTasks", vec![]);
+        let queue_depth_gauge = register_gauge("SharedRuntime_QueueDepth", vec![]);
+        let metrics = tokio::runtime::Handle::current().metrics();
+        loop {
+            workers_gauge.store(metrics.num_workers() as i64);
+            alive_tasks_gauge.store(metrics.num_alive_tasks() as i64);
+            queue_depth_gauge.store(metrics.global_queue_depth() as i64);
+            async_sleep(SHARED_RUNTIME_METRICS_INTERVAL).await;
+        }
+    });
+
+    let handle = runtime.handle().clone();
+    *shared_runtime = Some(runtime);
+    handle
+}
+
 pub fn async_runtime_single() -> tokio::runtime::Runtime {
     tokio::runtime::Builder::new_current_thread()
         .enable_all()
@@ -95,3 +140,90 @@ where
 pub async fn async_sleep(duration: std::time::Duration) {
     tokio::time::sleep(duration).await;
 }
+
+/// the max frames captured by `stack_dump`
+const STACK_DUMP_MAX_FRAMES: usize = 64;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const STACK_DUMP_EMPTY_FRAME: AtomicUsize = AtomicUsize::new(0);
+
+static STACK_DUMP_FRAMES: [AtomicUsize; STACK_DUMP_MAX_FRAMES] =
+    [STACK_DUMP_EMPTY_FRAME; STACK_DUMP_MAX_FRAMES];
+static STACK_DUMP_LEN: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref STACK_DUMP_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// the `SIGUSR2` handler, capture the instruction pointers of the interrupted thread
+/// without allocating, they're resolved by `stack_dump` in the requesting thread
+#[cfg(unix)]
+extern "C" fn stack_dump_handler(_signal: libc::c_int) {
+    let mut len = 0;
+    unsafe {
+        backtrace::trace_unsynchronized(|frame| {
+            STACK_DUMP_FRAMES[len].store(frame.ip() as usize, Ordering::Relaxed);
+            len += 1;
+            len < STACK_DUMP_MAX_FRAMES
+        });
+    }
+    STACK_DUMP_LEN.store(len, Ordering::SeqCst);
+}
+
+/// dump the stack of the thread `pthread`(the `thread_id::get()` of the thread) by interrupting
+/// it with a `SIGUSR2`, the thread may be stuck so `None` is returned if it's not dumped
+/// within the `timeout`
+#[cfg(unix)]
+pub(crate) fn stack_dump(pthread: usize, timeout: Duration) -> Option<String> {
+    let _lock = STACK_DUMP_LOCK.lock().unwrap();
+
+    STACK_DUMP_LEN.store(0, Ordering::SeqCst);
+    unsafe {
+        libc::signal(
+            libc::SIGUSR2,
+            stack_dump_handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+        if libc::pthread_kill(pthread as libc::pthread_t, libc::SIGUSR2) != 0 {
+            return None;
+        }
+    }
+
+    let begin = std::time::Instant::now();
+    let len = loop {
+        let len = STACK_DUMP_LEN.load(Ordering::SeqCst);
+        if len > 0 {
+            break len;
+        }
+        if begin.elapsed() >= timeout {
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    let mut dump = String::new();
+    for (index, frame) in STACK_DUMP_FRAMES.iter().take(len).enumerate() {
+        let ip = frame.load(Ordering::Relaxed);
+        let mut resolved = false;
+        backtrace::resolve(ip as *mut std::ffi::c_void, |symbol| {
+            resolved = true;
+            dump.push_str(format!("{:>4}: {:#x} - ", index, ip).as_str());
+            match symbol.name() {
+                Some(name) => dump.push_str(format!("{:#}", name).as_str()),
+                None => dump.push_str("<unknown>"),
+            }
+            if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+                dump.push_str(format!("\n             at {}:{}", file.display(), line).as_str());
+            }
+            dump.push('\n');
+        });
+        if !resolved {
+            dump.push_str(format!("{:>4}: {:#x} - <unknown>\n", index, ip).as_str());
+        }
+    }
+    Some(dump)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn stack_dump(_pthread: usize, _timeout: Duration) -> Option<String> {
+    None
+}